@@ -0,0 +1,59 @@
+use bestme::audio::simd;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// One 10 ms callback of 48 kHz stereo
+const CALLBACK_SAMPLES: usize = 960;
+
+fn callback_block() -> Vec<f32> {
+    (0..CALLBACK_SAMPLES).map(|i| (i as f32 * 0.05).sin() * 0.5).collect()
+}
+
+fn bench_peak(c: &mut Criterion) {
+    let samples = callback_block();
+    let mut group = c.benchmark_group("peak");
+
+    group.bench_function("scalar", |b| {
+        b.iter(|| black_box(&samples).iter().fold(0.0f32, |peak, sample| peak.max(sample.abs())))
+    });
+    group.bench_function("simd", |b| b.iter(|| simd::peak(black_box(&samples))));
+
+    group.finish();
+}
+
+fn bench_downmix(c: &mut Criterion) {
+    let samples = callback_block();
+    let mut group = c.benchmark_group("downmix_stereo");
+
+    group.bench_function("scalar", |b| {
+        b.iter(|| {
+            black_box(&samples)
+                .chunks_exact(2)
+                .map(|frame| frame.iter().sum::<f32>() / 2.0)
+                .collect::<Vec<f32>>()
+        })
+    });
+    group.bench_function("simd", |b| b.iter(|| simd::downmix(black_box(&samples), 2)));
+
+    group.finish();
+}
+
+fn bench_gain(c: &mut Criterion) {
+    let samples = callback_block();
+    let mut group = c.benchmark_group("gain");
+
+    group.bench_function("scalar", |b| {
+        b.iter(|| black_box(&samples).iter().map(|sample| sample * 1.5).collect::<Vec<f32>>())
+    });
+    group.bench_function("simd", |b| {
+        b.iter(|| {
+            let mut output = Vec::with_capacity(CALLBACK_SAMPLES);
+            simd::scale(black_box(&samples), 1.5, &mut output);
+            output
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_peak, bench_downmix, bench_gain);
+criterion_main!(benches);
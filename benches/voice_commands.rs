@@ -0,0 +1,22 @@
+use bestme::audio::voice_commands::{VoiceCommandConfig, VoiceCommandManager};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// A segment of ordinary dictation, which every command is fuzzily compared against
+const DICTATION: &str = "so the plan for next quarter is to finish the migration before we start on the \
+    new reporting pages and then review what the customers asked for in the last survey";
+
+fn bench_process_transcription(c: &mut Criterion) {
+    let (mut manager, _events) = VoiceCommandManager::new(VoiceCommandConfig::default()).unwrap();
+    manager.start().unwrap();
+    let mut group = c.benchmark_group("voice_commands");
+
+    group.bench_function("dictation", |b| b.iter(|| manager.process_transcription(black_box(DICTATION))));
+    group.bench_function("misheard_command", |b| {
+        b.iter(|| manager.process_transcription(black_box("capitalise that")))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_process_transcription);
+criterion_main!(benches);
@@ -0,0 +1,105 @@
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+
+use bestme::api::{live_events, ApiController, ApiDevice, ApiStatus, LiveEvent, LiveEventKind, LiveEvents};
+use bestme::audio::device::DeviceManager;
+use bestme::audio::history::{DateRange, SearchHit};
+use bestme::audio::session::{Session, SessionSummary};
+use bestme::config::ConfigManager;
+
+use crate::plugin::{AudioState, TranscribeState};
+
+/// Events the window gets that WebSocket clients get too
+const LIVE_EVENTS: [(&str, LiveEventKind); 6] = [
+    ("transcription:partial", LiveEventKind::Partial),
+    ("transcription:final", LiveEventKind::Final),
+    ("audio:metrics", LiveEventKind::Level),
+    ("voice-command:detected", LiveEventKind::Command),
+    ("voice-command:cancelled", LiveEventKind::Command),
+    ("voice-command:error", LiveEventKind::Command),
+];
+
+/// Pass the window's live transcription, level and command events on to the API's WebSocket clients
+pub fn forward_live_events(app_handle: &AppHandle) -> LiveEvents {
+    let events = live_events();
+    for (name, kind) in LIVE_EVENTS {
+        let events = events.clone();
+        app_handle.listen_global(name, move |event| {
+            let data = event.payload()
+                .and_then(|payload| serde_json::from_str(payload).ok())
+                .unwrap_or_default();
+            // Nothing is kept while no client is connected
+            let _ = events.send(LiveEvent { kind, event: name.to_string(), data });
+        });
+    }
+    events
+}
+
+/// The application as the HTTP API drives it, doing what the window's buttons do
+pub struct AppApi {
+    pub audio_state: Arc<Mutex<AudioState>>,
+    pub transcribe_state: Arc<TranscribeState>,
+    pub config_manager: Arc<Mutex<ConfigManager>>,
+    pub device_manager: Arc<Mutex<DeviceManager>>,
+}
+
+impl ApiController for AppApi {
+    fn start(&self, device: Option<&str>) -> Result<()> {
+        // The device asked for, else the saved one, else the first there is
+        let device = match device {
+            Some(device) => device.to_string(),
+            None => self.config_manager.lock().get_config().audio.input_device.clone()
+                .or_else(|| self.device_manager.lock().get_input_devices().into_iter().next().map(|(id, _)| id))
+                .context("No input device to record from")?,
+        };
+        self.audio_state.lock().start_recording(&device)?;
+        self.transcribe_state.start_transcription()
+    }
+
+    fn stop(&self) -> Result<()> {
+        self.transcribe_state.stop_transcription()?;
+        self.audio_state.lock().stop_recording()
+    }
+
+    fn status(&self) -> ApiStatus {
+        ApiStatus {
+            recording: self.audio_state.lock().is_recording(),
+            transcribing: self.transcribe_state.is_transcribing(),
+            paused: self.transcribe_state.is_paused(),
+            device: self.config_manager.lock().get_config().audio.input_device.clone(),
+        }
+    }
+
+    fn devices(&self) -> Vec<ApiDevice> {
+        self.device_manager.lock().get_input_devices().into_iter()
+            .map(|(id, name)| ApiDevice { id, name })
+            .collect()
+    }
+
+    fn select_device(&self, id: &str) -> Result<()> {
+        self.audio_state.lock().set_device(id)?;
+
+        // Saved as the window saves it, so the selection survives a restart
+        let mut config_manager = self.config_manager.lock();
+        config_manager.get_config_mut().audio.input_device = Some(id.to_string());
+        config_manager.save().context("Failed to save input device")
+    }
+
+    fn transcript(&self) -> String {
+        self.transcribe_state.get_transcription()
+    }
+
+    fn sessions(&self) -> Result<Vec<SessionSummary>> {
+        self.transcribe_state.list_sessions()
+    }
+
+    fn session(&self, id: &str) -> Result<Session> {
+        self.transcribe_state.get_session(id)
+    }
+
+    fn search(&self, query: &str, range: &DateRange, limit: usize) -> Result<Vec<SearchHit>> {
+        self.transcribe_state.search_transcripts(query, range, limit)
+    }
+}
@@ -1,6 +1,8 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod plugin;
+#[cfg(feature = "http-api")]
+mod api;
 
 use log::{error, info, debug, warn};
 use parking_lot::Mutex;
@@ -15,7 +17,10 @@ use serde_json::Value as JsonValue;
 use bestme::audio::device::DeviceManager;
 use bestme::config::ConfigManager;
 use bestme::config::WhisperModelSize;
-use bestme::audio::voice_commands::VoiceCommandConfig as LibVoiceCommandConfig;
+use bestme::audio::active_window::ActiveWindow;
+use bestme::audio::command_script::CommandScript;
+use bestme::audio::command_stats::CommandUsage;
+use bestme::audio::voice_commands::{strip_commands, CommandCategory, CommandMode, VoiceCommandConfig as LibVoiceCommandConfig, VoiceCommandType, DEFAULT_PROFILE};
 
 // Import our custom plugins
 use plugin::{
@@ -28,15 +33,63 @@ use plugin::{
 
 use plugin::transcribe::SUPPORTED_LANGUAGES;
 
-// Extension trait for DeviceManager to implement list_devices
-trait DeviceManagerExt {
-    fn list_devices(&self) -> Result<Vec<cpal::Device>, String>;
+/// Carry out the voice commands that control capture rather than edit text
+///
+/// Pausing only stops text being kept, since "resume" still has to be heard;
+/// stopping ends both transcription and recording.
+fn control_capture(command_type: &VoiceCommandType, audio_state: &Mutex<AudioState>, transcribe_state: &TranscribeState) {
+    let result = match command_type {
+        VoiceCommandType::Pause => transcribe_state.pause_transcription(),
+        VoiceCommandType::Resume => transcribe_state.resume_transcription(),
+        VoiceCommandType::Stop => transcribe_state.stop_transcription()
+            .and_then(|_| audio_state.lock().stop_recording()),
+        _ => return,
+    };
+    
+    if let Err(e) = result {
+        error!("Failed to carry out {:?} voice command: {}", command_type, e);
+    }
 }
 
-impl DeviceManagerExt for DeviceManager {
-    fn list_devices(&self) -> Result<Vec<cpal::Device>, String> {
-        self.get_input_devices().map_err(|e| e.to_string())
+/// Bring the hidden main window back to the front
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        if let Err(e) = window.show().and_then(|_| window.set_focus()) {
+            warn!("Failed to show main window: {}", e);
+        }
+    }
+}
+
+/// Tray (Windows, Linux) or menu bar (macOS) icon for reopening and quitting the app
+fn create_tray(app: &tauri::App) -> tauri::Result<()> {
+    use tauri::menu::{Menu, MenuItem};
+    use tauri::tray::TrayIconBuilder;
+    
+    let show = MenuItem::with_id(app, "show", "Show BestMe", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let menu = Menu::with_items(app, &[&show, &quit])?;
+    
+    let mut tray = TrayIconBuilder::with_id("main")
+        .tooltip("BestMe")
+        .menu(&menu)
+        .on_menu_event(|app, event| match event.id().as_ref() {
+            "show" => show_main_window(app),
+            "quit" => app.exit(0),
+            _ => {}
+        });
+    
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    
+    // Menu bar icons follow the light/dark appearance
+    #[cfg(target_os = "macos")]
+    {
+        tray = tray.icon_as_template(true);
     }
+    
+    tray.build(app)?;
+    Ok(())
 }
 
 // Commands that will be exposed to the frontend
@@ -44,25 +97,39 @@ impl DeviceManagerExt for DeviceManager {
 async fn get_audio_devices(
     device_manager: tauri::State<'_, Arc<Mutex<DeviceManager>>>
 ) -> Result<Vec<(String, String)>, String> {
-    let device_manager = device_manager.inner();
-    let devices = device_manager.lock().list_devices()
-        .map_err(|e| e.to_string())?;
-    
-    Ok(devices.into_iter()
-        .map(|d| (d.id().to_string(), d.name().to_string()))
-        .collect())
+    // Stable device IDs paired with display names
+    Ok(device_manager.inner().lock().get_input_devices())
 }
 
 #[tauri::command]
-async fn get_whisper_models() -> Vec<String> {
+async fn get_whisper_models(config_manager: tauri::State<'_, Arc<Mutex<ConfigManager>>>) -> Result<Vec<String>, String> {
     // Add all the available Whisper models
-    vec![
+    let mut models = vec![
         "tiny".to_string(),
         "base".to_string(),
         "small".to_string(),
         "medium".to_string(),
         "large".to_string(),
-    ]
+        "tiny-q8_0".to_string(),
+        "base-q8_0".to_string(),
+        "small-q8_0".to_string(),
+        "medium-q5_0".to_string(),
+        "medium-q8_0".to_string(),
+        "large-q5_0".to_string(),
+        "large-q8_0".to_string(),
+        "large-v3-turbo".to_string(),
+        "large-v3-turbo-q5_0".to_string(),
+        "large-v3-turbo-q8_0".to_string(),
+        "distil-small.en".to_string(),
+        "distil-medium.en".to_string(),
+    ];
+    
+    // Then any registered model files, named "custom:<display name>"
+    let config_manager = config_manager.inner().lock();
+    models.extend(config_manager.get_config().audio.speech.custom_models.iter()
+        .map(|model| format!("{}{}", bestme::config::CUSTOM_MODEL_PREFIX, model.name)));
+    
+    Ok(models)
 }
 
 #[tauri::command]
@@ -94,10 +161,83 @@ async fn get_model_download_info() -> Vec<serde_json::Value> {
             "name": "large",
             "size": "3 GB",
             "description": "Highest accuracy, slowest processing"
+        }),
+        json!({
+            "name": "tiny-q8_0",
+            "size": "44 MB",
+            "description": "Tiny model with 8-bit weights"
+        }),
+        json!({
+            "name": "base-q8_0",
+            "size": "82 MB",
+            "description": "Base model with 8-bit weights"
+        }),
+        json!({
+            "name": "small-q8_0",
+            "size": "264 MB",
+            "description": "Small model with 8-bit weights, nearly full accuracy"
+        }),
+        json!({
+            "name": "medium-q5_0",
+            "size": "539 MB",
+            "description": "Medium accuracy in the memory of a small model, good for 8 GB machines"
+        }),
+        json!({
+            "name": "medium-q8_0",
+            "size": "823 MB",
+            "description": "Medium model with 8-bit weights, nearly full accuracy"
+        }),
+        json!({
+            "name": "large-q5_0",
+            "size": "1.1 GB",
+            "description": "Large model with 5-bit weights"
+        }),
+        json!({
+            "name": "large-q8_0",
+            "size": "1.7 GB",
+            "description": "Large model with 8-bit weights"
+        }),
+        json!({
+            "name": "large-v3-turbo",
+            "size": "1.6 GB",
+            "description": "Near large accuracy at several times the speed"
+        }),
+        json!({
+            "name": "large-v3-turbo-q5_0",
+            "size": "574 MB",
+            "description": "Large v3 turbo with 5-bit weights, the best accuracy for 8 GB machines"
+        }),
+        json!({
+            "name": "large-v3-turbo-q8_0",
+            "size": "874 MB",
+            "description": "Large v3 turbo with 8-bit weights"
+        }),
+        json!({
+            "name": "distil-small.en",
+            "size": "336 MB",
+            "description": "English only, faster than small at similar accuracy"
+        }),
+        json!({
+            "name": "distil-medium.en",
+            "size": "789 MB",
+            "description": "English only, near medium accuracy at the speed of small"
         })
     ]
 }
 
+#[tauri::command]
+async fn get_vosk_models() -> Vec<serde_json::Value> {
+    use bestme::audio::vosk::VOSK_MODELS;
+    
+    VOSK_MODELS.iter()
+        .map(|&(name, _, size, language)| serde_json::json!({
+            "name": name,
+            "size": size,
+            "language": language
+        }))
+        .collect()
+}
+
 #[tauri::command]
 async fn get_supported_languages() -> Vec<[String; 2]> {
     use plugin::transcribe::SUPPORTED_LANGUAGES;
@@ -126,14 +266,20 @@ async fn save_all_settings(
     
     // Update speech settings
     let speech = &mut config.audio.speech;
-    speech.model_size = match model_name.as_str() {
-        "tiny" => bestme::config::WhisperModelSize::Tiny,
-        "base" => bestme::config::WhisperModelSize::Base,
-        "small" => bestme::config::WhisperModelSize::Small,
-        "medium" => bestme::config::WhisperModelSize::Medium,
-        "large" => bestme::config::WhisperModelSize::Large,
-        _ => bestme::config::WhisperModelSize::Small,
-    };
+    
+    // Registered model files, which can be chosen as "custom:<name>"
+    if let Some(custom_models) = speech_settings.get("custom_models") {
+        speech.custom_models = serde_json::from_value(custom_models.clone())
+            .map_err(|e| format!("Invalid custom models: {}", e))?;
+    }
+    
+    // Model names may carry a quantization suffix, e.g. "medium-q5_0"
+    if speech.set_model_size_from_str(&model_name).is_err() {
+        speech.model_size = bestme::config::WhisperModelSize::Small;
+        speech.quantization = bestme::config::ModelQuantization::None;
+        speech.custom_model = None;
+    }
+    speech.download.offline = offline_mode;
     
     // Update speech settings if provided
     if let Some(speech_obj) = speech_settings.as_object() {
@@ -153,6 +299,11 @@ async fn save_all_settings(
             speech.context_formatting = context_formatting;
         }
         
+        if let Some(formatting) = speech_obj.get("formatting") {
+            speech.formatting = serde_json::from_value(formatting.clone())
+                .map_err(|e| format!("Invalid formatting settings: {}", e))?;
+        }
+        
         if let Some(segment_duration) = speech_obj.get("segment_duration").and_then(|v| v.as_f64()) {
             speech.segment_duration = segment_duration as f32;
         }
@@ -160,6 +311,80 @@ async fn save_all_settings(
         if let Some(buffer_size) = speech_obj.get("buffer_size").and_then(|v| v.as_u64()) {
             speech.buffer_size = buffer_size as f32;
         }
+        
+        if let Some(engine) = speech_obj.get("engine").and_then(|v| v.as_str()) {
+            speech.engine = match engine.to_lowercase().as_str() {
+                "cloud" => bestme::config::SttEngineKind::Cloud,
+                "vosk" => bestme::config::SttEngineKind::Vosk,
+                _ => bestme::config::SttEngineKind::Whisper,
+            };
+        }
+        
+        if let Some(vosk_model) = speech_obj.get("vosk_model").and_then(|v| v.as_str()) {
+            if bestme::audio::vosk::model_url(vosk_model).is_none() {
+                return Err(format!("Invalid Vosk model: {}", vosk_model));
+            }
+            speech.vosk.model = vosk_model.to_string();
+        }
+        
+        if let Some(cloud) = speech_obj.get("cloud") {
+            speech.cloud = serde_json::from_value(cloud.clone())
+                .map_err(|e| format!("Invalid cloud transcription settings: {}", e))?;
+        }
+        
+        if let Some(decoding) = speech_obj.get("decoding") {
+            speech.decoding = serde_json::from_value(decoding.clone())
+                .map_err(|e| format!("Invalid decoding settings: {}", e))?;
+        }
+        
+        if let Some(idle_unload) = speech_obj.get("idle_unload") {
+            speech.idle_unload = serde_json::from_value(idle_unload.clone())
+                .map_err(|e| format!("Invalid idle unload settings: {}", e))?;
+        }
+        
+        if let Some(live_output) = speech_obj.get("live_output") {
+            speech.live_output = serde_json::from_value(live_output.clone())
+                .map_err(|e| format!("Invalid live output settings: {}", e))?;
+        }
+        
+        if let Some(replacements) = speech_obj.get("replacements") {
+            speech.replacements = serde_json::from_value(replacements.clone())
+                .map_err(|e| format!("Invalid replacement rules: {}", e))?;
+        }
+        
+        if let Some(dictionaries) = speech_obj.get("dictionaries") {
+            speech.dictionaries = serde_json::from_value(dictionaries.clone())
+                .map_err(|e| format!("Invalid replacement dictionaries: {}", e))?;
+        }
+        
+        if let Some(active_dictionary) = speech_obj.get("active_dictionary") {
+            speech.active_dictionary = active_dictionary.as_str().filter(|name| !name.is_empty()).map(|name| name.to_string());
+        }
+        
+        if let Some(post_processing) = speech_obj.get("post_processing") {
+            speech.post_processing = serde_json::from_value(post_processing.clone())
+                .map_err(|e| format!("Invalid post-processing settings: {}", e))?;
+        }
+        
+        if let Some(profanity) = speech_obj.get("profanity") {
+            speech.profanity = serde_json::from_value(profanity.clone())
+                .map_err(|e| format!("Invalid profanity settings: {}", e))?;
+        }
+        
+        if let Some(refine) = speech_obj.get("refine") {
+            speech.refine = serde_json::from_value(refine.clone())
+                .map_err(|e| format!("Invalid refine settings: {}", e))?;
+        }
+        
+        if let Some(speaker_change) = speech_obj.get("speaker_change") {
+            speech.speaker_change = serde_json::from_value(speaker_change.clone())
+                .map_err(|e| format!("Invalid speaker change settings: {}", e))?;
+        }
+        
+        if let Some(corrections) = speech_obj.get("corrections") {
+            speech.corrections = serde_json::from_value(corrections.clone())
+                .map_err(|e| format!("Invalid correction settings: {}", e))?;
+        }
     }
     
     // Save the config
@@ -194,6 +419,77 @@ async fn toggle_voice_commands(
     }
 }
 
+#[tauri::command]
+async fn set_voice_command_mode(
+    mode: CommandMode,
+    state: tauri::State<'_, Arc<Mutex<VoiceCommandState>>>
+) -> Result<(), String> {
+    state.inner().lock().set_mode(mode)
+}
+
+#[tauri::command]
+async fn set_voice_commands_asleep(
+    asleep: bool,
+    state: tauri::State<'_, Arc<Mutex<VoiceCommandState>>>
+) -> Result<(), String> {
+    state.inner().lock().set_asleep(asleep)
+}
+
+#[tauri::command]
+async fn is_voice_commands_asleep(state: tauri::State<'_, Arc<Mutex<VoiceCommandState>>>) -> Result<bool, String> {
+    Ok(state.inner().lock().is_asleep())
+}
+
+#[tauri::command]
+async fn cancel_voice_commands(state: tauri::State<'_, Arc<Mutex<VoiceCommandState>>>) -> Result<(), String> {
+    state.inner().lock().cancel()
+}
+
+#[tauri::command]
+async fn get_voice_command_context(state: tauri::State<'_, Arc<Mutex<VoiceCommandState>>>) -> Result<Option<ActiveWindow>, String> {
+    Ok(state.inner().lock().active_window())
+}
+
+#[tauri::command]
+async fn list_voice_command_profiles(state: tauri::State<'_, Arc<Mutex<VoiceCommandState>>>) -> Result<Vec<String>, String> {
+    Ok(state.inner().lock().profiles())
+}
+
+#[tauri::command]
+async fn list_voice_commands(state: tauri::State<'_, Arc<Mutex<VoiceCommandState>>>) -> Result<Vec<CommandCategory>, String> {
+    Ok(state.inner().lock().available_commands())
+}
+
+#[tauri::command]
+async fn get_voice_command_stats(state: tauri::State<'_, Arc<Mutex<VoiceCommandState>>>) -> Result<Vec<CommandUsage>, String> {
+    Ok(state.inner().lock().command_stats())
+}
+
+#[tauri::command]
+async fn report_voice_command_mistake(state: tauri::State<'_, Arc<Mutex<VoiceCommandState>>>) -> Result<(), String> {
+    state.inner().lock().report_mistake()
+}
+
+#[tauri::command]
+async fn clear_voice_command_stats(state: tauri::State<'_, Arc<Mutex<VoiceCommandState>>>) -> Result<(), String> {
+    state.inner().lock().clear_command_stats()
+}
+
+#[tauri::command]
+async fn switch_voice_command_profile(
+    name: String,
+    config_manager: tauri::State<'_, Arc<Mutex<ConfigManager>>>,
+    state: tauri::State<'_, Arc<Mutex<VoiceCommandState>>>
+) -> Result<(), String> {
+    state.inner().lock().switch_profile(&name)?;
+    
+    // Start with the same profile next time
+    let mut config_manager = config_manager.inner().lock();
+    config_manager.get_config_mut().audio.voice_commands.active_profile =
+        (!name.eq_ignore_ascii_case(DEFAULT_PROFILE)).then_some(name);
+    config_manager.save().map_err(|e| format!("Failed to save voice command profile: {}", e))
+}
+
 #[tauri::command]
 async fn get_voice_command_settings(config_manager: tauri::State<'_, Arc<Mutex<ConfigManager>>>) -> Result<serde_json::Value, String> {
     let config_manager = config_manager.inner().lock();
@@ -211,6 +507,7 @@ async fn save_voice_command_settings(
     command_prefix: Option<String>,
     require_prefix: bool,
     sensitivity: f32,
+    script: Option<String>,
     config_manager: tauri::State<'_, Arc<Mutex<ConfigManager>>>,
     voice_command_state: tauri::State<'_, Arc<Mutex<VoiceCommandState>>>
 ) -> Result<(), String> {
@@ -227,6 +524,11 @@ async fn save_voice_command_settings(
     voice_command_config.require_prefix = require_prefix;
     voice_command_config.sensitivity = sensitivity;
     
+    if let Some(script) = script {
+        CommandScript::parse(&script).map_err(|e| format!("Invalid voice command script, {}", e))?;
+        voice_command_config.script = script;
+    }
+    
     // Save the config to ConfigManager
     config_manager.get_config_mut().audio.voice_commands = voice_command_config.clone();
     
@@ -278,6 +580,16 @@ fn main() {
     let config_manager = Arc::new(Mutex::new(ConfigManager::new().expect("Failed to initialize config manager")));
     let device_manager = Arc::new(Mutex::new(DeviceManager::new().expect("Failed to initialize device manager")));
     
+    // Older configs stored device names; switch them to stable device IDs
+    {
+        let mut config = config_manager.lock();
+        if device_manager.lock().migrate_settings(&mut config.get_config_mut().audio) {
+            if let Err(e) = config.save() {
+                error!("Failed to save migrated device settings: {}", e);
+            }
+        }
+    }
+    
     // Create state objects
     let audio_state = Arc::new(Mutex::new(AudioState::new(device_manager.clone())));
     
@@ -296,6 +608,21 @@ fn main() {
     {
         let mut audio = audio_state.lock();
         audio.set_transcribe_state(Arc::clone(&transcribe_state));
+        
+        let audio_settings = config_manager.lock().get_config().audio.clone();
+        if let Err(e) = audio.set_input_volume(audio_settings.input_volume) {
+            error!("Failed to apply input volume: {}", e);
+        }
+        
+        if let Err(e) = audio.set_additional_devices(audio_settings.additional_devices, audio_settings.mix_mode) {
+            error!("Failed to apply additional devices: {}", e);
+        }
+        
+        audio.set_recording_settings(audio_settings.recording);
+        audio.set_waveform_settings(&audio_settings.waveform);
+        if let Err(e) = audio.set_preroll_settings(&audio_settings.preroll) {
+            error!("Failed to apply pre-roll settings: {}", e);
+        }
     }
     
     {
@@ -333,10 +660,22 @@ fn main() {
             get_audio_devices,
             get_whisper_models,
             get_model_download_info,
+            get_vosk_models,
             get_supported_languages,
             save_all_settings,
             get_settings,
             toggle_voice_commands,
+            set_voice_command_mode,
+            set_voice_commands_asleep,
+            is_voice_commands_asleep,
+            cancel_voice_commands,
+            get_voice_command_context,
+            list_voice_command_profiles,
+            switch_voice_command_profile,
+            list_voice_commands,
+            get_voice_command_stats,
+            report_voice_command_mistake,
+            clear_voice_command_stats,
             get_voice_command_settings,
             save_voice_command_settings,
         ])
@@ -350,34 +689,69 @@ fn main() {
                 voice_state.set_app_handle(app_handle.clone());
             }
             
-            // Setup integration between transcription and voice commands
+            // Setup integration between transcription and voice commands: they are carried out before
+            // the text is kept, so their trigger words stay out of the transcript, and text heard while
+            // paused is checked too, so "resume" still works; while asleep nothing is kept at all
             {
-                let app_handle_clone = app.app_handle();
-                app_handle_clone.listen_global("transcription:update", move |event| {
-                    if let Some(payload) = event.payload() {
-                        if let Ok(text) = serde_json::from_str::<String>(payload) {
-                            debug!("Processing transcription for voice commands: '{}'", text);
-                            
-                            // Process transcription for voice commands
-                            let voice_state = voice_command_state.lock();
-                            match voice_state.process_transcription(&text) {
-                                Ok(commands) => {
-                                    if !commands.is_empty() {
-                                        info!("Detected {} voice commands in transcription", commands.len());
-                                        for cmd in &commands {
-                                            info!("Command: {:?}, Trigger: {}", cmd.command_type, cmd.trigger_text);
-                                        }
+                let voice_command_state = Arc::clone(&voice_command_state);
+                let audio_state = Arc::clone(&audio_state);
+                let weak_transcribe_state = Arc::downgrade(&transcribe_state);
+                transcribe_state.set_command_handler(move |text| {
+                    debug!("Processing transcription for voice commands: '{}'", text);
+                    
+                    // Process transcription for voice commands, knowing whether it was heard asleep
+                    let (asleep, result) = {
+                        let voice_command_state = voice_command_state.lock();
+                        (voice_command_state.is_asleep(), voice_command_state.process_transcription(text))
+                    };
+                    match result {
+                        Ok(commands) => {
+                            if !commands.is_empty() {
+                                info!("Detected {} voice commands in transcription", commands.len());
+                                for cmd in &commands {
+                                    info!("Command: {:?}, Trigger: {}", cmd.command_type, cmd.trigger_text);
+                                    if let Some(transcribe_state) = weak_transcribe_state.upgrade() {
+                                        control_capture(&cmd.command_type, &audio_state, &transcribe_state);
                                     }
-                                },
-                                Err(e) => {
-                                    error!("Failed to process transcription for voice commands: {}", e);
                                 }
                             }
-                        } else {
-                            warn!("Failed to parse transcription payload: {}", payload);
+                            if asleep {
+                                // Dictation isn't heard asleep, nor what was said with "wake up"
+                                String::new()
+                            } else {
+                                // Dictation is typed into the focused application too, with its punctuation,
+                                // when the profile in use turns that on
+                                if let Err(e) = voice_command_state.lock().inject_utterance(text, &commands) {
+                                    error!("Failed to type dictated text: {}", e);
+                                }
+                                strip_commands(text, &commands)
+                            }
+                        },
+                        Err(e) => {
+                            error!("Failed to process transcription for voice commands: {}", e);
+                            text.to_string()
+                        }
+                    }
+                });
+            }
+            
+            // The window hides instead of closing, so keep a way to bring it back
+            if let Err(e) = create_tray(app) {
+                error!("Failed to create tray icon: {}", e);
+            }
+            
+            // The tray icon shows whether voice commands are asleep
+            {
+                let tray_handle = app_handle.clone();
+                app_handle.listen_global("voice-command:sleep", move |event| {
+                    let asleep = event.payload()
+                        .and_then(|payload| serde_json::from_str::<bool>(payload).ok())
+                        .unwrap_or(false);
+                    if let Some(tray) = tray_handle.tray_by_id("main") {
+                        let tooltip = if asleep { "BestMe (asleep)" } else { "BestMe" };
+                        if let Err(e) = tray.set_tooltip(Some(tooltip)) {
+                            warn!("Failed to update tray icon: {}", e);
                         }
-                    } else {
-                        warn!("Received transcription update event with no payload");
                     }
                 });
             }
@@ -395,6 +769,55 @@ fn main() {
                 });
             }
             
+            // Load the speech model in the background so the first transcription doesn't wait for it
+            let preload_on_startup = {
+                let config = app_state.config_manager.lock().get_config();
+                config.audio.speech.preload.on_startup
+            };
+            
+            if preload_on_startup {
+                let transcribe_state = Arc::clone(&transcribe_state);
+                let app_handle = app.app_handle();
+                tokio::spawn(async move {
+                    // Failures are logged and reported to the frontend by the preload itself
+                    let _ = transcribe_state.preload_engine(Some(app_handle)).await;
+                });
+            }
+            
+            // Free the speech model's memory while it isn't used, if configured
+            {
+                let transcribe_state = Arc::clone(&transcribe_state);
+                let app_handle = app.app_handle();
+                tokio::spawn(async move {
+                    transcribe_state.unload_when_idle(Some(app_handle)).await;
+                });
+            }
+            
+            // Serve the local HTTP API for scripts and other tools, if configured
+            #[cfg(feature = "http-api")]
+            {
+                let settings = app_state.config_manager.lock().get_config().general.api.clone();
+                if settings.enabled {
+                    let controller = Arc::new(api::AppApi {
+                        audio_state: Arc::clone(&app_state.audio_state),
+                        transcribe_state: Arc::clone(&app_state.transcribe_state),
+                        config_manager: Arc::clone(&app_state.config_manager),
+                        device_manager: Arc::clone(&app_state.device_manager),
+                    });
+                    let app_handle = app.app_handle();
+                    let events = api::forward_live_events(&app_handle);
+                    tokio::spawn(async move {
+                        match bestme::api::ApiServer::start(&settings, controller, events).await {
+                            // Served for as long as the application runs
+                            Ok(server) => {
+                                app_handle.manage(server);
+                            }
+                            Err(e) => error!("Failed to start the HTTP API: {}", e),
+                        }
+                    });
+                }
+            }
+            
             // Start voice commands if enabled in configuration
             let voice_commands_enabled = {
                 let config = app_state.config_manager.lock().get_config();
@@ -417,6 +840,12 @@ fn main() {
             
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("Error while running Tauri application");
+        .build(tauri::generate_context!())
+        .expect("Error while building Tauri application")
+        .run(|app, event| {
+            // Clicking the Dock icon on macOS reopens the hidden window
+            if let tauri::RunEvent::Reopen { has_visible_windows: false, .. } = event {
+                show_main_window(app);
+            }
+        });
 } 
@@ -1,13 +1,21 @@
 use anyhow::Result;
-use log::{error, info, debug};
+use log::{error, info, debug, warn};
 use parking_lot::Mutex;
 use std::sync::Arc;
 use tauri::{Manager, AppHandle, State, plugin};
 use tokio::sync::mpsc;
+use serde_json::json;
 use std::marker::PhantomData;
 
 use bestme::audio::device::DeviceManager;
-use bestme::audio::capture::{CaptureManager, ThreadedCaptureManager, AudioData, AudioEvent};
+use bestme::audio::hotplug::{DeviceEvent, DeviceWatcher, DEFAULT_POLL_INTERVAL};
+use bestme::audio::capture::{CaptureManager, ThreadedCaptureManager, AudioData, AudioEvent, MAX_INPUT_VOLUME};
+use bestme::audio::clock::ClockDrift;
+use bestme::audio::meter::AudioMetrics;
+use bestme::audio::permission::{self, MicrophonePermission};
+use bestme::audio::recording::RecordingManager;
+use bestme::audio::waveform::{WaveformBucket, WaveformBuffer};
+use bestme::config::{ConfigManager, MixMode, PreRollSettings, RecordingSettings, WaveformSettings};
 
 use crate::plugin::TranscribeState;
 
@@ -19,7 +27,17 @@ pub struct AudioState {
     transcribe_state: Option<Arc<TranscribeState>>,
     is_recording: Arc<Mutex<bool>>,
     peak_level: Arc<Mutex<f32>>,
+    metrics: Arc<Mutex<AudioMetrics>>,
+    clock_drift: Arc<Mutex<ClockDrift>>,
+    waveform: Arc<Mutex<WaveformBuffer>>,
+    input_volume: Arc<Mutex<f32>>,
+    additional_devices: Arc<Mutex<(Vec<String>, MixMode)>>,
     selected_device: Arc<Mutex<Option<String>>>,
+    device_watcher: Arc<Mutex<Option<DeviceWatcher>>>,
+    app_handle: Option<AppHandle>,
+    recorder: Arc<Mutex<Option<RecordingManager>>>,
+    recording_settings: Arc<Mutex<RecordingSettings>>,
+    preroll_ms: Arc<Mutex<Option<u32>>>,
 }
 
 impl AudioState {
@@ -31,28 +49,37 @@ impl AudioState {
             transcribe_state: None,
             is_recording: Arc::new(Mutex::new(false)),
             peak_level: Arc::new(Mutex::new(0.0)),
+            metrics: Arc::new(Mutex::new(AudioMetrics::default())),
+            clock_drift: Arc::new(Mutex::new(ClockDrift::default())),
+            waveform: Arc::new(Mutex::new(Self::waveform_buffer(&WaveformSettings::default()))),
+            input_volume: Arc::new(Mutex::new(1.0)),
+            additional_devices: Arc::new(Mutex::new((Vec::new(), MixMode::default()))),
             selected_device: Arc::new(Mutex::new(None)),
+            device_watcher: Arc::new(Mutex::new(None)),
+            app_handle: None,
+            recorder: Arc::new(Mutex::new(None)),
+            recording_settings: Arc::new(Mutex::new(RecordingSettings::default())),
+            preroll_ms: Arc::new(Mutex::new(None)),
         }
     }
     
     pub fn set_transcribe_state(&mut self, transcribe_state: Arc<TranscribeState>) {
         self.transcribe_state = Some(transcribe_state);
     }
+    
+    pub fn set_app_handle(&mut self, app_handle: AppHandle) {
+        self.app_handle = Some(app_handle);
+    }
 
-    pub fn start_recording(&self, device_name: &str) -> Result<()> {
-        info!("Starting audio recording with device: {}", device_name);
+    pub fn start_recording(&self, device_id: &str) -> Result<()> {
+        info!("Starting audio recording with device: {}", device_id);
 
-        // Get the device
-        let device = {
+        // Resolve the stable device ID (older frontends may still pass a name)
+        let device_id = {
             let device_manager = self.device_manager.lock();
-            let devices = device_manager.list_devices()
-                .map_err(|e| anyhow::anyhow!("Failed to list devices: {}", e))?;
-            
-            let device = devices.into_iter()
-                .find(|d| d.name().map(|n| n == device_name).unwrap_or(false))
-                .ok_or_else(|| anyhow::anyhow!("Device '{}' not found", device_name))?;
-            
-            device
+            device_manager.get_input_device(device_id)
+                .map(|(id, _)| id)
+                .ok_or_else(|| anyhow::anyhow!("Device '{}' not found", device_id))?
         };
         
         // Get or create the capture manager
@@ -82,7 +109,20 @@ impl AudioState {
         };
         
         // Set the device
-        manager.set_device(device)?;
+        manager.set_device(&device_id)?;
+        
+        // Apply the current input volume
+        manager.set_input_volume(*self.input_volume.lock())?;
+        
+        // Apply additional devices to mix with the selected device
+        let (device_ids, mix_mode) = self.additional_devices.lock().clone();
+        manager.set_additional_devices(device_ids, mix_mode)?;
+        
+        // Return to pre-roll buffering when the recording stops
+        let preroll_ms = *self.preroll_ms.lock();
+        if preroll_ms.is_some() {
+            Self::apply_preroll(&manager, preroll_ms)?;
+        }
         
         // Set up peak level callback
         let peak_level = Arc::clone(&self.peak_level);
@@ -118,9 +158,11 @@ impl AudioState {
         // Store selected device
         {
             let mut selected_device = self.selected_device.lock();
-            *selected_device = Some(device_name.to_string());
+            *selected_device = Some(device_id);
         }
         
+        self.emit_recording_state("audio:started");
+        
         Ok(())
     }
 
@@ -153,18 +195,156 @@ impl AudioState {
             let mut peak = self.peak_level.lock();
             *peak = 0.0;
         }
+        *self.metrics.lock() = AudioMetrics::default();
+        self.waveform.lock().clear();
+        
+        self.emit_recording_state("audio:stopped");
         
         Ok(())
     }
+    
+    /// Tell the frontend recording started or stopped, whoever asked for it
+    fn emit_recording_state(&self, event: &str) {
+        if let Some(handle) = &self.app_handle {
+            if let Err(e) = handle.emit_all(event, ()) {
+                error!("Failed to emit {} event: {}", event, e);
+            }
+        }
+    }
 
     pub fn get_peak_level(&self) -> f32 {
         *self.peak_level.lock()
     }
     
+    pub fn get_metrics(&self) -> AudioMetrics {
+        *self.metrics.lock()
+    }
+    
+    // Latest device clock drift measurement, for diagnostics
+    pub fn get_clock_drift(&self) -> ClockDrift {
+        *self.clock_drift.lock()
+    }
+    
+    // Resize the waveform history kept for the visualizer
+    pub fn set_waveform_settings(&self, settings: &WaveformSettings) {
+        *self.waveform.lock() = Self::waveform_buffer(settings);
+    }
+    
+    pub fn get_waveform(&self) -> Vec<WaveformBucket> {
+        self.waveform.lock().to_vec()
+    }
+    
+    fn waveform_buffer(settings: &WaveformSettings) -> WaveformBuffer {
+        WaveformBuffer::new(settings.history_ms, settings.interval_ms, settings.buckets)
+    }
+    
     pub fn is_recording(&self) -> bool {
         *self.is_recording.lock()
     }
     
+    pub fn get_input_volume(&self) -> f32 {
+        *self.input_volume.lock()
+    }
+    
+    // Set the input volume, applying it live if a capture manager is running
+    pub fn set_input_volume(&self, volume: f32) -> Result<()> {
+        let volume = volume.clamp(0.0, MAX_INPUT_VOLUME);
+        
+        {
+            let mut input_volume = self.input_volume.lock();
+            *input_volume = volume;
+        }
+        
+        let cm = self.capture_manager.lock();
+        if let Some(manager) = cm.as_ref() {
+            manager.set_input_volume(volume)?;
+        }
+        
+        Ok(())
+    }
+    
+    // Set the WAV recording settings; recording starts with capture when enabled
+    pub fn set_recording_settings(&self, settings: RecordingSettings) {
+        *self.recording_settings.lock() = settings;
+        
+        // Pick up a new output directory or rotation limits on the next start
+        let mut recorder = self.recorder.lock();
+        if recorder.as_ref().map(|r| !r.is_recording()).unwrap_or(false) {
+            *recorder = None;
+        }
+    }
+    
+    // Start writing captured audio to WAV files
+    pub fn start_wav_recording(&self) -> Result<()> {
+        let mut recorder = self.recorder.lock();
+        if recorder.is_none() {
+            *recorder = Some(RecordingManager::new(self.recording_settings.lock().clone())?);
+        }
+        
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.start()?;
+        }
+        
+        Ok(())
+    }
+    
+    // Stop writing WAV files and return the files that were saved
+    pub fn stop_wav_recording(&self) -> Result<Vec<String>> {
+        let mut recorder = self.recorder.lock();
+        let files = match recorder.as_mut() {
+            Some(recorder) => recorder.stop()?,
+            None => Vec::new(),
+        };
+        
+        Ok(files.into_iter().map(|path| path.display().to_string()).collect())
+    }
+    
+    pub fn is_wav_recording(&self) -> bool {
+        self.recorder.lock().as_ref().map(|r| r.is_recording()).unwrap_or(false)
+    }
+    
+    // Buffer audio while idle so it can be prepended when recording starts
+    pub fn set_preroll_settings(&self, settings: &PreRollSettings) -> Result<()> {
+        let preroll_ms = if settings.enabled { Some(settings.duration_ms) } else { None };
+        *self.preroll_ms.lock() = preroll_ms;
+        
+        let cm = self.capture_manager.lock();
+        if let Some(manager) = cm.as_ref() {
+            Self::apply_preroll(manager, preroll_ms)?;
+        }
+        
+        Ok(())
+    }
+    
+    fn apply_preroll(manager: &ThreadedCaptureManager, preroll_ms: Option<u32>) -> Result<()> {
+        manager.set_preroll(preroll_ms)?;
+        
+        if preroll_ms.is_some() {
+            manager.start_preroll()
+        } else {
+            manager.stop_preroll()
+        }
+    }
+    
+    pub fn get_additional_devices(&self) -> (Vec<String>, MixMode) {
+        self.additional_devices.lock().clone()
+    }
+    
+    // Set the devices captured together with the selected device (applies on the next start)
+    pub fn set_additional_devices(&self, device_ids: Vec<String>, mix_mode: MixMode) -> Result<()> {
+        {
+            let mut additional_devices = self.additional_devices.lock();
+            *additional_devices = (device_ids.clone(), mix_mode.clone());
+        }
+        
+        let cm = self.capture_manager.lock();
+        if let Some(manager) = cm.as_ref() {
+            manager.set_additional_devices(device_ids, mix_mode)?;
+        }
+        
+        Ok(())
+    }
+    
     // Process audio events from the event receiver
     fn process_audio_events(&self) {
         let event_receiver = {
@@ -174,7 +354,14 @@ impl AudioState {
         
         if let Some(mut receiver) = event_receiver {
             let peak_level = Arc::clone(&self.peak_level);
+            let metrics = Arc::clone(&self.metrics);
+            let clock_drift = Arc::clone(&self.clock_drift);
+            let waveform = Arc::clone(&self.waveform);
             let is_recording = Arc::clone(&self.is_recording);
+            let selected_device = Arc::clone(&self.selected_device);
+            let app_handle = self.app_handle.clone();
+            let recorder = Arc::clone(&self.recorder);
+            let recording_settings = Arc::clone(&self.recording_settings);
             
             // Start a task to process events
             tokio::spawn(async move {
@@ -185,13 +372,38 @@ impl AudioState {
                             let mut peak = peak_level.lock();
                             *peak = level;
                         },
+                        AudioEvent::Metrics(latest) => {
+                            *metrics.lock() = latest;
+                            
+                            if let Some(handle) = &app_handle {
+                                if let Err(e) = handle.emit_all("audio:metrics", latest) {
+                                    error!("Failed to emit audio metrics: {}", e);
+                                }
+                            }
+                        },
+                        AudioEvent::Waveform(buckets) => {
+                            waveform.lock().extend(&buckets);
+                            
+                            if let Some(handle) = &app_handle {
+                                if let Err(e) = handle.emit_all("audio:waveform", json!({
+                                    "buckets": buckets,
+                                })) {
+                                    error!("Failed to emit waveform: {}", e);
+                                }
+                            }
+                        },
                         AudioEvent::LevelChanged(level) => {
                             // Legacy compatibility for level changes
                             let mut peak = peak_level.lock();
                             *peak = level;
                         },
-                        AudioEvent::Data(_) => {
-                            // Event already processed by the callback
+                        AudioEvent::Data(audio_data) => {
+                            // Transcription gets audio through the callback; only record here
+                            if let Some(recorder) = recorder.lock().as_mut() {
+                                if let Err(e) = recorder.write(&audio_data) {
+                                    error!("Failed to record audio: {}", e);
+                                }
+                            }
                         },
                         AudioEvent::Error(err) => {
                             error!("Audio error: {}", err);
@@ -199,10 +411,88 @@ impl AudioState {
                         AudioEvent::Stopped => {
                             let mut recording = is_recording.lock();
                             *recording = false;
+                            
+                            // Finalize the WAV file along with the capture session
+                            if let Some(recorder) = recorder.lock().as_mut() {
+                                if let Err(e) = recorder.stop() {
+                                    error!("Failed to finish recording: {}", e);
+                                }
+                            }
                         },
                         AudioEvent::Started => {
                             // Just log the event
                             debug!("Audio recording started");
+                            
+                            let settings = recording_settings.lock().clone();
+                            if settings.enabled {
+                                let mut recorder = recorder.lock();
+                                if recorder.is_none() {
+                                    match RecordingManager::new(settings) {
+                                        Ok(manager) => *recorder = Some(manager),
+                                        Err(e) => error!("Failed to create recording manager: {}", e),
+                                    }
+                                }
+                                
+                                if let Some(recorder) = recorder.as_mut() {
+                                    if let Err(e) = recorder.start() {
+                                        error!("Failed to start recording: {}", e);
+                                    }
+                                }
+                            }
+                        },
+                        AudioEvent::SpeechStart => {
+                            debug!("Speech detected");
+                        },
+                        AudioEvent::SpeechEnd => {
+                            debug!("Speech ended");
+                        },
+                        AudioEvent::DeviceChanged { previous, current } => {
+                            info!("Capture device changed from {:?} to {:?}", previous, current);
+                            {
+                                let mut selected = selected_device.lock();
+                                *selected = current.clone();
+                            }
+                            
+                            if let Some(handle) = &app_handle {
+                                if let Err(e) = handle.emit_all("audio:device-changed", json!({
+                                    "previous": previous,
+                                    "current": current,
+                                })) {
+                                    error!("Failed to emit device changed event: {}", e);
+                                }
+                            }
+                        },
+                        AudioEvent::StreamRecovered { reason } => {
+                            info!("Capture stream recovered: {}", reason);
+                            
+                            if let Some(handle) = &app_handle {
+                                if let Err(e) = handle.emit_all("audio:stream-recovered", json!({
+                                    "reason": reason,
+                                })) {
+                                    error!("Failed to emit stream recovered event: {}", e);
+                                }
+                            }
+                        },
+                        AudioEvent::ClockDrift(drift) => {
+                            *clock_drift.lock() = drift;
+                            
+                            if let Some(handle) = &app_handle {
+                                if let Err(e) = handle.emit_all("audio:clock-drift", drift) {
+                                    error!("Failed to emit clock drift: {}", e);
+                                }
+                            }
+                        },
+                        AudioEvent::FramesDropped { dropped, total } => {
+                            warn!("Dropped {} audio frames ({} total)", dropped, total);
+                            
+                            if let Some(handle) = &app_handle {
+                                if let Err(e) = handle.emit_all("audio:frames-dropped", json!({
+                                    "dropped": dropped,
+                                    "total": total,
+                                })) {
+                                    error!("Failed to emit frames dropped event: {}", e);
+                                }
+                            }
                         }
                     }
                 }
@@ -210,6 +500,37 @@ impl AudioState {
         }
     }
 
+    // Watch for device hot-plug and forward changes to the frontend
+    pub fn start_device_watcher(&self, app_handle: AppHandle) -> Result<()> {
+        let mut watcher_slot = self.device_watcher.lock();
+        if watcher_slot.is_some() {
+            return Ok(());
+        }
+        
+        let (watcher, mut receiver) = DeviceWatcher::start(Arc::clone(&self.device_manager), DEFAULT_POLL_INTERVAL)?;
+        *watcher_slot = Some(watcher);
+        
+        tokio::spawn(async move {
+            while let Some(event) = receiver.recv().await {
+                let (name, id, device_name) = match event {
+                    DeviceEvent::DeviceAdded { id, name } => ("audio:device-added", id, name),
+                    DeviceEvent::DeviceRemoved { id, name } => ("audio:device-removed", id, name),
+                    DeviceEvent::DefaultChanged { id, name } => ("audio:default-device-changed", id, name),
+                };
+                
+                info!("{}: {} ({})", name, device_name, id);
+                if let Err(e) = app_handle.emit_all(name, json!({
+                    "id": id,
+                    "name": device_name,
+                })) {
+                    error!("Failed to emit device event: {}", e);
+                }
+            }
+        });
+        
+        Ok(())
+    }
+    
     // Initialize the AudioState
     pub fn initialize(&self) -> Result<()> {
         // Try to use default device
@@ -223,7 +544,14 @@ impl AudioState {
         let (capture_manager, receiver) = ThreadedCaptureManager::create_from_capture_manager()?;
         
         // Set the device
-        capture_manager.set_device(default_device.clone())?;
+        capture_manager.set_device(&default_device.0)?;
+        
+        // Apply the current input volume
+        capture_manager.set_input_volume(*self.input_volume.lock())?;
+        
+        // Apply additional devices to mix with the default device
+        let (device_ids, mix_mode) = self.additional_devices.lock().clone();
+        capture_manager.set_additional_devices(device_ids, mix_mode)?;
         
         // Set up a callback for peak level updates
         let peak_level = Arc::clone(&self.peak_level);
@@ -247,6 +575,12 @@ impl AudioState {
             })?;
         }
         
+        // Start listening for pre-roll audio if enabled
+        let preroll_ms = *self.preroll_ms.lock();
+        if preroll_ms.is_some() {
+            Self::apply_preroll(&capture_manager, preroll_ms)?;
+        }
+        
         // Store the capture manager
         {
             let mut cm = self.capture_manager.lock();
@@ -267,10 +601,11 @@ impl AudioState {
     
     // Set the audio device
     pub fn set_device(&self, device_id: &str) -> Result<()> {
-        // Get the device
-        let device = {
+        // Resolve the stable device ID
+        let device_id = {
             let device_manager = self.device_manager.lock();
-            device_manager.get_device_by_id(device_id)
+            device_manager.get_input_device(device_id)
+                .map(|(id, _)| id)
                 .ok_or_else(|| anyhow::anyhow!("Device not found with ID: {}", device_id))?
         };
         
@@ -289,12 +624,12 @@ impl AudioState {
         };
         
         // Set the device
-        manager.set_device(device)?;
+        manager.set_device(&device_id)?;
         
         // Store selected device
         {
             let mut selected_device = self.selected_device.lock();
-            *selected_device = Some(device_id.to_string());
+            *selected_device = Some(device_id);
         }
         
         Ok(())
@@ -330,10 +665,16 @@ impl tauri::Plugin for AudioPlugin {
                 .setup(|app, _| {
                     // Initialize the audio state if needed
                     let audio_state = app.state::<Arc<Mutex<AudioState>>>();
+                    audio_state.lock().set_app_handle(app.clone());
                     if let Err(e) = audio_state.lock().initialize() {
                         error!("Failed to initialize audio state: {}", e);
                     }
                     
+                    // Keep the device list current as devices are plugged in or removed
+                    if let Err(e) = audio_state.lock().start_device_watcher(app.clone()) {
+                        error!("Failed to start device watcher: {}", e);
+                    }
+                    
                     Ok(())
                 })
                 .build(),
@@ -384,6 +725,98 @@ pub async fn get_level(state: tauri::State<'_, Arc<Mutex<AudioState>>>) -> f32 {
     state.inner().lock().get_peak_level()
 }
 
+#[tauri::command]
+pub async fn get_audio_metrics(state: tauri::State<'_, Arc<Mutex<AudioState>>>) -> AudioMetrics {
+    state.inner().lock().get_metrics()
+}
+
+#[tauri::command]
+pub async fn get_microphone_permission() -> MicrophonePermission {
+    permission::microphone_permission()
+}
+
+#[tauri::command]
+pub async fn request_microphone_permission() -> MicrophonePermission {
+    // The system prompt blocks until answered
+    tokio::task::spawn_blocking(permission::request_microphone_permission)
+        .await
+        .unwrap_or(MicrophonePermission::NotDetermined)
+}
+
+#[tauri::command]
+pub async fn get_clock_drift(state: tauri::State<'_, Arc<Mutex<AudioState>>>) -> ClockDrift {
+    state.inner().lock().get_clock_drift()
+}
+
+#[tauri::command]
+pub async fn get_waveform(state: tauri::State<'_, Arc<Mutex<AudioState>>>) -> Vec<WaveformBucket> {
+    state.inner().lock().get_waveform()
+}
+
+#[tauri::command]
+pub async fn get_input_volume(state: tauri::State<'_, Arc<Mutex<AudioState>>>) -> f32 {
+    state.inner().lock().get_input_volume()
+}
+
+#[tauri::command]
+pub async fn set_input_volume(
+    volume: f32,
+    state: tauri::State<'_, Arc<Mutex<AudioState>>>,
+    config_manager: tauri::State<'_, Arc<Mutex<ConfigManager>>>
+) -> Result<(), String> {
+    state.inner().lock().set_input_volume(volume)
+        .map_err(|e| e.to_string())?;
+    
+    // Persist the new volume
+    let mut config_manager = config_manager.inner().lock();
+    config_manager.get_config_mut().audio.input_volume = volume.clamp(0.0, MAX_INPUT_VOLUME);
+    config_manager.save()
+        .map_err(|e| format!("Failed to save input volume: {}", e))
+}
+
+#[tauri::command]
+pub async fn start_wav_recording(state: tauri::State<'_, Arc<Mutex<AudioState>>>) -> Result<(), String> {
+    state.inner().lock().start_wav_recording()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn stop_wav_recording(state: tauri::State<'_, Arc<Mutex<AudioState>>>) -> Result<Vec<String>, String> {
+    state.inner().lock().stop_wav_recording()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn is_wav_recording(state: tauri::State<'_, Arc<Mutex<AudioState>>>) -> bool {
+    state.inner().lock().is_wav_recording()
+}
+
+#[tauri::command]
+pub async fn get_additional_devices(
+    state: tauri::State<'_, Arc<Mutex<AudioState>>>
+) -> Result<(Vec<String>, MixMode), String> {
+    Ok(state.inner().lock().get_additional_devices())
+}
+
+#[tauri::command]
+pub async fn set_additional_devices(
+    device_ids: Vec<String>,
+    mix_mode: MixMode,
+    state: tauri::State<'_, Arc<Mutex<AudioState>>>,
+    config_manager: tauri::State<'_, Arc<Mutex<ConfigManager>>>
+) -> Result<(), String> {
+    state.inner().lock().set_additional_devices(device_ids.clone(), mix_mode.clone())
+        .map_err(|e| e.to_string())?;
+    
+    // Persist the device selection
+    let mut config_manager = config_manager.inner().lock();
+    let audio = &mut config_manager.get_config_mut().audio;
+    audio.additional_devices = device_ids;
+    audio.mix_mode = mix_mode;
+    config_manager.save()
+        .map_err(|e| format!("Failed to save additional devices: {}", e))
+}
+
 #[tauri::command]
 pub async fn is_recording(state: tauri::State<'_, Arc<Mutex<AudioState>>>) -> bool {
     state.inner().lock().is_recording()
@@ -396,26 +829,33 @@ pub async fn get_audio_devices(
     let state = state.inner().lock();
     let device_manager = state.device_manager.lock();
     
-    let devices = device_manager.list_devices()
-        .map_err(|e| e.to_string())?;
+    // Stable device IDs paired with display names
+    Ok(device_manager.get_input_devices())
+}
+
+#[tauri::command]
+pub async fn get_capture_sources(
+    state: tauri::State<'_, Arc<Mutex<AudioState>>>
+) -> Result<Vec<(String, String)>, String> {
+    let state = state.inner().lock();
+    let device_manager = state.device_manager.lock();
     
-    Ok(devices.into_iter()
-        .filter_map(|d| {
-            let id = d.id().to_string();
-            let name = match d.name() {
-                Ok(name) => name,
-                Err(_) => return None,
-            };
-            Some((id, name))
-        })
-        .collect())
+    // Input devices plus output devices that can be captured via loopback
+    Ok(device_manager.get_capture_sources())
 }
 
 #[tauri::command]
 pub async fn set_device(
     device_id: String,
-    state: tauri::State<'_, Arc<Mutex<AudioState>>>
+    state: tauri::State<'_, Arc<Mutex<AudioState>>>,
+    config_manager: tauri::State<'_, Arc<Mutex<ConfigManager>>>
 ) -> Result<(), String> {
     state.inner().lock().set_device(&device_id)
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+    
+    // Persist the stable ID so the selection survives device renames
+    let mut config_manager = config_manager.inner().lock();
+    config_manager.get_config_mut().audio.input_device = Some(device_id);
+    config_manager.save()
+        .map_err(|e| format!("Failed to save input device: {}", e))
 } 
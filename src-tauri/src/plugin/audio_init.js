@@ -43,6 +43,16 @@ window.__TAURI_PLUGIN_AUDIO__ = {
         return window.__TAURI__.invoke("plugin:audio:stop_recording");
       },
       
+      // Microphone permission: "granted", "denied", "restricted" or "not_determined"
+      async getMicrophonePermission() {
+        return window.__TAURI__.invoke("plugin:audio:get_microphone_permission");
+      },
+      
+      // Show the system microphone prompt if the user has not been asked yet
+      async requestMicrophonePermission() {
+        return window.__TAURI__.invoke("plugin:audio:request_microphone_permission");
+      },
+      
       // Get current audio level
       async getLevel() {
         return window.__TAURI__.invoke("plugin:audio:get_level");
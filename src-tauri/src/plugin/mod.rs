@@ -1,4 +1,5 @@
 pub mod audio;
+pub mod models;
 pub mod transcribe;
 pub mod voice_commands;
 
@@ -0,0 +1,190 @@
+use anyhow::{Context, Result};
+use log::{info, warn};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Model files are named `ggml-<name>.bin`
+const MODEL_PREFIX: &str = "ggml-";
+const MODEL_EXTENSION: &str = "bin";
+
+/// Checksums are stored next to each model as `ggml-<name>.bin.sha256`
+const CHECKSUM_EXTENSION: &str = "sha256";
+
+/// Magic numbers at the start of whisper.cpp model files
+const GGML_MAGIC: u32 = 0x6767_6d6c;
+const GGUF_MAGIC: &[u8; 4] = b"GGUF";
+
+/// A model file in the models directory
+#[derive(Debug, Clone, Serialize)]
+pub struct InstalledModel {
+    /// Model name, e.g. "medium-q5_0"
+    pub name: String,
+
+    /// Full path to the model file
+    pub path: PathBuf,
+
+    /// File size in bytes
+    pub size_bytes: u64,
+
+    /// Whether the model is the one selected in the settings
+    pub in_use: bool,
+}
+
+/// Result of checking a model file
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ModelIntegrity {
+    /// The file matches the checksum recorded when it was downloaded
+    Valid,
+
+    /// The file looks like a model, but no checksum was recorded for it
+    NoChecksum,
+
+    /// The file is truncated, damaged or not a model
+    Corrupt { reason: String },
+}
+
+/// Lists, verifies and removes downloaded Whisper models
+pub struct ModelManager {
+    models_dir: PathBuf,
+}
+
+impl ModelManager {
+    /// Manage the models in `models_dir`
+    pub fn new(models_dir: PathBuf) -> Self {
+        Self { models_dir }
+    }
+
+    /// Models found in the directory, largest first; `active` is the selected model name
+    pub fn list(&self, active: &str) -> Result<Vec<InstalledModel>> {
+        if !self.models_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut models = Vec::new();
+        for entry in fs::read_dir(&self.models_dir).context("Failed to read models directory")? {
+            let path = entry?.path();
+            let Some(name) = model_name(&path) else { continue };
+
+            models.push(InstalledModel {
+                in_use: name == active,
+                size_bytes: fs::metadata(&path)?.len(),
+                name,
+                path,
+            });
+        }
+
+        models.sort_by_key(|model| std::cmp::Reverse(model.size_bytes));
+        Ok(models)
+    }
+
+    /// Delete a model and its checksum, returning the bytes freed
+    pub fn delete(&self, name: &str) -> Result<u64> {
+        let path = self.model_path(name)?;
+        let size = fs::metadata(&path)
+            .with_context(|| format!("Model {} is not installed", name))?
+            .len();
+
+        fs::remove_file(&path).with_context(|| format!("Failed to delete {}", path.display()))?;
+        let _ = fs::remove_file(checksum_path(&path));
+
+        info!("Deleted model {} ({} bytes)", name, size);
+        Ok(size)
+    }
+
+    /// Check a model's header and, when one was recorded, its checksum
+    pub fn verify(&self, name: &str) -> Result<ModelIntegrity> {
+        let path = self.model_path(name)?;
+        let mut file = fs::File::open(&path).with_context(|| format!("Model {} is not installed", name))?;
+
+        let mut magic = [0u8; 4];
+        if file.read_exact(&mut magic).is_err() {
+            return Ok(ModelIntegrity::Corrupt { reason: "file is too short".to_string() });
+        }
+        if u32::from_le_bytes(magic) != GGML_MAGIC && &magic != GGUF_MAGIC {
+            return Ok(ModelIntegrity::Corrupt { reason: "not a GGML or GGUF model".to_string() });
+        }
+
+        let expected = match fs::read_to_string(checksum_path(&path)) {
+            Ok(expected) => expected.trim().to_lowercase(),
+            Err(_) => return Ok(ModelIntegrity::NoChecksum),
+        };
+
+        let actual = sha256_file(&path)?;
+        if actual == expected {
+            Ok(ModelIntegrity::Valid)
+        } else {
+            warn!("Checksum mismatch for model {}: expected {}, got {}", name, expected, actual);
+            Ok(ModelIntegrity::Corrupt { reason: "checksum mismatch".to_string() })
+        }
+    }
+
+    /// Record the checksum of a freshly downloaded model so it can be verified later
+    pub fn record_checksum(&self, name: &str, checksum: &str) -> Result<()> {
+        let path = self.model_path(name)?;
+        fs::write(checksum_path(&path), checksum).context("Failed to write model checksum")
+    }
+
+    /// Total size of the models directory in bytes, including partial downloads
+    pub fn disk_usage(&self) -> Result<u64> {
+        if !self.models_dir.exists() {
+            return Ok(0);
+        }
+
+        let mut total = 0;
+        for entry in fs::read_dir(&self.models_dir).context("Failed to read models directory")? {
+            let metadata = entry?.metadata()?;
+            if metadata.is_file() {
+                total += metadata.len();
+            }
+        }
+        Ok(total)
+    }
+
+    /// Path of a named model, rejecting names that would leave the models directory
+    fn model_path(&self, name: &str) -> Result<PathBuf> {
+        if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+            anyhow::bail!("Invalid model name: {}", name);
+        }
+        Ok(self.models_dir.join(format!("{}{}.{}", MODEL_PREFIX, name, MODEL_EXTENSION)))
+    }
+}
+
+/// Model name for a `ggml-<name>.bin` path
+fn model_name(path: &Path) -> Option<String> {
+    if path.extension()? != MODEL_EXTENSION || !path.is_file() {
+        return None;
+    }
+
+    let stem = path.file_stem()?.to_str()?;
+    stem.strip_prefix(MODEL_PREFIX).map(|name| name.to_string())
+}
+
+/// Checksum file stored alongside a model
+fn checksum_path(model_path: &Path) -> PathBuf {
+    let mut path = model_path.as_os_str().to_owned();
+    path.push(".");
+    path.push(CHECKSUM_EXTENSION);
+    PathBuf::from(path)
+}
+
+/// Hex-encoded SHA-256 of a file
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+
+    // Models are hundreds of megabytes, so hash in large chunks
+    let mut buffer = vec![0u8; 1 << 20];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect())
+}
@@ -1,8 +1,9 @@
 use anyhow::{Result, anyhow};
 use log::{info, debug, error, warn};
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 use reqwest::Client;
 use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, HashSet};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -10,28 +11,146 @@ use std::fs;
 use tauri::{Manager, AppHandle, State, plugin};
 use tokio::sync::mpsc;
 use tokio::io::AsyncWriteExt;
-use whisper_rs::{WhisperContext, FullParams, SamplingStrategy};
 use futures::StreamExt;
 use serde_json::json;
 use std::marker::PhantomData;
 
+use bestme::audio::benchmark::{self, BenchmarkResult, BenchmarkSample};
 use bestme::audio::capture::AudioData;
-use bestme::config::{ConfigManager, WhisperModelSize};
+use bestme::audio::denoise::NoiseSuppressor;
+use bestme::audio::clipboard::{ClipboardEntry, ClipboardOutput};
+use bestme::audio::cloud::CloudEngine;
+use bestme::audio::corrections::{CorrectionSuggestion, CorrectionTracker};
+use bestme::audio::engine::{self, FallbackEngine, SttEngine, TranscribeOptions, TranscriptSegment, WhisperEngine};
+use bestme::audio::export::{self, ExportFormat};
+use bestme::audio::file::{self as audio_file, FileTranscriptionEvent};
+use bestme::audio::formatting::TranscriptFormatter;
+use bestme::audio::hallucination::{self, Hallucination, HallucinationStats};
+use bestme::audio::live_output::LiveOutput;
+use bestme::audio::history::{DateRange, SearchHit, TranscriptHistory, DEFAULT_SEARCH_LIMIT};
+use bestme::audio::overlap;
+use bestme::audio::partial::{PartialStabilizer, SlidingCaption};
+use bestme::audio::pipeline::{TextPipeline, TextProcessor};
+use bestme::audio::profanity::ProfanityFilter;
+use bestme::audio::punctuate::Punctuator;
+use bestme::audio::recording;
+use bestme::audio::refine::{RefineKind, Refiner};
+use bestme::audio::replace::{self, ReplacementPreview};
+use bestme::audio::resample::{self, StreamResampler};
+use bestme::audio::retranscribe::{self, TranscriptVersion};
+use bestme::audio::script_hooks::ScriptHooks;
+use bestme::audio::segmenter::SpeechSegmenter;
+use bestme::audio::session::{Session, SessionStore, SessionSummary};
+use bestme::audio::silence::{SilenceTrimmer, Trimmed};
+use bestme::audio::speaker_change::{SpeakerChangeDetector, VoiceProfile};
+use bestme::audio::subtitle;
+use bestme::audio::throughput::{SegmentMetrics, ThroughputStats, ThroughputSummary};
+use bestme::audio::transcript_file::TranscriptFile;
+use bestme::audio::vosk;
+#[cfg(feature = "vosk")]
+use bestme::audio::vosk::VoskEngine;
+use bestme::audio::wake_word::{WakeWordDetector, WakeWordModel, WAKE_WORD_SAMPLE_RATE};
+use bestme::audio::transcribe::{label_segment, Speaker};
+use bestme::config::{ClipboardOutputSettings, ConfigManager, CUSTOM_MODEL_PREFIX, ModelDownloadSettings, ModelQuantization, ReplacementDictionary, ReplacementRule, SpeechSettings, SttEngineKind, WhisperModelSize};
+
+use super::models::{sha256_file, InstalledModel, ModelIntegrity, ModelManager};
+use super::voice_commands::VoiceCommandState;
 
 // Constants for audio processing
 const WHISPER_SAMPLE_RATE: usize = 16000;
 const AUDIO_BUFFER_SIZE: usize = WHISPER_SAMPLE_RATE * 5; // 5 seconds of audio
 const MAX_TEXT_LENGTH: usize = 8192;
+const BENCHMARK_SAMPLE: &str = "resources/benchmark.wav";
+const IDLE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
 
-/// The model URLs for each Whisper model size
-const MODEL_URLS: [(&str, &str); 5] = [
-    ("tiny", "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-tiny.bin"),
-    ("base", "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin"),
-    ("small", "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.bin"),
-    ("medium", "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-medium.bin"),
-    ("large", "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large.bin"),
+/// The model URLs for each Whisper model, including quantized variants, with the SHA-256 of each file
+///
+/// Upstream only publishes q5_0 weights for the medium and large models. The
+/// distilled English models are published by the distil-whisper project.
+/// Downloads are checked against the pinned SHA-256, and a model without one
+/// isn't downloaded at all, since nothing would show the file is the right one.
+// TODO: Pin the SHA-256 of each file as published upstream
+const MODEL_URLS: [(&str, &str, Option<&str>); 17] = [
+    ("tiny", "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-tiny.bin", None),
+    ("base", "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin", None),
+    ("small", "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.bin", None),
+    ("medium", "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-medium.bin", None),
+    ("large", "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large.bin", None),
+    ("tiny-q8_0", "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-tiny-q8_0.bin", None),
+    ("base-q8_0", "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base-q8_0.bin", None),
+    ("small-q8_0", "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small-q8_0.bin", None),
+    ("medium-q5_0", "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-medium-q5_0.bin", None),
+    ("medium-q8_0", "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-medium-q8_0.bin", None),
+    ("large-q5_0", "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v2-q5_0.bin", None),
+    ("large-q8_0", "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v2-q8_0.bin", None),
+    ("large-v3-turbo", "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3-turbo.bin", None),
+    ("large-v3-turbo-q5_0", "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3-turbo-q5_0.bin", None),
+    ("large-v3-turbo-q8_0", "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3-turbo-q8_0.bin", None),
+    ("distil-small.en", "https://huggingface.co/distil-whisper/distil-small.en/resolve/main/ggml-distil-small.en.bin", None),
+    ("distil-medium.en", "https://huggingface.co/distil-whisper/distil-medium.en/resolve/main/ggml-medium-32-2.en.bin", None),
 ];
 
+/// Attempts made before a model download is abandoned
+const DOWNLOAD_ATTEMPTS: u32 = 5;
+
+/// URLs to download a model from: each configured mirror, then the upstream URL
+///
+/// Mirrors are expected to serve the upstream file names under their base URL.
+fn download_urls(upstream_url: &str, mirrors: &[String]) -> Vec<String> {
+    let file_name = upstream_url.rsplit('/').next().unwrap_or(upstream_url);
+    
+    mirrors.iter()
+        .map(|mirror| format!("{}/{}", mirror.trim_end_matches('/'), file_name))
+        .chain(std::iter::once(upstream_url.to_string()))
+        .collect()
+}
+
+/// HTTP client for model downloads, going through the configured proxy if there is one
+///
+/// Without a configured proxy reqwest uses the HTTP_PROXY/HTTPS_PROXY environment variables.
+fn download_client(settings: &ModelDownloadSettings) -> Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(proxy) = &settings.proxy {
+        let proxy = reqwest::Proxy::all(proxy)
+            .map_err(|e| anyhow::anyhow!("Invalid download proxy {}: {}", proxy, e))?;
+        builder = builder.proxy(proxy);
+    }
+    Ok(builder.build()?)
+}
+
+/// Model live captions are transcribed with, when one is configured besides the main model
+fn caption_model(speech: &SpeechSettings) -> Option<&str> {
+    speech.partials.fast_model.as_deref()
+        .filter(|model| !model.is_empty() && *model != speech.model_name())
+}
+
+/// Open the transcript history, moving in sessions and JSON transcripts saved as files when it is new
+///
+/// Without a usable database file, sessions are only kept until the app closes.
+fn open_history() -> Result<TranscriptHistory> {
+    let opened = TranscriptHistory::default_path().and_then(|path| {
+        let fresh = !path.exists();
+        let history = TranscriptHistory::open(&path)?;
+        if fresh {
+            let imported = SessionStore::default_dir().and_then(|dir| history.import(&SessionStore::new(dir)));
+            if let Err(e) = imported {
+                warn!("Failed to import saved sessions: {}", e);
+            }
+            
+            let imported = TranscriptFile::default_dir().and_then(|dir| history.import_transcripts(&dir));
+            if let Err(e) = imported {
+                warn!("Failed to import transcript files: {}", e);
+            }
+        }
+        Ok(history)
+    });
+    
+    opened.or_else(|e| {
+        error!("Failed to open transcript history: {}", e);
+        TranscriptHistory::open_in_memory()
+    })
+}
+
 /// Supported language codes for Whisper
 pub const SUPPORTED_LANGUAGES: &[(&str, &str)] = &[
     ("auto", "Auto-detect"),
@@ -76,17 +195,35 @@ pub const SUPPORTED_LANGUAGES: &[(&str, &str)] = &[
     ("az", "Azerbaijani"),
 ];
 
+/// Carries out the voice commands in final text, giving back what was dictated
+pub type CommandHandler = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
 // Structure to hold transcription state
 pub struct TranscribeState {
     config_manager: Arc<Mutex<ConfigManager>>,
-    transcription_text: Arc<Mutex<String>>,
+    session: Arc<Mutex<Option<Session>>>, // the running session, or the last one once stopped
+    history: TranscriptHistory,
+    live_output: Arc<Mutex<Option<LiveOutput>>>, // file final text is appended to, when live output is on
+    clipboard_output: Arc<Mutex<ClipboardOutput>>, // copies final text when clipboard output is on, keeping its history across sessions
+    pipeline: Arc<RwLock<TextPipeline>>, // post-processing of final text, rebuilt when its settings change
+    script_steps: Vec<Arc<dyn TextProcessor>>, // the hook scripts' on_final_text step, kept when the pipeline is rebuilt
     transcription_active: Arc<Mutex<bool>>,
+    transcription_paused: Arc<Mutex<bool>>, // text is only listened to for voice commands while paused
+    command_handler: Arc<RwLock<Option<CommandHandler>>>,
     audio_receiver: Arc<Mutex<Option<mpsc::Receiver<AudioData>>>>,
     audio_sender: Arc<Mutex<Option<mpsc::Sender<AudioData>>>>,
-    whisper_context: Arc<Mutex<Option<WhisperContext>>>,
+    engine: Arc<RwLock<Option<Box<dyn SttEngine>>>>,
+    engine_settings: Arc<Mutex<Option<SpeechSettings>>>, // settings the loaded engine was built from
+    engine_loading: Arc<tokio::sync::Mutex<()>>,
+    engine_last_used: Arc<Mutex<std::time::Instant>>,
+    caption_engine: Arc<RwLock<Option<(String, Box<dyn SttEngine>)>>>, // fast model for live captions, by name
     audio_buffer: Arc<Mutex<Vec<f32>>>,
     app_handle: Option<AppHandle>,
     download_progress: Arc<Mutex<Option<(String, f32)>>>, // (model_size, progress 0.0-1.0)
+    noise_suppression: Arc<Mutex<bool>>,
+    hallucinations: Arc<Mutex<HallucinationStats>>,
+    throughput: Arc<Mutex<ThroughputStats>>,
+    corrections: Arc<Mutex<CorrectionTracker>>, // corrections made to transcripts, for learning replacement rules
     get_model_path: Box<dyn Fn(&str) -> PathBuf + Send + Sync>,
 }
 
@@ -94,8 +231,25 @@ impl TranscribeState {
     pub fn new(config_manager: Arc<Mutex<ConfigManager>>, app_handle: Option<AppHandle>) -> Result<Self, anyhow::Error> {
         let (audio_sender, audio_receiver) = tokio::sync::mpsc::channel(100);
         
+        let noise_suppression = config_manager.lock().get_config().audio.noise_suppression;
+        let hook_scripts = config_manager.lock().get_config().audio.voice_commands.hook_scripts.clone();
+        let script_steps: Vec<Arc<dyn TextProcessor>> = if hook_scripts.is_empty() {
+            Vec::new()
+        } else {
+            vec![Arc::new(ScriptHooks::new(&hook_scripts))]
+        };
+        let pipeline = TextPipeline::from_settings(&config_manager.lock().get_config().audio.speech, Punctuator::new(), &script_steps);
+        
         // Default function to get model path - uses app directory
         let get_model_path: Box<dyn Fn(&str) -> PathBuf + Send + Sync> = Box::new(move |model_size| {
+            // Registered custom models are used from wherever they are
+            if let Some(name) = model_size.strip_prefix(CUSTOM_MODEL_PREFIX) {
+                let config_manager = config_manager.lock();
+                if let Some(model) = config_manager.get_config().audio.speech.custom_models.iter().find(|model| model.name == name) {
+                    return PathBuf::from(&model.path);
+                }
+            }
+            
             // First check if there's a custom model path in config
             let custom_path = {
                 let config_manager = config_manager.lock();
@@ -129,16 +283,41 @@ impl TranscribeState {
             models_dir.join(format!("ggml-{}.bin", model_size))
         });
         
+        // Sessions are kept in a searchable database with the other application data
+        let history = open_history()?;
+        
+        let corrections = CorrectionTracker::default_path()
+            .and_then(|path| CorrectionTracker::load(&path))
+            .unwrap_or_else(|e| {
+                warn!("Failed to load corrections: {}", e);
+                CorrectionTracker::default()
+            });
+        
         Ok(Self {
             config_manager,
-            transcription_text: Arc::new(Mutex::new(String::new())),
+            session: Arc::new(Mutex::new(None)),
+            history,
+            live_output: Arc::new(Mutex::new(None)),
+            clipboard_output: Arc::new(Mutex::new(ClipboardOutput::new(ClipboardOutputSettings::default()))),
+            pipeline: Arc::new(RwLock::new(pipeline)),
+            script_steps,
             transcription_active: Arc::new(Mutex::new(false)),
+            transcription_paused: Arc::new(Mutex::new(false)),
+            command_handler: Arc::new(RwLock::new(None)),
             audio_receiver: Arc::new(Mutex::new(Some(audio_receiver))),
             audio_sender: Arc::new(Mutex::new(Some(audio_sender))),
-            whisper_context: Arc::new(Mutex::new(None)),
+            engine: Arc::new(RwLock::new(None)),
+            engine_settings: Arc::new(Mutex::new(None)),
+            engine_loading: Arc::new(tokio::sync::Mutex::new(())),
+            engine_last_used: Arc::new(Mutex::new(std::time::Instant::now())),
+            caption_engine: Arc::new(RwLock::new(None)),
             audio_buffer: Arc::new(Mutex::new(Vec::with_capacity(AUDIO_BUFFER_SIZE))),
             app_handle,
             download_progress: Arc::new(Mutex::new(None)),
+            noise_suppression: Arc::new(Mutex::new(noise_suppression)),
+            hallucinations: Arc::new(Mutex::new(HallucinationStats::default())),
+            throughput: Arc::new(Mutex::new(ThroughputStats::default())),
+            corrections: Arc::new(Mutex::new(corrections)),
             get_model_path,
         })
     }
@@ -148,6 +327,12 @@ impl TranscribeState {
         Ok(())
     }
 
+    /// Hand final text to `handler` before it is kept, so the trigger words of the
+    /// voice commands it carries out stay out of the transcript
+    pub fn set_command_handler(&self, handler: impl Fn(&str) -> String + Send + Sync + 'static) {
+        *self.command_handler.write() = Some(Arc::new(handler));
+    }
+    
     pub fn create_audio_channel(&self) -> mpsc::Sender<AudioData> {
         let (sender, receiver) = mpsc::channel::<AudioData>(100);
         
@@ -164,65 +349,765 @@ impl TranscribeState {
         sender
     }
 
+    // The end of the current session's transcript, for the live view
     pub fn get_transcription(&self) -> String {
-        let text = self.transcription_text.lock();
-        text.clone()
+        let text = self.session.lock().as_ref().map(Session::text).unwrap_or_default();
+        let skip = text.chars().count().saturating_sub(MAX_TEXT_LENGTH);
+        text.chars().skip(skip).collect()
+    }
+    
+    pub fn get_current_session(&self) -> Option<Session> {
+        self.session.lock().clone()
+    }
+    
+    // Saved sessions, newest first
+    pub fn list_sessions(&self) -> Result<Vec<SessionSummary>> {
+        self.history.list_sessions()
+    }
+    
+    pub fn get_session(&self, id: &str) -> Result<Session> {
+        match self.get_current_session().filter(|session| session.id == id) {
+            Some(session) => Ok(session),
+            None => self.history.load_session(id),
+        }
+    }
+    
+    // Title a session, or remove its title with None
+    pub fn rename_session(&self, id: &str, title: Option<String>) -> Result<Session> {
+        // The current session may have segments that aren't saved yet
+        let current = {
+            let mut current = self.session.lock();
+            current.as_mut().filter(|session| session.id == id).map(|session| {
+                session.title = title.clone().filter(|title| !title.trim().is_empty());
+                session.clone()
+            })
+        };
+        
+        match current {
+            Some(session) => {
+                self.history.save_session(&session)?;
+                Ok(session)
+            }
+            None => self.history.rename_session(id, title),
+        }
+    }
+    
+    pub fn delete_session(&self, id: &str) -> Result<()> {
+        let transcribing = self.is_transcribing();
+        {
+            let mut current = self.session.lock();
+            if current.as_ref().is_some_and(|session| session.id == id) {
+                if transcribing {
+                    return Err(anyhow!("Can't delete the session being transcribed"));
+                }
+                *current = None;
+            }
+        }
+        
+        self.history.delete_session(id)
+    }
+    
+    // Summarize a saved session, or the current one, with the configured language model,
+    // also sending the summary as a "transcription:refined" event
+    pub async fn summarize_session(&self, id: Option<&str>) -> Result<String> {
+        let session = match id {
+            Some(id) => self.get_session(id)?,
+            None => self.get_current_session().ok_or_else(|| anyhow!("No session to summarize"))?,
+        };
+        
+        let refine = self.config_manager.lock().get_config().audio.speech.refine.clone();
+        let summary = Refiner::new(refine)?.summarize(&session).await?;
+        
+        if let Some(handle) = &self.app_handle {
+            let _ = handle.emit_all(
+                "transcription:refined",
+                json!({
+                    "kind": RefineKind::Summary.name(),
+                    "session_id": session.id,
+                    "text": summary
+                })
+            );
+        }
+        Ok(summary)
+    }
+    
+    // Segments of saved sessions containing the words of `query`, best matches first
+    pub fn search_transcripts(&self, query: &str, range: &DateRange, limit: usize) -> Result<Vec<SearchHit>> {
+        self.history.search(query, range, limit)
+    }
+    
+    // Copy JSON transcript files of any version into the history, from the transcripts directory unless
+    // another is given; returns the number copied
+    pub fn import_transcripts(&self, dir: Option<&Path>) -> Result<usize> {
+        match dir {
+            Some(dir) => self.history.import_transcripts(dir),
+            None => self.history.import_transcripts(&TranscriptFile::default_dir()?),
+        }
+    }
+    
+    // Save the current session, so it shows up in the history
+    fn save_session(&self) {
+        let session = self.session.lock().clone();
+        if let Some(session) = session {
+            if let Err(e) = self.history.save_session(&session) {
+                warn!("Failed to save session {}: {}", session.id, e);
+            }
+        }
     }
     
     pub fn get_download_progress(&self) -> Option<(String, f32)> {
         let progress = self.download_progress.lock();
         progress.clone()
     }
+    
+    // Segments dropped as hallucinations since the app started
+    pub fn get_hallucination_stats(&self) -> HallucinationStats {
+        *self.hallucinations.lock()
+    }
+    
+    // Rolling processing metrics over recent segments, to tell whether the model keeps up
+    pub fn get_throughput(&self) -> ThroughputSummary {
+        self.throughput.lock().summary()
+    }
+    
+    pub fn is_noise_suppression_enabled(&self) -> bool {
+        *self.noise_suppression.lock()
+    }
+    
+    // Enable or bypass noise suppression, persisting the choice in the config
+    pub fn set_noise_suppression(&self, enabled: bool) -> Result<()> {
+        {
+            let mut noise_suppression = self.noise_suppression.lock();
+            *noise_suppression = enabled;
+        }
+        
+        let mut config_manager = self.config_manager.lock();
+        config_manager.get_config_mut().audio.noise_suppression = enabled;
+        config_manager.save()?;
+        
+        info!("Noise suppression {}", if enabled { "enabled" } else { "bypassed" });
+        Ok(())
+    }
+    
+    pub fn get_replacement_rules(&self) -> Vec<ReplacementRule> {
+        self.config_manager.lock().get_config().audio.speech.replacements.clone()
+    }
+    
+    // Replace the find/replace rules, persisting them in the config; they apply from the next segment
+    pub fn set_replacement_rules(&self, rules: Vec<ReplacementRule>) -> Result<()> {
+        for (i, rule) in rules.iter().enumerate().filter(|(_, rule)| rule.enabled && !rule.pattern.is_empty()) {
+            replace::compile(rule).map_err(|e| anyhow!("Rule {}: {:#}", i + 1, e))?;
+        }
+        
+        {
+            let mut config_manager = self.config_manager.lock();
+            config_manager.get_config_mut().audio.speech.replacements = rules;
+            config_manager.save()?;
+        }
+        
+        self.rebuild_pipeline();
+        Ok(())
+    }
+    
+    // Build the post-processing of final text from the current settings
+    fn rebuild_pipeline(&self) {
+        let speech = self.config_manager.lock().get_config().audio.speech.clone();
+        *self.pipeline.write() = TextPipeline::from_settings(&speech, Punctuator::new(), &self.script_steps);
+    }
+    
+    // Insert a rule at `index`, or after the others, returning the new list
+    pub fn add_replacement_rule(&self, rule: ReplacementRule, index: Option<usize>) -> Result<Vec<ReplacementRule>> {
+        let mut rules = self.get_replacement_rules();
+        let index = index.unwrap_or(rules.len()).min(rules.len());
+        rules.insert(index, rule);
+        self.set_replacement_rules(rules.clone())?;
+        Ok(rules)
+    }
+    
+    // Remove the rule at `index`, returning the new list
+    pub fn remove_replacement_rule(&self, index: usize) -> Result<Vec<ReplacementRule>> {
+        let mut rules = self.get_replacement_rules();
+        if index >= rules.len() {
+            return Err(anyhow!("No replacement rule {}", index));
+        }
+        rules.remove(index);
+        self.set_replacement_rules(rules.clone())?;
+        Ok(rules)
+    }
+    
+    pub fn get_replacement_dictionaries(&self) -> Vec<ReplacementDictionary> {
+        self.config_manager.lock().get_config().audio.speech.dictionaries.clone()
+    }
+    
+    // Replace the named rule sets, persisting them; the active one stays active if it is still there
+    pub fn set_replacement_dictionaries(&self, dictionaries: Vec<ReplacementDictionary>) -> Result<()> {
+        for (i, dictionary) in dictionaries.iter().enumerate() {
+            if dictionary.name.trim().is_empty() {
+                return Err(anyhow!("Dictionary {} has no name", i + 1));
+            }
+            if dictionaries[..i].iter().any(|other| other.name == dictionary.name) {
+                return Err(anyhow!("There are two dictionaries named {}", dictionary.name));
+            }
+            for (j, rule) in dictionary.rules.iter().enumerate().filter(|(_, rule)| rule.enabled && !rule.pattern.is_empty()) {
+                replace::compile(rule).map_err(|e| anyhow!("{}, rule {}: {:#}", dictionary.name, j + 1, e))?;
+            }
+        }
+        
+        {
+            let mut config_manager = self.config_manager.lock();
+            let speech = &mut config_manager.get_config_mut().audio.speech;
+            if let Some(active) = speech.active_dictionary.clone() {
+                if !dictionaries.iter().any(|dictionary| dictionary.name == active) {
+                    info!("Dictionary {} removed, deactivating it", active);
+                    speech.active_dictionary = None;
+                }
+            }
+            speech.dictionaries = dictionaries;
+            config_manager.save()?;
+        }
+        
+        self.rebuild_pipeline();
+        Ok(())
+    }
+    
+    pub fn get_active_dictionary(&self) -> Option<String> {
+        self.config_manager.lock().get_config().audio.speech.active_dictionary.clone()
+    }
+    
+    // Apply a dictionary's rules after the general ones from the next segment, or none of them
+    pub fn set_active_dictionary(&self, name: Option<String>) -> Result<()> {
+        {
+            let mut config_manager = self.config_manager.lock();
+            let speech = &mut config_manager.get_config_mut().audio.speech;
+            if let Some(name) = &name {
+                if !speech.dictionaries.iter().any(|dictionary| &dictionary.name == name) {
+                    return Err(anyhow!("No dictionary named {}", name));
+                }
+            }
+            if speech.active_dictionary == name {
+                return Ok(());
+            }
+            
+            speech.active_dictionary = name.clone();
+            config_manager.save()?;
+        }
+        
+        info!("Active dictionary: {}", name.as_deref().unwrap_or("none"));
+        self.rebuild_pipeline();
+        Ok(())
+    }
+    
+    // Activate the dictionary for the application being dictated into, or none if it has none,
+    // returning the active dictionary's name
+    pub fn activate_dictionary_for_application(&self, application: &str) -> Result<Option<String>> {
+        let name = self.config_manager.lock().get_config().audio.speech
+            .dictionary_for_application(application)
+            .map(|dictionary| dictionary.name.clone());
+        self.set_active_dictionary(name.clone())?;
+        Ok(name)
+    }
+    
+    // The enrolled wake word, if there is one
+    pub fn get_wake_word(&self) -> Option<String> {
+        let path = WakeWordModel::default_path().ok().filter(|path| path.exists())?;
+        WakeWordModel::load(&path).ok().map(|model| model.phrase)
+    }
+    
+    // Enroll the wake word from recordings of the user saying it, replacing any enrolled before;
+    // it is listened for from the next run of transcription
+    pub fn enroll_wake_word(&self, phrase: &str, recordings: &[PathBuf]) -> Result<()> {
+        let quality = self.config_manager.lock().get_config().audio.resampler.quality.clone();
+        let recordings = recordings.iter()
+            .map(|path| {
+                let decoded = audio_file::decode_audio_file(path)?;
+                resample::resample_once(&decoded.samples, decoded.sample_rate, WAKE_WORD_SAMPLE_RATE, &quality)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        
+        let model = WakeWordModel::enroll(phrase, &recordings)?;
+        model.save(&WakeWordModel::default_path()?)?;
+        info!("Wake word \"{}\" enrolled", model.phrase);
+        Ok(())
+    }
+    
+    // Change the text of a segment in the current or a saved session, learning from the correction
+    //
+    // The line or paragraph break before the segment is kept.
+    pub fn edit_segment(&self, session_id: &str, index: usize, text: &str) -> Result<Session> {
+        let edit = |session: &mut Session| -> Result<String> {
+            let segment = session.segments.get_mut(index)
+                .ok_or_else(|| anyhow!("Session {} has no segment {}", session.id, index))?;
+            let original = segment.text.clone();
+            let break_before = &original[..original.len() - original.trim_start().len()];
+            segment.text = format!("{}{}", break_before, text.trim_start());
+            Ok(original)
+        };
+        
+        // The current session may have segments that aren't saved yet
+        let current = {
+            let mut current = self.session.lock();
+            match current.as_mut().filter(|session| session.id == session_id) {
+                Some(session) => Some(edit(session).map(|original| (session.clone(), original))?),
+                None => None,
+            }
+        };
+        
+        let (session, original) = match current {
+            Some(edited) => edited,
+            None => {
+                let mut session = self.history.load_session(session_id)?;
+                let original = edit(&mut session)?;
+                (session, original)
+            }
+        };
+        self.history.save_session(&session)?;
+        
+        self.learn_correction(&original, text);
+        Ok(session)
+    }
+    
+    // Count the corrections in an edit, suggesting the frequent ones as replacement rules with a
+    // "transcription:correction-suggested" event, or adding them once made often enough
+    fn learn_correction(&self, original: &str, corrected: &str) {
+        let settings = self.config_manager.lock().get_config().audio.speech.corrections.clone();
+        if !settings.learn {
+            return;
+        }
+        
+        let counted = {
+            let mut corrections = self.corrections.lock();
+            let counted = corrections.record(original, corrected);
+            if counted.is_empty() {
+                return;
+            }
+            self.save_corrections(&corrections);
+            counted
+        };
+        
+        for suggestion in counted.into_iter().filter(|suggestion| !self.has_rule_for(&suggestion.pattern)) {
+            if settings.auto_add_after > 0 && suggestion.count >= settings.auto_add_after {
+                match self.accept_correction(&suggestion.pattern, &suggestion.replacement) {
+                    Ok(_) => {
+                        info!("Learned replacement rule: {} -> {}", suggestion.pattern, suggestion.replacement);
+                        if let Some(handle) = &self.app_handle {
+                            let _ = handle.emit_all("transcription:correction-learned", &suggestion);
+                        }
+                    }
+                    Err(e) => warn!("Failed to add learned replacement rule: {}", e),
+                }
+            } else if suggestion.count >= settings.suggest_after {
+                if let Some(handle) = &self.app_handle {
+                    let _ = handle.emit_all("transcription:correction-suggested", &suggestion);
+                }
+            }
+        }
+    }
+    
+    // Whether an active replacement rule already finds `pattern`
+    fn has_rule_for(&self, pattern: &str) -> bool {
+        self.config_manager.lock().get_config().audio.speech.active_replacements()
+            .iter()
+            .any(|rule| rule.enabled && !rule.regex && rule.pattern.eq_ignore_ascii_case(pattern))
+    }
+    
+    fn save_corrections(&self, corrections: &CorrectionTracker) {
+        if let Err(e) = CorrectionTracker::default_path().and_then(|path| corrections.save(&path)) {
+            warn!("Failed to save corrections: {}", e);
+        }
+    }
+    
+    // Corrections made often enough to suggest as replacement rules, most frequent first
+    pub fn get_correction_suggestions(&self) -> Vec<CorrectionSuggestion> {
+        let suggest_after = self.config_manager.lock().get_config().audio.speech.corrections.suggest_after;
+        self.corrections.lock().suggestions(suggest_after)
+            .into_iter()
+            .filter(|suggestion| !self.has_rule_for(&suggestion.pattern))
+            .collect()
+    }
+    
+    // Add a suggested correction as a replacement rule, returning the new list of rules
+    pub fn accept_correction(&self, pattern: &str, replacement: &str) -> Result<Vec<ReplacementRule>> {
+        let suggestion = CorrectionSuggestion {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            count: 0,
+        };
+        let rules = self.add_replacement_rule(suggestion.to_rule(), None)?;
+        
+        let mut corrections = self.corrections.lock();
+        corrections.forget(pattern, replacement);
+        self.save_corrections(&corrections);
+        Ok(rules)
+    }
+    
+    // Stop suggesting a correction
+    pub fn dismiss_correction(&self, pattern: &str, replacement: &str) {
+        let mut corrections = self.corrections.lock();
+        corrections.dismiss(pattern, replacement);
+        self.save_corrections(&corrections);
+    }
 
-    // Load Whisper model based on model size
-    async fn load_whisper_model(&self, model_size: &WhisperModelSize) -> Result<()> {
-        // Get model path from config or use default path
-        let model_path = (self.get_model_path)(self.get_model_size_string(model_size));
+    // Load the configured speech-to-text engine
+    //
+    // The cloud engine falls back to the local Whisper model when it is installed,
+    // but never downloads it, so cloud users don't pay for a model they may not need.
+    async fn load_engine(&self, speech: &SpeechSettings) -> Result<()> {
+        let model_name = speech.model_name();
+        let mut engine: Box<dyn SttEngine> = match speech.engine {
+            SttEngineKind::Cloud if speech.download.offline => {
+                info!("Offline mode is enabled, transcribing locally");
+                Box::new(self.whisper_engine(&model_name).await?)
+            }
+            SttEngineKind::Cloud => {
+                let cloud = Box::new(CloudEngine::new(speech.cloud.clone()));
+                let model_path = self.get_model_path(&model_name);
+                if model_path.exists() {
+                    Box::new(FallbackEngine::new(cloud, Box::new(WhisperEngine::new(model_path))))
+                } else {
+                    warn!("Whisper model {} is not installed, cloud transcription can't fall back when offline", model_name);
+                    cloud
+                }
+            }
+            #[cfg(feature = "vosk")]
+            SttEngineKind::Vosk => Box::new(self.vosk_engine(&speech.vosk.model).await?),
+            #[cfg(not(feature = "vosk"))]
+            SttEngineKind::Vosk => return Err(anyhow::anyhow!("This build does not include the Vosk engine")),
+            SttEngineKind::Whisper => Box::new(self.whisper_engine(&model_name).await?),
+        };
         
-        info!("Loading Whisper model: {:?} from {:?}", model_size, model_path);
+        // Load in a blocking task since loading a model is CPU-intensive
+        let loaded = tokio::task::spawn_blocking(move || -> Result<Box<dyn SttEngine>> {
+            engine.load()?;
+            Ok(engine)
+        }).await?;
         
-        // Check if model exists, if not, try to download it
-        if !model_path.exists() {
-            info!("Model file not found, attempting to download it");
-            self.download_model(model_size, &model_path).await?;
-        }
-        
-        // Load model in a blocking task since it's CPU-intensive
-        let model_path_str = model_path.to_string_lossy().to_string();
-        match tokio::task::spawn_blocking(move || {
-            // Use the new_with_params method instead of the deprecated new method
-            WhisperContext::new_with_params(&model_path_str, Default::default())
-        }).await? {
-            Ok(context) => {
-                let mut whisper_context = self.whisper_context.lock();
-                *whisper_context = Some(context);
-                info!("Whisper model loaded successfully");
+        match loaded {
+            Ok(engine) => {
+                info!("The {} engine loaded successfully", engine.name());
+                self.set_engine(engine);
+                *self.engine_settings.lock() = Some(speech.clone());
+                *self.engine_last_used.lock() = std::time::Instant::now();
                 Ok(())
             },
             Err(e) => {
-                error!("Failed to load Whisper model: {}", e);
-                Err(anyhow::anyhow!("Failed to load Whisper model: {}", e))
+                error!("Failed to load the speech-to-text engine: {}", e);
+                Err(e)
             }
         }
     }
     
-    // Get model path based on model size
-    fn get_model_path(&self, model_size: &WhisperModelSize) -> PathBuf {
-        (self.get_model_path)(self.get_model_size_string(model_size))
+    // Load the configured engine unless it is already loaded with the same settings
+    //
+    // Waits for a load already in progress, e.g. a preload started at launch,
+    // instead of loading the model a second time.
+    async fn ensure_engine(&self, speech: &SpeechSettings) -> Result<()> {
+        let _loading = self.engine_loading.lock().await;
+        
+        let loaded = self.engine.read().as_ref().is_some_and(|engine| engine.is_loaded());
+        let same = self.engine_settings.lock().as_ref().is_some_and(|settings| settings.same_engine(speech));
+        if loaded && same {
+            debug!("The speech-to-text engine is already loaded");
+            return Ok(());
+        }
+        
+        self.load_engine(speech).await
     }
     
-    // Download the model (using method that mirrors Tauri 2.0's model)
-    async fn download_model(&self, model_size: &WhisperModelSize, model_path: &Path) -> Result<()> {
-        let model_name = self.get_model_size_string(model_size);
+    // Load the configured engine in the background so the first transcription doesn't wait for it
+    //
+    // After loading, a second of silence is transcribed to warm the engine up.
+    // Progress is reported with "transcription:preload" events whose stage is
+    // loading, warming, ready or failed.
+    pub async fn preload_engine(&self, app_handle: Option<AppHandle>) -> Result<()> {
+        let speech = self.config_manager.lock().get_config().audio.speech.clone();
+        let started = std::time::Instant::now();
+        let emit = |stage: &str, progress: f32, error: Option<String>| {
+            if let Some(handle) = &app_handle {
+                let _ = handle.emit_all(
+                    "transcription:preload",
+                    json!({
+                        "stage": stage,
+                        "progress": progress,
+                        "model": speech.model_name(),
+                        "elapsed_ms": started.elapsed().as_millis() as u64,
+                        "error": error,
+                    })
+                );
+            }
+        };
+        
+        info!("Preloading the speech-to-text engine");
+        emit("loading", 0.0, None);
+        
+        if let Err(e) = self.ensure_engine(&speech).await {
+            error!("Failed to preload the speech-to-text engine: {}", e);
+            emit("failed", 0.0, Some(e.to_string()));
+            return Err(e);
+        }
+        
+        // The cloud engine would send the silence over the network
+        let local = speech.engine != SttEngineKind::Cloud || speech.download.offline;
+        if speech.preload.warm_up && local {
+            emit("warming", 0.5, None);
+            
+            let engine = Arc::clone(&self.engine);
+            let options = TranscribeOptions::from_settings(&speech);
+            let warmed = tokio::task::spawn_blocking(move || match engine.read().as_ref() {
+                Some(engine) => engine::warm_up(engine.as_ref(), &options),
+                None => Err(anyhow::anyhow!("No speech-to-text engine loaded")),
+            }).await?;
+            
+            // A failed warm-up only means the first transcription is slower
+            match warmed {
+                Ok(elapsed) => debug!("Warmed up the speech-to-text engine in {:?}", elapsed),
+                Err(e) => warn!("Failed to warm up the speech-to-text engine: {}", e),
+            }
+        }
+        
+        info!("Speech-to-text engine ready after {:?}", started.elapsed());
+        emit("ready", 1.0, None);
+        Ok(())
+    }
+    
+    // Unload the engine once it has gone unused for the configured time, checking until the app exits
+    //
+    // Nothing is unloaded while transcription is active. The engine is loaded
+    // again the next time it is needed, and "transcription:model-unloaded" is
+    // emitted so the frontend can show that the next start will take longer.
+    pub async fn unload_when_idle(&self, app_handle: Option<AppHandle>) {
+        let mut interval = tokio::time::interval(IDLE_CHECK_INTERVAL);
+        
+        loop {
+            interval.tick().await;
+            
+            let idle_unload = self.config_manager.lock().get_config().audio.speech.idle_unload.clone();
+            if !idle_unload.enabled || self.is_transcribing() {
+                continue;
+            }
+            
+            let timeout = std::time::Duration::from_secs(idle_unload.timeout_mins as u64 * 60);
+            if self.engine_last_used.lock().elapsed() < timeout {
+                continue;
+            }
+            
+            // Don't take the engine from under a load in progress
+            let _loading = self.engine_loading.lock().await;
+            let engine = self.engine.write().take();
+            if let Some(mut engine) = engine {
+                info!("Unloading the {} engine after {} minutes without transcription", engine.name(), idle_unload.timeout_mins);
+                engine.unload();
+                *self.engine_settings.lock() = None;
+                
+                if let Some((_, mut caption_engine)) = self.caption_engine.write().take() {
+                    caption_engine.unload();
+                }
+                
+                if let Some(handle) = &app_handle {
+                    let _ = handle.emit_all(
+                        "transcription:model-unloaded",
+                        json!({ "idle_mins": idle_unload.timeout_mins })
+                    );
+                }
+            }
+        }
+    }
+    
+    // Load the fast model for live captions, if one is configured and it isn't loaded yet
+    async fn ensure_caption_engine(&self, speech: &SpeechSettings) -> Result<()> {
+        let Some(fast_model) = caption_model(speech) else {
+            return Ok(());
+        };
+        
+        let loaded = self.caption_engine.read().as_ref().is_some_and(|(name, engine)| name == fast_model && engine.is_loaded());
+        if loaded {
+            return Ok(());
+        }
+        
+        let mut engine: Box<dyn SttEngine> = Box::new(self.whisper_engine(fast_model).await?);
+        let engine = tokio::task::spawn_blocking(move || -> Result<Box<dyn SttEngine>> {
+            engine.load()?;
+            Ok(engine)
+        }).await??;
+        
+        info!("Loaded {} for live captions", fast_model);
+        if let Some((_, mut previous)) = self.caption_engine.write().replace((fast_model.to_string(), engine)) {
+            previous.unload();
+        }
+        Ok(())
+    }
+    
+    // Transcribe a segment so far for a live caption, with the fast model when it is loaded
+    async fn process_caption_buffer(&self, window: Vec<f32>) -> Result<Vec<TranscriptSegment>> {
+        let speech_config = self.config_manager.lock().get_config().audio.speech.clone();
+        
+        let fast_model = caption_model(&speech_config);
+        let loaded = self.caption_engine.read().as_ref().is_some_and(|(name, engine)| Some(name.as_str()) == fast_model && engine.is_loaded());
+        if !loaded {
+            return self.process_audio_buffer(window).await.map(|(segments, _)| segments);
+        }
         
-        // Find the URL for the specified model
-        let model_url = MODEL_URLS
+        let engine = Arc::clone(&self.caption_engine);
+        let options = TranscribeOptions::from_settings(&speech_config);
+        let (segments, window) = tokio::task::spawn_blocking(move || match engine.read().as_ref() {
+            Some((_, engine)) => engine.transcribe(&window, &options).map(|segments| (segments, window)),
+            None => Err(anyhow::anyhow!("No live caption model loaded")),
+        }).await??;
+        
+        Ok(hallucination::filter_segments(segments, &window, &speech_config.hallucinations).0)
+    }
+    
+    // Whisper engine for a model by name (size plus optional quantization, e.g. "medium-q5_0")
+    async fn whisper_engine(&self, model_name: &str) -> Result<WhisperEngine> {
+        // Get model path from config or use default path
+        let model_path = (self.get_model_path)(model_name);
+        
+        info!("Loading Whisper model: {} from {:?}", model_name, model_path);
+        
+        // Custom models bypass the downloader, so a missing file is an error
+        if model_name.starts_with(CUSTOM_MODEL_PREFIX) {
+            engine::check_model_file(&model_path)?;
+            return Ok(WhisperEngine::new(model_path));
+        }
+        
+        // Check if model exists, if not, try to download it
+        if !model_path.exists() {
+            info!("Model file not found, attempting to download it");
+            self.download_model(model_name, &model_path).await?;
+        }
+        
+        Ok(WhisperEngine::new(model_path))
+    }
+    
+    // Vosk engine for a model by name (e.g. "small-en-us"), downloading the model if needed
+    #[cfg(feature = "vosk")]
+    async fn vosk_engine(&self, model_name: &str) -> Result<VoskEngine> {
+        let dir_name = vosk::model_dir_name(model_name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown Vosk model: {}", model_name))?;
+        let mut model_dir = self.models_dir().join(dir_name);
+        
+        if !model_dir.is_dir() {
+            info!("Vosk model not found, attempting to download it");
+            model_dir = self.download_vosk_model(model_name).await?;
+        }
+        
+        Ok(VoskEngine::new(model_dir))
+    }
+    
+    // Use an already loaded speech-to-text engine in place of the current one
+    pub fn set_engine(&self, engine: Box<dyn SttEngine>) {
+        // The settings it was built from are unknown until load_engine records them
+        *self.engine_settings.lock() = None;
+        
+        if let Some(mut previous) = self.engine.write().replace(engine) {
+            info!("Unloading the {} speech-to-text engine", previous.name());
+            previous.unload();
+        }
+    }
+    
+    // Get model path based on model name
+    fn get_model_path(&self, model_name: &str) -> PathBuf {
+        (self.get_model_path)(model_name)
+    }
+    
+    // Directory models are downloaded to
+    fn models_dir(&self) -> PathBuf {
+        self.get_model_path("tiny")
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+    
+    // Manager for the directory models are downloaded to
+    fn model_manager(&self) -> ModelManager {
+        ModelManager::new(self.models_dir())
+    }
+    
+    // Download the model (using method that mirrors Tauri 2.0's model)
+    //
+    // Interrupted downloads are resumed from the `.tmp` file, and the result is
+    // checked against the SHA-256 pinned in `MODEL_URLS` before it is used.
+    // Configured mirrors are tried before Hugging Face, and nothing is
+    // downloaded in offline mode.
+    async fn download_model(&self, model_name: &str, model_path: &Path) -> Result<()> {
+        // Find the URL and the pinned hash for the specified model
+        let (model_url, sha256) = MODEL_URLS
             .iter()
-            .find(|(size, _)| *size == model_name)
-            .map(|(_, url)| *url)
+            .find(|(size, _, _)| *size == model_name)
+            .map(|(_, url, sha256)| (*url, *sha256))
             .ok_or_else(|| anyhow::anyhow!("Model URL not found for size: {}", model_name))?;
+        let sha256 = sha256
+            .ok_or_else(|| anyhow::anyhow!("No SHA-256 pinned for model {}, so it can't be verified", model_name))?;
         
-        info!("Downloading Whisper model from: {}", model_url);
+        let checksum = self.download_file(model_name, model_url, Some(sha256), model_path).await?;
+        
+        // Remember the checksum so the model can be verified later
+        if let Err(e) = self.model_manager().record_checksum(model_name, &checksum) {
+            warn!("Failed to record checksum for model {}: {}", model_name, e);
+        }
+        
+        info!("Model download completed: {}", model_path.display());
+        Ok(())
+    }
+    
+    // Download a Vosk model archive and unpack it into the models directory
+    async fn download_vosk_model(&self, model_name: &str) -> Result<PathBuf> {
+        let (model_url, dir_name) = vosk::model_url(model_name)
+            .zip(vosk::model_dir_name(model_name))
+            .ok_or_else(|| anyhow::anyhow!("Unknown Vosk model: {}", model_name))?;
+        
+        let models_dir = self.models_dir();
+        let model_dir = models_dir.join(dir_name);
+        let archive_path = models_dir.join(format!("{}.zip", dir_name));
+        
+        if self.offline() {
+            return Err(anyhow::anyhow!(
+                "Vosk model {} is not installed and offline mode is enabled; unpack {} into {} or disable offline mode",
+                model_name,
+                model_url,
+                models_dir.display()
+            ));
+        }
+        // Vosk publishes no hashes; the archive is only checked by unpacking it
+        self.download_file(model_name, model_url, None, &archive_path).await?;
+        
+        // Unpack off the async runtime; entries that would leave the models directory are rejected
+        let archive = archive_path.clone();
+        let unpacked = tokio::task::spawn_blocking(move || -> Result<()> {
+            let file = fs::File::open(&archive)?;
+            zip::ZipArchive::new(file)?.extract(&models_dir)?;
+            Ok(())
+        }).await?;
+        let _ = tokio::fs::remove_file(&archive_path).await;
+        unpacked?;
+        
+        if !model_dir.is_dir() {
+            return Err(anyhow::anyhow!("Vosk archive for {} did not contain {}", model_name, dir_name));
+        }
+        
+        info!("Vosk model unpacked: {}", model_dir.display());
+        Ok(model_dir)
+    }
+    
+    // Whether downloads are disabled
+    fn offline(&self) -> bool {
+        self.config_manager.lock().get_config().audio.speech.download.offline
+    }
+    
+    // Download a file from the configured mirrors or `upstream_url`, returning its SHA-256,
+    // which must be `expected_sha256` when one is given
+    async fn download_file(&self, model_name: &str, upstream_url: &str, expected_sha256: Option<&str>, path: &Path) -> Result<String> {
+        let settings = self.config_manager.lock().get_config().audio.speech.download.clone();
+        if settings.offline {
+            return Err(anyhow::anyhow!(
+                "Model {} is not installed and offline mode is enabled; copy it to {} or disable offline mode",
+                model_name,
+                path.display()
+            ));
+        }
+        
+        let urls = download_urls(upstream_url, &settings.mirrors);
+        
+        info!("Downloading model {} from: {}", model_name, urls.join(", "));
         
         // Update download progress state to indicate we're starting
         {
@@ -230,32 +1115,150 @@ impl TranscribeState {
             *progress = Some((model_name.to_string(), 0.0));
         }
         
-        // Create a client for downloading
-        let client = reqwest::Client::new();
+        let result = self.download_with_retries(model_name, &urls, &settings, expected_sha256, path).await;
+        
+        // Reset progress
+        {
+            let mut p = self.download_progress.lock();
+            *p = None;
+        }
         
-        // Start a streaming download
-        let response = client.get(model_url).send().await?;
-        let total_size = response.content_length().unwrap_or(0);
+        result
+    }
+    
+    // Download into the temp file, retrying with backoff, then verify and move it into place
+    //
+    // Each retry moves on to the next URL, so an unreachable mirror falls back
+    // to the next one (and finally to Hugging Face). The URL the temp file came
+    // from is kept next to it, and only a download from the same URL is
+    // resumed, so the file never mixes bytes from two hosts.
+    async fn download_with_retries(
+        &self,
+        model_name: &str,
+        urls: &[String],
+        settings: &ModelDownloadSettings,
+        expected_sha256: Option<&str>,
+        model_path: &Path,
+    ) -> Result<String> {
+        let client = download_client(settings)?;
+        let temp_path = model_path.with_extension("tmp");
+        let source_path = model_path.with_extension("tmp.source");
         
+        let mut attempt = 1;
+        loop {
+            let model_url = &urls[(attempt as usize - 1) % urls.len()];
+            
+            // Start over when the temp file came from another URL
+            let source = tokio::fs::read_to_string(&source_path).await.unwrap_or_default();
+            if source != *model_url {
+                let _ = tokio::fs::remove_file(&temp_path).await;
+                tokio::fs::write(&source_path, model_url).await?;
+            }
+            
+            match self.download_attempt(&client, model_name, model_url, &temp_path, attempt).await {
+                Ok(()) => break,
+                Err(e) if attempt < DOWNLOAD_ATTEMPTS => {
+                    warn!(
+                        "Download of model {} from {} interrupted (attempt {}/{}): {}",
+                        model_name, model_url, attempt, DOWNLOAD_ATTEMPTS, e
+                    );
+                    
+                    // Let the frontend show that the download is being retried
+                    let progress = self.download_progress.lock().as_ref().map(|(_, p)| *p).unwrap_or(0.0);
+                    if let Some(handle) = &self.app_handle {
+                        let _ = handle.emit_all(
+                            "transcribe:download-progress",
+                            json!({
+                                "model": model_name,
+                                "progress": progress,
+                                "attempt": attempt,
+                                "retrying": true,
+                                "error": e.to_string()
+                            })
+                        );
+                    }
+                    
+                    tokio::time::sleep(std::time::Duration::from_secs(2u64.pow(attempt))).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        
+        // Hash the finished file off the async runtime
+        let hash_path = temp_path.clone();
+        let checksum = tokio::task::spawn_blocking(move || sha256_file(&hash_path)).await??;
+        
+        if let Some(expected) = expected_sha256 {
+            if !expected.eq_ignore_ascii_case(&checksum) {
+                // A bad resume can't be repaired, so the next download starts from scratch
+                let _ = tokio::fs::remove_file(&temp_path).await;
+                return Err(anyhow::anyhow!(
+                    "Checksum mismatch for model {}: expected {}, got {}", model_name, expected, checksum
+                ));
+            }
+            info!("Verified SHA-256 of model {}", model_name);
+        }
+        
+        // Rename the temporary file to the final file
+        tokio::fs::rename(&temp_path, model_path).await?;
+        let _ = tokio::fs::remove_file(&source_path).await;
+        
+        Ok(checksum)
+    }
+    
+    // Download the rest of a model into the temp file, resuming from what is already there
+    async fn download_attempt(
+        &self,
+        client: &reqwest::Client,
+        model_name: &str,
+        model_url: &str,
+        temp_path: &Path,
+        attempt: u32,
+    ) -> Result<()> {
+        let resume_from = tokio::fs::metadata(temp_path).await.map(|m| m.len()).unwrap_or(0);
+        
+        let mut request = client.get(model_url);
+        if resume_from > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", resume_from));
+        }
+        let response = request.send().await?;
+        
+        // The temp file is already as long as the model (or longer), so start over
+        if response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+            tokio::fs::remove_file(temp_path).await?;
+            return Err(anyhow::anyhow!("Server rejected resume from {} bytes", resume_from));
+        }
+        let response = response.error_for_status()?;
+        
+        // Servers that ignore the range send the whole file again
+        let (mut file, mut downloaded) = if response.status() == reqwest::StatusCode::PARTIAL_CONTENT {
+            info!("Resuming download of model {} from {} bytes", model_name, resume_from);
+            let file = tokio::fs::OpenOptions::new().append(true).open(temp_path).await?;
+            (file, resume_from)
+        } else {
+            (tokio::fs::File::create(temp_path).await?, 0)
+        };
+        
+        let total_size = downloaded + response.content_length().unwrap_or(0);
         if total_size == 0 {
             return Err(anyhow::anyhow!("Could not determine file size"));
         }
         
-        // Create a temp file and download to it
-        let temp_path = model_path.with_extension("tmp");
-        let mut file = tokio::fs::File::create(&temp_path).await?;
         let mut stream = response.bytes_stream();
-        
         let progress = Arc::clone(&self.download_progress);
         let app_handle = self.app_handle.clone();
         
-        let mut downloaded: u64 = 0;
-        let mut last_progress: f32 = 0.0;
+        let mut last_progress: f32 = downloaded as f32 / total_size as f32;
         
         while let Some(item) = stream.next().await {
             let chunk = match item {
                 Ok(chunk) => chunk,
-                Err(e) => return Err(anyhow::anyhow!("Error during download: {}", e)),
+                Err(e) => {
+                    // Keep what arrived so the next attempt can resume
+                    file.flush().await?;
+                    return Err(anyhow::anyhow!("Error during download: {}", e));
+                }
             };
             
             // Write the chunk to file
@@ -281,7 +1284,9 @@ impl TranscribeState {
                         "transcribe:download-progress", 
                         json!({
                             "model": model_name,
-                            "progress": current_progress
+                            "progress": current_progress,
+                            "attempt": attempt,
+                            "retrying": false
                         })
                     );
                 }
@@ -291,103 +1296,34 @@ impl TranscribeState {
         // Ensure the file is fully written to disk
         file.flush().await?;
         
-        // Close the file
-        drop(file);
-        
-        // Rename the temporary file to the final file
-        tokio::fs::rename(&temp_path, model_path).await?;
-        
-        // Reset progress
-        {
-            let mut p = progress.lock();
-            *p = None;
+        if downloaded < total_size {
+            return Err(anyhow::anyhow!("Connection closed after {} of {} bytes", downloaded, total_size));
         }
         
-        info!("Model download completed: {}", model_path.display());
         Ok(())
     }
     
-    // Process audio buffer using Whisper
-    async fn process_audio_buffer(&self, audio_buffer: Vec<f32>) -> Result<String> {
-        // Get the Whisper context
-        let context = {
-            let whisper_context = self.whisper_context.lock();
-            
-            if whisper_context.is_none() {
-                // Ensure model is loaded first
-                drop(whisper_context);
-                
-                let config = self.config_manager.lock().get_config().audio.speech.clone();
-                self.load_whisper_model(&config.model_size).await?;
-                
-                // Now get the context again
-                let whisper_context = self.whisper_context.lock();
-                whisper_context.as_ref().ok_or_else(|| anyhow::anyhow!("Failed to load Whisper model"))?
-            } else {
-                whisper_context.as_ref().ok_or_else(|| anyhow::anyhow!("Whisper context not available"))?
-            }
-        };
-        
-        // Get config
+    // Process audio buffer with the speech-to-text engine, loading the configured engine if needed;
+    // returns the segments kept and why any others were dropped as hallucinations
+    async fn process_audio_buffer(&self, audio_buffer: Vec<f32>) -> Result<(Vec<TranscriptSegment>, Vec<Hallucination>)> {
         let speech_config = self.config_manager.lock().get_config().audio.speech.clone();
+        *self.engine_last_used.lock() = std::time::Instant::now();
         
-        // Set up parameters for Whisper
-        let mut params = whisper_rs::FullParams::new(whisper_rs::SamplingStrategy::Greedy { best_of: 0 });
-        
-        // Set language if specified, otherwise auto-detect
-        if speech_config.language != "auto" {
-            params.set_language(Some(&speech_config.language));
+        let loaded = self.engine.read().as_ref().is_some_and(|engine| engine.is_loaded());
+        if !loaded {
+            self.ensure_engine(&speech_config).await?;
         }
         
-        // Set translation if enabled
-        if speech_config.translate_to_english {
-            params.set_translate(true);
-        }
-        
-        // Other parameters
-        params.set_print_special(false);
-        params.set_print_progress(false);
-        params.set_print_realtime(false);
-        params.set_print_timestamps(false);
+        let engine = Arc::clone(&self.engine);
+        let options = TranscribeOptions::from_settings(&speech_config);
         
-        // Process audio in a blocking task (Whisper is CPU-intensive)
-        let result = tokio::task::spawn_blocking(move || {
-            let audio_buffer = audio_buffer;
-            let context = context;
-            
-            // Run Whisper inference
-            match context.full(params, &audio_buffer) {
-                Ok(_) => {
-                    // Extract number of segments
-                    let num_segments = context.full_n_segments();
-                    
-                    // Get text from each segment
-                    let mut text = String::new();
-                    for i in 0..num_segments {
-                        if let Ok(segment) = context.full_get_segment_text(i) {
-                            text.push_str(&segment);
-                            text.push(' ');
-                        }
-                    }
-                    
-                    Ok(text)
-                },
-                Err(e) => Err(anyhow::anyhow!("Whisper inference failed: {}", e)),
-            }
+        // Process audio in a blocking task (transcription is CPU-intensive)
+        let (segments, audio_buffer) = tokio::task::spawn_blocking(move || match engine.read().as_ref() {
+            Some(engine) => engine.transcribe(&audio_buffer, &options).map(|segments| (segments, audio_buffer)),
+            None => Err(anyhow::anyhow!("No speech-to-text engine loaded")),
         }).await??;
         
-        Ok(result)
-    }
-    
-    // Get model size string from enum
-    fn get_model_size_string(&self, model_size: &WhisperModelSize) -> &'static str {
-        match model_size {
-            WhisperModelSize::Tiny => "tiny",
-            WhisperModelSize::Base => "base",
-            WhisperModelSize::Small => "small",
-            WhisperModelSize::Medium => "medium",
-            WhisperModelSize::Large => "large",
-        }
+        Ok(hallucination::filter_segments(segments, &audio_buffer, &speech_config.hallucinations))
     }
     
     // Start transcription
@@ -404,6 +1340,20 @@ impl TranscribeState {
             let mut active = self.transcription_active.lock();
             *active = true;
         }
+        *self.transcription_paused.lock() = false;
+        self.emit_state();
+        
+        // Each run of transcription is a session of its own
+        {
+            let audio = self.config_manager.lock().get_config().audio.clone();
+            *self.session.lock() = Some(Session::new(&audio.speech, audio.input_device));
+            *self.live_output.lock() = audio.speech.live_output.enabled.then(|| LiveOutput::new(audio.speech.live_output.clone()));
+            self.clipboard_output.lock().start(audio.speech.clipboard_output.clone());
+        }
+        self.save_session();
+        
+        // Pick up post-processing settings saved since the last run
+        self.rebuild_pipeline();
         
         // Start processing audio
         let audio_receiver = {
@@ -413,22 +1363,31 @@ impl TranscribeState {
         
         if let Some(mut receiver) = audio_receiver {
             let audio_buffer = Arc::clone(&self.audio_buffer);
-            let transcription_text = Arc::clone(&self.transcription_text);
+            let session = Arc::clone(&self.session);
+            let live_output = Arc::clone(&self.live_output);
+            let clipboard_output = Arc::clone(&self.clipboard_output);
+            let pipeline = Arc::clone(&self.pipeline);
             let transcription_active = Arc::clone(&self.transcription_active);
+            let transcription_paused = Arc::clone(&self.transcription_paused);
+            let command_handler = Arc::clone(&self.command_handler);
             let config_manager = Arc::clone(&self.config_manager);
-            let whisper_context = Arc::clone(&self.whisper_context);
+            let noise_suppression = Arc::clone(&self.noise_suppression);
             let self_clone = self.clone();
             let app_handle = self.app_handle.clone();
             
             // Spawn a task to process audio data
             tokio::spawn(async move {
                 let mut buffer_timer = tokio::time::interval(std::time::Duration::from_secs(1));
+                let mut denoiser = NoiseSuppressor::new();
+                let mut resamplers: HashMap<Option<String>, StreamResampler> = HashMap::new();
+                let mut track_buffers: HashMap<String, Vec<f32>> = HashMap::new();
+                let resampler_settings = config_manager.lock().get_config().audio.resampler.clone();
                 
-                // Load model eagerly
+                // Load model eagerly, unless it was preloaded
                 {
                     let config = config_manager.lock().get_config().audio.speech.clone();
-                    if let Err(e) = self_clone.load_whisper_model(&config.model_size).await {
-                        error!("Failed to load Whisper model: {}", e);
+                    if let Err(e) = self_clone.ensure_engine(&config).await {
+                        error!("Failed to load speech-to-text engine: {}", e);
                         
                         // Update active flag
                         let mut active = transcription_active.lock();
@@ -439,20 +1398,113 @@ impl TranscribeState {
                             let _ = handle.emit_all(
                                 "transcribe:error",
                                 json!({
-                                    "error": format!("Failed to load Whisper model: {}", e)
+                                    "error": format!("Failed to load speech-to-text engine: {}", e)
                                 })
                             );
                         }
                         
                         return;
                     }
+                    
+                    // Without the fast model, captions come from the configured model
+                    if let Err(e) = self_clone.ensure_caption_engine(&config).await {
+                        warn!("Failed to load the live caption model: {}", e);
+                    }
                 }
                 
                 // Custom buffer handling
                 let mut last_processed = std::time::Instant::now();
-                let segment_duration = {
+                let (segment_duration, segmentation, silence, partials, segment_overlap) = {
                     let config = config_manager.lock().get_config().audio.speech.clone();
-                    std::time::Duration::from_secs_f32(config.segment_duration)
+                    (std::time::Duration::from_secs_f32(config.segment_duration), config.segmentation, config.silence, config.partials, config.overlap)
+                };
+                let trimmer = SilenceTrimmer::new(WHISPER_SAMPLE_RATE as u32, &silence);
+                let confidence_settings = config_manager.lock().get_config().audio.speech.confidence.clone();
+                
+                // Long final segments are shown as captions split at sentence boundaries
+                let subtitle_settings = config_manager.lock().get_config().audio.speech.subtitles.clone();
+                
+                // Finished utterances are also sent to a language model when refinement is on;
+                // its version arrives as a separate event, next to the raw text
+                let refiner = {
+                    let refine = config_manager.lock().get_config().audio.speech.refine.clone();
+                    match refine.enabled.then(|| Refiner::new(refine)) {
+                        Some(Ok(refiner)) => Some(refiner),
+                        Some(Err(e)) => {
+                            warn!("Refinement disabled: {}", e);
+                            None
+                        }
+                        None => None,
+                    }
+                };
+                
+                // Final segments are post-processed; live captions are shown as the engine wrote them,
+                // except that profanity is filtered from them too
+                let profanity = ProfanityFilter::new(&config_manager.lock().get_config().audio.speech.profanity);
+                
+                // Each stream's transcript is formatted as it grows, timed from when capture started
+                let formatting = {
+                    let config = config_manager.lock().get_config().audio.speech.clone();
+                    config.context_formatting.then_some(config.formatting)
+                };
+                let mut formatters: HashMap<Option<String>, TranscriptFormatter> = HashMap::new();
+                let session_start = std::time::Instant::now();
+                
+                // Without per-speaker tracks, speaker changes are guessed from pauses and shifts in the voice
+                let speaker_change = config_manager.lock().get_config().audio.speech.speaker_change.clone();
+                let mut speaker_changes = SpeakerChangeDetector::new(&speaker_change);
+                
+                // The wake word is listened for in the microphone's audio, arming voice commands as soon as it is said
+                let mut wake_word = {
+                    let voice_commands = config_manager.lock().get_config().audio.voice_commands.clone();
+                    if voice_commands.enabled && voice_commands.wake_word.enabled {
+                        match WakeWordModel::default_path().and_then(|path| WakeWordModel::load(&path)) {
+                            Ok(model) => Some(WakeWordDetector::new(model, &voice_commands.wake_word)),
+                            Err(e) => {
+                                warn!("Wake word disabled, it isn't enrolled: {}", e);
+                                None
+                            }
+                        }
+                    } else {
+                        None
+                    }
+                };
+                
+                // With adaptive segmentation each stream is processed when its speaker pauses, not on a timer
+                let mut segmenters: HashMap<Option<String>, SpeechSegmenter> = HashMap::new();
+                let mut complete: HashSet<Option<String>> = HashSet::new();
+                
+                // The end of each segment is transcribed again with the next, and repeated words dropped
+                let overlap_samples = if segment_overlap.enabled {
+                    (segment_overlap.duration_secs * WHISPER_SAMPLE_RATE as f32) as usize
+                } else {
+                    0
+                };
+                let mut track_overlaps: HashMap<String, Vec<f32>> = HashMap::new();
+                let mut previous_texts: HashMap<Option<String>, String> = HashMap::new();
+                
+                // Live captions: each stream's segment so far is re-transcribed and stabilized
+                let partial_interval = std::time::Duration::from_millis(partials.interval_ms as u64);
+                let mut last_partial = std::time::Instant::now();
+                let mut stabilizers: HashMap<Option<String>, PartialStabilizer> = HashMap::new();
+                let mut captions: HashMap<Option<String>, SlidingCaption> = HashMap::new();
+                let mut main_segment_len = 0usize;
+                
+                // A fast caption model only hears the end of a long segment
+                let caption_window = {
+                    let config = config_manager.lock().get_config().audio.speech.clone();
+                    caption_model(&config).map(|_| (partials.window_secs * WHISPER_SAMPLE_RATE as f32) as usize)
+                };
+                
+                // Captions and the final text of a segment share an ID, so the final text can replace them
+                let mut segment_ids: HashMap<Option<String>, u64> = HashMap::new();
+                let mut next_segment_id = 0u64;
+                
+                // The main buffer has to hold the longest segment and the audio shared with the previous one
+                let main_buffer_limit = if segmentation.adaptive {
+                    AUDIO_BUFFER_SIZE.max((segmentation.max_duration_secs * WHISPER_SAMPLE_RATE as f32) as usize + overlap_samples)
+                } else {
+                    AUDIO_BUFFER_SIZE
                 };
                 
                 while let Some(audio_data) = receiver.recv().await {
@@ -460,102 +1512,510 @@ impl TranscribeState {
                         break;
                     }
                     
+                    if let Some(output) = live_output.lock().as_mut() {
+                        if let Err(e) = output.flush_if_due() {
+                            warn!("{}", e);
+                        }
+                    }
+                    
+                    // Audio delivered as per-device tracks is transcribed per source, and labelled with its speaker
+                    let track = audio_data.source_id().map(|id| id.to_string());
+                    let speaker = track.as_deref().map(Speaker::for_source);
+                    
+                    // Denoise before buffering unless bypassed from the frontend (remote audio is left alone)
+                    denoiser.set_bypass(!*noise_suppression.lock() || speaker == Some(Speaker::Others));
+                    let audio_data = denoiser.process(&audio_data);
+                    
+                    // Resample to Whisper's 16 kHz mono, keeping filter state across chunks
+                    if resamplers.get(&track).map(|r| r.from_rate() != audio_data.sample_rate()).unwrap_or(true) {
+                        match StreamResampler::new(
+                            audio_data.sample_rate(),
+                            WHISPER_SAMPLE_RATE as u32,
+                            &resampler_settings.quality,
+                            resampler_settings.chunk_ms,
+                        ) {
+                            Ok(r) => {
+                                resamplers.insert(track.clone(), r);
+                            },
+                            Err(e) => {
+                                error!("Failed to create resampler: {}", e);
+                                continue;
+                            }
+                        }
+                    }
+                    
+                    let samples = match resamplers.get_mut(&track).map(|r| r.process(&audio_data.to_mono())) {
+                        Some(Ok(samples)) => samples,
+                        Some(Err(e)) => {
+                            error!("Failed to resample audio: {}", e);
+                            continue;
+                        },
+                        None => continue,
+                    };
+                    
+                    if let Some(detector) = wake_word.as_mut().filter(|_| speaker != Some(Speaker::Others)) {
+                        if detector.process(&samples) {
+                            info!("Wake word \"{}\" heard", detector.phrase());
+                            if let Some(handle) = &app_handle {
+                                handle.state::<Arc<Mutex<VoiceCommandState>>>().lock().arm_command_mode();
+                                let _ = handle.emit_all("transcription:wake-word", json!({ "phrase": detector.phrase() }));
+                            }
+                        }
+                    }
+                    
                     // Add to buffer
-                    {
+                    if let Some(source_id) = &track {
+                        track_buffers.entry(source_id.clone()).or_default().extend(samples.iter());
+                    } else {
                         let mut buffer = audio_buffer.lock();
-                        buffer.extend(audio_data.data.iter());
+                        buffer.extend(samples.iter());
+                        main_segment_len += samples.len();
                         
                         // Resize if buffer is too large
-                        if buffer.len() > AUDIO_BUFFER_SIZE {
-                            buffer.drain(0..(buffer.len() - AUDIO_BUFFER_SIZE));
+                        if buffer.len() > main_buffer_limit {
+                            buffer.drain(0..(buffer.len() - main_buffer_limit));
+                        }
+                    }
+                    
+                    if segmentation.adaptive {
+                        let segmenter = segmenters.entry(track.clone())
+                            .or_insert_with(|| SpeechSegmenter::new(WHISPER_SAMPLE_RATE as u32, &segmentation));
+                        if segmenter.push(&samples) {
+                            complete.insert(track.clone());
                         }
                     }
                     
-                    // Check if it's time to process the buffer
                     let now = std::time::Instant::now();
-                    if now.duration_since(last_processed) >= segment_duration {
-                        // Process the buffer
-                        let buffer_copy = {
+                    let segment_due = if segmentation.adaptive {
+                        !complete.is_empty()
+                    } else {
+                        now.duration_since(last_processed) >= segment_duration
+                    };
+                    
+                    // Send partial results for the segment so far, unless the segment is about to be processed
+                    if partials.enabled && !segment_due && now.duration_since(last_partial) >= partial_interval {
+                        let mut windows: Vec<(Option<String>, Vec<f32>)> = track_buffers.iter()
+                            .map(|(source_id, buffer)| (Some(source_id.clone()), buffer.clone()))
+                            .collect();
+                        {
+                            // The main buffer is a rolling window, so only take what arrived this segment
                             let buffer = audio_buffer.lock();
-                            buffer.clone()
-                        };
+                            let start = buffer.len().saturating_sub(main_segment_len);
+                            windows.push((None, buffer[start..].to_vec()));
+                        }
+                        
+                        for (track, window) in windows {
+                            if window.is_empty() || (silence.enabled && matches!(trimmer.trim(&window), Trimmed::Silence { .. })) {
+                                continue;
+                            }
+                            
+                            // Once the segment outgrows the caption window, only its end is transcribed
+                            let sliding = caption_window.is_some_and(|len| window.len() > len);
+                            let window = match caption_window {
+                                Some(len) if sliding => window[window.len() - len..].to_vec(),
+                                _ => window,
+                            };
+                            
+                            // A failed partial only delays the caption; errors are reported for full segments
+                            let text = match self_clone.process_caption_buffer(window).await {
+                                Ok(segments) => engine::segments_text(&segments),
+                                Err(e) => {
+                                    debug!("Partial transcription failed: {}", e);
+                                    continue;
+                                }
+                            };
+                            
+                            let text = captions.entry(track.clone()).or_default().update(&text, sliding).to_string();
+                            let hypothesis = stabilizers.entry(track.clone()).or_default().update(&text);
+                            if hypothesis.text().is_empty() {
+                                continue;
+                            }
+                            
+                            let segment_id = *segment_ids.entry(track.clone()).or_insert_with(|| {
+                                next_segment_id += 1;
+                                next_segment_id
+                            });
+                            
+                            if let Some(handle) = &app_handle {
+                                let _ = handle.emit_all(
+                                    "transcription:partial",
+                                    json!({
+                                        "segment_id": segment_id,
+                                        "speaker": track.as_deref().map(|id| Speaker::for_source(id).to_string()),
+                                        "stable": profanity.apply(&hypothesis.stable),
+                                        "volatile": profanity.apply(&hypothesis.volatile)
+                                    })
+                                );
+                            }
+                        }
+                        
+                        last_partial = std::time::Instant::now();
+                    }
+                    
+                    // Check if it's time to process the buffer
+                    if segment_due {
+                        // Process the buffer, and each source's track since the last segment, with the end of the previous one
+                        let due_tracks: Vec<String> = track_buffers.keys()
+                            .filter(|source_id| !segmentation.adaptive || complete.contains(&Some(source_id.to_string())))
+                            .cloned()
+                            .collect();
+                        let mut pending: Vec<(Option<String>, Vec<f32>)> = due_tracks.into_iter()
+                            .filter_map(|source_id| track_buffers.remove(&source_id).map(|buffer| (source_id, buffer)))
+                            .map(|(source_id, buffer)| {
+                                let mut segment = track_overlaps.remove(&source_id).unwrap_or_default();
+                                segment.extend(buffer);
+                                
+                                let carried = overlap_samples.min(segment.len());
+                                track_overlaps.insert(source_id.clone(), segment[segment.len() - carried..].to_vec());
+                                (Some(source_id), segment)
+                            })
+                            .collect();
+                        if main_segment_len > 0 && (!segmentation.adaptive || complete.contains(&None)) {
+                            let buffer = audio_buffer.lock();
+                            let start = buffer.len().saturating_sub(main_segment_len + overlap_samples);
+                            pending.push((None, buffer[start..].to_vec()));
+                            main_segment_len = 0;
+                        }
+                        complete.clear();
+                        
+                        // The full transcriptions replace these segments' partial results
+                        let mut final_ids: HashMap<Option<String>, u64> = HashMap::new();
+                        for (track, _) in &pending {
+                            stabilizers.remove(track);
+                            captions.remove(track);
+                            let segment_id = segment_ids.remove(track).unwrap_or_else(|| {
+                                next_segment_id += 1;
+                                next_segment_id
+                            });
+                            final_ids.insert(track.clone(), segment_id);
+                        }
+                        pending.retain(|(_, buffer)| !buffer.is_empty());
                         
                         // Skip if buffer is empty
-                        if buffer_copy.is_empty() {
+                        if pending.is_empty() {
                             continue;
                         }
                         
-                        // Process the buffer
-                        match self_clone.process_audio_buffer(buffer_copy).await {
-                            Ok(text) => {
-                                if !text.trim().is_empty() {
-                                    // Update transcription text
-                                    {
-                                        let mut t = transcription_text.lock();
-                                        // Add the new text with a space
-                                        if !t.is_empty() && !t.ends_with(' ') {
-                                            *t += " ";
+                        // Each segment gets a final result, empty when nothing was said, which replaces its captions
+                        let emit_final = |segment_id: u64, speaker: Option<Speaker>, text: &str| {
+                            if let Some(handle) = &app_handle {
+                                let _ = handle.emit_all(
+                                    "transcription:final",
+                                    json!({
+                                        "segment_id": segment_id,
+                                        "speaker": speaker.map(|s| s.to_string()),
+                                        "text": text
+                                    })
+                                );
+                            }
+                        };
+                        
+                        let taken = session_start.elapsed();
+                        let segment_count = pending.len();
+                        for (index, (track, buffer_copy)) in pending.into_iter().enumerate() {
+                            let segment_id = final_ids.get(&track).copied().unwrap_or_default();
+                            let speaker = track.as_deref().map(Speaker::for_source);
+                            
+                            // The segment ends about when it was taken from the buffer
+                            let mut buffer_start = taken.saturating_sub(std::time::Duration::from_secs_f64(buffer_copy.len() as f64 / WHISPER_SAMPLE_RATE as f64));
+                            
+                            // Skip inference on silent buffers, and trim silent edges from the rest
+                            let buffer_copy = if silence.enabled {
+                                match trimmer.trim(&buffer_copy) {
+                                    Trimmed::Speech { start, samples } => {
+                                        buffer_start += std::time::Duration::from_secs_f64(start as f64 / WHISPER_SAMPLE_RATE as f64);
+                                        samples.to_vec()
+                                    }
+                                    Trimmed::Silence { level_db } => {
+                                        let duration_secs = buffer_copy.len() as f32 / WHISPER_SAMPLE_RATE as f32;
+                                        debug!("Skipping {:.1}s of silence ({:.1} dBFS)", duration_secs, level_db);
+                                        
+                                        if let Some(handle) = &app_handle {
+                                            let _ = handle.emit_all(
+                                                "transcription:skipped-silence",
+                                                json!({
+                                                    "duration_secs": duration_secs,
+                                                    "level_db": level_db
+                                                })
+                                            );
+                                        }
+                                        emit_final(segment_id, speaker, "");
+                                        continue;
+                                    }
+                                }
+                            } else {
+                                buffer_copy
+                            };
+                            
+                            // Process the buffer
+                            let voice = (speaker_change.enabled && speaker.is_none())
+                                .then(|| VoiceProfile::measure(&buffer_copy, WHISPER_SAMPLE_RATE as u32))
+                                .flatten();
+                            let audio_secs = buffer_copy.len() as f32 / WHISPER_SAMPLE_RATE as f32;
+                            let inference_start = std::time::Instant::now();
+                            let result = self_clone.process_audio_buffer(buffer_copy).await;
+                            
+                            // Report how long it took, so a model too slow for live speech shows
+                            if result.is_ok() {
+                                let metrics = SegmentMetrics::new(audio_secs, inference_start.elapsed(), now.elapsed(), segment_count - index - 1);
+                                self_clone.throughput.lock().record(metrics);
+                                
+                                if let Some(handle) = &app_handle {
+                                    let _ = handle.emit_all("transcribe:metrics", json!(metrics));
+                                }
+                            }
+                            
+                            let mut final_text = String::new();
+                            match result {
+                                Ok((segments, dropped)) => {
+                                    if !dropped.is_empty() {
+                                        debug!("Dropped {} hallucinated segment(s): {:?}", dropped.len(), dropped);
+                                        self_clone.hallucinations.lock().record(&dropped);
+                                    }
+                                    
+                                    let confidence = engine::segments_confidence(&segments);
+                                    let text = engine::segments_text(&segments);
+                                    
+                                    // Report the detected language, so a wrong guess is visible
+                                    if let (Some(language), Some(handle)) = (engine::segments_language(&segments), &app_handle) {
+                                        let _ = handle.emit_all(
+                                            "transcription:language",
+                                            json!({
+                                                "speaker": speaker.map(|s| s.to_string()),
+                                                "language": language.language,
+                                                "probability": language.probability
+                                            })
+                                        );
+                                    }
+                                    
+                                    let text = if segment_overlap.enabled && !text.trim().is_empty() {
+                                        let previous = previous_texts.insert(track.clone(), text.clone()).unwrap_or_default();
+                                        overlap::overlap_suffix(&previous, &text, segment_overlap.duration_secs)
+                                    } else {
+                                        text
+                                    };
+                                    
+                                    let heard = pipeline.read().process(&text);
+                                    
+                                    // Voice commands are carried out before the text is kept, leaving what was dictated;
+                                    // whether it was paused is read first, so the "resume" itself isn't kept
+                                    let paused = *transcription_paused.lock();
+                                    let handler = command_handler.read().clone();
+                                    let text = match handler {
+                                        Some(handler) if !heard.trim().is_empty() => handler(&heard),
+                                        _ => heard.clone(),
+                                    };
+                                    
+                                    if paused {
+                                        // Paused text isn't kept, only listened to for voice commands such as "resume"
+                                        if let Some(handle) = app_handle.as_ref().filter(|_| !heard.trim().is_empty()) {
+                                            let _ = handle.emit_all("transcription:heard", json!(heard));
+                                        }
+                                    } else if !text.trim().is_empty() {
+                                        // Format the text as part of the stream's transcript, which decides what separates it from the text so far
+                                        let start = segments.first().map_or(buffer_start, |segment| buffer_start + segment.start);
+                                        let end = segments.last().map_or(buffer_start, |segment| buffer_start + segment.end);
+                                        let caption_pieces = subtitle::split_long_segment(start, end, &text, &subtitle_settings);
+                                        let changed = (speaker_change.enabled && speaker.is_none() && speaker_changes.detect(voice, start, end))
+                                            .then(|| speaker_change.marker.clone());
+                                        let (separator, text) = match &formatting {
+                                            Some(settings) => {
+                                                let formatter = formatters.entry(track.clone()).or_insert_with(|| TranscriptFormatter::new(settings));
+                                                if let Some(marker) = changed {
+                                                    formatter.mark_speaker_change(marker);
+                                                }
+                                                let fresh = formatter.transcript().is_empty();
+                                                let fragment = formatter.push(&text, start, end);
+                                                let body = fragment.trim_start();
+                                                let separator = if fresh { " " } else { &fragment[..fragment.len() - body.len()] };
+                                                (separator.to_string(), label_segment(speaker, body.to_string()))
+                                            }
+                                            None => match changed {
+                                                Some(marker) => (marker.separator().to_string(), format!("{}{}", marker.prefix(), text)),
+                                                None => (" ".to_string(), label_segment(speaker, text)),
+                                            },
+                                        };
+                                        
+                                        // Line and paragraph breaks come first, as in the transcript
+                                        final_text = format!("{}{}", separator.trim_matches(' '), text);
+                                        
+                                        // Add the text to the session's transcript
+                                        if let Some(session) = session.lock().as_mut() {
+                                            session.push(start, end, speaker.map(|s| s.to_string()), final_text.clone(), confidence);
+                                        }
+                                        
+                                        // And to the user's live output file
+                                        if let Some(output) = live_output.lock().as_mut() {
+                                            if let Err(e) = output.append(&final_text) {
+                                                warn!("Failed to append to live output file: {}", e);
+                                            }
                                         }
-                                        *t += &text;
                                         
-                                        // Trim to MAX_TEXT_LENGTH
-                                        if t.len() > MAX_TEXT_LENGTH {
-                                            *t = t.chars().skip(t.len() - MAX_TEXT_LENGTH).collect();
+                                        // And to the clipboard, when it is copied segment by segment
+                                        if let Err(e) = clipboard_output.lock().append(&final_text) {
+                                            warn!("Failed to copy text to the clipboard: {}", e);
+                                        }
+                                        
+                                        // Emit transcription event to frontend
+                                        if let Some(handle) = &app_handle {
+                                            let _ = handle.emit_all("transcription:update", json!(final_text));
+                                            
+                                            // The segment as captions short enough to overlay, timed from when capture started
+                                            let _ = handle.emit_all(
+                                                "transcription:captions",
+                                                json!({
+                                                    "segment_id": segment_id,
+                                                    "speaker": speaker.map(|s| s.to_string()),
+                                                    "captions": caption_pieces.iter().map(|piece| json!({
+                                                        "start_ms": piece.start.as_millis() as u64,
+                                                        "end_ms": piece.end.as_millis() as u64,
+                                                        "text": piece.text
+                                                    })).collect::<Vec<_>>()
+                                                })
+                                            );
+                                            
+                                            // Flag doubtful text so the UI can highlight it for review
+                                            if let Some(confidence) = confidence.filter(|c| confidence_settings.flag_low && *c < confidence_settings.threshold) {
+                                                let _ = handle.emit_all(
+                                                    "transcription:low-confidence",
+                                                    json!({
+                                                        "text": text,
+                                                        "speaker": speaker.map(|s| s.to_string()),
+                                                        "confidence": confidence
+                                                    })
+                                                );
+                                            }
+                                            
+                                            // Refine without holding up the next segment
+                                            if let Some(refiner) = refiner.clone() {
+                                                let handle = handle.clone();
+                                                let original = text.clone();
+                                                tokio::spawn(async move {
+                                                    match refiner.cleanup(&original).await {
+                                                        Ok(refined) => {
+                                                            let _ = handle.emit_all(
+                                                                "transcription:refined",
+                                                                json!({
+                                                                    "kind": RefineKind::Cleanup.name(),
+                                                                    "segment_id": segment_id,
+                                                                    "speaker": speaker.map(|s| s.to_string()),
+                                                                    "original": original,
+                                                                    "text": refined
+                                                                })
+                                                            );
+                                                        }
+                                                        Err(e) => warn!("Failed to refine transcription: {}", e),
+                                                    }
+                                                });
+                                            }
                                         }
                                     }
+                                },
+                                Err(e) => {
+                                    error!("Transcription error: {}", e);
                                     
-                                    // Emit transcription event to frontend
+                                    // Emit error event to frontend
                                     if let Some(handle) = &app_handle {
                                         let _ = handle.emit_all(
-                                            "transcription:update",
-                                            json!(&text)
+                                            "transcribe:error",
+                                            json!({
+                                                "error": format!("Transcription error: {}", e)
+                                            })
                                         );
                                     }
                                 }
-                            },
-                            Err(e) => {
-                                error!("Transcription error: {}", e);
-                                
-                                // Emit error event to frontend
-                                if let Some(handle) = &app_handle {
-                                    let _ = handle.emit_all(
-                                        "transcribe:error",
-                                        json!({
-                                            "error": format!("Transcription error: {}", e)
-                                        })
-                                    );
-                                }
                             }
+                            
+                            emit_final(segment_id, speaker, &final_text);
                         }
                         
                         last_processed = now;
                     }
                 }
                 
+                // Segments finished after stopping are saved too
+                self_clone.save_session();
+                live_output.lock().take();
+                
                 // Update active flag when done
                 let mut active = transcription_active.lock();
                 *active = false;
             });
         }
-        
+        
+        Ok(())
+    }
+    
+    // Stop transcription
+    pub fn stop_transcription(&self) -> Result<()> {
+        *self.transcription_active.lock() = false;
+        *self.transcription_paused.lock() = false;
+        self.emit_state();
+        
+        // The idle timeout counts from the end of the session
+        *self.engine_last_used.lock() = std::time::Instant::now();
+        
+        if let Some(session) = self.session.lock().as_mut() {
+            session.end();
+        }
+        self.save_session();
+        
+        if let Some(output) = self.live_output.lock().as_mut() {
+            if let Err(e) = output.flush() {
+                warn!("{}", e);
+            }
+        }
+        
+        // The session's text is copied now, when it is copied as a whole
+        if let Err(e) = self.clipboard_output.lock().finish() {
+            warn!("Failed to copy the transcript to the clipboard: {}", e);
+        }
+        
+        Ok(())
+    }
+    
+    pub fn is_transcribing(&self) -> bool {
+        *self.transcription_active.lock()
+    }
+    
+    // Stop keeping transcribed text, still listening to it for voice commands
+    pub fn pause_transcription(&self) -> Result<()> {
+        if !self.is_transcribing() {
+            return Err(anyhow::anyhow!("Not transcribing"));
+        }
+        *self.transcription_paused.lock() = true;
+        self.emit_state();
         Ok(())
     }
     
-    // Stop transcription
-    pub fn stop_transcription(&self) -> Result<()> {
-        let mut active = self.transcription_active.lock();
-        *active = false;
-        
+    // Keep transcribed text again after a pause
+    pub fn resume_transcription(&self) -> Result<()> {
+        if !self.is_transcribing() {
+            return Err(anyhow::anyhow!("Not transcribing"));
+        }
+        *self.transcription_paused.lock() = false;
+        self.emit_state();
         Ok(())
     }
     
-    pub fn is_transcribing(&self) -> bool {
-        *self.transcription_active.lock()
+    pub fn is_paused(&self) -> bool {
+        *self.transcription_paused.lock()
+    }
+    
+    // Let the frontend know whether transcription is running and paused, whatever changed it
+    fn emit_state(&self) {
+        if let Some(handle) = &self.app_handle {
+            let state = json!({ "active": self.is_transcribing(), "paused": self.is_paused() });
+            if let Err(e) = handle.emit_all("transcription:state", state) {
+                error!("Failed to emit transcription state event: {}", e);
+            }
+        }
     }
     
     pub fn clear_transcription(&self) -> Result<()> {
-        let mut text = self.transcription_text.lock();
-        *text = String::new();
+        if let Some(session) = self.session.lock().as_mut() {
+            session.segments.clear();
+        }
         
         // Emit clear event to frontend
         if let Some(handle) = &self.app_handle {
@@ -580,14 +2040,29 @@ impl Clone for TranscribeState {
     fn clone(&self) -> Self {
         Self {
             config_manager: Arc::clone(&self.config_manager),
-            transcription_text: Arc::clone(&self.transcription_text),
+            session: Arc::clone(&self.session),
+            history: self.history.clone(),
+            live_output: Arc::clone(&self.live_output),
+            clipboard_output: Arc::clone(&self.clipboard_output),
+            pipeline: Arc::clone(&self.pipeline),
+            script_steps: self.script_steps.clone(),
             transcription_active: Arc::clone(&self.transcription_active),
+            transcription_paused: Arc::clone(&self.transcription_paused),
+            command_handler: Arc::clone(&self.command_handler),
             audio_receiver: Arc::clone(&self.audio_receiver),
             audio_sender: Arc::clone(&self.audio_sender),
-            whisper_context: Arc::clone(&self.whisper_context),
+            engine: Arc::clone(&self.engine),
+            engine_settings: Arc::clone(&self.engine_settings),
+            engine_loading: Arc::clone(&self.engine_loading),
+            engine_last_used: Arc::clone(&self.engine_last_used),
+            caption_engine: Arc::clone(&self.caption_engine),
             audio_buffer: Arc::clone(&self.audio_buffer),
             app_handle: self.app_handle.clone(),
             download_progress: Arc::clone(&self.download_progress),
+            noise_suppression: Arc::clone(&self.noise_suppression),
+            hallucinations: Arc::clone(&self.hallucinations),
+            throughput: Arc::clone(&self.throughput),
+            corrections: Arc::clone(&self.corrections),
             get_model_path: self.get_model_path.clone(),
         }
     }
@@ -640,14 +2115,11 @@ pub async fn start_transcription(
         let mut config = config_manager.get_config_mut();
         
         if let Some(model_size) = options.get("model_size").and_then(|v| v.as_str()) {
-            config.audio.speech.model_size = match model_size {
-                "tiny" => WhisperModelSize::Tiny,
-                "base" => WhisperModelSize::Base,
-                "small" => WhisperModelSize::Small,
-                "medium" => WhisperModelSize::Medium,
-                "large" => WhisperModelSize::Large,
-                _ => WhisperModelSize::Small, // Default
-            };
+            // Accepts quantized names such as "medium-q5_0"
+            if config.audio.speech.set_model_size_from_str(model_size).is_err() {
+                config.audio.speech.model_size = WhisperModelSize::Small; // Default
+                config.audio.speech.quantization = ModelQuantization::None;
+            }
         }
         
         if let Some(language) = options.get("language").and_then(|v| v.as_str()) {
@@ -693,6 +2165,21 @@ pub async fn is_transcribing(state: State<'_, Arc<TranscribeState>>) -> bool {
     state.is_transcribing()
 }
 
+#[tauri::command]
+pub async fn pause_transcription(state: State<'_, Arc<TranscribeState>>) -> Result<(), String> {
+    state.pause_transcription().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn resume_transcription(state: State<'_, Arc<TranscribeState>>) -> Result<(), String> {
+    state.resume_transcription().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn is_transcription_paused(state: State<'_, Arc<TranscribeState>>) -> bool {
+    state.is_paused()
+}
+
 #[tauri::command]
 pub async fn clear_transcription(
     state: State<'_, Arc<TranscribeState>>
@@ -700,32 +2187,262 @@ pub async fn clear_transcription(
     state.clear_transcription().map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn get_current_session(state: State<'_, Arc<TranscribeState>>) -> Option<Session> {
+    state.get_current_session()
+}
+
+#[tauri::command]
+pub async fn list_sessions(state: State<'_, Arc<TranscribeState>>) -> Result<Vec<SessionSummary>, String> {
+    state.list_sessions().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_session(id: String, state: State<'_, Arc<TranscribeState>>) -> Result<Session, String> {
+    state.get_session(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn rename_session(
+    id: String,
+    title: Option<String>,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<Session, String> {
+    state.rename_session(&id, title).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_wake_word(state: State<'_, Arc<TranscribeState>>) -> Result<Option<String>, String> {
+    Ok(state.get_wake_word())
+}
+
+#[tauri::command]
+pub async fn enroll_wake_word(
+    phrase: String,
+    recordings: Vec<PathBuf>,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<(), String> {
+    state.enroll_wake_word(&phrase, &recordings).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn edit_segment(
+    session_id: String,
+    index: usize,
+    text: String,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<Session, String> {
+    state.edit_segment(&session_id, index, &text).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_session(id: String, state: State<'_, Arc<TranscribeState>>) -> Result<(), String> {
+    state.delete_session(&id).map_err(|e| e.to_string())
+}
+
+// Export a session to a file as Markdown ("md"), JSON ("json"), Word ("docx") or plain text ("txt")
+#[tauri::command]
+pub async fn export_session(
+    id: String,
+    format: String,
+    path: String,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<(), String> {
+    let format = ExportFormat::from_name(&format).ok_or_else(|| format!("Unsupported export format: {}", format))?;
+    let session = state.get_session(&id).map_err(|e| e.to_string())?;
+    
+    export::export_session(&session, format, Path::new(&path)).map_err(|e| {
+        error!("Failed to export session {}: {}", id, e);
+        e.to_string()
+    })
+}
+
+// Summarize a session, the current one when no ID is given, with the configured language model
+#[tauri::command]
+pub async fn summarize_session(
+    id: Option<String>,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<String, String> {
+    state.summarize_session(id.as_deref()).await.map_err(|e| e.to_string())
+}
+
+// Find what was said, e.g. "invoices" within last Tuesday
+#[tauri::command]
+pub async fn search_transcripts(
+    query: String,
+    range: Option<DateRange>,
+    limit: Option<usize>,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<Vec<SearchHit>, String> {
+    state.search_transcripts(&query, &range.unwrap_or_default(), limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn import_transcripts(
+    dir: Option<String>,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<usize, String> {
+    state.import_transcripts(dir.as_deref().map(Path::new)).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_download_progress(state: State<'_, Arc<TranscribeState>>) -> Option<(String, f32)> {
     state.get_download_progress()
 }
 
+#[tauri::command]
+pub async fn get_hallucination_stats(state: State<'_, Arc<TranscribeState>>) -> HallucinationStats {
+    state.get_hallucination_stats()
+}
+
+// Load the configured model ahead of transcription, reporting progress with "transcription:preload" events;
+// reports ready straight away when the model is already loaded
+#[tauri::command]
+pub async fn preload_model(
+    app_handle: AppHandle,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<(), String> {
+    state.preload_engine(Some(app_handle)).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_processing_metrics(state: State<'_, Arc<TranscribeState>>) -> ThroughputSummary {
+    state.get_throughput()
+}
+
+#[tauri::command]
+pub async fn get_noise_suppression(state: State<'_, Arc<TranscribeState>>) -> bool {
+    state.is_noise_suppression_enabled()
+}
+
+#[tauri::command]
+pub async fn set_noise_suppression(
+    enabled: bool,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<(), String> {
+    state.set_noise_suppression(enabled).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_replacement_rules(state: State<'_, Arc<TranscribeState>>) -> Vec<ReplacementRule> {
+    state.get_replacement_rules()
+}
+
+// Replace all find/replace rules; fails without changing anything if a pattern is invalid
+#[tauri::command]
+pub async fn set_replacement_rules(
+    rules: Vec<ReplacementRule>,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<(), String> {
+    state.set_replacement_rules(rules).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn add_replacement_rule(
+    rule: ReplacementRule,
+    index: Option<usize>,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<Vec<ReplacementRule>, String> {
+    state.add_replacement_rule(rule, index).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn remove_replacement_rule(
+    index: usize,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<Vec<ReplacementRule>, String> {
+    state.remove_replacement_rule(index).map_err(|e| e.to_string())
+}
+
+// Show what rules would make of some text without saving them; uses the saved rules when none are given
+#[tauri::command]
+pub async fn preview_replacements(
+    text: String,
+    rules: Option<Vec<ReplacementRule>>,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<ReplacementPreview, String> {
+    let rules = rules.unwrap_or_else(|| state.get_replacement_rules());
+    replace::preview(&rules, &text).map_err(|e| format!("{:#}", e))
+}
+
+#[tauri::command]
+pub async fn get_replacement_dictionaries(state: State<'_, Arc<TranscribeState>>) -> Vec<ReplacementDictionary> {
+    state.get_replacement_dictionaries()
+}
+
+// Replace all dictionaries; fails without changing anything if a name is missing or taken, or a pattern is invalid
+#[tauri::command]
+pub async fn set_replacement_dictionaries(
+    dictionaries: Vec<ReplacementDictionary>,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<(), String> {
+    state.set_replacement_dictionaries(dictionaries).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_active_dictionary(state: State<'_, Arc<TranscribeState>>) -> Option<String> {
+    state.get_active_dictionary()
+}
+
+// Activate a dictionary by name, or deactivate the active one with none
+#[tauri::command]
+pub async fn set_active_dictionary(
+    name: Option<String>,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<(), String> {
+    state.set_active_dictionary(name).map_err(|e| e.to_string())
+}
+
+// Called when the foreground application changes, with its executable name or path
+#[tauri::command]
+pub async fn activate_dictionary_for_application(
+    application: String,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<Option<String>, String> {
+    state.activate_dictionary_for_application(&application).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_correction_suggestions(state: State<'_, Arc<TranscribeState>>) -> Result<Vec<CorrectionSuggestion>, String> {
+    Ok(state.get_correction_suggestions())
+}
+
+#[tauri::command]
+pub async fn accept_correction(
+    pattern: String,
+    replacement: String,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<Vec<ReplacementRule>, String> {
+    state.accept_correction(&pattern, &replacement).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn dismiss_correction(
+    pattern: String,
+    replacement: String,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<(), String> {
+    state.dismiss_correction(&pattern, &replacement);
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn download_model_command(
     model_size: String,
     state: State<'_, Arc<TranscribeState>>
 ) -> Result<(), String> {
-    // Convert string to WhisperModelSize
-    let model_size_enum = match model_size.as_str() {
-        "tiny" => WhisperModelSize::Tiny,
-        "base" => WhisperModelSize::Base,
-        "small" => WhisperModelSize::Small,
-        "medium" => WhisperModelSize::Medium,
-        "large" => WhisperModelSize::Large,
-        _ => return Err(format!("Invalid model size: {}", model_size)),
-    };
+    // Only models with a known download URL, including quantized variants
+    if !MODEL_URLS.iter().any(|(name, _, _)| *name == model_size) {
+        return Err(format!("Invalid model size: {}", model_size));
+    }
     
     // Get model path
-    let model_path = state.get_model_path(&model_size_enum);
+    let model_path = state.get_model_path(&model_size);
     
     // Start download
     tokio::spawn(async move {
-        if let Err(e) = state.download_model(&model_size_enum, &model_path).await {
+        if let Err(e) = state.download_model(&model_size, &model_path).await {
             error!("Failed to download model: {}", e);
             
             // Emit error event to frontend
@@ -755,6 +2472,47 @@ pub async fn download_model_command(
     Ok(())
 }
 
+#[tauri::command]
+pub async fn download_vosk_model_command(
+    model: String,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<(), String> {
+    if vosk::model_url(&model).is_none() {
+        return Err(format!("Invalid Vosk model: {}", model));
+    }
+    
+    let state = Arc::clone(state.inner());
+    tokio::spawn(async move {
+        match state.download_vosk_model(&model).await {
+            Ok(_) => {
+                if let Some(handle) = &state.app_handle {
+                    let _ = handle.emit_all("transcribe:download-complete", json!({ "model": model }));
+                }
+            }
+            Err(e) => {
+                error!("Failed to download Vosk model: {}", e);
+                if let Some(handle) = &state.app_handle {
+                    let _ = handle.emit_all(
+                        "transcribe:error",
+                        json!({ "error": format!("Failed to download model: {}", e) })
+                    );
+                }
+            }
+        }
+    });
+    
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn is_vosk_model_downloaded(
+    model: String,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<bool, String> {
+    let dir_name = vosk::model_dir_name(&model).ok_or_else(|| format!("Invalid Vosk model: {}", model))?;
+    Ok(state.models_dir().join(dir_name).is_dir())
+}
+
 #[tauri::command]
 pub async fn is_model_downloaded(
     model_size: String,
@@ -763,4 +2521,274 @@ pub async fn is_model_downloaded(
     // Check if model exists
     let path = state.ensure_model_exists(&model_size);
     Ok(path.is_ok())
-} 
+}
+
+#[tauri::command]
+pub async fn list_models(state: State<'_, Arc<TranscribeState>>) -> Result<Vec<InstalledModel>, String> {
+    let active = state.config_manager.lock().get_config().audio.speech.model_name();
+    state.model_manager().list(&active).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_model(
+    model_size: String,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<u64, String> {
+    // The selected model would just be downloaded again on the next start
+    let active = state.config_manager.lock().get_config().audio.speech.model_name();
+    if model_size == active {
+        return Err(format!("Model {} is in use", model_size));
+    }
+    
+    state.model_manager().delete(&model_size).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn verify_model(
+    model_size: String,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<ModelIntegrity, String> {
+    // Hashing a large model takes a while
+    let manager = state.model_manager();
+    tokio::task::spawn_blocking(move || manager.verify(&model_size))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_models_disk_usage(state: State<'_, Arc<TranscribeState>>) -> Result<u64, String> {
+    state.model_manager().disk_usage().map_err(|e| e.to_string())
+}
+
+// Benchmark each installed Whisper model, and each registered custom model, on an audio sample
+//
+// Without a path the sample bundled with the app is used. Models are loaded
+// one at a time next to the engine in use, and "transcribe:benchmark-progress"
+// is emitted as each finishes.
+#[tauri::command]
+pub async fn benchmark_models(
+    path: Option<String>,
+    reference: Option<String>,
+    app_handle: AppHandle,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<Vec<BenchmarkResult>, String> {
+    let sample_path = match path {
+        Some(path) => PathBuf::from(path),
+        None => app_handle.path_resolver()
+            .resolve_resource(BENCHMARK_SAMPLE)
+            .filter(|path| path.exists())
+            .ok_or_else(|| "No benchmark sample is bundled, choose an audio file".to_string())?,
+    };
+    
+    // Decoding is CPU-bound
+    let sample = tokio::task::spawn_blocking(move || BenchmarkSample::load(&sample_path, reference))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Failed to load benchmark sample: {}", e))?;
+    let sample = Arc::new(sample);
+    
+    let speech = state.config_manager.lock().get_config().audio.speech.clone();
+    let options = TranscribeOptions::from_settings(&speech);
+    
+    let mut models: Vec<(String, PathBuf)> = state.model_manager()
+        .list(&speech.model_name())
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|model| (model.name, model.path))
+        .collect();
+    models.extend(speech.custom_models.iter()
+        .map(|model| (format!("{}{}", CUSTOM_MODEL_PREFIX, model.name), PathBuf::from(&model.path))));
+    
+    if models.is_empty() {
+        return Err("No models are installed".to_string());
+    }
+    
+    let total = models.len();
+    let mut results = Vec::with_capacity(total);
+    for (index, (name, model_path)) in models.into_iter().enumerate() {
+        let sample = Arc::clone(&sample);
+        let options = options.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let mut engine = WhisperEngine::new(model_path);
+            benchmark::run_benchmark(&name, &mut engine, &sample, &options)
+        }).await.map_err(|e| e.to_string())?;
+        
+        let _ = app_handle.emit_all(
+            "transcribe:benchmark-progress",
+            json!({
+                "completed": index + 1,
+                "total": total,
+                "result": result
+            })
+        );
+        results.push(result);
+    }
+    
+    Ok(results)
+}
+
+#[tauri::command]
+pub async fn transcribe_file(
+    path: String,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<String, String> {
+    let (speech_settings, quality) = {
+        let config_manager = state.config_manager.lock();
+        let audio = &config_manager.get_config().audio;
+        (audio.speech.clone(), audio.resampler.quality.clone())
+    };
+    
+    // Forward progress to the frontend
+    let (progress_sender, mut progress_receiver) = mpsc::channel(32);
+    let app_handle = state.app_handle.clone();
+    let progress_path = path.clone();
+    let forward = tokio::spawn(async move {
+        while let Some(event) = progress_receiver.recv().await {
+            let handle = match &app_handle {
+                Some(handle) => handle,
+                None => continue,
+            };
+            
+            let _ = match event {
+                FileTranscriptionEvent::Decoded { duration_secs } => handle.emit_all(
+                    "transcription:file-decoded",
+                    json!({ "path": progress_path, "duration": duration_secs })
+                ),
+                FileTranscriptionEvent::Progress(progress) => handle.emit_all(
+                    "transcription:file-progress",
+                    json!({ "path": progress_path, "progress": progress })
+                ),
+                FileTranscriptionEvent::Segment(text) => handle.emit_all(
+                    "transcription:file-segment",
+                    json!({ "path": progress_path, "text": text })
+                ),
+                FileTranscriptionEvent::Completed(text) => handle.emit_all(
+                    "transcription:file-complete",
+                    json!({ "path": progress_path, "text": text })
+                ),
+            };
+        }
+    });
+    
+    let result = audio_file::transcribe_file(
+        Path::new(&path),
+        speech_settings,
+        &quality,
+        Some(progress_sender),
+    ).await;
+    let _ = forward.await;
+    
+    result.map_err(|e| {
+        error!("Failed to transcribe {}: {}", path, e);
+        e.to_string()
+    })
+}
+
+#[tauri::command]
+pub async fn list_recordings(state: State<'_, Arc<TranscribeState>>) -> Result<Vec<String>, String> {
+    let settings = state.config_manager.lock().get_config().audio.recording.clone();
+    let dir = recording::recordings_dir(&settings).map_err(|e| e.to_string())?;
+    
+    recording::list_recordings(&dir)
+        .map(|recordings| recordings.iter().map(|path| path.to_string_lossy().into_owned()).collect())
+        .map_err(|e| e.to_string())
+}
+
+// Dictations copied to the clipboard, newest first
+#[tauri::command]
+pub async fn get_clipboard_history(state: State<'_, Arc<TranscribeState>>) -> Result<Vec<ClipboardEntry>, String> {
+    Ok(state.clipboard_output.lock().history())
+}
+
+#[tauri::command]
+pub async fn copy_clipboard_entry(index: usize, state: State<'_, Arc<TranscribeState>>) -> Result<(), String> {
+    state.clipboard_output.lock().copy_entry(index).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn clear_clipboard_history(state: State<'_, Arc<TranscribeState>>) -> Result<(), String> {
+    state.clipboard_output.lock().clear_history();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_transcript_versions(path: String) -> Result<Vec<TranscriptVersion>, String> {
+    retranscribe::transcript_versions(Path::new(&path)).map_err(|e| e.to_string())
+}
+
+// Transcribe a saved recording again with another model and/or language, saving a new transcript version
+//
+// The recording is transcribed apart from live capture, which keeps running
+// with its own model. Progress is reported with "transcription:retranscribe-*" events.
+#[tauri::command]
+pub async fn retranscribe_recording(
+    path: String,
+    model: Option<String>,
+    language: Option<String>,
+    state: State<'_, Arc<TranscribeState>>
+) -> Result<TranscriptVersion, String> {
+    let (mut speech_settings, quality) = {
+        let config_manager = state.config_manager.lock();
+        let audio = &config_manager.get_config().audio;
+        (audio.speech.clone(), audio.resampler.quality.clone())
+    };
+    
+    // Only this transcription uses the other settings; the config is left alone
+    if let Some(model) = model {
+        speech_settings.set_model_size_from_str(&model).map_err(|e| e.to_string())?;
+    }
+    if let Some(language) = language {
+        speech_settings.language = language;
+    }
+    
+    // Forward progress to the frontend
+    let (progress_sender, mut progress_receiver) = mpsc::channel(32);
+    let app_handle = state.app_handle.clone();
+    let progress_path = path.clone();
+    let forward = tokio::spawn(async move {
+        while let Some(event) = progress_receiver.recv().await {
+            let handle = match &app_handle {
+                Some(handle) => handle,
+                None => continue,
+            };
+            
+            let _ = match event {
+                FileTranscriptionEvent::Progress(progress) => handle.emit_all(
+                    "transcription:retranscribe-progress",
+                    json!({ "path": progress_path, "progress": progress })
+                ),
+                FileTranscriptionEvent::Segment(text) => handle.emit_all(
+                    "transcription:retranscribe-segment",
+                    json!({ "path": progress_path, "text": text })
+                ),
+                // The saved version is reported once the transcription is done
+                FileTranscriptionEvent::Decoded { .. } | FileTranscriptionEvent::Completed(_) => Ok(()),
+            };
+        }
+    });
+    
+    let result = retranscribe::retranscribe_recording(
+        Path::new(&path),
+        speech_settings,
+        &quality,
+        Some(progress_sender),
+    ).await;
+    let _ = forward.await;
+    
+    match result {
+        Ok(version) => {
+            if let Some(handle) = &state.app_handle {
+                let _ = handle.emit_all(
+                    "transcription:retranscribe-complete",
+                    json!({ "path": path, "version": version })
+                );
+            }
+            Ok(version)
+        }
+        Err(e) => {
+            error!("Failed to transcribe {} again: {}", path, e);
+            Err(e.to_string())
+        }
+    }
+}
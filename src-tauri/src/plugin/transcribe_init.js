@@ -7,12 +7,28 @@ window.__TAURI_PLUGIN_TRANSCRIBE__ = {
     // Create event emitter for transcription events
     const listeners = {
       "update": [],
+      "partial": [],
+      "final": [],
+      "captions": [],
+      "low-confidence": [],
+      "refined": [],
+      "correction-suggested": [],
+      "correction-learned": [],
+      "language": [],
+      "wake-word": [],
       "clear": [],
+      "skipped-silence": [],
       "started": [],
       "stopped": [],
+      "state": [],
+      "heard": [],
       "error": [],
       "download-progress": [],
-      "download-complete": []
+      "download-complete": [],
+      "preload": [],
+      "model-unloaded": [],
+      "metrics": [],
+      "benchmark-progress": []
     };
     
     // Listen for events from the Rust side
@@ -21,10 +37,60 @@ window.__TAURI_PLUGIN_TRANSCRIBE__ = {
       listeners["update"].forEach(cb => cb(text));
     });
     
+    window.__TAURI__.event.listen("transcription:partial", (event) => {
+      const partial = event.payload;
+      listeners["partial"].forEach(cb => cb(partial));
+    });
+    
+    window.__TAURI__.event.listen("transcription:final", (event) => {
+      const result = event.payload;
+      listeners["final"].forEach(cb => cb(result));
+    });
+    
+    window.__TAURI__.event.listen("transcription:captions", (event) => {
+      const captions = event.payload;
+      listeners["captions"].forEach(cb => cb(captions));
+    });
+    
+    window.__TAURI__.event.listen("transcription:low-confidence", (event) => {
+      const flagged = event.payload;
+      listeners["low-confidence"].forEach(cb => cb(flagged));
+    });
+    
+    window.__TAURI__.event.listen("transcription:refined", (event) => {
+      const refined = event.payload;
+      listeners["refined"].forEach(cb => cb(refined));
+    });
+    
+    window.__TAURI__.event.listen("transcription:correction-suggested", (event) => {
+      const suggestion = event.payload;
+      listeners["correction-suggested"].forEach(cb => cb(suggestion));
+    });
+    
+    window.__TAURI__.event.listen("transcription:correction-learned", (event) => {
+      const suggestion = event.payload;
+      listeners["correction-learned"].forEach(cb => cb(suggestion));
+    });
+    
+    window.__TAURI__.event.listen("transcription:wake-word", (event) => {
+      const wakeWord = event.payload;
+      listeners["wake-word"].forEach(cb => cb(wakeWord));
+    });
+    
+    window.__TAURI__.event.listen("transcription:language", (event) => {
+      const detected = event.payload;
+      listeners["language"].forEach(cb => cb(detected));
+    });
+    
     window.__TAURI__.event.listen("transcription:clear", () => {
       listeners["clear"].forEach(cb => cb());
     });
     
+    window.__TAURI__.event.listen("transcription:skipped-silence", (event) => {
+      const skipped = event.payload;
+      listeners["skipped-silence"].forEach(cb => cb(skipped));
+    });
+    
     window.__TAURI__.event.listen("transcribe:started", () => {
       listeners["started"].forEach(cb => cb());
     });
@@ -33,6 +99,16 @@ window.__TAURI_PLUGIN_TRANSCRIBE__ = {
       listeners["stopped"].forEach(cb => cb());
     });
     
+    window.__TAURI__.event.listen("transcription:state", (event) => {
+      const state = event.payload;
+      listeners["state"].forEach(cb => cb(state));
+    });
+    
+    window.__TAURI__.event.listen("transcription:heard", (event) => {
+      const text = event.payload;
+      listeners["heard"].forEach(cb => cb(text));
+    });
+    
     window.__TAURI__.event.listen("transcribe:error", (event) => {
       const error = event.payload;
       listeners["error"].forEach(cb => cb(error));
@@ -48,6 +124,26 @@ window.__TAURI_PLUGIN_TRANSCRIBE__ = {
       listeners["download-complete"].forEach(cb => cb(model));
     });
     
+    window.__TAURI__.event.listen("transcription:preload", (event) => {
+      const status = event.payload;
+      listeners["preload"].forEach(cb => cb(status));
+    });
+    
+    window.__TAURI__.event.listen("transcription:model-unloaded", (event) => {
+      const unloaded = event.payload;
+      listeners["model-unloaded"].forEach(cb => cb(unloaded));
+    });
+    
+    window.__TAURI__.event.listen("transcribe:metrics", (event) => {
+      const metrics = event.payload;
+      listeners["metrics"].forEach(cb => cb(metrics));
+    });
+    
+    window.__TAURI__.event.listen("transcribe:benchmark-progress", (event) => {
+      const progress = event.payload;
+      listeners["benchmark-progress"].forEach(cb => cb(progress));
+    });
+    
     // Export API
     return {
       // Start transcription
@@ -70,16 +166,226 @@ window.__TAURI_PLUGIN_TRANSCRIBE__ = {
         return window.__TAURI__.invoke("plugin:transcribe:is_transcribing");
       },
       
+      // Stop keeping text while still listening for voice commands
+      async pauseTranscription() {
+        return window.__TAURI__.invoke("plugin:transcribe:pause_transcription");
+      },
+      
+      // Keep text again after a pause
+      async resumeTranscription() {
+        return window.__TAURI__.invoke("plugin:transcribe:resume_transcription");
+      },
+      
+      // Check if transcription is paused
+      async isPaused() {
+        return window.__TAURI__.invoke("plugin:transcribe:is_transcription_paused");
+      },
+      
       // Clear current transcription
       async clearTranscription() {
         return window.__TAURI__.invoke("plugin:transcribe:clear_transcription");
       },
       
+      // The running session, or the last one once stopped, as
+      // { id, title, started, ended, device, model, language, segments: [{ start_ms, end_ms, speaker, text, confidence }] }
+      async getCurrentSession() {
+        return window.__TAURI__.invoke("plugin:transcribe:get_current_session");
+      },
+      
+      // Saved sessions without their transcripts, newest first
+      async listSessions() {
+        return window.__TAURI__.invoke("plugin:transcribe:list_sessions");
+      },
+      
+      // A session with its whole transcript
+      async getSession(id) {
+        return window.__TAURI__.invoke("plugin:transcribe:get_session", { id });
+      },
+      
+      // Title a session (null removes the title)
+      async renameSession(id, title) {
+        return window.__TAURI__.invoke("plugin:transcribe:rename_session", { id, title });
+      },
+      
+      // The enrolled wake word, or null
+      async getWakeWord() {
+        return window.__TAURI__.invoke("plugin:transcribe:get_wake_word");
+      },
+      
+      // Enroll the wake word from at least three recordings (audio file paths) of it being said,
+      // replacing the one enrolled before
+      async enrollWakeWord(phrase, recordings) {
+        return window.__TAURI__.invoke("plugin:transcribe:enroll_wake_word", { phrase, recordings });
+      },
+      
+      // Change the text of a segment (by index) in a session, keeping the break before it;
+      // resolves to the session. Corrections are counted to suggest replacement rules
+      async editSegment(sessionId, index, text) {
+        return window.__TAURI__.invoke("plugin:transcribe:edit_segment", { sessionId, index, text });
+      },
+      
+      // Delete a saved session (not the one being transcribed)
+      async deleteSession(id) {
+        return window.__TAURI__.invoke("plugin:transcribe:delete_session", { id });
+      },
+      
+      // Export a session to a file; format is "md", "json", "docx" or "txt"
+      async exportSession(id, format, path) {
+        return window.__TAURI__.invoke("plugin:transcribe:export_session", { id, format, path });
+      },
+      
+      // Segments of saved sessions containing all words of the query, best matches first, as
+      // [{ session_id, session_title, time, start_ms, speaker, text }]; narrow by when they were said
+      // with { from, to } as ISO 8601 strings
+      async searchTranscripts(query, range = {}, limit) {
+        return window.__TAURI__.invoke("plugin:transcribe:search_transcripts", {
+          query,
+          range: { from: range.from || null, to: range.to || null },
+          limit
+        });
+      },
+      
+      // Add JSON transcript files, including ones written by older versions, to the history;
+      // from the transcripts folder unless another is given. Resolves to the number added
+      async importTranscripts(dir) {
+        return window.__TAURI__.invoke("plugin:transcribe:import_transcripts", { dir: dir || null });
+      },
+      
+      // Find/replace rules applied to final text, in order, as
+      // [{ pattern, replacement, regex, case_sensitive, whole_word, enabled }]
+      async getReplacementRules() {
+        return window.__TAURI__.invoke("plugin:transcribe:get_replacement_rules");
+      },
+      
+      // Replace all rules; rejects without saving if a pattern is invalid
+      async setReplacementRules(rules) {
+        return window.__TAURI__.invoke("plugin:transcribe:set_replacement_rules", { rules });
+      },
+      
+      // Insert a rule at index, or after the others; resolves to the new list
+      async addReplacementRule(rule, index) {
+        return window.__TAURI__.invoke("plugin:transcribe:add_replacement_rule", { rule, index });
+      },
+      
+      // Remove the rule at index; resolves to the new list
+      async removeReplacementRule(index) {
+        return window.__TAURI__.invoke("plugin:transcribe:remove_replacement_rule", { index });
+      },
+      
+      // What rules (the saved ones if omitted) make of text, as { text, matches } with a match count per rule
+      async previewReplacements(text, rules) {
+        return window.__TAURI__.invoke("plugin:transcribe:preview_replacements", { text, rules: rules || null });
+      },
+      
+      // Named rule sets for particular applications:
+      // [{ name, applications: ["code.exe", ...], rules: [{ pattern, replacement, ... }] }]
+      async getReplacementDictionaries() {
+        return window.__TAURI__.invoke("plugin:transcribe:get_replacement_dictionaries");
+      },
+      
+      async setReplacementDictionaries(dictionaries) {
+        return window.__TAURI__.invoke("plugin:transcribe:set_replacement_dictionaries", { dictionaries });
+      },
+      
+      // Name of the active dictionary, or null
+      async getActiveDictionary() {
+        return window.__TAURI__.invoke("plugin:transcribe:get_active_dictionary");
+      },
+      
+      // Activate a dictionary by name; null deactivates the active one
+      async setActiveDictionary(name) {
+        return window.__TAURI__.invoke("plugin:transcribe:set_active_dictionary", { name: name || null });
+      },
+      
+      // Activate the dictionary for an application (e.g. "code.exe") when it comes to the foreground;
+      // resolves to the dictionary's name, or null when the application has none
+      async activateDictionaryForApplication(application) {
+        return window.__TAURI__.invoke("plugin:transcribe:activate_dictionary_for_application", { application });
+      },
+      
+      // Corrections made often enough to become replacement rules, as [{ pattern, replacement, count }]
+      async getCorrectionSuggestions() {
+        return window.__TAURI__.invoke("plugin:transcribe:get_correction_suggestions");
+      },
+      
+      // Add a suggested correction as a replacement rule; resolves to the new list of rules
+      async acceptCorrection(pattern, replacement) {
+        return window.__TAURI__.invoke("plugin:transcribe:accept_correction", { pattern, replacement });
+      },
+      
+      // Stop suggesting a correction
+      async dismissCorrection(pattern, replacement) {
+        return window.__TAURI__.invoke("plugin:transcribe:dismiss_correction", { pattern, replacement });
+      },
+      
+      // Summarize a session (the current one if no ID is given) with the configured language model
+      async summarizeSession(id) {
+        return window.__TAURI__.invoke("plugin:transcribe:summarize_session", { id: id || null });
+      },
+      
       // Get download progress of the model
       async getDownloadProgress() {
         return window.__TAURI__.invoke("plugin:transcribe:get_download_progress");
       },
       
+      // Segments dropped as hallucinations, as { no_speech, known_phrase, silent_audio }
+      async getHallucinationStats() {
+        return window.__TAURI__.invoke("plugin:transcribe:get_hallucination_stats");
+      },
+      
+      // Load the model ahead of transcription; progress is reported to onPreload
+      async preloadModel() {
+        return window.__TAURI__.invoke("plugin:transcribe:preload_model");
+      },
+      
+      // Averages over recent segments as { segments, avg_audio_secs, avg_inference_ms,
+      // real_time_factor, avg_latency_ms, max_queue_depth, keeping_up }
+      async getProcessingMetrics() {
+        return window.__TAURI__.invoke("plugin:transcribe:get_processing_metrics");
+      },
+      
+      // Run an audio file (or the bundled sample) through each installed model; resolves to
+      // [{ model, load_ms, inference_ms, real_time_factor, word_error_rate, confidence, text, error }].
+      // The reference transcript, if given, scores word_error_rate
+      async benchmarkModels(path, reference) {
+        return window.__TAURI__.invoke("plugin:transcribe:benchmark_models", { path, reference });
+      },
+      
+      // Saved recordings, newest first
+      async listRecordings() {
+        return window.__TAURI__.invoke("plugin:transcribe:list_recordings");
+      },
+      
+      // Dictations copied to the clipboard as [{ text, copied_at }], newest first
+      async getClipboardHistory() {
+        return window.__TAURI__.invoke("plugin:transcribe:get_clipboard_history");
+      },
+      
+      // Copy a dictation from the clipboard history again, by its place in the list
+      async copyClipboardEntry(index) {
+        return window.__TAURI__.invoke("plugin:transcribe:copy_clipboard_entry", { index });
+      },
+      
+      // Forget the dictations copied to the clipboard
+      async clearClipboardHistory() {
+        return window.__TAURI__.invoke("plugin:transcribe:clear_clipboard_history");
+      },
+      
+      // Transcripts of a recording as [{ version, model, language, created, text }]
+      async getTranscriptVersions(path) {
+        return window.__TAURI__.invoke("plugin:transcribe:get_transcript_versions", { path });
+      },
+      
+      // Transcribe a recording again, e.g. with { model: "large-v3-turbo", language: "de" };
+      // resolves to the new transcript version
+      async retranscribeRecording(path, options = {}) {
+        return window.__TAURI__.invoke("plugin:transcribe:retranscribe_recording", {
+          path,
+          model: options.model,
+          language: options.language
+        });
+      },
+      
       // Download a model
       async downloadModel(modelSize) {
         return window.__TAURI__.invoke("plugin:transcribe:download_model_command", { modelSize });
@@ -90,6 +396,36 @@ window.__TAURI_PLUGIN_TRANSCRIBE__ = {
         return window.__TAURI__.invoke("plugin:transcribe:is_model_downloaded", { modelSize });
       },
       
+      // List downloaded models with their sizes
+      async listModels() {
+        return window.__TAURI__.invoke("plugin:transcribe:list_models");
+      },
+      
+      // Delete a downloaded model (not the one in use), returning the bytes freed
+      async deleteModel(modelSize) {
+        return window.__TAURI__.invoke("plugin:transcribe:delete_model", { modelSize });
+      },
+      
+      // Check a downloaded model for corruption
+      async verifyModel(modelSize) {
+        return window.__TAURI__.invoke("plugin:transcribe:verify_model", { modelSize });
+      },
+      
+      // Total size of the models directory in bytes
+      async getModelsDiskUsage() {
+        return window.__TAURI__.invoke("plugin:transcribe:get_models_disk_usage");
+      },
+      
+      // Download and unpack a Vosk model
+      async downloadVoskModel(model) {
+        return window.__TAURI__.invoke("plugin:transcribe:download_vosk_model_command", { model });
+      },
+      
+      // Check if a Vosk model is downloaded
+      async isVoskModelDownloaded(model) {
+        return window.__TAURI__.invoke("plugin:transcribe:is_vosk_model_downloaded", { model });
+      },
+      
       // Event subscriptions
       onUpdate(callback) {
         listeners["update"].push(callback);
@@ -99,6 +435,90 @@ window.__TAURI_PLUGIN_TRANSCRIBE__ = {
         };
       },
       
+      // Live captions as { segment_id, speaker, stable, volatile }; stable words won't change
+      onPartial(callback) {
+        listeners["partial"].push(callback);
+        return () => {
+          const index = listeners["partial"].indexOf(callback);
+          if (index !== -1) listeners["partial"].splice(index, 1);
+        };
+      },
+      
+      // Final text of a segment as { segment_id, speaker, text }, replacing the captions with the same
+      // segment_id; text is empty when nothing was said
+      onFinal(callback) {
+        listeners["final"].push(callback);
+        return () => {
+          const index = listeners["final"].indexOf(callback);
+          if (index !== -1) listeners["final"].splice(index, 1);
+        };
+      },
+      
+      // Final segments split at sentence boundaries for a caption overlay:
+      // { segment_id, speaker, captions: [{ start_ms, end_ms, text }] }, timed from when transcription started
+      onCaptions(callback) {
+        listeners["captions"].push(callback);
+        return () => {
+          const index = listeners["captions"].indexOf(callback);
+          if (index !== -1) listeners["captions"].splice(index, 1);
+        };
+      },
+      
+      // A correction made often enough to become a replacement rule, as { pattern, replacement, count }
+      onCorrectionSuggested(callback) {
+        listeners["correction-suggested"].push(callback);
+        return () => {
+          const index = listeners["correction-suggested"].indexOf(callback);
+          if (index !== -1) listeners["correction-suggested"].splice(index, 1);
+        };
+      },
+      
+      // A correction added as a replacement rule without asking, as { pattern, replacement, count }
+      onCorrectionLearned(callback) {
+        listeners["correction-learned"].push(callback);
+        return () => {
+          const index = listeners["correction-learned"].indexOf(callback);
+          if (index !== -1) listeners["correction-learned"].splice(index, 1);
+        };
+      },
+      
+      // Transcriptions below the confidence threshold as { text, speaker, confidence }
+      onLowConfidence(callback) {
+        listeners["low-confidence"].push(callback);
+        return () => {
+          const index = listeners["low-confidence"].indexOf(callback);
+          if (index !== -1) listeners["low-confidence"].splice(index, 1);
+        };
+      },
+      
+      // Language model versions of the transcript: { kind: "cleanup", segment_id, speaker, original, text }
+      // for each final segment when refinement is on, and { kind: "summary", session_id, text } for summaries
+      onRefined(callback) {
+        listeners["refined"].push(callback);
+        return () => {
+          const index = listeners["refined"].indexOf(callback);
+          if (index !== -1) listeners["refined"].splice(index, 1);
+        };
+      },
+      
+      // The wake word was heard in the audio, as { phrase }
+      onWakeWord(callback) {
+        listeners["wake-word"].push(callback);
+        return () => {
+          const index = listeners["wake-word"].indexOf(callback);
+          if (index !== -1) listeners["wake-word"].splice(index, 1);
+        };
+      },
+      
+      // Language detected in each segment as { speaker, language, probability }
+      onLanguageDetected(callback) {
+        listeners["language"].push(callback);
+        return () => {
+          const index = listeners["language"].indexOf(callback);
+          if (index !== -1) listeners["language"].splice(index, 1);
+        };
+      },
+      
       onClear(callback) {
         listeners["clear"].push(callback);
         return () => {
@@ -107,6 +527,14 @@ window.__TAURI_PLUGIN_TRANSCRIBE__ = {
         };
       },
       
+      onSkippedSilence(callback) {
+        listeners["skipped-silence"].push(callback);
+        return () => {
+          const index = listeners["skipped-silence"].indexOf(callback);
+          if (index !== -1) listeners["skipped-silence"].splice(index, 1);
+        };
+      },
+      
       onStarted(callback) {
         listeners["started"].push(callback);
         return () => {
@@ -145,6 +573,61 @@ window.__TAURI_PLUGIN_TRANSCRIBE__ = {
           const index = listeners["download-complete"].indexOf(callback);
           if (index !== -1) listeners["download-complete"].splice(index, 1);
         };
+      },
+      
+      // Model preloading as { stage, progress, model, elapsed_ms, error };
+      // stage is loading, warming, ready or failed
+      onPreload(callback) {
+        listeners["preload"].push(callback);
+        return () => {
+          const index = listeners["preload"].indexOf(callback);
+          if (index !== -1) listeners["preload"].splice(index, 1);
+        };
+      },
+      
+      // The model was unloaded after { idle_mins } without transcription; it loads again on the next start
+      onModelUnloaded(callback) {
+        listeners["model-unloaded"].push(callback);
+        return () => {
+          const index = listeners["model-unloaded"].indexOf(callback);
+          if (index !== -1) listeners["model-unloaded"].splice(index, 1);
+        };
+      },
+      
+      // Transcription starting, stopping, pausing or resuming, as { active, paused }
+      onStateChange(callback) {
+        listeners["state"].push(callback);
+        return () => {
+          const index = listeners["state"].indexOf(callback);
+          if (index !== -1) listeners["state"].splice(index, 1);
+        };
+      },
+      
+      // Text heard while paused, which is only checked for voice commands
+      onHeard(callback) {
+        listeners["heard"].push(callback);
+        return () => {
+          const index = listeners["heard"].indexOf(callback);
+          if (index !== -1) listeners["heard"].splice(index, 1);
+        };
+      },
+      
+      // Each segment's { audio_secs, inference_ms, real_time_factor, latency_ms, queue_depth }
+      onMetrics(callback) {
+        listeners["metrics"].push(callback);
+        return () => {
+          const index = listeners["metrics"].indexOf(callback);
+          if (index !== -1) listeners["metrics"].splice(index, 1);
+        };
+      },
+      
+      // Each benchmarked model as { completed, total, result }
+      onBenchmarkProgress(callback) {
+        listeners["benchmark-progress"].push(callback);
+        return () => {
+          const index = listeners["benchmark-progress"].indexOf(callback);
+          if (index !== -1) listeners["benchmark-progress"].splice(index, 1);
+        };
       }
     };
   }
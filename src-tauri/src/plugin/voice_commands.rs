@@ -1,7 +1,7 @@
 use anyhow::{Result, anyhow};
 use log::{info, error, debug, warn};
 use parking_lot::Mutex;
-use std::{path::PathBuf, sync::Arc, collections::HashMap};
+use std::{path::PathBuf, sync::Arc};
 use tauri::{AppHandle, Manager, State};
 use tokio::sync::mpsc;
 use serde::Serialize;
@@ -10,13 +10,12 @@ use regex;
 use chrono;
 use std::marker::PhantomData;
 
+use bestme::audio::active_window::{self, ActiveWindow, ActiveWindowWatcher};
+use bestme::audio::command_stats::{CommandStats, CommandUsage};
 use bestme::audio::voice_commands::{
-    Command,
-    CommandContext,
-    CommandEvent,
-    CommandHistory,
-    CommandResult,
-    CommandTrigger,
+    VoiceCommand as Command,
+    CommandCategory,
+    CommandMode,
     VoiceCommandConfig,
     VoiceCommandEvent,
     VoiceCommandManager as TauriVoiceCommandManager,
@@ -32,37 +31,6 @@ use crate::plugin::TranscribeState;
 /// Maximum number of commands to keep in history
 const MAX_COMMAND_HISTORY: usize = 20;
 
-/// Configuration for voice commands
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct TauriVoiceCommandConfig {
-    /// Whether voice commands are enabled
-    pub enabled: bool,
-    /// The prefix that must be spoken before commands
-    pub command_prefix: Option<String>,
-    /// Whether the prefix is required
-    pub require_prefix: bool,
-    /// Confidence threshold for command detection (0.0-1.0)
-    pub sensitivity: f32,
-    /// Custom command mappings
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub custom_commands: Vec<(String, String)>,
-    /// Whether this is the default configuration
-    pub default_commands: bool,
-}
-
-impl Default for TauriVoiceCommandConfig {
-    fn default() -> Self {
-        Self {
-            enabled: false,
-            command_prefix: Some("computer".to_string()),
-            require_prefix: true,
-            sensitivity: 0.7,
-            custom_commands: Vec::new(),
-            default_commands: true,
-        }
-    }
-}
-
 /// Data structure for a detected command
 #[derive(Debug, Clone, Serialize)]
 pub struct CommandData {
@@ -70,6 +38,8 @@ pub struct CommandData {
     pub command_type: String,
     /// The text that triggered the command
     pub trigger_text: String,
+    /// Mode voice commands were in once it was heard
+    pub mode: CommandMode,
     /// When the command was detected
     pub timestamp: String,
 }
@@ -79,6 +49,7 @@ impl From<Command> for CommandData {
         Self {
             command_type: format!("{:?}", cmd.command_type),
             trigger_text: cmd.trigger_text,
+            mode: cmd.mode,
             timestamp: chrono::Local::now().to_rfc3339(),
         }
     }
@@ -113,6 +84,9 @@ pub struct VoiceCommandState {
     /// Voice command manager
     manager: Arc<Mutex<Option<TauriVoiceCommandManager>>>,
     
+    /// Configuration the manager was last created with, used again when it is started
+    config: VoiceCommandConfig,
+    
     /// Whether the system is enabled
     is_enabled: Arc<Mutex<bool>>,
     
@@ -125,6 +99,9 @@ pub struct VoiceCommandState {
     /// Current text being edited
     current_text: Arc<Mutex<String>>,
     
+    /// Watches which application has the focus, for commands heard in some applications only
+    window_watcher: Option<ActiveWindowWatcher>,
+    
     /// App handle for Tauri 2.0
     app_handle: Option<AppHandle>,
 }
@@ -134,10 +111,12 @@ impl VoiceCommandState {
     pub fn new() -> Self {
         Self {
             manager: Arc::new(Mutex::new(None)),
+            config: Self::default_config(),
             is_enabled: Arc::new(Mutex::new(false)),
             last_command: Arc::new(Mutex::new(None)),
             command_history: Arc::new(Mutex::with_capacity(MAX_COMMAND_HISTORY)),
             current_text: Arc::new(Mutex::new(String::new())),
+            window_watcher: None,
             app_handle: None,
         }
     }
@@ -148,8 +127,15 @@ impl VoiceCommandState {
     }
     
     /// Initialize voice command manager
-    pub fn initialize(&mut self, config: TauriVoiceCommandConfig) -> Result<()> {
-        let (manager, receiver) = TauriVoiceCommandManager::new(config)?;
+    pub fn initialize(&mut self, config: VoiceCommandConfig) -> Result<()> {
+        self.config = config.clone();
+        let (mut manager, receiver) = TauriVoiceCommandManager::new(config)?;
+        
+        // Commands are counted on from where they were left
+        match CommandStats::default_path().and_then(|path| CommandStats::load(&path)) {
+            Ok(stats) => manager.set_command_stats(stats),
+            Err(e) => warn!("Failed to load voice command statistics: {}", e),
+        }
         
         // Set up event handling for voice commands
         let commands_history = Arc::clone(&self.command_history);
@@ -187,6 +173,76 @@ impl VoiceCommandState {
                             }
                         }
                     },
+                    VoiceCommandEvent::ModeChanged(mode) => {
+                        info!("Voice commands switched to {:?} mode", mode);
+                        
+                        // Emit mode event
+                        if let Some(handle) = &app_handle {
+                            if let Err(e) = handle.emit_all("voice-command:mode", mode) {
+                                error!("Failed to emit voice command mode event: {}", e);
+                            }
+                        }
+                    },
+                    VoiceCommandEvent::SleepChanged(asleep) => {
+                        // Emit sleep event, for the window and the tray icon to show it
+                        if let Some(handle) = &app_handle {
+                            if let Err(e) = handle.emit_all("voice-command:sleep", asleep) {
+                                error!("Failed to emit voice command sleep event: {}", e);
+                            }
+                        }
+                    },
+                    VoiceCommandEvent::Cancelled => {
+                        // Emit cancelled event, for the window to drop the caption of what was said
+                        if let Some(handle) = &app_handle {
+                            if let Err(e) = handle.emit_all("voice-command:cancelled", ()) {
+                                error!("Failed to emit voice command cancelled event: {}", e);
+                            }
+                        }
+                    },
+                    VoiceCommandEvent::ContextChanged(window) => {
+                        // Emit context event, for the window to show which commands are heard
+                        if let Some(handle) = &app_handle {
+                            if let Err(e) = handle.emit_all("voice-command:context", window) {
+                                error!("Failed to emit voice command context event: {}", e);
+                            }
+                        }
+                    },
+                    VoiceCommandEvent::ProfileChanged(profile) => {
+                        info!("Voice commands switched to the {} profile", profile);
+                        
+                        // Emit profile event
+                        if let Some(handle) = &app_handle {
+                            if let Err(e) = handle.emit_all("voice-command:profile", profile) {
+                                error!("Failed to emit voice command profile event: {}", e);
+                            }
+                        }
+                    },
+                    VoiceCommandEvent::ShellCommandFinished(output) => {
+                        info!("Shell command {} exited with {:?}", output.name, output.status);
+                        
+                        // Emit shell command event
+                        if let Some(handle) = &app_handle {
+                            if let Err(e) = handle.emit_all("voice-command:shell-finished", output) {
+                                error!("Failed to emit voice command shell event: {}", e);
+                            }
+                        }
+                    },
+                    VoiceCommandEvent::HelpRequested(categories) => {
+                        // Emit help event, for the commands to be shown
+                        if let Some(handle) = &app_handle {
+                            if let Err(e) = handle.emit_all("voice-command:help", categories) {
+                                error!("Failed to emit voice command help event: {}", e);
+                            }
+                        }
+                    },
+                    VoiceCommandEvent::TextUpdated(text) => {
+                        // Emit text event, so the text shown needn't be fetched again
+                        if let Some(handle) = &app_handle {
+                            if let Err(e) = handle.emit_all("voice-command:text-updated", text) {
+                                error!("Failed to emit voice command text event: {}", e);
+                            }
+                        }
+                    },
                     VoiceCommandEvent::Error(err) => {
                         error!("Voice command error: {}", err);
                         
@@ -218,13 +274,29 @@ impl VoiceCommandState {
             *mgr = Some(manager);
         }
         
+        // Tell the manager which application has the focus as it changes
+        self.window_watcher = None;
+        match ActiveWindowWatcher::start(active_window::DEFAULT_POLL_INTERVAL) {
+            Ok((watcher, mut windows)) => {
+                let manager = Arc::clone(&self.manager);
+                tokio::spawn(async move {
+                    while let Some(window) = windows.recv().await {
+                        if let Some(manager) = manager.lock().as_mut() {
+                            manager.set_active_window(window);
+                        }
+                    }
+                });
+                self.window_watcher = Some(watcher);
+            }
+            Err(e) => warn!("Failed to watch the focused window, commands are heard in every application: {}", e),
+        }
+        
         Ok(())
     }
     
     /// Start voice command processing
     pub fn start(&mut self) -> Result<()> {
-        let config = self.create_default_config();
-        self.initialize(config)?;
+        self.initialize(self.config.clone())?;
         
         {
             let mut enabled = self.is_enabled.lock();
@@ -251,6 +323,10 @@ impl VoiceCommandState {
             let mut enabled = self.is_enabled.lock();
             *enabled = false;
             
+            if let Some(mut watcher) = self.window_watcher.take() {
+                watcher.stop();
+            }
+            
             Ok(())
         } else {
             // Not an error if already stopped
@@ -258,6 +334,89 @@ impl VoiceCommandState {
         }
     }
     
+    /// Accept commands without the prefix for a while, after the wake word was heard
+    pub fn arm_command_mode(&self) {
+        if let Some(manager) = self.manager.lock().as_mut() {
+            manager.arm();
+        }
+        
+        if let Some(handle) = &self.app_handle {
+            if let Err(e) = handle.emit_all("voice-command:armed", ()) {
+                error!("Failed to emit voice command armed event: {}", e);
+            }
+        }
+    }
+    
+    /// Switch between dictation, command and mixed mode
+    pub fn set_mode(&self, mode: CommandMode) -> Result<(), String> {
+        match self.manager.lock().as_mut() {
+            Some(manager) => {
+                manager.set_mode(mode);
+                Ok(())
+            }
+            None => Err("Voice command manager not initialized".to_string()),
+        }
+    }
+    
+    /// Go to sleep, hearing nothing but "wake up", or wake up
+    pub fn set_asleep(&self, asleep: bool) -> Result<(), String> {
+        match self.manager.lock().as_mut() {
+            Some(manager) => {
+                manager.set_asleep(asleep);
+                Ok(())
+            }
+            None => Err("Voice command manager not initialized".to_string()),
+        }
+    }
+    
+    /// Whether nothing but "wake up" is heard
+    pub fn is_asleep(&self) -> bool {
+        self.manager.lock().as_ref().is_some_and(|manager| manager.is_asleep())
+    }
+    
+    /// Cancel what is going on, as "cancel" does
+    pub fn cancel(&self) -> Result<(), String> {
+        match self.manager.lock().as_mut() {
+            Some(manager) => {
+                manager.cancel();
+                Ok(())
+            }
+            None => Err("Voice command manager not initialized".to_string()),
+        }
+    }
+    
+    /// Type what was said in `text` into the application that has the focus, when that is turned on
+    pub fn inject_utterance(&self, text: &str, commands: &[Command]) -> Result<(), String> {
+        match self.manager.lock().as_mut() {
+            Some(manager) if manager.injects_text() => manager.inject_utterance(text, commands).map_err(|e| e.to_string()),
+            Some(_) => Ok(()),
+            None => Err("Voice command manager not initialized".to_string()),
+        }
+    }
+    
+    /// The window commands are heard for, if it is known
+    pub fn active_window(&self) -> Option<ActiveWindow> {
+        self.manager.lock().as_ref().and_then(|manager| manager.active_window().cloned())
+    }
+    
+    /// Names of the voice command profiles, with "default" for the main settings
+    pub fn profiles(&self) -> Vec<String> {
+        self.manager.lock().as_ref().map(|manager| manager.profiles()).unwrap_or_default()
+    }
+    
+    /// The commands that can be said now, by category
+    pub fn available_commands(&self) -> Vec<CommandCategory> {
+        self.manager.lock().as_ref().map(|manager| manager.available_commands()).unwrap_or_default()
+    }
+    
+    /// Switch to the voice command profile called `name`
+    pub fn switch_profile(&self, name: &str) -> Result<(), String> {
+        match self.manager.lock().as_mut() {
+            Some(manager) => manager.switch_profile(name).map_err(|e| e.to_string()),
+            None => Err("Voice command manager not initialized".to_string()),
+        }
+    }
+    
     /// Process transcription text for voice commands
     pub fn process_transcription(&self, text: &str) -> Result<Vec<Command>> {
         if !*self.is_enabled.lock() {
@@ -267,7 +426,12 @@ impl VoiceCommandState {
         let manager = self.manager.lock();
         if let Some(manager) = manager.as_ref() {
             match manager.process_transcription(text) {
-                Ok(commands) => Ok(commands),
+                Ok(commands) => {
+                    if !commands.is_empty() {
+                        self.save_command_stats(manager.command_stats());
+                    }
+                    Ok(commands)
+                },
                 Err(_) => Ok(Vec::new())
             }
         } else {
@@ -275,6 +439,41 @@ impl VoiceCommandState {
         }
     }
     
+    fn save_command_stats(&self, stats: &CommandStats) {
+        if let Err(e) = CommandStats::default_path().and_then(|path| stats.save(&path)) {
+            warn!("Failed to save voice command statistics: {}", e);
+        }
+    }
+    
+    /// How often each command was carried out, how well it was heard and how often by mistake
+    pub fn command_stats(&self) -> Vec<CommandUsage> {
+        self.manager.lock().as_ref().map(|manager| manager.command_stats().usage()).unwrap_or_default()
+    }
+    
+    /// Count the last command carried out as heard by mistake
+    pub fn report_mistake(&self) -> Result<(), String> {
+        match self.manager.lock().as_mut() {
+            Some(manager) if manager.report_mistake() => {
+                self.save_command_stats(manager.command_stats());
+                Ok(())
+            }
+            Some(_) => Err("No command to report".to_string()),
+            None => Err("Voice command manager not initialized".to_string()),
+        }
+    }
+    
+    /// Forget the commands counted, e.g. once the sensitivity was changed
+    pub fn clear_command_stats(&self) -> Result<(), String> {
+        match self.manager.lock().as_mut() {
+            Some(manager) => {
+                manager.clear_command_stats();
+                self.save_command_stats(manager.command_stats());
+                Ok(())
+            }
+            None => Err("Voice command manager not initialized".to_string()),
+        }
+    }
+    
     /// Enable voice commands with async interface for Tauri 2.0
     pub async fn enable(&mut self) -> Result<(), String> {
         self.start().map_err(|e| e.to_string())
@@ -310,14 +509,13 @@ impl VoiceCommandState {
         *self.is_enabled.lock()
     }
     
-    pub fn create_default_config(&self) -> TauriVoiceCommandConfig {
-        TauriVoiceCommandConfig {
-            enabled: true,
+    /// Configuration used until one is given, as when there are no saved settings
+    pub fn default_config() -> VoiceCommandConfig {
+        VoiceCommandConfig {
             command_prefix: Some("computer".to_string()),
             require_prefix: true,
             sensitivity: 0.5,
-            custom_commands: Vec::new(),
-            default_commands: true,
+            ..VoiceCommandConfig::default()
         }
     }
     
@@ -346,25 +544,6 @@ impl VoiceCommandState {
         }
     }
     
-    /// Helper to convert from TauriVoiceCommandConfig to VoiceCommandConfig
-    pub fn convert_config(&self, tauri_config: TauriVoiceCommandConfig) -> VoiceCommandConfig {
-        let mut config = VoiceCommandConfig::default();
-        
-        config.enabled = tauri_config.enabled;
-        config.command_prefix = tauri_config.command_prefix;
-        config.require_prefix = tauri_config.require_prefix;
-        config.sensitivity = tauri_config.sensitivity;
-        
-        // Map custom commands
-        let mut custom_commands = HashMap::new();
-        for (phrase, action) in tauri_config.custom_commands {
-            custom_commands.insert(phrase, action);
-        }
-        config.custom_commands = custom_commands;
-        
-        config
-    }
-    
     /// Apply a delete operation to the current text
     pub fn apply_delete(&self, scope_name: &str) -> Result<String, String> {
         let manager = self.manager.lock();
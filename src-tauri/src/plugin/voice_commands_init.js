@@ -10,7 +10,16 @@ window.__TAURI_PLUGIN_VOICE_COMMANDS__ = {
       "command-error": [],
       "started": [],
       "stopped": [],
-      "status-change": []
+      "status-change": [],
+      "armed": [],
+      "mode-change": [],
+      "profile-change": [],
+      "shell-command-finished": [],
+      "help": [],
+      "text-updated": [],
+      "sleep-change": [],
+      "context-change": [],
+      "cancelled": []
     };
     
     // Listen for events from the Rust side
@@ -34,6 +43,49 @@ window.__TAURI_PLUGIN_VOICE_COMMANDS__ = {
       listeners["status-change"].forEach(cb => cb(false));
     });
     
+    window.__TAURI__.event.listen("voice-command:armed", () => {
+      listeners["armed"].forEach(cb => cb());
+    });
+    
+    window.__TAURI__.event.listen("voice-command:mode", (event) => {
+      const mode = event.payload;
+      listeners["mode-change"].forEach(cb => cb(mode));
+    });
+    
+    window.__TAURI__.event.listen("voice-command:profile", (event) => {
+      const profile = event.payload;
+      listeners["profile-change"].forEach(cb => cb(profile));
+    });
+    
+    window.__TAURI__.event.listen("voice-command:sleep", (event) => {
+      const asleep = event.payload;
+      listeners["sleep-change"].forEach(cb => cb(asleep));
+    });
+    
+    window.__TAURI__.event.listen("voice-command:cancelled", () => {
+      listeners["cancelled"].forEach(cb => cb());
+    });
+    
+    window.__TAURI__.event.listen("voice-command:context", (event) => {
+      const context = event.payload;
+      listeners["context-change"].forEach(cb => cb(context));
+    });
+    
+    window.__TAURI__.event.listen("voice-command:shell-finished", (event) => {
+      const output = event.payload;
+      listeners["shell-command-finished"].forEach(cb => cb(output));
+    });
+    
+    window.__TAURI__.event.listen("voice-command:help", (event) => {
+      const categories = event.payload;
+      listeners["help"].forEach(cb => cb(categories));
+    });
+    
+    window.__TAURI__.event.listen("voice-command:text-updated", (event) => {
+      const text = event.payload;
+      listeners["text-updated"].forEach(cb => cb(text));
+    });
+    
     // Export API
     return {
       // Start voice command processing
@@ -51,6 +103,68 @@ window.__TAURI_PLUGIN_VOICE_COMMANDS__ = {
         return window.__TAURI__.event.emit("voice-command:update-text", text);
       },
       
+      // Switch to "Dictation", "Command" or "Mixed" mode
+      async setMode(mode) {
+        return window.__TAURI__.invoke("set_voice_command_mode", { mode });
+      },
+      
+      // Hear nothing but "wake up" until it is said, as "go to sleep" does
+      async sleep() {
+        return window.__TAURI__.invoke("set_voice_commands_asleep", { asleep: true });
+      },
+      
+      // Hear commands and dictation again
+      async wake() {
+        return window.__TAURI__.invoke("set_voice_commands_asleep", { asleep: false });
+      },
+      
+      // Whether voice commands are asleep
+      async isAsleep() {
+        return window.__TAURI__.invoke("is_voice_commands_asleep");
+      },
+      
+      // Stop spelling and running programs and forget the wake word, as "cancel" does
+      async cancel() {
+        return window.__TAURI__.invoke("cancel_voice_commands");
+      },
+      
+      // The focused window commands are heard for, as { app, title }, or null until it is known
+      async getContext() {
+        return window.__TAURI__.invoke("get_voice_command_context");
+      },
+      
+      // Names of the command profiles, "default" first
+      async listProfiles() {
+        return window.__TAURI__.invoke("list_voice_command_profiles");
+      },
+      
+      // The commands that can be said now, as [{ name, commands: [{ trigger, description, parameters: [{ name, kind }] }] }]
+      // with `kind` "number" or "text"; triggers show parameters as {name} and alternatives as word|words
+      async listCommands() {
+        return window.__TAURI__.invoke("list_voice_commands");
+      },
+      
+      // How each command was heard, most used first, as [{ command_type, count, average_score, wrong }]
+      // with `average_score` from 0 to 1 and `wrong` the times it was reported as heard by mistake
+      async getCommandStats() {
+        return window.__TAURI__.invoke("get_voice_command_stats");
+      },
+      
+      // Report the last command carried out as heard by mistake, as saying "that was wrong" does
+      async reportMistake() {
+        return window.__TAURI__.invoke("report_voice_command_mistake");
+      },
+      
+      // Forget the commands counted, e.g. after changing the sensitivity
+      async clearCommandStats() {
+        return window.__TAURI__.invoke("clear_voice_command_stats");
+      },
+      
+      // Switch to a command profile by name; it is also used next time
+      async switchProfile(name) {
+        return window.__TAURI__.invoke("switch_voice_command_profile", { name });
+      },
+      
       // Get the current status
       async getStatus() {
         return window.__TAURI__.invoke("plugin:voice_commands:get_voice_commands_status");
@@ -105,6 +219,87 @@ window.__TAURI_PLUGIN_VOICE_COMMANDS__ = {
           const index = listeners["status-change"].indexOf(callback);
           if (index !== -1) listeners["status-change"].splice(index, 1);
         };
+      },
+      
+      // The wake word was heard; commands are accepted without the prefix for a few seconds
+      onArmed(callback) {
+        listeners["armed"].push(callback);
+        return () => {
+          const index = listeners["armed"].indexOf(callback);
+          if (index !== -1) listeners["armed"].splice(index, 1);
+        };
+      },
+      
+      // Switched between dictation, command and mixed mode, by voice or setMode
+      onModeChange(callback) {
+        listeners["mode-change"].push(callback);
+        return () => {
+          const index = listeners["mode-change"].indexOf(callback);
+          if (index !== -1) listeners["mode-change"].splice(index, 1);
+        };
+      },
+      
+      // Switched to another command profile, by voice or switchProfile
+      onProfileChange(callback) {
+        listeners["profile-change"].push(callback);
+        return () => {
+          const index = listeners["profile-change"].indexOf(callback);
+          if (index !== -1) listeners["profile-change"].splice(index, 1);
+        };
+      },
+      
+      // Went to sleep or woke up, by voice or sleep() and wake(); gets whether it is asleep
+      onSleepChange(callback) {
+        listeners["sleep-change"].push(callback);
+        return () => {
+          const index = listeners["sleep-change"].indexOf(callback);
+          if (index !== -1) listeners["sleep-change"].splice(index, 1);
+        };
+      },
+      
+      // What was going on was cancelled, by voice or cancel(); what was just said is dropped
+      onCancel(callback) {
+        listeners["cancelled"].push(callback);
+        return () => {
+          const index = listeners["cancelled"].indexOf(callback);
+          if (index !== -1) listeners["cancelled"].splice(index, 1);
+        };
+      },
+      
+      // Another window got the focus; gets its { app, title }, which decides the commands heard
+      onContextChange(callback) {
+        listeners["context-change"].push(callback);
+        return () => {
+          const index = listeners["context-change"].indexOf(callback);
+          if (index !== -1) listeners["context-change"].splice(index, 1);
+        };
+      },
+      
+      // A program run by a voice command exited; gets its name, status, stdout and stderr
+      onShellCommandFinished(callback) {
+        listeners["shell-command-finished"].push(callback);
+        return () => {
+          const index = listeners["shell-command-finished"].indexOf(callback);
+          if (index !== -1) listeners["shell-command-finished"].splice(index, 1);
+        };
+      },
+      
+      // "What can I say" was heard; gets the commands as listCommands() returns them
+      onHelp(callback) {
+        listeners["help"].push(callback);
+        return () => {
+          const index = listeners["help"].indexOf(callback);
+          if (index !== -1) listeners["help"].splice(index, 1);
+        };
+      },
+      
+      // A command changed the text; gets all of it, as getText() would return it
+      onTextUpdated(callback) {
+        listeners["text-updated"].push(callback);
+        return () => {
+          const index = listeners["text-updated"].indexOf(callback);
+          if (index !== -1) listeners["text-updated"].splice(index, 1);
+        };
       }
     };
   }
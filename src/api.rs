@@ -0,0 +1,545 @@
+use anyhow::{Context, Result};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{FromRef, Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post, put};
+use axum::{Json, Router};
+use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::{broadcast, oneshot};
+use tokio::task::JoinHandle;
+
+use crate::audio::history::{DateRange, SearchHit, DEFAULT_SEARCH_LIMIT};
+use crate::audio::session::{Session, SessionSummary};
+use crate::config::ApiSettings;
+
+/// What the HTTP API drives: the application's recording, transcription and transcripts
+pub trait ApiController: Send + Sync + 'static {
+    /// Start recording and transcribing, from `device` or the selected device
+    fn start(&self, device: Option<&str>) -> Result<()>;
+
+    /// Stop transcribing and recording
+    fn stop(&self) -> Result<()>;
+
+    /// Whether audio is recorded and transcribed, and from which device
+    fn status(&self) -> ApiStatus;
+
+    /// Input devices that can be recorded from
+    fn devices(&self) -> Vec<ApiDevice>;
+
+    /// Record from the device with stable ID `id` from now on
+    fn select_device(&self, id: &str) -> Result<()>;
+
+    /// Text of the running session, or of the last one once stopped
+    fn transcript(&self) -> String;
+
+    /// Saved sessions, newest first
+    fn sessions(&self) -> Result<Vec<SessionSummary>>;
+
+    /// A saved session with its transcript
+    fn session(&self, id: &str) -> Result<Session>;
+
+    /// Segments of saved sessions matching `query`
+    fn search(&self, query: &str, range: &DateRange, limit: usize) -> Result<Vec<SearchHit>>;
+}
+
+/// Live events a WebSocket client can fall behind by before it misses some
+const LIVE_EVENT_CAPACITY: usize = 256;
+
+/// What a live event is about, for clients to pick the ones they show
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LiveEventKind {
+    /// Text of a segment still being said, which may change
+    Partial,
+
+    /// A segment's final text
+    Final,
+
+    /// Input levels
+    Level,
+
+    /// A voice command heard, carried out or failed
+    Command,
+}
+
+/// An event sent to every client of `GET /events` as a JSON text frame
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LiveEvent {
+    pub kind: LiveEventKind,
+
+    /// The application's name for the event, e.g. "voice-command:detected"
+    pub event: String,
+
+    /// What the application reported with it
+    pub data: serde_json::Value,
+}
+
+/// Where live events are published for the API's WebSocket clients
+pub type LiveEvents = broadcast::Sender<LiveEvent>;
+
+/// A channel for live events; events published while no client is connected are dropped
+pub fn live_events() -> LiveEvents {
+    broadcast::channel(LIVE_EVENT_CAPACITY).0
+}
+
+/// What `GET /status` reports
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ApiStatus {
+    pub recording: bool,
+    pub transcribing: bool,
+
+    /// Text is only listened to for voice commands while paused
+    pub paused: bool,
+
+    /// Stable ID of the device recorded from
+    pub device: Option<String>,
+}
+
+/// An input device as `GET /devices` lists it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiDevice {
+    /// Stable ID, for selecting the device
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct StartRequest {
+    device: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceRequest {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchQuery {
+    q: String,
+    from: Option<chrono::DateTime<chrono::Local>>,
+    to: Option<chrono::DateTime<chrono::Local>>,
+    limit: Option<usize>,
+}
+
+/// A failed request, answered with its status and `{"error": message}`
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(error: anyhow::Error) -> Self {
+        Self(StatusCode::BAD_REQUEST, error.to_string())
+    }
+}
+
+type Controller = Arc<dyn ApiController>;
+
+/// What the handlers share, each taking the part it needs
+#[derive(Clone)]
+struct ApiState {
+    controller: Controller,
+    events: LiveEvents,
+}
+
+impl FromRef<ApiState> for Controller {
+    fn from_ref(state: &ApiState) -> Self {
+        state.controller.clone()
+    }
+}
+
+impl FromRef<ApiState> for LiveEvents {
+    fn from_ref(state: &ApiState) -> Self {
+        state.events.clone()
+    }
+}
+
+/// The API's routes, answering only requests with `token` as their bearer token
+///
+/// - `GET /status`, `GET /devices`, `PUT /device` with `{"id": ...}`
+/// - `POST /start` with an optional `{"device": ...}`, `POST /stop`
+/// - `GET /transcript`, `GET /sessions`, `GET /sessions/{id}`
+/// - `GET /search?q=...`, optionally with `from`, `to` and `limit`
+/// - `GET /events`, a WebSocket streaming what is published on `events`
+///
+/// Browsers can't send headers with WebSockets, so `/events` also takes the
+/// token as the `token` query parameter.
+pub fn router(controller: Controller, token: String, events: LiveEvents) -> Router {
+    let token = Arc::new(token);
+    Router::new()
+        .route("/status", get(status))
+        .route("/start", post(start))
+        .route("/stop", post(stop))
+        .route("/devices", get(devices))
+        .route("/device", put(select_device))
+        .route("/transcript", get(transcript))
+        .route("/sessions", get(sessions))
+        .route("/sessions/:id", get(session))
+        .route("/search", get(search))
+        .route("/events", get(live))
+        .layer(middleware::from_fn_with_state(token, authorize))
+        .with_state(ApiState { controller, events })
+}
+
+/// Turn away requests without the token, comparing it in constant time
+async fn authorize(State(token): State<Arc<String>>, request: Request, next: Next) -> Response {
+    let sent = request.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string)
+        .or_else(|| query_token(&request))
+        .unwrap_or_default();
+    let matches = sent.len() == token.len()
+        && sent.bytes().zip(token.bytes()).fold(0, |difference, (a, b)| difference | (a ^ b)) == 0;
+    if !matches {
+        return ApiError(StatusCode::UNAUTHORIZED, "Missing or wrong API token".to_string()).into_response();
+    }
+    next.run(request).await
+}
+
+/// The percent-decoded `token` query parameter, taken only for the WebSocket
+fn query_token(request: &Request) -> Option<String> {
+    if request.uri().path() != "/events" {
+        return None;
+    }
+    let Query(mut query) = Query::<HashMap<String, String>>::try_from_uri(request.uri()).ok()?;
+    query.remove("token")
+}
+
+/// Call the controller on a blocking thread, since starting and stopping wait on the capture thread
+async fn call<T: Send + 'static>(controller: Controller, call: impl FnOnce(&dyn ApiController) -> T + Send + 'static) -> Result<T, ApiError> {
+    tokio::task::spawn_blocking(move || call(controller.as_ref()))
+        .await
+        .map_err(|e| ApiError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+async fn status(State(controller): State<Controller>) -> Result<Json<ApiStatus>, ApiError> {
+    Ok(Json(call(controller, |controller| controller.status()).await?))
+}
+
+async fn start(State(controller): State<Controller>, request: Option<Json<StartRequest>>) -> Result<Json<ApiStatus>, ApiError> {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    let status = call(controller, move |controller| -> Result<ApiStatus> {
+        controller.start(request.device.as_deref())?;
+        Ok(controller.status())
+    }).await??;
+    Ok(Json(status))
+}
+
+async fn stop(State(controller): State<Controller>) -> Result<Json<ApiStatus>, ApiError> {
+    let status = call(controller, |controller| -> Result<ApiStatus> {
+        controller.stop()?;
+        Ok(controller.status())
+    }).await??;
+    Ok(Json(status))
+}
+
+async fn devices(State(controller): State<Controller>) -> Result<Json<Vec<ApiDevice>>, ApiError> {
+    Ok(Json(call(controller, |controller| controller.devices()).await?))
+}
+
+async fn select_device(State(controller): State<Controller>, Json(request): Json<DeviceRequest>) -> Result<Json<ApiStatus>, ApiError> {
+    let status = call(controller, move |controller| -> Result<ApiStatus> {
+        controller.select_device(&request.id)?;
+        Ok(controller.status())
+    }).await??;
+    Ok(Json(status))
+}
+
+async fn transcript(State(controller): State<Controller>) -> Result<Json<serde_json::Value>, ApiError> {
+    let text = call(controller, |controller| controller.transcript()).await?;
+    Ok(Json(serde_json::json!({ "text": text })))
+}
+
+async fn sessions(State(controller): State<Controller>) -> Result<Json<Vec<SessionSummary>>, ApiError> {
+    Ok(Json(call(controller, |controller| controller.sessions()).await??))
+}
+
+async fn session(State(controller): State<Controller>, Path(id): Path<String>) -> Result<Json<Session>, ApiError> {
+    call(controller, move |controller| controller.session(&id)).await?
+        .map(Json)
+        .map_err(|e| ApiError(StatusCode::NOT_FOUND, e.to_string()))
+}
+
+async fn search(State(controller): State<Controller>, Query(query): Query<SearchQuery>) -> Result<Json<Vec<SearchHit>>, ApiError> {
+    let range = DateRange { from: query.from, to: query.to };
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    Ok(Json(call(controller, move |controller| controller.search(&query.q, &range, limit)).await??))
+}
+
+async fn live(socket: WebSocketUpgrade, State(events): State<LiveEvents>) -> Response {
+    let events = events.subscribe();
+    socket.on_upgrade(move |socket| stream_events(socket, events))
+}
+
+/// Send each live event to a client until it goes away
+async fn stream_events(mut socket: WebSocket, mut events: broadcast::Receiver<LiveEvent>) {
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let frame = match serde_json::to_string(&event) {
+                        Ok(frame) => frame,
+                        Err(e) => {
+                            error!("Failed to encode live event: {}", e);
+                            continue;
+                        }
+                    };
+                    if socket.send(Message::Text(frame)).await.is_err() {
+                        break;
+                    }
+                }
+                // A slow client skips what it fell behind on rather than holding the others up
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    debug!("WebSocket client missed {} live events", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            // Clients only listen, so anything they send but a close is ignored
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// The HTTP API, served in the background until it is stopped or dropped
+pub struct ApiServer {
+    address: SocketAddr,
+
+    /// Tells the server to finish the requests it has and stop
+    shutdown: Option<oneshot::Sender<()>>,
+
+    task: Option<JoinHandle<()>>,
+}
+
+impl ApiServer {
+    /// Serve the API on the loopback address in `settings`, streaming what is published on `events`
+    ///
+    /// Fails without a token or for an address other computers could reach.
+    pub async fn start(settings: &ApiSettings, controller: Controller, events: LiveEvents) -> Result<Self> {
+        if settings.token.is_empty() {
+            anyhow::bail!("Set a token for the HTTP API before turning it on");
+        }
+        let address: SocketAddr = settings.bind_address.parse()
+            .with_context(|| format!("Invalid HTTP API address {}", settings.bind_address))?;
+        if !address.ip().is_loopback() {
+            anyhow::bail!("The HTTP API only listens on this computer; use 127.0.0.1 rather than {}", address.ip());
+        }
+
+        let listener = tokio::net::TcpListener::bind(address).await
+            .with_context(|| format!("Failed to listen on {}", address))?;
+        let address = listener.local_addr()?;
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        let app = router(controller, settings.token.clone(), events);
+
+        let task = tokio::spawn(async move {
+            let served = axum::serve(listener, app)
+                .with_graceful_shutdown(async {
+                    let _ = stopped.await;
+                })
+                .await;
+            if let Err(e) = served {
+                error!("HTTP API stopped: {}", e);
+            }
+        });
+        info!("HTTP API listening on http://{}", address);
+
+        Ok(Self {
+            address,
+            shutdown: Some(shutdown),
+            task: Some(task),
+        })
+    }
+
+    /// Address the API listens on, with the port chosen when it was 0
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Stop serving, once the requests being answered are done
+    pub async fn stop(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+        info!("HTTP API stopped");
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+
+    /// Keeps what the API asked of it
+    #[derive(Default)]
+    struct FakeController {
+        status: Mutex<ApiStatus>,
+    }
+
+    impl ApiController for FakeController {
+        fn start(&self, device: Option<&str>) -> Result<()> {
+            // The capture manager waits on its thread like this, which panics on the runtime
+            let (sender, _receiver) = tokio::sync::mpsc::channel(1);
+            sender.blocking_send(())?;
+            let mut status = self.status.lock();
+            status.recording = true;
+            status.transcribing = true;
+            if let Some(device) = device {
+                status.device = Some(device.to_string());
+            }
+            Ok(())
+        }
+
+        fn stop(&self) -> Result<()> {
+            let mut status = self.status.lock();
+            status.recording = false;
+            status.transcribing = false;
+            Ok(())
+        }
+
+        fn status(&self) -> ApiStatus {
+            self.status.lock().clone()
+        }
+
+        fn devices(&self) -> Vec<ApiDevice> {
+            vec![ApiDevice { id: "mic-1".to_string(), name: "Microphone".to_string() }]
+        }
+
+        fn select_device(&self, id: &str) -> Result<()> {
+            if !self.devices().iter().any(|device| device.id == id) {
+                anyhow::bail!("Device not found with ID: {}", id);
+            }
+            self.status.lock().device = Some(id.to_string());
+            Ok(())
+        }
+
+        fn transcript(&self) -> String {
+            "Hello there.".to_string()
+        }
+
+        fn sessions(&self) -> Result<Vec<SessionSummary>> {
+            Ok(Vec::new())
+        }
+
+        fn session(&self, id: &str) -> Result<Session> {
+            anyhow::bail!("No session {}", id)
+        }
+
+        fn search(&self, _query: &str, _range: &DateRange, _limit: usize) -> Result<Vec<SearchHit>> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_drives_the_controller_with_the_token() {
+        let settings = ApiSettings { enabled: true, bind_address: "127.0.0.1:0".to_string(), token: "secret".to_string() };
+        let server = ApiServer::start(&settings, Arc::new(FakeController::default()), live_events()).await.unwrap();
+        let url = |path: &str| format!("http://{}{}", server.address(), path);
+        let client = reqwest::Client::new();
+
+        // Nothing is answered without the token
+        let response = client.get(url("/status")).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+        let response = client.get(url("/status")).bearer_auth("wrong").send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let status: ApiStatus = client.post(url("/start")).bearer_auth("secret")
+            .json(&serde_json::json!({ "device": "mic-1" }))
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert!(status.recording && status.transcribing);
+        assert_eq!(status.device.as_deref(), Some("mic-1"));
+
+        let response = client.put(url("/device")).bearer_auth("secret")
+            .json(&serde_json::json!({ "id": "missing" }))
+            .send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        let transcript: serde_json::Value = client.get(url("/transcript")).bearer_auth("secret")
+            .send().await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(transcript["text"], "Hello there.");
+
+        let status: ApiStatus = client.post(url("/stop")).bearer_auth("secret").send().await.unwrap().json().await.unwrap();
+        assert!(!status.recording);
+        server.stop().await;
+
+        // Only loopback addresses are served, and only with a token
+        let open = ApiSettings { bind_address: "0.0.0.0:0".to_string(), ..settings.clone() };
+        assert!(ApiServer::start(&open, Arc::new(FakeController::default()), live_events()).await.is_err());
+        let tokenless = ApiSettings { token: String::new(), ..settings };
+        assert!(ApiServer::start(&tokenless, Arc::new(FakeController::default()), live_events()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_streams_live_events() {
+        use futures_util::StreamExt;
+        use tokio_tungstenite::tungstenite::Message;
+
+        let settings = ApiSettings { enabled: true, bind_address: "127.0.0.1:0".to_string(), token: "secret".to_string() };
+        let events = live_events();
+        let server = ApiServer::start(&settings, Arc::new(FakeController::default()), events.clone()).await.unwrap();
+
+        // Only with the token, which browsers give in the query
+        assert!(tokio_tungstenite::connect_async(format!("ws://{}/events", server.address())).await.is_err());
+        let url = format!("ws://{}/events?token=secret", server.address());
+        let (mut first, _) = tokio_tungstenite::connect_async(url.as_str()).await.unwrap();
+        let (mut second, _) = tokio_tungstenite::connect_async(url.as_str()).await.unwrap();
+
+        // Wait until both have subscribed, then every client gets each event
+        while events.receiver_count() < 2 {
+            tokio::task::yield_now().await;
+        }
+        let event = LiveEvent {
+            kind: LiveEventKind::Final,
+            event: "transcription:final".to_string(),
+            data: serde_json::json!("Hello there."),
+        };
+        events.send(event.clone()).unwrap();
+        for client in [&mut first, &mut second] {
+            let frame = match client.next().await.unwrap().unwrap() {
+                Message::Text(frame) => frame,
+                other => panic!("Expected a text frame, got {:?}", other),
+            };
+            assert_eq!(serde_json::from_str::<LiveEvent>(&frame).unwrap(), event);
+            assert!(frame.contains(r#""kind":"final""#));
+        }
+        server.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_takes_the_query_token_only_for_events() {
+        let settings = ApiSettings { enabled: true, bind_address: "127.0.0.1:0".to_string(), token: "a b&c".to_string() };
+        let server = ApiServer::start(&settings, Arc::new(FakeController::default()), live_events()).await.unwrap();
+
+        let response = reqwest::get(format!("http://{}/status?token=a%20b%26c", server.address())).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        // The token is compared once decoded
+        tokio_tungstenite::connect_async(format!("ws://{}/events?token=a%20b%26c", server.address()).as_str()).await.unwrap();
+        server.stop().await;
+    }
+}
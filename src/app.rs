@@ -1,15 +1,23 @@
 use anyhow::{Context, Result};
-use log::{error, info, warn};
+use log::{debug, error, info, warn};
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
+use std::collections::HashMap;
 use std::io::{self, Write};
 use std::sync::Arc;
 use parking_lot;
 
 use crate::audio::{
-    device::DeviceManager,
-    capture::{CaptureManager, AudioEvent},
-    transcribe::{TranscriptionManager, TranscriptionEvent},
+    aec::EchoCanceller,
+    device::{self, DeviceManager},
+    capture::{CaptureCommand, CaptureManager, AudioEvent, AudioData},
+    denoise::NoiseSuppressor,
+    export::{self, ExportFormat},
+    history::TranscriptHistory,
+    recording::RecordingManager,
+    resample::StreamResampler,
+    script_hooks::ScriptHooks,
+    transcribe::{self, Speaker, TranscriptionManager, TranscriptionEvent},
     AudioConfig,
 };
 use crate::config::{Config, ConfigManager};
@@ -35,9 +43,21 @@ pub struct App {
     /// Audio event receiver
     audio_receiver: Option<mpsc::Receiver<AudioEvent>>,
     
+    /// Recovery requests from the audio thread, carried out on this thread, which owns the streams
+    recovery_sender: mpsc::Sender<CaptureCommand>,
+    
+    /// Recovery requests waiting for the menu loop
+    recovery_receiver: mpsc::Receiver<CaptureCommand>,
+    
     /// Audio processing task
     audio_task: Option<JoinHandle<()>>,
     
+    /// Loopback capture providing the echo cancellation reference
+    reference_capture: Option<CaptureManager>,
+    
+    /// Echo reference processing task
+    reference_task: Option<JoinHandle<()>>,
+    
     /// Transcription manager
     transcription_manager: Option<TranscriptionManager>,
     
@@ -53,11 +73,18 @@ pub struct App {
 
 impl App {
     /// Create a new application instance
-    pub fn new(config_manager: ConfigManager) -> Result<Self> {
+    pub fn new(mut config_manager: ConfigManager) -> Result<Self> {
         // Initialize audio device manager
         let device_manager = DeviceManager::new()
             .context("Failed to initialize audio device manager")?;
         
+        // Older configs stored device names; switch them to stable device IDs
+        if device_manager.migrate_settings(&mut config_manager.get_config_mut().audio) {
+            if let Err(e) = config_manager.save() {
+                warn!("Failed to save migrated device settings: {}", e);
+            }
+        }
+        
         // Determine if we should use GUI mode
         // For now, we'll always use GUI mode on Windows
         #[cfg(target_os = "windows")]
@@ -66,6 +93,8 @@ impl App {
         #[cfg(not(target_os = "windows"))]
         let use_gui = false;
         
+        let (recovery_sender, recovery_receiver) = mpsc::channel(10);
+        
         Ok(Self {
             config_manager,
             device_manager,
@@ -73,7 +102,11 @@ impl App {
             use_gui,
             capture_manager: None,
             audio_receiver: None,
+            recovery_sender,
+            recovery_receiver,
             audio_task: None,
+            reference_capture: None,
+            reference_task: None,
             transcription_manager: None,
             transcription_receiver: None,
             transcription_task: None,
@@ -159,6 +192,14 @@ impl App {
             }
         }
         
+        let loopback_devices = self.device_manager.get_loopback_devices();
+        if !loopback_devices.is_empty() {
+            println!("\nSystem Audio (Loopback) Sources:");
+            for (_, name) in &loopback_devices {
+                println!("- {}", name);
+            }
+        }
+        
         Ok(())
     }
     
@@ -167,25 +208,25 @@ impl App {
         // Stop any existing capture
         self.stop_audio_capture().await;
         
-        // Get device to use
-        let _device = if let Some(id) = device_id {
-            self.device_manager.get_input_device(id)
-                .ok_or_else(|| anyhow::anyhow!("Device with ID {} not found", id))?
-        } else {
-            self.device_manager.get_default_input_device()
-                .ok_or_else(|| anyhow::anyhow!("No default input device found"))?
-        };
-        
         // Create audio config from the application config
-        let _audio_config = AudioConfig {
-            input_device: device_id.map(String::from),
-            input_volume: self.config_manager.get_config().audio.input_volume,
-            ..AudioConfig::default()
-        };
+        let mut audio_config = AudioConfig::from_settings(&self.config_manager.get_config().audio);
+        if let Some(id) = device_id {
+            audio_config.input_device = Some(id.to_string());
+        }
+        
+        // Get device to use (network audio needs no local device)
+        if audio_config.network.is_none() {
+            if let Some(id) = device_id {
+                self.device_manager.get_input_device(id)
+                    .ok_or_else(|| anyhow::anyhow!("Device with ID {} not found", id))?;
+            } else {
+                self.device_manager.get_default_input_device()
+                    .ok_or_else(|| anyhow::anyhow!("No default input device found"))?;
+            }
+        }
         
         // Create capture manager
-        let _audio_config = self.config_manager.get_config().audio.clone();
-        let (capture_manager, receiver) = match CaptureManager::new() {
+        let (mut capture_manager, receiver) = match CaptureManager::for_config(audio_config) {
             Ok(result) => result,
             Err(e) => {
                 error!("Failed to create capture manager: {}", e);
@@ -193,15 +234,50 @@ impl App {
             }
         };
         
+        // Fall back to the default device or restart a stalled stream from the menu loop
+        let recovery_sender = self.recovery_sender.clone();
+        capture_manager.on_device_lost(move || {
+            if let Err(e) = recovery_sender.try_send(CaptureCommand::RecoverDevice) {
+                error!("Failed to schedule device recovery: {}", e);
+            }
+        });
+        
+        let stall_sender = self.recovery_sender.clone();
+        capture_manager.on_stream_stalled(move |reason| {
+            if let Err(e) = stall_sender.try_send(CaptureCommand::RestartStream(reason)) {
+                error!("Failed to schedule stream restart: {}", e);
+            }
+        });
+        
+        // Optionally keep the captured audio on disk, pauses included, before VAD drops them
+        let recording_settings = self.config_manager.get_config().audio.recording.clone();
+        let recorder = if recording_settings.enabled {
+            match RecordingManager::new(recording_settings).and_then(|mut r| r.start().map(|_| r)) {
+                Ok(recorder) => Some(Arc::new(parking_lot::Mutex::new(recorder))),
+                Err(e) => {
+                    warn!("Audio recording disabled: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        
+        if let Some(recorder) = recorder.clone() {
+            capture_manager.on_raw_audio(move |audio_data| {
+                if let Err(e) = recorder.lock().write(&audio_data) {
+                    error!("Failed to record audio: {}", e);
+                }
+            });
+        }
+        
         // Store capture manager and receiver
         self.capture_manager = Some(capture_manager);
         self.audio_receiver = Some(receiver);
         
         // Initialize transcription if not initialized
         if self.transcription_manager.is_none() {
-            let speech_settings = self.config_manager.get_config().audio.speech.clone();
-            let (transcription_manager, transcription_receiver) = TranscriptionManager::new(speech_settings)
-                .context("Failed to create transcription manager")?;
+            let (transcription_manager, transcription_receiver) = self.create_transcription_manager()?;
             
             self.transcription_manager = Some(transcription_manager);
             self.transcription_receiver = Some(transcription_receiver);
@@ -226,19 +302,30 @@ impl App {
                     let transcription_task = tokio::spawn(async move {
                         while let Some(event) = transcription_receiver.recv().await {
                             match event {
-                                TranscriptionEvent::Transcription(text) => {
+                                TranscriptionEvent::Transcription { text, .. } => {
                                     println!("\nTranscription: {}", text);
                                 },
-                                TranscriptionEvent::PartialTranscription(text) => {
-                                    print!("\rPartial: {}", text);
+                                TranscriptionEvent::LanguageDetected { language, .. } => {
+                                    debug!("Detected language: {} ({:?})", language.language, language.probability);
+                                },
+                                TranscriptionEvent::LowConfidence { confidence, .. } => {
+                                    println!("(low confidence: {:.0}%, please review)", confidence * 100.0);
+                                },
+                                TranscriptionEvent::PartialTranscription { speaker, stable, volatile } => {
+                                    let text = format!("{} {}", stable, volatile);
+                                    print!("\rPartial: {}", transcribe::label_segment(speaker, text.trim().to_string()));
                                     let _ = io::stdout().flush();
                                 },
+                                TranscriptionEvent::Refined { text, .. } => {
+                                    println!("Refined: {}", text);
+                                },
                                 TranscriptionEvent::Started => {
                                     println!("Transcription started");
                                 },
                                 TranscriptionEvent::Stopped => {
                                     println!("Transcription stopped");
                                 },
+                                TranscriptionEvent::SkippedSilence { .. } => {},
                                 TranscriptionEvent::Error(err) => {
                                     eprintln!("Transcription error: {}", err);
                                 },
@@ -247,20 +334,112 @@ impl App {
                     });
                     self.transcription_task = Some(transcription_task);
                     
+                    // Optional echo cancellation against a loopback reference
+                    let echo_canceller = if self.config_manager.get_config().audio.echo_cancellation.enabled {
+                        match self.start_echo_reference() {
+                            Ok(canceller) => Some(canceller),
+                            Err(e) => {
+                                warn!("Echo cancellation unavailable: {}", e);
+                                None
+                            }
+                        }
+                    } else {
+                        None
+                    };
+                    
                     // Process audio with improved error handling
                     let transcription_manager_clone = transcription_manager.clone();
+                    let noise_suppression = self.config_manager.get_config().audio.noise_suppression;
+                    let resampler_settings = self.config_manager.get_config().audio.resampler.clone();
+                    
                     let task = tokio::spawn(async move {
+                        // Streaming resamplers to Whisper's 16 kHz, one per track, created once the input rate is known
+                        let mut resamplers: HashMap<Option<String>, StreamResampler> = HashMap::new();
+                        
+                        // Optional noise suppression stage after echo cancellation. RNNoise is
+                        // non-linear, so it has to run after the echo path has been removed.
+                        let mut denoiser = if noise_suppression {
+                            info!("Noise suppression enabled");
+                            match StreamResampler::new(48000, 16000, &resampler_settings.quality, resampler_settings.chunk_ms) {
+                                Ok(output_resampler) => Some((NoiseSuppressor::new(), output_resampler)),
+                                Err(e) => {
+                                    error!("Failed to create noise suppression resampler: {}", e);
+                                    None
+                                }
+                            }
+                        } else {
+                            None
+                        };
+                        
                         while let Some(event) = receiver.recv().await {
                             match event {
                                 AudioEvent::Data(audio_data) => {
-                                    // Extract raw samples for transcription processing
-                                    let samples = audio_data.get_samples();
+                                    // Per-device tracks are transcribed separately
+                                    let speaker = audio_data.source_id().map(Speaker::for_source);
+                                    
+                                    // Convert to 16 kHz mono samples for transcription processing
+                                    let track = audio_data.source_id().map(|id| id.to_string());
+                                    let needs_resampler = resamplers.get(&track)
+                                        .map(|r| r.from_rate() != audio_data.sample_rate())
+                                        .unwrap_or(true);
+                                    if needs_resampler {
+                                        match StreamResampler::new(
+                                            audio_data.sample_rate(),
+                                            16000,
+                                            &resampler_settings.quality,
+                                            resampler_settings.chunk_ms,
+                                        ) {
+                                            Ok(r) => {
+                                                resamplers.insert(track.clone(), r);
+                                            },
+                                            Err(e) => {
+                                                error!("Failed to create resampler: {}", e);
+                                                continue;
+                                            }
+                                        }
+                                    }
+                                    
+                                    let mut samples = match resamplers.get_mut(&track).map(|r| r.process(&audio_data.to_mono())) {
+                                        Some(Ok(samples)) => samples,
+                                        Some(Err(e)) => {
+                                            error!("Failed to resample audio: {}", e);
+                                            continue;
+                                        },
+                                        None => continue,
+                                    };
+                                    
+                                    // Remote audio from a loopback track needs no echo removal or denoising
+                                    let remote = speaker == Some(Speaker::Others);
                                     
-                                    // Pass the samples to the transcription manager
-                                    if let Err(e) = transcription_manager_clone.process_audio(samples).await {
+                                    // Remove speaker playback picked up by the microphone
+                                    if let Some(canceller) = echo_canceller.as_ref().filter(|_| !remote) {
+                                        samples = canceller.lock().process(&samples);
+                                    }
+                                    
+                                    // Denoise at 48 kHz and convert back to 16 kHz
+                                    if let Some((denoiser, output_resampler)) = denoiser.as_mut().filter(|_| !remote) {
+                                        let denoised = denoiser.process(&AudioData::new(samples, 16000, 1));
+                                        samples = match output_resampler.process(denoised.get_samples()) {
+                                            Ok(samples) => samples,
+                                            Err(e) => {
+                                                error!("Failed to resample denoised audio: {}", e);
+                                                continue;
+                                            }
+                                        };
+                                    }
+                                    
+                                    // Pass the samples to the transcription manager, buffered by source for tracks
+                                    let result = match track.as_deref() {
+                                        Some(source_id) => transcription_manager_clone.process_track(source_id, &samples).await,
+                                        None => transcription_manager_clone.process_audio(&samples).await,
+                                    };
+                                    if let Err(e) = result {
                                         error!("Error processing audio for transcription: {}", e);
                                     }
                                 },
+                                AudioEvent::Metrics(_) | AudioEvent::Waveform(_) => {
+                                    // Only used by the level meter and waveform UI
+                                },
                                 AudioEvent::Level(_level) => {
                                     // Handle audio level event
                                 },
@@ -269,6 +448,16 @@ impl App {
                                 },
                                 AudioEvent::Stopped => {
                                     println!("Audio processing stopped");
+                                    if let Some(recorder) = recorder.as_ref() {
+                                        match recorder.lock().stop() {
+                                            Ok(files) => {
+                                                for file in files {
+                                                    println!("Saved recording: {}", file.display());
+                                                }
+                                            },
+                                            Err(e) => error!("Failed to finish recording: {}", e),
+                                        }
+                                    }
                                     break;
                                 },
                                 AudioEvent::Error(error) => {
@@ -278,6 +467,28 @@ impl App {
                                 AudioEvent::LevelChanged(_level) => {
                                     // Handle level changed event
                                 },
+                                AudioEvent::SpeechStart => {
+                                    debug!("Speech started");
+                                },
+                                AudioEvent::SpeechEnd => {
+                                    debug!("Speech ended");
+                                },
+                                AudioEvent::DeviceChanged { previous, current } => {
+                                    println!(
+                                        "\nInput device {} disconnected, now using {}",
+                                        previous.as_deref().unwrap_or("(default)"),
+                                        current.as_deref().unwrap_or("default device")
+                                    );
+                                },
+                                AudioEvent::StreamRecovered { reason } => {
+                                    println!("\nAudio capture restarted ({})", reason);
+                                },
+                                AudioEvent::ClockDrift(drift) => {
+                                    debug!("Capture clock drift: {:.1} ppm", drift.drift_ppm);
+                                },
+                                AudioEvent::FramesDropped { dropped, total } => {
+                                    warn!("Transcription fell behind, dropped {} audio frames ({} total)", dropped, total);
+                                },
                             }
                         }
                     });
@@ -294,6 +505,91 @@ impl App {
         Ok(())
     }
     
+    /// Start capturing the loopback reference used for echo cancellation
+    fn start_echo_reference(&mut self) -> Result<Arc<parking_lot::Mutex<EchoCanceller>>> {
+        let settings = self.config_manager.get_config().audio.clone();
+        
+        let reference_id = settings.echo_cancellation.reference_device.clone()
+            .or_else(device::default_loopback_id)
+            .ok_or_else(|| anyhow::anyhow!("No loopback source available for echo reference"))?;
+        
+        if !device::is_loopback_id(&reference_id) {
+            return Err(anyhow::anyhow!("Echo reference {} is not a loopback source", reference_id));
+        }
+        
+        let (mut reference_capture, mut receiver) = CaptureManager::new()?;
+        reference_capture.set_config(AudioConfig {
+            input_device: Some(reference_id.clone()),
+            ..AudioConfig::default()
+        });
+        reference_capture.start()
+            .with_context(|| format!("Failed to start echo reference capture on {}", reference_id))?;
+        info!("Echo cancellation enabled (reference: {})", reference_id);
+        
+        let canceller = Arc::new(parking_lot::Mutex::new(
+            EchoCanceller::new(16000, settings.echo_cancellation.filter_ms),
+        ));
+        let task_canceller = canceller.clone();
+        let resampler_settings = settings.resampler.clone();
+        
+        let task = tokio::spawn(async move {
+            let mut resampler: Option<StreamResampler> = None;
+            
+            while let Some(event) = receiver.recv().await {
+                match event {
+                    AudioEvent::Data(audio_data) => {
+                        let needs_resampler = resampler.as_ref()
+                            .map(|r| r.from_rate() != audio_data.sample_rate())
+                            .unwrap_or(true);
+                        if needs_resampler {
+                            match StreamResampler::new(
+                                audio_data.sample_rate(),
+                                16000,
+                                &resampler_settings.quality,
+                                resampler_settings.chunk_ms,
+                            ) {
+                                Ok(r) => resampler = Some(r),
+                                Err(e) => {
+                                    error!("Failed to create echo reference resampler: {}", e);
+                                    continue;
+                                }
+                            }
+                        }
+                        
+                        match resampler.as_mut().map(|r| r.process(&audio_data.to_mono())) {
+                            Some(Ok(samples)) => task_canceller.lock().push_reference(&samples),
+                            Some(Err(e)) => error!("Failed to resample echo reference: {}", e),
+                            None => {}
+                        }
+                    },
+                    AudioEvent::Stopped => break,
+                    AudioEvent::Error(error) => {
+                        error!("Echo reference capture error: {}", error);
+                    },
+                    _ => {}
+                }
+            }
+        });
+        
+        self.reference_capture = Some(reference_capture);
+        self.reference_task = Some(task);
+        
+        Ok(canceller)
+    }
+    
+    /// A transcription manager for the current settings, with the hook scripts' text step
+    fn create_transcription_manager(&self) -> Result<(TranscriptionManager, mpsc::Receiver<TranscriptionEvent>)> {
+        let config = self.config_manager.get_config();
+        let (mut transcription_manager, transcription_receiver) = TranscriptionManager::new(config.audio.speech.clone())
+            .context("Failed to create transcription manager")?;
+        
+        let hook_scripts = &config.audio.voice_commands.hook_scripts;
+        if !hook_scripts.is_empty() {
+            transcription_manager.add_text_processor(Arc::new(ScriptHooks::new(hook_scripts)));
+        }
+        Ok((transcription_manager, transcription_receiver))
+    }
+    
     /// Stop audio capture
     async fn stop_audio_capture(&mut self) {
         // Shutdown async tasks directly without creating a new runtime
@@ -305,7 +601,7 @@ impl App {
     /// Run the main menu
     async fn main_menu(&mut self) -> Result<()> {
         let mut input = String::new();
-        let devices = self.device_manager.get_input_devices();
+        let devices = self.device_manager.get_capture_sources();
         
         while self.running {
             println!("\nMain Menu:");
@@ -315,15 +611,15 @@ impl App {
             println!("3. Stop audio capture");
             println!("4. List audio devices");
             println!("5. Configure Whisper settings");
-            println!("6. Exit");
+            println!("6. Export a saved session");
+            println!("7. Exit");
             
             print!("> ");
             io::stdout().flush()?;
             
-            input.clear();
-            io::stdin().read_line(&mut input)?;
+            let choice = self.read_choice().await?;
             
-            match input.trim() {
+            match choice.trim() {
                 "1" => {
                     println!("Starting audio capture with default device...");
                     if let Err(e) = self.start_audio_capture(None).await {
@@ -373,6 +669,11 @@ impl App {
                     self.configure_whisper().await?;
                 },
                 "6" => {
+                    if let Err(e) = self.export_session() {
+                        error!("Failed to export session: {}", e);
+                    }
+                },
+                "7" => {
                     println!("Exiting...");
                     self.running = false;
                 },
@@ -385,6 +686,91 @@ impl App {
         Ok(())
     }
     
+    /// Read a menu choice, recovering capture while waiting for it
+    async fn read_choice(&mut self) -> Result<String> {
+        let mut line = tokio::task::spawn_blocking(|| {
+            let mut input = String::new();
+            io::stdin().read_line(&mut input).map(|_| input)
+        });
+        
+        loop {
+            tokio::select! {
+                input = &mut line => return Ok(input??),
+                Some(command) = self.recovery_receiver.recv() => self.recover_capture(command),
+            }
+        }
+    }
+    
+    /// Carry out a recovery request from the audio thread
+    fn recover_capture(&mut self, command: CaptureCommand) {
+        let capture_manager = match self.capture_manager.as_mut() {
+            Some(capture_manager) => capture_manager,
+            None => return,
+        };
+        
+        match command {
+            CaptureCommand::RecoverDevice => {
+                if let Err(e) = capture_manager.recover_from_device_loss() {
+                    error!("Failed to recover from device loss: {}", e);
+                }
+            },
+            CaptureCommand::RestartStream(reason) => {
+                if let Err(e) = capture_manager.restart_stalled_stream(&reason) {
+                    error!("Failed to restart capture stream: {}", e);
+                }
+            },
+            _ => {},
+        }
+    }
+    
+    /// Export a saved session to a file in the working directory
+    fn export_session(&self) -> Result<()> {
+        let history = TranscriptHistory::open(&TranscriptHistory::default_path()?)?;
+        let sessions = history.list_sessions()?;
+        if sessions.is_empty() {
+            println!("No saved sessions");
+            return Ok(());
+        }
+        
+        println!("Select a session:");
+        for (i, session) in sessions.iter().enumerate() {
+            let name = session.title.clone().unwrap_or_else(|| session.started.format("%Y-%m-%d %H:%M").to_string());
+            println!("{}. {} ({} segments)", i + 1, name, session.segments);
+        }
+        
+        let mut input = String::new();
+        print!("> ");
+        io::stdout().flush()?;
+        io::stdin().read_line(&mut input)?;
+        
+        let session = match input.trim().parse::<usize>().ok().and_then(|index| sessions.get(index.wrapping_sub(1))) {
+            Some(session) => session,
+            None => {
+                println!("Invalid session index");
+                return Ok(());
+            }
+        };
+        
+        print!("Format (md, json, docx, txt): ");
+        io::stdout().flush()?;
+        input.clear();
+        io::stdin().read_line(&mut input)?;
+        
+        let format = match ExportFormat::from_name(input.trim()) {
+            Some(format) => format,
+            None => {
+                println!("Unsupported format");
+                return Ok(());
+            }
+        };
+        
+        let path = std::path::PathBuf::from(format!("{}.{}", session.id, format.extension()));
+        export::export_session(&history.load_session(&session.id)?, format, &path)?;
+        println!("Exported to {}", path.display());
+        
+        Ok(())
+    }
+    
     /// Configure Whisper settings
     async fn configure_whisper(&mut self) -> Result<()> {
         let mut input = String::new();
@@ -402,6 +788,9 @@ impl App {
             println!("3. Small (balanced)");
             println!("4. Medium (more accurate)");
             println!("5. Large (most accurate)");
+            println!("6. Large v3 turbo (near large accuracy, much faster)");
+            println!("7. Distil small (English only, faster than small)");
+            println!("8. Distil medium (English only, near medium accuracy)");
             
             if let Some(path) = &config.audio.speech.model_path {
                 println!("Current model path: {}", path);
@@ -415,7 +804,7 @@ impl App {
         }
         
         // Get model size selection
-        print!("\nSelect model size (1-5) > ");
+        print!("\nSelect model size (1-8) > ");
         io::stdout().flush()?;
         
         input.clear();
@@ -427,6 +816,9 @@ impl App {
             "3" => crate::config::WhisperModelSize::Small,
             "4" => crate::config::WhisperModelSize::Medium,
             "5" => crate::config::WhisperModelSize::Large,
+            "6" => crate::config::WhisperModelSize::LargeV3Turbo,
+            "7" => crate::config::WhisperModelSize::DistilSmallEn,
+            "8" => crate::config::WhisperModelSize::DistilMediumEn,
             _ => {
                 println!("Invalid option, keeping current setting");
                 self.config_manager.get_config().audio.speech.model_size.clone()
@@ -472,7 +864,7 @@ impl App {
         
         // Get output format if saving is enabled
         let output_format = if save_transcription {
-            println!("\nOutput format (txt/json):");
+            println!("\nOutput format (txt/json/srt/vtt):");
             print!("> ");
             io::stdout().flush()?;
             
@@ -480,10 +872,9 @@ impl App {
             io::stdin().read_line(&mut input)?;
             
             let format = input.trim().to_lowercase();
-            if format == "json" {
-                "json".to_string()
-            } else {
-                "txt".to_string()
+            match format.as_str() {
+                "json" | "srt" | "vtt" => format,
+                _ => "txt".to_string(),
             }
         } else {
             "txt".to_string()
@@ -504,9 +895,7 @@ impl App {
         
         // Recreate transcription manager if it exists
         if self.transcription_manager.is_some() {
-            let speech_settings = self.config_manager.get_config().audio.speech.clone();
-            let (transcription_manager, transcription_receiver) = TranscriptionManager::new(speech_settings)
-                .context("Failed to create transcription manager")?;
+            let (transcription_manager, transcription_receiver) = self.create_transcription_manager()?;
             
             self.transcription_manager = Some(transcription_manager);
             self.transcription_receiver = Some(transcription_receiver);
@@ -526,6 +915,10 @@ impl App {
             let _ = capture_manager.stop();
         }
         
+        if let Some(reference_capture) = &mut self.reference_capture {
+            let _ = reference_capture.stop();
+        }
+        
         // Stop transcription if running
         if let Some(transcription_manager) = &mut self.transcription_manager {
             if let Err(e) = transcription_manager.stop().await {
@@ -560,8 +953,15 @@ impl App {
             }
         }
         
+        if let Some(task) = self.reference_task.take() {
+            if !task.is_finished() && tokio::time::timeout(std::time::Duration::from_secs(2), task).await.is_err() {
+                warn!("Echo reference task did not complete within timeout, will be aborted");
+            }
+        }
+        
         // Clean up remaining resources
         self.capture_manager = None;
+        self.reference_capture = None;
         
         info!("Async tasks shutdown complete");
         Ok(())
@@ -0,0 +1,227 @@
+use anyhow::Result;
+use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc as std_mpsc;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Default interval between checks of the focused window
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The window that has the focus, and the application it belongs to
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ActiveWindow {
+    /// Executable or process name of the application, e.g. "Code.exe"
+    pub app: String,
+
+    /// Title of the window
+    pub title: String,
+}
+
+impl ActiveWindow {
+    /// Whether the window belongs to `app`, an executable name or path
+    pub fn is_app(&self, app: &str) -> bool {
+        let app = executable_name(app);
+        !app.is_empty() && executable_name(&self.app) == app
+    }
+}
+
+/// An application's name as compared, without letter case, directories or an ".exe" or ".app" extension,
+/// so "Code", "code.exe" and "C:\Program Files\Code\Code.exe" are the same
+pub fn executable_name(name: &str) -> String {
+    let name = name.trim().rsplit(['/', '\\']).next().unwrap_or_default().to_lowercase();
+    [".exe", ".app"].iter()
+        .find_map(|extension| name.strip_suffix(extension))
+        .map(str::to_string)
+        .unwrap_or(name)
+}
+
+/// The window that has the focus now, if it can be found
+///
+/// Windows asks the window manager, macOS System Events (which needs the
+/// accessibility permission) and Linux `xdotool`, so X11 sessions only.
+pub fn active_window() -> Option<ActiveWindow> {
+    #[cfg(target_os = "windows")]
+    {
+        windows_foreground::active_window()
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let output = std::process::Command::new("osascript")
+            .args([
+                "-e", "tell application \"System Events\"",
+                "-e", "set proc to first process whose frontmost is true",
+                "-e", "set title to \"\"",
+                "-e", "try",
+                "-e", "set title to name of front window of proc",
+                "-e", "end try",
+                "-e", "return (name of proc) & linefeed & title",
+                "-e", "end tell",
+            ])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        let output = String::from_utf8_lossy(&output.stdout);
+        let (app, title) = output.trim_end().split_once('\n').unwrap_or((output.trim_end(), ""));
+        Some(ActiveWindow { app: app.to_string(), title: title.to_string() })
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let output = std::process::Command::new("xdotool")
+            .args(["getactivewindow", "getwindowpid", "getwindowname"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        let output = String::from_utf8_lossy(&output.stdout);
+        let mut lines = output.lines();
+        let pid = lines.next()?.trim();
+        let title = lines.next().unwrap_or_default();
+        let app = std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+        Some(ActiveWindow { app: app.trim().to_string(), title: title.to_string() })
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    {
+        None
+    }
+}
+
+/// Background watcher reporting each time another window gets the focus
+pub struct ActiveWindowWatcher {
+    /// Whether the watcher thread should keep running
+    running: Arc<AtomicBool>,
+
+    /// Wakes the watcher thread for shutdown
+    wake: std_mpsc::Sender<()>,
+
+    /// Watcher thread handle
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ActiveWindowWatcher {
+    /// Start watching the focused window, checking every `poll_interval`
+    ///
+    /// The window is sent on the returned channel once at the start and then
+    /// whenever the application or the title changes.
+    pub fn start(poll_interval: Duration) -> Result<(Self, mpsc::Receiver<ActiveWindow>)> {
+        let (event_sender, event_receiver) = mpsc::channel(16);
+        let (wake_sender, wake_receiver) = std_mpsc::channel::<()>();
+        let running = Arc::new(AtomicBool::new(true));
+
+        let thread_running = running.clone();
+        let thread = std::thread::Builder::new()
+            .name("active-window-watcher".to_string())
+            .spawn(move || {
+                info!("Active window watcher started");
+                let mut known: Option<ActiveWindow> = None;
+
+                while thread_running.load(Ordering::SeqCst) {
+                    if let Some(window) = active_window().filter(|window| known.as_ref() != Some(window)) {
+                        debug!("Focus moved to {} ({})", window.app, window.title);
+                        known = Some(window.clone());
+                        if event_sender.blocking_send(window).is_err() {
+                            // Nobody is listening any more
+                            break;
+                        }
+                    }
+
+                    match wake_receiver.recv_timeout(poll_interval) {
+                        Err(std_mpsc::RecvTimeoutError::Timeout) => {}
+                        Ok(()) | Err(std_mpsc::RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+
+                info!("Active window watcher stopped");
+            })?;
+
+        Ok((
+            Self {
+                running,
+                wake: wake_sender,
+                thread: Some(thread),
+            },
+            event_receiver,
+        ))
+    }
+
+    /// Stop the watcher and wait for its thread to exit
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        let _ = self.wake.send(());
+
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                error!("Active window watcher thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for ActiveWindowWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows_foreground {
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId};
+
+    use super::ActiveWindow;
+
+    /// The foreground window, with the file name of the program that owns it
+    pub fn active_window() -> Option<ActiveWindow> {
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            if hwnd.0 == 0 {
+                return None;
+            }
+
+            let mut buffer = [0u16; 512];
+            let len = GetWindowTextW(hwnd, &mut buffer).max(0) as usize;
+            let title = String::from_utf16_lossy(&buffer[..len]);
+
+            let mut pid = 0;
+            GetWindowThreadProcessId(hwnd, Some(&mut pid));
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+            let mut path = [0u16; 1024];
+            let mut len = path.len() as u32;
+            let found = QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, PWSTR(path.as_mut_ptr()), &mut len).as_bool();
+            CloseHandle(process);
+            if !found {
+                return None;
+            }
+
+            let path = String::from_utf16_lossy(&path[..len as usize]);
+            let app = path.rsplit('\\').next().unwrap_or_default().to_string();
+            Some(ActiveWindow { app, title })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compares_applications_by_executable() {
+        let window = ActiveWindow { app: "Code.exe".to_string(), title: "main.rs - bestme".to_string() };
+        assert!(window.is_app("code"));
+        assert!(window.is_app(r"C:\Program Files\Microsoft VS Code\Code.exe"));
+        assert!(!window.is_app("firefox.exe"));
+        assert!(!window.is_app(""));
+
+        assert_eq!(executable_name("/Applications/Safari.app"), "safari");
+        assert_eq!(executable_name(" firefox "), "firefox");
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::VecDeque;
+
+use log::{debug, warn};
+
+/// Adaptation step size for the NLMS filter
+const NLMS_STEP_SIZE: f32 = 0.5;
+
+/// Regularization term to avoid division by zero on silent reference audio
+const NLMS_REGULARIZATION: f32 = 1e-6;
+
+/// Geigel double-talk threshold: near-end speech is assumed when the microphone
+/// is louder than this fraction of the recent reference peak
+const DOUBLE_TALK_THRESHOLD: f32 = 0.5;
+
+/// Maximum reference audio kept ahead of the microphone (in filter lengths)
+const MAX_REFERENCE_BACKLOG: usize = 8;
+
+/// Acoustic echo canceller based on a normalized LMS adaptive filter
+///
+/// The loopback (speaker) signal is used as the reference. The filter learns
+/// the echo path from the speakers to the microphone and subtracts the
+/// estimated echo from the microphone signal. Both signals must be mono at
+/// the same sample rate.
+pub struct EchoCanceller {
+    /// Adaptive filter coefficients
+    weights: Vec<f32>,
+
+    /// Most recent reference samples, newest first
+    history: VecDeque<f32>,
+
+    /// Reference samples waiting to be aligned with microphone samples
+    reference_queue: VecDeque<f32>,
+
+    /// Running energy of the reference history
+    history_energy: f32,
+
+    /// Candidates for the peak of the reference history as (sample number, magnitude),
+    /// oldest first with falling magnitudes, so the front is the current peak
+    peaks: VecDeque<(u64, f32)>,
+
+    /// Number of reference samples shifted into the history so far
+    shifted: u64,
+
+    /// Whether adaptation is currently frozen because of double talk
+    double_talk: bool,
+}
+
+impl EchoCanceller {
+    /// Create a new echo canceller
+    ///
+    /// `filter_ms` is the longest echo tail that can be cancelled.
+    pub fn new(sample_rate: u32, filter_ms: u32) -> Self {
+        let taps = ((sample_rate as u64 * filter_ms.max(1) as u64) / 1000).max(1) as usize;
+        debug!("Creating echo canceller with {} taps", taps);
+
+        Self {
+            weights: vec![0.0; taps],
+            history: std::iter::repeat(0.0).take(taps).collect(),
+            reference_queue: VecDeque::with_capacity(taps * 2),
+            history_energy: 0.0,
+            peaks: VecDeque::new(),
+            shifted: 0,
+            double_talk: false,
+        }
+    }
+
+    /// Queue reference (loopback) samples
+    pub fn push_reference(&mut self, samples: &[f32]) {
+        self.reference_queue.extend(samples.iter().copied());
+
+        // If the microphone stream stalls, drop the oldest reference audio
+        let max_backlog = self.weights.len() * MAX_REFERENCE_BACKLOG;
+        if self.reference_queue.len() > max_backlog {
+            let excess = self.reference_queue.len() - max_backlog;
+            warn!("Echo reference backlog too large, dropping {} samples", excess);
+            self.reference_queue.drain(..excess);
+        }
+    }
+
+    /// Remove echo from microphone samples
+    pub fn process(&mut self, mic: &[f32]) -> Vec<f32> {
+        let mut output = Vec::with_capacity(mic.len());
+
+        for &sample in mic {
+            // Shift the next reference sample into the history
+            let reference = self.reference_queue.pop_front().unwrap_or(0.0);
+            if let Some(oldest) = self.history.pop_back() {
+                self.history_energy -= oldest * oldest;
+            }
+            self.history.push_front(reference);
+            self.history_energy = (self.history_energy + reference * reference).max(0.0);
+            self.track_peak(reference);
+
+            // Estimate the echo and subtract it
+            let estimate: f32 = self.weights.iter()
+                .zip(self.history.iter())
+                .map(|(w, x)| w * x)
+                .sum();
+            let error = sample - estimate;
+
+            // Freeze adaptation while the near end is talking
+            let reference_peak = self.peaks.front().map_or(0.0, |&(_, peak)| peak);
+            self.double_talk = reference_peak > 0.0 && sample.abs() > DOUBLE_TALK_THRESHOLD * reference_peak;
+
+            if !self.double_talk && self.history_energy > NLMS_REGULARIZATION {
+                let step = NLMS_STEP_SIZE * error / (self.history_energy + NLMS_REGULARIZATION);
+                for (w, x) in self.weights.iter_mut().zip(self.history.iter()) {
+                    *w += step * x;
+                }
+            }
+
+            output.push(error.clamp(-1.0, 1.0));
+        }
+
+        output
+    }
+
+    /// Update the history's peak for a newly shifted-in reference sample, in amortized constant time
+    fn track_peak(&mut self, reference: f32) {
+        let magnitude = reference.abs();
+        while self.peaks.back().is_some_and(|&(_, peak)| peak <= magnitude) {
+            self.peaks.pop_back();
+        }
+        self.peaks.push_back((self.shifted, magnitude));
+        self.shifted += 1;
+
+        // Samples older than the filter length have left the history
+        let taps = self.weights.len() as u64;
+        while self.peaks.front().is_some_and(|&(number, _)| number + taps < self.shifted) {
+            self.peaks.pop_front();
+        }
+    }
+
+    /// Check if double talk was detected on the last processed sample
+    pub fn is_double_talk(&self) -> bool {
+        self.double_talk
+    }
+
+    /// Reset the learned echo path and discard queued reference audio
+    pub fn reset(&mut self) {
+        self.weights.iter_mut().for_each(|w| *w = 0.0);
+        self.history.iter_mut().for_each(|x| *x = 0.0);
+        self.reference_queue.clear();
+        self.history_energy = 0.0;
+        self.peaks.clear();
+        self.double_talk = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random noise in [-0.5, 0.5)
+    fn noise(len: usize, seed: u32) -> Vec<f32> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                ((state >> 16) & 0x7fff) as f32 / 32768.0 - 0.5
+            })
+            .collect()
+    }
+
+    fn energy(samples: &[f32]) -> f32 {
+        samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32
+    }
+
+    #[test]
+    fn test_echo_is_cancelled() {
+        let mut aec = EchoCanceller::new(16000, 4);
+        let reference = noise(32000, 7);
+
+        // Microphone hears a delayed, attenuated copy of the speakers
+        let delay = 10;
+        let mic: Vec<f32> = (0..reference.len())
+            .map(|i| if i >= delay { reference[i - delay] * 0.3 } else { 0.0 })
+            .collect();
+
+        // Feed both streams in 10 ms chunks like the capture pipeline does
+        let mut output = Vec::with_capacity(mic.len());
+        for (ref_chunk, mic_chunk) in reference.chunks(160).zip(mic.chunks(160)) {
+            aec.push_reference(ref_chunk);
+            output.extend(aec.process(mic_chunk));
+        }
+
+        // After convergence the residual echo should be much quieter than the input
+        let tail = reference.len() - 4000;
+        assert!(energy(&output[tail..]) < energy(&mic[tail..]) * 0.01);
+    }
+
+    #[test]
+    fn test_reference_peak_follows_the_history() {
+        let mut aec = EchoCanceller::new(1000, 4);
+        let reference = noise(200, 11);
+        for (i, &sample) in reference.iter().enumerate() {
+            aec.push_reference(&[sample]);
+            aec.process(&[0.0]);
+            let window = &reference[(i + 1).saturating_sub(4)..=i];
+            let expected = window.iter().fold(0.0f32, |peak, x| peak.max(x.abs()));
+            assert_eq!(aec.peaks.front().map_or(0.0, |&(_, peak)| peak), expected);
+        }
+    }
+
+    #[test]
+    fn test_passthrough_without_reference() {
+        let mut aec = EchoCanceller::new(16000, 4);
+        let mic = noise(1600, 3);
+
+        assert_eq!(aec.process(&mic), mic);
+    }
+}
@@ -0,0 +1,195 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+/// An application voice commands can open
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LaunchableApp {
+    /// Name said to open it, e.g. "notepad"
+    pub name: String,
+
+    /// Program to run
+    pub command: String,
+
+    /// Arguments given to the program
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// Settings for voice commands opening applications and switching windows
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppControlSettings {
+    /// Whether "open ..." and "switch to ..." are understood; they are only heard after the command prefix or wake word
+    pub enabled: bool,
+
+    /// Applications that can be opened, by name
+    pub applications: Vec<LaunchableApp>,
+}
+
+impl AppControlSettings {
+    /// The application called `name`, without regard to letter case or punctuation
+    pub fn find(&self, name: &str) -> Option<&LaunchableApp> {
+        let name = normalize(name);
+        self.applications.iter().find(|app| normalize(&app.name) == name)
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Opens applications and brings windows to the front for voice commands
+pub trait AppControl: Send {
+    /// Start an application
+    fn launch(&self, app: &LaunchableApp) -> Result<()> {
+        Command::new(&app.command)
+            .args(&app.args)
+            .spawn()
+            .with_context(|| format!("Failed to open {} ({})", app.name, app.command))?;
+        Ok(())
+    }
+
+    /// Bring the first window whose title contains `title` to the front
+    fn switch_to(&self, title: &str) -> Result<()>;
+}
+
+/// App control for the platform the application runs on
+pub fn platform_app_control() -> Box<dyn AppControl> {
+    Box::new(PlatformAppControl)
+}
+
+/// Opens applications and switches windows with the platform's own tools
+///
+/// Switching windows uses the window manager on Windows, System Events on
+/// macOS (which needs the accessibility permission) and `wmctrl` on Linux,
+/// so X11 sessions only.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlatformAppControl;
+
+impl AppControl for PlatformAppControl {
+    fn switch_to(&self, title: &str) -> Result<()> {
+        #[cfg(target_os = "windows")]
+        {
+            windows_focus::switch_to(title)
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            // Window titles are passed as arguments rather than written into the script
+            let output = Command::new("osascript")
+                .args([
+                    "-e", "on run argv",
+                    "-e", "tell application \"System Events\"",
+                    "-e", "repeat with proc in (processes whose background only is false)",
+                    "-e", "repeat with win in windows of proc",
+                    "-e", "if name of win contains (item 1 of argv) then",
+                    "-e", "set frontmost of proc to true",
+                    "-e", "perform action \"AXRaise\" of win",
+                    "-e", "return",
+                    "-e", "end if",
+                    "-e", "end repeat",
+                    "-e", "end repeat",
+                    "-e", "end tell",
+                    "-e", "error \"No window found\"",
+                    "-e", "end run",
+                    title,
+                ])
+                .output()
+                .context("Failed to run osascript")?;
+            if !output.status.success() {
+                anyhow::bail!("No window titled {}: {}", title, String::from_utf8_lossy(&output.stderr).trim());
+            }
+            Ok(())
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            let status = Command::new("wmctrl")
+                .args(["-a", title])
+                .status()
+                .context("Failed to run wmctrl; install it to switch windows by voice")?;
+            if !status.success() {
+                anyhow::bail!("No window titled {}", title);
+            }
+            Ok(())
+        }
+
+        #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+        {
+            anyhow::bail!("Switching to {} isn't supported on this platform", title)
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows_focus {
+    use windows::Win32::Foundation::{BOOL, HWND, LPARAM};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        EnumWindows, GetWindowTextW, IsIconic, IsWindowVisible, SetForegroundWindow, ShowWindow, SW_RESTORE,
+    };
+
+    /// The window being looked for, passed through `EnumWindows`
+    struct Search {
+        title: String,
+        found: Option<HWND>,
+    }
+
+    unsafe extern "system" fn visit(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let search = &mut *(lparam.0 as *mut Search);
+        if !IsWindowVisible(hwnd).as_bool() {
+            return true.into();
+        }
+
+        let mut buffer = [0u16; 512];
+        let len = GetWindowTextW(hwnd, &mut buffer).max(0) as usize;
+        if String::from_utf16_lossy(&buffer[..len]).to_lowercase().contains(&search.title) {
+            search.found = Some(hwnd);
+            return false.into();
+        }
+        true.into()
+    }
+
+    /// Bring the first visible top-level window whose title contains `title` to the front
+    pub fn switch_to(title: &str) -> anyhow::Result<()> {
+        let mut search = Search { title: title.to_lowercase(), found: None };
+        unsafe {
+            // Stopping early reports failure, so the result says nothing about the search
+            let _ = EnumWindows(Some(visit), LPARAM(&mut search as *mut Search as isize));
+            let hwnd = search.found.ok_or_else(|| anyhow::anyhow!("No window titled {}", title))?;
+
+            if IsIconic(hwnd).as_bool() {
+                ShowWindow(hwnd, SW_RESTORE);
+            }
+            if !SetForegroundWindow(hwnd).as_bool() {
+                anyhow::bail!("Failed to switch to {}", title);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finds_applications_by_spoken_name() {
+        let settings = AppControlSettings {
+            enabled: true,
+            applications: vec![LaunchableApp {
+                name: "Visual Studio Code".to_string(),
+                command: "code".to_string(),
+                args: Vec::new(),
+            }],
+        };
+
+        assert_eq!(settings.find("visual studio code.").map(|app| app.command.as_str()), Some("code"));
+        assert_eq!(settings.find("Visual  Studio, Code").map(|app| app.command.as_str()), Some("code"));
+        assert!(settings.find("visual studio").is_none());
+    }
+}
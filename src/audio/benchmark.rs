@@ -0,0 +1,237 @@
+use anyhow::Result;
+use log::{info, warn};
+use serde::Serialize;
+use std::path::Path;
+use std::time::Instant;
+
+use super::engine::{self, SttEngine, TranscribeOptions};
+use super::file::decode_audio_file;
+use super::resample::resample_once;
+use crate::config::ResamplerQuality;
+
+/// Sample rate engines are given
+const SAMPLE_RATE: u32 = 16000;
+
+/// Audio to benchmark models with, and what is said in it if known
+#[derive(Debug, Clone)]
+pub struct BenchmarkSample {
+    /// 16 kHz mono samples
+    pub samples: Vec<f32>,
+
+    /// Transcript of the sample, used to score accuracy
+    pub reference: Option<String>,
+}
+
+impl BenchmarkSample {
+    /// Decode a sample from an audio file
+    ///
+    /// Without a reference, a transcript next to the file with a `.txt`
+    /// extension is used if there is one.
+    pub fn load(path: &Path, reference: Option<String>) -> Result<Self> {
+        let decoded = decode_audio_file(path)?;
+        let samples = resample_once(&decoded.samples, decoded.sample_rate, SAMPLE_RATE, &ResamplerQuality::High)?;
+
+        let reference = reference
+            .or_else(|| std::fs::read_to_string(path.with_extension("txt")).ok())
+            .filter(|text| !text.trim().is_empty());
+
+        Ok(Self { samples, reference })
+    }
+
+    /// Length of the sample in seconds
+    pub fn duration_secs(&self) -> f32 {
+        self.samples.len() as f32 / SAMPLE_RATE as f32
+    }
+}
+
+/// How one model did on a sample
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkResult {
+    /// Model name, e.g. "small-q8_0"
+    pub model: String,
+
+    /// Time taken to load the model, in milliseconds
+    pub load_ms: f32,
+
+    /// Time taken to transcribe the sample, in milliseconds
+    pub inference_ms: f32,
+
+    /// Inference time over sample length; above 1.0 the model can't keep up with live speech
+    pub real_time_factor: f32,
+
+    /// Share of the reference's words the model got wrong, when the sample has a reference
+    pub word_error_rate: Option<f32>,
+
+    /// The model's own confidence in its transcript, a rough accuracy proxy without a reference
+    pub confidence: Option<f32>,
+
+    /// What the model heard
+    pub text: String,
+
+    /// Why the model couldn't be benchmarked, if it couldn't
+    pub error: Option<String>,
+}
+
+impl BenchmarkResult {
+    fn failed(model: &str, error: anyhow::Error) -> Self {
+        Self {
+            model: model.to_string(),
+            load_ms: 0.0,
+            inference_ms: 0.0,
+            real_time_factor: 0.0,
+            word_error_rate: None,
+            confidence: None,
+            text: String::new(),
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// Load an engine, transcribe the sample with it and unload it again
+///
+/// Blocks for as long as loading and transcription take. A second of silence
+/// is transcribed first, so buffer allocation doesn't count against the model.
+pub fn run_benchmark(
+    model: &str,
+    engine: &mut dyn SttEngine,
+    sample: &BenchmarkSample,
+    options: &TranscribeOptions,
+) -> BenchmarkResult {
+    info!("Benchmarking {} on {:.1}s of audio", model, sample.duration_secs());
+
+    let load_start = Instant::now();
+    if let Err(e) = engine.load() {
+        warn!("Failed to load {} for benchmarking: {}", model, e);
+        return BenchmarkResult::failed(model, e);
+    }
+    let load_ms = load_start.elapsed().as_secs_f32() * 1000.0;
+
+    if let Err(e) = engine::warm_up(engine, options) {
+        warn!("Failed to warm up {}: {}", model, e);
+    }
+
+    let inference_start = Instant::now();
+    let transcribed = engine.transcribe(&sample.samples, options);
+    let inference_secs = inference_start.elapsed().as_secs_f32();
+    engine.unload();
+
+    let segments = match transcribed {
+        Ok(segments) => segments,
+        Err(e) => return BenchmarkResult::failed(model, e),
+    };
+    let text = engine::segments_text(&segments);
+
+    BenchmarkResult {
+        model: model.to_string(),
+        load_ms,
+        inference_ms: inference_secs * 1000.0,
+        real_time_factor: inference_secs / sample.duration_secs().max(f32::EPSILON),
+        word_error_rate: sample.reference.as_deref().map(|reference| word_error_rate(reference, &text)),
+        confidence: engine::segments_confidence(&segments),
+        text,
+        error: None,
+    }
+}
+
+/// Word error rate of a transcript: substituted, missing and extra words over the reference's words
+///
+/// Words are compared without case or punctuation. The rate can exceed 1.0
+/// when the transcript adds many words.
+pub fn word_error_rate(reference: &str, hypothesis: &str) -> f32 {
+    let reference = words(reference);
+    let hypothesis = words(hypothesis);
+    if reference.is_empty() {
+        return if hypothesis.is_empty() { 0.0 } else { 1.0 };
+    }
+
+    // Edit distance over words, one row at a time
+    let mut previous: Vec<usize> = (0..=hypothesis.len()).collect();
+    for (i, expected) in reference.iter().enumerate() {
+        let mut current = vec![i + 1; hypothesis.len() + 1];
+        for (j, heard) in hypothesis.iter().enumerate() {
+            let substitution = previous[j] + usize::from(expected != heard);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[hypothesis.len()] as f32 / reference.len() as f32
+}
+
+/// Lowercase words without punctuation
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| word.chars().filter(|c| c.is_alphanumeric() || *c == '\'').flat_map(char::to_lowercase).collect::<String>())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::engine::TranscriptSegment;
+    use std::time::Duration;
+
+    #[test]
+    fn test_word_error_rate() {
+        assert_eq!(word_error_rate("Hello there, world.", "hello there world"), 0.0);
+        assert_eq!(word_error_rate("the cat sat down", "the cat sat"), 0.25);
+        assert_eq!(word_error_rate("the cat sat down", "a cat sat down now"), 0.5);
+        assert_eq!(word_error_rate("", ""), 0.0);
+        assert_eq!(word_error_rate("", "noise"), 1.0);
+    }
+
+    /// Engine that hears a fixed text
+    struct FixedEngine {
+        loaded: bool,
+    }
+
+    impl SttEngine for FixedEngine {
+        fn name(&self) -> &'static str {
+            "fixed"
+        }
+
+        fn load(&mut self) -> Result<()> {
+            self.loaded = true;
+            Ok(())
+        }
+
+        fn is_loaded(&self) -> bool {
+            self.loaded
+        }
+
+        fn transcribe(&self, _audio: &[f32], _options: &TranscribeOptions) -> Result<Vec<TranscriptSegment>> {
+            Ok(vec![TranscriptSegment {
+                start: Duration::ZERO,
+                end: Duration::from_secs(1),
+                text: " The quick brown fox.".to_string(),
+                confidence: Some(0.8),
+                language: None,
+                no_speech_probability: None,
+            }])
+        }
+
+        fn unload(&mut self) {
+            self.loaded = false;
+        }
+    }
+
+    #[test]
+    fn test_benchmark_scores_against_reference() {
+        let sample = BenchmarkSample {
+            samples: vec![0.0; 32000],
+            reference: Some("The quick brown fox jumps.".to_string()),
+        };
+        let mut engine = FixedEngine { loaded: false };
+
+        let result = run_benchmark("fixed", &mut engine, &sample, &TranscribeOptions::default());
+        assert_eq!(result.error, None);
+        assert_eq!(result.text, "The quick brown fox.");
+        assert_eq!(result.word_error_rate, Some(0.2));
+        assert_eq!(result.confidence, Some(0.8));
+        assert!(result.real_time_factor >= 0.0);
+
+        // The model doesn't stay in memory
+        assert!(!engine.is_loaded());
+    }
+}
@@ -1,21 +1,94 @@
 use anyhow::Result;
 use cpal::traits::{HostTrait, DeviceTrait, StreamTrait};
 use log::{debug, error, info, warn};
+use ringbuf::{HeapConsumer, HeapRb};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 use parking_lot::Mutex;
 use tokio::sync::mpsc;
 
 use super::AudioConfig;
+use super::clock::{ClockDrift, DriftTracker};
+use super::latency;
+use super::meter::{AudioMetrics, LevelMeter};
+use super::mixer::AudioMixer;
+use super::network::NetworkSource;
+use super::overflow::EventOutbox;
+use super::permission;
+use super::preroll::PreRollBuffer;
+use super::resample::{resample_once, StreamResampler};
+use super::simd::{self, downmix};
+use super::source::{AudioSource, DataCallback, ErrorCallback, SourceError, SourceFormat, SourceHandle};
+use crate::config::{MixMode, ResamplerQuality};
+use super::device::{default_input_id, find_device_by_id, is_loopback_id, loopback_supported};
+#[cfg(target_os = "linux")]
+use super::device::pulse_source_name;
+use super::vad::{VadTransition, VoiceActivityDetector};
+use super::watchdog::{StallReason, StreamWatchdog};
+use super::waveform::{WaveformAggregator, WaveformBucket};
 
-/// Size of the ring buffer for audio samples
-#[allow(dead_code)]
+/// Minimum size of the ring buffer between the audio callback and the consumer thread
 const RING_BUFFER_SIZE: usize = 16 * 1024;
 
+/// Audio the ring buffer can hold before the callback starts dropping samples
+const RING_BUFFER_MS: usize = 500;
+
+/// How long the consumer thread sleeps when no audio has arrived
+const CONSUMER_IDLE_TIMEOUT: Duration = Duration::from_millis(20);
+
+/// How often measured clock drift is reported
+const DRIFT_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Maximum input gain that can be applied to captured audio
+pub const MAX_INPUT_VOLUME: f32 = 2.0;
+
+/// Level above which the soft limiter starts compressing samples
+const SOFT_CLIP_THRESHOLD: f32 = 0.9;
+
+/// How far one device may run ahead of the others before the mixer pads with silence
+const MIXER_MAX_BACKLOG_MS: u32 = 200;
+
+/// Callback slot shared with the consumer thread so it can be replaced while capturing
+type SharedCallback<T> = Arc<Mutex<Option<Arc<dyn Fn(T) + Send + Sync + 'static>>>>;
+
+/// Soft clip a sample to avoid harsh distortion
+#[inline]
+fn soft_clip(value: f32) -> f32 {
+    let magnitude = value.abs();
+    
+    if magnitude <= SOFT_CLIP_THRESHOLD {
+        return value;
+    }
+    
+    // Smoothly approach 1.0 instead of hard clipping
+    let headroom = 1.0 - SOFT_CLIP_THRESHOLD;
+    let limited = SOFT_CLIP_THRESHOLD + headroom * ((magnitude - SOFT_CLIP_THRESHOLD) / headroom).tanh();
+    limited.copysign(value)
+}
+
+/// Apply gain to a block of samples with soft clipping
+fn apply_gain(samples: &[f32], gain: f32) -> Vec<f32> {
+    let mut output = Vec::with_capacity(samples.len());
+    
+    // Most blocks never reach the limiter, so only walk them again when one does
+    if simd::scale(samples, gain, &mut output) > SOFT_CLIP_THRESHOLD {
+        output.iter_mut().for_each(|sample| *sample = soft_clip(*sample));
+    }
+    
+    output
+}
+
 /// Audio event types that can be emitted by the capture system
 #[derive(Debug, Clone)]
 pub enum AudioEvent {
     /// Audio level update (peak level between 0.0 and 1.0)
     Level(f32),
+    /// Level metrics (RMS, loudness, clipping), sent at the configured metering rate
+    Metrics(AudioMetrics),
+    /// Min/max waveform buckets for visualization
+    Waveform(Vec<WaveformBucket>),
     /// Audio data received
     Data(AudioData),
     /// Error occurred
@@ -26,6 +99,31 @@ pub enum AudioEvent {
     Started,
     /// Legacy name for level changes (for compatibility)
     LevelChanged(f32),
+    /// Voice activity detected after a period of silence
+    SpeechStart,
+    /// Voice activity ended (after the VAD hangover period)
+    SpeechEnd,
+    /// Capture moved to another device after the active one disconnected
+    DeviceChanged {
+        /// ID of the device that was lost (None if it was the default device)
+        previous: Option<String>,
+        /// ID of the device now being captured
+        current: Option<String>,
+    },
+    /// Capture was restarted after the stream stalled or failed
+    StreamRecovered {
+        /// Why the stream was rebuilt
+        reason: String,
+    },
+    /// Device clock drift measured against the monotonic clock
+    ClockDrift(ClockDrift),
+    /// Audio frames were discarded because processing fell behind
+    FramesDropped {
+        /// Frames dropped since the last report
+        dropped: u64,
+        /// Frames dropped since capture started
+        total: u64,
+    },
 }
 
 /// Audio data structure
@@ -39,6 +137,12 @@ pub struct AudioData {
     
     /// Number of channels
     channels: u16,
+    
+    /// ID of the device this audio came from, when sources are delivered as separate tracks
+    source: Option<String>,
+    
+    /// Time of the first sample since the stream opened, corrected for device clock drift
+    timestamp: Option<Duration>,
 }
 
 impl AudioData {
@@ -48,9 +152,33 @@ impl AudioData {
             samples,
             sample_rate,
             channels,
+            source: None,
+            timestamp: None,
         }
     }
     
+    /// Set the capture time of the first sample
+    pub fn with_timestamp(mut self, timestamp: Duration) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+    
+    /// Capture time of the first sample since the stream opened (None for audio not from a capture stream)
+    pub fn timestamp(&self) -> Option<Duration> {
+        self.timestamp
+    }
+    
+    /// Tag the audio with the device it came from
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+    
+    /// ID of the device this audio came from (None if sources were mixed)
+    pub fn source_id(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+    
     /// Get audio samples
     pub fn get_samples(&self) -> &[f32] {
         &self.samples
@@ -66,43 +194,29 @@ impl AudioData {
         self.channels
     }
     
+    /// Downmix to mono by averaging channels
+    pub fn to_mono(&self) -> Vec<f32> {
+        downmix(&self.samples, self.channels)
+    }
+    
     /// Convert to mono and resample to target sample rate if needed
+    ///
+    /// This resamples the buffer in isolation; use `StreamResampler` for
+    /// continuous streams to avoid artifacts at chunk boundaries.
     pub fn to_whisper_input(&self, target_sample_rate: u32) -> Vec<f32> {
-        let mut result = Vec::with_capacity(self.samples.len());
+        let mono = self.to_mono();
         
-        // If stereo, convert to mono by averaging channels
-        if self.channels == 2 {
-            for i in 0..(self.samples.len() / 2) {
-                let mono_sample = (self.samples[i * 2] + self.samples[i * 2 + 1]) / 2.0;
-                result.push(mono_sample);
-            }
-        } else {
-            // Already mono
-            result = self.samples.clone();
+        if self.sample_rate == target_sample_rate {
+            return mono;
         }
         
-        // Simple resampling if needed (this is a basic implementation)
-        // For production, use a proper resampling library
-        if self.sample_rate != target_sample_rate {
-            // Basic linear interpolation for resampling
-            let ratio = self.sample_rate as f32 / target_sample_rate as f32;
-            let target_len = (result.len() as f32 / ratio) as usize;
-            let mut resampled = Vec::with_capacity(target_len);
-            
-            for i in 0..target_len {
-                let src_idx = i as f32 * ratio;
-                let src_idx_floor = src_idx.floor() as usize;
-                let src_idx_ceil = (src_idx_floor + 1).min(result.len() - 1);
-                let t = src_idx - src_idx_floor as f32;
-                
-                let sample = result[src_idx_floor] * (1.0 - t) + result[src_idx_ceil] * t;
-                resampled.push(sample);
+        match resample_once(&mono, self.sample_rate, target_sample_rate, &ResamplerQuality::default()) {
+            Ok(resampled) => resampled,
+            Err(e) => {
+                error!("Failed to resample audio: {}", e);
+                Vec::new()
             }
-            
-            return resampled;
         }
-        
-        result
     }
     
     /// Get an iterator over the samples
@@ -119,21 +233,72 @@ pub struct CaptureManager {
     /// Audio configuration
     config: AudioConfig,
     
-    /// Current audio stream
-    audio_stream: Option<cpal::Stream>,
+    /// Where the main audio comes from (a cpal device unless replaced for testing)
+    source: Box<dyn AudioSource>,
+    
+    /// Keeps the main source running
+    audio_stream: Option<SourceHandle>,
+    
+    /// Streams for additional devices mixed into the main stream
+    secondary_streams: Vec<SourceHandle>,
+    
+    /// Thread that drains the ring buffers and processes captured audio
+    consumer_thread: Option<JoinHandle<()>>,
+    
+    /// Whether the consumer thread should keep running
+    consumer_running: Arc<AtomicBool>,
+    
+    /// Audio frames dropped since capture started
+    dropped_frames: Arc<AtomicU64>,
     
     /// Peak audio level (for visualization)
     peak_level: Arc<Mutex<f32>>,
     
-    /// Callback for peak level updates (use Arc to make it clonable)
-    peak_level_callback: Option<Arc<dyn Fn(f32) + Send + Sync + 'static>>,
+    /// Most recent level metrics
+    metrics: Arc<Mutex<AudioMetrics>>,
+    
+    /// Device clock measured against the monotonic clock (shared with the audio callback)
+    clock: Arc<Mutex<DriftTracker>>,
+    
+    /// Input gain applied to captured samples (shared with the audio callback)
+    input_volume: Arc<Mutex<f32>>,
+    
+    /// Callback for peak level updates (shared so it can change while streams are open)
+    peak_level_callback: SharedCallback<f32>,
     
-    /// Callback for audio data (use Arc to make it clonable)
-    audio_data_callback: Option<Arc<dyn Fn(AudioData) + Send + Sync + 'static>>,
+    /// Callback for audio data (shared so it can change while streams are open)
+    audio_data_callback: SharedCallback<AudioData>,
     
-    /// Flag indicating if recording is active
+    /// Callback for recorded audio before voice activity gating (shared like the others)
+    raw_audio_callback: SharedCallback<AudioData>,
+    
+    /// Flag indicating if the capture streams are open
     is_recording: bool,
     
+    /// Whether captured audio is delivered (false while only filling the pre-roll)
+    forwarding: Arc<AtomicBool>,
+    
+    /// Whether capture returns to pre-roll instead of closing when recording stops
+    preroll_armed: bool,
+    
+    /// Set by the stream error callback when the capture device disappears
+    device_lost: Arc<AtomicBool>,
+    
+    /// Called from the audio thread when the capture device disappears
+    device_lost_callback: Option<Arc<dyn Fn() + Send + Sync + 'static>>,
+    
+    /// Incremented by the audio callback on every buffer
+    heartbeat: Arc<AtomicU64>,
+    
+    /// Set by the stream error callback for errors that need a stream rebuild
+    stream_fault: Arc<AtomicBool>,
+    
+    /// Watches the running stream for stalls
+    watchdog: Option<StreamWatchdog>,
+    
+    /// Called from the watchdog thread when the stream stalls
+    stall_callback: Option<Arc<dyn Fn(StallReason) + Send + Sync + 'static>>,
+    
     /// Sender for audio events
     event_sender: mpsc::Sender<AudioEvent>,
 }
@@ -141,222 +306,761 @@ pub struct CaptureManager {
 impl CaptureManager {
     /// Create a new capture manager
     pub fn new() -> Result<(Self, mpsc::Receiver<AudioEvent>)> {
+        Self::with_source(Box::new(CpalSource::default()))
+    }
+    
+    /// Create a capture manager that reads its main audio from `source`
+    ///
+    /// Used to replay WAV fixtures or synthetic signals instead of a device.
+    /// Additional devices are still opened through cpal.
+    pub fn with_source(source: Box<dyn AudioSource>) -> Result<(Self, mpsc::Receiver<AudioEvent>)> {
         // Create a channel for audio events
         let (event_sender, event_receiver) = mpsc::channel(100);
         
         let manager = Self {
             config: AudioConfig::default(),
+            source,
             audio_stream: None,
+            secondary_streams: Vec::new(),
+            consumer_thread: None,
+            consumer_running: Arc::new(AtomicBool::new(false)),
+            dropped_frames: Arc::new(AtomicU64::new(0)),
             peak_level: Arc::new(Mutex::new(0.0)),
-            peak_level_callback: None,
-            audio_data_callback: None,
+            metrics: Arc::new(Mutex::new(AudioMetrics::default())),
+            clock: Arc::new(Mutex::new(DriftTracker::new(AudioConfig::default().sample_rate))),
+            input_volume: Arc::new(Mutex::new(1.0)),
+            peak_level_callback: Arc::new(Mutex::new(None)),
+            audio_data_callback: Arc::new(Mutex::new(None)),
+            raw_audio_callback: Arc::new(Mutex::new(None)),
             is_recording: false,
+            forwarding: Arc::new(AtomicBool::new(false)),
+            preroll_armed: false,
+            device_lost: Arc::new(AtomicBool::new(false)),
+            device_lost_callback: None,
+            heartbeat: Arc::new(AtomicU64::new(0)),
+            stream_fault: Arc::new(AtomicBool::new(false)),
+            watchdog: None,
+            stall_callback: None,
             event_sender,
         };
         
         Ok((manager, event_receiver))
     }
     
+    /// Create a capture manager for `config`, receiving from the network if it is enabled
+    pub fn for_config(config: AudioConfig) -> Result<(Self, mpsc::Receiver<AudioEvent>)> {
+        let source: Box<dyn AudioSource> = match &config.network {
+            Some(network) => Box::new(NetworkSource::new(network.clone())),
+            None => Box::new(CpalSource::default()),
+        };
+        
+        let (mut manager, receiver) = Self::with_source(source)?;
+        manager.set_config(config);
+        Ok((manager, receiver))
+    }
+    
     /// Set a callback for peak level updates
     pub fn on_peak_level<F: Fn(f32) + Send + Sync + 'static>(&mut self, callback: F) {
-        self.peak_level_callback = Some(Arc::new(callback));
+        *self.peak_level_callback.lock() = Some(Arc::new(callback));
     }
     
     /// Set a callback for audio data
     pub fn on_audio_data<F: Fn(AudioData) + Send + Sync + 'static>(&mut self, callback: F) {
-        self.audio_data_callback = Some(Arc::new(callback));
+        *self.audio_data_callback.lock() = Some(Arc::new(callback));
+    }
+    
+    /// Set a callback for all audio while recording, before voice activity detection drops silence
+    ///
+    /// Unlike `on_audio_data` the audio keeps its pauses, so it lines up with wall time.
+    /// It is not called when sources are delivered as separate tracks.
+    pub fn on_raw_audio<F: Fn(AudioData) + Send + Sync + 'static>(&mut self, callback: F) {
+        *self.raw_audio_callback.lock() = Some(Arc::new(callback));
+    }
+    
+    /// Set a callback invoked when the capture device is lost
+    ///
+    /// The callback runs on the audio thread; it should only schedule a call to
+    /// `recover_from_device_loss` on the thread that owns this manager.
+    pub fn on_device_lost<F: Fn() + Send + Sync + 'static>(&mut self, callback: F) {
+        self.device_lost_callback = Some(Arc::new(callback));
     }
     
-    /// Set the audio device
-    pub fn set_device(&mut self, device: cpal::Device) {
-        // Update device name in config
-        if let Ok(name) = device.name() {
-            self.config.input_device = Some(name);
+    /// Set a callback invoked when the capture stream stalls or fails
+    ///
+    /// The callback runs on the watchdog thread; it should only schedule a call to
+    /// `restart_stalled_stream` on the thread that owns this manager.
+    pub fn on_stream_stalled<F: Fn(StallReason) + Send + Sync + 'static>(&mut self, callback: F) {
+        self.stall_callback = Some(Arc::new(callback));
+    }
+    
+    /// Set the audio configuration used for the next capture session
+    pub fn set_config(&mut self, config: AudioConfig) {
+        self.set_input_volume(config.input_volume);
+        self.config = config;
+    }
+    
+    /// Set the input volume (gain), taking effect immediately on a running stream
+    pub fn set_input_volume(&mut self, volume: f32) {
+        let volume = volume.clamp(0.0, MAX_INPUT_VOLUME);
+        self.config.input_volume = volume;
+        *self.input_volume.lock() = volume;
+    }
+    
+    /// Get the current input volume
+    pub fn get_input_volume(&self) -> f32 {
+        *self.input_volume.lock()
+    }
+    
+    /// Set the additional devices mixed with the main device on the next start
+    pub fn set_additional_devices(&mut self, device_ids: Vec<String>, mix_mode: MixMode) {
+        self.config.additional_devices = device_ids;
+        self.config.mix_mode = mix_mode;
+    }
+    
+    /// Set the capture device by its stable ID
+    pub fn set_device(&mut self, device_id: &str) {
+        self.config.input_device = Some(device_id.to_string());
+        
+        // Pre-roll should come from the device that will be recorded
+        if self.is_prerolling() {
+            self.teardown_streams();
+            if let Err(e) = self.open_streams() {
+                warn!("Failed to restart pre-roll on {}: {}", device_id, e);
+            }
+        }
+    }
+    
+    /// Set the pre-roll length used the next time the streams are opened
+    pub fn set_preroll(&mut self, preroll_ms: Option<u32>) {
+        self.config.preroll_ms = preroll_ms;
+    }
+    
+    /// Keep the device open while idle, buffering audio for the next recording
+    ///
+    /// Levels and waveform are still reported, but no audio is delivered until
+    /// `start` is called, at which point the buffered audio is sent first.
+    /// Stopping a recording returns to pre-roll until `stop_preroll` is called.
+    pub fn start_preroll(&mut self) -> Result<()> {
+        if self.config.preroll_ms.is_none() {
+            warn!("Pre-roll is not configured");
+            return Ok(());
+        }
+        
+        self.preroll_armed = true;
+        if self.is_recording {
+            return Ok(());
+        }
+        
+        self.forwarding.store(false, Ordering::SeqCst);
+        self.open_streams()?;
+        
+        info!("Buffering {} ms of pre-roll audio", self.config.preroll_ms.unwrap_or_default());
+        Ok(())
+    }
+    
+    /// Stop buffering pre-roll audio, closing the device if no recording is active
+    pub fn stop_preroll(&mut self) {
+        self.preroll_armed = false;
+        
+        if self.is_recording && !self.forwarding.load(Ordering::SeqCst) {
+            self.teardown_streams();
+            self.device_lost.store(false, Ordering::SeqCst);
+            info!("Stopped pre-roll buffering");
         }
     }
     
     /// Start audio capture and send events
     pub fn start(&mut self) -> Result<()> {
-        if self.is_recording {
+        if self.forwarding.swap(true, Ordering::SeqCst) && self.is_recording {
             warn!("Audio capture already running");
             return Ok(());
         }
         
-        // Find the device
-        let host = cpal::default_host();
-        let device = if let Some(device_name) = &self.config.input_device {
-            // Try to find device by name
-            let devices = host.input_devices()?;
-            let mut found_device = None;
+        // Streams kept open for pre-roll are reused; the consumer sends the buffered audio first
+        if !self.is_recording {
+            if let Err(e) = self.open_streams() {
+                self.forwarding.store(false, Ordering::SeqCst);
+                return Err(e);
+            }
+        }
+        
+        info!("Started audio recording");
+        
+        // Send started event
+        let event_sender = self.event_sender.clone();
+        tokio::spawn(async move {
+            if let Err(e) = event_sender.send(AudioEvent::Started).await {
+                error!("Failed to send audio start event: {}", e);
+            }
+        });
+        
+        Ok(())
+    }
+    
+    /// Open the capture streams and the consumer thread
+    fn open_streams(&mut self) -> Result<()> {
+        // Resolve the main source and the format it delivers
+        let SourceFormat { sample_rate, channels } = self.source.prepare(&self.config)?;
+        info!("Capturing from {} ({} Hz, {} channels)", self.source.name(), sample_rate, channels);
+        
+        // Timestamps restart with every stream
+        *self.clock.lock() = DriftTracker::new(sample_rate);
+        
+        // Open additional devices; their audio is mixed on the consumer thread
+        let secondary_sources = self.open_secondary_streams(sample_rate)?;
+        let mixer = if secondary_sources.is_empty() {
+            None
+        } else {
+            let max_backlog = (sample_rate * MIXER_MAX_BACKLOG_MS / 1000) as usize;
+            Some(AudioMixer::new(secondary_sources.len() + 1, self.config.mix_mode.clone(), max_backlog))
+        };
+        let output_channels = mixer.as_ref()
+            .map(|m| m.output_channels())
+            .unwrap_or(channels);
+        
+        // In tracks mode each mixer channel is delivered on its own, tagged with its device
+        let track_ids = if mixer.is_some() && self.config.mix_mode == MixMode::Tracks {
+            let main_id = self.config.input_device.clone().unwrap_or_else(|| "default".to_string());
+            Some(std::iter::once(main_id)
+                .chain(secondary_sources.iter().map(|source| source.name.clone()))
+                .collect())
+        } else {
+            None
+        };
+        
+        // Voice activity detector used to gate silent audio
+        let vad = if self.config.vad_enabled {
+            info!("Voice activity detection enabled (sensitivity: {})", self.config.vad_sensitivity);
+            Some(VoiceActivityDetector::new(
+                sample_rate,
+                output_channels,
+                self.config.vad_sensitivity,
+                self.config.vad_hangover_ms,
+            ))
+        } else {
+            None
+        };
+        
+        // The callback only copies samples into a pre-allocated ring buffer;
+        // all processing happens on the consumer thread
+        let capacity = ring_buffer_capacity(sample_rate, channels);
+        let (mut producer, consumer) = HeapRb::<f32>::new(capacity).split();
+        let overruns = Arc::new(AtomicUsize::new(0));
+        
+        self.dropped_frames.store(0, Ordering::Relaxed);
+        let outbox = EventOutbox::new(
+            self.event_sender.clone(),
+            self.config.overflow_policy.clone(),
+            self.dropped_frames.clone(),
+        );
+        
+        let processor = CaptureProcessor {
+            sample_rate,
+            channels,
+            output_channels,
+            input_volume: self.input_volume.clone(),
+            peak_level: self.peak_level.clone(),
+            metrics: self.metrics.clone(),
+            clock: self.clock.clone(),
+            frame_position: 0,
+            last_drift_report: Instant::now(),
+            meter: LevelMeter::new(
+                sample_rate,
+                output_channels,
+                self.config.metrics_interval_ms,
+                self.config.clip_threshold,
+            ),
+            waveform: if self.config.waveform_enabled {
+                Some(WaveformAggregator::new(
+                    sample_rate,
+                    output_channels,
+                    self.config.waveform_interval_ms,
+                    self.config.waveform_buckets,
+                ))
+            } else {
+                None
+            },
+            peak_callback: self.peak_level_callback.clone(),
+            audio_callback: self.audio_data_callback.clone(),
+            raw_callback: self.raw_audio_callback.clone(),
+            forwarding: self.forwarding.clone(),
+            was_forwarding: true,
+            preroll: self.config.preroll_ms.map(|ms| PreRollBuffer::new(sample_rate, output_channels, ms)),
+            outbox,
+            mixer,
+            secondary_sources,
+            vad,
+            track_ids,
+            overruns: overruns.clone(),
+            scratch: vec![0.0; capacity],
+        };
+        
+        let consumer_thread = self.spawn_consumer(processor, consumer)?;
+        let wake = consumer_thread.thread().clone();
+        self.consumer_thread = Some(consumer_thread);
+        
+        // Input data callback - receives audio samples
+        let heartbeat = self.heartbeat.clone();
+        let clock = self.clock.clone();
+        let frame_size = channels.max(1) as u64;
+        let mut unclocked_frames = 0u64;
+        let input_data_fn: DataCallback = Box::new(move |data: &[f32]| {
+            heartbeat.fetch_add(1, Ordering::Relaxed);
             
-            for device in devices {
-                if let Ok(name) = device.name() {
-                    if name == *device_name {
-                        found_device = Some(device);
-                        break;
+            // Never wait for the lock here; frames are counted on the next buffer instead
+            unclocked_frames += data.len() as u64 / frame_size;
+            if let Some(mut clock) = clock.try_lock() {
+                clock.record(unclocked_frames, Instant::now());
+                unclocked_frames = 0;
+            }
+            
+            let written = producer.push_slice(data);
+            if written < data.len() {
+                overruns.fetch_add(data.len() - written, Ordering::Relaxed);
+            }
+            wake.unpark();
+        });
+        
+        // Create an error callback
+        let err_event_sender = self.event_sender.clone();
+        let device_lost = self.device_lost.clone();
+        let device_lost_callback = self.device_lost_callback.clone();
+        let stream_fault = self.stream_fault.clone();
+        let err_fn: ErrorCallback = Box::new(move |err: SourceError| {
+            let err_str = format!("Audio capture error: {}", err);
+            error!("{}", err_str);
+            
+            // Let the owner switch to a fallback device, or have the watchdog rebuild the stream
+            if err == SourceError::DeviceLost {
+                if !device_lost.swap(true, Ordering::SeqCst) {
+                    if let Some(callback) = &device_lost_callback {
+                        callback();
                     }
                 }
+            } else {
+                stream_fault.store(true, Ordering::SeqCst);
             }
             
-            found_device.unwrap_or_else(|| host.default_input_device()
-                .expect("No input device available"))
-        } else {
-            // Use default device
-            host.default_input_device()
-                .ok_or_else(|| anyhow::anyhow!("No default input device"))?
+            // The audio thread must not block
+            if let Err(e) = err_event_sender.try_send(AudioEvent::Error(err_str)) {
+                error!("Failed to send audio error event: {}", e);
+            }
+        });
+        
+        // Start the main source
+        let stream = match self.source.start(input_data_fn, err_fn) {
+            Ok(stream) => stream,
+            Err(e) => {
+                self.secondary_streams.clear();
+                self.stop_consumer();
+                return Err(e);
+            }
         };
         
-        info!("Using audio device: {}", device.name()?);
+        // Store the stream in the struct
+        self.audio_stream = Some(stream);
+        
+        self.start_watchdog();
+        self.is_recording = true;
+        
+        Ok(())
+    }
+    
+    /// Fall back to the default input device if the active device was lost
+    ///
+    /// Returns true if capture was restarted on another device.
+    pub fn recover_from_device_loss(&mut self) -> Result<bool> {
+        if !self.device_lost.swap(false, Ordering::SeqCst) || !self.is_recording {
+            return Ok(false);
+        }
+        
+        let previous = self.config.input_device.clone();
+        warn!(
+            "Capture device {} disconnected, falling back to default input",
+            previous.as_deref().unwrap_or("(default)")
+        );
+        
+        // Drop the dead streams without reporting a stop
+        self.teardown_streams();
+        
+        self.config.input_device = None;
+        let current = default_input_id(&cpal::default_host());
+        
+        if let Err(e) = self.open_streams() {
+            let event_sender = self.event_sender.clone();
+            let err_str = format!("No fallback capture device available: {}", e);
+            tokio::spawn(async move {
+                let _ = event_sender.send(AudioEvent::Error(err_str)).await;
+                let _ = event_sender.send(AudioEvent::Stopped).await;
+            });
+            return Err(e);
+        }
+        
+        info!("Capture resumed on {}", current.as_deref().unwrap_or("default device"));
         
+        let event_sender = self.event_sender.clone();
+        tokio::spawn(async move {
+            if let Err(e) = event_sender.send(AudioEvent::DeviceChanged { previous, current }).await {
+                error!("Failed to send device changed event: {}", e);
+            }
+        });
+        
+        Ok(true)
+    }
+    
+    /// Rebuild the capture stream after the watchdog reported a stall or failure
+    ///
+    /// Returns true if capture was restarted.
+    pub fn restart_stalled_stream(&mut self, reason: &StallReason) -> Result<bool> {
+        // Device loss has its own recovery path
+        if !self.is_recording || self.device_lost.load(Ordering::SeqCst) {
+            return Ok(false);
+        }
+        
+        warn!("Restarting capture stream ({})", reason);
+        self.teardown_streams();
+        
+        if let Err(e) = self.open_streams() {
+            let event_sender = self.event_sender.clone();
+            let err_str = format!("Failed to restart stalled capture stream: {}", e);
+            tokio::spawn(async move {
+                let _ = event_sender.send(AudioEvent::Error(err_str)).await;
+                let _ = event_sender.send(AudioEvent::Stopped).await;
+            });
+            return Err(e);
+        }
+        
+        info!("Capture stream restarted");
+        
+        let event_sender = self.event_sender.clone();
+        let reason = reason.to_string();
+        tokio::spawn(async move {
+            if let Err(e) = event_sender.send(AudioEvent::StreamRecovered { reason }).await {
+                error!("Failed to send stream recovered event: {}", e);
+            }
+        });
+        
+        Ok(true)
+    }
+    
+    /// Drop the running streams and their helper threads without reporting a stop
+    fn teardown_streams(&mut self) {
+        if let Some(mut watchdog) = self.watchdog.take() {
+            watchdog.stop();
+        }
+        
+        self.audio_stream = None;
+        self.secondary_streams.clear();
+        self.stop_consumer();
+        self.stream_fault.store(false, Ordering::SeqCst);
+        self.is_recording = false;
+    }
+    
+    /// Start watching the running stream, if enabled and someone can restart it
+    fn start_watchdog(&mut self) {
+        let (timeout, callback) = match (self.config.watchdog_timeout_secs, self.stall_callback.clone()) {
+            (Some(timeout), Some(callback)) => (timeout, callback),
+            _ => return,
+        };
+        
+        match StreamWatchdog::start(
+            self.heartbeat.clone(),
+            self.stream_fault.clone(),
+            std::time::Duration::from_secs(timeout.max(1) as u64),
+            move |reason| callback(reason),
+        ) {
+            Ok(watchdog) => self.watchdog = Some(watchdog),
+            Err(e) => warn!("Failed to start audio watchdog: {}", e),
+        }
+    }
+    
+    /// Choose a stream configuration for a capture device
+    fn stream_config_for(audio_config: &AudioConfig, device: &cpal::Device, loopback: bool) -> Result<cpal::StreamConfig> {
         // Get a config we can use
-        let config = match device.default_input_config() {
-            Ok(config) => config,
-            Err(_) => {
-                // If default config fails, try to find one manually
-                let supported_configs = device.supported_input_configs()?
-                    .collect::<Vec<_>>();
-                
-                let config_range = supported_configs.iter()
-                    .find(|c| c.channels() == self.config.channels && c.sample_format() == cpal::SampleFormat::F32)
-                    .cloned()
-                    .or_else(|| supported_configs.into_iter().next())
-                    .ok_or_else(|| anyhow::anyhow!("No supported audio configuration found"))?;
-                
-                // Convert the config range to a specific config by selecting the max sample rate
-                config_range.with_max_sample_rate()
+        let config = if loopback {
+            // Loopback streams must use the output device's mix format
+            device.default_output_config()
+                .map_err(|e| anyhow::anyhow!("Failed to get loopback device config: {}", e))?
+        } else {
+            match device.default_input_config() {
+                Ok(config) => config,
+                Err(_) => {
+                    // If default config fails, try to find one manually
+                    let supported_configs = device.supported_input_configs()?
+                        .collect::<Vec<_>>();
+                    
+                    let config_range = supported_configs.iter()
+                        .find(|c| c.channels() == audio_config.channels && c.sample_format() == cpal::SampleFormat::F32)
+                        .cloned()
+                        .or_else(|| supported_configs.into_iter().next())
+                        .ok_or_else(|| anyhow::anyhow!("No supported audio configuration found"))?;
+                    
+                    // Convert the config range to a specific config by selecting the max sample rate
+                    config_range.with_max_sample_rate()
+                }
             }
         };
         
         info!("Using audio config: {:?}", config);
         debug!("Sample format: {:?}", config.sample_format());
         
+        let sample_rate = if loopback { config.sample_rate() } else { cpal::SampleRate(audio_config.sample_rate) };
+        
+        // An exact low-latency buffer overrides the preset
+        let buffer_ms = audio_config.low_latency_buffer_ms
+            .unwrap_or_else(|| latency::preset_buffer_ms(&audio_config.latency));
+        let buffer_size = latency::buffer_size(buffer_ms, sample_rate.0, config.buffer_size());
+        
         // Create a config to use for the stream
         let stream_config = cpal::StreamConfig {
             channels: config.channels(),
-            sample_rate: cpal::SampleRate(self.config.sample_rate),
-            buffer_size: match config.buffer_size() {
-                cpal::SupportedBufferSize::Range { min: _, max: _ } => cpal::BufferSize::Default,
-                cpal::SupportedBufferSize::Unknown => cpal::BufferSize::Default,
-            },
+            sample_rate,
+            buffer_size,
         };
         
-        info!("Using stream config: {:?}", stream_config);
+        Ok(Self::negotiate_buffer_size(device, stream_config))
+    }
+    
+    /// Check that the device accepts a fixed buffer size, falling back to its default buffer
+    ///
+    /// Some drivers advertise small buffers but refuse to open with them, so a
+    /// throwaway stream is built first rather than failing the real one.
+    fn negotiate_buffer_size(device: &cpal::Device, config: cpal::StreamConfig) -> cpal::StreamConfig {
+        let frames = match config.buffer_size {
+            cpal::BufferSize::Fixed(frames) => frames,
+            cpal::BufferSize::Default => return config,
+        };
         
-        // Store actual config values for audio data
-        let sample_rate = stream_config.sample_rate.0;
-        let channels = stream_config.channels;
+        let probe = device.build_input_stream(
+            &config,
+            |_: &[f32], _: &cpal::InputCallbackInfo| {},
+            |_| {},
+            None,
+        );
         
-        // Set up references to be moved into closures
-        let peak_level = self.peak_level.clone();
+        match probe {
+            Ok(_) => {
+                info!(
+                    "Capturing with {} frame buffer ({:.1} ms)",
+                    frames,
+                    frames as f32 * 1000.0 / config.sample_rate.0 as f32
+                );
+                config
+            }
+            Err(e) => {
+                warn!("Device refused {} frame buffer ({}), using default buffer size", frames, e);
+                cpal::StreamConfig {
+                    buffer_size: cpal::BufferSize::Default,
+                    ..config
+                }
+            }
+        }
+    }
+    
+    /// Open streams for the additional devices in the configuration
+    ///
+    /// Returns the ring buffers the streams feed, in mixer source order (source 0 is the main device).
+    fn open_secondary_streams(&mut self, target_rate: u32) -> Result<Vec<SecondarySource>> {
+        self.secondary_streams.clear();
         
-        // Create weak references to callbacks that will be captured by the closure
-        let peak_callback = self.peak_level_callback.clone();
-        let audio_callback = self.audio_data_callback.clone();
-        let input_event_sender = self.event_sender.clone();
+        let mut sources = Vec::new();
+        if self.config.additional_devices.is_empty() {
+            return Ok(sources);
+        }
         
-        // Input data callback - receives audio samples
-        let input_data_fn = move |data: &[f32], _: &cpal::InputCallbackInfo| {
-            let mut peak = 0.0f32;
-            let mut buffer = Vec::with_capacity(data.len());
-            
-            // Calculate peak level for visualization
-            for &sample in data.iter() {
-                let abs_sample = sample.abs();
-                if abs_sample > peak {
-                    peak = abs_sample;
+        let host = cpal::default_host();
+        for device_id in self.config.additional_devices.clone() {
+            match self.build_secondary_stream(&host, &device_id, target_rate) {
+                Ok((stream, source)) => {
+                    info!("Mixing additional device: {}", device_id);
+                    self.secondary_streams.push(stream);
+                    sources.push(source);
                 }
-                buffer.push(sample);
+                Err(e) => warn!("Skipping additional device '{}': {}", device_id, e),
             }
-            
-            // Update peak level
-            {
-                let mut level = peak_level.lock();
-                *level = peak;
-            }
-            
-            // Send peak level event
-            let peak_sender = input_event_sender.clone();
-            tokio::spawn(async move {
-                if let Err(e) = peak_sender.send(AudioEvent::Level(peak)).await {
-                    error!("Failed to send audio level event: {}", e);
+        }
+        
+        Ok(sources)
+    }
+    
+    /// Start an input stream for an additional device that feeds its own ring buffer
+    fn build_secondary_stream(
+        &self,
+        host: &cpal::Host,
+        device_id: &str,
+        target_rate: u32,
+    ) -> Result<(SourceHandle, SecondarySource)> {
+        // Sound server sources are recorded by name, and the server converts them to the main stream's rate
+        #[cfg(target_os = "linux")]
+        let server = CaptureTarget::server(&self.config, Some(device_id), target_rate);
+        #[cfg(not(target_os = "linux"))]
+        let server = None;
+        
+        let target = match server {
+            Some(target) => {
+                if !is_loopback_id(device_id) {
+                    permission::ensure_microphone_access()?;
                 }
-            });
-            
-            // Call peak level callback if provided
-            if let Some(callback) = &peak_callback {
-                callback(peak);
-            }
-            
-            // Create audio data and call audio data callback if provided
-            let audio_data = AudioData::new(buffer, sample_rate, channels);
-            
-            if let Some(callback) = &audio_callback {
-                callback(audio_data.clone());
+                target
             }
-            
-            // Send audio data event
-            let data_sender = input_event_sender.clone();
-            let data_clone = audio_data;
-            tokio::spawn(async move {
-                if let Err(e) = data_sender.send(AudioEvent::Data(data_clone)).await {
-                    error!("Failed to send audio data event: {}", e);
+            None => {
+                let (device, loopback) = Self::find_source(host, device_id)?;
+                if !loopback {
+                    permission::ensure_microphone_access()?;
                 }
-            });
+                let stream_config = Self::stream_config_for(&self.config, &device, loopback)?;
+                CaptureTarget::Device(device, stream_config)
+            }
         };
+        let SourceFormat { sample_rate, channels } = target.format();
+        
+        // Loopback sources run at the output device rate and must match the main stream
+        let resampler = StreamResampler::new(
+            sample_rate,
+            target_rate,
+            &self.config.resampler_quality,
+            self.config.resampler_chunk_ms,
+        )?;
+        
+        let capacity = ring_buffer_capacity(sample_rate, channels);
+        let (mut producer, consumer) = HeapRb::<f32>::new(capacity).split();
+        
+        let overruns = Arc::new(AtomicUsize::new(0));
+        let callback_overruns = overruns.clone();
+        let data_fn: DataCallback = Box::new(move |data: &[f32]| {
+            let written = producer.push_slice(data);
+            if written < data.len() {
+                callback_overruns.fetch_add(data.len() - written, Ordering::Relaxed);
+            }
+        });
         
-        // Create an error callback
         let err_event_sender = self.event_sender.clone();
-        let err_fn = move |err| {
-            let err_str = format!("Audio capture error: {}", err);
+        let source_name = device_id.to_string();
+        let err_fn: ErrorCallback = Box::new(move |err: SourceError| {
+            let err_str = format!("Audio capture error on {}: {}", source_name, err);
             error!("{}", err_str);
             
-            let sender = err_event_sender.clone();
-            tokio::spawn(async move {
-                if let Err(e) = sender.send(AudioEvent::Error(err_str.clone())).await {
-                    error!("Failed to send audio error event: {}", e);
-                }
-            });
-        };
+            if let Err(e) = err_event_sender.try_send(AudioEvent::Error(err_str)) {
+                error!("Failed to send audio error event: {}", e);
+            }
+        });
         
-        // Build and store the input stream
-        let stream = device.build_input_stream(
-            &stream_config,
-            input_data_fn,
-            err_fn,
-            None
-        )?;
+        // Additional streams run from here; the mixer drops what piles up before the main stream starts
+        let stream = target.start(data_fn, err_fn)?;
         
-        // Store the stream in the struct
-        self.audio_stream = Some(stream);
+        Ok((stream, SecondarySource {
+            name: device_id.to_string(),
+            consumer,
+            channels,
+            resampler,
+            overruns,
+        }))
+    }
+    
+    /// Start the thread that drains the capture ring buffer
+    fn spawn_consumer(&mut self, mut processor: CaptureProcessor, mut consumer: HeapConsumer<f32>) -> Result<JoinHandle<()>> {
+        self.consumer_running.store(true, Ordering::SeqCst);
+        let running = self.consumer_running.clone();
+        
+        let thread = std::thread::Builder::new()
+            .name("audio-consumer".to_string())
+            .spawn(move || {
+                let frame = processor.channels.max(1) as usize;
+                let mut buffer = vec![0.0f32; consumer.capacity()];
+                
+                loop {
+                    // Check before draining so audio queued before a stop is still processed
+                    let active = running.load(Ordering::SeqCst);
+                    
+                    processor.drain_secondary_sources();
+                    processor.report_overruns();
+                    
+                    // Only take whole frames so channels stay aligned
+                    let available = consumer.len() - consumer.len() % frame;
+                    if available > 0 {
+                        let count = consumer.pop_slice(&mut buffer[..available]);
+                        processor.process(&buffer[..count]);
+                        continue;
+                    }
+                    
+                    if !active {
+                        break;
+                    }
+                    
+                    std::thread::park_timeout(CONSUMER_IDLE_TIMEOUT);
+                }
+                
+                processor.outbox.finish();
+                
+                debug!("Audio consumer thread stopped");
+            })?;
         
-        // Start playing the stream
-        self.audio_stream.as_ref().unwrap().play()?;
+        Ok(thread)
+    }
+    
+    /// Stop the consumer thread after it has processed the remaining audio
+    fn stop_consumer(&mut self) {
+        self.consumer_running.store(false, Ordering::SeqCst);
         
-        info!("Started audio recording");
-        self.is_recording = true;
+        if let Some(thread) = self.consumer_thread.take() {
+            thread.thread().unpark();
+            if thread.join().is_err() {
+                error!("Audio consumer thread panicked");
+            }
+        }
+    }
+    
+    /// Find the configured capture device
+    ///
+    /// Returns the device and whether it is an output device captured via loopback.
+    fn find_device(config: &AudioConfig, host: &cpal::Host) -> Result<(cpal::Device, bool)> {
+        let device_id = match &config.input_device {
+            Some(id) => id,
+            None => {
+                // Use default device
+                let device = host.default_input_device()
+                    .ok_or_else(|| anyhow::anyhow!("No default input device"))?;
+                return Ok((device, false));
+            }
+        };
         
-        // Send started event
-        let event_sender = self.event_sender.clone();
-        tokio::spawn(async move {
-            if let Err(e) = event_sender.send(AudioEvent::Started).await {
-                error!("Failed to send audio start event: {}", e);
+        match Self::find_source(host, device_id) {
+            Ok(found) => Ok(found),
+            Err(e) if !is_loopback_id(device_id) => {
+                warn!("{}, using default device", e);
+                let device = host.default_input_device()
+                    .ok_or_else(|| anyhow::anyhow!("No input device available"))?;
+                Ok((device, false))
             }
-        });
+            Err(e) => Err(e),
+        }
+    }
+    
+    /// Look up a capture source by ID without falling back to the default device
+    fn find_source(host: &cpal::Host, device_id: &str) -> Result<(cpal::Device, bool)> {
+        if is_loopback_id(device_id) && !loopback_supported(device_id) {
+            return Err(anyhow::anyhow!("Loopback capture is not supported for '{}' on this platform", device_id));
+        }
         
-        Ok(())
+        find_device_by_id(host, device_id)?
+            .ok_or_else(|| anyhow::anyhow!("Capture device '{}' not found", device_id))
     }
     
     /// Stop audio capture
+    ///
+    /// If pre-roll is armed the device stays open and goes back to buffering.
     pub fn stop(&mut self) -> Result<()> {
-        if !self.is_recording {
+        if !self.is_recording || !self.forwarding.swap(false, Ordering::SeqCst) {
             return Ok(());
         }
         
-        // Drop the stream to stop recording
-        self.audio_stream = None;
+        // Drop the streams to stop recording
+        if !self.preroll_armed {
+            self.teardown_streams();
+            self.device_lost.store(false, Ordering::SeqCst);
+        }
         
         info!("Stopped audio recording");
-        self.is_recording = false;
         
         // Send stopped event
         let event_sender = self.event_sender.clone();
@@ -369,11 +1073,26 @@ impl CaptureManager {
         Ok(())
     }
     
+    /// Get the number of audio frames dropped since capture started
+    pub fn get_dropped_frames(&self) -> u64 {
+        self.dropped_frames.load(Ordering::Relaxed)
+    }
+    
     /// Get the current peak audio level
     pub fn get_peak_level(&self) -> f32 {
         *self.peak_level.lock()
     }
     
+    /// Get the most recent level metrics
+    pub fn get_metrics(&self) -> AudioMetrics {
+        *self.metrics.lock()
+    }
+    
+    /// Get the measured drift of the device clock for the current stream
+    pub fn get_clock_drift(&self) -> ClockDrift {
+        self.clock.lock().stats()
+    }
+    
     /// Get the audio configuration
     pub fn get_config(&self) -> &AudioConfig {
         &self.config
@@ -381,7 +1100,470 @@ impl CaptureManager {
     
     /// Check if audio capture is active
     pub fn is_active(&self) -> bool {
-        self.is_recording
+        self.is_recording && self.forwarding.load(Ordering::SeqCst)
+    }
+    
+    /// Check if the device is open only to fill the pre-roll buffer
+    pub fn is_prerolling(&self) -> bool {
+        self.is_recording && !self.forwarding.load(Ordering::SeqCst)
+    }
+}
+
+/// Size a capture ring buffer to hold `RING_BUFFER_MS` of interleaved audio
+fn ring_buffer_capacity(sample_rate: u32, channels: u16) -> usize {
+    let samples = sample_rate as usize * channels.max(1) as usize * RING_BUFFER_MS / 1000;
+    samples.max(RING_BUFFER_SIZE)
+}
+
+/// Captures the configured cpal input device (or a loopback source)
+#[derive(Default)]
+pub struct CpalSource {
+    /// Device and stream configuration chosen by `prepare`
+    prepared: Option<CaptureTarget>,
+    
+    /// Exclusive-mode stream opened by `prepare`, used instead of the cpal stream
+    #[cfg(target_os = "windows")]
+    exclusive: Option<super::wasapi::ExclusiveStream>,
+}
+
+impl AudioSource for CpalSource {
+    fn name(&self) -> String {
+        self.prepared.as_ref()
+            .map(CaptureTarget::name)
+            .unwrap_or_else(|| "audio device".to_string())
+    }
+    
+    fn prepare(&mut self, config: &AudioConfig) -> Result<SourceFormat> {
+        // Sound server sources are recorded by name rather than through a cpal device
+        #[cfg(target_os = "linux")]
+        if let Some(target) = CaptureTarget::server(config, config.input_device.as_deref(), config.sample_rate) {
+            if !config.input_device.as_deref().is_some_and(is_loopback_id) {
+                permission::ensure_microphone_access()?;
+            }
+            
+            info!("Using sound server source: {}", target.name());
+            let format = target.format();
+            self.prepared = Some(target);
+            return Ok(format);
+        }
+        
+        let host = cpal::default_host();
+        let (device, loopback) = CaptureManager::find_device(config, &host)?;
+        
+        if !loopback {
+            permission::ensure_microphone_access()?;
+        }
+        
+        info!("Using audio device: {}{}", device.name()?, if loopback { " (loopback)" } else { "" });
+        
+        #[cfg(target_os = "windows")]
+        {
+            // A stream from an earlier start would keep the device to itself
+            self.exclusive = None;
+            
+            if config.exclusive_mode && !loopback {
+                let buffer_ms = config.low_latency_buffer_ms
+                    .unwrap_or_else(|| latency::preset_buffer_ms(&config.latency));
+                let opened = super::wasapi::ExclusiveStream::open(
+                    config.input_device.as_deref(),
+                    config.sample_rate,
+                    config.channels,
+                    buffer_ms,
+                );
+                match opened {
+                    Ok(stream) => {
+                        // The device is only kept for its name; its shared-mode stream isn't built
+                        let format = stream.format();
+                        let stream_config = cpal::StreamConfig {
+                            channels: format.channels,
+                            sample_rate: cpal::SampleRate(format.sample_rate),
+                            buffer_size: cpal::BufferSize::Default,
+                        };
+                        self.prepared = Some(CaptureTarget::Device(device, stream_config));
+                        self.exclusive = Some(stream);
+                        return Ok(format);
+                    }
+                    Err(e) => warn!("Device refused exclusive mode ({:#}), capturing in shared mode", e),
+                }
+            }
+        }
+        
+        let stream_config = CaptureManager::stream_config_for(config, &device, loopback)?;
+        info!("Using stream config: {:?}", stream_config);
+        
+        let target = CaptureTarget::Device(device, stream_config);
+        let format = target.format();
+        self.prepared = Some(target);
+        
+        Ok(format)
+    }
+    
+    fn start(&mut self, on_data: DataCallback, on_error: ErrorCallback) -> Result<SourceHandle> {
+        #[cfg(target_os = "windows")]
+        if let Some(stream) = self.exclusive.take() {
+            return stream.start(on_data, on_error);
+        }
+        
+        self.prepared.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Audio device was not prepared"))?
+            .start(on_data, on_error)
+    }
+}
+
+/// What a capture stream records from
+enum CaptureTarget {
+    /// A cpal device, with the stream configuration chosen for it
+    Device(cpal::Device, cpal::StreamConfig),
+    
+    /// A PulseAudio/PipeWire source recorded by name, which the server converts to `format`
+    #[cfg(target_os = "linux")]
+    Server {
+        source: String,
+        format: SourceFormat,
+        latency_ms: u32,
+    },
+}
+
+impl CaptureTarget {
+    /// Target for a sound server source, if `device_id` names one
+    #[cfg(target_os = "linux")]
+    fn server(config: &AudioConfig, device_id: Option<&str>, sample_rate: u32) -> Option<Self> {
+        let source = device_id.and_then(pulse_source_name)?;
+        
+        Some(CaptureTarget::Server {
+            source: source.to_string(),
+            format: SourceFormat { sample_rate, channels: config.channels },
+            latency_ms: config.low_latency_buffer_ms
+                .unwrap_or_else(|| latency::preset_buffer_ms(&config.latency)),
+        })
+    }
+    
+    /// Name used in logs
+    fn name(&self) -> String {
+        match self {
+            CaptureTarget::Device(device, _) => device.name().unwrap_or_else(|_| "audio device".to_string()),
+            #[cfg(target_os = "linux")]
+            CaptureTarget::Server { source, .. } => source.clone(),
+        }
+    }
+    
+    /// Format the stream delivers
+    fn format(&self) -> SourceFormat {
+        match self {
+            CaptureTarget::Device(_, stream_config) => SourceFormat {
+                sample_rate: stream_config.sample_rate.0,
+                channels: stream_config.channels,
+            },
+            #[cfg(target_os = "linux")]
+            CaptureTarget::Server { format, .. } => *format,
+        }
+    }
+    
+    /// Start the stream; it runs until the handle is dropped
+    fn start(&self, mut on_data: DataCallback, mut on_error: ErrorCallback) -> Result<SourceHandle> {
+        match self {
+            CaptureTarget::Device(device, stream_config) => {
+                let stream = device.build_input_stream(
+                    stream_config,
+                    move |data: &[f32], _: &cpal::InputCallbackInfo| on_data(data),
+                    move |err: cpal::StreamError| {
+                        on_error(match err {
+                            cpal::StreamError::DeviceNotAvailable => SourceError::DeviceLost,
+                            err => SourceError::Stream(err.to_string()),
+                        })
+                    },
+                    None,
+                )?;
+                
+                // Start playing the stream
+                stream.play()?;
+                
+                Ok(SourceHandle::new(stream))
+            }
+            #[cfg(target_os = "linux")]
+            CaptureTarget::Server { source, format, latency_ms } => {
+                let recorder = super::pulse::SourceRecorder::start(source, *format, *latency_ms, on_data, on_error)?;
+                Ok(SourceHandle::new(recorder))
+            }
+        }
+    }
+}
+
+/// Ring buffer and format conversion for an additional capture device
+struct SecondarySource {
+    /// Device ID (for logging)
+    name: String,
+    
+    /// Samples written by the device callback
+    consumer: HeapConsumer<f32>,
+    
+    /// Channels in the device stream
+    channels: u16,
+    
+    /// Converts the device rate to the main stream rate
+    resampler: StreamResampler,
+    
+    /// Samples dropped because the ring buffer was full
+    overruns: Arc<AtomicUsize>,
+}
+
+/// Processing that used to run inside the audio callback, now on the consumer thread
+struct CaptureProcessor {
+    sample_rate: u32,
+    channels: u16,
+    output_channels: u16,
+    input_volume: Arc<Mutex<f32>>,
+    peak_level: Arc<Mutex<f32>>,
+    metrics: Arc<Mutex<AudioMetrics>>,
+    
+    /// Drift-corrected clock used to timestamp audio
+    clock: Arc<Mutex<DriftTracker>>,
+    
+    /// Output frames processed since the stream opened
+    frame_position: u64,
+    
+    /// When clock drift was last reported
+    last_drift_report: Instant,
+    
+    meter: LevelMeter,
+    waveform: Option<WaveformAggregator>,
+    peak_callback: SharedCallback<f32>,
+    audio_callback: SharedCallback<AudioData>,
+    
+    /// Called with the recorded audio before VAD gating
+    raw_callback: SharedCallback<AudioData>,
+    
+    /// Whether audio is delivered or only kept in the pre-roll buffer
+    forwarding: Arc<AtomicBool>,
+    
+    /// Forwarding state seen by the previous block, to detect the start of a recording
+    was_forwarding: bool,
+    
+    /// Audio kept while idle (None if pre-roll is disabled)
+    preroll: Option<PreRollBuffer>,
+    
+    outbox: EventOutbox,
+    mixer: Option<AudioMixer>,
+    secondary_sources: Vec<SecondarySource>,
+    vad: Option<VoiceActivityDetector>,
+    
+    /// Source ID for each output channel when sources are delivered as tracks
+    track_ids: Option<Vec<String>>,
+    
+    /// Samples dropped by the main callback because the ring buffer was full
+    overruns: Arc<AtomicUsize>,
+    
+    /// Reusable buffer for draining secondary sources
+    scratch: Vec<f32>,
+}
+
+impl CaptureProcessor {
+    /// Move audio from additional devices into the mixer
+    fn drain_secondary_sources(&mut self) {
+        let mixer = match self.mixer.as_mut() {
+            Some(mixer) => mixer,
+            None => return,
+        };
+        
+        let gain = *self.input_volume.lock();
+        
+        for (index, source) in self.secondary_sources.iter_mut().enumerate() {
+            let frame = source.channels.max(1) as usize;
+            let available = source.consumer.len().min(self.scratch.len());
+            let available = available - available % frame;
+            if available == 0 {
+                continue;
+            }
+            
+            let count = source.consumer.pop_slice(&mut self.scratch[..available]);
+            let buffer = apply_gain(&self.scratch[..count], gain);
+            
+            // Source 0 is the main device
+            match source.resampler.process(&downmix(&buffer, source.channels)) {
+                Ok(samples) => mixer.push(index + 1, &samples),
+                Err(e) => error!("Failed to resample audio from {}: {}", source.name, e),
+            }
+        }
+    }
+    
+    /// Report samples the audio callbacks had to drop
+    fn report_overruns(&mut self) {
+        let dropped = self.overruns.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            warn!("Capture ring buffer full, dropped {} samples", dropped);
+            self.outbox.record_dropped((dropped / self.channels.max(1) as usize) as u64);
+        }
+        
+        // Mixed sources are padded with silence, so only log their overruns
+        for source in &self.secondary_sources {
+            let dropped = source.overruns.swap(0, Ordering::Relaxed);
+            if dropped > 0 {
+                warn!("Capture ring buffer for {} full, dropped {} samples", source.name, dropped);
+            }
+        }
+    }
+    
+    /// Process a block of interleaved samples from the main device
+    fn process(&mut self, data: &[f32]) {
+        let gain = *self.input_volume.lock();
+        
+        // Apply input gain
+        let mut buffer = apply_gain(data, gain);
+        
+        // Combine with audio from additional devices
+        if let Some(mixer) = self.mixer.as_mut() {
+            mixer.push(0, &downmix(&buffer, self.channels));
+            buffer = mixer.pop_mixed();
+            
+            if buffer.is_empty() {
+                return;
+            }
+        }
+        
+        // Position of this block on the stream timeline
+        let block_start = self.frame_position;
+        self.frame_position += (buffer.len() / self.output_channels.max(1) as usize) as u64;
+        self.report_clock_drift();
+        
+        // Calculate peak level for visualization
+        let peak = simd::peak(&buffer);
+        
+        // Update peak level
+        {
+            let mut level = self.peak_level.lock();
+            *level = peak;
+        }
+        
+        // Send peak level event
+        self.outbox.send(AudioEvent::Level(peak));
+        
+        // Call peak level callback if provided
+        let peak_callback = self.peak_callback.lock().clone();
+        if let Some(callback) = &peak_callback {
+            callback(peak);
+        }
+        
+        // Report RMS, loudness and clipping at the metering rate
+        if let Some(metrics) = self.meter.process(&buffer) {
+            *self.metrics.lock() = metrics;
+            self.outbox.send(AudioEvent::Metrics(metrics));
+        }
+        
+        // Waveform is reported before VAD gating so silence is drawn too
+        if let Some(buckets) = self.waveform.as_mut().and_then(|w| w.process(&buffer)) {
+            self.outbox.send(AudioEvent::Waveform(buckets));
+        }
+        
+        // While idle, keep the audio for the next recording instead of delivering it
+        if !self.forwarding.load(Ordering::Relaxed) {
+            if let Some(preroll) = self.preroll.as_mut() {
+                preroll.push(&buffer);
+            }
+            self.was_forwarding = false;
+            return;
+        }
+        
+        if !self.was_forwarding {
+            self.was_forwarding = true;
+            self.send_preroll(block_start);
+        }
+        
+        self.send_raw(&buffer, block_start);
+        
+        // Run voice activity detection and suppress silent buffers
+        if let Some(vad) = self.vad.as_mut() {
+            let transition = vad.process(&buffer);
+            
+            let speech_event = match transition {
+                VadTransition::SpeechStart => Some(AudioEvent::SpeechStart),
+                VadTransition::SpeechEnd => Some(AudioEvent::SpeechEnd),
+                VadTransition::None => None,
+            };
+            
+            if let Some(event) = speech_event {
+                self.outbox.send(event);
+            }
+            
+            // Forward the buffer that ends speech so trailing audio is kept
+            if !vad.is_speaking() && transition != VadTransition::SpeechEnd {
+                return;
+            }
+        }
+        
+        self.send_data(buffer, block_start);
+    }
+    
+    /// Deliver the audio buffered before the recording started
+    ///
+    /// It was captured before start was pressed, so it bypasses the VAD gate.
+    fn send_preroll(&mut self, block_start: u64) {
+        let (samples, frames) = match self.preroll.as_mut() {
+            Some(preroll) if !preroll.is_empty() => {
+                let frames = preroll.frames() as u64;
+                (preroll.take(), frames)
+            }
+            _ => return,
+        };
+        
+        debug!("Sending {} pre-roll samples", samples.len());
+        self.send_raw(&samples, block_start.saturating_sub(frames));
+        self.send_data(samples, block_start.saturating_sub(frames));
+    }
+    
+    /// Call the raw audio callback with audio that has not been through the VAD gate
+    fn send_raw(&self, samples: &[f32], start_frame: u64) {
+        if self.track_ids.is_some() {
+            return;
+        }
+        
+        let raw_callback = self.raw_callback.lock().clone();
+        if let Some(callback) = &raw_callback {
+            let timestamp = self.clock.lock().timestamp(start_frame);
+            callback(AudioData::new(samples.to_vec(), self.sample_rate, self.output_channels).with_timestamp(timestamp));
+        }
+    }
+    
+    /// Periodically report the measured device clock drift
+    fn report_clock_drift(&mut self) {
+        if self.last_drift_report.elapsed() < DRIFT_REPORT_INTERVAL {
+            return;
+        }
+        
+        self.last_drift_report = Instant::now();
+        let drift = self.clock.lock().stats();
+        debug!("Capture clock drift: {:.1} ppm over {:.0} s", drift.drift_ppm, drift.wall_secs);
+        self.outbox.send(AudioEvent::ClockDrift(drift));
+    }
+    
+    /// Create audio data, call the audio data callback and send the data event
+    ///
+    /// In tracks mode the interleaved buffer is split into one tagged mono buffer per source.
+    fn send_data(&mut self, samples: Vec<f32>, start_frame: u64) {
+        let timestamp = self.clock.lock().timestamp(start_frame);
+        let buffers = match &self.track_ids {
+            Some(track_ids) => {
+                let channels = track_ids.len();
+                track_ids.iter().enumerate()
+                    .map(|(channel, id)| {
+                        let track = samples.iter().skip(channel).step_by(channels).copied().collect();
+                        AudioData::new(track, self.sample_rate, 1)
+                            .with_source(id.clone())
+                            .with_timestamp(timestamp)
+                    })
+                    .collect()
+            }
+            None => vec![AudioData::new(samples, self.sample_rate, self.output_channels).with_timestamp(timestamp)],
+        };
+        
+        let audio_callback = self.audio_callback.lock().clone();
+        for audio_data in buffers {
+            if let Some(callback) = &audio_callback {
+                callback(audio_data.clone());
+            }
+            
+            // Send audio data event
+            self.outbox.send(AudioEvent::Data(audio_data));
+        }
     }
 }
 
@@ -389,7 +1571,15 @@ impl CaptureManager {
 pub enum CaptureCommand {
     Start,
     Stop,
-    SetDevice(cpal::Device),
+    SetDevice(String),
+    SetConfig(AudioConfig),
+    SetInputVolume(f32),
+    SetAdditionalDevices(Vec<String>, MixMode),
+    SetPreRoll(Option<u32>),
+    StartPreRoll,
+    StopPreRoll,
+    RecoverDevice,
+    RestartStream(StallReason),
     SetPeakCallback(Box<dyn Fn(f32) + Send + Sync + 'static>),
     SetAudioCallback(Box<dyn Fn(AudioData) + Send + Sync + 'static>),
     Exit,
@@ -420,11 +1610,41 @@ impl ThreadedCaptureManager {
             .map_err(|e| anyhow::anyhow!("Failed to send stop command: {}", e))
     }
     
-    pub fn set_device(&self, device: cpal::Device) -> Result<()> {
-        self.command_sender.blocking_send(CaptureCommand::SetDevice(device))
+    pub fn set_device(&self, device_id: &str) -> Result<()> {
+        self.command_sender.blocking_send(CaptureCommand::SetDevice(device_id.to_string()))
             .map_err(|e| anyhow::anyhow!("Failed to send set device command: {}", e))
     }
     
+    pub fn set_config(&self, config: AudioConfig) -> Result<()> {
+        self.command_sender.blocking_send(CaptureCommand::SetConfig(config))
+            .map_err(|e| anyhow::anyhow!("Failed to send set config command: {}", e))
+    }
+    
+    pub fn set_input_volume(&self, volume: f32) -> Result<()> {
+        self.command_sender.blocking_send(CaptureCommand::SetInputVolume(volume))
+            .map_err(|e| anyhow::anyhow!("Failed to send set input volume command: {}", e))
+    }
+    
+    pub fn set_additional_devices(&self, device_ids: Vec<String>, mix_mode: MixMode) -> Result<()> {
+        self.command_sender.blocking_send(CaptureCommand::SetAdditionalDevices(device_ids, mix_mode))
+            .map_err(|e| anyhow::anyhow!("Failed to send set additional devices command: {}", e))
+    }
+    
+    pub fn set_preroll(&self, preroll_ms: Option<u32>) -> Result<()> {
+        self.command_sender.blocking_send(CaptureCommand::SetPreRoll(preroll_ms))
+            .map_err(|e| anyhow::anyhow!("Failed to send set pre-roll command: {}", e))
+    }
+    
+    pub fn start_preroll(&self) -> Result<()> {
+        self.command_sender.blocking_send(CaptureCommand::StartPreRoll)
+            .map_err(|e| anyhow::anyhow!("Failed to send start pre-roll command: {}", e))
+    }
+    
+    pub fn stop_preroll(&self) -> Result<()> {
+        self.command_sender.blocking_send(CaptureCommand::StopPreRoll)
+            .map_err(|e| anyhow::anyhow!("Failed to send stop pre-roll command: {}", e))
+    }
+    
     pub fn on_peak_level<F: Fn(f32) + Send + Sync + 'static>(&self, callback: F) -> Result<()> {
         self.command_sender.blocking_send(CaptureCommand::SetPeakCallback(Box::new(callback)))
             .map_err(|e| anyhow::anyhow!("Failed to send peak callback command: {}", e))
@@ -446,14 +1666,32 @@ impl Drop for ThreadedCaptureManager {
 impl CaptureManager {
     // Create a thread-safe wrapper around CaptureManager
     pub fn create_threaded() -> Result<(ThreadedCaptureManager, mpsc::Receiver<AudioEvent>)> {
-        let (_event_sender, event_receiver) = mpsc::channel(100);
+        let (event_sender, event_receiver) = mpsc::channel(100);
         let (cmd_sender, mut cmd_receiver) = mpsc::channel(10);
+        let recovery_sender = cmd_sender.clone();
         
         // Create the manager and spawn a thread to manage it
         std::thread::spawn(move || {
             // Create manager in this thread
             match Self::new() {
                 Ok((mut manager, _)) => {
+                    // Route events to the receiver handed out to the caller
+                    manager.event_sender = event_sender;
+                    
+                    // Recover on this thread, which owns the streams
+                    let stall_sender = recovery_sender.clone();
+                    manager.on_device_lost(move || {
+                        if let Err(e) = recovery_sender.try_send(CaptureCommand::RecoverDevice) {
+                            error!("Failed to schedule device recovery: {}", e);
+                        }
+                    });
+                    
+                    manager.on_stream_stalled(move |reason| {
+                        if let Err(e) = stall_sender.try_send(CaptureCommand::RestartStream(reason)) {
+                            error!("Failed to schedule stream restart: {}", e);
+                        }
+                    });
+                    
                     // Main loop for processing commands
                     while let Some(cmd) = cmd_receiver.blocking_recv() {
                         match cmd {
@@ -467,8 +1705,38 @@ impl CaptureManager {
                                     error!("Failed to stop capture: {}", e);
                                 }
                             },
-                            CaptureCommand::SetDevice(device) => {
-                                manager.set_device(device);
+                            CaptureCommand::SetDevice(device_id) => {
+                                manager.set_device(&device_id);
+                            },
+                            CaptureCommand::SetConfig(config) => {
+                                manager.set_config(config);
+                            },
+                            CaptureCommand::SetInputVolume(volume) => {
+                                manager.set_input_volume(volume);
+                            },
+                            CaptureCommand::SetAdditionalDevices(device_ids, mix_mode) => {
+                                manager.set_additional_devices(device_ids, mix_mode);
+                            },
+                            CaptureCommand::SetPreRoll(preroll_ms) => {
+                                manager.set_preroll(preroll_ms);
+                            },
+                            CaptureCommand::StartPreRoll => {
+                                if let Err(e) = manager.start_preroll() {
+                                    error!("Failed to start pre-roll: {}", e);
+                                }
+                            },
+                            CaptureCommand::StopPreRoll => {
+                                manager.stop_preroll();
+                            },
+                            CaptureCommand::RecoverDevice => {
+                                if let Err(e) = manager.recover_from_device_loss() {
+                                    error!("Failed to recover from device loss: {}", e);
+                                }
+                            },
+                            CaptureCommand::RestartStream(reason) => {
+                                if let Err(e) = manager.restart_stalled_stream(&reason) {
+                                    error!("Failed to restart capture stream: {}", e);
+                                }
                             },
                             CaptureCommand::SetPeakCallback(callback) => {
                                 manager.on_peak_level(callback);
@@ -482,6 +1750,7 @@ impl CaptureManager {
                     
                     // Clean up when finished
                     let _ = manager.stop();
+                    manager.stop_preroll();
                 },
                 Err(e) => {
                     error!("Failed to create CaptureManager: {}", e);
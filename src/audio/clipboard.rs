@@ -0,0 +1,288 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::VecDeque;
+
+use crate::config::{ClipboardMode, ClipboardOutputSettings};
+
+/// The system clipboard
+pub trait Clipboard: Send {
+    /// Put `text` on the clipboard, replacing what was there
+    fn copy(&self, text: &str) -> Result<()>;
+}
+
+/// Clipboard for the platform the application runs on
+pub fn platform_clipboard() -> Box<dyn Clipboard> {
+    Box::new(PlatformClipboard)
+}
+
+/// Copies with the platform's own means
+///
+/// Windows uses the clipboard API, macOS `pbcopy` and Linux `wl-copy` in
+/// Wayland sessions and `xclip` otherwise.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlatformClipboard;
+
+impl Clipboard for PlatformClipboard {
+    fn copy(&self, text: &str) -> Result<()> {
+        #[cfg(target_os = "windows")]
+        {
+            windows_clipboard::copy(text)
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            pipe("pbcopy", &[], text)
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            if std::env::var_os("WAYLAND_DISPLAY").is_some_and(|display| !display.is_empty()) {
+                pipe("wl-copy", &[], text)
+            } else {
+                pipe("xclip", &["-selection", "clipboard"], text)
+            }
+        }
+
+        #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+        {
+            anyhow::bail!("Copying {:?} isn't supported on this platform", text)
+        }
+    }
+}
+
+/// Give `text` to a program that puts its input on the clipboard
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn pipe(program: &str, args: &[&str], text: &str) -> Result<()> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow!("Failed to run {}; install it to copy text to the clipboard: {}", program, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    if !child.wait()?.success() {
+        anyhow::bail!("{} failed to copy text to the clipboard", program);
+    }
+    Ok(())
+}
+
+/// A dictation that was copied to the clipboard
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClipboardEntry {
+    /// The text copied
+    pub text: String,
+
+    /// When it was copied
+    pub copied_at: DateTime<Local>,
+}
+
+/// Copies final text to the clipboard as it is transcribed, keeping the last dictations copied
+///
+/// Each segment is copied as it arrives, or the session's text is gathered
+/// and copied when transcription stops, as the settings say. The history
+/// outlasts the sessions, so an earlier dictation can be copied again.
+pub struct ClipboardOutput {
+    settings: ClipboardOutputSettings,
+    clipboard: Box<dyn Clipboard>,
+
+    /// Text of the session so far, when it is copied on stop
+    session: String,
+
+    /// Dictations copied, newest first
+    history: VecDeque<ClipboardEntry>,
+}
+
+impl ClipboardOutput {
+    /// Copy to the system clipboard as `settings` say
+    pub fn new(settings: ClipboardOutputSettings) -> Self {
+        Self::with_clipboard(settings, platform_clipboard())
+    }
+
+    /// Copy to `clipboard` as `settings` say
+    pub fn with_clipboard(settings: ClipboardOutputSettings, clipboard: Box<dyn Clipboard>) -> Self {
+        Self {
+            settings,
+            clipboard,
+            session: String::new(),
+            history: VecDeque::new(),
+        }
+    }
+
+    /// Start a session with `settings`, which may have changed since the last one
+    pub fn start(&mut self, settings: ClipboardOutputSettings) {
+        self.settings = settings;
+        self.session.clear();
+        self.history.truncate(self.settings.history_size.max(1));
+    }
+
+    /// Take a segment's final text, with any line or paragraph break it starts with
+    pub fn append(&mut self, text: &str) -> Result<()> {
+        if !self.settings.enabled || text.trim().is_empty() {
+            return Ok(());
+        }
+
+        match self.settings.mode {
+            ClipboardMode::EachSegment => self.copy_and_keep(text.trim().to_string()),
+            ClipboardMode::OnStop => {
+                let body = text.trim_start();
+                let separator = if self.session.is_empty() {
+                    ""
+                } else if body.len() < text.len() {
+                    &text[..text.len() - body.len()]
+                } else {
+                    " "
+                };
+                self.session.push_str(separator);
+                self.session.push_str(body);
+                Ok(())
+            }
+        }
+    }
+
+    /// Copy the session's text when transcription stops, if it is copied on stop
+    pub fn finish(&mut self) -> Result<()> {
+        let session = std::mem::take(&mut self.session);
+        if session.trim().is_empty() {
+            return Ok(());
+        }
+        self.copy_and_keep(session.trim_end().to_string())
+    }
+
+    /// Dictations copied, newest first
+    pub fn history(&self) -> Vec<ClipboardEntry> {
+        self.history.iter().cloned().collect()
+    }
+
+    /// Copy the dictation at `index` of the history again, making it the newest
+    pub fn copy_entry(&mut self, index: usize) -> Result<()> {
+        let entry = self.history.get(index)
+            .ok_or_else(|| anyhow!("No dictation {} in the clipboard history", index))?;
+        self.clipboard.copy(&entry.text)?;
+        if let Some(mut entry) = self.history.remove(index) {
+            entry.copied_at = Local::now();
+            self.history.push_front(entry);
+        }
+        Ok(())
+    }
+
+    /// Forget the dictations copied
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    fn copy_and_keep(&mut self, text: String) -> Result<()> {
+        self.clipboard.copy(&text)?;
+        self.history.push_front(ClipboardEntry { text, copied_at: Local::now() });
+        self.history.truncate(self.settings.history_size.max(1));
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows_clipboard {
+    use windows::Win32::Foundation::{HANDLE, HWND};
+    use windows::Win32::System::DataExchange::{CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData};
+    use windows::Win32::System::Memory::{GlobalAlloc, GlobalFree, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
+
+    /// Clipboard format of UTF-16 text
+    const CF_UNICODETEXT: u32 = 13;
+
+    /// Put `text` on the clipboard as UTF-16 text, which the clipboard then owns
+    pub fn copy(text: &str) -> anyhow::Result<()> {
+        let units: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+        unsafe {
+            if !OpenClipboard(HWND(0)).as_bool() {
+                anyhow::bail!("Failed to open the clipboard; another application may be using it");
+            }
+            let result = (|| -> anyhow::Result<()> {
+                EmptyClipboard();
+                let memory = GlobalAlloc(GMEM_MOVEABLE, units.len() * std::mem::size_of::<u16>())?;
+                let target = GlobalLock(memory) as *mut u16;
+                if target.is_null() {
+                    let _ = GlobalFree(memory);
+                    anyhow::bail!("Failed to copy text to the clipboard");
+                }
+                std::ptr::copy_nonoverlapping(units.as_ptr(), target, units.len());
+                GlobalUnlock(memory);
+                if let Err(e) = SetClipboardData(CF_UNICODETEXT, HANDLE(memory.0)) {
+                    let _ = GlobalFree(memory);
+                    return Err(e.into());
+                }
+                Ok(())
+            })();
+            CloseClipboard();
+            result
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    /// Records what it was asked to copy
+    struct RecordingClipboard(Arc<Mutex<Vec<String>>>);
+
+    impl Clipboard for RecordingClipboard {
+        fn copy(&self, text: &str) -> Result<()> {
+            self.0.lock().push(text.to_string());
+            Ok(())
+        }
+    }
+
+    fn output(mode: ClipboardMode, copied: &Arc<Mutex<Vec<String>>>) -> ClipboardOutput {
+        let settings = ClipboardOutputSettings { enabled: true, mode, history_size: 2 };
+        ClipboardOutput::with_clipboard(settings, Box::new(RecordingClipboard(copied.clone())))
+    }
+
+    #[test]
+    fn test_copies_segments_and_keeps_history() {
+        let copied = Arc::new(Mutex::new(Vec::new()));
+        let mut clipboard = output(ClipboardMode::EachSegment, &copied);
+        clipboard.append(" Hello there.").unwrap();
+        clipboard.append("\n\nNext topic.").unwrap();
+        clipboard.append("Last one.").unwrap();
+        clipboard.finish().unwrap();
+        assert_eq!(*copied.lock(), ["Hello there.", "Next topic.", "Last one."]);
+
+        // Only the last dictations are kept, newest first
+        let texts = |clipboard: &ClipboardOutput| clipboard.history().into_iter().map(|entry| entry.text).collect::<Vec<_>>();
+        assert_eq!(texts(&clipboard), ["Last one.", "Next topic."]);
+        clipboard.copy_entry(1).unwrap();
+        assert_eq!(copied.lock().last().unwrap(), "Next topic.");
+        assert_eq!(texts(&clipboard), ["Next topic.", "Last one."]);
+        assert!(clipboard.copy_entry(2).is_err());
+
+        clipboard.clear_history();
+        assert!(clipboard.history().is_empty());
+    }
+
+    #[test]
+    fn test_copies_session_on_stop() {
+        let copied = Arc::new(Mutex::new(Vec::new()));
+        let mut clipboard = output(ClipboardMode::OnStop, &copied);
+        clipboard.append(" Hello there.").unwrap();
+        clipboard.append("How are you?").unwrap();
+        clipboard.append("\n\nNext topic.").unwrap();
+        assert!(copied.lock().is_empty());
+
+        clipboard.finish().unwrap();
+        assert_eq!(*copied.lock(), ["Hello there. How are you?\n\nNext topic."]);
+        assert_eq!(clipboard.history().len(), 1);
+
+        // Nothing is copied while it is turned off
+        clipboard.start(ClipboardOutputSettings::default());
+        clipboard.append("Not copied.").unwrap();
+        clipboard.finish().unwrap();
+        assert_eq!(copied.lock().len(), 1);
+    }
+}
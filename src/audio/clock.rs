@@ -0,0 +1,180 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Capture time before the measured rate is trusted
+const DRIFT_WARMUP: Duration = Duration::from_secs(10);
+
+/// Largest drift accepted as genuine; anything beyond is a stall or dropout
+const MAX_DRIFT_PPM: f64 = 5_000.0;
+
+/// Measured difference between the device clock and the monotonic clock
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ClockDrift {
+    /// Audio captured, in seconds at the nominal sample rate
+    pub device_secs: f64,
+
+    /// Monotonic time since the first buffer arrived
+    pub wall_secs: f64,
+
+    /// How fast the device clock runs in parts per million (positive is fast)
+    pub drift_ppm: f64,
+
+    /// Sample rate the device actually delivers, measured against the monotonic clock
+    pub effective_sample_rate: f64,
+}
+
+/// Tracks device frames against the monotonic clock to correct timestamps
+///
+/// A device nominally running at 48 kHz may deliver slightly more or fewer
+/// frames per second. Over an hour even 50 ppm adds up to 180 ms, so
+/// timestamps derived from frame counts are computed from the measured rate.
+#[derive(Debug, Clone)]
+pub struct DriftTracker {
+    nominal_rate: f64,
+
+    /// Arrival of the first buffer
+    first: Option<Instant>,
+
+    /// Arrival of the buffer the rate is measured from and the frames delivered up to it
+    origin: Option<(Instant, u64)>,
+
+    /// Frames delivered since capture started
+    frames: u64,
+
+    /// Arrival of the most recent buffer
+    last: Option<Instant>,
+
+    /// Current estimate of the delivered sample rate
+    rate: f64,
+}
+
+impl DriftTracker {
+    /// Create a tracker for a device with the given nominal sample rate
+    pub fn new(sample_rate: u32) -> Self {
+        let nominal_rate = sample_rate.max(1) as f64;
+
+        Self {
+            nominal_rate,
+            first: None,
+            origin: None,
+            frames: 0,
+            last: None,
+            rate: nominal_rate,
+        }
+    }
+
+    /// Record a buffer of `frames` frames arriving at `now`
+    pub fn record(&mut self, frames: u64, now: Instant) {
+        self.frames += frames;
+        self.last = Some(now);
+        self.first.get_or_insert(now);
+
+        // The first buffer was captured before it arrived, so it only marks the origin
+        let (origin, origin_frames) = *self.origin.get_or_insert((now, frames));
+
+        let wall = now.saturating_duration_since(origin);
+        if wall < DRIFT_WARMUP {
+            return;
+        }
+
+        let measured = (self.frames - origin_frames) as f64 / wall.as_secs_f64();
+        let limit = self.nominal_rate * MAX_DRIFT_PPM / 1e6;
+        if (measured - self.nominal_rate).abs() > limit {
+            // Frames lost to a stall say nothing about the clock; keep the estimate
+            // so far and measure again from this buffer
+            self.origin = Some((now, self.frames));
+            return;
+        }
+
+        self.rate = measured;
+    }
+
+    /// Drift-corrected time of a frame position since capture started
+    pub fn timestamp(&self, frame: u64) -> Duration {
+        Duration::from_secs_f64(frame as f64 / self.rate)
+    }
+
+    /// Measured drift in parts per million (0 until enough audio has been seen)
+    pub fn drift_ppm(&self) -> f64 {
+        (self.rate / self.nominal_rate - 1.0) * 1e6
+    }
+
+    /// Current measurements
+    pub fn stats(&self) -> ClockDrift {
+        let wall_secs = match (self.first, self.last) {
+            (Some(first), Some(last)) => last.saturating_duration_since(first).as_secs_f64(),
+            _ => 0.0,
+        };
+
+        ClockDrift {
+            device_secs: self.frames as f64 / self.nominal_rate,
+            wall_secs,
+            drift_ppm: self.drift_ppm(),
+            effective_sample_rate: self.rate,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed `seconds` of 10 ms buffers from a device whose clock runs `ppm` fast
+    fn simulate(tracker: &mut DriftTracker, start: Instant, seconds: u64, ppm: f64) {
+        let actual_rate = 48000.0 * (1.0 + ppm / 1e6);
+        let buffers = seconds * 100;
+        for i in 0..=buffers {
+            let arrival = start + Duration::from_secs_f64(i as f64 * 480.0 / actual_rate);
+            tracker.record(480, arrival);
+        }
+    }
+
+    #[test]
+    fn test_measures_fast_device_clock() {
+        let mut tracker = DriftTracker::new(48000);
+        simulate(&mut tracker, Instant::now(), 60, 100.0);
+
+        assert!((tracker.drift_ppm() - 100.0).abs() < 1.0, "{}", tracker.drift_ppm());
+
+        // 60 s of frames at the nominal rate actually took slightly less wall time
+        let stamp = tracker.timestamp(48000 * 60).as_secs_f64();
+        assert!((stamp - 60.0 / 1.0001).abs() < 1e-4, "{}", stamp);
+    }
+
+    #[test]
+    fn test_no_correction_during_warmup() {
+        let mut tracker = DriftTracker::new(48000);
+        simulate(&mut tracker, Instant::now(), 5, 500.0);
+
+        assert_eq!(tracker.drift_ppm(), 0.0);
+        assert_eq!(tracker.timestamp(48000), Duration::from_secs(1));
+
+        let stats = tracker.stats();
+        assert!((stats.device_secs - 5.01).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_stall_is_not_drift() {
+        let start = Instant::now();
+        let mut tracker = DriftTracker::new(16000);
+        tracker.record(160, start);
+
+        // A buffer arriving after a long gap would look like a very slow clock
+        tracker.record(160, start + Duration::from_secs(30));
+        assert_eq!(tracker.drift_ppm(), 0.0);
+        assert_eq!(tracker.timestamp(16000), Duration::from_secs(1));
+        assert!((tracker.stats().wall_secs - 30.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_measures_again_after_stall() {
+        let start = Instant::now();
+        let mut tracker = DriftTracker::new(48000);
+        simulate(&mut tracker, start, 20, 100.0);
+
+        // Five seconds without audio, then the same device carries on
+        simulate(&mut tracker, start + Duration::from_secs(25), 30, 100.0);
+        assert!((tracker.drift_ppm() - 100.0).abs() < 1.0, "{}", tracker.drift_ppm());
+    }
+}
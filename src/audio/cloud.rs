@@ -0,0 +1,307 @@
+use anyhow::{Context, Result};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use log::{debug, info};
+use serde_json::Value;
+use std::io::Cursor;
+use std::time::Duration;
+
+use super::engine::{DetectedLanguage, SttEngine, TranscribeOptions, TranscriptSegment};
+use crate::config::{CloudApiStyle, CloudSettings};
+
+/// Sample rate of the audio engines are given
+const SAMPLE_RATE: u32 = 16000;
+
+/// Transcription through a hosted HTTP API
+///
+/// Each buffer is uploaded as a 16-bit WAV file. Requests run on the Tokio
+/// runtime the engine was loaded on, so `transcribe` has to be called from a
+/// blocking task rather than directly from async code.
+pub struct CloudEngine {
+    /// Endpoint, credentials and model
+    settings: CloudSettings,
+
+    /// HTTP client and the runtime it runs on, once loaded
+    client: Option<(reqwest::Client, tokio::runtime::Handle)>,
+}
+
+impl CloudEngine {
+    /// Engine for the configured API; nothing is sent until it is loaded
+    pub fn new(settings: CloudSettings) -> Self {
+        Self { settings, client: None }
+    }
+
+    /// Upload one buffer and parse the segments out of the response
+    async fn request(&self, client: &reqwest::Client, wav: Vec<u8>, options: &TranscribeOptions, duration: Duration) -> Result<Vec<TranscriptSegment>> {
+        let api_key = self.settings.api_key.as_deref().unwrap_or_default();
+
+        let request = match self.settings.api {
+            CloudApiStyle::OpenAi => {
+                // Translation is a separate endpoint next to the transcription one
+                let endpoint = if options.translate {
+                    self.settings.endpoint.replace("/audio/transcriptions", "/audio/translations")
+                } else {
+                    self.settings.endpoint.clone()
+                };
+
+                let file = reqwest::multipart::Part::bytes(wav)
+                    .file_name("audio.wav")
+                    .mime_str("audio/wav")?;
+                let mut form = reqwest::multipart::Form::new()
+                    .part("file", file)
+                    .text("model", self.settings.model.clone())
+                    .text("response_format", "verbose_json")
+                    .text("temperature", options.decoding.temperature.to_string());
+                if let (Some(language), false) = (&options.language, options.translate) {
+                    form = form.text("language", language.clone());
+                }
+
+                client.post(endpoint).bearer_auth(api_key).multipart(form)
+            }
+            CloudApiStyle::Deepgram => {
+                if options.translate {
+                    debug!("Deepgram does not translate, transcribing in the spoken language");
+                }
+
+                let mut query = vec![("model", self.settings.model.clone()), ("punctuate", "true".to_string())];
+                match &options.language {
+                    Some(language) => query.push(("language", language.clone())),
+                    None => query.push(("detect_language", "true".to_string())),
+                }
+
+                client.post(&self.settings.endpoint)
+                    .header(reqwest::header::AUTHORIZATION, format!("Token {}", api_key))
+                    .header(reqwest::header::CONTENT_TYPE, "audio/wav")
+                    .query(&query)
+                    .body(wav)
+            }
+        };
+
+        let response = request.send().await
+            .with_context(|| format!("Failed to reach {}", self.settings.endpoint))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("Transcription API returned {}: {}", status, body.trim()));
+        }
+
+        let json: Value = response.json().await.context("Invalid transcription API response")?;
+        let mut segments = match self.settings.api {
+            CloudApiStyle::OpenAi => parse_openai_response(&json, duration),
+            CloudApiStyle::Deepgram => parse_deepgram_response(&json, duration),
+        };
+
+        // Only a detected language is worth reporting
+        if options.language.is_some() {
+            for segment in &mut segments {
+                segment.language = None;
+            }
+        }
+        Ok(segments)
+    }
+}
+
+impl SttEngine for CloudEngine {
+    fn name(&self) -> &'static str {
+        "cloud"
+    }
+
+    fn load(&mut self) -> Result<()> {
+        if self.settings.api_key.as_deref().unwrap_or_default().is_empty() {
+            return Err(anyhow::anyhow!("No API key configured for cloud transcription"));
+        }
+
+        let runtime = tokio::runtime::Handle::try_current()
+            .context("Cloud transcription must be loaded on a Tokio runtime")?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(self.settings.timeout_secs as u64))
+            .build()?;
+
+        info!("Using cloud transcription at {}", self.settings.endpoint);
+        self.client = Some((client, runtime));
+        Ok(())
+    }
+
+    fn is_loaded(&self) -> bool {
+        self.client.is_some()
+    }
+
+    fn transcribe(&self, audio: &[f32], options: &TranscribeOptions) -> Result<Vec<TranscriptSegment>> {
+        let (client, runtime) = self.client.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Cloud transcription is not loaded"))?;
+
+        let wav = encode_wav(audio)?;
+        let duration = Duration::from_secs_f64(audio.len() as f64 / SAMPLE_RATE as f64);
+        runtime.block_on(self.request(client, wav, options, duration))
+    }
+
+    fn unload(&mut self) {
+        self.client = None;
+    }
+}
+
+/// Encode 16 kHz mono samples as a 16-bit WAV file
+fn encode_wav(samples: &[f32]) -> Result<Vec<u8>> {
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+
+    let mut cursor = Cursor::new(Vec::with_capacity(44 + samples.len() * 2));
+    let mut writer = WavWriter::new(&mut cursor, spec)?;
+    for sample in samples {
+        writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)?;
+    }
+    writer.finalize()?;
+
+    Ok(cursor.into_inner())
+}
+
+/// Segments from an OpenAI `verbose_json` response, or its plain text spanning the buffer
+fn parse_openai_response(json: &Value, duration: Duration) -> Vec<TranscriptSegment> {
+    let language = json["language"].as_str().map(|language| DetectedLanguage {
+        language: language.to_string(),
+        probability: None,
+    });
+
+    let mut segments: Vec<TranscriptSegment> = json["segments"].as_array()
+        .map(|segments| {
+            segments.iter()
+                .filter_map(|segment| {
+                    Some(TranscriptSegment {
+                        start: seconds(&segment["start"])?,
+                        end: seconds(&segment["end"])?,
+                        text: segment["text"].as_str()?.to_string(),
+                        // Mean log probability of the segment's tokens
+                        confidence: segment["avg_logprob"].as_f64().map(|logprob| logprob.exp().min(1.0) as f32),
+                        language: language.clone(),
+                        no_speech_probability: segment["no_speech_prob"].as_f64().map(|probability| probability as f32),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if segments.is_empty() {
+        segments = whole_buffer(json["text"].as_str(), duration);
+        for segment in &mut segments {
+            segment.language = language.clone();
+        }
+    }
+    segments
+}
+
+/// The first alternative of a Deepgram response, timed from its first to its last word
+fn parse_deepgram_response(json: &Value, duration: Duration) -> Vec<TranscriptSegment> {
+    let channel = &json["results"]["channels"][0];
+    let alternative = &channel["alternatives"][0];
+    let words = alternative["words"].as_array();
+
+    let mut segments = match (alternative["transcript"].as_str(), words.and_then(|words| words.first().zip(words.last()))) {
+        (Some(text), Some((first, last))) if !text.trim().is_empty() => vec![TranscriptSegment {
+            start: seconds(&first["start"]).unwrap_or_default(),
+            end: seconds(&last["end"]).unwrap_or(duration),
+            text: text.to_string(),
+            confidence: alternative["confidence"].as_f64().map(|confidence| confidence as f32),
+            language: None,
+            no_speech_probability: None,
+        }],
+        (text, _) => whole_buffer(text, duration),
+    };
+
+    if let Some(language) = channel["detected_language"].as_str() {
+        for segment in &mut segments {
+            segment.language = Some(DetectedLanguage {
+                language: language.to_string(),
+                probability: channel["language_confidence"].as_f64().map(|probability| probability as f32),
+            });
+        }
+    }
+    segments
+}
+
+/// A single segment covering the whole buffer, if there is any text
+fn whole_buffer(text: Option<&str>, duration: Duration) -> Vec<TranscriptSegment> {
+    match text {
+        Some(text) if !text.trim().is_empty() => vec![TranscriptSegment {
+            start: Duration::ZERO,
+            end: duration,
+            text: text.to_string(),
+            confidence: None,
+            language: None,
+            no_speech_probability: None,
+        }],
+        _ => Vec::new(),
+    }
+}
+
+/// A JSON number of seconds as a duration
+fn seconds(value: &Value) -> Option<Duration> {
+    value.as_f64().filter(|secs| *secs >= 0.0).map(Duration::from_secs_f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_wav_encoding() {
+        let wav = encode_wav(&[0.0, 1.0, -1.0, 2.0]).unwrap();
+
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(wav.len(), 44 + 4 * 2);
+        assert_eq!(i16::from_le_bytes([wav[46], wav[47]]), i16::MAX);
+        assert_eq!(i16::from_le_bytes([wav[50], wav[51]]), i16::MAX);
+    }
+
+    #[test]
+    fn test_parses_openai_responses() {
+        let verbose = json!({
+            "text": "Hello there. How are you?",
+            "language": "english",
+            "segments": [
+                { "start": 0.0, "end": 1.2, "text": " Hello there.", "avg_logprob": -0.1 },
+                { "start": 1.5, "end": 2.8, "text": " How are you?", "avg_logprob": -0.7, "no_speech_prob": 0.02 }
+            ]
+        });
+        let segments = parse_openai_response(&verbose, Duration::from_secs(3));
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[1].start, Duration::from_millis(1500));
+        assert!((segments[1].confidence.unwrap() - 0.4966).abs() < 1e-3);
+        assert_eq!(segments[1].language.as_ref().map(|l| l.language.as_str()), Some("english"));
+        assert_eq!(segments[1].no_speech_probability, Some(0.02));
+
+        let plain = json!({ "text": "Hello" });
+        let segments = parse_openai_response(&plain, Duration::from_secs(3));
+        assert_eq!(segments[0].end, Duration::from_secs(3));
+        assert_eq!(segments[0].confidence, None);
+        assert!(parse_openai_response(&json!({ "text": " " }), Duration::from_secs(3)).is_empty());
+    }
+
+    #[test]
+    fn test_parses_deepgram_responses() {
+        let response = json!({
+            "results": { "channels": [{ "detected_language": "en", "language_confidence": 0.8, "alternatives": [{
+                "transcript": "hello world",
+                "confidence": 0.93,
+                "words": [
+                    { "word": "hello", "start": 0.25, "end": 0.5 },
+                    { "word": "world", "start": 0.6, "end": 1.0 }
+                ]
+            }]}]}
+        });
+        let segments = parse_deepgram_response(&response, Duration::from_secs(3));
+
+        assert_eq!(segments, vec![TranscriptSegment {
+            start: Duration::from_millis(250),
+            end: Duration::from_secs(1),
+            text: "hello world".to_string(),
+            confidence: Some(0.93),
+            language: Some(DetectedLanguage { language: "en".to_string(), probability: Some(0.8) }),
+            no_speech_probability: None,
+        }]);
+        assert!(parse_deepgram_response(&json!({}), Duration::from_secs(3)).is_empty());
+    }
+}
@@ -0,0 +1,379 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+
+use super::voice_commands::{CommandParameter, VoiceCommand, VoiceCommandType};
+
+/// A part of a command pattern
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A word, or one of several ("word|words")
+    Word(Vec<String>),
+
+    /// A spoken or written number, e.g. "three", "twenty one" or "21"
+    Number(String),
+
+    /// Words as said, up to what follows in the pattern or the end of the command
+    Text(String),
+}
+
+/// What a pattern parameter takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParameterKind {
+    /// A number, `{name}`
+    Number,
+
+    /// Words as said, `{name:text}`
+    Text,
+}
+
+/// A command with parameters, e.g. "delete last {count} word|words"
+///
+/// Patterns are words, matched without letter case or punctuation, with `|`
+/// between alternatives; `{name}` takes a number and `{name:text}` words as
+/// said, as few as let the rest of the pattern match, or the rest of the
+/// command at its end. Patterns match from the start of the command.
+#[derive(Debug, Clone)]
+pub struct CommandPattern {
+    tokens: Vec<Token>,
+    command_type: VoiceCommandType,
+}
+
+impl CommandPattern {
+    /// Parse a pattern for commands of `command_type`
+    pub fn new(pattern: &str, command_type: VoiceCommandType) -> Result<Self> {
+        let words: Vec<&str> = pattern.split_whitespace().collect();
+        let mut tokens = Vec::with_capacity(words.len());
+        for word in &words {
+            let token = match word.strip_prefix('{').and_then(|slot| slot.strip_suffix('}')) {
+                Some(slot) => match slot.split_once(':') {
+                    Some((name, "text")) => Token::Text(name.to_string()),
+                    Some((_, kind)) => return Err(anyhow!("Unknown parameter type {} in command pattern: {}", kind, pattern)),
+                    None => Token::Number(slot.to_string()),
+                },
+                None => Token::Word(word.to_lowercase().split('|').map(|word| word.to_string()).collect()),
+            };
+
+            // Where text stops would be anyone's guess
+            if matches!(tokens.last(), Some(Token::Text(_))) && !matches!(token, Token::Word(_)) {
+                return Err(anyhow!("Text must be followed by a word or end a command pattern: {}", pattern));
+            }
+            tokens.push(token);
+        }
+
+        if !matches!(tokens.first(), Some(Token::Word(_))) {
+            return Err(anyhow!("Command patterns start with a word: {}", pattern));
+        }
+        Ok(Self { tokens, command_type })
+    }
+
+    /// Whether a command trigger has parameters, and so is a pattern
+    pub fn is_pattern(trigger: &str) -> bool {
+        trigger.contains('{')
+    }
+
+    /// The kind of command the pattern gives
+    pub fn command_type(&self) -> &VoiceCommandType {
+        &self.command_type
+    }
+
+    /// What to say, written as a pattern with `{name}` for each parameter
+    pub fn trigger(&self) -> String {
+        let words: Vec<String> = self.tokens.iter()
+            .map(|token| match token {
+                Token::Word(alternatives) => alternatives.join("|"),
+                Token::Number(name) | Token::Text(name) => format!("{{{}}}", name),
+            })
+            .collect();
+        words.join(" ")
+    }
+
+    /// The parameters, in the order they are said, with what they take
+    pub fn parameters(&self) -> Vec<(&str, ParameterKind)> {
+        self.tokens.iter()
+            .filter_map(|token| match token {
+                Token::Number(name) => Some((name.as_str(), ParameterKind::Number)),
+                Token::Text(name) => Some((name.as_str(), ParameterKind::Text)),
+                Token::Word(_) => None,
+            })
+            .collect()
+    }
+
+    /// What the parameter `name` takes, if the pattern has it
+    pub fn parameter_kind(&self, name: &str) -> Option<ParameterKind> {
+        self.tokens.iter().find_map(|token| match token {
+            Token::Number(slot) if slot == name => Some(ParameterKind::Number),
+            Token::Text(slot) if slot == name => Some(ParameterKind::Text),
+            _ => None,
+        })
+    }
+
+    /// The command `text` gives, with its parameters, if it matches
+    pub fn parse(&self, text: &str) -> Option<VoiceCommand> {
+        let command = VoiceCommand::new(self.command_type.clone(), text);
+        self.parse_from(text, &words(text), 0, 0, command)
+    }
+
+    /// Match the pattern from token `token` on against the words from `i` on
+    fn parse_from(&self, text: &str, words: &[Word], token: usize, i: usize, command: VoiceCommand) -> Option<VoiceCommand> {
+        let Some(current) = self.tokens.get(token) else {
+            // The command runs to the end of the last word matched
+            let end = i.checked_sub(1).map_or(0, |last| words[last].end);
+            return Some(VoiceCommand { span: Some(0..end), ..command });
+        };
+
+        match current {
+            Token::Word(alternatives) => {
+                let word = words.get(i)?;
+                if !alternatives.contains(&word.text) {
+                    return None;
+                }
+                self.parse_from(text, words, token + 1, i + 1, command)
+            }
+            Token::Number(name) => {
+                let spoken: Vec<&str> = words[i..].iter().map(|word| word.text.as_str()).collect();
+                let (number, used) = parse_number(&spoken)?;
+                let command = command.with_parameter(name, CommandParameter::Number(number));
+                self.parse_from(text, words, token + 1, i + used, command)
+            }
+            Token::Text(name) => {
+                let start = words.get(i)?.start;
+                let at_end = token + 1 == self.tokens.len();
+
+                // The fewest words that let the rest of the pattern match, or the rest at the end
+                let ends = if at_end { words.len()..=words.len() } else { i + 1..=words.len() };
+                ends.into_iter().find_map(|end| {
+                    let stop = if at_end { text.len() } else { words[end - 1].end };
+                    let said = text[start..stop].trim_matches(|c: char| !c.is_alphanumeric());
+                    let command = command.clone().with_parameter(name, CommandParameter::Text(said.to_string()));
+                    self.parse_from(text, words, token + 1, end, command)
+                })
+            }
+        }
+    }
+}
+
+/// The commands with parameters understood, tried in order
+#[derive(Debug, Clone)]
+pub struct CommandGrammar {
+    patterns: Vec<CommandPattern>,
+}
+
+impl CommandGrammar {
+    /// The built-in commands with parameters
+    pub fn new() -> Self {
+        let patterns = [
+            ("delete last {count} word|words", VoiceCommandType::Delete),
+            ("delete {count} word|words", VoiceCommandType::Delete),
+            ("go to line {line}", VoiceCommandType::GoToLine),
+            ("correct {original:text} to {replacement:text}", VoiceCommandType::Replace),
+            ("replace {original:text} with {replacement:text}", VoiceCommandType::Replace),
+            ("switch to {profile:text} profile", VoiceCommandType::SwitchProfile),
+            ("select all", VoiceCommandType::Select),
+            ("select last word|sentence|paragraph", VoiceCommandType::Select),
+            ("select from {from:text} to {to:text}", VoiceCommandType::Select),
+            ("select {phrase:text}", VoiceCommandType::Select),
+        ];
+        Self {
+            patterns: patterns.into_iter()
+                .map(|(pattern, command_type)| CommandPattern::new(pattern, command_type).expect("built-in command patterns are valid"))
+                .collect(),
+        }
+    }
+
+    /// The commands opening applications and switching windows
+    pub fn app_control() -> Self {
+        let patterns = [
+            ("open {app:text}", VoiceCommandType::LaunchApp),
+            ("launch {app:text}", VoiceCommandType::LaunchApp),
+            ("switch to {window:text}", VoiceCommandType::SwitchWindow),
+        ];
+        Self {
+            patterns: patterns.into_iter()
+                .map(|(pattern, command_type)| CommandPattern::new(pattern, command_type).expect("built-in command patterns are valid"))
+                .collect(),
+        }
+    }
+
+    /// Add a pattern, tried before the ones added earlier
+    pub fn add(&mut self, pattern: CommandPattern) {
+        self.patterns.insert(0, pattern);
+    }
+
+    /// The command `text` gives, from the first pattern it matches
+    pub fn parse(&self, text: &str) -> Option<VoiceCommand> {
+        self.patterns.iter().find_map(|pattern| pattern.parse(text))
+    }
+
+    /// The patterns, in the order they are tried
+    pub fn patterns(&self) -> &[CommandPattern] {
+        &self.patterns
+    }
+}
+
+impl Default for CommandGrammar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A word of a command, lowercase and without punctuation
+struct Word {
+    text: String,
+
+    /// Where the word starts and ends in the command, punctuation included
+    start: usize,
+    end: usize,
+}
+
+/// Words of `text`
+///
+/// Hyphens separate words, so "twenty-one" is read as two.
+fn words(text: &str) -> Vec<Word> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        let separator = c.is_whitespace() || c == '-';
+        match start {
+            Some(from) if separator => {
+                let word: String = text[from..i].chars().filter(|c| c.is_alphanumeric()).collect();
+                if !word.is_empty() {
+                    words.push(Word { text: word.to_lowercase(), start: from, end: i });
+                }
+                start = None;
+            }
+            None if !separator => start = Some(i),
+            _ => {}
+        }
+    }
+    words
+}
+
+/// A number at the start of `words`, written or spoken up to "nine hundred and ninety nine",
+/// and how many words it took
+pub fn parse_number(words: &[&str]) -> Option<(u32, usize)> {
+    let first = *words.first()?;
+    if let Ok(number) = first.parse::<u32>() {
+        return Some((number, 1));
+    }
+
+    let mut number = 0;
+    let mut i = 0;
+    if words.get(1) == Some(&"hundred") {
+        let hundreds = if first == "a" { 1 } else { small_number(first).filter(|n| (1..10).contains(n))? };
+        number = hundreds * 100;
+        i = 2;
+        if words.get(i) == Some(&"and") && below_hundred(&words[i + 1..]).is_some() {
+            i += 1;
+        }
+    }
+
+    match below_hundred(&words[i..]) {
+        Some((rest, used)) => Some((number + rest, i + used)),
+        None if i > 0 => Some((number, i)),
+        None => None,
+    }
+}
+
+/// A number below a hundred at the start of `words`, e.g. "seven" or "forty two"
+fn below_hundred(words: &[&str]) -> Option<(u32, usize)> {
+    let first = *words.first()?;
+    if let Some(tens) = tens(first) {
+        return match words.get(1).and_then(|word| small_number(word)).filter(|n| (1..10).contains(n)) {
+            Some(units) => Some((tens + units, 2)),
+            None => Some((tens, 1)),
+        };
+    }
+    small_number(first).map(|number| (number, 1))
+}
+
+fn small_number(word: &str) -> Option<u32> {
+    const SMALL: [&str; 20] = [
+        "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+        "eleven", "twelve", "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen", "nineteen",
+    ];
+    SMALL.iter().position(|small| *small == word).map(|n| n as u32)
+}
+
+fn tens(word: &str) -> Option<u32> {
+    const TENS: [&str; 8] = ["twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety"];
+    TENS.iter().position(|tens| *tens == word).map(|n| (n as u32 + 2) * 10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(spoken: &str) -> Option<(u32, usize)> {
+        let words: Vec<String> = words(spoken).into_iter().map(|word| word.text).collect();
+        parse_number(&words.iter().map(String::as_str).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_parses_numbers() {
+        assert_eq!(number("3 words"), Some((3, 1)));
+        assert_eq!(number("three words"), Some((3, 1)));
+        assert_eq!(number("Nineteen"), Some((19, 1)));
+        assert_eq!(number("twenty-one words"), Some((21, 2)));
+        assert_eq!(number("forty words"), Some((40, 1)));
+        assert_eq!(number("a hundred"), Some((100, 2)));
+        assert_eq!(number("two hundred and five words"), Some((205, 4)));
+        assert_eq!(number("two hundred and words"), Some((200, 2)));
+        assert_eq!(number("nine hundred ninety nine"), Some((999, 4)));
+        assert_eq!(number("some words"), None);
+        assert_eq!(number(""), None);
+    }
+
+    #[test]
+    fn test_parses_commands() {
+        let grammar = CommandGrammar::new();
+
+        let command = grammar.parse("Delete last three words.").unwrap();
+        assert_eq!(command.command_type, VoiceCommandType::Delete);
+        assert_eq!(command.number("count"), Some(3));
+
+        assert_eq!(grammar.parse("delete 1 word").unwrap().number("count"), Some(1));
+        assert_eq!(grammar.parse("go to line twenty-two").unwrap().number("line"), Some(22));
+
+        let command = grammar.parse("select \"quarterly report\"").unwrap();
+        assert_eq!(command.command_type, VoiceCommandType::Select);
+        assert_eq!(command.text("phrase"), Some("quarterly report"));
+
+        let command = grammar.parse("select from the report to the end.").unwrap();
+        assert_eq!((command.text("from"), command.text("to")), (Some("the report"), Some("the end")));
+        assert!(grammar.parse("select all").unwrap().parameters.is_empty());
+
+        let command = grammar.parse("Correct gradel to Gradle.").unwrap();
+        assert_eq!(command.command_type, VoiceCommandType::Replace);
+        assert_eq!((command.text("original"), command.text("replacement")), (Some("gradel"), Some("Gradle")));
+        let command = grammar.parse("replace their with there").unwrap();
+        assert_eq!((command.text("original"), command.text("replacement")), (Some("their"), Some("there")));
+
+        // Commands without their parameters, or not at the start, are left to the plain triggers
+        assert!(grammar.parse("delete last word").is_none());
+        assert!(grammar.parse("go to line").is_none());
+        assert!(grammar.parse("select").is_none());
+        assert!(grammar.parse("we should delete three words").is_none());
+    }
+
+    #[test]
+    fn test_custom_patterns() {
+        let mut grammar = CommandGrammar::new();
+        grammar.add(CommandPattern::new("indent {count} times", VoiceCommandType::Custom("indent".to_string())).unwrap());
+        let command = grammar.parse("indent four times").unwrap();
+        assert_eq!(command.command_type, VoiceCommandType::Custom("indent".to_string()));
+        assert_eq!(command.number("count"), Some(4));
+
+        assert!(CommandPattern::new("{count} times", VoiceCommandType::Delete).is_err());
+        assert!(CommandPattern::new("find {what:text} {n}", VoiceCommandType::Select).is_err());
+        assert!(CommandPattern::new("find {what:text} now", VoiceCommandType::Select).is_ok());
+        assert!(CommandPattern::new("find {what:date}", VoiceCommandType::Select).is_err());
+    }
+
+    #[test]
+    fn test_describes_patterns() {
+        let pattern = CommandPattern::new("Move {what:text} down {count} line|lines", VoiceCommandType::Custom("move".to_string())).unwrap();
+        assert_eq!(pattern.trigger(), "move {what} down {count} line|lines");
+        assert_eq!(pattern.parameters(), [("what", ParameterKind::Text), ("count", ParameterKind::Number)]);
+    }
+}
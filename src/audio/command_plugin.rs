@@ -0,0 +1,389 @@
+use anyhow::{anyhow, Context, Result};
+use log::{info, warn};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use wasmi::{Caller, Config, Engine, Extern, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc};
+
+use super::command_grammar::CommandPattern;
+use super::keystroke::KeyChord;
+use super::voice_commands::{CommandParameter, VoiceCommand, VoiceCommandType};
+
+/// Module the host functions are imported from
+const HOST_MODULE: &str = "bestme";
+
+/// Fuel a plugin gets for each call, so one that never returns is stopped
+const FUEL_PER_CALL: u64 = 10_000_000;
+
+/// Most memory a plugin can have, in bytes
+const MAX_MEMORY_BYTES: usize = 16 * 1024 * 1024;
+
+/// What a plugin sees and what it did, while one of its functions runs
+#[derive(Debug, Default)]
+struct HostState {
+    /// Triggers registered by `init`, by id
+    patterns: Vec<CommandPattern>,
+
+    /// The text being edited
+    text: String,
+
+    /// What was said for the command being run
+    trigger_text: String,
+
+    /// Parameters spoken with the command being run
+    parameters: BTreeMap<String, CommandParameter>,
+
+    /// What the plugin did, in order
+    steps: Vec<VoiceCommand>,
+
+    /// How much memory the plugin may take
+    limits: StoreLimits,
+}
+
+impl HostState {
+    /// A step for what the plugin did
+    fn push(&mut self, command_type: VoiceCommandType, parameters: Vec<(&str, CommandParameter)>) {
+        let step = parameters.into_iter().fold(
+            VoiceCommand::new(command_type, &self.trigger_text),
+            |step, (name, value)| step.with_parameter(name, value),
+        );
+        self.steps.push(step);
+    }
+
+    /// A spoken parameter as text, with numbers in digits
+    fn parameter(&self, name: &str) -> Option<String> {
+        match self.parameters.get(name)? {
+            CommandParameter::Number(number) => Some(number.to_string()),
+            CommandParameter::Text(text) => Some(text.clone()),
+        }
+    }
+}
+
+/// Voice commands implemented by a WebAssembly module
+///
+/// The module exports its `memory`, `init()`, which registers its commands,
+/// and `on_command(id)`, run when one of them is said. It imports these from
+/// `bestme`, with strings passed as a pointer to UTF-8 and a length in bytes:
+///
+/// - `register_command(ptr, len) -> id` registers a trigger, a command pattern
+///   such as "shout {what:text}"; -1 when it isn't valid
+/// - `text_len() -> len` and `read_text(ptr, len) -> copied` read the text being edited
+/// - `parameter_len(name_ptr, name_len) -> len`, -1 when it wasn't said, and
+///   `read_parameter(name_ptr, name_len, ptr, len) -> copied` read a spoken parameter
+/// - `insert_text(ptr, len)`, `delete_words(count)` and
+///   `replace_text(original_ptr, original_len, replacement_ptr, replacement_len)` edit the text
+/// - `press_keys(ptr, len) -> status` presses a key chord such as "ctrl+s"; -1 when it isn't one
+///
+/// The edits and keys are carried out once `on_command` returns, as a macro.
+pub struct CommandPlugin {
+    /// Name of the plugin, from its file
+    name: String,
+
+    store: Store<HostState>,
+
+    on_command: TypedFunc<i32, ()>,
+}
+
+impl CommandPlugin {
+    /// Start a plugin from a WebAssembly module, registering its commands
+    pub fn new(name: &str, wasm: &[u8]) -> Result<Self> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, wasm).map_err(|e| anyhow!("Plugin {} isn't valid WebAssembly: {}", name, e))?;
+
+        let mut linker = Linker::new(&engine);
+        link_host_functions(&mut linker).map_err(|e| anyhow!("Failed to set up plugin {}: {}", name, e))?;
+        let limits = StoreLimitsBuilder::new().memory_size(MAX_MEMORY_BYTES).build();
+        let mut store = Store::new(&engine, HostState { limits, ..HostState::default() });
+        store.limiter(|state| &mut state.limits);
+        let instance = linker.instantiate(&mut store, &module)
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(|e| anyhow!("Failed to start plugin {}: {}", name, e))?;
+
+        let on_command = instance.get_typed_func::<i32, ()>(&store, "on_command")
+            .map_err(|_| anyhow!("Plugin {} doesn't export on_command(id)", name))?;
+        let init = instance.get_typed_func::<(), ()>(&store, "init")
+            .map_err(|_| anyhow!("Plugin {} doesn't export init()", name))?;
+        store.set_fuel(FUEL_PER_CALL).map_err(|e| anyhow!("{}", e))?;
+        init.call(&mut store, ()).map_err(|e| anyhow!("Plugin {} failed to start: {}", name, e))?;
+
+        Ok(Self {
+            name: name.to_string(),
+            store,
+            on_command,
+        })
+    }
+
+    /// Start the plugin in a `.wasm` file, named after the file
+    pub fn load(path: &Path) -> Result<Self> {
+        let name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        let wasm = std::fs::read(path).with_context(|| format!("Failed to read plugin {}", path.display()))?;
+        Self::new(&name, &wasm)
+    }
+
+    /// Start every plugin in `dir`, in order of their file names, leaving out the ones that fail
+    pub fn load_dir(dir: &Path) -> Vec<Self> {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Failed to read plugin directory {}: {}", dir.display(), e);
+                return Vec::new();
+            }
+        };
+        let mut paths: Vec<PathBuf> = entries.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "wasm"))
+            .collect();
+        paths.sort();
+
+        paths.iter()
+            .filter_map(|path| match Self::load(path) {
+                Ok(plugin) => {
+                    info!("Loaded voice command plugin {} with {} commands", plugin.name, plugin.patterns().len());
+                    Some(plugin)
+                }
+                Err(e) => {
+                    warn!("Skipping voice command plugin: {}", e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Name of the plugin
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Triggers of the plugin's commands
+    pub fn patterns(&self) -> &[CommandPattern] {
+        &self.store.data().patterns
+    }
+
+    /// Run the plugin's command `text` matches, if any, on `current_text`
+    ///
+    /// What it did is given as a macro, with the spoken parameters and the
+    /// plugin's name as "plugin".
+    pub fn run(&mut self, text: &str, current_text: &str) -> Option<Result<VoiceCommand>> {
+        let (id, spoken) = self.patterns().iter()
+            .enumerate()
+            .find_map(|(id, pattern)| pattern.parse(text).map(|spoken| (id, spoken)))?;
+        Some(self.call(id, spoken, current_text))
+    }
+
+    fn call(&mut self, id: usize, spoken: VoiceCommand, current_text: &str) -> Result<VoiceCommand> {
+        let state = self.store.data_mut();
+        state.text = current_text.to_string();
+        state.trigger_text = spoken.trigger_text.clone();
+        state.parameters = spoken.parameters.clone();
+        state.steps.clear();
+
+        self.store.set_fuel(FUEL_PER_CALL).map_err(|e| anyhow!("{}", e))?;
+        self.on_command.call(&mut self.store, id as i32)
+            .map_err(|e| anyhow!("Plugin {} failed: {}", self.name, e))?;
+
+        let steps = std::mem::take(&mut self.store.data_mut().steps);
+        let mut command = VoiceCommand::new(VoiceCommandType::Macro, &spoken.trigger_text).with_steps(steps);
+        command.parameters = spoken.parameters;
+        command.span = spoken.span;
+        Ok(command.with_parameter("plugin", CommandParameter::Text(self.name.clone())))
+    }
+}
+
+/// The memory the plugin exports
+fn memory(caller: &Caller<'_, HostState>) -> Result<Memory, wasmi::Error> {
+    caller.get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| wasmi::Error::new("the plugin doesn't export its memory"))
+}
+
+/// The string of `len` bytes at `ptr` in the plugin's memory
+fn read_str(caller: &Caller<'_, HostState>, ptr: i32, len: i32) -> Result<String, wasmi::Error> {
+    let mut bytes = vec![0; len.max(0) as usize];
+    memory(caller)?.read(caller, ptr as u32 as usize, &mut bytes)?;
+    String::from_utf8(bytes).map_err(|_| wasmi::Error::new("strings from plugins must be UTF-8"))
+}
+
+/// Copy as much of `bytes` as fits in `len` bytes at `ptr`, giving how many were copied
+fn write_bytes(caller: &mut Caller<'_, HostState>, bytes: &[u8], ptr: i32, len: i32) -> Result<i32, wasmi::Error> {
+    let copied = bytes.len().min(len.max(0) as usize);
+    memory(caller)?.write(caller, ptr as u32 as usize, &bytes[..copied])?;
+    Ok(copied as i32)
+}
+
+fn link_host_functions(linker: &mut Linker<HostState>) -> Result<(), wasmi::Error> {
+    linker.func_wrap(HOST_MODULE, "register_command", |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+        let trigger = read_str(&caller, ptr, len)?;
+        let patterns = &mut caller.data_mut().patterns;
+        match CommandPattern::new(&trigger, VoiceCommandType::Macro) {
+            Ok(pattern) => {
+                patterns.push(pattern);
+                Ok(patterns.len() as i32 - 1)
+            }
+            Err(e) => {
+                warn!("Plugin command left out: {}", e);
+                Ok(-1)
+            }
+        }
+    })?;
+
+    linker.func_wrap(HOST_MODULE, "text_len", |caller: Caller<'_, HostState>| caller.data().text.len() as i32)?;
+
+    linker.func_wrap(HOST_MODULE, "read_text", |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+        let text = caller.data().text.clone();
+        write_bytes(&mut caller, text.as_bytes(), ptr, len)
+    })?;
+
+    linker.func_wrap(HOST_MODULE, "parameter_len", |caller: Caller<'_, HostState>, name_ptr: i32, name_len: i32| {
+        let name = read_str(&caller, name_ptr, name_len)?;
+        Ok(caller.data().parameter(&name).map_or(-1, |value| value.len() as i32))
+    })?;
+
+    linker.func_wrap(
+        HOST_MODULE,
+        "read_parameter",
+        |mut caller: Caller<'_, HostState>, name_ptr: i32, name_len: i32, ptr: i32, len: i32| {
+            let name = read_str(&caller, name_ptr, name_len)?;
+            let value = caller.data().parameter(&name).unwrap_or_default();
+            write_bytes(&mut caller, value.as_bytes(), ptr, len)
+        },
+    )?;
+
+    linker.func_wrap(HOST_MODULE, "insert_text", |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+        let text = read_str(&caller, ptr, len)?;
+        caller.data_mut().push(VoiceCommandType::InsertText, vec![("text", CommandParameter::Text(text))]);
+        Ok(())
+    })?;
+
+    linker.func_wrap(HOST_MODULE, "delete_words", |mut caller: Caller<'_, HostState>, count: i32| {
+        let count = CommandParameter::Number(count.max(0) as u32);
+        caller.data_mut().push(VoiceCommandType::Delete, vec![("count", count)]);
+    })?;
+
+    linker.func_wrap(
+        HOST_MODULE,
+        "replace_text",
+        |mut caller: Caller<'_, HostState>, original_ptr: i32, original_len: i32, replacement_ptr: i32, replacement_len: i32| {
+            let original = read_str(&caller, original_ptr, original_len)?;
+            let replacement = read_str(&caller, replacement_ptr, replacement_len)?;
+            caller.data_mut().push(VoiceCommandType::Replace, vec![
+                ("original", CommandParameter::Text(original)),
+                ("replacement", CommandParameter::Text(replacement)),
+            ]);
+            Ok(())
+        },
+    )?;
+
+    linker.func_wrap(HOST_MODULE, "press_keys", |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+        let keys = read_str(&caller, ptr, len)?;
+        if let Err(e) = KeyChord::parse(&keys) {
+            warn!("Plugin keys left out: {}", e);
+            return Ok(-1);
+        }
+        caller.data_mut().push(VoiceCommandType::Keystroke, vec![("keys", CommandParameter::Text(keys))]);
+        Ok(0)
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A plugin with "shout {what:text}", inserting what was said with "!",
+    /// and "save and close", which presses ctrl+s then an invalid chord
+    const PLUGIN: &str = r#"
+        (module
+            (import "bestme" "register_command" (func $register_command (param i32 i32) (result i32)))
+            (import "bestme" "parameter_len" (func $parameter_len (param i32 i32) (result i32)))
+            (import "bestme" "read_parameter" (func $read_parameter (param i32 i32 i32 i32) (result i32)))
+            (import "bestme" "insert_text" (func $insert_text (param i32 i32)))
+            (import "bestme" "press_keys" (func $press_keys (param i32 i32) (result i32)))
+            (import "bestme" "text_len" (func $text_len (result i32)))
+            (import "bestme" "delete_words" (func $delete_words (param i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "shout {what:text}")
+            (data (i32.const 32) "save and close")
+            (data (i32.const 64) "what")
+            (data (i32.const 96) "ctrl+s")
+            (data (i32.const 112) "ctrl+nope")
+            (func (export "init")
+                (drop (call $register_command (i32.const 0) (i32.const 17)))
+                (drop (call $register_command (i32.const 32) (i32.const 14))))
+            (func (export "on_command") (param $id i32) (local $len i32)
+                (if (i32.eqz (local.get $id))
+                    (then
+                        (local.set $len (call $read_parameter (i32.const 64) (i32.const 4) (i32.const 256)
+                            (call $parameter_len (i32.const 64) (i32.const 4))))
+                        (i32.store8 (i32.add (i32.const 256) (local.get $len)) (i32.const 33))
+                        (call $insert_text (i32.const 256) (i32.add (local.get $len) (i32.const 1))))
+                    (else
+                        (if (call $text_len) (then (call $delete_words (i32.const 1))))
+                        (drop (call $press_keys (i32.const 96) (i32.const 6)))
+                        (drop (call $press_keys (i32.const 112) (i32.const 9)))))))
+    "#;
+
+    #[test]
+    fn test_runs_plugin_commands() {
+        let wasm = wat::parse_str(PLUGIN).unwrap();
+        let mut plugin = CommandPlugin::new("shouting", &wasm).unwrap();
+        assert_eq!(plugin.name(), "shouting");
+        assert_eq!(plugin.patterns().len(), 2);
+
+        let command = plugin.run("shout hello there", "").unwrap().unwrap();
+        assert_eq!(command.command_type, VoiceCommandType::Macro);
+        assert_eq!(command.text("plugin"), Some("shouting"));
+        assert_eq!(command.steps.len(), 1);
+        assert_eq!(command.steps[0].command_type, VoiceCommandType::InsertText);
+        assert_eq!(command.steps[0].text("text"), Some("hello there!"));
+
+        // Only valid chords are pressed, and it sees the text being edited
+        let steps = |command: VoiceCommand| command.steps.into_iter().map(|step| step.command_type).collect::<Vec<_>>();
+        let command = plugin.run("save and close", "").unwrap().unwrap();
+        assert_eq!(steps(command), [VoiceCommandType::Keystroke]);
+        let command = plugin.run("save and close", "Draft").unwrap().unwrap();
+        assert_eq!(steps(command), [VoiceCommandType::Delete, VoiceCommandType::Keystroke]);
+
+        assert!(plugin.run("whisper hello", "").is_none());
+    }
+
+    #[test]
+    fn test_stops_runaway_plugins() {
+        let wasm = wat::parse_str(r#"
+            (module
+                (import "bestme" "register_command" (func $register_command (param i32 i32) (result i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "spin")
+                (func (export "init") (drop (call $register_command (i32.const 0) (i32.const 4))))
+                (func (export "on_command") (param i32) (loop $forever (br $forever))))
+        "#).unwrap();
+        let mut plugin = CommandPlugin::new("spinner", &wasm).unwrap();
+        assert!(plugin.run("spin", "").unwrap().is_err());
+
+        let error = CommandPlugin::new("broken", b"not wasm").err().unwrap().to_string();
+        assert!(error.starts_with("Plugin broken isn't valid WebAssembly"));
+    }
+
+    #[test]
+    fn test_limits_plugin_memory() {
+        // 512 pages of 64 KiB is 32 MiB, over the limit
+        let wasm = wat::parse_str(r#"
+            (module
+                (memory (export "memory") 512)
+                (func (export "init"))
+                (func (export "on_command") (param i32)))
+        "#).unwrap();
+        let error = CommandPlugin::new("hungry", &wasm).err().unwrap().to_string();
+        assert!(error.starts_with("Failed to start plugin hungry"), "{}", error);
+
+        // Growing past the limit fails inside the plugin, which traps here
+        let wasm = wat::parse_str(r#"
+            (module
+                (memory (export "memory") 1)
+                (func (export "init") (if (i32.eq (memory.grow (i32.const 511)) (i32.const -1)) (then unreachable)))
+                (func (export "on_command") (param i32)))
+        "#).unwrap();
+        let error = CommandPlugin::new("growing", &wasm).err().unwrap().to_string();
+        assert!(error.starts_with("Plugin growing failed to start"), "{}", error);
+    }
+}
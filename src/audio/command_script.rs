@@ -0,0 +1,410 @@
+use anyhow::{anyhow, Result};
+
+use super::command_grammar::{CommandPattern, ParameterKind};
+use super::keystroke::KeyChord;
+use super::voice_commands::{CommandParameter, VoiceCommand, VoiceCommandType};
+
+/// A value given to an action
+#[derive(Debug, Clone, PartialEq)]
+enum Argument {
+    /// A number written in the script
+    Number(u32),
+
+    /// A quoted string written in the script
+    Text(String),
+
+    /// A parameter of the trigger, by name
+    Parameter(String),
+}
+
+/// A part of a script line
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Text(String),
+    Number(u32),
+    Name(String),
+    Arrow,
+    Open,
+    Close,
+    Comma,
+    Semicolon,
+}
+
+/// The name of the parameter an action gives its command, and what it takes
+type ActionParameter = (&'static str, ParameterKind);
+
+/// An action a script can run: its name, the command it gives, and the parameter it takes, if any
+const ACTIONS: [(&str, VoiceCommandType, Option<ActionParameter>); 20] = [
+    ("insert_text", VoiceCommandType::InsertText, Some(("text", ParameterKind::Text))),
+    ("delete_words", VoiceCommandType::Delete, Some(("count", ParameterKind::Number))),
+    ("go_to_line", VoiceCommandType::GoToLine, Some(("line", ParameterKind::Number))),
+    ("select", VoiceCommandType::Select, Some(("phrase", ParameterKind::Text))),
+    ("undo", VoiceCommandType::Undo, None),
+    ("redo", VoiceCommandType::Redo, None),
+    ("capitalize", VoiceCommandType::Capitalize, None),
+    ("lowercase", VoiceCommandType::Lowercase, None),
+    ("uppercase", VoiceCommandType::Uppercase, None),
+    ("new_line", VoiceCommandType::NewLine, None),
+    ("new_paragraph", VoiceCommandType::NewParagraph, None),
+    ("period", VoiceCommandType::Period, None),
+    ("comma", VoiceCommandType::Comma, None),
+    ("question_mark", VoiceCommandType::QuestionMark, None),
+    ("exclamation_mark", VoiceCommandType::ExclamationMark, None),
+    ("pause", VoiceCommandType::Pause, None),
+    ("resume", VoiceCommandType::Resume, None),
+    ("stop", VoiceCommandType::Stop, None),
+    ("help", VoiceCommandType::Help, None),
+    ("key", VoiceCommandType::Keystroke, Some(("keys", ParameterKind::Text))),
+];
+
+/// An action run by a scripted command
+#[derive(Debug, Clone)]
+struct ScriptedAction {
+    command_type: VoiceCommandType,
+
+    /// Parameters of the command, by name, with the values they get
+    arguments: Vec<(String, Argument)>,
+
+    /// Whether every spoken parameter is passed on, as for `custom` actions
+    pass_parameters: bool,
+}
+
+impl ScriptedAction {
+    /// The command the action gives, with the parameters spoken in `spoken`
+    fn command(&self, spoken: &VoiceCommand) -> Option<VoiceCommand> {
+        let mut command = VoiceCommand::new(self.command_type.clone(), &spoken.trigger_text);
+        if self.pass_parameters {
+            command.parameters = spoken.parameters.clone();
+        }
+
+        for (name, argument) in &self.arguments {
+            let value = match argument {
+                Argument::Number(number) => CommandParameter::Number(*number),
+                Argument::Text(text) => CommandParameter::Text(text.clone()),
+                Argument::Parameter(parameter) => spoken.parameters.get(parameter)?.clone(),
+            };
+            command = command.with_parameter(name, value);
+        }
+        Some(command)
+    }
+}
+
+/// A command defined in a script
+#[derive(Debug, Clone)]
+pub struct ScriptedCommand {
+    pattern: CommandPattern,
+
+    /// The actions run, in order; more than one make a macro
+    actions: Vec<ScriptedAction>,
+}
+
+impl ScriptedCommand {
+    /// The command `text` gives, with its parameters, if it matches the trigger
+    pub fn detect(&self, text: &str) -> Option<VoiceCommand> {
+        let spoken = self.pattern.parse(text)?;
+        let command = match self.actions.as_slice() {
+            [action] => action.command(&spoken)?,
+            actions => {
+                let steps = actions.iter().map(|action| action.command(&spoken)).collect::<Option<Vec<_>>>()?;
+                VoiceCommand::new(VoiceCommandType::Macro, text).with_steps(steps)
+            }
+        };
+        Some(VoiceCommand { span: spoken.span, ..command })
+    }
+
+    /// What to say for it, with its parameters
+    pub fn pattern(&self) -> &CommandPattern {
+        &self.pattern
+    }
+
+    /// The kind of command it gives
+    pub fn command_type(&self) -> &VoiceCommandType {
+        match self.actions.as_slice() {
+            [action] => &action.command_type,
+            _ => &VoiceCommandType::Macro,
+        }
+    }
+}
+
+/// Commands defined by the user, one per line, as a trigger and the action it runs:
+///
+/// ```text
+/// # Lines starting with # are comments
+/// "insert signature" => insert_text("Best regards,\nAlex")
+/// "delete {n} words" => delete_words(n)
+/// "scratch that" => undo()
+/// "indent {count} times" => custom("indent")
+/// "deploy {branch:text}" => run("deploy")
+/// "wrap it up" => insert_text("That's all for today."); new_paragraph(); stop()
+/// ```
+///
+/// Triggers are command patterns, so `{name}` takes a number and `{name:text}`
+/// the rest of the command; actions take them by name, or strings and numbers.
+/// `custom("name")` gives a custom command with all the spoken parameters, and
+/// `key("ctrl+s")` a keystroke for the application to press. `run("name")`
+/// runs the program configured as that shell command, filling its arguments
+/// from the spoken parameters. Several actions separated by `;` make a macro,
+/// run in order and undone together.
+#[derive(Debug, Clone, Default)]
+pub struct CommandScript {
+    commands: Vec<ScriptedCommand>,
+}
+
+impl CommandScript {
+    /// Parse a script, with the line of the first mistake in it when it isn't valid
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut commands = Vec::new();
+        for (i, line) in source.lines().enumerate() {
+            let command = parse_line(line).map_err(|e| anyhow!("line {}: {}", i + 1, e))?;
+            commands.extend(command);
+        }
+        Ok(Self { commands })
+    }
+
+    /// The command `text` gives, from the first scripted command it matches
+    pub fn detect(&self, text: &str) -> Option<VoiceCommand> {
+        self.commands.iter().find_map(|command| command.detect(text))
+    }
+
+    /// The commands defined
+    pub fn commands(&self) -> &[ScriptedCommand] {
+        &self.commands
+    }
+}
+
+/// Parse one line of a script, which can be blank or a comment
+fn parse_line(line: &str) -> Result<Option<ScriptedCommand>> {
+    let tokens = tokenize(line)?;
+    let (trigger, actions) = match tokens.as_slice() {
+        [] => return Ok(None),
+        [Token::Text(trigger), Token::Arrow, actions @ ..] if !actions.is_empty() => (trigger, actions),
+        [Token::Text(_), ..] => return Err(anyhow!("expected `=> action(...)` after the trigger")),
+        _ => return Err(anyhow!("expected a quoted trigger, e.g. \"scratch that\" => undo()")),
+    };
+    let pattern = CommandPattern::new(trigger, VoiceCommandType::Macro)?;
+
+    let actions = actions.split(|token| *token == Token::Semicolon)
+        .map(|action| parse_action(&pattern, action))
+        .collect::<Result<Vec<_>>>()?;
+    let undoes = |action: &ScriptedAction| matches!(action.command_type, VoiceCommandType::Undo | VoiceCommandType::Redo);
+    if actions.len() > 1 && actions.iter().any(undoes) {
+        return Err(anyhow!("undo and redo can't be part of a macro"));
+    }
+    Ok(Some(ScriptedCommand { pattern, actions }))
+}
+
+/// Parse an action, e.g. `delete_words(n)`, for a command triggered by `pattern`
+fn parse_action(pattern: &CommandPattern, tokens: &[Token]) -> Result<ScriptedAction> {
+    let (action, arguments) = match tokens {
+        [Token::Name(action), Token::Open, arguments @ .., Token::Close] => (action, parse_arguments(arguments)?),
+        [] => return Err(anyhow!("expected an action after `;`")),
+        _ => return Err(anyhow!("expected an action, e.g. undo()")),
+    };
+
+    if action == "custom" {
+        return match arguments.as_slice() {
+            [Argument::Text(name)] => Ok(ScriptedAction {
+                command_type: VoiceCommandType::Custom(name.clone()),
+                arguments: Vec::new(),
+                pass_parameters: true,
+            }),
+            _ => Err(anyhow!("custom takes the command name as a string, e.g. custom(\"indent\")")),
+        };
+    }
+
+    if action == "run" {
+        return match arguments.as_slice() {
+            [Argument::Text(name)] => Ok(ScriptedAction {
+                command_type: VoiceCommandType::RunShellCommand,
+                arguments: vec![("program".to_string(), Argument::Text(name.clone()))],
+                pass_parameters: true,
+            }),
+            _ => Err(anyhow!("run takes the shell command name as a string, e.g. run(\"deploy\")")),
+        };
+    }
+
+    let (_, command_type, takes) = ACTIONS.iter()
+        .find(|(name, _, _)| *name == action.as_str())
+        .ok_or_else(|| anyhow!("unknown action {}", action))?;
+    let arguments = match (takes, arguments.as_slice()) {
+        (None, []) => Vec::new(),
+        (None, _) => return Err(anyhow!("{} takes no arguments", action)),
+        (Some((name, kind)), [argument]) => {
+            check_argument(pattern, action, *kind, argument)?;
+            // Keys written in the script are checked now rather than when they are said
+            if let (VoiceCommandType::Keystroke, Argument::Text(keys)) = (command_type, argument) {
+                KeyChord::parse(keys)?;
+            }
+            vec![(name.to_string(), argument.clone())]
+        }
+        (Some(_), _) => return Err(anyhow!("{} takes one argument", action)),
+    };
+
+    Ok(ScriptedAction {
+        command_type: command_type.clone(),
+        arguments,
+        pass_parameters: false,
+    })
+}
+
+/// Arguments between the parentheses of an action, separated by commas
+fn parse_arguments(tokens: &[Token]) -> Result<Vec<Argument>> {
+    let mut arguments = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let expects_argument = i % 2 == 0;
+        match (token, expects_argument) {
+            (Token::Text(text), true) => arguments.push(Argument::Text(text.clone())),
+            (Token::Number(number), true) => arguments.push(Argument::Number(*number)),
+            (Token::Name(name), true) => arguments.push(Argument::Parameter(name.clone())),
+            (Token::Comma, false) => {}
+            (_, true) => return Err(anyhow!("expected an argument")),
+            (_, false) => return Err(anyhow!("expected `,` or `)` after an argument")),
+        }
+    }
+    if tokens.last() == Some(&Token::Comma) {
+        return Err(anyhow!("expected an argument after `,`"));
+    }
+    Ok(arguments)
+}
+
+/// Check an argument is something `action` can take
+fn check_argument(pattern: &CommandPattern, action: &str, kind: ParameterKind, argument: &Argument) -> Result<()> {
+    let given = match argument {
+        Argument::Number(_) => ParameterKind::Number,
+        Argument::Text(_) => ParameterKind::Text,
+        Argument::Parameter(name) => pattern.parameter_kind(name)
+            .ok_or_else(|| anyhow!("the trigger has no parameter {}", name))?,
+    };
+    match (kind, given) {
+        (ParameterKind::Number, ParameterKind::Text) => Err(anyhow!("{} takes a number, not text", action)),
+        (ParameterKind::Text, ParameterKind::Number) => Err(anyhow!("{} takes text, not a number", action)),
+        _ => Ok(()),
+    }
+}
+
+/// Split a script line into tokens, leaving out a trailing comment
+fn tokenize(line: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '#' => break,
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            ';' => Token::Semicolon,
+            '=' if chars.next_if_eq(&'>').is_some() => Token::Arrow,
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => text.push('\n'),
+                            Some('t') => text.push('\t'),
+                            Some(escaped @ ('"' | '\\')) => text.push(escaped),
+                            _ => return Err(anyhow!("unknown escape in string; use \\n, \\t, \\\" or \\\\")),
+                        },
+                        Some(c) => text.push(c),
+                        None => return Err(anyhow!("unterminated string")),
+                    }
+                }
+                Token::Text(text)
+            }
+            c if c.is_ascii_digit() => {
+                let mut digits = c.to_string();
+                while let Some(digit) = chars.next_if(|c| c.is_ascii_digit()) {
+                    digits.push(digit);
+                }
+                Token::Number(digits.parse().map_err(|_| anyhow!("number too large: {}", digits))?)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut name = c.to_string();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    name.push(c);
+                }
+                Token::Name(name)
+            }
+            c => return Err(anyhow!("unexpected `{}`", c)),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = r#"
+        # Signing off
+        "insert signature" => insert_text("Best regards,\nAlex")
+        "delete {n} words" => delete_words(n)
+        "scratch that" => undo()   # same as "undo"
+        "find {what:text}" => select(what)
+        "indent {count} times" => custom("indent")
+        "deploy {branch:text}" => run("deploy")
+        "wrap it up" => insert_text("That's all."); new_paragraph(); key("ctrl+s"); stop()
+    "#;
+
+    #[test]
+    fn test_runs_script_commands() {
+        let script = CommandScript::parse(SCRIPT).unwrap();
+        assert_eq!(script.commands().len(), 7);
+
+        let command = script.detect("insert signature").unwrap();
+        assert_eq!(command.command_type, VoiceCommandType::InsertText);
+        assert_eq!(command.text("text"), Some("Best regards,\nAlex"));
+
+        let command = script.detect("delete four words").unwrap();
+        assert_eq!(command.command_type, VoiceCommandType::Delete);
+        assert_eq!(command.number("count"), Some(4));
+
+        assert_eq!(script.detect("scratch that").unwrap().command_type, VoiceCommandType::Undo);
+        assert_eq!(script.detect("find the summary").unwrap().text("phrase"), Some("the summary"));
+
+        let command = script.detect("indent two times").unwrap();
+        assert_eq!(command.command_type, VoiceCommandType::Custom("indent".to_string()));
+        assert_eq!(command.number("count"), Some(2));
+
+        let command = script.detect("deploy release two").unwrap();
+        assert_eq!(command.command_type, VoiceCommandType::RunShellCommand);
+        assert_eq!(command.text("program"), Some("deploy"));
+        assert_eq!(command.text("branch"), Some("release two"));
+
+        assert!(script.detect("insert a signature").is_none());
+    }
+
+    #[test]
+    fn test_macros() {
+        let script = CommandScript::parse(SCRIPT).unwrap();
+        let command = script.detect("wrap it up").unwrap();
+        assert_eq!(command.command_type, VoiceCommandType::Macro);
+
+        let steps: Vec<&VoiceCommandType> = command.steps.iter().map(|step| &step.command_type).collect();
+        assert_eq!(steps, [&VoiceCommandType::InsertText, &VoiceCommandType::NewParagraph, &VoiceCommandType::Keystroke, &VoiceCommandType::Stop]);
+        assert_eq!(command.steps[0].text("text"), Some("That's all."));
+        assert_eq!(command.steps[2].text("keys"), Some("ctrl+s"));
+
+        let error = |script: &str| CommandScript::parse(script).unwrap_err().to_string();
+        assert_eq!(error("\"oops\" => new_line(); undo()"), "line 1: undo and redo can't be part of a macro");
+        assert_eq!(error("\"oops\" => new_line();"), "line 1: expected an action after `;`");
+    }
+
+    #[test]
+    fn test_reports_mistakes() {
+        let error = |script: &str| CommandScript::parse(script).unwrap_err().to_string();
+
+        assert_eq!(error("\"undo it\" => undo()\n\"sign\" => sign_off()"), "line 2: unknown action sign_off");
+        assert_eq!(error("\"sign\" => insert_text(\"Best"), "line 1: unterminated string");
+        assert_eq!(error("\"delete {n} words\" => delete_words(count)"), "line 1: the trigger has no parameter count");
+        assert_eq!(error("\"find {what:text}\" => delete_words(what)"), "line 1: delete_words takes a number, not text");
+        assert_eq!(error("\"undo it\" => undo(1)"), "line 1: undo takes no arguments");
+        assert_eq!(error("\"ship it\" => run(deploy)"), "line 1: run takes the shell command name as a string, e.g. run(\"deploy\")");
+        assert_eq!(error("\"save\" => key(\"ctrl+sav\")"), "line 1: unknown key sav in \"ctrl+sav\"");
+        assert_eq!(error("\"undo it\" undo()"), "line 1: expected `=> action(...)` after the trigger");
+        assert_eq!(error("undo => undo()"), "line 1: expected a quoted trigger, e.g. \"scratch that\" => undo()");
+        assert!(error("\"{n} words\" => delete_words(n)").starts_with("line 1: Command patterns start with a word"));
+    }
+}
@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::voice_commands::{VoiceCommand, VoiceCommandType};
+
+/// How often a command was carried out, how well it was heard and how often wrongly
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommandUsage {
+    /// The command
+    pub command_type: VoiceCommandType,
+
+    /// Times it was carried out
+    pub count: u32,
+
+    /// How closely what was said matched it on average, from 0 to 1
+    pub average_score: f32,
+
+    /// Times the user said it was heard by mistake
+    pub wrong: u32,
+}
+
+/// A command and what was counted of it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct TrackedCommand {
+    command_type: VoiceCommandType,
+    count: u32,
+
+    /// Sum of the scores it was heard with
+    total_score: f64,
+    wrong: u32,
+}
+
+/// Counts the commands carried out and the ones reported as heard by mistake,
+/// so the sensitivity can be set from how commands are really heard
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommandStats {
+    commands: Vec<TrackedCommand>,
+}
+
+impl CommandStats {
+    /// File the counts are kept in, in the application data folder
+    pub fn default_path() -> Result<PathBuf> {
+        let project_dirs = directories::ProjectDirs::from("com", "bestme", "BestMe")
+            .context("Failed to determine project directories")?;
+        Ok(project_dirs.data_dir().join("command_stats.json"))
+    }
+
+    /// Load counts saved at `path`, starting afresh when there are none
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read command statistics {}", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("Invalid command statistics {}", path.display()))
+    }
+
+    /// Save the counts to `path`
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to save command statistics {}", path.display()))
+    }
+
+    /// Count `command` as carried out, with the score it was heard with
+    pub fn record(&mut self, command: &VoiceCommand) {
+        let tracked = self.tracked(&command.command_type);
+        tracked.count += 1;
+        tracked.total_score += f64::from(command.score.clamp(0.0, 1.0));
+    }
+
+    /// Count a command of `command_type` as heard by mistake
+    pub fn report_wrong(&mut self, command_type: &VoiceCommandType) {
+        self.tracked(command_type).wrong += 1;
+    }
+
+    /// What was counted of each command, most used first
+    pub fn usage(&self) -> Vec<CommandUsage> {
+        let mut usage: Vec<CommandUsage> = self.commands.iter()
+            .map(|tracked| CommandUsage {
+                command_type: tracked.command_type.clone(),
+                count: tracked.count,
+                average_score: if tracked.count == 0 { 0.0 } else { (tracked.total_score / f64::from(tracked.count)) as f32 },
+                wrong: tracked.wrong,
+            })
+            .collect();
+        usage.sort_by_key(|usage| std::cmp::Reverse(usage.count));
+        usage
+    }
+
+    /// Forget everything counted
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    fn tracked(&mut self, command_type: &VoiceCommandType) -> &mut TrackedCommand {
+        let index = match self.commands.iter().position(|tracked| &tracked.command_type == command_type) {
+            Some(index) => index,
+            None => {
+                self.commands.push(TrackedCommand {
+                    command_type: command_type.clone(),
+                    count: 0,
+                    total_score: 0.0,
+                    wrong: 0,
+                });
+                self.commands.len() - 1
+            }
+        };
+        &mut self.commands[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_commands_and_mistakes() {
+        let mut stats = CommandStats::default();
+        let mut misheard = VoiceCommand::new(VoiceCommandType::Delete, "delete lust word");
+        misheard.score = 0.8;
+        stats.record(&misheard);
+        stats.record(&VoiceCommand::new(VoiceCommandType::Delete, "delete last word"));
+        stats.record(&VoiceCommand::new(VoiceCommandType::Undo, "undo"));
+        stats.report_wrong(&VoiceCommandType::Delete);
+
+        let usage = stats.usage();
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].command_type, VoiceCommandType::Delete);
+        assert_eq!((usage[0].count, usage[0].wrong), (2, 1));
+        assert!((usage[0].average_score - 0.9).abs() < 1e-6);
+        assert_eq!((usage[1].count, usage[1].average_score, usage[1].wrong), (1, 1.0, 0));
+
+        // Saved counts load as they were
+        let path = std::env::temp_dir().join(format!("bestme-command-stats-{}.json", std::process::id()));
+        stats.save(&path).unwrap();
+        assert_eq!(CommandStats::load(&path).unwrap().usage(), usage);
+        std::fs::remove_file(&path).unwrap();
+
+        stats.clear();
+        assert!(stats.usage().is_empty());
+    }
+}
@@ -0,0 +1,264 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::config::ReplacementRule;
+
+/// Longest phrase, in words, taken for a correction; longer edits are rewrites rather than fixes
+const MAX_PHRASE_WORDS: usize = 4;
+
+/// A phrase the user changed in a transcript
+#[derive(Debug, Clone, PartialEq)]
+pub struct Correction {
+    /// The phrase as transcribed
+    pub original: String,
+
+    /// The phrase as the user wrote it
+    pub corrected: String,
+}
+
+/// A correction made often enough to become a replacement rule
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CorrectionSuggestion {
+    /// The phrase as transcribed
+    pub pattern: String,
+
+    /// The phrase as the user wrote it
+    pub replacement: String,
+
+    /// How many times the user made the correction
+    pub count: u32,
+}
+
+impl CorrectionSuggestion {
+    /// Replacement rule making the correction automatically
+    pub fn to_rule(&self) -> ReplacementRule {
+        ReplacementRule {
+            pattern: self.pattern.clone(),
+            replacement: self.replacement.clone(),
+            whole_word: true,
+            ..ReplacementRule::default()
+        }
+    }
+}
+
+/// A correction and how often it was made
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct TrackedCorrection {
+    original: String,
+    corrected: String,
+    count: u32,
+
+    /// The user doesn't want it suggested again
+    dismissed: bool,
+}
+
+/// Counts the corrections the user makes to transcripts, so frequent ones can become replacement rules
+///
+/// Phrases are matched without letter case, as replacement rules are.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CorrectionTracker {
+    corrections: Vec<TrackedCorrection>,
+}
+
+impl CorrectionTracker {
+    /// File the counts are kept in, in the application data folder
+    pub fn default_path() -> Result<PathBuf> {
+        let project_dirs = directories::ProjectDirs::from("com", "bestme", "BestMe")
+            .context("Failed to determine project directories")?;
+        Ok(project_dirs.data_dir().join("corrections.json"))
+    }
+
+    /// Load counts saved at `path`, starting afresh when there are none
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read corrections {}", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("Invalid corrections {}", path.display()))
+    }
+
+    /// Save the counts to `path`
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to save corrections {}", path.display()))
+    }
+
+    /// Count the corrections made by changing `original` text to `corrected`
+    ///
+    /// Returns them with their counts so far, except ones the user dismissed.
+    pub fn record(&mut self, original: &str, corrected: &str) -> Vec<CorrectionSuggestion> {
+        let mut counted = Vec::new();
+        for correction in find_corrections(original, corrected) {
+            let index = match self.find(&correction.original, &correction.corrected) {
+                Some(index) => index,
+                None => {
+                    self.corrections.push(TrackedCorrection {
+                        original: correction.original.to_lowercase(),
+                        corrected: correction.corrected,
+                        count: 0,
+                        dismissed: false,
+                    });
+                    self.corrections.len() - 1
+                }
+            };
+
+            let tracked = &mut self.corrections[index];
+            tracked.count += 1;
+            if !tracked.dismissed {
+                counted.push(suggestion(tracked));
+            }
+        }
+        counted
+    }
+
+    /// Corrections made at least `min_count` times and not dismissed, most frequent first
+    pub fn suggestions(&self, min_count: u32) -> Vec<CorrectionSuggestion> {
+        let mut suggestions: Vec<CorrectionSuggestion> = self.corrections.iter()
+            .filter(|tracked| !tracked.dismissed && tracked.count >= min_count)
+            .map(suggestion)
+            .collect();
+        suggestions.sort_by_key(|suggestion| std::cmp::Reverse(suggestion.count));
+        suggestions
+    }
+
+    /// Stop suggesting a correction, while still counting it
+    pub fn dismiss(&mut self, pattern: &str, replacement: &str) {
+        if let Some(index) = self.find(pattern, replacement) {
+            self.corrections[index].dismissed = true;
+        }
+    }
+
+    /// Stop tracking a correction, e.g. once it is a replacement rule
+    pub fn forget(&mut self, pattern: &str, replacement: &str) {
+        if let Some(index) = self.find(pattern, replacement) {
+            self.corrections.remove(index);
+        }
+    }
+
+    fn find(&self, original: &str, corrected: &str) -> Option<usize> {
+        self.corrections.iter()
+            .position(|tracked| tracked.original == original.to_lowercase() && tracked.corrected == corrected)
+    }
+}
+
+fn suggestion(tracked: &TrackedCorrection) -> CorrectionSuggestion {
+    CorrectionSuggestion {
+        pattern: tracked.original.clone(),
+        replacement: tracked.corrected.clone(),
+        count: tracked.count,
+    }
+}
+
+/// Phrases changed between `original` and `corrected` text, e.g. "cooper netties" to "Kubernetes"
+///
+/// Words are compared without letter case or surrounding punctuation, so
+/// capitalizing or punctuating text isn't taken for a correction, and long
+/// rewritten passages are left out.
+pub fn find_corrections(original: &str, corrected: &str) -> Vec<Correction> {
+    let before = words(original);
+    let after = words(corrected);
+    let same = |a: &str, b: &str| a.to_lowercase() == b.to_lowercase();
+
+    // Longest common subsequence of words from each position on
+    let mut common = vec![vec![0usize; after.len() + 1]; before.len() + 1];
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            common[i][j] = if same(before[i], after[j]) {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut corrections = Vec::new();
+    let mut removed: Vec<&str> = Vec::new();
+    let mut inserted: Vec<&str> = Vec::new();
+    let mut flush = |removed: &mut Vec<&str>, inserted: &mut Vec<&str>| {
+        if !removed.is_empty() && !inserted.is_empty() && removed.len() <= MAX_PHRASE_WORDS && inserted.len() <= MAX_PHRASE_WORDS {
+            corrections.push(Correction { original: removed.join(" "), corrected: inserted.join(" ") });
+        }
+        removed.clear();
+        inserted.clear();
+    };
+
+    let (mut i, mut j) = (0, 0);
+    while i < before.len() || j < after.len() {
+        if i < before.len() && j < after.len() && same(before[i], after[j]) {
+            flush(&mut removed, &mut inserted);
+            i += 1;
+            j += 1;
+        } else if j < after.len() && (i == before.len() || common[i][j + 1] >= common[i + 1][j]) {
+            inserted.push(after[j]);
+            j += 1;
+        } else {
+            removed.push(before[i]);
+            i += 1;
+        }
+    }
+    flush(&mut removed, &mut inserted);
+
+    corrections
+}
+
+/// Words of `text` without the punctuation around them
+fn words(text: &str) -> Vec<&str> {
+    text.split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(original: &str, corrected: &str) -> Vec<(String, String)> {
+        find_corrections(original, corrected).into_iter().map(|c| (c.original, c.corrected)).collect()
+    }
+
+    #[test]
+    fn test_finds_changed_phrases() {
+        assert_eq!(
+            pairs("we deploy on cooper netties, today.", "We deploy on Kubernetes today."),
+            [("cooper netties".to_string(), "Kubernetes".to_string())]
+        );
+        assert_eq!(
+            pairs("ask jon about the get hub issue", "ask John about the GitHub issue"),
+            [("jon".to_string(), "John".to_string()), ("get hub".to_string(), "GitHub".to_string())]
+        );
+
+        // Capitalization, punctuation, added words and rewrites aren't corrections
+        assert!(pairs("so we ship", "So, we ship!").is_empty());
+        assert!(pairs("we ship", "we ship on friday").is_empty());
+        assert!(pairs("the meeting ran long and nobody took notes", "the meeting was a waste of everyone's time honestly").is_empty());
+    }
+
+    #[test]
+    fn test_counts_and_suggests() {
+        let mut tracker = CorrectionTracker::default();
+        let counted = tracker.record("on cooper netties", "on Kubernetes");
+        assert_eq!(counted[0].count, 1);
+        assert!(tracker.suggestions(2).is_empty());
+
+        let counted = tracker.record("Cooper Netties again", "Kubernetes again");
+        assert_eq!(counted, [CorrectionSuggestion { pattern: "cooper netties".to_string(), replacement: "Kubernetes".to_string(), count: 2 }]);
+        assert_eq!(tracker.suggestions(2), counted);
+
+        let rule = counted[0].to_rule();
+        assert_eq!((rule.pattern.as_str(), rule.replacement.as_str(), rule.whole_word), ("cooper netties", "Kubernetes", true));
+
+        tracker.dismiss("cooper netties", "Kubernetes");
+        assert!(tracker.suggestions(1).is_empty());
+        assert!(tracker.record("cooper netties", "Kubernetes").is_empty());
+
+        tracker.forget("cooper netties", "Kubernetes");
+        assert_eq!(tracker.record("cooper netties", "Kubernetes")[0].count, 1);
+    }
+}
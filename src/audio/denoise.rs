@@ -0,0 +1,131 @@
+use log::{debug, error};
+use nnnoiseless::DenoiseState;
+
+use super::capture::AudioData;
+use super::resample::StreamResampler;
+use crate::config::ResamplerQuality;
+
+/// Sample rate required by the RNNoise model
+const DENOISE_SAMPLE_RATE: u32 = 48000;
+
+/// RNNoise operates on 16-bit PCM magnitudes rather than normalized floats
+const PCM_SCALE: f32 = 32767.0;
+
+/// Noise suppression stage based on RNNoise (nnnoiseless)
+///
+/// Audio is converted to 48 kHz mono, processed in 10 ms frames and returned
+/// as mono audio at 48 kHz. Samples that do not fill a whole frame are kept
+/// until the next call, so the stage adds at most one frame of latency.
+pub struct NoiseSuppressor {
+    /// RNNoise state
+    state: Box<DenoiseState<'static>>,
+
+    /// Input samples waiting for a full frame
+    pending: Vec<f32>,
+
+    /// Converts input audio to the 48 kHz rate RNNoise expects
+    resampler: Option<StreamResampler>,
+
+    /// Whether processing is bypassed
+    bypass: bool,
+}
+
+impl NoiseSuppressor {
+    /// Create a new noise suppressor
+    pub fn new() -> Self {
+        Self {
+            state: DenoiseState::new(),
+            pending: Vec::with_capacity(DenoiseState::FRAME_SIZE * 2),
+            resampler: None,
+            bypass: false,
+        }
+    }
+
+    /// Enable or disable bypass mode
+    pub fn set_bypass(&mut self, bypass: bool) {
+        if bypass != self.bypass {
+            debug!("Noise suppression bypass: {}", bypass);
+            self.pending.clear();
+        }
+        self.bypass = bypass;
+    }
+
+    /// Check if processing is bypassed
+    pub fn is_bypassed(&self) -> bool {
+        self.bypass
+    }
+
+    /// Denoise a chunk of audio
+    ///
+    /// When bypassed the input is returned unchanged.
+    pub fn process(&mut self, audio: &AudioData) -> AudioData {
+        if self.bypass {
+            return audio.clone();
+        }
+
+        // RNNoise needs 48 kHz mono input
+        let mono = match self.resample_input(audio) {
+            Ok(mono) => mono,
+            Err(e) => {
+                error!("Failed to prepare audio for noise suppression: {}", e);
+                return audio.clone();
+            }
+        };
+        self.pending.extend(mono.iter().map(|s| s * PCM_SCALE));
+
+        let frame_size = DenoiseState::FRAME_SIZE;
+        let frames = self.pending.len() / frame_size;
+        let mut output = vec![0.0f32; frames * frame_size];
+
+        for (i, out_frame) in output.chunks_exact_mut(frame_size).enumerate() {
+            let in_frame = &self.pending[i * frame_size..(i + 1) * frame_size];
+            self.state.process_frame(out_frame, in_frame);
+        }
+
+        self.pending.drain(..frames * frame_size);
+
+        for sample in output.iter_mut() {
+            *sample = (*sample / PCM_SCALE).clamp(-1.0, 1.0);
+        }
+
+        AudioData::new(output, DENOISE_SAMPLE_RATE, 1)
+    }
+
+    /// Downmix and resample input to 48 kHz mono
+    fn resample_input(&mut self, audio: &AudioData) -> anyhow::Result<Vec<f32>> {
+        // Recreate the resampler if the input rate changes (e.g. after a device switch)
+        let needs_resampler = self.resampler
+            .as_ref()
+            .map(|r| r.from_rate() != audio.sample_rate())
+            .unwrap_or(true);
+
+        if needs_resampler {
+            self.resampler = Some(StreamResampler::new(
+                audio.sample_rate(),
+                DENOISE_SAMPLE_RATE,
+                &ResamplerQuality::Fast,
+                10,
+            )?);
+        }
+
+        match self.resampler.as_mut() {
+            Some(resampler) => resampler.process(&audio.to_mono()),
+            None => Ok(audio.to_mono()),
+        }
+    }
+
+    /// Clear any buffered audio and reset the model state
+    pub fn reset(&mut self) {
+        self.state = DenoiseState::new();
+        self.pending.clear();
+        if let Some(resampler) = self.resampler.as_mut() {
+            resampler.reset();
+        }
+    }
+}
+
+impl Default for NoiseSuppressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -3,12 +3,179 @@ use log::info;
 use std::collections::HashMap;
 use cpal::traits::{DeviceTrait, HostTrait};
 use super::get_device_description;
+use crate::config::AudioSettings;
+
+/// Prefix used for device IDs that capture system audio from an output device
+pub const LOOPBACK_PREFIX: &str = "loopback:";
+
+/// Prefix used for device IDs of PulseAudio/PipeWire sources (Linux)
+pub const PULSE_PREFIX: &str = "pulse:";
+
+/// Check if a device ID refers to a loopback (system audio) capture source
+pub fn is_loopback_id(id: &str) -> bool {
+    id.starts_with(LOOPBACK_PREFIX)
+}
+
+/// Sound server source name for a PulseAudio/PipeWire device ID (including monitors)
+pub fn pulse_source_name(id: &str) -> Option<&str> {
+    id.strip_prefix(LOOPBACK_PREFIX).unwrap_or(id).strip_prefix(PULSE_PREFIX)
+}
+
+/// Check if loopback capture is available for a source ID on this platform
+///
+/// WASAPI can capture any output device; on Linux the sound server exposes
+/// the monitor of each output as a source.
+pub fn loopback_supported(id: &str) -> bool {
+    cfg!(target_os = "windows") || (is_loopback_id(id) && pulse_source_name(id).is_some())
+}
+
+/// Get the loopback source ID for the default output device
+pub fn default_loopback_id() -> Option<String> {
+    // Sinks are monitored through "<sink>.monitor" on both PulseAudio and PipeWire
+    #[cfg(target_os = "linux")]
+    if let Some(sink) = super::pulse::default_sink() {
+        return Some(format!("{}{}{}.monitor", LOOPBACK_PREFIX, PULSE_PREFIX, sink));
+    }
+    
+    let host = cpal::default_host();
+    let default_name = host.default_output_device()?.name().ok()?;
+    
+    enumerate_devices(&host, true).ok()?
+        .into_iter()
+        .find(|d| d.name == default_name)
+        .map(|d| format!("{}{}", LOOPBACK_PREFIX, d.id))
+}
+
+/// Get the stable ID of the default input device
+pub fn default_input_id(host: &cpal::Host) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    if let Some(source) = super::pulse::default_source() {
+        return Some(format!("{}{}", PULSE_PREFIX, source));
+    }
+    
+    let default_name = host.default_input_device()?.name().ok()?;
+    
+    enumerate_devices(host, false).ok()?
+        .into_iter()
+        .find(|d| d.name == default_name)
+        .map(|d| d.id)
+}
+
+/// Build a persistent identifier for a device without an OS-provided ID
+///
+/// The ID is derived from the host, the device name and its position among
+/// devices with the same name, so identical devices get distinct IDs.
+pub fn stable_device_id(host_name: &str, device_name: &str, occurrence: usize) -> String {
+    // FNV-1a: unlike DefaultHasher, the output is stable across Rust releases
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let key = format!("{}/{}#{}", host_name, device_name, occurrence);
+    for byte in key.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    
+    format!("dev-{:016x}", hash)
+}
+
+/// Audio device found during enumeration
+pub struct EnumeratedDevice {
+    /// Stable device ID
+    pub id: String,
+    
+    /// Device name as reported by the host
+    pub name: String,
+    
+    /// The cpal device
+    pub device: cpal::Device,
+}
+
+/// Enumerate input (or output) devices and assign stable IDs
+///
+/// On Windows the WASAPI endpoint ID is used, on macOS the CoreAudio device
+/// UID; elsewhere a persistent hash.
+pub fn enumerate_devices(host: &cpal::Host, output: bool) -> Result<Vec<EnumeratedDevice>> {
+    let devices: Vec<cpal::Device> = if output {
+        host.output_devices()?.collect()
+    } else {
+        host.input_devices()?.collect()
+    };
+    
+    #[cfg(target_os = "windows")]
+    let endpoints = windows_endpoints::list(output).unwrap_or_else(|e| {
+        log::warn!("Failed to read endpoint IDs, using generated IDs: {}", e);
+        Vec::new()
+    });
+    
+    #[cfg(target_os = "macos")]
+    let endpoints = macos_devices::list(output).unwrap_or_else(|e| {
+        log::warn!("Failed to read CoreAudio device UIDs, using generated IDs: {}", e);
+        Vec::new()
+    });
+    
+    let host_name = host.id().name();
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    let mut enumerated = Vec::with_capacity(devices.len());
+    
+    for device in devices {
+        let name = match device.name() {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+        
+        let occurrence = occurrences.entry(name.clone()).or_insert(0);
+        
+        #[cfg(any(target_os = "windows", target_os = "macos"))]
+        let id = endpoints.iter()
+            .filter(|(endpoint_name, _)| *endpoint_name == name)
+            .nth(*occurrence)
+            .map(|(_, id)| id.clone())
+            .unwrap_or_else(|| stable_device_id(host_name, &name, *occurrence));
+        
+        #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+        let id = stable_device_id(host_name, &name, *occurrence);
+        *occurrence += 1;
+        
+        enumerated.push(EnumeratedDevice { id, name, device });
+    }
+    
+    Ok(enumerated)
+}
+
+/// Find a capture source by ID
+///
+/// Returns the device and whether it is an output device captured via loopback.
+/// IDs saved by older versions (plain device names) are still accepted.
+/// Sound server sources aren't cpal devices; they are recorded by name with
+/// `pulse::SourceRecorder`.
+pub fn find_device_by_id(host: &cpal::Host, id: &str) -> Result<Option<(cpal::Device, bool)>> {
+    let (device_id, loopback) = match id.strip_prefix(LOOPBACK_PREFIX) {
+        Some(output_id) => (output_id, true),
+        None => (id, false),
+    };
+    
+    let devices = enumerate_devices(host, loopback)?;
+    
+    let position = devices.iter()
+        .position(|d| d.id == device_id)
+        .or_else(|| {
+            // Legacy configuration stored device names
+            let found = devices.iter().position(|d| d.name == device_id);
+            if found.is_some() {
+                info!("Matched legacy device name '{}'", device_id);
+            }
+            found
+        });
+    
+    Ok(position.map(|index| (devices.into_iter().nth(index).unwrap().device, loopback)))
+}
 
 /// Audio device manager
 #[derive(Clone)]
 pub struct DeviceManager {
     /// Input devices
     input_devices: HashMap<String, String>,
+    /// Output devices that can be captured via loopback (Windows, and monitors on Linux)
+    loopback_devices: HashMap<String, String>,
     /// Default input device ID
     default_input_device: Option<String>,
 }
@@ -18,6 +185,7 @@ impl DeviceManager {
     pub fn new() -> Result<Self> {
         let mut manager = Self {
             input_devices: HashMap::new(),
+            loopback_devices: HashMap::new(),
             default_input_device: None,
         };
         
@@ -32,6 +200,8 @@ impl DeviceManager {
     /// Refresh device list
     pub fn refresh_devices(&mut self) -> Result<()> {
         self.input_devices.clear();
+        self.loopback_devices.clear();
+        self.default_input_device = None;
         
         // Platform-specific implementations
         #[cfg(target_os = "windows")]
@@ -40,27 +210,23 @@ impl DeviceManager {
             return Ok(());
         }
         
+        #[cfg(target_os = "linux")]
+        {
+            match self.refresh_devices_pulse() {
+                Ok(()) if !self.input_devices.is_empty() => return Ok(()),
+                Ok(()) => info!("Sound server reported no sources, using ALSA devices"),
+                Err(e) => info!("PulseAudio/PipeWire unavailable, using ALSA devices: {}", e),
+            }
+        }
+        
         #[cfg(not(target_os = "windows"))]
         {
             // Default implementation for non-Windows platforms
             let host = cpal::default_host();
             
-            // Try to get the default input device
-            if let Some(default_device) = host.default_input_device() {
-                let device_name = default_device.name().context("Could not get default device name")?;
-                self.default_input_device = Some(device_name.clone());
-                self.input_devices.insert(device_name.clone(), device_name);
-            }
-            
             // Try to get all input devices
-            match host.input_devices() {
-                Ok(devices) => {
-                    for device in devices {
-                        if let Ok(name) = device.name() {
-                            self.input_devices.insert(name.clone(), name);
-                        }
-                    }
-                },
+            match enumerate_devices(&host, false) {
+                Ok(devices) => self.insert_input_devices(&host, devices)?,
                 Err(e) => {
                     info!("Could not get input devices: {}", e);
                 }
@@ -75,6 +241,34 @@ impl DeviceManager {
         Ok(())
     }
     
+    /// Record enumerated input devices and resolve the default device ID
+    fn insert_input_devices(&mut self, host: &cpal::Host, devices: Vec<EnumeratedDevice>) -> Result<()> {
+        let default_name = match host.default_input_device() {
+            Some(device) => Some(device.name().context("Could not get default device name")?),
+            None => None,
+        };
+        
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        for device in devices {
+            if self.default_input_device.is_none() && default_name.as_deref() == Some(device.name.as_str()) {
+                self.default_input_device = Some(device.id.clone());
+            }
+            
+            // Tell identical devices apart in the UI
+            let count = seen.entry(device.name.clone()).or_insert(0);
+            *count += 1;
+            let display_name = if *count > 1 {
+                format!("{} ({})", device.name, count)
+            } else {
+                device.name
+            };
+            
+            self.input_devices.insert(device.id, display_name);
+        }
+        
+        Ok(())
+    }
+    
     /// Add a mock audio device for testing in headless/WSL environments
     fn add_mock_device_for_testing(&mut self) {
         info!("Adding mock audio device for testing purposes");
@@ -95,10 +289,73 @@ impl DeviceManager {
             .collect()
     }
     
-    /// Get input device by ID
+    /// Get all loopback (system audio) capture sources
+    pub fn get_loopback_devices(&self) -> Vec<(String, String)> {
+        self.loopback_devices.iter()
+            .map(|(id, name)| (id.clone(), name.clone()))
+            .collect()
+    }
+    
+    /// Get all capture sources: input devices followed by loopback sources
+    pub fn get_capture_sources(&self) -> Vec<(String, String)> {
+        let mut sources = self.get_input_devices();
+        sources.extend(self.get_loopback_devices());
+        sources
+    }
+    
+    /// Get input device (or loopback source) by ID
+    ///
+    /// Device names saved by older versions are accepted and mapped to their ID.
     pub fn get_input_device(&self, id: &str) -> Option<(String, String)> {
-        self.input_devices.get(id)
-            .map(|name| (id.to_string(), name.clone()))
+        let id = self.resolve_device_id(id)?;
+        self.input_devices.get(&id)
+            .or_else(|| self.loopback_devices.get(&id))
+            .map(|name| (id.clone(), name.clone()))
+    }
+    
+    /// Map a device ID or legacy device name to the current stable ID
+    pub fn resolve_device_id(&self, id_or_name: &str) -> Option<String> {
+        if self.input_devices.contains_key(id_or_name) || self.loopback_devices.contains_key(id_or_name) {
+            return Some(id_or_name.to_string());
+        }
+        
+        if let Some(name) = id_or_name.strip_prefix(LOOPBACK_PREFIX) {
+            let display_name = format!("{} (System Audio)", name);
+            return self.loopback_devices.iter()
+                .find(|(_, n)| **n == display_name)
+                .map(|(id, _)| id.clone());
+        }
+        
+        self.input_devices.iter()
+            .find(|(_, n)| *n == id_or_name)
+            .map(|(id, _)| id.clone())
+    }
+    
+    /// Replace device names in saved settings with stable device IDs
+    ///
+    /// Returns true if any setting was changed and should be saved.
+    pub fn migrate_settings(&self, settings: &mut AudioSettings) -> bool {
+        let mut changed = false;
+        
+        let mut migrate = |value: &mut String| {
+            if let Some(id) = self.resolve_device_id(value) {
+                if id != *value {
+                    info!("Migrating device '{}' to ID {}", value, id);
+                    *value = id;
+                    changed = true;
+                }
+            }
+        };
+        
+        if let Some(device) = settings.input_device.as_mut() {
+            migrate(device);
+        }
+        settings.additional_devices.iter_mut().for_each(&mut migrate);
+        if let Some(device) = settings.echo_cancellation.reference_device.as_mut() {
+            migrate(device);
+        }
+        
+        changed
     }
     
     /// Get default input device
@@ -114,7 +371,7 @@ impl DeviceManager {
     
     /// Get device name
     pub fn get_device_name(&self, id: &str) -> Option<String> {
-        self.input_devices.get(id).cloned()
+        self.get_input_device(id).map(|(_, name)| name)
     }
     
     /// Get the supported configurations for a device 
@@ -125,37 +382,280 @@ impl DeviceManager {
         Ok(Vec::new())
     }
     
+    /// Refresh device list from the PulseAudio or PipeWire sound server
+    ///
+    /// Sources are identified by their server-side name, which is derived from
+    /// the device's bus path and serial and so survives reconnects. Monitors of
+    /// output devices are listed as loopback sources.
+    #[cfg(target_os = "linux")]
+    fn refresh_devices_pulse(&mut self) -> Result<()> {
+        use super::pulse;
+        
+        let sources = pulse::list_sources()?;
+        
+        for source in &sources {
+            if source.is_monitor() {
+                let name = source.description.strip_prefix("Monitor of ").unwrap_or(&source.description);
+                self.loopback_devices.insert(
+                    format!("{}{}{}", LOOPBACK_PREFIX, PULSE_PREFIX, source.name),
+                    format!("{} (System Audio)", name),
+                );
+            } else {
+                self.input_devices.insert(format!("{}{}", PULSE_PREFIX, source.name), source.description.clone());
+            }
+        }
+        
+        // PipeWire keeps the default in its metadata
+        self.default_input_device = pulse::default_source()
+            .map(|name| format!("{}{}", PULSE_PREFIX, name))
+            .filter(|id| self.input_devices.contains_key(id));
+        
+        info!(
+            "Sound server reported {} sources and {} monitors",
+            self.input_devices.len(),
+            self.loopback_devices.len()
+        );
+        Ok(())
+    }
+    
     /// Refresh device list with Windows-specific optimizations
     #[cfg(target_os = "windows")]
     pub fn refresh_devices_windows(&mut self) -> Result<()> {
         info!("Using Windows-specific audio device detection");
         self.input_devices.clear();
+        self.loopback_devices.clear();
         
-        // Use Windows-specific APIs to get devices more reliably
-        // This is a simple implementation for now - in a real app, we might use
-        // the windows crate with more detailed device enumeration
         let host = cpal::default_host();
         
-        if let Some(default_device) = host.default_input_device() {
-            if let Ok(name) = default_device.name() {
-                info!("Found Windows default input device: {}", name);
-                self.default_input_device = Some(name.clone());
-                self.input_devices.insert(name.clone(), name);
+        // Get all Windows input devices, keyed by endpoint ID
+        if let Ok(devices) = enumerate_devices(&host, false) {
+            for device in &devices {
+                info!("Found Windows input device: {} ({})", device.name, device.id);
             }
+            self.insert_input_devices(&host, devices)?;
         }
         
-        // Get all Windows input devices
-        if let Ok(devices) = host.input_devices() {
+        // WASAPI can capture what an output device is playing (loopback)
+        if let Ok(devices) = enumerate_devices(&host, true) {
             for device in devices {
-                if let Ok(name) = device.name() {
-                    info!("Found Windows input device: {}", name);
-                    self.input_devices.insert(name.clone(), name);
-                }
+                info!("Found Windows loopback source: {}", device.name);
+                self.loopback_devices.insert(
+                    format!("{}{}", LOOPBACK_PREFIX, device.id),
+                    format!("{} (System Audio)", device.name),
+                );
             }
         }
         
-        info!("Windows audio device detection found {} devices", self.input_devices.len());
+        info!(
+            "Windows audio device detection found {} devices and {} loopback sources",
+            self.input_devices.len(),
+            self.loopback_devices.len()
+        );
         Ok(())
     }
-} 
+}
 
+#[cfg(target_os = "windows")]
+mod windows_endpoints {
+    use windows::Win32::Devices::FunctionDiscovery::PKEY_Device_FriendlyName;
+    use windows::Win32::Media::Audio::{eCapture, eRender, IMMDeviceEnumerator, MMDeviceEnumerator, DEVICE_STATE_ACTIVE};
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CoTaskMemFree, CLSCTX_ALL, COINIT_MULTITHREADED, STGM_READ,
+    };
+    
+    /// List active WASAPI endpoints as (friendly name, endpoint ID) pairs
+    pub fn list(output: bool) -> anyhow::Result<Vec<(String, String)>> {
+        unsafe {
+            // COM may already be initialized on this thread, possibly in another mode
+            let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+            
+            let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+            let flow = if output { eRender } else { eCapture };
+            let collection = enumerator.EnumAudioEndpoints(flow, DEVICE_STATE_ACTIVE)?;
+            
+            let mut endpoints = Vec::new();
+            for index in 0..collection.GetCount()? {
+                let device = collection.Item(index)?;
+                
+                let id_ptr = device.GetId()?;
+                let id = id_ptr.to_string()?;
+                CoTaskMemFree(Some(id_ptr.0 as *const _));
+                
+                let store = device.OpenPropertyStore(STGM_READ)?;
+                let value = store.GetValue(&PKEY_Device_FriendlyName)?;
+                let name = value.Anonymous.Anonymous.Anonymous.pwszVal.to_string()?;
+                
+                endpoints.push((name, id));
+            }
+            
+            Ok(endpoints)
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos_devices {
+    use std::ffi::CStr;
+    use std::mem;
+    use std::os::raw::{c_char, c_void};
+    use std::ptr::null;
+    
+    use core_foundation_sys::base::CFRelease;
+    use core_foundation_sys::string::{kCFStringEncodingUTF8, CFStringGetCString, CFStringRef};
+    use coreaudio_sys::{
+        kAudioDevicePropertyDeviceNameCFString, kAudioDevicePropertyDeviceUID,
+        kAudioDevicePropertyStreamConfiguration, kAudioHardwarePropertyDevices,
+        kAudioObjectPropertyElementMaster, kAudioObjectPropertyScopeGlobal, kAudioObjectPropertyScopeInput,
+        kAudioObjectPropertyScopeOutput, kAudioObjectSystemObject, AudioBufferList, AudioDeviceID,
+        AudioObjectGetPropertyData, AudioObjectGetPropertyDataSize, AudioObjectPropertyAddress,
+        AudioObjectPropertyScope, AudioObjectPropertySelector,
+    };
+    
+    /// List CoreAudio devices with input (or output) channels as (name, UID) pairs
+    ///
+    /// The UID is assigned by the driver and survives reconnects and reboots.
+    pub fn list(output: bool) -> anyhow::Result<Vec<(String, String)>> {
+        let scope = if output { kAudioObjectPropertyScopeOutput } else { kAudioObjectPropertyScopeInput };
+        
+        let mut devices = Vec::new();
+        for device in device_ids()? {
+            // Same test cpal uses to decide whether a device is an input or an output
+            if channel_count(device, scope)? == 0 {
+                continue;
+            }
+            
+            let name = string_property(device, kAudioDevicePropertyDeviceNameCFString)?;
+            let uid = string_property(device, kAudioDevicePropertyDeviceUID)?;
+            devices.push((name, uid));
+        }
+        
+        Ok(devices)
+    }
+    
+    fn address(selector: AudioObjectPropertySelector, scope: AudioObjectPropertyScope) -> AudioObjectPropertyAddress {
+        AudioObjectPropertyAddress {
+            mSelector: selector,
+            mScope: scope,
+            mElement: kAudioObjectPropertyElementMaster,
+        }
+    }
+    
+    fn check(status: i32, what: &str) -> anyhow::Result<()> {
+        if status != 0 {
+            anyhow::bail!("Failed to read {} (OSStatus {})", what, status);
+        }
+        Ok(())
+    }
+    
+    /// IDs of all audio devices known to the system
+    fn device_ids() -> anyhow::Result<Vec<AudioDeviceID>> {
+        let address = address(kAudioHardwarePropertyDevices, kAudioObjectPropertyScopeGlobal);
+        
+        unsafe {
+            let mut size = 0u32;
+            check(
+                AudioObjectGetPropertyDataSize(kAudioObjectSystemObject, &address, 0, null(), &mut size),
+                "device list",
+            )?;
+            
+            let mut ids: Vec<AudioDeviceID> = vec![0; size as usize / mem::size_of::<AudioDeviceID>()];
+            check(
+                AudioObjectGetPropertyData(
+                    kAudioObjectSystemObject,
+                    &address,
+                    0,
+                    null(),
+                    &mut size,
+                    ids.as_mut_ptr() as *mut c_void,
+                ),
+                "device list",
+            )?;
+            
+            // A device may have disappeared between the two calls
+            ids.truncate(size as usize / mem::size_of::<AudioDeviceID>());
+            Ok(ids)
+        }
+    }
+    
+    /// Total channels a device has in the given scope
+    fn channel_count(device: AudioDeviceID, scope: AudioObjectPropertyScope) -> anyhow::Result<u32> {
+        let address = address(kAudioDevicePropertyStreamConfiguration, scope);
+        
+        unsafe {
+            let mut size = 0u32;
+            check(
+                AudioObjectGetPropertyDataSize(device, &address, 0, null(), &mut size),
+                "stream configuration",
+            )?;
+            if size == 0 {
+                return Ok(0);
+            }
+            
+            // AudioBufferList ends in a variable-length array; u64 storage keeps it aligned
+            let mut storage = vec![0u64; (size as usize + 7) / 8];
+            check(
+                AudioObjectGetPropertyData(device, &address, 0, null(), &mut size, storage.as_mut_ptr() as *mut c_void),
+                "stream configuration",
+            )?;
+            
+            let list = &*(storage.as_ptr() as *const AudioBufferList);
+            let buffers = std::slice::from_raw_parts(list.mBuffers.as_ptr(), list.mNumberBuffers as usize);
+            Ok(buffers.iter().map(|buffer| buffer.mNumberChannels).sum())
+        }
+    }
+    
+    /// Read a CFString device property
+    fn string_property(device: AudioDeviceID, selector: AudioObjectPropertySelector) -> anyhow::Result<String> {
+        let address = address(selector, kAudioObjectPropertyScopeGlobal);
+        
+        unsafe {
+            let mut value: CFStringRef = null();
+            let mut size = mem::size_of::<CFStringRef>() as u32;
+            check(
+                AudioObjectGetPropertyData(device, &address, 0, null(), &mut size, &mut value as *mut _ as *mut c_void),
+                "device property",
+            )?;
+            if value.is_null() {
+                anyhow::bail!("Device property is empty");
+            }
+            
+            let mut buffer = [0 as c_char; 512];
+            let converted = CFStringGetCString(value, buffer.as_mut_ptr(), buffer.len() as _, kCFStringEncodingUTF8);
+            CFRelease(value as *const c_void);
+            
+            if converted == 0 {
+                anyhow::bail!("Device property could not be converted to UTF-8");
+            }
+            Ok(CStr::from_ptr(buffer.as_ptr()).to_string_lossy().into_owned())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_stable_device_id_is_deterministic() {
+        assert_eq!(
+            stable_device_id("ALSA", "USB Microphone", 0),
+            stable_device_id("ALSA", "USB Microphone", 0)
+        );
+    }
+    
+    #[test]
+    fn test_identical_devices_get_distinct_ids() {
+        assert_ne!(
+            stable_device_id("ALSA", "USB Microphone", 0),
+            stable_device_id("ALSA", "USB Microphone", 1)
+        );
+    }
+    
+    #[test]
+    fn test_pulse_source_names() {
+        assert_eq!(pulse_source_name("pulse:alsa_input.usb-Yeti-00.analog-stereo"), Some("alsa_input.usb-Yeti-00.analog-stereo"));
+        assert_eq!(pulse_source_name("loopback:pulse:alsa_output.pci.analog-stereo.monitor"), Some("alsa_output.pci.analog-stereo.monitor"));
+        assert_eq!(pulse_source_name("dev-0123456789abcdef"), None);
+        assert!(loopback_supported("loopback:pulse:alsa_output.pci.analog-stereo.monitor"));
+    }
+}
@@ -0,0 +1,502 @@
+use anyhow::Result;
+use log::warn;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::io::Read;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::config::{DecodingSettings, SpeechSettings};
+
+#[cfg(feature = "whisper")]
+use std::path::PathBuf;
+#[cfg(feature = "whisper")]
+use log::info;
+
+/// How long a failing primary engine is skipped before it is tried again
+const FALLBACK_RETRY_AFTER: Duration = Duration::from_secs(60);
+#[cfg(feature = "whisper")]
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+/// Spoken language an engine detected
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DetectedLanguage {
+    /// Language as the engine names it, usually a code like "en" (OpenAI names it, e.g. "english")
+    pub language: String,
+
+    /// Probability of the detection (0.0 - 1.0), if the engine reports it
+    pub probability: Option<f32>,
+}
+
+/// A piece of transcribed text, timed from the start of its buffer
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscriptSegment {
+    /// Time the segment starts
+    pub start: Duration,
+
+    /// Time the segment ends
+    pub end: Duration,
+
+    /// Recognised text
+    pub text: String,
+
+    /// How sure the engine is of the text (0.0 - 1.0), if it reports that
+    pub confidence: Option<f32>,
+
+    /// Language detected in the segment, when none was set
+    pub language: Option<DetectedLanguage>,
+
+    /// Probability that the segment contains no speech (0.0 - 1.0), if the engine reports it
+    pub no_speech_probability: Option<f32>,
+}
+
+/// Per-buffer options passed to an engine
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TranscribeOptions {
+    /// Spoken language, or `None` to detect it
+    pub language: Option<String>,
+
+    /// Translate the speech to English
+    pub translate: bool,
+
+    /// Decoder tuning, for engines that support it
+    pub decoding: DecodingSettings,
+}
+
+impl TranscribeOptions {
+    /// Options for the current speech settings
+    pub fn from_settings(settings: &SpeechSettings) -> Self {
+        let language = match settings.language.as_str() {
+            "" | "auto" => None,
+            language => Some(language.to_string()),
+        };
+
+        Self {
+            language,
+            translate: settings.translate_to_english,
+            decoding: settings.decoding.clone(),
+        }
+    }
+}
+
+/// A speech-to-text backend
+///
+/// Engines are loaded once and then shared with blocking tasks, so
+/// `transcribe` takes `&self` and may run on several buffers at once.
+pub trait SttEngine: Send + Sync {
+    /// Short name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Load the model (or whatever else the engine needs) so it is ready to transcribe
+    fn load(&mut self) -> Result<()>;
+
+    /// Whether `load` has succeeded and `unload` has not been called since
+    fn is_loaded(&self) -> bool;
+
+    /// Transcribe a buffer of 16 kHz mono audio
+    fn transcribe(&self, audio: &[f32], options: &TranscribeOptions) -> Result<Vec<TranscriptSegment>>;
+
+    /// Release the model
+    fn unload(&mut self);
+}
+
+/// Text of a transcription, with segments joined by spaces
+pub fn segments_text(segments: &[TranscriptSegment]) -> String {
+    segments.iter()
+        .map(|segment| segment.text.trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Confidence of a transcription, averaging its segments weighted by their length in words
+pub fn segments_confidence(segments: &[TranscriptSegment]) -> Option<f32> {
+    let (weighted, words) = segments.iter()
+        .filter_map(|segment| {
+            let words = segment.text.split_whitespace().count();
+            segment.confidence.map(|confidence| (confidence * words as f32, words))
+        })
+        .fold((0.0, 0), |(weighted, total), (confidence, words)| (weighted + confidence, total + words));
+
+    (words > 0).then(|| weighted / words as f32)
+}
+
+/// Language detected in a transcription, from its first segment that has one
+pub fn segments_language(segments: &[TranscriptSegment]) -> Option<DetectedLanguage> {
+    segments.iter().find_map(|segment| segment.language.clone())
+}
+
+/// Container format of a Whisper model file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelFileFormat {
+    /// whisper.cpp's original format
+    Ggml,
+
+    /// The newer self-describing format
+    Gguf,
+}
+
+/// Check that a file is a GGML or GGUF model before it is loaded
+///
+/// whisper.cpp aborts on some malformed files instead of returning an error,
+/// so files registered by hand are checked by their magic number first.
+pub fn check_model_file(path: &Path) -> Result<ModelFileFormat> {
+    let mut magic = [0u8; 4];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .map_err(|e| anyhow::anyhow!("Failed to read model file {}: {}", path.display(), e))?;
+
+    match &magic {
+        // 0x67676d6c ("ggml") written little-endian
+        b"lmgg" => Ok(ModelFileFormat::Ggml),
+        b"GGUF" => Ok(ModelFileFormat::Gguf),
+        _ => Err(anyhow::anyhow!("{} is not a GGML or GGUF model file", path.display())),
+    }
+}
+
+/// Transcribe a second of silence so the engine's first real buffer isn't slowed down
+///
+/// The first inference allocates the decoder's buffers and pulls the model's
+/// weights into memory. Returns how long the warm-up took.
+pub fn warm_up(engine: &dyn SttEngine, options: &TranscribeOptions) -> Result<Duration> {
+    let started = Instant::now();
+    engine.transcribe(&[0.0; 16000], options)?;
+    Ok(started.elapsed())
+}
+
+/// Uses one engine and switches to another while the first is failing
+///
+/// Meant for a cloud engine backed by local Whisper: when the network is
+/// down every buffer would otherwise wait for a timeout, so after a failure
+/// the primary engine is left alone for a while.
+pub struct FallbackEngine {
+    /// Engine tried first
+    primary: Box<dyn SttEngine>,
+
+    /// Engine used while the primary is failing
+    fallback: Box<dyn SttEngine>,
+
+    /// When the primary engine last failed
+    primary_failed_at: Mutex<Option<Instant>>,
+}
+
+impl FallbackEngine {
+    /// Use `primary`, falling back to `fallback` when it fails
+    pub fn new(primary: Box<dyn SttEngine>, fallback: Box<dyn SttEngine>) -> Self {
+        Self {
+            primary,
+            fallback,
+            primary_failed_at: Mutex::new(None),
+        }
+    }
+}
+
+impl SttEngine for FallbackEngine {
+    fn name(&self) -> &'static str {
+        self.primary.name()
+    }
+
+    fn load(&mut self) -> Result<()> {
+        if !self.fallback.is_loaded() {
+            self.fallback.load()?;
+        }
+
+        // The fallback alone can still transcribe, so a primary that won't load is not fatal
+        if !self.primary.is_loaded() {
+            if let Err(e) = self.primary.load() {
+                warn!("Failed to load the {} engine, using {}: {}", self.primary.name(), self.fallback.name(), e);
+                *self.primary_failed_at.lock() = Some(Instant::now());
+            }
+        }
+        Ok(())
+    }
+
+    fn is_loaded(&self) -> bool {
+        self.fallback.is_loaded()
+    }
+
+    fn transcribe(&self, audio: &[f32], options: &TranscribeOptions) -> Result<Vec<TranscriptSegment>> {
+        let primary_failing = self.primary_failed_at.lock()
+            .is_some_and(|failed_at| failed_at.elapsed() < FALLBACK_RETRY_AFTER);
+
+        if self.primary.is_loaded() && !primary_failing {
+            match self.primary.transcribe(audio, options) {
+                Ok(segments) => {
+                    *self.primary_failed_at.lock() = None;
+                    return Ok(segments);
+                }
+                Err(e) => {
+                    warn!("The {} engine failed, using {}: {}", self.primary.name(), self.fallback.name(), e);
+                    *self.primary_failed_at.lock() = Some(Instant::now());
+                }
+            }
+        }
+
+        self.fallback.transcribe(audio, options)
+    }
+
+    fn unload(&mut self) {
+        self.primary.unload();
+        self.fallback.unload();
+    }
+}
+
+/// Local transcription with whisper.cpp, the default engine
+#[cfg(feature = "whisper")]
+pub struct WhisperEngine {
+    /// Path to the GGML model file
+    model_file: PathBuf,
+
+    /// Loaded model
+    context: Option<WhisperContext>,
+}
+
+#[cfg(feature = "whisper")]
+impl WhisperEngine {
+    /// Engine for a model file; the model is read when the engine is loaded
+    pub fn new(model_file: PathBuf) -> Self {
+        Self { model_file, context: None }
+    }
+}
+
+#[cfg(feature = "whisper")]
+impl SttEngine for WhisperEngine {
+    fn name(&self) -> &'static str {
+        "whisper"
+    }
+
+    fn load(&mut self) -> Result<()> {
+        info!("Loading Whisper model from {:?}", self.model_file);
+        check_model_file(&self.model_file)?;
+        let context = WhisperContext::new_with_params(&self.model_file.to_string_lossy(), WhisperContextParameters::new())
+            .map_err(|e| anyhow::anyhow!("Failed to load whisper model: {}", e))?;
+        self.context = Some(context);
+        Ok(())
+    }
+
+    fn is_loaded(&self) -> bool {
+        self.context.is_some()
+    }
+
+    fn transcribe(&self, audio: &[f32], options: &TranscribeOptions) -> Result<Vec<TranscriptSegment>> {
+        let context = self.context.as_ref().ok_or_else(|| anyhow::anyhow!("Whisper model is not loaded"))?;
+
+        // A state per call lets buffers from different tracks run in parallel
+        let mut state = context.create_state()
+            .map_err(|e| anyhow::anyhow!("Failed to create whisper state: {}", e))?;
+
+        // English-only models (e.g. distil-small.en) can neither detect a language nor translate
+        let multilingual = context.is_multilingual();
+
+        // Without a set language, detect it here to learn its probability (`full` would detect it the same way)
+        let detected = match options.language {
+            Some(_) => None,
+            None if !multilingual => None,
+            None => state.pcm_to_mel(audio, 1).ok()
+                .and_then(|_| state.lang_detect(0, 1).ok())
+                .and_then(|probabilities| {
+                    let (id, probability) = probabilities.into_iter()
+                        .enumerate()
+                        .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+                    Some((whisper_rs::get_lang_str(id as i32)?, probability))
+                }),
+        };
+
+        let decoding = &options.decoding;
+        let strategy = if decoding.beam_size > 1 {
+            SamplingStrategy::BeamSearch { beam_size: decoding.beam_size as i32, patience: -1.0 }
+        } else {
+            SamplingStrategy::Greedy { best_of: decoding.best_of.max(1) as i32 }
+        };
+
+        let mut params = FullParams::new(strategy);
+        params.set_temperature(decoding.temperature);
+        params.set_temperature_inc(decoding.temperature_increment);
+        params.set_no_speech_thold(decoding.no_speech_threshold);
+        params.set_logprob_thold(decoding.logprob_threshold);
+        if multilingual {
+            params.set_language(options.language.as_deref().or(detected.map(|(language, _)| language)));
+        } else {
+            params.set_language(Some("en"));
+        }
+        params.set_translate(options.translate && multilingual);
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+
+        state.full(params, audio)
+            .map_err(|e| anyhow::anyhow!("Failed to run inference: {}", e))?;
+
+        let num_segments = state.full_n_segments()
+            .map_err(|e| anyhow::anyhow!("Failed to get segments: {}", e))?;
+
+        let mut segments = Vec::new();
+        for i in 0..num_segments {
+            if let Ok(text) = state.full_get_segment_text(i) {
+                // Segment times are in centiseconds from the start of the buffer
+                let t0 = state.full_get_segment_t0(i).unwrap_or(0).max(0) as u64;
+                let t1 = state.full_get_segment_t1(i).unwrap_or(0).max(0) as u64;
+
+                // Mean probability of the text tokens; timestamps and other special tokens come after end-of-text
+                let num_tokens = state.full_n_tokens(i).unwrap_or(0);
+                let probabilities: Vec<f32> = (0..num_tokens)
+                    .filter(|&j| state.full_get_token_id(i, j).is_ok_and(|id| id < context.token_eot()))
+                    .filter_map(|j| state.full_get_token_prob(i, j).ok())
+                    .collect();
+                let confidence = (!probabilities.is_empty())
+                    .then(|| probabilities.iter().sum::<f32>() / probabilities.len() as f32);
+
+                segments.push(TranscriptSegment {
+                    start: Duration::from_millis(t0 * 10),
+                    end: Duration::from_millis(t1 * 10),
+                    text,
+                    confidence,
+                    language: detected.map(|(language, probability)| DetectedLanguage {
+                        language: language.to_string(),
+                        probability: Some(probability),
+                    }),
+                    // Not exposed by this version of whisper.cpp, which applies its no-speech threshold itself
+                    no_speech_probability: None,
+                });
+            }
+        }
+
+        Ok(segments)
+    }
+
+    fn unload(&mut self) {
+        self.context = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn segment(text: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            start: Duration::ZERO,
+            end: Duration::ZERO,
+            text: text.to_string(),
+            confidence: None,
+            language: None,
+            no_speech_probability: None,
+        }
+    }
+
+    /// Engine that returns a fixed text, or fails
+    struct FixedEngine {
+        text: Option<&'static str>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl SttEngine for FixedEngine {
+        fn name(&self) -> &'static str {
+            "fixed"
+        }
+
+        fn load(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn is_loaded(&self) -> bool {
+            true
+        }
+
+        fn transcribe(&self, _audio: &[f32], _options: &TranscribeOptions) -> Result<Vec<TranscriptSegment>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match self.text {
+                Some(text) => Ok(vec![segment(text)]),
+                None => Err(anyhow::anyhow!("offline")),
+            }
+        }
+
+        fn unload(&mut self) {}
+    }
+
+    #[test]
+    fn test_fallback_skips_failing_primary() {
+        let primary_calls = Arc::new(AtomicUsize::new(0));
+        let engine = FallbackEngine::new(
+            Box::new(FixedEngine { text: None, calls: Arc::clone(&primary_calls) }),
+            Box::new(FixedEngine { text: Some("local"), calls: Arc::new(AtomicUsize::new(0)) }),
+        );
+        let options = TranscribeOptions::default();
+
+        assert_eq!(segments_text(&engine.transcribe(&[], &options).unwrap()), "local");
+        assert_eq!(segments_text(&engine.transcribe(&[], &options).unwrap()), "local");
+
+        // The second buffer went straight to the fallback
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_model_files_are_checked() {
+        let dir = std::env::temp_dir().join(format!("bestme-model-check-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let ggml = dir.join("ggml.bin");
+        std::fs::write(&ggml, [0x6c, 0x6d, 0x67, 0x67, 0, 0]).unwrap();
+        let gguf = dir.join("model.gguf");
+        std::fs::write(&gguf, b"GGUF\x03\x00").unwrap();
+        let text = dir.join("notes.txt");
+        std::fs::write(&text, "not a model").unwrap();
+
+        assert_eq!(check_model_file(&ggml).unwrap(), ModelFileFormat::Ggml);
+        assert_eq!(check_model_file(&gguf).unwrap(), ModelFileFormat::Gguf);
+        assert!(check_model_file(&text).is_err());
+        assert!(check_model_file(&dir.join("missing.bin")).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_segments_are_joined() {
+        let segments = [segment(" Hello there."), segment("  "), segment(" How are you? ")];
+
+        assert_eq!(segments_text(&segments), "Hello there. How are you?");
+        assert_eq!(segments_text(&[]), "");
+    }
+
+    #[test]
+    fn test_confidence_is_weighted_by_words() {
+        let segments = [
+            TranscriptSegment { confidence: Some(0.9), ..segment("one two three") },
+            TranscriptSegment { confidence: Some(0.5), ..segment("four") },
+            segment("unscored words here"),
+        ];
+
+        assert!((segments_confidence(&segments).unwrap() - 0.8).abs() < 1e-6);
+        assert_eq!(segments_confidence(&[segment("hello")]), None);
+    }
+
+    #[test]
+    fn test_language_from_first_detecting_segment() {
+        let english = DetectedLanguage { language: "en".to_string(), probability: Some(0.9) };
+        let segments = [
+            segment("..."),
+            TranscriptSegment { language: Some(english.clone()), ..segment("hello") },
+        ];
+
+        assert_eq!(segments_language(&segments), Some(english));
+        assert_eq!(segments_language(&[segment("hello")]), None);
+    }
+
+    #[test]
+    fn test_options_from_settings() {
+        let mut settings = crate::config::Config::default().audio.speech;
+        settings.language = "auto".to_string();
+        settings.translate_to_english = true;
+
+        assert_eq!(
+            TranscribeOptions::from_settings(&settings),
+            TranscribeOptions { language: None, translate: true, decoding: DecodingSettings::default() }
+        );
+
+        settings.language = "de".to_string();
+        assert_eq!(TranscribeOptions::from_settings(&settings).language.as_deref(), Some("de"));
+    }
+}
@@ -0,0 +1,308 @@
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::io::{Cursor, Write as _};
+use std::path::Path;
+
+use super::session::{Session, SessionSegment};
+
+/// Formats a session can be exported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Markdown (`.md`), with the details as a list and each paragraph led by its time and speaker
+    Markdown,
+
+    /// The session as saved, segment by segment (`.json`)
+    Json,
+
+    /// Word document (`.docx`)
+    Docx,
+
+    /// Plain text (`.txt`)
+    Text,
+}
+
+impl ExportFormat {
+    /// Format named by its name or file extension, e.g. "markdown" or "md"
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim_start_matches('.').to_lowercase().as_str() {
+            "md" | "markdown" => Some(Self::Markdown),
+            "json" => Some(Self::Json),
+            "docx" | "word" => Some(Self::Docx),
+            "txt" | "text" => Some(Self::Text),
+            _ => None,
+        }
+    }
+
+    /// File extension without the dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Json => "json",
+            Self::Docx => "docx",
+            Self::Text => "txt",
+        }
+    }
+}
+
+/// Render a session in `format` and write it to `path`
+pub fn export_session(session: &Session, format: ExportFormat, path: &Path) -> Result<()> {
+    let contents = render(session, format)?;
+    std::fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Render a session in `format`
+///
+/// Text is grouped into paragraphs by speaker and by the paragraph breaks
+/// of the transcript, each led by the time it starts at.
+pub fn render(session: &Session, format: ExportFormat) -> Result<Vec<u8>> {
+    match format {
+        ExportFormat::Markdown => Ok(render_markdown(session).into_bytes()),
+        ExportFormat::Json => Ok(serde_json::to_vec_pretty(session)?),
+        ExportFormat::Docx => render_docx(session),
+        ExportFormat::Text => Ok(render_text(session).into_bytes()),
+    }
+}
+
+/// A stretch of one speaker's speech
+#[derive(Debug, PartialEq)]
+struct Paragraph<'a> {
+    start_ms: u64,
+    speaker: Option<&'a str>,
+
+    /// Lines of the paragraph, as broken in the transcript
+    lines: Vec<String>,
+}
+
+impl Paragraph<'_> {
+    /// Time and speaker the paragraph is led by, e.g. "[00:01:05] Me:"
+    fn lead(&self) -> String {
+        match self.speaker {
+            Some(speaker) => format!("[{}] {}:", timestamp(self.start_ms), speaker),
+            None => format!("[{}]", timestamp(self.start_ms)),
+        }
+    }
+}
+
+/// Group a session's segments into paragraphs
+fn paragraphs(session: &Session) -> Vec<Paragraph<'_>> {
+    let mut paragraphs: Vec<Paragraph> = Vec::new();
+
+    for segment in &session.segments {
+        let body = segment_body(segment);
+        if body.is_empty() {
+            continue;
+        }
+
+        // A segment starting on a new line carries the break before it
+        let breaks = segment.text[..segment.text.len() - segment.text.trim_start().len()].matches('\n').count();
+        let speaker = segment.speaker.as_deref();
+
+        match paragraphs.last_mut() {
+            Some(paragraph) if paragraph.speaker == speaker && breaks == 0 => {
+                if let Some(line) = paragraph.lines.last_mut() {
+                    line.push(' ');
+                    line.push_str(body);
+                }
+            }
+            Some(paragraph) if paragraph.speaker == speaker && breaks == 1 => paragraph.lines.push(body.to_string()),
+            _ => paragraphs.push(Paragraph { start_ms: segment.start_ms, speaker, lines: vec![body.to_string()] }),
+        }
+    }
+
+    paragraphs
+}
+
+/// Text of a segment without its break or speaker label
+fn segment_body(segment: &SessionSegment) -> &str {
+    let text = segment.text.trim();
+    segment.speaker.as_deref()
+        .and_then(|speaker| text.strip_prefix(speaker))
+        .and_then(|rest| rest.strip_prefix(": "))
+        .unwrap_or(text)
+}
+
+/// Offset into a session as "HH:MM:SS"
+fn timestamp(ms: u64) -> String {
+    format!("{:02}:{:02}:{:02}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60)
+}
+
+/// The session's title, or when it was recorded
+fn title(session: &Session) -> String {
+    session.title.clone()
+        .unwrap_or_else(|| format!("Transcript {}", session.started.format("%Y-%m-%d %H:%M")))
+}
+
+/// What the session was recorded with, as (label, value) pairs
+fn details(session: &Session) -> Vec<(&'static str, String)> {
+    let mut date = session.started.format("%Y-%m-%d %H:%M").to_string();
+    if let Some(ended) = session.ended {
+        let _ = write!(date, " - {}", ended.format("%H:%M"));
+    }
+
+    let mut details = vec![("Date", date)];
+    if let Some(device) = &session.device {
+        details.push(("Device", device.clone()));
+    }
+    details.push(("Model", session.model.clone()));
+    details.push(("Language", session.language.clone()));
+    details
+}
+
+fn render_text(session: &Session) -> String {
+    let mut text = format!("{}\n", title(session));
+    for (label, value) in details(session) {
+        let _ = writeln!(text, "{}: {}", label, value);
+    }
+
+    for paragraph in paragraphs(session) {
+        let _ = write!(text, "\n{} {}\n", paragraph.lead(), paragraph.lines.join("\n"));
+    }
+    text
+}
+
+fn render_markdown(session: &Session) -> String {
+    let mut markdown = format!("# {}\n\n", title(session));
+    for (label, value) in details(session) {
+        let _ = writeln!(markdown, "- **{}:** {}", label, value);
+    }
+
+    // Lines within a paragraph end in two spaces to keep their breaks
+    for paragraph in paragraphs(session) {
+        let _ = write!(markdown, "\n**{}** {}\n", paragraph.lead(), paragraph.lines.join("  \n"));
+    }
+    markdown
+}
+
+/// Package parts of a minimal Word document
+const DOCX_CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/></Types>"#;
+const DOCX_RELATIONSHIPS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/></Relationships>"#;
+
+fn render_docx(session: &Session) -> Result<Vec<u8>> {
+    let mut body = format!(
+        r#"<w:p><w:r><w:rPr><w:b/><w:sz w:val="32"/></w:rPr><w:t xml:space="preserve">{}</w:t></w:r></w:p>"#,
+        escape_xml(&title(session)),
+    );
+    for (label, value) in details(session) {
+        let _ = write!(body, r#"<w:p><w:r><w:t xml:space="preserve">{}: {}</w:t></w:r></w:p>"#, label, escape_xml(&value));
+    }
+
+    for paragraph in paragraphs(session) {
+        let _ = write!(body, r#"<w:p><w:r><w:rPr><w:b/></w:rPr><w:t xml:space="preserve">{} </w:t></w:r>"#, escape_xml(&paragraph.lead()));
+        for (i, line) in paragraph.lines.iter().enumerate() {
+            let line_break = if i > 0 { "<w:br/>" } else { "" };
+            let _ = write!(body, r#"<w:r>{}<w:t xml:space="preserve">{}</w:t></w:r>"#, line_break, escape_xml(line));
+        }
+        body.push_str("</w:p>");
+    }
+
+    let document = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>{}</w:body></w:document>"#,
+        body,
+    );
+
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (name, contents) in [
+        ("[Content_Types].xml", DOCX_CONTENT_TYPES),
+        ("_rels/.rels", DOCX_RELATIONSHIPS),
+        ("word/document.xml", document.as_str()),
+    ] {
+        zip.start_file(name, options)?;
+        zip.write_all(contents.as_bytes())?;
+    }
+
+    Ok(zip.finish().context("Failed to write Word document")?.into_inner())
+}
+
+/// Escape text for XML, dropping control characters XML can't hold
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' | '\n' | '\r' => escaped.push(' '),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use chrono::TimeZone;
+    use std::io::Read;
+    use std::time::Duration;
+
+    fn session() -> Session {
+        let mut session = Session::new(&Config::default().audio.speech, Some("Microphone".to_string()));
+        session.started = chrono::Local.with_ymd_and_hms(2024, 1, 2, 9, 30, 0).unwrap();
+        session.ended = Some(chrono::Local.with_ymd_and_hms(2024, 1, 2, 10, 0, 0).unwrap());
+        session.language = "en".to_string();
+
+        let me = Some("Me".to_string());
+        let others = Some("Others".to_string());
+        let secs = Duration::from_secs;
+        session.push(secs(0), secs(2), me.clone(), "Me: Did the invoice go out?".to_string(), None);
+        session.push(secs(3), secs(4), me.clone(), "Me: It's late.".to_string(), None);
+        session.push(secs(5), secs(9), others.clone(), "Others: Yes, <today> & on time.".to_string(), None);
+        session.push(secs(65), secs(66), others.clone(), "\nOthers: 1. Send it".to_string(), None);
+        session.push(secs(70), secs(72), others, "\n\nOthers: Anything else?".to_string(), None);
+        session
+    }
+
+    #[test]
+    fn test_format_names() {
+        assert_eq!(ExportFormat::from_name("Markdown"), Some(ExportFormat::Markdown));
+        assert_eq!(ExportFormat::from_name(".md"), Some(ExportFormat::Markdown));
+        assert_eq!(ExportFormat::from_name("docx"), Some(ExportFormat::Docx));
+        assert_eq!(ExportFormat::from_name("pdf"), None);
+        assert_eq!(ExportFormat::Text.extension(), "txt");
+    }
+
+    #[test]
+    fn test_text_and_markdown() {
+        let session = session();
+
+        let text = String::from_utf8(render(&session, ExportFormat::Text).unwrap()).unwrap();
+        assert_eq!(text, "Transcript 2024-01-02 09:30\n\
+            Date: 2024-01-02 09:30 - 10:00\n\
+            Device: Microphone\n\
+            Model: small\n\
+            Language: en\n\
+            \n[00:00:00] Me: Did the invoice go out? It's late.\n\
+            \n[00:00:05] Others: Yes, <today> & on time.\n1. Send it\n\
+            \n[00:01:10] Others: Anything else?\n");
+
+        let markdown = String::from_utf8(render(&session, ExportFormat::Markdown).unwrap()).unwrap();
+        assert!(markdown.starts_with("# Transcript 2024-01-02 09:30\n\n- **Date:** 2024-01-02 09:30 - 10:00\n"));
+        assert!(markdown.contains("\n**[00:00:05] Others:** Yes, <today> & on time.  \n1. Send it\n"));
+
+        let json: Session = serde_json::from_slice(&render(&session, ExportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json, session);
+    }
+
+    #[test]
+    fn test_docx() {
+        let mut session = session();
+        session.title = Some("Invoices".to_string());
+
+        let docx = render(&session, ExportFormat::Docx).unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(docx)).unwrap();
+        assert!(archive.by_name("[Content_Types].xml").is_ok());
+
+        let mut document = String::new();
+        archive.by_name("word/document.xml").unwrap().read_to_string(&mut document).unwrap();
+        assert!(document.contains(">Invoices</w:t>"));
+        assert!(document.contains("Yes, &lt;today&gt; &amp; on time."));
+        assert!(document.contains("<w:br/><w:t xml:space=\"preserve\">1. Send it</w:t>"));
+    }
+}
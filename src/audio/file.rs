@@ -0,0 +1,238 @@
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use std::fs::File;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use tokio::sync::mpsc;
+
+use super::simd::downmix;
+use super::resample::resample_once;
+use super::transcribe::{TranscriptionEvent, TranscriptionManager};
+use crate::config::{ResamplerQuality, SpeechSettings};
+
+/// Sample rate expected by the transcription engine
+const TRANSCRIPTION_SAMPLE_RATE: u32 = 16000;
+
+/// Seconds of audio fed to the transcription manager at a time
+const FILE_CHUNK_SECONDS: usize = 3;
+
+/// Decoded mono audio
+#[derive(Debug, Clone)]
+pub struct DecodedAudio {
+    /// Mono samples
+    pub samples: Vec<f32>,
+
+    /// Sample rate in Hz
+    pub sample_rate: u32,
+}
+
+impl DecodedAudio {
+    /// Duration in seconds
+    pub fn duration_secs(&self) -> f32 {
+        self.samples.len() as f32 / self.sample_rate.max(1) as f32
+    }
+}
+
+/// Progress events for file transcription
+#[derive(Debug, Clone)]
+pub enum FileTranscriptionEvent {
+    /// File decoded and ready to transcribe
+    Decoded { duration_secs: f32 },
+
+    /// Fraction of the file transcribed (0.0 - 1.0)
+    Progress(f32),
+
+    /// Text transcribed from part of the file
+    Segment(String),
+
+    /// Transcription finished with the full text
+    Completed(String),
+}
+
+/// Decode an audio file (WAV, MP3, FLAC, OGG/Vorbis) to mono samples
+pub fn decode_audio_file(path: &Path) -> Result<DecodedAudio> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open audio file {}", path.display()))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(extension);
+    }
+
+    let probed = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| anyhow::anyhow!("Unsupported audio format: {}", e))?;
+    let mut format = probed.format;
+
+    let track = format.tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .context("No audio track found")?;
+    let track_id = track.id;
+    let sample_rate = track.codec_params.sample_rate
+        .context("Audio track has no sample rate")?;
+
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| anyhow::anyhow!("Unsupported audio codec: {}", e))?;
+
+    let mut samples = Vec::new();
+    let mut sample_buffer: Option<SampleBuffer<f32>> = None;
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(anyhow::anyhow!("Failed to read audio packet: {}", e)),
+        };
+
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(SymphoniaError::DecodeError(e)) => {
+                // Corrupt frames are skipped rather than failing the whole file
+                warn!("Skipping undecodable audio packet: {}", e);
+                continue;
+            }
+            Err(e) => return Err(anyhow::anyhow!("Failed to decode audio: {}", e)),
+        };
+
+        let spec = *decoded.spec();
+        let channels = spec.channels.count() as u16;
+
+        if sample_buffer.as_ref().map(|b| b.capacity() < decoded.capacity()).unwrap_or(true) {
+            sample_buffer = Some(SampleBuffer::new(decoded.capacity() as u64, spec));
+        }
+
+        if let Some(buffer) = sample_buffer.as_mut() {
+            buffer.copy_interleaved_ref(decoded);
+            samples.extend(downmix(buffer.samples(), channels));
+        }
+    }
+
+    debug!("Decoded {} samples at {} Hz from {}", samples.len(), sample_rate, path.display());
+
+    Ok(DecodedAudio { samples, sample_rate })
+}
+
+/// Transcribe an audio file
+///
+/// The file is decoded, resampled to 16 kHz mono and fed through a
+/// `TranscriptionManager`. Progress is reported on `progress` if provided.
+/// Returns the full transcript.
+pub async fn transcribe_file(
+    path: &Path,
+    settings: SpeechSettings,
+    quality: &ResamplerQuality,
+    progress: Option<mpsc::Sender<FileTranscriptionEvent>>,
+) -> Result<String> {
+    info!("Transcribing file {}", path.display());
+
+    // Decoding and resampling are CPU-bound
+    let decode_path = path.to_path_buf();
+    let quality = quality.clone();
+    let samples = tokio::task::spawn_blocking(move || -> Result<Vec<f32>> {
+        let decoded = decode_audio_file(&decode_path)?;
+        resample_once(&decoded.samples, decoded.sample_rate, TRANSCRIPTION_SAMPLE_RATE, &quality)
+    })
+    .await
+    .context("Failed to run decoding task")??;
+
+    let send = |event: FileTranscriptionEvent| {
+        let progress = progress.clone();
+        async move {
+            if let Some(sender) = progress {
+                let _ = sender.send(event).await;
+            }
+        }
+    };
+
+    send(FileTranscriptionEvent::Decoded {
+        duration_secs: samples.len() as f32 / TRANSCRIPTION_SAMPLE_RATE as f32,
+    }).await;
+
+    let (mut manager, mut events) = TranscriptionManager::new(settings)?;
+
+    // Results are collected from return values; drain the event channel so it never fills up
+    let drain = tokio::spawn(async move {
+        while let Some(event) = events.recv().await {
+            if let TranscriptionEvent::Error(e) = event {
+                warn!("File transcription error: {}", e);
+            }
+        }
+    });
+
+    manager.start().await?;
+
+    let mut segments = Vec::new();
+    let chunk_size = FILE_CHUNK_SECONDS * TRANSCRIPTION_SAMPLE_RATE as usize;
+    let total = samples.len().max(1);
+
+    for (index, chunk) in samples.chunks(chunk_size).enumerate() {
+        if let Some(text) = manager.process_audio(chunk).await? {
+            send(FileTranscriptionEvent::Segment(text.clone())).await;
+            segments.push(text);
+        }
+
+        let processed = ((index + 1) * chunk_size).min(total);
+        send(FileTranscriptionEvent::Progress(processed as f32 / total as f32)).await;
+    }
+
+    if let Some(text) = manager.flush().await? {
+        send(FileTranscriptionEvent::Segment(text.clone())).await;
+        segments.push(text);
+    }
+
+    manager.stop().await?;
+    drop(manager);
+    let _ = drain.await;
+
+    let transcript = segments.join(" ");
+    send(FileTranscriptionEvent::Progress(1.0)).await;
+    send(FileTranscriptionEvent::Completed(transcript.clone())).await;
+
+    info!("Finished transcribing {}", path.display());
+
+    Ok(transcript)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_wav_downmixes_to_mono() {
+        let path = std::env::temp_dir().join(format!("bestme_decode_test_{}.wav", std::process::id()));
+
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 22050,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..2205 {
+            writer.write_sample(i16::MAX / 2).unwrap();
+            writer.write_sample(0i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let decoded = decode_audio_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(decoded.sample_rate, 22050);
+        assert_eq!(decoded.samples.len(), 2205);
+        assert!((decoded.samples[0] - 0.25).abs() < 0.01);
+        assert!((decoded.duration_secs() - 0.1).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,465 @@
+use std::time::Duration;
+
+use super::punctuate::{capitalize, ends_sentence, is_closed};
+use crate::config::{FormattingSettings, SpeakerChangeMarker};
+
+/// Characters that attach to the word before them
+const CLOSING: [char; 10] = [',', '.', '!', '?', ';', ':', '%', ')', ']', '…'];
+
+/// Characters that attach to the word after them
+const OPENING: [char; 2] = ['(', '['];
+
+/// Ordinals that open a list item when followed by a comma, e.g. "second, call the bank"
+const ORDINALS: [(&str, u32); 13] = [
+    ("first", 1), ("firstly", 1), ("second", 2), ("secondly", 2), ("third", 3), ("thirdly", 3),
+    ("fourth", 4), ("fifth", 5), ("sixth", 6), ("seventh", 7), ("eighth", 8), ("ninth", 9), ("tenth", 10),
+];
+
+/// Words that open a list item when followed by a number, e.g. "step two"
+const ITEM_WORDS: [&str; 4] = ["number", "step", "point", "item"];
+
+/// Formats a stream's transcript as it grows
+///
+/// Each fragment is formatted in the context of the transcript before it:
+/// it is capitalized when the transcript ended a sentence, starts a new
+/// paragraph after a long pause, and continues a numbered list when it opens
+/// with the next item. Spacing around punctuation is tidied, and spelled-out
+/// numbers from ten up and percentages are written as digits.
+#[derive(Debug, Clone)]
+pub struct TranscriptFormatter {
+    /// Pause between fragments that starts a new paragraph
+    paragraph_pause: Duration,
+
+    /// Whether spelled-out numbers are written as digits
+    format_numbers: bool,
+
+    /// Whether spoken list items are numbered on their own lines
+    format_lists: bool,
+
+    /// Formatted transcript so far
+    transcript: String,
+
+    /// End of the last fragment
+    last_end: Option<Duration>,
+
+    /// Number of the last item of the list being spoken, if any
+    list_item: Option<u32>,
+
+    /// Marker for a change of speaker before the next fragment
+    speaker_change: Option<SpeakerChangeMarker>,
+}
+
+impl TranscriptFormatter {
+    /// Create a formatter for an empty transcript
+    pub fn new(settings: &FormattingSettings) -> Self {
+        Self {
+            paragraph_pause: Duration::from_secs_f32(settings.paragraph_pause_secs),
+            format_numbers: settings.format_numbers,
+            format_lists: settings.format_lists,
+            transcript: String::new(),
+            last_end: None,
+            list_item: None,
+            speaker_change: None,
+        }
+    }
+
+    /// Add a fragment spoken between `start` and `end` of the stream
+    ///
+    /// Returns the formatted fragment as appended to the transcript, starting
+    /// with the space, line or paragraph break that separates it.
+    pub fn push(&mut self, text: &str, start: Duration, end: Duration) -> String {
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() {
+            return String::new();
+        }
+
+        let paragraph = self.last_end.is_some_and(|last| start.saturating_sub(last) >= self.paragraph_pause);
+        self.last_end = Some(self.last_end.map_or(end, |last| last.max(end)));
+        let speaker_change = self.speaker_change.take().filter(|_| !self.transcript.is_empty());
+        if paragraph || speaker_change.is_some() {
+            self.list_item = None;
+        }
+
+        let mut text = space_punctuation(&text);
+        if self.format_numbers {
+            text = format_numbers(&text);
+        }
+
+        let mut separator = if self.transcript.is_empty() {
+            ""
+        } else if let Some(marker) = &speaker_change {
+            if paragraph { "\n\n" } else { marker.separator() }
+        } else if paragraph {
+            "\n\n"
+        } else if text.starts_with(CLOSING) {
+            ""
+        } else {
+            " "
+        };
+        let mut sentence_start = self.transcript.is_empty() || paragraph || speaker_change.is_some() || is_closed(&self.transcript);
+
+        let mut appended = String::new();
+        let mut prefix = speaker_change.as_ref().map_or("", |marker| marker.prefix());
+        for mut sentence in split_sentences(&text) {
+            if sentence_start && self.format_lists {
+                if let Some((number, rest, closes)) = list_marker(&sentence, self.list_item) {
+                    self.list_item = (!closes).then_some(number);
+                    if separator == " " {
+                        separator = "\n";
+                    }
+                    sentence = format!("{}. {}", number, capitalize(&rest));
+                }
+            }
+            if sentence_start {
+                sentence = capitalize(&sentence);
+            }
+
+            appended.push_str(separator);
+            appended.push_str(prefix);
+            appended.push_str(&sentence);
+            prefix = "";
+            sentence_start = is_closed(&sentence);
+            separator = " ";
+        }
+
+        self.transcript.push_str(&appended);
+        appended
+    }
+
+    /// Mark the next fragment as spoken by someone else, breaking the transcript before it
+    pub fn mark_speaker_change(&mut self, marker: SpeakerChangeMarker) {
+        self.speaker_change = Some(marker);
+    }
+
+    /// Formatted transcript so far
+    pub fn transcript(&self) -> &str {
+        &self.transcript
+    }
+
+    /// Start a new transcript, e.g. for a new session
+    pub fn reset(&mut self) {
+        self.transcript.clear();
+        self.last_end = None;
+        self.list_item = None;
+        self.speaker_change = None;
+    }
+}
+
+/// Remove spaces before closing and after opening punctuation, and add them after clause punctuation
+fn space_punctuation(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut spaced = String::with_capacity(text.len());
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c == ' ' {
+            let before_closing = chars.get(i + 1).is_some_and(|next| CLOSING.contains(next));
+            let after_opening = spaced.ends_with(OPENING);
+            if before_closing || after_opening {
+                continue;
+            }
+        }
+
+        spaced.push(c);
+        if [',', ';', ':', '!', '?'].contains(&c) && chars.get(i + 1).is_some_and(|next| next.is_alphabetic()) {
+            spaced.push(' ');
+        }
+    }
+
+    spaced
+}
+
+/// Split text into sentences at terminators
+fn split_sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut sentence: Vec<&str> = Vec::new();
+
+    for word in text.split(' ') {
+        sentence.push(word);
+        if ends_sentence(word) {
+            sentences.push(sentence.join(" "));
+            sentence.clear();
+        }
+    }
+    if !sentence.is_empty() {
+        sentences.push(sentence.join(" "));
+    }
+
+    sentences
+}
+
+/// A word split from the punctuation after it
+fn split_trailing(word: &str) -> (&str, &str) {
+    word.split_at(word.trim_end_matches(|c: char| !c.is_alphanumeric()).len())
+}
+
+/// The list item a sentence opens, its remaining text and whether it is the last item
+fn list_marker(sentence: &str, current: Option<u32>) -> Option<(u32, String, bool)> {
+    let words: Vec<&str> = sentence.split(' ').collect();
+    let (first, punctuation) = split_trailing(words[0]);
+    let first = first.to_lowercase();
+    let clause = punctuation.starts_with([',', ':']);
+
+    let (number, marker_len, closes) = if let Some(&(_, number)) = ORDINALS.iter().find(|(word, _)| *word == first) {
+        // "first of all, ..."
+        let of_all = words.len() > 2
+            && words[1].eq_ignore_ascii_case("of")
+            && split_trailing(words[2]).0.eq_ignore_ascii_case("all");
+        match (clause, of_all && number == 1) {
+            (true, _) => (number, 1, false),
+            (false, true) => (number, 3, false),
+            (false, false) => return None,
+        }
+    } else if ITEM_WORDS.contains(&first.as_str()) && punctuation.is_empty() && words.len() > 1 {
+        (cardinal(split_trailing(words[1]).0)?, 2, false)
+    } else if (first == "finally" || first == "lastly") && clause {
+        (current? + 1, 1, true)
+    } else {
+        return None;
+    };
+
+    if number != 1 && current != Some(number - 1) {
+        return None;
+    }
+
+    Some((number, words[marker_len..].join(" "), closes))
+}
+
+/// Value of a small cardinal number, written as a word or digits
+fn cardinal(word: &str) -> Option<u32> {
+    match number_word(word) {
+        Some(NumberWord::Unit(n) | NumberWord::Teen(n)) if n > 0 => Some(n as u32),
+        _ => word.parse().ok(),
+    }
+}
+
+/// Part of a spelled-out number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NumberWord {
+    /// Zero to nine
+    Unit(u64),
+
+    /// Ten to nineteen
+    Teen(u64),
+
+    /// Twenty, thirty, ... ninety
+    Tens(u64),
+
+    /// Hundred
+    Hundred,
+
+    /// Thousand, million or billion
+    Scale(u64),
+}
+
+/// Parse a single number word
+fn number_word(word: &str) -> Option<NumberWord> {
+    const UNITS: [&str; 10] = ["zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine"];
+    const TEENS: [&str; 10] = [
+        "ten", "eleven", "twelve", "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen", "nineteen",
+    ];
+    const TENS: [&str; 8] = ["twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety"];
+
+    let word = word.to_lowercase();
+    let position = |words: &[&str]| words.iter().position(|w| *w == word).map(|i| i as u64);
+
+    if let Some(n) = position(&UNITS) {
+        return Some(NumberWord::Unit(n));
+    }
+    if let Some(n) = position(&TEENS) {
+        return Some(NumberWord::Teen(10 + n));
+    }
+    if let Some(n) = position(&TENS) {
+        return Some(NumberWord::Tens(20 + n * 10));
+    }
+
+    match word.as_str() {
+        "hundred" => Some(NumberWord::Hundred),
+        "thousand" => Some(NumberWord::Scale(1_000)),
+        "million" => Some(NumberWord::Scale(1_000_000)),
+        "billion" => Some(NumberWord::Scale(1_000_000_000)),
+        _ => None,
+    }
+}
+
+/// Whether a number word can follow the previous one in the same number
+fn follows(previous: Option<NumberWord>, next: NumberWord) -> bool {
+    use NumberWord::*;
+
+    match (previous, next) {
+        (None, Hundred | Scale(_)) => false,
+        (None, _) => true,
+        (Some(Unit(0)), _) => false,
+        (Some(Unit(_) | Teen(_)), Hundred) => true,
+        (Some(Unit(_) | Teen(_) | Tens(_) | Hundred), Scale(_)) => true,
+        (Some(Tens(_)), Unit(n)) => n > 0,
+        (Some(Hundred | Scale(_)), Unit(_) | Teen(_) | Tens(_)) => true,
+        _ => false,
+    }
+}
+
+/// Value of the spelled-out number the words start with, the words it takes and the punctuation after it
+fn number_run(words: &[&str]) -> Option<(u64, usize, String)> {
+    let (mut total, mut current) = (0u64, 0u64);
+    let mut previous = None;
+    let mut used = 0;
+    let mut trailing = "";
+
+    let mut i = 0;
+    while i < words.len() {
+        let (core, punctuation) = split_trailing(words[i]);
+
+        // "one hundred and five"
+        if core.eq_ignore_ascii_case("and") && punctuation.is_empty() && matches!(previous, Some(NumberWord::Hundred | NumberWord::Scale(_))) {
+            i += 1;
+            continue;
+        }
+
+        // "twenty-five" is two parts of one word
+        let Some(parts) = core.split('-').map(number_word).collect::<Option<Vec<_>>>() else {
+            break;
+        };
+        let mut last = previous;
+        let mut fits = true;
+        for &part in &parts {
+            fits &= follows(last, part);
+            last = Some(part);
+        }
+        if !fits {
+            break;
+        }
+
+        for part in parts {
+            match part {
+                NumberWord::Unit(n) | NumberWord::Teen(n) | NumberWord::Tens(n) => current += n,
+                NumberWord::Hundred => current *= 100,
+                NumberWord::Scale(scale) => {
+                    total += current * scale;
+                    current = 0;
+                }
+            }
+        }
+        previous = last;
+        i += 1;
+        used = i;
+
+        if !punctuation.is_empty() {
+            trailing = punctuation;
+            break;
+        }
+    }
+
+    (used > 0).then(|| (total + current, used, trailing.to_string()))
+}
+
+/// Write spelled-out numbers from ten up, and any percentage, as digits
+pub fn format_numbers(text: &str) -> String {
+    let words: Vec<&str> = text.split(' ').collect();
+    let mut formatted: Vec<String> = Vec::with_capacity(words.len());
+
+    let mut i = 0;
+    while i < words.len() {
+        let (number, used, trailing, spelled) = match number_run(&words[i..]) {
+            Some((value, used, trailing)) => (value.to_string(), used, trailing, Some(value)),
+            None => {
+                let (core, punctuation) = split_trailing(words[i]);
+                let digits = core.starts_with(|c: char| c.is_ascii_digit())
+                    && core.chars().all(|c| c.is_ascii_digit() || c == '.' || c == ',');
+                if !digits {
+                    formatted.push(words[i].to_string());
+                    i += 1;
+                    continue;
+                }
+                (core.to_string(), 1, punctuation.to_string(), None)
+            }
+        };
+
+        // "five percent" is always "5%"
+        let percent = trailing.is_empty()
+            && words.get(i + used).is_some_and(|word| split_trailing(word).0.eq_ignore_ascii_case("percent"));
+        if percent {
+            formatted.push(format!("{}%{}", number, split_trailing(words[i + used]).1));
+            i += used + 1;
+        } else if spelled.map_or(true, |value| value >= 10) {
+            formatted.push(format!("{}{}", number, trailing));
+            i += used;
+        } else {
+            // Small numbers read better as words
+            formatted.extend(words[i..i + used].iter().map(|word| word.to_string()));
+            i += used;
+        }
+    }
+
+    formatted.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formatter() -> TranscriptFormatter {
+        TranscriptFormatter::new(&FormattingSettings::default())
+    }
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_fragments_continue_the_transcript() {
+        let mut formatter = formatter();
+        assert_eq!(formatter.push(" so we went there .", secs(0), secs(2)), "So we went there.");
+        assert_eq!(formatter.push("and then  home", secs(2), secs(3)), " And then home");
+        assert_eq!(formatter.push("after dinner ,right?", secs(3), secs(5)), " after dinner, right?");
+        assert_eq!(formatter.push("( maybe ) yes", secs(5), secs(6)), " (Maybe) yes");
+
+        // A long pause starts a paragraph
+        assert_eq!(formatter.push("next morning.", secs(10), secs(11)), "\n\nNext morning.");
+        assert_eq!(formatter.transcript(), "So we went there. And then home after dinner, right? (Maybe) yes\n\nNext morning.");
+
+        formatter.reset();
+        assert_eq!(formatter.push("again", secs(0), secs(1)), "Again");
+    }
+
+    #[test]
+    fn test_speaker_changes() {
+        let mut formatter = formatter();
+        formatter.mark_speaker_change(SpeakerChangeMarker::Dash);
+        assert_eq!(formatter.push("shall we start", secs(0), secs(1)), "Shall we start");
+
+        formatter.mark_speaker_change(SpeakerChangeMarker::Dash);
+        assert_eq!(formatter.push("yes, go ahead", secs(1), secs(2)), "\n— Yes, go ahead");
+        assert_eq!(formatter.push("please", secs(2), secs(3)), " please");
+
+        formatter.mark_speaker_change(SpeakerChangeMarker::Paragraph);
+        assert_eq!(formatter.push("thanks.", secs(3), secs(4)), "\n\nThanks.");
+
+        // A long pause still starts a paragraph
+        formatter.mark_speaker_change(SpeakerChangeMarker::Dash);
+        assert_eq!(formatter.push("right", secs(10), secs(11)), "\n\n— Right");
+    }
+
+    #[test]
+    fn test_numbers_and_lists() {
+        let mut formatter = formatter();
+        assert_eq!(
+            formatter.push("we need twenty-five chairs and three tables.", secs(0), secs(2)),
+            "We need 25 chairs and three tables."
+        );
+        assert_eq!(
+            formatter.push("that is one hundred and twenty people, up five percent.", secs(2), secs(4)),
+            " That is 120 people, up 5%."
+        );
+        assert_eq!(formatter.push("first, book the room.", secs(4), secs(5)), "\n1. Book the room.");
+        assert_eq!(formatter.push("second, order food. then relax.", secs(5), secs(6)), "\n2. Order food. Then relax.");
+        assert_eq!(formatter.push("finally, send invites.", secs(6), secs(7)), "\n3. Send invites.");
+
+        // The list is over, so this isn't a fourth item
+        assert_eq!(formatter.push("step four", secs(7), secs(8)), " Step four");
+
+        // Nor is a second without a first
+        let mut formatter = self::formatter();
+        assert_eq!(formatter.push("second, nothing", secs(0), secs(1)), "Second, nothing");
+        assert_eq!(formatter.push("number one is done", secs(1), secs(2)), " number one is done");
+        assert_eq!(formatter.push(". number one is done", secs(2), secs(3)), ".\n1. Is done");
+    }
+}
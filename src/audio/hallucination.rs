@@ -0,0 +1,202 @@
+use serde::Serialize;
+
+use super::engine::TranscriptSegment;
+use super::silence::{SilenceTrimmer, Trimmed};
+use crate::config::{HallucinationSettings, SilenceSettings};
+
+/// Sample rate of the audio engines are given
+const SAMPLE_RATE: u32 = 16000;
+
+/// Phrases Whisper tends to produce from silence or music, learned from video subtitles
+///
+/// A segment is only dropped when this is all it says, compared without case or punctuation.
+const KNOWN_HALLUCINATIONS: [&str; 13] = [
+    "thanks for watching",
+    "thank you for watching",
+    "thanks for watching and see you next time",
+    "please subscribe",
+    "please like and subscribe",
+    "subscribe to my channel",
+    "dont forget to like and subscribe",
+    "subtitles by the amaraorg community",
+    "transcription by castingwords",
+    "music",
+    "blank audio",
+    "silence",
+    "you",
+];
+
+/// Why a segment was taken for a hallucination
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hallucination {
+    /// The engine itself thought there was probably no speech
+    NoSpeech,
+
+    /// The text is a phrase commonly made up from silence, or only a sound annotation like "[Music]"
+    KnownPhrase,
+
+    /// The audio under the segment is too quiet to contain speech
+    SilentAudio,
+}
+
+/// Segments dropped as hallucinations, by reason
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct HallucinationStats {
+    /// Dropped for a high no-speech probability
+    pub no_speech: u64,
+
+    /// Dropped for matching a known phrase or annotation
+    pub known_phrase: u64,
+
+    /// Dropped for near-silent audio
+    pub silent_audio: u64,
+}
+
+impl HallucinationStats {
+    /// Count dropped segments
+    pub fn record(&mut self, dropped: &[Hallucination]) {
+        for hallucination in dropped {
+            match hallucination {
+                Hallucination::NoSpeech => self.no_speech += 1,
+                Hallucination::KnownPhrase => self.known_phrase += 1,
+                Hallucination::SilentAudio => self.silent_audio += 1,
+            }
+        }
+    }
+
+    /// All dropped segments
+    pub fn total(&self) -> u64 {
+        self.no_speech + self.known_phrase + self.silent_audio
+    }
+}
+
+/// Drop segments that are likely hallucinations
+///
+/// `audio` is the buffer the segments were transcribed from. Returns the kept
+/// segments and the reason each of the others was dropped.
+pub fn filter_segments(
+    segments: Vec<TranscriptSegment>,
+    audio: &[f32],
+    settings: &HallucinationSettings,
+) -> (Vec<TranscriptSegment>, Vec<Hallucination>) {
+    if !settings.enabled {
+        return (segments, Vec::new());
+    }
+
+    let trimmer = SilenceTrimmer::new(SAMPLE_RATE, &SilenceSettings {
+        enabled: true,
+        threshold_db: settings.min_level_db,
+        padding_ms: 0,
+    });
+
+    let mut dropped = Vec::new();
+    let kept = segments.into_iter()
+        .filter(|segment| match detect(segment, audio, settings, &trimmer) {
+            Some(hallucination) => {
+                dropped.push(hallucination);
+                false
+            }
+            None => true,
+        })
+        .collect();
+
+    (kept, dropped)
+}
+
+/// Why a segment looks hallucinated, if it does
+fn detect(segment: &TranscriptSegment, audio: &[f32], settings: &HallucinationSettings, trimmer: &SilenceTrimmer) -> Option<Hallucination> {
+    if segment.no_speech_probability.is_some_and(|probability| probability >= settings.no_speech_threshold) {
+        return Some(Hallucination::NoSpeech);
+    }
+
+    let text = segment.text.trim();
+    let normalized = normalize(text);
+    let annotation = !text.is_empty()
+        && ((text.starts_with('[') && text.ends_with(']'))
+            || (text.starts_with('(') && text.ends_with(')'))
+            || text.chars().all(|c| c == '♪' || c.is_whitespace() || c.is_ascii_punctuation()));
+    let known = KNOWN_HALLUCINATIONS.contains(&normalized.as_str())
+        || settings.extra_phrases.iter().any(|phrase| normalize(phrase) == normalized);
+    if annotation || known {
+        return Some(Hallucination::KnownPhrase);
+    }
+
+    // Only judge audio the segment actually covers
+    let to_sample = |time: std::time::Duration| (time.as_secs_f64() * SAMPLE_RATE as f64) as usize;
+    let end = to_sample(segment.end).min(audio.len());
+    let start = to_sample(segment.start).min(end);
+    if end > start && matches!(trimmer.trim(&audio[start..end]), Trimmed::Silence { .. }) {
+        return Some(Hallucination::SilentAudio);
+    }
+
+    None
+}
+
+/// Lowercase words without punctuation, for comparing phrases
+fn normalize(text: &str) -> String {
+    text.split(|c: char| c.is_whitespace() || c == '_' || c == '-')
+        .map(|word| word.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect::<String>())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn segment(text: &str, start_ms: u64, end_ms: u64) -> TranscriptSegment {
+        TranscriptSegment {
+            start: Duration::from_millis(start_ms),
+            end: Duration::from_millis(end_ms),
+            text: text.to_string(),
+            confidence: None,
+            language: None,
+            no_speech_probability: None,
+        }
+    }
+
+    fn speech(len: usize) -> Vec<f32> {
+        (0..len).map(|i| (i as f32 * 0.1).sin() * 0.3).collect()
+    }
+
+    #[test]
+    fn test_known_phrases_and_annotations_are_dropped() {
+        let settings = HallucinationSettings::default();
+        let audio = speech(32000);
+        let segments = vec![
+            segment(" Thanks for watching!", 0, 1000),
+            segment(" [BLANK_AUDIO]", 0, 1000),
+            segment(" ♪ ♪", 0, 1000),
+            segment(" Thanks for watching the demo, it starts now.", 0, 2000),
+        ];
+
+        let (kept, dropped) = filter_segments(segments, &audio, &settings);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(dropped, vec![Hallucination::KnownPhrase; 3]);
+    }
+
+    #[test]
+    fn test_silent_and_no_speech_segments_are_dropped() {
+        let settings = HallucinationSettings::default();
+        let mut audio = speech(16000);
+        audio.extend(vec![0.0; 16000]);
+
+        let unsure = TranscriptSegment { no_speech_probability: Some(0.95), ..segment("Hello", 0, 1000) };
+        let segments = vec![segment("Hello", 0, 1000), segment("Goodbye", 1000, 2000), unsure];
+
+        let (kept, dropped) = filter_segments(segments, &audio, &settings);
+        assert_eq!(kept, vec![segment("Hello", 0, 1000)]);
+        assert_eq!(dropped, vec![Hallucination::SilentAudio, Hallucination::NoSpeech]);
+
+        let mut stats = HallucinationStats::default();
+        stats.record(&dropped);
+        assert_eq!(stats.total(), 2);
+        assert_eq!(stats.silent_audio, 1);
+
+        // Nothing is dropped while the filter is off
+        let off = HallucinationSettings { enabled: false, ..HallucinationSettings::default() };
+        assert_eq!(filter_segments(vec![segment("you", 1000, 2000)], &audio, &off).0.len(), 1);
+    }
+}
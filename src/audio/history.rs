@@ -0,0 +1,467 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Local, TimeZone};
+use log::{info, warn};
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::session::{Session, SessionSegment, SessionStore, SessionSummary};
+use super::transcript_file::TranscriptFile;
+
+/// Most results a search returns unless asked for more
+pub const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+/// Tables for sessions and their segments, with a full-text index kept in step with the segments
+const SCHEMA: &str = "
+    PRAGMA foreign_keys = ON;
+    CREATE TABLE IF NOT EXISTS sessions (
+        id TEXT PRIMARY KEY,
+        title TEXT,
+        started_ms INTEGER NOT NULL,
+        ended_ms INTEGER,
+        device TEXT,
+        model TEXT NOT NULL,
+        language TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS segments (
+        id INTEGER PRIMARY KEY,
+        session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        start_ms INTEGER NOT NULL,
+        end_ms INTEGER NOT NULL,
+        speaker TEXT,
+        text TEXT NOT NULL,
+        confidence REAL
+    );
+    CREATE INDEX IF NOT EXISTS segments_by_session ON segments(session_id, position);
+    CREATE VIRTUAL TABLE IF NOT EXISTS segments_fts USING fts5(
+        text, content='segments', content_rowid='id', tokenize='porter unicode61'
+    );
+    CREATE TRIGGER IF NOT EXISTS segments_inserted AFTER INSERT ON segments BEGIN
+        INSERT INTO segments_fts(rowid, text) VALUES (new.id, new.text);
+    END;
+    CREATE TRIGGER IF NOT EXISTS segments_deleted AFTER DELETE ON segments BEGIN
+        INSERT INTO segments_fts(segments_fts, rowid, text) VALUES ('delete', old.id, old.text);
+    END;
+";
+
+/// When something was said, to narrow a search down
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub struct DateRange {
+    /// Earliest time included
+    pub from: Option<DateTime<Local>>,
+
+    /// Time results have to be before
+    pub to: Option<DateTime<Local>>,
+}
+
+/// A segment matching a search
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchHit {
+    pub session_id: String,
+    pub session_title: Option<String>,
+
+    /// When the segment was said
+    pub time: DateTime<Local>,
+
+    /// Start of the segment, in milliseconds from the start of the session's audio
+    pub start_ms: u64,
+
+    pub speaker: Option<String>,
+    pub text: String,
+}
+
+/// Sessions kept in an SQLite database, with their transcripts indexed for full-text search
+///
+/// Clones share one connection.
+#[derive(Clone)]
+pub struct TranscriptHistory {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl TranscriptHistory {
+    /// Open the database at `path`, creating it if needed
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+        }
+
+        let connection = Connection::open(path)
+            .with_context(|| format!("Failed to open transcript history {}", path.display()))?;
+        Self::with_connection(connection)
+    }
+
+    /// Open a database that only lasts as long as the history
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self> {
+        connection.execute_batch(SCHEMA).context("Failed to create transcript history tables")?;
+        Ok(Self { connection: Arc::new(Mutex::new(connection)) })
+    }
+
+    /// Database file in the application data folder
+    pub fn default_path() -> Result<PathBuf> {
+        let project_dirs = directories::ProjectDirs::from("com", "bestme", "BestMe")
+            .context("Failed to determine project directories")?;
+        Ok(project_dirs.data_dir().join("transcripts.db"))
+    }
+
+    /// Save a session, replacing any earlier save of it
+    pub fn save_session(&self, session: &Session) -> Result<()> {
+        let mut connection = self.connection.lock();
+        let transaction = connection.transaction()?;
+
+        transaction.execute(
+            "INSERT INTO sessions (id, title, started_ms, ended_ms, device, model, language)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(id) DO UPDATE SET title = ?2, started_ms = ?3, ended_ms = ?4, device = ?5, model = ?6, language = ?7",
+            params![
+                session.id,
+                session.title,
+                session.started.timestamp_millis(),
+                session.ended.map(|ended| ended.timestamp_millis()),
+                session.device,
+                session.model,
+                session.language,
+            ],
+        )?;
+
+        // Segments are few enough per session to write again in full
+        transaction.execute("DELETE FROM segments WHERE session_id = ?1", params![session.id])?;
+        {
+            let mut insert = transaction.prepare(
+                "INSERT INTO segments (session_id, position, start_ms, end_ms, speaker, text, confidence)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+            for (position, segment) in session.segments.iter().enumerate() {
+                insert.execute(params![
+                    session.id,
+                    position as i64,
+                    segment.start_ms as i64,
+                    segment.end_ms as i64,
+                    segment.speaker,
+                    segment.text,
+                    segment.confidence.map(f64::from),
+                ])?;
+            }
+        }
+
+        transaction.commit().with_context(|| format!("Failed to save session {}", session.id))
+    }
+
+    /// Load a saved session with its transcript
+    pub fn load_session(&self, id: &str) -> Result<Session> {
+        let connection = self.connection.lock();
+
+        let (title, started_ms, ended_ms, device, model, language) = connection.query_row(
+            "SELECT title, started_ms, ended_ms, device, model, language FROM sessions WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, Option<i64>>(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
+        ).optional()?.ok_or_else(|| anyhow!("No session {}", id))?;
+
+        let mut statement = connection.prepare(
+            "SELECT start_ms, end_ms, speaker, text, confidence FROM segments WHERE session_id = ?1 ORDER BY position",
+        )?;
+        let segments = statement.query_map(params![id], |row| {
+            Ok(SessionSegment {
+                start_ms: row.get::<_, i64>(0)? as u64,
+                end_ms: row.get::<_, i64>(1)? as u64,
+                speaker: row.get(2)?,
+                text: row.get(3)?,
+                confidence: row.get::<_, Option<f64>>(4)?.map(|confidence| confidence as f32),
+            })
+        })?.collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(Session {
+            id: id.to_string(),
+            title,
+            started: local_time(started_ms),
+            ended: ended_ms.map(local_time),
+            device,
+            model,
+            language,
+            segments,
+        })
+    }
+
+    /// Saved sessions without their transcripts, newest first
+    pub fn list_sessions(&self) -> Result<Vec<SessionSummary>> {
+        let connection = self.connection.lock();
+        let mut statement = connection.prepare(
+            "SELECT s.id, s.title, s.started_ms, s.ended_ms, s.device, s.model, s.language, COUNT(g.id)
+             FROM sessions s LEFT JOIN segments g ON g.session_id = s.id
+             GROUP BY s.id ORDER BY s.started_ms DESC",
+        )?;
+
+        let sessions = statement.query_map([], |row| {
+            Ok(SessionSummary {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                started: local_time(row.get(2)?),
+                ended: row.get::<_, Option<i64>>(3)?.map(local_time),
+                device: row.get(4)?,
+                model: row.get(5)?,
+                language: row.get(6)?,
+                segments: row.get::<_, i64>(7)? as usize,
+            })
+        })?.collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(sessions)
+    }
+
+    /// Give a saved session a title, or remove it with None
+    pub fn rename_session(&self, id: &str, title: Option<String>) -> Result<Session> {
+        let title = title.filter(|title| !title.trim().is_empty());
+        let updated = self.connection.lock().execute("UPDATE sessions SET title = ?2 WHERE id = ?1", params![id, title])?;
+        if updated == 0 {
+            return Err(anyhow!("No session {}", id));
+        }
+
+        self.load_session(id)
+    }
+
+    /// Delete a saved session with its transcript
+    pub fn delete_session(&self, id: &str) -> Result<()> {
+        let deleted = self.connection.lock().execute("DELETE FROM sessions WHERE id = ?1", params![id])?;
+        if deleted == 0 {
+            return Err(anyhow!("No session {}", id));
+        }
+        Ok(())
+    }
+
+    /// Find segments containing all words of `query`, best matches first
+    ///
+    /// Words match regardless of case and ending, so "invoice" finds
+    /// "Invoices". Only segments said within `range` are returned.
+    pub fn search(&self, query: &str, range: &DateRange, limit: usize) -> Result<Vec<SearchHit>> {
+        let expression = match match_expression(query) {
+            Some(expression) => expression,
+            None => return Ok(Vec::new()),
+        };
+
+        let connection = self.connection.lock();
+        let mut statement = connection.prepare(
+            "SELECT s.id, s.title, s.started_ms + g.start_ms, g.start_ms, g.speaker, g.text
+             FROM segments_fts
+             JOIN segments g ON g.id = segments_fts.rowid
+             JOIN sessions s ON s.id = g.session_id
+             WHERE segments_fts MATCH ?1
+               AND (?2 IS NULL OR s.started_ms + g.start_ms >= ?2)
+               AND (?3 IS NULL OR s.started_ms + g.start_ms < ?3)
+             ORDER BY rank
+             LIMIT ?4",
+        )?;
+
+        let hits = statement.query_map(
+            params![
+                expression,
+                range.from.map(|from| from.timestamp_millis()),
+                range.to.map(|to| to.timestamp_millis()),
+                limit as i64,
+            ],
+            |row| {
+                Ok(SearchHit {
+                    session_id: row.get(0)?,
+                    session_title: row.get(1)?,
+                    time: local_time(row.get(2)?),
+                    start_ms: row.get::<_, i64>(3)? as u64,
+                    speaker: row.get(4)?,
+                    text: row.get(5)?,
+                })
+            },
+        )?.collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(hits)
+    }
+
+    /// Whether a session has been saved
+    pub fn contains(&self, id: &str) -> Result<bool> {
+        let found = self.connection.lock()
+            .query_row("SELECT 1 FROM sessions WHERE id = ?1", params![id], |_| Ok(()))
+            .optional()?;
+        Ok(found.is_some())
+    }
+
+    /// Copy sessions saved as files into the history, skipping ones it already has
+    ///
+    /// Returns the number of sessions copied.
+    pub fn import(&self, store: &SessionStore) -> Result<usize> {
+        let mut imported = 0;
+        for summary in store.list()? {
+            if self.contains(&summary.id)? {
+                continue;
+            }
+            self.save_session(&store.load(&summary.id)?)?;
+            imported += 1;
+        }
+
+        if imported > 0 {
+            info!("Imported {} session(s) into the transcript history", imported);
+        }
+        Ok(imported)
+    }
+
+    /// Copy JSON transcript files of any version in `dir` into the history, skipping ones it already has
+    ///
+    /// Files that can't be read are skipped. Returns the number of transcripts copied.
+    pub fn import_transcripts(&self, dir: &Path) -> Result<usize> {
+        if !dir.exists() {
+            return Ok(0);
+        }
+
+        let mut imported = 0;
+        for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read transcripts directory {}", dir.display()))? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+
+            let session = match TranscriptFile::read(&path) {
+                Ok(transcript) => transcript.into_session(),
+                Err(e) => {
+                    warn!("Skipping transcript: {:#}", e);
+                    continue;
+                }
+            };
+            if !self.contains(&session.id)? {
+                self.save_session(&session)?;
+                imported += 1;
+            }
+        }
+
+        if imported > 0 {
+            info!("Imported {} transcript file(s) into the transcript history", imported);
+        }
+        Ok(imported)
+    }
+}
+
+/// FTS5 query matching text with all the words typed, in any order
+///
+/// Each word is quoted, so punctuation and FTS5 operators in it are taken
+/// literally. Returns None when there are no words to look for.
+fn match_expression(query: &str) -> Option<String> {
+    let terms: Vec<String> = query.split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+fn local_time(timestamp_ms: i64) -> DateTime<Local> {
+    Local.timestamp_millis_opt(timestamp_ms).single().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::transcript_file::TranscriptFileSegment;
+    use crate::config::Config;
+    use std::time::Duration;
+
+    fn session(id: &str, started_ms: i64, texts: &[&str]) -> Session {
+        let mut session = Session::new(&Config::default().audio.speech, None);
+        session.id = id.to_string();
+        session.started = local_time(started_ms);
+        for (i, text) in texts.iter().enumerate() {
+            let start = Duration::from_secs(i as u64 * 5);
+            session.push(start, start + Duration::from_secs(4), Some("Me".to_string()), text.to_string(), Some(0.75));
+        }
+        session
+    }
+
+    #[test]
+    fn test_match_expression() {
+        assert_eq!(match_expression("  "), None);
+        assert_eq!(match_expression("- ?"), None);
+        assert_eq!(match_expression("invoices"), Some("\"invoices\"".to_string()));
+        assert_eq!(match_expression("don't say \"NEAR\""), Some("\"don't\" \"say\" \"\"\"NEAR\"\"\"".to_string()));
+    }
+
+    #[test]
+    fn test_sessions_round_trip() {
+        let history = TranscriptHistory::open_in_memory().unwrap();
+        let mut first = session("first", 1_700_000_000_000, &["Hello there.", "\n\nNew topic."]);
+        first.device = Some("Microphone".to_string());
+        history.save_session(&first).unwrap();
+        assert_eq!(history.load_session("first").unwrap(), first);
+
+        // Saving again replaces the transcript rather than adding to it
+        first.push(Duration::from_secs(20), Duration::from_secs(21), None, "Bye.".to_string(), None);
+        first.ended = Some(local_time(1_700_000_060_000));
+        history.save_session(&first).unwrap();
+        history.save_session(&session("second", 1_700_000_500_000, &[])).unwrap();
+        assert_eq!(history.load_session("first").unwrap(), first);
+
+        let listed: Vec<(String, usize)> = history.list_sessions().unwrap().into_iter().map(|s| (s.id, s.segments)).collect();
+        assert_eq!(listed, [("second".to_string(), 0), ("first".to_string(), 3)]);
+
+        assert_eq!(history.rename_session("first", Some("Standup".to_string())).unwrap().title.as_deref(), Some("Standup"));
+        assert!(history.rename_session("missing", None).is_err());
+
+        history.delete_session("first").unwrap();
+        assert!(history.load_session("first").is_err());
+        assert!(history.delete_session("first").is_err());
+    }
+
+    #[test]
+    fn test_search() {
+        let history = TranscriptHistory::open_in_memory().unwrap();
+        let monday = 1_700_000_000_000;
+        let tuesday = monday + 86_400_000;
+        history.save_session(&session("monday", monday, &["We sent the invoice.", "Lunch was good."])).unwrap();
+        history.save_session(&session("tuesday", tuesday, &["Me: The invoices are late, don't worry."])).unwrap();
+
+        let ids = |hits: Vec<SearchHit>| hits.into_iter().map(|hit| hit.session_id).collect::<Vec<_>>();
+        let mut all = ids(history.search("INVOICE", &DateRange::default(), 10).unwrap());
+        all.sort();
+        assert_eq!(all, ["monday", "tuesday"]);
+        assert_eq!(ids(history.search("invoices late", &DateRange::default(), 10).unwrap()), ["tuesday"]);
+        assert_eq!(ids(history.search("don't", &DateRange::default(), 10).unwrap()), ["tuesday"]);
+        assert!(history.search("", &DateRange::default(), 10).unwrap().is_empty());
+
+        let range = DateRange { from: Some(local_time(tuesday)), to: Some(local_time(tuesday + 86_400_000)) };
+        let hits = history.search("invoice", &range, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].time, local_time(tuesday));
+        assert_eq!(hits[0].speaker.as_deref(), Some("Me"));
+
+        // Deleted sessions drop out of the index
+        history.delete_session("tuesday").unwrap();
+        assert_eq!(ids(history.search("invoice", &DateRange::default(), 10).unwrap()), ["monday"]);
+    }
+    #[test]
+    fn test_import_transcripts() {
+        let dir = std::env::temp_dir().join(format!("bestme-transcripts-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("transcription_20240301_101530.json"), r#"{
+            "timestamp": "2024-03-01T10:15:30.123+00:00", "text": "From an old version.",
+            "model": "small", "language": "auto", "detected_language": null
+        }"#).unwrap();
+        let mut transcript = TranscriptFile::new(&Config::default().audio.speech);
+        transcript.push(TranscriptFileSegment {
+            start_ms: 0,
+            end_ms: 1000,
+            text: "From this version.".to_string(),
+            confidence: None,
+            language: None,
+            speaker: None,
+        });
+        std::fs::write(dir.join("transcription_new.json"), serde_json::to_string(&transcript).unwrap()).unwrap();
+        std::fs::write(dir.join("broken.json"), "{").unwrap();
+
+        let history = TranscriptHistory::open_in_memory().unwrap();
+        assert_eq!(history.import_transcripts(&dir).unwrap(), 2);
+        assert_eq!(history.import_transcripts(&dir).unwrap(), 0);
+        let hits = history.search("version", &DateRange::default(), 10).unwrap();
+        assert_eq!(hits.len(), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(history.import_transcripts(&dir).unwrap(), 0);
+    }
+}
@@ -0,0 +1,321 @@
+use anyhow::Result;
+use log::{debug, error, info, warn};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc as std_mpsc;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use super::device::DeviceManager;
+
+/// Default interval between device re-enumerations when no OS notifications are available
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Device change reported by the watcher
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceEvent {
+    /// A capture source became available
+    DeviceAdded { id: String, name: String },
+    /// A capture source disappeared
+    DeviceRemoved { id: String, name: String },
+    /// The system default input changed
+    DefaultChanged { id: String, name: String },
+}
+
+/// Compare two device snapshots and report what changed
+pub fn diff_devices(previous: &HashMap<String, String>, current: &HashMap<String, String>) -> Vec<DeviceEvent> {
+    let mut events: Vec<DeviceEvent> = previous.iter()
+        .filter(|(id, _)| !current.contains_key(*id))
+        .map(|(id, name)| DeviceEvent::DeviceRemoved { id: id.clone(), name: name.clone() })
+        .collect();
+
+    events.extend(
+        current.iter()
+            .filter(|(id, _)| !previous.contains_key(*id))
+            .map(|(id, name)| DeviceEvent::DeviceAdded { id: id.clone(), name: name.clone() }),
+    );
+
+    // Keep the order stable so consumers see removals before additions, sorted by ID
+    events.sort_by(|a, b| {
+        let key = |event: &DeviceEvent| match event {
+            DeviceEvent::DeviceRemoved { id, .. } => (0, id.clone()),
+            DeviceEvent::DeviceAdded { id, .. } => (1, id.clone()),
+            DeviceEvent::DefaultChanged { id, .. } => (2, id.clone()),
+        };
+        key(a).cmp(&key(b))
+    });
+
+    events
+}
+
+/// Background watcher that keeps a `DeviceManager` in sync with the system
+///
+/// On Windows, WASAPI endpoint notifications trigger an immediate refresh; on
+/// Linux, PulseAudio/PipeWire events do. Elsewhere (or if registration fails)
+/// devices are re-enumerated periodically.
+pub struct DeviceWatcher {
+    /// Whether the watcher thread should keep running
+    running: Arc<AtomicBool>,
+
+    /// Wakes the watcher thread (used for notifications and shutdown)
+    wake: std_mpsc::Sender<()>,
+
+    /// Watcher thread handle
+    thread: Option<JoinHandle<()>>,
+}
+
+impl DeviceWatcher {
+    /// Start watching for device changes
+    ///
+    /// The shared device manager is replaced with a fresh enumeration whenever
+    /// devices change, and the changes are sent on the returned channel.
+    pub fn start(
+        device_manager: Arc<Mutex<DeviceManager>>,
+        poll_interval: Duration,
+    ) -> Result<(Self, mpsc::Receiver<DeviceEvent>)> {
+        let (event_sender, event_receiver) = mpsc::channel(32);
+        let (wake_sender, wake_receiver) = std_mpsc::channel();
+        let running = Arc::new(AtomicBool::new(true));
+
+        let thread_running = running.clone();
+        let notifier_wake = wake_sender.clone();
+
+        let thread = std::thread::Builder::new()
+            .name("device-watcher".to_string())
+            .spawn(move || {
+                // OS notifications must be registered on the thread that waits for them
+                #[cfg(target_os = "windows")]
+                let notifier = match windows_notify::EndpointNotifier::register(notifier_wake) {
+                    Ok(notifier) => Some(notifier),
+                    Err(e) => {
+                        warn!("Device notifications unavailable, polling instead: {}", e);
+                        None
+                    }
+                };
+
+                #[cfg(target_os = "linux")]
+                let notifier = match super::pulse::Subscription::start(notifier_wake) {
+                    Ok(subscription) => Some(subscription),
+                    Err(e) => {
+                        warn!("Sound server events unavailable, polling instead: {}", e);
+                        None
+                    }
+                };
+
+                #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+                let notifier: Option<()> = {
+                    drop(notifier_wake);
+                    None
+                };
+
+                info!("Device watcher started ({})", if notifier.is_some() { "notifications" } else { "polling" });
+
+                let (mut known, mut known_default) = {
+                    let manager = device_manager.lock();
+                    let sources: HashMap<String, String> = manager.get_capture_sources().into_iter().collect();
+                    (sources, manager.get_default_input_device())
+                };
+
+                while thread_running.load(Ordering::SeqCst) {
+                    let woken = if notifier.is_some() {
+                        wake_receiver.recv().map_err(|_| std_mpsc::RecvTimeoutError::Disconnected)
+                    } else {
+                        wake_receiver.recv_timeout(poll_interval)
+                    };
+
+                    match woken {
+                        Ok(()) | Err(std_mpsc::RecvTimeoutError::Timeout) => {},
+                        Err(std_mpsc::RecvTimeoutError::Disconnected) => break,
+                    }
+
+                    if !thread_running.load(Ordering::SeqCst) {
+                        break;
+                    }
+
+                    // Coalesce bursts of notifications (a plug event fires several)
+                    std::thread::sleep(Duration::from_millis(100));
+                    while wake_receiver.try_recv().is_ok() {}
+
+                    let fresh = match DeviceManager::new() {
+                        Ok(manager) => manager,
+                        Err(e) => {
+                            warn!("Failed to re-enumerate audio devices: {}", e);
+                            continue;
+                        }
+                    };
+
+                    let current: HashMap<String, String> = fresh.get_capture_sources().into_iter().collect();
+                    let current_default = fresh.get_default_input_device();
+
+                    let mut events = diff_devices(&known, &current);
+                    if current_default != known_default {
+                        if let Some((id, name)) = current_default.clone() {
+                            events.push(DeviceEvent::DefaultChanged { id, name });
+                        }
+                    }
+                    if events.is_empty() {
+                        continue;
+                    }
+
+                    debug!("Detected {} device changes", events.len());
+                    *device_manager.lock() = fresh;
+                    known = current;
+                    known_default = current_default;
+
+                    for event in events {
+                        if event_sender.blocking_send(event).is_err() {
+                            // Nobody is listening any more
+                            thread_running.store(false, Ordering::SeqCst);
+                            break;
+                        }
+                    }
+                }
+
+                info!("Device watcher stopped");
+            })?;
+
+        Ok((
+            Self {
+                running,
+                wake: wake_sender,
+                thread: Some(thread),
+            },
+            event_receiver,
+        ))
+    }
+
+    /// Stop the watcher and wait for its thread to exit
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        let _ = self.wake.send(());
+
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                error!("Device watcher thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for DeviceWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows_notify {
+    use std::sync::mpsc;
+
+    use windows::core::{implement, Result, PCWSTR};
+    use windows::Win32::Media::Audio::{
+        EDataFlow, ERole, IMMDeviceEnumerator, IMMNotificationClient, IMMNotificationClient_Impl,
+        MMDeviceEnumerator, DEVICE_STATE,
+    };
+    use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_MULTITHREADED};
+    use windows::Win32::UI::Shell::PropertiesSystem::PROPERTYKEY;
+
+    /// COM callback that wakes the watcher thread on endpoint changes
+    #[implement(IMMNotificationClient)]
+    struct NotificationClient {
+        wake: mpsc::Sender<()>,
+    }
+
+    impl NotificationClient {
+        fn notify(&self) -> Result<()> {
+            let _ = self.wake.send(());
+            Ok(())
+        }
+    }
+
+    #[allow(non_snake_case)]
+    impl IMMNotificationClient_Impl for NotificationClient {
+        fn OnDeviceStateChanged(&self, _device_id: &PCWSTR, _new_state: DEVICE_STATE) -> Result<()> {
+            self.notify()
+        }
+
+        fn OnDeviceAdded(&self, _device_id: &PCWSTR) -> Result<()> {
+            self.notify()
+        }
+
+        fn OnDeviceRemoved(&self, _device_id: &PCWSTR) -> Result<()> {
+            self.notify()
+        }
+
+        fn OnDefaultDeviceChanged(&self, _flow: EDataFlow, _role: ERole, _device_id: &PCWSTR) -> Result<()> {
+            self.notify()
+        }
+
+        fn OnPropertyValueChanged(&self, _device_id: &PCWSTR, _key: &PROPERTYKEY) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Registration of a notification client with the WASAPI device enumerator
+    pub struct EndpointNotifier {
+        enumerator: IMMDeviceEnumerator,
+        client: IMMNotificationClient,
+    }
+
+    impl EndpointNotifier {
+        /// Register for endpoint notifications on the current thread
+        pub fn register(wake: mpsc::Sender<()>) -> anyhow::Result<Self> {
+            unsafe {
+                CoInitializeEx(None, COINIT_MULTITHREADED)
+                    .map_err(|e| anyhow::anyhow!("Failed to initialize COM: {}", e))?;
+
+                let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
+                    .map_err(|e| anyhow::anyhow!("Failed to create device enumerator: {}", e))?;
+
+                let client: IMMNotificationClient = NotificationClient { wake }.into();
+                enumerator.RegisterEndpointNotificationCallback(&client)
+                    .map_err(|e| anyhow::anyhow!("Failed to register device notifications: {}", e))?;
+
+                Ok(Self { enumerator, client })
+            }
+        }
+    }
+
+    impl Drop for EndpointNotifier {
+        fn drop(&mut self) {
+            unsafe {
+                let _ = self.enumerator.UnregisterEndpointNotificationCallback(&self.client);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(devices: &[(&str, &str)]) -> HashMap<String, String> {
+        devices.iter()
+            .map(|(id, name)| (id.to_string(), name.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_reports_added_and_removed() {
+        let previous = snapshot(&[("mic", "Microphone"), ("headset", "Headset")]);
+        let current = snapshot(&[("mic", "Microphone"), ("usb", "USB Mic")]);
+
+        assert_eq!(
+            diff_devices(&previous, &current),
+            vec![
+                DeviceEvent::DeviceRemoved { id: "headset".to_string(), name: "Headset".to_string() },
+                DeviceEvent::DeviceAdded { id: "usb".to_string(), name: "USB Mic".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_unchanged_is_empty() {
+        let devices = snapshot(&[("mic", "Microphone")]);
+
+        assert!(diff_devices(&devices, &devices).is_empty());
+    }
+}
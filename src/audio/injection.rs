@@ -0,0 +1,351 @@
+use anyhow::{anyhow, Result};
+
+use super::active_window::ActiveWindow;
+
+/// Most chunks of typed text kept for undoing
+const MAX_CHUNKS: usize = 50;
+
+/// Where dictated text goes when it is typed into other applications
+pub trait OutputSink: Send {
+    /// Type `text` into the application that has the focus, as it is written
+    fn type_text(&self, text: &str) -> Result<()>;
+
+    /// Erase the `count` characters before the cursor
+    fn erase(&self, count: usize) -> Result<()>;
+}
+
+/// Output for the platform the application runs on
+pub fn platform_sink() -> Box<dyn OutputSink> {
+    Box::new(PlatformSink)
+}
+
+/// Types with the platform's own means
+///
+/// Windows sends Unicode input events, macOS posts keyboard events (which needs
+/// the accessibility permission) and Linux uses `wtype` in Wayland sessions, which
+/// types through the virtual keyboard protocol, and `xdotool` otherwise.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlatformSink;
+
+impl OutputSink for PlatformSink {
+    fn type_text(&self, text: &str) -> Result<()> {
+        #[cfg(target_os = "windows")]
+        {
+            windows_output::type_text(text)
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            mac_output::type_text(text)
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            if is_wayland() {
+                run("wtype", &["--", text])
+            } else {
+                run("xdotool", &["type", "--clearmodifiers", "--", text])
+            }
+        }
+
+        #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+        {
+            anyhow::bail!("Typing {:?} isn't supported on this platform", text)
+        }
+    }
+
+    fn erase(&self, count: usize) -> Result<()> {
+        if count == 0 {
+            return Ok(());
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            windows_output::erase(count)
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            mac_output::erase(count)
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            if is_wayland() {
+                let args: Vec<&str> = std::iter::repeat(["-k", "BackSpace"]).take(count).flatten().collect();
+                run("wtype", &args)
+            } else {
+                run("xdotool", &["key", "--clearmodifiers", "--repeat", &count.to_string(), "BackSpace"])
+            }
+        }
+
+        #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+        {
+            anyhow::bail!("Erasing typed text isn't supported on this platform")
+        }
+    }
+}
+
+/// Whether the session is a Wayland one, where `xdotool` can't type into other applications
+#[cfg(target_os = "linux")]
+fn is_wayland() -> bool {
+    std::env::var_os("WAYLAND_DISPLAY").is_some_and(|display| !display.is_empty())
+}
+
+/// Run one of the typing tools, saying which to install when it is missing
+#[cfg(target_os = "linux")]
+fn run(program: &str, args: &[&str]) -> Result<()> {
+    let status = std::process::Command::new(program)
+        .args(args)
+        .status()
+        .map_err(|e| anyhow!("Failed to run {}; install it to type text by voice: {}", program, e))?;
+    if !status.success() {
+        anyhow::bail!("{} failed to type text", program);
+    }
+    Ok(())
+}
+
+/// A piece of dictated text typed into another application
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InjectedChunk {
+    /// The characters typed, with the space setting it off from the chunk before
+    pub text: String,
+
+    /// Application it was typed into, if it was known
+    pub app: Option<String>,
+}
+
+impl InjectedChunk {
+    /// Backspaces taking the chunk out again, one for each character and line break
+    pub fn backspaces(&self) -> usize {
+        self.text.chars().filter(|c| *c != '\r').count()
+    }
+
+    /// Whether the chunk can be changed while `window` has the focus
+    fn check_focus(&self, window: Option<&ActiveWindow>) -> Result<()> {
+        match (&self.app, window) {
+            (Some(app), Some(window)) if !window.is_app(app) => {
+                Err(anyhow!("The text was typed into {}, which no longer has the focus", app))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The text typed into other applications, so it can be taken out and typed again
+///
+/// Applications don't say what their text is, so undoing erases the last chunk
+/// typed with backspaces and redoing types it again; each works only while the
+/// application the chunk was typed into has the focus.
+#[derive(Debug, Clone, Default)]
+pub struct InjectedText {
+    /// Chunks typed, oldest first
+    typed: Vec<InjectedChunk>,
+
+    /// Chunks taken out, the last one first to be typed again
+    undone: Vec<InjectedChunk>,
+}
+
+impl InjectedText {
+    /// The chunk to type for `text` in `window`, set off from the chunk before by a space
+    ///
+    /// No space is added at the start, after whitespace, or before whitespace or punctuation.
+    pub fn chunk(&self, text: &str, window: Option<&ActiveWindow>) -> InjectedChunk {
+        let after_text = self.typed.last().is_some_and(|last| !last.text.ends_with(char::is_whitespace));
+        let joins = text.starts_with(|c: char| c.is_whitespace() || matches!(c, ',' | '.' | ';' | ':' | '!' | '?'));
+        let text = if after_text && !joins { format!(" {}", text) } else { text.to_string() };
+        InjectedChunk { text, app: window.map(|window| window.app.clone()) }
+    }
+
+    /// Keep `chunk` as typed, so it is the next to undo
+    pub fn record(&mut self, chunk: InjectedChunk) {
+        self.typed.push(chunk);
+        if self.typed.len() > MAX_CHUNKS {
+            self.typed.remove(0);
+        }
+        self.undone.clear();
+    }
+
+    /// Whether there is a chunk to undo
+    pub fn can_undo(&self) -> bool {
+        !self.typed.is_empty()
+    }
+
+    /// Whether there is a chunk to type again
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Take the last chunk typed, for erasing it while `window` has the focus
+    pub fn undo(&mut self, window: Option<&ActiveWindow>) -> Result<InjectedChunk> {
+        if let Some(chunk) = self.typed.last() {
+            chunk.check_focus(window)?;
+        }
+        let chunk = self.typed.pop().ok_or_else(|| anyhow!("Nothing was typed to undo"))?;
+        self.undone.push(chunk.clone());
+        Ok(chunk)
+    }
+
+    /// Take the last chunk undone, for typing it again while `window` has the focus
+    pub fn redo(&mut self, window: Option<&ActiveWindow>) -> Result<InjectedChunk> {
+        if let Some(chunk) = self.undone.last() {
+            chunk.check_focus(window)?;
+        }
+        let chunk = self.undone.pop().ok_or_else(|| anyhow!("Nothing was undone to type again"))?;
+        self.typed.push(chunk.clone());
+        Ok(chunk)
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows_output {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP, KEYEVENTF_UNICODE,
+        VIRTUAL_KEY, VK_BACK, VK_RETURN,
+    };
+
+    fn key_input(key: VIRTUAL_KEY, scan: u16, flags: KEYBD_EVENT_FLAGS) -> INPUT {
+        INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: key,
+                    wScan: scan,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: 0,
+                },
+            },
+        }
+    }
+
+    /// Press and release `key`
+    fn press(inputs: &mut Vec<INPUT>, key: VIRTUAL_KEY) {
+        inputs.push(key_input(key, 0, KEYBD_EVENT_FLAGS(0)));
+        inputs.push(key_input(key, 0, KEYEVENTF_KEYUP));
+    }
+
+    fn send(inputs: &[INPUT]) -> anyhow::Result<()> {
+        let sent = unsafe { SendInput(inputs, std::mem::size_of::<INPUT>() as i32) };
+        if sent as usize != inputs.len() {
+            anyhow::bail!("Failed to type text; the application with the focus may not take input from BestMe");
+        }
+        Ok(())
+    }
+
+    /// Send each UTF-16 unit of `text` as a character of its own, and line breaks as enter
+    pub fn type_text(text: &str) -> anyhow::Result<()> {
+        let mut inputs = Vec::new();
+        for c in text.chars().filter(|c| *c != '\r') {
+            if c == '\n' {
+                press(&mut inputs, VK_RETURN);
+                continue;
+            }
+            let mut units = [0u16; 2];
+            for unit in c.encode_utf16(&mut units) {
+                inputs.push(key_input(VIRTUAL_KEY(0), *unit, KEYEVENTF_UNICODE));
+                inputs.push(key_input(VIRTUAL_KEY(0), *unit, KEYEVENTF_UNICODE | KEYEVENTF_KEYUP));
+            }
+        }
+        send(&inputs)
+    }
+
+    pub fn erase(count: usize) -> anyhow::Result<()> {
+        let mut inputs = Vec::with_capacity(count * 2);
+        for _ in 0..count {
+            press(&mut inputs, VK_BACK);
+        }
+        send(&inputs)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod mac_output {
+    use core_graphics::event::{CGEvent, CGEventTapLocation, CGKeyCode};
+    use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+
+    /// Key codes of return and delete, the key erasing backwards
+    const RETURN: CGKeyCode = 36;
+    const DELETE: CGKeyCode = 51;
+
+    /// Keyboard events carry at most this many UTF-16 units of text
+    const MAX_UNITS: usize = 20;
+
+    /// Post a key press and release, typing `text` instead of the key's own character when given
+    fn post(key: CGKeyCode, text: Option<&str>) -> anyhow::Result<()> {
+        for down in [true, false] {
+            let source = CGEventSource::new(CGEventSourceStateID::HIDSystemState)
+                .map_err(|_| anyhow::anyhow!("Failed to create a keyboard event source"))?;
+            let event = CGEvent::new_keyboard_event(source, key, down)
+                .map_err(|_| anyhow::anyhow!("Failed to create a keyboard event"))?;
+            if let Some(text) = text {
+                event.set_string(text);
+            }
+            event.post(CGEventTapLocation::HID);
+        }
+        Ok(())
+    }
+
+    /// Type `text` a few characters per event, and line breaks as return
+    pub fn type_text(text: &str) -> anyhow::Result<()> {
+        for (index, line) in text.split('\n').enumerate() {
+            if index > 0 {
+                post(RETURN, None)?;
+            }
+            let mut piece = String::new();
+            for c in line.chars().filter(|c| *c != '\r') {
+                if piece.encode_utf16().count() + c.len_utf16() > MAX_UNITS {
+                    post(0, Some(&piece))?;
+                    piece.clear();
+                }
+                piece.push(c);
+            }
+            if !piece.is_empty() {
+                post(0, Some(&piece))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn erase(count: usize) -> anyhow::Result<()> {
+        for _ in 0..count {
+            post(DELETE, None)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undoes_and_redoes_typed_chunks() {
+        let editor = ActiveWindow { app: "Code.exe".to_string(), title: "notes.md".to_string() };
+        let browser = ActiveWindow { app: "firefox".to_string(), title: "Inbox".to_string() };
+        let mut injected = InjectedText::default();
+
+        let first = injected.chunk("Hello", Some(&editor));
+        assert_eq!(first.text, "Hello");
+        injected.record(first);
+        let second = injected.chunk("wörld", Some(&editor));
+        assert_eq!((second.text.as_str(), second.backspaces()), (" wörld", 6));
+        injected.record(second.clone());
+        assert_eq!(injected.chunk(", then", None).text, ", then");
+
+        // Only undone while the application typed into has the focus
+        assert!(injected.undo(Some(&browser)).is_err());
+        assert_eq!(injected.undo(Some(&editor)).unwrap(), second);
+        assert!(injected.can_redo());
+        assert_eq!(injected.redo(None).unwrap(), second);
+
+        // Typing something new drops what could be typed again
+        injected.undo(None).unwrap();
+        injected.record(InjectedChunk { text: "!".to_string(), app: None });
+        assert!(!injected.can_redo());
+        injected.undo(None).unwrap();
+        injected.undo(None).unwrap();
+        assert!(!injected.can_undo());
+        assert!(injected.undo(None).is_err());
+    }
+}
@@ -0,0 +1,353 @@
+use anyhow::{anyhow, Result};
+use std::fmt;
+
+/// A key held while another is pressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Modifier {
+    Ctrl,
+    Alt,
+    Shift,
+
+    /// The Windows key, or Command on macOS
+    Meta,
+}
+
+/// A key that is pressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// A letter, digit or punctuation key, by the character it types without shift
+    Char(char),
+    Enter,
+    Tab,
+    Escape,
+    Backspace,
+    Delete,
+    Space,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+
+    /// A function key, F1 to F12
+    Function(u8),
+}
+
+/// Punctuation keys that can be pressed, with their X11 key names
+const PUNCTUATION: [(char, &str); 11] = [
+    ('-', "minus"), ('=', "equal"), (',', "comma"), ('.', "period"), ('/', "slash"), (';', "semicolon"),
+    ('\'', "apostrophe"), ('[', "bracketleft"), (']', "bracketright"), ('\\', "backslash"), ('`', "grave"),
+];
+
+/// Keys pressed together, e.g. "ctrl+shift+s"
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    /// Keys held down, in the order written
+    pub modifiers: Vec<Modifier>,
+
+    /// The key pressed while they are held
+    pub key: Key,
+}
+
+impl KeyChord {
+    /// Parse keys joined by `+`, modifiers first, without regard to letter case
+    ///
+    /// Modifiers are ctrl, alt, shift and meta (also cmd, win or super); the key
+    /// is a letter, digit, punctuation key, a named key such as enter, tab, esc,
+    /// backspace, delete, space, the arrows, home, end, pageup and pagedown, or
+    /// F1 to F12.
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim().to_lowercase();
+        let (names, key_name) = text.rsplit_once('+').unwrap_or(("", text.as_str()));
+        if key_name.trim().is_empty() {
+            return Err(anyhow!("no key to press in {:?}", text));
+        }
+
+        let mut modifiers = Vec::new();
+        for name in names.split('+').map(str::trim).filter(|name| !name.is_empty()) {
+            let modifier = match name {
+                "ctrl" | "control" => Modifier::Ctrl,
+                "alt" | "option" => Modifier::Alt,
+                "shift" => Modifier::Shift,
+                "meta" | "cmd" | "command" | "win" | "super" => Modifier::Meta,
+                _ => return Err(anyhow!("{} isn't a modifier key; only the last key of {:?} can be another", name, text)),
+            };
+            if !modifiers.contains(&modifier) {
+                modifiers.push(modifier);
+            }
+        }
+
+        let key = parse_key(key_name.trim()).ok_or_else(|| anyhow!("unknown key {} in {:?}", key_name.trim(), text))?;
+        Ok(Self { modifiers, key })
+    }
+}
+
+fn parse_key(name: &str) -> Option<Key> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        let typeable = c.is_ascii_alphanumeric() || PUNCTUATION.iter().any(|(punctuation, _)| *punctuation == c);
+        return typeable.then_some(Key::Char(c));
+    }
+
+    let key = match name {
+        "enter" | "return" => Key::Enter,
+        "tab" => Key::Tab,
+        "esc" | "escape" => Key::Escape,
+        "backspace" => Key::Backspace,
+        "delete" | "del" => Key::Delete,
+        "space" => Key::Space,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" | "pgup" => Key::PageUp,
+        "pagedown" | "pgdn" => Key::PageDown,
+        _ => {
+            let number: u8 = name.strip_prefix('f')?.parse().ok()?;
+            return (1..=12).contains(&number).then_some(Key::Function(number));
+        }
+    };
+    Some(key)
+}
+
+impl fmt::Display for KeyChord {
+    /// The chord as `xdotool` takes it, e.g. "ctrl+shift+s"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for modifier in &self.modifiers {
+            let name = match modifier {
+                Modifier::Ctrl => "ctrl",
+                Modifier::Alt => "alt",
+                Modifier::Shift => "shift",
+                Modifier::Meta => "super",
+            };
+            write!(f, "{}+", name)?;
+        }
+
+        match self.key {
+            Key::Char(c) => match PUNCTUATION.iter().find(|(punctuation, _)| *punctuation == c) {
+                Some((_, name)) => write!(f, "{}", name),
+                None => write!(f, "{}", c),
+            },
+            Key::Enter => write!(f, "Return"),
+            Key::Tab => write!(f, "Tab"),
+            Key::Escape => write!(f, "Escape"),
+            Key::Backspace => write!(f, "BackSpace"),
+            Key::Delete => write!(f, "Delete"),
+            Key::Space => write!(f, "space"),
+            Key::Up => write!(f, "Up"),
+            Key::Down => write!(f, "Down"),
+            Key::Left => write!(f, "Left"),
+            Key::Right => write!(f, "Right"),
+            Key::Home => write!(f, "Home"),
+            Key::End => write!(f, "End"),
+            Key::PageUp => write!(f, "Prior"),
+            Key::PageDown => write!(f, "Next"),
+            Key::Function(number) => write!(f, "F{}", number),
+        }
+    }
+}
+
+/// Presses keys in whichever application has the focus, for voice commands
+pub trait Keyboard: Send {
+    /// Press and release `chord`
+    fn press(&self, chord: &KeyChord) -> Result<()>;
+}
+
+/// Keyboard for the platform the application runs on
+pub fn platform_keyboard() -> Box<dyn Keyboard> {
+    Box::new(PlatformKeyboard)
+}
+
+/// Presses keys with the platform's own means
+///
+/// Windows sends input events, macOS uses System Events (which needs the
+/// accessibility permission) and Linux uses `xdotool`, so X11 sessions only.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlatformKeyboard;
+
+impl Keyboard for PlatformKeyboard {
+    fn press(&self, chord: &KeyChord) -> Result<()> {
+        #[cfg(target_os = "windows")]
+        {
+            windows_input::press(chord)
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            let modifiers: Vec<&str> = chord.modifiers.iter()
+                .map(|modifier| match modifier {
+                    Modifier::Ctrl => "control down",
+                    Modifier::Alt => "option down",
+                    Modifier::Shift => "shift down",
+                    Modifier::Meta => "command down",
+                })
+                .collect();
+            let modifiers = format!("{{{}}}", modifiers.join(", "));
+
+            // Characters are passed as an argument rather than written into the script
+            let (press, argument) = match chord.key {
+                Key::Char(c) => (format!("keystroke (item 1 of argv) using {}", modifiers), c.to_string()),
+                key => (format!("key code {} using {}", mac_key_code(key), modifiers), String::new()),
+            };
+            let script = format!("tell application \"System Events\" to {}", press);
+            let output = std::process::Command::new("osascript")
+                .args(["-e", "on run argv", "-e", script.as_str(), "-e", "end run", argument.as_str()])
+                .output()
+                .map_err(|e| anyhow!("Failed to run osascript: {}", e))?;
+            if !output.status.success() {
+                anyhow::bail!("Failed to press {}: {}", chord, String::from_utf8_lossy(&output.stderr).trim());
+            }
+            Ok(())
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            let status = std::process::Command::new("xdotool")
+                .args(["key", "--clearmodifiers", &chord.to_string()])
+                .status()
+                .map_err(|e| anyhow!("Failed to run xdotool; install it to press keys by voice: {}", e))?;
+            if !status.success() {
+                anyhow::bail!("Failed to press {}", chord);
+            }
+            Ok(())
+        }
+
+        #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+        {
+            anyhow::bail!("Pressing {} isn't supported on this platform", chord)
+        }
+    }
+}
+
+/// Virtual key code of a key that isn't typed as a character on macOS
+#[cfg(target_os = "macos")]
+fn mac_key_code(key: Key) -> u8 {
+    const FUNCTION_KEYS: [u8; 12] = [122, 120, 99, 118, 96, 97, 98, 100, 101, 109, 103, 111];
+    match key {
+        Key::Enter => 36,
+        Key::Tab => 48,
+        Key::Escape => 53,
+        Key::Backspace => 51,
+        Key::Delete => 117,
+        Key::Space => 49,
+        Key::Left => 123,
+        Key::Right => 124,
+        Key::Down => 125,
+        Key::Up => 126,
+        Key::Home => 115,
+        Key::End => 119,
+        Key::PageUp => 116,
+        Key::PageDown => 121,
+        Key::Function(number) => FUNCTION_KEYS[usize::from(number.clamp(1, 12)) - 1],
+        Key::Char(_) => unreachable!("characters are typed with keystroke"),
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows_input {
+    use super::{Key, KeyChord, Modifier};
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, VkKeyScanW, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
+        VIRTUAL_KEY, VK_BACK, VK_CONTROL, VK_DELETE, VK_DOWN, VK_END, VK_ESCAPE, VK_F1, VK_HOME, VK_LEFT, VK_LWIN,
+        VK_MENU, VK_NEXT, VK_PRIOR, VK_RETURN, VK_RIGHT, VK_SHIFT, VK_SPACE, VK_TAB, VK_UP,
+    };
+
+    fn key_input(key: VIRTUAL_KEY, flags: KEYBD_EVENT_FLAGS) -> INPUT {
+        INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: key,
+                    wScan: 0,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: 0,
+                },
+            },
+        }
+    }
+
+    fn virtual_key(key: Key) -> anyhow::Result<VIRTUAL_KEY> {
+        Ok(match key {
+            // Letters and digits have their uppercase ASCII code as key code
+            Key::Char(c) if c.is_ascii_alphanumeric() => VIRTUAL_KEY(c.to_ascii_uppercase() as u16),
+            Key::Char(c) => {
+                // The low byte is the key typing the character in the current layout, -1 if none does
+                let scan = unsafe { VkKeyScanW(c as u16) };
+                if scan == -1 {
+                    anyhow::bail!("No key types {} in this keyboard layout", c);
+                }
+                VIRTUAL_KEY((scan as u16) & 0xff)
+            }
+            Key::Enter => VK_RETURN,
+            Key::Tab => VK_TAB,
+            Key::Escape => VK_ESCAPE,
+            Key::Backspace => VK_BACK,
+            Key::Delete => VK_DELETE,
+            Key::Space => VK_SPACE,
+            Key::Up => VK_UP,
+            Key::Down => VK_DOWN,
+            Key::Left => VK_LEFT,
+            Key::Right => VK_RIGHT,
+            Key::Home => VK_HOME,
+            Key::End => VK_END,
+            Key::PageUp => VK_PRIOR,
+            Key::PageDown => VK_NEXT,
+            Key::Function(number) => VIRTUAL_KEY(VK_F1.0 + u16::from(number.clamp(1, 12)) - 1),
+        })
+    }
+
+    /// Hold the modifiers, press the key, then release them all in reverse order
+    pub fn press(chord: &KeyChord) -> anyhow::Result<()> {
+        let mut keys: Vec<VIRTUAL_KEY> = chord.modifiers.iter()
+            .map(|modifier| match modifier {
+                Modifier::Ctrl => VK_CONTROL,
+                Modifier::Alt => VK_MENU,
+                Modifier::Shift => VK_SHIFT,
+                Modifier::Meta => VK_LWIN,
+            })
+            .collect();
+        keys.push(virtual_key(chord.key)?);
+
+        let inputs: Vec<INPUT> = keys.iter()
+            .map(|key| key_input(*key, KEYBD_EVENT_FLAGS(0)))
+            .chain(keys.iter().rev().map(|key| key_input(*key, KEYEVENTF_KEYUP)))
+            .collect();
+
+        let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+        if sent as usize != inputs.len() {
+            anyhow::bail!("Failed to press {:?}", chord);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_key_chords() {
+        let chord = KeyChord::parse("Ctrl+Shift+S").unwrap();
+        assert_eq!(chord.modifiers, [Modifier::Ctrl, Modifier::Shift]);
+        assert_eq!(chord.key, Key::Char('s'));
+        assert_eq!(chord.to_string(), "ctrl+shift+s");
+
+        assert_eq!(KeyChord::parse("cmd + enter").unwrap().to_string(), "super+Return");
+        assert_eq!(KeyChord::parse("ctrl+=").unwrap().key, Key::Char('='));
+        assert_eq!(KeyChord::parse("alt+f4").unwrap().key, Key::Function(4));
+        assert_eq!(KeyChord::parse("ctrl+/").unwrap().to_string(), "ctrl+slash");
+        assert_eq!(KeyChord::parse("pagedown").unwrap().modifiers, []);
+
+        assert!(KeyChord::parse("").is_err());
+        assert!(KeyChord::parse("ctrl+").is_err());
+        assert!(KeyChord::parse("ctrl+f13").is_err());
+        assert!(KeyChord::parse("s+ctrl").is_err());
+        assert!(KeyChord::parse("ctrl+é").is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use log::{debug, warn};
+
+use crate::config::LatencyPreset;
+
+/// Device buffer lengths in milliseconds for the low, normal and relaxed presets
+///
+/// WASAPI shared mode runs the engine on a 10 ms period, so asking for less
+/// gains nothing.
+#[cfg(windows)]
+const PRESET_BUFFER_MS: [u32; 3] = [10, 20, 50];
+
+/// CoreAudio copes well with small IO buffers
+#[cfg(target_os = "macos")]
+const PRESET_BUFFER_MS: [u32; 3] = [5, 10, 40];
+
+/// The ALSA plugins for PulseAudio and PipeWire need more headroom than direct hardware access
+#[cfg(not(any(windows, target_os = "macos")))]
+const PRESET_BUFFER_MS: [u32; 3] = [10, 30, 80];
+
+/// Device buffer length for a preset on this platform (milliseconds)
+pub fn preset_buffer_ms(preset: &LatencyPreset) -> u32 {
+    match preset {
+        LatencyPreset::Low => PRESET_BUFFER_MS[0],
+        LatencyPreset::Normal => PRESET_BUFFER_MS[1],
+        LatencyPreset::Relaxed => PRESET_BUFFER_MS[2],
+    }
+}
+
+/// Fixed buffer size for `buffer_ms`, limited to the sizes the device supports
+///
+/// Devices that do not report their limits keep their default buffer.
+pub fn buffer_size(buffer_ms: u32, sample_rate: u32, supported: &cpal::SupportedBufferSize) -> cpal::BufferSize {
+    let frames = (sample_rate as u64 * buffer_ms as u64 / 1000).max(1) as u32;
+
+    match supported {
+        cpal::SupportedBufferSize::Range { min, max } => {
+            // Guard against drivers reporting an inverted range rather than panicking in clamp
+            let validated = frames.max(*min).min(*max);
+            if validated != frames {
+                warn!(
+                    "Device supports {}-{} frame buffers, using {} frames instead of {}",
+                    min, max, validated, frames
+                );
+            }
+            cpal::BufferSize::Fixed(validated)
+        }
+        cpal::SupportedBufferSize::Unknown => {
+            debug!("Device does not report buffer sizes, using its default buffer");
+            cpal::BufferSize::Default
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_are_ordered() {
+        let low = preset_buffer_ms(&LatencyPreset::Low);
+        let normal = preset_buffer_ms(&LatencyPreset::Normal);
+        let relaxed = preset_buffer_ms(&LatencyPreset::Relaxed);
+
+        assert!(low < normal && normal < relaxed);
+    }
+
+    #[test]
+    fn test_buffer_size_is_validated_against_device_range() {
+        let range = cpal::SupportedBufferSize::Range { min: 256, max: 2048 };
+
+        assert_eq!(buffer_size(20, 48000, &range), cpal::BufferSize::Fixed(960));
+        assert_eq!(buffer_size(1, 48000, &range), cpal::BufferSize::Fixed(256));
+        assert_eq!(buffer_size(100, 48000, &range), cpal::BufferSize::Fixed(2048));
+        assert_eq!(buffer_size(20, 48000, &cpal::SupportedBufferSize::Unknown), cpal::BufferSize::Default);
+    }
+}
@@ -0,0 +1,194 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::NaiveDate;
+use log::{info, warn};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::config::{FlushPolicy, LiveOutputSettings};
+
+/// Appends final text to a file as it is transcribed
+///
+/// The file is opened on the first append. Text is buffered and written to
+/// disk as the flush policy says, and whatever is left when the output is
+/// dropped. Each run of transcription starts on a new line of the file.
+pub struct LiveOutput {
+    settings: LiveOutputSettings,
+
+    /// The open file, the day it is for and whether text was appended to it yet
+    file: Option<(NaiveDate, BufWriter<File>, bool)>,
+
+    /// Whether the file had text before this output opened it
+    had_text: bool,
+
+    /// When buffered text was last written to disk
+    last_flush: Instant,
+}
+
+impl LiveOutput {
+    /// Append to the file in `settings`
+    pub fn new(settings: LiveOutputSettings) -> Self {
+        Self {
+            settings,
+            file: None,
+            had_text: false,
+            last_flush: Instant::now(),
+        }
+    }
+
+    /// File text is appended to on `date`: with daily rotation, the date goes before the extension
+    pub fn path_on(&self, date: NaiveDate) -> PathBuf {
+        let path = PathBuf::from(&self.settings.path);
+        if !self.settings.rotate_daily {
+            return path;
+        }
+
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let name = match path.extension() {
+            Some(extension) => format!("{}-{}.{}", stem, date.format("%Y-%m-%d"), extension.to_string_lossy()),
+            None => format!("{}-{}", stem, date.format("%Y-%m-%d")),
+        };
+        path.with_file_name(name)
+    }
+
+    /// Append a segment's final text, with any line or paragraph break it starts with
+    pub fn append(&mut self, text: &str) -> Result<()> {
+        self.append_on(text, chrono::Local::now().date_naive())
+    }
+
+    fn append_on(&mut self, text: &str, date: NaiveDate) -> Result<()> {
+        if self.settings.path.trim().is_empty() {
+            return Err(anyhow!("No live output file configured"));
+        }
+
+        // A new day starts a new file
+        if self.settings.rotate_daily && self.file.as_ref().is_some_and(|(day, _, _)| *day != date) {
+            self.flush()?;
+            self.file = None;
+        }
+
+        if self.file.is_none() {
+            let path = self.path_on(date);
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                std::fs::create_dir_all(dir)
+                    .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+            }
+
+            let file = OpenOptions::new().create(true).append(true).open(&path)
+                .with_context(|| format!("Failed to open live output file {}", path.display()))?;
+            self.had_text = file.metadata().map(|metadata| metadata.len() > 0).unwrap_or(false);
+            info!("Appending transcription to {}", path.display());
+            self.file = Some((date, BufWriter::new(file), false));
+        }
+
+        let had_text = self.had_text;
+        if let Some((_, writer, written)) = self.file.as_mut() {
+            let body = text.trim_start();
+            let separator = if !*written {
+                if had_text { "\n" } else { "" }
+            } else if body.len() < text.len() {
+                &text[..text.len() - body.len()]
+            } else {
+                " "
+            };
+
+            write!(writer, "{}{}", separator, body).context("Failed to append to live output file")?;
+            *written = true;
+        }
+
+        if self.settings.flush == FlushPolicy::EachSegment {
+            return self.flush();
+        }
+        self.flush_if_due()
+    }
+
+    /// Write buffered text to disk if the flush interval has passed
+    pub fn flush_if_due(&mut self) -> Result<()> {
+        let interval = Duration::from_secs(self.settings.flush_interval_secs as u64);
+        if self.settings.flush == FlushPolicy::Interval && self.last_flush.elapsed() >= interval {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Write buffered text to disk
+    pub fn flush(&mut self) -> Result<()> {
+        self.last_flush = Instant::now();
+        match self.file.as_mut() {
+            Some((_, writer, _)) => writer.flush().context("Failed to write live output file"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for LiveOutput {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            warn!("{}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(dir: &std::path::Path, flush: FlushPolicy, rotate_daily: bool) -> LiveOutput {
+        LiveOutput::new(LiveOutputSettings {
+            enabled: true,
+            path: dir.join("notes.md").to_string_lossy().into_owned(),
+            flush,
+            flush_interval_secs: 5,
+            rotate_daily,
+        })
+    }
+
+    #[test]
+    fn test_appends_across_runs_and_days() {
+        let dir = std::env::temp_dir().join(format!("bestme-live-output-{}", std::process::id()));
+        let monday = NaiveDate::from_ymd_opt(2024, 1, 29).unwrap();
+        let tuesday = monday.succ_opt().unwrap();
+
+        let mut live = output(&dir, FlushPolicy::EachSegment, true);
+        assert_eq!(live.path_on(monday), dir.join("notes-2024-01-29.md"));
+
+        live.append_on(" Hello there.", monday).unwrap();
+        live.append_on("How are you?", monday).unwrap();
+        live.append_on("\n\nNext topic.", monday).unwrap();
+
+        // Each segment is on disk as soon as it is appended
+        let read = |date| std::fs::read_to_string(dir.join(format!("notes-{}.md", date))).unwrap();
+        assert_eq!(read("2024-01-29"), "Hello there. How are you?\n\nNext topic.");
+
+        live.append_on("Good morning.", tuesday).unwrap();
+        assert_eq!(read("2024-01-30"), "Good morning.");
+        drop(live);
+
+        // A later run goes on a new line
+        let mut live = output(&dir, FlushPolicy::EachSegment, true);
+        live.append_on("Back again.", monday).unwrap();
+        assert_eq!(read("2024-01-29"), "Hello there. How are you?\n\nNext topic.\nBack again.");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_flushes_on_stop() {
+        let dir = std::env::temp_dir().join(format!("bestme-live-output-stop-{}", std::process::id()));
+        let path = dir.join("notes.md");
+
+        let mut live = output(&dir, FlushPolicy::OnStop, false);
+        assert_eq!(live.path_on(NaiveDate::from_ymd_opt(2024, 1, 29).unwrap()), path);
+        live.append("Kept for now.").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+
+        drop(live);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "Kept for now.");
+
+        let mut unset = LiveOutput::new(LiveOutputSettings::default());
+        assert!(unset.append("Nowhere to go.").is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
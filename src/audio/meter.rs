@@ -0,0 +1,303 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+/// Length of the loudness blocks in milliseconds
+const BLOCK_MS: u32 = 100;
+
+/// Short-term loudness window (EBU R128) in blocks
+const SHORT_TERM_BLOCKS: usize = 30;
+
+/// Loudness reported for silence
+const SILENCE_DB: f32 = -100.0;
+
+/// Level measurements for one metering interval
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct AudioMetrics {
+    /// Peak absolute sample value (0.0 - 1.0)
+    pub peak: f32,
+
+    /// RMS level (0.0 - 1.0)
+    pub rms: f32,
+
+    /// RMS level in dBFS
+    pub rms_db: f32,
+
+    /// Short-term loudness over the last 3 seconds in LUFS
+    pub lufs_short_term: f32,
+
+    /// Whether any sample reached the clipping threshold
+    pub clipped: bool,
+
+    /// Number of samples at or above the clipping threshold
+    pub clip_count: u32,
+}
+
+/// Second-order IIR filter section
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self { b, a, z: [0.0; 2] }
+    }
+
+    #[inline]
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// ITU-R BS.1770 K-weighting filter (high shelf followed by high pass)
+#[derive(Debug, Clone, Copy)]
+struct KWeighting {
+    shelf: Biquad,
+    high_pass: Biquad,
+}
+
+impl KWeighting {
+    fn new(sample_rate: u32) -> Self {
+        let rate = sample_rate.max(1) as f64;
+
+        // Pre-filter: high shelf modelling the acoustic effect of the head
+        let f0 = 1681.974450955533;
+        let gain_db = 3.999843853973347;
+        let q = 0.7071752369554196;
+        let k = (std::f64::consts::PI * f0 / rate).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad::new(
+            [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        );
+
+        // RLB weighting: second-order high pass
+        let f0 = 38.13547087602444;
+        let q = 0.5003270373238773;
+        let k = (std::f64::consts::PI * f0 / rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        let high_pass = Biquad::new(
+            [1.0, -2.0, 1.0],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        );
+
+        Self { shelf, high_pass }
+    }
+
+    #[inline]
+    fn process(&mut self, x: f32) -> f64 {
+        self.high_pass.process(self.shelf.process(x as f64))
+    }
+}
+
+/// Computes peak, RMS, short-term loudness and clipping for interleaved audio
+///
+/// Samples are accumulated until `interval_ms` worth of frames has been seen,
+/// at which point `process` returns the metrics for that interval.
+#[derive(Debug, Clone)]
+pub struct LevelMeter {
+    channels: usize,
+    clip_threshold: f32,
+
+    /// Frames per reported interval
+    interval_frames: usize,
+
+    /// Frames per loudness block
+    block_frames: usize,
+
+    /// K-weighting filter state per channel
+    filters: Vec<KWeighting>,
+
+    /// Mean square of each completed loudness block
+    blocks: VecDeque<f64>,
+
+    /// K-weighted energy and frame count of the block in progress
+    block_energy: f64,
+    block_count: usize,
+
+    /// Interval accumulators
+    peak: f32,
+    sum_squares: f64,
+    samples: usize,
+    frames: usize,
+    clip_count: u32,
+}
+
+impl LevelMeter {
+    /// Create a meter reporting every `interval_ms` milliseconds
+    pub fn new(sample_rate: u32, channels: u16, interval_ms: u32, clip_threshold: f32) -> Self {
+        let channels = channels.max(1) as usize;
+        let rate = sample_rate.max(1) as usize;
+
+        Self {
+            channels,
+            clip_threshold: clip_threshold.clamp(0.0, 1.0),
+            interval_frames: (rate * interval_ms.max(1) as usize / 1000).max(1),
+            block_frames: (rate * BLOCK_MS as usize / 1000).max(1),
+            filters: vec![KWeighting::new(sample_rate); channels],
+            blocks: VecDeque::with_capacity(SHORT_TERM_BLOCKS),
+            block_energy: 0.0,
+            block_count: 0,
+            peak: 0.0,
+            sum_squares: 0.0,
+            samples: 0,
+            frames: 0,
+            clip_count: 0,
+        }
+    }
+
+    /// Feed interleaved samples, returning metrics whenever an interval completes
+    ///
+    /// If a buffer spans several intervals only the last one is returned.
+    pub fn process(&mut self, samples: &[f32]) -> Option<AudioMetrics> {
+        let mut result = None;
+
+        for frame in samples.chunks_exact(self.channels) {
+            let mut weighted = 0.0;
+            for (channel, &sample) in frame.iter().enumerate() {
+                let magnitude = sample.abs();
+                self.peak = self.peak.max(magnitude);
+                self.sum_squares += (sample as f64) * (sample as f64);
+                if magnitude >= self.clip_threshold {
+                    self.clip_count += 1;
+                }
+
+                let filtered = self.filters[channel].process(sample);
+                weighted += filtered * filtered;
+            }
+
+            self.samples += frame.len();
+            self.frames += 1;
+
+            self.block_energy += weighted;
+            self.block_count += 1;
+            if self.block_count >= self.block_frames {
+                if self.blocks.len() == SHORT_TERM_BLOCKS {
+                    self.blocks.pop_front();
+                }
+                self.blocks.push_back(self.block_energy / self.block_count as f64);
+                self.block_energy = 0.0;
+                self.block_count = 0;
+            }
+
+            if self.frames >= self.interval_frames {
+                result = Some(self.take_metrics());
+            }
+        }
+
+        result
+    }
+
+    /// Short-term loudness (LUFS) over the blocks seen so far
+    pub fn short_term_loudness(&self) -> f32 {
+        if self.blocks.is_empty() {
+            return SILENCE_DB;
+        }
+
+        let mean = self.blocks.iter().sum::<f64>() / self.blocks.len() as f64;
+        if mean <= 0.0 {
+            return SILENCE_DB;
+        }
+
+        ((-0.691 + 10.0 * mean.log10()) as f32).max(SILENCE_DB)
+    }
+
+    /// Discard accumulated state
+    pub fn reset(&mut self) {
+        for filter in self.filters.iter_mut() {
+            filter.shelf.z = [0.0; 2];
+            filter.high_pass.z = [0.0; 2];
+        }
+        self.blocks.clear();
+        self.block_energy = 0.0;
+        self.block_count = 0;
+        self.take_metrics();
+    }
+
+    /// Build metrics for the current interval and start a new one
+    fn take_metrics(&mut self) -> AudioMetrics {
+        let rms = if self.samples > 0 {
+            (self.sum_squares / self.samples as f64).sqrt() as f32
+        } else {
+            0.0
+        };
+
+        let metrics = AudioMetrics {
+            peak: self.peak,
+            rms,
+            rms_db: if rms > 0.0 { (20.0 * rms.log10()).max(SILENCE_DB) } else { SILENCE_DB },
+            lufs_short_term: self.short_term_loudness(),
+            clipped: self.clip_count > 0,
+            clip_count: self.clip_count,
+        };
+
+        self.peak = 0.0;
+        self.sum_squares = 0.0;
+        self.samples = 0;
+        self.frames = 0;
+        self.clip_count = 0;
+
+        metrics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(frequency: f32, amplitude: f32, sample_rate: u32, seconds: f32) -> Vec<f32> {
+        (0..(sample_rate as f32 * seconds) as usize)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * frequency * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_full_scale_sine_loudness() {
+        let mut meter = LevelMeter::new(48000, 1, 100, 1.0);
+        let mut last = None;
+        for chunk in sine(1000.0, 1.0, 48000, 3.0).chunks(480) {
+            if let Some(metrics) = meter.process(chunk) {
+                last = Some(metrics);
+            }
+        }
+
+        // A 0 dBFS 1 kHz sine on one channel reads -3.01 LUFS
+        let metrics = last.unwrap();
+        assert!((metrics.lufs_short_term + 3.01).abs() < 0.1, "{}", metrics.lufs_short_term);
+        assert!((metrics.rms - std::f32::consts::FRAC_1_SQRT_2).abs() < 0.01);
+        assert!((metrics.rms_db + 3.01).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_reports_once_per_interval() {
+        let mut meter = LevelMeter::new(16000, 2, 50, 0.99);
+
+        // 50 ms of stereo audio is 800 frames
+        assert!(meter.process(&vec![0.1; 799 * 2]).is_none());
+        let metrics = meter.process(&[0.1, 0.1]).unwrap();
+        assert!((metrics.peak - 0.1).abs() < 1e-6);
+        assert!(!metrics.clipped);
+    }
+
+    #[test]
+    fn test_counts_clipped_samples() {
+        let mut meter = LevelMeter::new(16000, 1, 10, 0.99);
+
+        let mut samples = vec![0.2; 160];
+        samples[10] = 1.0;
+        samples[20] = -0.995;
+        let metrics = meter.process(&samples).unwrap();
+
+        assert!(metrics.clipped);
+        assert_eq!(metrics.clip_count, 2);
+        assert_eq!(metrics.peak, 1.0);
+    }
+}
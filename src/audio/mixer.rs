@@ -0,0 +1,153 @@
+use std::collections::VecDeque;
+
+use log::warn;
+
+use crate::config::MixMode;
+
+/// Combines mono audio from several capture devices into a single stream
+///
+/// Each source pushes samples into its own queue. Output is produced only for
+/// the span that every source has delivered, which keeps the devices aligned.
+/// If a source falls behind by more than the allowed backlog (e.g. it stalled
+/// or its clock runs slow), the gap is filled with silence so capture keeps
+/// flowing.
+#[derive(Debug)]
+pub struct AudioMixer {
+    /// Pending samples for each source
+    queues: Vec<VecDeque<f32>>,
+
+    /// How sources are combined
+    mode: MixMode,
+
+    /// Maximum samples a source may run ahead before lagging sources are padded
+    max_backlog: usize,
+}
+
+impl AudioMixer {
+    /// Create a mixer for `sources` mono inputs
+    ///
+    /// `max_backlog` is the number of samples a source may get ahead of the others.
+    pub fn new(sources: usize, mode: MixMode, max_backlog: usize) -> Self {
+        Self {
+            queues: (0..sources.max(1)).map(|_| VecDeque::new()).collect(),
+            mode,
+            max_backlog: max_backlog.max(1),
+        }
+    }
+
+    /// Number of sources being mixed
+    pub fn source_count(&self) -> usize {
+        self.queues.len()
+    }
+
+    /// Number of channels in the mixed output
+    pub fn output_channels(&self) -> u16 {
+        match self.mode {
+            MixMode::Mix => 1,
+            MixMode::Separate | MixMode::Tracks => self.queues.len() as u16,
+        }
+    }
+
+    /// Queue mono samples from a source
+    pub fn push(&mut self, source: usize, samples: &[f32]) {
+        if let Some(queue) = self.queues.get_mut(source) {
+            queue.extend(samples.iter().copied());
+        }
+    }
+
+    /// Take all audio that is ready, mixed according to the mix mode
+    ///
+    /// Returns an empty vector when no source has enough data yet.
+    pub fn pop_mixed(&mut self) -> Vec<f32> {
+        let longest = self.queues.iter().map(|q| q.len()).max().unwrap_or(0);
+        let shortest = self.queues.iter().map(|q| q.len()).min().unwrap_or(0);
+
+        // Pad lagging sources with silence once the backlog limit is exceeded
+        if longest - shortest > self.max_backlog {
+            let target = longest - self.max_backlog;
+            for (index, queue) in self.queues.iter_mut().enumerate() {
+                if queue.len() < target {
+                    warn!("Mixer source {} fell behind, padding {} samples", index, target - queue.len());
+                    queue.resize(target, 0.0);
+                }
+            }
+        }
+
+        let frames = self.queues.iter().map(|q| q.len()).min().unwrap_or(0);
+        if frames == 0 {
+            return Vec::new();
+        }
+
+        match self.mode {
+            MixMode::Mix => {
+                let scale = 1.0 / self.queues.len() as f32;
+                let mut output = vec![0.0f32; frames];
+                for queue in self.queues.iter_mut() {
+                    for (out, sample) in output.iter_mut().zip(queue.drain(..frames)) {
+                        *out += sample * scale;
+                    }
+                }
+                output
+            }
+            MixMode::Separate | MixMode::Tracks => {
+                let channels = self.queues.len();
+                let mut output = vec![0.0f32; frames * channels];
+                for (channel, queue) in self.queues.iter_mut().enumerate() {
+                    for (frame, sample) in queue.drain(..frames).enumerate() {
+                        output[frame * channels + channel] = sample;
+                    }
+                }
+                output
+            }
+        }
+    }
+
+    /// Discard all queued audio
+    pub fn reset(&mut self) {
+        self.queues.iter_mut().for_each(|q| q.clear());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mix_waits_for_all_sources() {
+        let mut mixer = AudioMixer::new(2, MixMode::Mix, 1000);
+
+        mixer.push(0, &[0.5; 100]);
+        assert!(mixer.pop_mixed().is_empty());
+
+        mixer.push(1, &[0.1; 60]);
+        let mixed = mixer.pop_mixed();
+        assert_eq!(mixed.len(), 60);
+        assert!((mixed[0] - 0.3).abs() < 1e-6);
+
+        // The remainder of source 0 stays queued
+        mixer.push(1, &[0.1; 40]);
+        assert_eq!(mixer.pop_mixed().len(), 40);
+    }
+
+    #[test]
+    fn test_separate_interleaves_sources() {
+        let mut mixer = AudioMixer::new(2, MixMode::Separate, 1000);
+        assert_eq!(mixer.output_channels(), 2);
+
+        mixer.push(0, &[1.0, 2.0]);
+        mixer.push(1, &[3.0, 4.0]);
+        assert_eq!(mixer.pop_mixed(), vec![1.0, 3.0, 2.0, 4.0]);
+    }
+
+    #[test]
+    fn test_stalled_source_is_padded() {
+        let mut mixer = AudioMixer::new(2, MixMode::Mix, 100);
+
+        mixer.push(0, &[0.4; 300]);
+        let mixed = mixer.pop_mixed();
+
+        // Everything beyond the allowed backlog is released with silence for source 1
+        assert_eq!(mixed.len(), 200);
+        assert!((mixed[0] - 0.2).abs() < 1e-6);
+    }
+}
@@ -1,25 +1,146 @@
+pub mod active_window;
+pub mod aec;
+pub mod app_control;
+pub mod benchmark;
 pub mod capture;
+pub mod clipboard;
+pub mod clock;
+pub mod cloud;
+pub mod command_grammar;
+pub mod command_plugin;
+pub mod command_script;
+pub mod command_stats;
+pub mod corrections;
+pub mod denoise;
 pub mod device;
+pub mod engine;
+pub mod export;
+pub mod file;
+pub mod formatting;
+pub mod hallucination;
+pub mod history;
+pub mod injection;
+pub mod hotplug;
+pub mod keystroke;
+pub mod latency;
+pub mod live_output;
+pub mod meter;
+pub mod mixer;
+pub mod network;
+pub mod overflow;
+pub mod overlap;
+pub mod partial;
+pub mod permission;
+pub mod pipeline;
+pub mod profanity;
+#[cfg(target_os = "linux")]
+pub mod pulse;
+pub mod preroll;
+pub mod punctuate;
+pub mod recording;
+pub mod refine;
+pub mod replace;
+pub mod resample;
+pub mod retranscribe;
+pub mod script_hooks;
+pub mod segmenter;
+pub mod session;
+pub mod shell_command;
+pub mod silence;
+pub mod simd;
+pub mod source;
+pub mod speaker_change;
+pub mod spelling;
+pub mod subtitle;
+pub mod system_control;
+pub mod throughput;
 pub mod transcribe;
+pub mod transcript_file;
+pub mod vad;
 pub mod voice_commands;
+pub mod vosk;
+pub mod wake_word;
+#[cfg(target_os = "windows")]
+pub mod wasapi;
+pub mod watchdog;
+pub mod waveform;
 
 use anyhow::Result;
 use cpal::traits::{DeviceTrait, HostTrait};
 
+use crate::config::{AudioSettings, LatencyPreset, MixMode, NetworkSettings, OverflowPolicy, ResamplerQuality};
+
 /// Audio configuration
 #[derive(Debug, Clone)]
 pub struct AudioConfig {
     /// Input device ID
     pub input_device: Option<String>,
     
-    /// Input volume level (0.0 - 1.0)
+    /// Input volume level (0.0 - 2.0, 1.0 is unity gain)
     pub input_volume: f32,
     
+    /// Additional input devices captured and combined with the main device
+    pub additional_devices: Vec<String>,
+    
+    /// How audio from multiple devices is combined
+    pub mix_mode: MixMode,
+    
+    /// What to do when the audio event receiver falls behind
+    pub overflow_policy: OverflowPolicy,
+    
     /// Sample rate
     pub sample_rate: u32,
     
     /// Number of channels
     pub channels: u16,
+    
+    /// Whether voice activity detection gates audio data
+    pub vad_enabled: bool,
+    
+    /// Voice activity detection sensitivity (0.0 - 1.0)
+    pub vad_sensitivity: f32,
+    
+    /// Time to keep forwarding audio after speech stops (milliseconds)
+    pub vad_hangover_ms: u32,
+    
+    /// Resampling quality preset
+    pub resampler_quality: ResamplerQuality,
+    
+    /// Resampler chunk duration in milliseconds (latency vs. CPU trade-off)
+    pub resampler_chunk_ms: u32,
+    
+    /// How often level metrics are reported (milliseconds)
+    pub metrics_interval_ms: u32,
+    
+    /// Sample magnitude counted as clipping
+    pub clip_threshold: f32,
+    
+    /// Whether waveform buckets are produced for visualization
+    pub waveform_enabled: bool,
+    
+    /// How often waveform buckets are sent (milliseconds)
+    pub waveform_interval_ms: u32,
+    
+    /// Min/max buckets per waveform update
+    pub waveform_buckets: usize,
+    
+    /// Seconds without audio before a stalled stream is rebuilt (None to disable)
+    pub watchdog_timeout_secs: Option<u32>,
+    
+    /// Capture buffer size preset
+    pub latency: LatencyPreset,
+    
+    /// Exact device buffer length overriding the latency preset (milliseconds)
+    pub low_latency_buffer_ms: Option<u32>,
+    
+    /// Open the microphone in WASAPI exclusive mode (Windows only, falls back to shared mode)
+    pub exclusive_mode: bool,
+    
+    /// Audio kept while idle and prepended when recording starts (None to disable)
+    pub preroll_ms: Option<u32>,
+    
+    /// Receive the main audio from the network instead of a device (None to disable)
+    pub network: Option<NetworkSettings>,
 }
 
 impl Default for AudioConfig {
@@ -27,8 +148,84 @@ impl Default for AudioConfig {
         Self {
             input_device: None,
             input_volume: 1.0,
+            additional_devices: Vec::new(),
+            mix_mode: MixMode::default(),
+            overflow_policy: OverflowPolicy::default(),
             sample_rate: 16000,
             channels: 1,
+            vad_enabled: false,
+            vad_sensitivity: 0.6,
+            vad_hangover_ms: 400,
+            resampler_quality: ResamplerQuality::default(),
+            resampler_chunk_ms: 20,
+            metrics_interval_ms: 100,
+            clip_threshold: 0.99,
+            waveform_enabled: true,
+            waveform_interval_ms: 50,
+            waveform_buckets: 32,
+            watchdog_timeout_secs: Some(5),
+            latency: LatencyPreset::default(),
+            low_latency_buffer_ms: None,
+            exclusive_mode: false,
+            preroll_ms: None,
+            network: None,
+        }
+    }
+}
+
+impl AudioConfig {
+    /// Create an audio configuration from the persisted audio settings
+    pub fn from_settings(settings: &AudioSettings) -> Self {
+        let low_latency_buffer_ms = if settings.low_latency.enabled {
+            Some(settings.low_latency.buffer_ms)
+        } else {
+            None
+        };
+        
+        // Small device buffers gain nothing if the resampler waits for larger chunks
+        let small_buffer_ms = low_latency_buffer_ms.or_else(|| {
+            (settings.latency == LatencyPreset::Low).then(|| latency::preset_buffer_ms(&LatencyPreset::Low))
+        });
+        let resampler_chunk_ms = match small_buffer_ms {
+            Some(buffer_ms) => settings.resampler.chunk_ms.min(buffer_ms.max(5)),
+            None => settings.resampler.chunk_ms,
+        };
+        
+        Self {
+            input_device: settings.input_device.clone(),
+            input_volume: settings.input_volume,
+            additional_devices: settings.additional_devices.clone(),
+            mix_mode: settings.mix_mode.clone(),
+            overflow_policy: settings.overflow_policy.clone(),
+            vad_enabled: settings.vad.enabled,
+            vad_sensitivity: settings.vad.sensitivity,
+            vad_hangover_ms: settings.vad.hangover_ms,
+            resampler_quality: settings.resampler.quality.clone(),
+            resampler_chunk_ms,
+            metrics_interval_ms: settings.metering.interval_ms,
+            clip_threshold: settings.metering.clip_threshold,
+            waveform_enabled: settings.waveform.enabled,
+            waveform_interval_ms: settings.waveform.interval_ms,
+            waveform_buckets: settings.waveform.buckets,
+            watchdog_timeout_secs: if settings.watchdog.enabled {
+                Some(settings.watchdog.timeout_secs)
+            } else {
+                None
+            },
+            latency: settings.latency.clone(),
+            low_latency_buffer_ms,
+            exclusive_mode: settings.low_latency.enabled && settings.low_latency.exclusive,
+            preroll_ms: if settings.preroll.enabled {
+                Some(settings.preroll.duration_ms)
+            } else {
+                None
+            },
+            network: if settings.network.enabled {
+                Some(settings.network.clone())
+            } else {
+                None
+            },
+            ..Self::default()
         }
     }
 }
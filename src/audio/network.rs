@@ -0,0 +1,443 @@
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use std::io::{ErrorKind, Read};
+use std::net::{SocketAddr, TcpListener, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use super::source::{AudioSource, DataCallback, ErrorCallback, SourceError, SourceFormat, SourceHandle};
+use super::AudioConfig;
+use crate::config::{NetworkFormat, NetworkProtocol, NetworkSettings};
+
+/// How often the receive thread checks whether it should stop
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Largest UDP datagram
+const MAX_DATAGRAM: usize = 65_536;
+
+/// Longest Opus frame in milliseconds
+#[cfg(feature = "opus")]
+const MAX_OPUS_FRAME_MS: usize = 120;
+
+/// Converts raw PCM bytes into samples
+///
+/// A sample split across two reads (common with TCP) is completed by the next read.
+#[derive(Debug, Clone)]
+pub struct PcmDecoder {
+    format: NetworkFormat,
+    pending: Vec<u8>,
+}
+
+impl PcmDecoder {
+    /// Create a decoder for one of the PCM formats
+    pub fn new(format: NetworkFormat) -> Self {
+        Self {
+            format,
+            pending: Vec::new(),
+        }
+    }
+
+    fn sample_size(&self) -> usize {
+        match self.format {
+            NetworkFormat::F32Le => 4,
+            _ => 2,
+        }
+    }
+
+    /// Decode `bytes`, appending the samples to `out`
+    pub fn decode(&mut self, bytes: &[u8], out: &mut Vec<f32>) {
+        let size = self.sample_size();
+
+        self.pending.extend_from_slice(bytes);
+        let whole = self.pending.len() - self.pending.len() % size;
+
+        out.extend(self.pending[..whole].chunks_exact(size).map(|sample| match self.format {
+            NetworkFormat::S16Be => i16::from_be_bytes([sample[0], sample[1]]) as f32 / 32768.0,
+            NetworkFormat::F32Le => f32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]),
+            _ => i16::from_le_bytes([sample[0], sample[1]]) as f32 / 32768.0,
+        }));
+
+        self.pending.drain(..whole);
+    }
+
+    /// Forget a partial sample (e.g. when a sender disconnects)
+    pub fn reset(&mut self) {
+        self.pending.clear();
+    }
+}
+
+/// Payload of an RTP packet (RFC 3550), or None if the packet is malformed
+pub fn rtp_payload(packet: &[u8]) -> Option<&[u8]> {
+    if packet.len() < 12 || packet[0] >> 6 != 2 {
+        return None;
+    }
+
+    let has_padding = packet[0] & 0x20 != 0;
+    let has_extension = packet[0] & 0x10 != 0;
+    let csrc_count = (packet[0] & 0x0f) as usize;
+
+    let mut start = 12 + 4 * csrc_count;
+    if has_extension {
+        let words = u16::from_be_bytes([*packet.get(start + 2)?, *packet.get(start + 3)?]) as usize;
+        start += 4 + 4 * words;
+    }
+
+    let mut end = packet.len();
+    if has_padding {
+        end = end.checked_sub(*packet.last()? as usize)?;
+    }
+
+    packet.get(start..end)
+}
+
+/// Sequence number of an RTP packet
+fn rtp_sequence(packet: &[u8]) -> u16 {
+    u16::from_be_bytes([packet[2], packet[3]])
+}
+
+/// Turns received packets or stream chunks into samples
+enum PacketDecoder {
+    Pcm(PcmDecoder),
+
+    #[cfg(feature = "opus")]
+    Opus {
+        decoder: opus::Decoder,
+        buffer: Vec<f32>,
+        channels: usize,
+    },
+}
+
+impl PacketDecoder {
+    fn new(settings: &NetworkSettings) -> Result<Self> {
+        match settings.format {
+            #[cfg(feature = "opus")]
+            NetworkFormat::Opus => {
+                let channels = match settings.channels {
+                    1 => opus::Channels::Mono,
+                    2 => opus::Channels::Stereo,
+                    n => anyhow::bail!("Opus network audio must be mono or stereo, not {} channels", n),
+                };
+                let decoder = opus::Decoder::new(settings.sample_rate, channels)
+                    .context("Failed to create Opus decoder")?;
+                let max_frame = settings.sample_rate as usize * MAX_OPUS_FRAME_MS / 1000;
+
+                Ok(Self::Opus {
+                    decoder,
+                    buffer: vec![0.0; max_frame * settings.channels as usize],
+                    channels: settings.channels as usize,
+                })
+            }
+
+            #[cfg(not(feature = "opus"))]
+            NetworkFormat::Opus => anyhow::bail!("Opus network audio requires building with the `opus` feature"),
+
+            ref format => Ok(Self::Pcm(PcmDecoder::new(format.clone()))),
+        }
+    }
+
+    fn decode(&mut self, payload: &[u8], out: &mut Vec<f32>) -> Result<()> {
+        match self {
+            Self::Pcm(decoder) => decoder.decode(payload, out),
+
+            #[cfg(feature = "opus")]
+            Self::Opus { decoder, buffer, channels } => {
+                let frames = decoder.decode_float(payload, buffer, false)?;
+                out.extend_from_slice(&buffer[..frames * *channels]);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        match self {
+            Self::Pcm(decoder) => decoder.reset(),
+
+            // Opus packets are self-contained
+            #[cfg(feature = "opus")]
+            Self::Opus { .. } => {}
+        }
+    }
+}
+
+/// Receives audio streamed from another machine
+///
+/// Raw PCM is accepted over UDP, RTP or TCP and Opus over UDP or RTP. The
+/// sender's format is not negotiated; it must match the configured format.
+pub struct NetworkSource {
+    settings: NetworkSettings,
+
+    /// Address the socket is bound to once started
+    local_addr: Option<SocketAddr>,
+}
+
+impl NetworkSource {
+    /// Create a source from the network input settings
+    pub fn new(settings: NetworkSettings) -> Self {
+        Self {
+            settings,
+            local_addr: None,
+        }
+    }
+
+    /// Address the source is listening on (useful when binding to port 0)
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+}
+
+impl AudioSource for NetworkSource {
+    fn name(&self) -> String {
+        format!("{:?} {}", self.settings.protocol, self.settings.bind_address).to_lowercase()
+    }
+
+    fn prepare(&mut self, _config: &AudioConfig) -> Result<SourceFormat> {
+        if self.settings.protocol == NetworkProtocol::Tcp && self.settings.format == NetworkFormat::Opus {
+            anyhow::bail!("Opus network audio needs packet boundaries; use UDP or RTP");
+        }
+
+        // Fail early on an unsupported format rather than when the first packet arrives
+        PacketDecoder::new(&self.settings)?;
+
+        Ok(SourceFormat {
+            sample_rate: self.settings.sample_rate,
+            channels: self.settings.channels.max(1),
+        })
+    }
+
+    fn start(&mut self, on_data: DataCallback, on_error: ErrorCallback) -> Result<SourceHandle> {
+        let decoder = PacketDecoder::new(&self.settings)?;
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = running.clone();
+
+        let thread = match self.settings.protocol {
+            NetworkProtocol::Tcp => {
+                let listener = TcpListener::bind(&self.settings.bind_address)
+                    .with_context(|| format!("Failed to listen on {}", self.settings.bind_address))?;
+                listener.set_nonblocking(true)?;
+                self.local_addr = Some(listener.local_addr()?);
+
+                std::thread::Builder::new()
+                    .name("audio-network-source".to_string())
+                    .spawn(move || receive_tcp(listener, decoder, on_data, on_error, thread_running))?
+            }
+            NetworkProtocol::Udp | NetworkProtocol::Rtp => {
+                let socket = UdpSocket::bind(&self.settings.bind_address)
+                    .with_context(|| format!("Failed to bind {}", self.settings.bind_address))?;
+                socket.set_read_timeout(Some(POLL_INTERVAL))?;
+                self.local_addr = Some(socket.local_addr()?);
+
+                let rtp = self.settings.protocol == NetworkProtocol::Rtp;
+                std::thread::Builder::new()
+                    .name("audio-network-source".to_string())
+                    .spawn(move || receive_udp(socket, rtp, decoder, on_data, on_error, thread_running))?
+            }
+        };
+
+        info!("Listening for network audio on {}", self.local_addr.unwrap());
+
+        Ok(SourceHandle::new(NetworkThread {
+            running,
+            thread: Some(thread),
+        }))
+    }
+}
+
+/// Receive loop for UDP and RTP packets
+fn receive_udp(
+    socket: UdpSocket,
+    rtp: bool,
+    mut decoder: PacketDecoder,
+    mut on_data: DataCallback,
+    mut on_error: ErrorCallback,
+    running: Arc<AtomicBool>,
+) {
+    let mut packet = vec![0u8; MAX_DATAGRAM];
+    let mut samples = Vec::new();
+    let mut sender: Option<SocketAddr> = None;
+    let mut next_sequence: Option<u16> = None;
+
+    while running.load(Ordering::SeqCst) {
+        let (len, from) = match socket.recv_from(&mut packet) {
+            Ok(received) => received,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => continue,
+            Err(e) => {
+                on_error(SourceError::Stream(format!("Network receive failed: {}", e)));
+                break;
+            }
+        };
+
+        if sender != Some(from) {
+            info!("Receiving network audio from {}", from);
+            sender = Some(from);
+            next_sequence = None;
+            decoder.reset();
+        }
+
+        let payload = if rtp {
+            let Some(payload) = rtp_payload(&packet[..len]) else {
+                debug!("Ignoring malformed RTP packet from {}", from);
+                continue;
+            };
+
+            let sequence = rtp_sequence(&packet[..len]);
+            if let Some(expected) = next_sequence {
+                if sequence != expected {
+                    debug!("RTP packets lost or reordered (expected {}, got {})", expected, sequence);
+                }
+            }
+            next_sequence = Some(sequence.wrapping_add(1));
+            payload
+        } else {
+            &packet[..len]
+        };
+
+        samples.clear();
+        match decoder.decode(payload, &mut samples) {
+            Ok(()) if !samples.is_empty() => on_data(&samples),
+            Ok(()) => {}
+            Err(e) => debug!("Failed to decode network audio: {}", e),
+        }
+    }
+
+    debug!("Network source finished");
+}
+
+/// Receive loop for a TCP stream, one sender at a time
+fn receive_tcp(
+    listener: TcpListener,
+    mut decoder: PacketDecoder,
+    mut on_data: DataCallback,
+    mut on_error: ErrorCallback,
+    running: Arc<AtomicBool>,
+) {
+    let mut buffer = vec![0u8; 8192];
+    let mut samples = Vec::new();
+
+    while running.load(Ordering::SeqCst) {
+        let (mut stream, from) = match listener.accept() {
+            Ok(accepted) => accepted,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                std::thread::sleep(POLL_INTERVAL);
+                continue;
+            }
+            Err(e) => {
+                on_error(SourceError::Stream(format!("Network accept failed: {}", e)));
+                break;
+            }
+        };
+
+        info!("Receiving network audio from {}", from);
+        decoder.reset();
+        if let Err(e) = stream.set_nonblocking(false).and_then(|_| stream.set_read_timeout(Some(POLL_INTERVAL))) {
+            warn!("Failed to configure connection from {}: {}", from, e);
+            continue;
+        }
+
+        while running.load(Ordering::SeqCst) {
+            let len = match stream.read(&mut buffer) {
+                Ok(0) => {
+                    info!("Network audio sender {} disconnected", from);
+                    break;
+                }
+                Ok(len) => len,
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted) => continue,
+                Err(e) => {
+                    warn!("Connection from {} failed: {}", from, e);
+                    break;
+                }
+            };
+
+            samples.clear();
+            if decoder.decode(&buffer[..len], &mut samples).is_ok() && !samples.is_empty() {
+                on_data(&samples);
+            }
+        }
+    }
+
+    debug!("Network source finished");
+}
+
+/// Stops the receive thread when dropped
+struct NetworkThread {
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for NetworkThread {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                warn!("Network source thread panicked");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_pcm_sample_split_across_reads() {
+        let mut decoder = PcmDecoder::new(NetworkFormat::S16Le);
+        let bytes: Vec<u8> = [16384i16, -32768, 8192].iter().flat_map(|s| s.to_le_bytes()).collect();
+
+        let mut samples = Vec::new();
+        decoder.decode(&bytes[..3], &mut samples);
+        assert_eq!(samples, vec![0.5]);
+
+        decoder.decode(&bytes[3..], &mut samples);
+        assert_eq!(samples, vec![0.5, -1.0, 0.25]);
+    }
+
+    #[test]
+    fn test_rtp_payload_skips_csrc_extension_and_padding() {
+        let mut packet = vec![0xb1, 96, 0, 7, 0, 0, 0, 0, 0, 0, 0, 1];
+        packet.extend_from_slice(&[0, 0, 0, 2]); // one CSRC
+        packet.extend_from_slice(&[0xbe, 0xde, 0, 1, 1, 2, 3, 4]); // one-word extension
+        packet.extend_from_slice(&[0x40, 0x00, 0xc0, 0x00]); // L16 payload
+        packet.extend_from_slice(&[0, 2]); // two bytes of padding
+
+        let payload = rtp_payload(&packet).unwrap();
+        assert_eq!(payload, &[0x40, 0x00, 0xc0, 0x00]);
+
+        let mut samples = Vec::new();
+        PcmDecoder::new(NetworkFormat::S16Be).decode(payload, &mut samples);
+        assert_eq!(samples, vec![0.5, -0.5]);
+
+        assert!(rtp_payload(&[0x40; 12]).is_none());
+    }
+
+    #[test]
+    fn test_receives_udp_audio() {
+        let mut source = NetworkSource::new(NetworkSettings {
+            enabled: true,
+            bind_address: "127.0.0.1:0".to_string(),
+            format: NetworkFormat::F32Le,
+            ..NetworkSettings::default()
+        });
+        let format = source.prepare(&AudioConfig::default()).unwrap();
+        assert_eq!(format, SourceFormat { sample_rate: 16000, channels: 1 });
+
+        let (sender, receiver) = mpsc::channel();
+        let _handle = source.start(
+            Box::new(move |data| {
+                let _ = sender.send(data.to_vec());
+            }),
+            Box::new(|_| {}),
+        ).unwrap();
+
+        let bytes: Vec<u8> = [0.25f32, -0.75].iter().flat_map(|s| s.to_le_bytes()).collect();
+        UdpSocket::bind("127.0.0.1:0").unwrap()
+            .send_to(&bytes, source.local_addr().unwrap())
+            .unwrap();
+
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)).unwrap(), vec![0.25, -0.75]);
+    }
+}
@@ -0,0 +1,323 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use log::{info, warn};
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+use super::capture::AudioEvent;
+use crate::config::OverflowPolicy;
+
+/// Events held back while the receiver is behind
+const MAX_PENDING_EVENTS: usize = 64;
+
+/// Delivers capture events according to an overflow policy
+///
+/// The capture consumer never waits on a slow receiver unless the policy
+/// asks for it. Events that cannot be delivered are queued, merged or
+/// dropped, and dropped audio is reported with `AudioEvent::FramesDropped`.
+pub struct EventOutbox {
+    /// Channel to the event receiver
+    sender: mpsc::Sender<AudioEvent>,
+
+    /// What to do when the channel is full
+    policy: OverflowPolicy,
+
+    /// Events waiting for room in the channel
+    pending: VecDeque<AudioEvent>,
+
+    /// Frames dropped since capture started (shared with the capture manager)
+    total_dropped: Arc<AtomicU64>,
+
+    /// Frames dropped since the last `FramesDropped` event
+    unreported: u64,
+
+    /// Whether audio is currently being discarded (pause-capture policy)
+    paused: bool,
+}
+
+impl EventOutbox {
+    /// Create an outbox for `sender`
+    pub fn new(sender: mpsc::Sender<AudioEvent>, policy: OverflowPolicy, total_dropped: Arc<AtomicU64>) -> Self {
+        Self {
+            sender,
+            policy,
+            pending: VecDeque::with_capacity(MAX_PENDING_EVENTS),
+            total_dropped,
+            unreported: 0,
+            paused: false,
+        }
+    }
+
+    /// Send an event, applying the overflow policy if the channel is full
+    pub fn send(&mut self, event: AudioEvent) {
+        if !self.flush() {
+            self.overflow(event);
+            return;
+        }
+
+        self.report_dropped();
+
+        match self.sender.try_send(event) {
+            Ok(()) => {
+                if self.paused {
+                    info!("Audio event receiver caught up, resuming capture");
+                    self.paused = false;
+                }
+            },
+            Err(TrySendError::Full(event)) => self.overflow(event),
+            Err(TrySendError::Closed(_)) => {},
+        }
+    }
+
+    /// Count frames lost before they reached the outbox (e.g. ring buffer overruns)
+    pub fn record_dropped(&mut self, frames: u64) {
+        if frames == 0 {
+            return;
+        }
+
+        self.unreported += frames;
+        self.total_dropped.fetch_add(frames, Ordering::Relaxed);
+        self.report_dropped();
+    }
+
+    /// Deliver everything still pending, waiting for the receiver if needed
+    ///
+    /// Must not be called from an async context.
+    pub fn finish(&mut self) {
+        while let Some(event) = self.pending.pop_front() {
+            if self.sender.blocking_send(event).is_err() {
+                break;
+            }
+        }
+
+        self.report_dropped();
+    }
+
+    /// Number of events waiting for the receiver
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Try to deliver pending events; returns true when nothing is left
+    fn flush(&mut self) -> bool {
+        while let Some(event) = self.pending.pop_front() {
+            match self.sender.try_send(event) {
+                Ok(()) => {},
+                Err(TrySendError::Full(event)) => {
+                    self.pending.push_front(event);
+                    return false;
+                },
+                Err(TrySendError::Closed(_)) => {
+                    self.pending.clear();
+                    break;
+                },
+            }
+        }
+
+        true
+    }
+
+    /// Handle an event that could not be delivered
+    fn overflow(&mut self, event: AudioEvent) {
+        match self.policy {
+            OverflowPolicy::DropOldest => {
+                self.pending.push_back(event);
+
+                // Only audio and level updates make room; state changes are always delivered
+                while self.pending.len() > MAX_PENDING_EVENTS {
+                    let oldest = match self.pending.iter().position(|e| matches!(e, AudioEvent::Data(_)) || is_meter_update(e)) {
+                        Some(oldest) => oldest,
+                        None => break,
+                    };
+                    if let Some(oldest) = self.pending.remove(oldest) {
+                        self.count_dropped(&oldest);
+                    }
+                }
+            },
+            OverflowPolicy::CoalesceLevels => {
+                // Only the latest level matters; audio is never dropped
+                if is_meter_update(&event) {
+                    let latest = std::mem::discriminant(&event);
+                    self.pending.retain(|e| std::mem::discriminant(e) != latest);
+                }
+                self.pending.push_back(event);
+
+                // Wait for the receiver once the queue is full
+                while self.pending.len() > MAX_PENDING_EVENTS {
+                    let oldest = match self.pending.pop_front() {
+                        Some(oldest) => oldest,
+                        None => break,
+                    };
+                    if self.sender.blocking_send(oldest).is_err() {
+                        break;
+                    }
+                }
+            },
+            OverflowPolicy::PauseCapture => {
+                if !self.paused {
+                    warn!("Audio event receiver is behind, pausing capture");
+                    self.paused = true;
+                }
+
+                // Discard audio until the receiver catches up, but keep state changes
+                match event {
+                    AudioEvent::Data(_) => self.count_dropped(&event),
+                    event if is_meter_update(&event) => {},
+                    event => {
+                        if self.pending.len() < MAX_PENDING_EVENTS {
+                            self.pending.push_back(event);
+                        }
+                    },
+                }
+            },
+        }
+    }
+
+    /// Count the frames in a dropped event
+    fn count_dropped(&mut self, event: &AudioEvent) {
+        if let AudioEvent::Data(data) = event {
+            let frames = (data.get_samples().len() / data.channels().max(1) as usize) as u64;
+            self.unreported += frames;
+            self.total_dropped.fetch_add(frames, Ordering::Relaxed);
+        }
+    }
+
+    /// Tell the receiver about newly dropped frames when there is room
+    fn report_dropped(&mut self) {
+        if self.unreported == 0 {
+            return;
+        }
+
+        let event = AudioEvent::FramesDropped {
+            dropped: self.unreported,
+            total: self.total_dropped.load(Ordering::Relaxed),
+        };
+
+        // Never queued; if there is no room now the count is reported later
+        if self.sender.try_send(event).is_ok() {
+            warn!("Dropped {} audio frames", self.unreported);
+            self.unreported = 0;
+        }
+    }
+}
+
+/// Whether an event only refreshes a level display and can be merged or skipped
+fn is_meter_update(event: &AudioEvent) -> bool {
+    matches!(
+        event,
+        AudioEvent::Level(_)
+            | AudioEvent::LevelChanged(_)
+            | AudioEvent::Metrics(_)
+            | AudioEvent::Waveform(_)
+            | AudioEvent::ClockDrift(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::capture::AudioData;
+
+    fn data(frames: usize) -> AudioEvent {
+        AudioEvent::Data(AudioData::new(vec![0.0; frames], 16000, 1))
+    }
+
+    fn drain(receiver: &mut mpsc::Receiver<AudioEvent>) -> Vec<AudioEvent> {
+        let mut events = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            events.push(event);
+        }
+        events
+    }
+
+    #[test]
+    fn test_drop_oldest_keeps_newest_audio() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let total = Arc::new(AtomicU64::new(0));
+        let mut outbox = EventOutbox::new(sender, OverflowPolicy::DropOldest, total.clone());
+
+        for _ in 0..(MAX_PENDING_EVENTS + 11) {
+            outbox.send(data(160));
+        }
+
+        // One event in the channel, the newest ones queued, the rest dropped
+        assert_eq!(outbox.pending_len(), MAX_PENDING_EVENTS);
+        assert_eq!(total.load(Ordering::Relaxed), 10 * 160);
+
+        drain(&mut receiver);
+        outbox.send(data(160));
+        let events = drain(&mut receiver);
+        assert!(events.iter().any(|e| matches!(e, AudioEvent::Data(_))));
+    }
+
+    #[test]
+    fn test_drop_oldest_keeps_state_changes() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let total = Arc::new(AtomicU64::new(0));
+        let mut outbox = EventOutbox::new(sender, OverflowPolicy::DropOldest, total.clone());
+
+        outbox.send(data(160));
+        outbox.send(AudioEvent::SpeechStart);
+        outbox.send(AudioEvent::Level(0.5));
+        outbox.send(AudioEvent::SpeechEnd);
+        for _ in 0..MAX_PENDING_EVENTS {
+            outbox.send(data(160));
+        }
+
+        // The level and the two oldest queued blocks make room, the speech boundaries stay
+        assert_eq!(outbox.pending_len(), MAX_PENDING_EVENTS);
+        assert_eq!(total.load(Ordering::Relaxed), 2 * 160);
+        let mut events = drain(&mut receiver);
+        while outbox.pending_len() > 0 {
+            outbox.flush();
+            events.extend(drain(&mut receiver));
+        }
+        assert!(matches!(events[1], AudioEvent::SpeechStart));
+        assert!(matches!(events[2], AudioEvent::SpeechEnd));
+        assert!(!events.iter().any(|e| matches!(e, AudioEvent::Level(_))));
+    }
+
+    #[test]
+    fn test_coalesce_keeps_latest_level() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let mut outbox = EventOutbox::new(sender, OverflowPolicy::CoalesceLevels, Arc::new(AtomicU64::new(0)));
+
+        outbox.send(AudioEvent::Level(0.1));
+        outbox.send(AudioEvent::Level(0.2));
+        outbox.send(data(160));
+        outbox.send(AudioEvent::Level(0.3));
+        assert_eq!(outbox.pending_len(), 2);
+
+        let mut events = drain(&mut receiver);
+        while outbox.pending_len() > 0 {
+            outbox.flush();
+            events.extend(drain(&mut receiver));
+        }
+
+        // Intermediate levels are merged, audio stays in order
+        assert_eq!(events.len(), 3);
+        assert!(matches!(events[1], AudioEvent::Data(_)));
+        assert!(matches!(events[2], AudioEvent::Level(level) if (level - 0.3).abs() < f32::EPSILON));
+    }
+
+    #[test]
+    fn test_pause_capture_reports_dropped_frames() {
+        let (sender, mut receiver) = mpsc::channel(2);
+        let total = Arc::new(AtomicU64::new(0));
+        let mut outbox = EventOutbox::new(sender, OverflowPolicy::PauseCapture, total.clone());
+
+        outbox.send(data(160));
+        outbox.send(data(160));
+        outbox.send(data(160));
+        outbox.send(AudioEvent::SpeechEnd);
+        assert_eq!(total.load(Ordering::Relaxed), 160);
+
+        // Once the receiver catches up, the state change and the drop count arrive
+        drain(&mut receiver);
+        outbox.send(AudioEvent::Level(0.5));
+        let events = drain(&mut receiver);
+        assert!(matches!(events[0], AudioEvent::SpeechEnd));
+        assert!(matches!(events[1], AudioEvent::FramesDropped { dropped: 160, total: 160 }));
+    }
+}
@@ -0,0 +1,134 @@
+use super::partial::same_word;
+
+/// Words at the end of the previous transcription searched for a repeat
+const MAX_OVERLAP_WORDS: usize = 30;
+
+/// Shortest run of shared words taken as a repeat rather than a coincidence
+const MIN_MATCH_WORDS: usize = 2;
+
+/// Most words spoken in a second, for sizing the repeat to the shared audio
+const MAX_WORDS_PER_SEC: f32 = 4.0;
+
+/// Words at the start of a segment that may come before the repeat, heard from a word cut at the edge
+const EDGE_WORDS: usize = 1;
+
+/// The part of a segment's transcription that doesn't repeat the end of the previous segment
+///
+/// Segments share the last `overlap_secs` of audio, so the words spoken in it
+/// end `previous` and start `next` again. Only a run of shared words (ignoring
+/// case and punctuation) that ends `previous` and starts within the first
+/// words of `next` is taken as that repeat, and no longer than the shared
+/// audio can hold; the words of `next` after it are returned. Without such a
+/// run, all of `next` is new, even if it shares a phrase with `previous`.
+pub fn overlap_suffix(previous: &str, next: &str, overlap_secs: f32) -> String {
+    let previous: Vec<&str> = previous.split_whitespace().collect();
+    let next: Vec<&str> = next.split_whitespace().collect();
+    let max_words = (overlap_secs.max(0.0) * MAX_WORDS_PER_SEC).ceil() as usize + EDGE_WORDS;
+
+    // Longest run, preferring the one that starts first in `next`
+    let mut best: Option<(usize, usize)> = None;
+    for start in 0..=EDGE_WORDS.min(next.len()) {
+        let longest = max_words.min(previous.len()).min(next.len() - start);
+        let length = (1..=longest).rev().find(|&length| {
+            previous[previous.len() - length..].iter()
+                .zip(&next[start..start + length])
+                .all(|(a, b)| same_word(a, b))
+        });
+        if let Some(length) = length {
+            if best.map_or(true, |(_, best_length)| length > best_length) {
+                best = Some((start, length));
+            }
+        }
+    }
+
+    // A single shared word only counts right where the two transcriptions meet
+    match best {
+        Some((start, length)) if length >= MIN_MATCH_WORDS || start == 0 => next[start + length..].join(" "),
+        _ => next.join(" "),
+    }
+}
+
+/// The part of a transcription that repeats anywhere near the end of the previous one
+///
+/// Used for sliding caption windows, which share most of their audio, so the
+/// words of the previous window may end up anywhere in the new one. The two
+/// are aligned on their longest run of shared words (ignoring case and
+/// punctuation), and only the words of `next` after that run are returned.
+/// Without such a run, all of `next` is new.
+pub fn novel_suffix(previous: &str, next: &str) -> String {
+    let previous: Vec<&str> = previous.split_whitespace().collect();
+    let previous = &previous[previous.len().saturating_sub(MAX_OVERLAP_WORDS)..];
+    let next: Vec<&str> = next.split_whitespace().collect();
+    let head = &next[..next.len().min(MAX_OVERLAP_WORDS)];
+
+    // Longest common run of words, as (length, end in previous, end in next)
+    let mut best = (0, 0, 0);
+    let mut runs = vec![vec![0usize; head.len() + 1]; previous.len() + 1];
+    for i in 1..=previous.len() {
+        for j in 1..=head.len() {
+            if same_word(previous[i - 1], head[j - 1]) {
+                runs[i][j] = runs[i - 1][j - 1] + 1;
+                // Prefer the run earliest in the new text on ties
+                if runs[i][j] > best.0 || (runs[i][j] == best.0 && j < best.2) {
+                    best = (runs[i][j], i, j);
+                }
+            }
+        }
+    }
+
+    // A single shared word only counts where the two transcriptions meet
+    let (length, previous_end, next_end) = best;
+    let joins = previous_end == previous.len() && next_end == length;
+    if length >= MIN_MATCH_WORDS || (length == 1 && joins) {
+        next[next_end..].join(" ")
+    } else {
+        next.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeated_words_are_dropped() {
+        assert_eq!(
+            novel_suffix("we should meet on Tuesday at the office", "Tuesday at the office, bring the slides"),
+            "bring the slides"
+        );
+
+        // The shared audio can start mid-word, losing a word at the edge
+        assert_eq!(
+            novel_suffix("the results look good so far", "good so far. Next quarter"),
+            "Next quarter"
+        );
+        assert_eq!(novel_suffix("this is the end", "end of the story"), "of the story");
+    }
+
+    #[test]
+    fn test_only_the_shared_audio_is_dropped_from_segments() {
+        assert_eq!(
+            overlap_suffix("we should meet on Tuesday at the office", "Tuesday at the office, bring the slides", 1.0),
+            "bring the slides"
+        );
+        assert_eq!(overlap_suffix("the results look good so far", "far. Next quarter", 1.0), "Next quarter");
+        assert_eq!(overlap_suffix("the results look good so far", "ar so far. Next quarter", 1.0), "Next quarter");
+
+        // A phrase said again after new words was said twice
+        assert_eq!(overlap_suffix("we went to the store", "then I went to the park", 1.0), "then I went to the park");
+
+        // Longer than the shared audio could hold
+        assert_eq!(overlap_suffix("one two three four five six", "one two three four five six seven", 0.5), "one two three four five six seven");
+        assert_eq!(overlap_suffix("I went to the shop", "the weather is nice", 2.0), "the weather is nice");
+        assert_eq!(overlap_suffix("hello there", "hello there", 0.0), "hello there");
+    }
+
+    #[test]
+    fn test_unrelated_text_is_kept() {
+        assert_eq!(novel_suffix("", "hello there"), "hello there");
+        assert_eq!(novel_suffix("good morning everyone", "let's get started"), "let's get started");
+
+        // A lone shared word in the middle is a coincidence
+        assert_eq!(novel_suffix("I went to the shop", "the weather is nice"), "the weather is nice");
+    }
+}
@@ -0,0 +1,174 @@
+use super::overlap::novel_suffix;
+
+/// A partial transcription split into settled and still-changing words
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PartialHypothesis {
+    /// Words two consecutive hypotheses agreed on; these are not revised
+    pub stable: String,
+
+    /// Words that may still change as more audio arrives
+    pub volatile: String,
+}
+
+impl PartialHypothesis {
+    /// The whole hypothesis as one line
+    pub fn text(&self) -> String {
+        match (self.stable.is_empty(), self.volatile.is_empty()) {
+            (_, true) => self.stable.clone(),
+            (true, false) => self.volatile.clone(),
+            (false, false) => format!("{} {}", self.stable, self.volatile),
+        }
+    }
+}
+
+/// Splits repeated transcriptions of a growing window into a stable prefix and a volatile suffix
+///
+/// A word becomes stable once two consecutive hypotheses agree on it and on
+/// everything before it. Stable words are never taken back, even if a later
+/// hypothesis disagrees, so captions don't flicker.
+#[derive(Debug, Clone, Default)]
+pub struct PartialStabilizer {
+    /// Words already reported as stable
+    stable: Vec<String>,
+
+    /// Words after the stable prefix in the previous hypothesis
+    previous: Vec<String>,
+}
+
+impl PartialStabilizer {
+    /// Create a stabilizer for a new window
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a hypothesis for the whole window so far
+    pub fn update(&mut self, hypothesis: &str) -> PartialHypothesis {
+        let words: Vec<&str> = hypothesis.split_whitespace().collect();
+
+        // Skip past the stable prefix; if the hypothesis is shorter it has nothing new yet
+        let tail: Vec<String> = words.iter().skip(self.stable.len()).map(|word| word.to_string()).collect();
+
+        let agreed = tail.iter()
+            .zip(&self.previous)
+            .take_while(|(word, previous)| same_word(word, previous))
+            .count();
+
+        self.stable.extend(tail[..agreed].iter().cloned());
+        self.previous = tail[agreed..].to_vec();
+
+        PartialHypothesis {
+            stable: self.stable.join(" "),
+            volatile: self.previous.join(" "),
+        }
+    }
+
+    /// Forget the window, e.g. once it has been transcribed in full
+    pub fn reset(&mut self) {
+        self.stable.clear();
+        self.previous.clear();
+    }
+}
+
+/// Joins transcriptions of a sliding window into one caption for the whole segment
+///
+/// Once a segment outgrows the window, each transcription only covers its most
+/// recent audio. The words it shares with the previous transcription are
+/// aligned and only what follows them is added, so the caption grows as if the
+/// whole segment had been transcribed and can be stabilized the same way.
+#[derive(Debug, Clone, Default)]
+pub struct SlidingCaption {
+    /// Caption for the segment so far
+    caption: String,
+
+    /// Previous transcription of the window
+    previous: String,
+}
+
+impl SlidingCaption {
+    /// Create a caption for a new segment
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a transcription of the window; `sliding` is whether the window no longer starts at the segment's start
+    pub fn update(&mut self, window_text: &str, sliding: bool) -> &str {
+        if !sliding || self.caption.is_empty() {
+            self.caption = window_text.trim().to_string();
+        } else {
+            let novel = novel_suffix(&self.previous, window_text);
+            if !novel.is_empty() {
+                self.caption.push(' ');
+                self.caption.push_str(&novel);
+            }
+        }
+
+        self.previous = window_text.to_string();
+        &self.caption
+    }
+
+    /// Forget the segment, e.g. once it has been transcribed in full
+    pub fn reset(&mut self) {
+        self.caption.clear();
+        self.previous.clear();
+    }
+}
+
+/// Whether two words match, ignoring case and punctuation
+pub fn same_word(a: &str, b: &str) -> bool {
+    let normalize = |word: &str| -> String {
+        word.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+    };
+    normalize(a) == normalize(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_words_stabilize_when_hypotheses_agree() {
+        let mut stabilizer = PartialStabilizer::new();
+
+        let first = stabilizer.update("the quick");
+        assert_eq!(first.stable, "");
+        assert_eq!(first.volatile, "the quick");
+
+        let second = stabilizer.update("the quick brown");
+        assert_eq!(second.stable, "the quick");
+        assert_eq!(second.volatile, "brown");
+
+        // Punctuation and case changes still count as agreement
+        let third = stabilizer.update("The quick, Brown fox");
+        assert_eq!(third.stable, "the quick Brown");
+        assert_eq!(third.volatile, "fox");
+        assert_eq!(third.text(), "the quick Brown fox");
+    }
+
+    #[test]
+    fn test_stable_words_are_not_revised() {
+        let mut stabilizer = PartialStabilizer::new();
+        stabilizer.update("I scream");
+        stabilizer.update("I scream for");
+
+        let revised = stabilizer.update("Ice cream for you");
+        assert_eq!(revised.stable, "I scream for");
+        assert_eq!(revised.volatile, "you");
+
+        stabilizer.reset();
+        assert_eq!(stabilizer.update("hello"), PartialHypothesis { stable: String::new(), volatile: "hello".to_string() });
+    }
+
+    #[test]
+    fn test_sliding_window_caption_grows() {
+        let mut caption = SlidingCaption::new();
+        assert_eq!(caption.update("we should meet", false), "we should meet");
+        assert_eq!(caption.update("we should meet on friday", false), "we should meet on friday");
+
+        // The window has moved past "we should"
+        assert_eq!(caption.update("meet on friday at the office", true), "we should meet on friday at the office");
+        assert_eq!(caption.update("friday at the office", true), "we should meet on friday at the office");
+
+        caption.reset();
+        assert_eq!(caption.update("at noon", true), "at noon");
+    }
+}
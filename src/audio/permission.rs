@@ -0,0 +1,127 @@
+use anyhow::Result;
+use log::warn;
+use serde::Serialize;
+
+/// Whether the application may record from microphones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MicrophonePermission {
+    /// Recording is allowed
+    Granted,
+
+    /// The user refused access
+    Denied,
+
+    /// Access is blocked by a system policy (e.g. parental controls or MDM)
+    Restricted,
+
+    /// The user has not been asked yet
+    NotDetermined,
+}
+
+/// Current microphone permission
+///
+/// Only macOS gates microphone access; elsewhere this is always `Granted`.
+pub fn microphone_permission() -> MicrophonePermission {
+    #[cfg(target_os = "macos")]
+    {
+        macos::status()
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        MicrophonePermission::Granted
+    }
+}
+
+/// Ask the user for microphone access if they have not been asked yet
+///
+/// Blocks until the user answers the system prompt.
+pub fn request_microphone_permission() -> MicrophonePermission {
+    let status = microphone_permission();
+    if status != MicrophonePermission::NotDetermined {
+        return status;
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        log::info!("Requesting microphone access");
+        if macos::request() {
+            MicrophonePermission::Granted
+        } else {
+            MicrophonePermission::Denied
+        }
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        status
+    }
+}
+
+/// Make sure microphones can be recorded, prompting the user if needed
+pub fn ensure_microphone_access() -> Result<()> {
+    match request_microphone_permission() {
+        MicrophonePermission::Granted => Ok(()),
+        MicrophonePermission::Restricted => {
+            warn!("Microphone access is restricted by system policy");
+            anyhow::bail!("Microphone access is restricted by system policy")
+        }
+        status => {
+            warn!("Microphone access not granted: {:?}", status);
+            anyhow::bail!(
+                "Microphone access denied; allow BestMe in System Settings > Privacy & Security > Microphone"
+            )
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use super::MicrophonePermission;
+    use block::ConcreteBlock;
+    use objc::runtime::{Object, BOOL, YES};
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::sync::mpsc;
+
+    #[link(name = "AVFoundation", kind = "framework")]
+    extern "C" {
+        static AVMediaTypeAudio: *mut Object;
+    }
+
+    /// Read `[AVCaptureDevice authorizationStatusForMediaType:]` for audio
+    pub fn status() -> MicrophonePermission {
+        let status: isize = unsafe {
+            msg_send![class!(AVCaptureDevice), authorizationStatusForMediaType: AVMediaTypeAudio]
+        };
+
+        // AVAuthorizationStatus
+        match status {
+            0 => MicrophonePermission::NotDetermined,
+            1 => MicrophonePermission::Restricted,
+            2 => MicrophonePermission::Denied,
+            _ => MicrophonePermission::Granted,
+        }
+    }
+
+    /// Show the system prompt and wait for the answer
+    pub fn request() -> bool {
+        let (sender, receiver) = mpsc::channel();
+
+        // The handler runs on an arbitrary dispatch queue
+        let handler = ConcreteBlock::new(move |granted: BOOL| {
+            let _ = sender.send(granted == YES);
+        });
+        let handler = handler.copy();
+
+        unsafe {
+            let _: () = msg_send![
+                class!(AVCaptureDevice),
+                requestAccessForMediaType: AVMediaTypeAudio
+                completionHandler: &*handler
+            ];
+        }
+
+        receiver.recv().unwrap_or(false)
+    }
+}
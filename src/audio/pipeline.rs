@@ -0,0 +1,205 @@
+use log::warn;
+use std::sync::Arc;
+
+use super::formatting::format_numbers;
+use super::profanity::ProfanityFilter;
+use super::punctuate::Punctuator;
+use super::replace::Replacer;
+use crate::config::{PostProcessingStep, SpeechSettings};
+
+/// A step of the post-processing applied to each final segment's text
+pub trait TextProcessor: Send + Sync {
+    /// Name of the step, as custom steps are listed in the settings
+    fn name(&self) -> &str;
+
+    /// Process a segment's text; text left empty drops the segment
+    fn process(&self, text: &str) -> String;
+}
+
+impl TextProcessor for Punctuator {
+    fn name(&self) -> &str {
+        "punctuation"
+    }
+
+    fn process(&self, text: &str) -> String {
+        self.punctuate(text)
+    }
+}
+
+impl TextProcessor for Replacer {
+    fn name(&self) -> &str {
+        "replacements"
+    }
+
+    fn process(&self, text: &str) -> String {
+        self.apply(text)
+    }
+}
+
+impl TextProcessor for ProfanityFilter {
+    fn name(&self) -> &str {
+        "profanity"
+    }
+
+    fn process(&self, text: &str) -> String {
+        self.apply(text)
+    }
+}
+
+/// Writes spelled-out numbers from ten up, and percentages, as digits
+///
+/// The transcript formatter does the same with context formatting on; this
+/// step formats numbers without it, or earlier in the pipeline.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NumberFormatter;
+
+impl TextProcessor for NumberFormatter {
+    fn name(&self) -> &str {
+        "numbers"
+    }
+
+    fn process(&self, text: &str) -> String {
+        format_numbers(&text.split_whitespace().collect::<Vec<_>>().join(" "))
+    }
+}
+
+/// Steps applied in order to final text before it is emitted or saved
+#[derive(Clone, Default)]
+pub struct TextPipeline {
+    steps: Vec<Arc<dyn TextProcessor>>,
+}
+
+impl TextPipeline {
+    /// Create a pipeline without steps, which leaves text as it is
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the steps listed in `settings`, in their order
+    ///
+    /// Steps whose own settings turn them off (auto_punctuate, no replacement
+    /// rules, profanity mode off) are left out. Replacements include the
+    /// active dictionary's rules. Custom steps are taken from
+    /// `custom` by name; those the settings don't list run last.
+    pub fn from_settings(settings: &SpeechSettings, punctuator: Punctuator, custom: &[Arc<dyn TextProcessor>]) -> Self {
+        let mut pipeline = Self::new();
+
+        for step in &settings.post_processing.steps {
+            match step {
+                PostProcessingStep::Punctuation => {
+                    if settings.auto_punctuate {
+                        pipeline.push(Arc::new(punctuator.clone()));
+                    }
+                }
+                PostProcessingStep::Numbers => pipeline.push(Arc::new(NumberFormatter)),
+                PostProcessingStep::Replacements => {
+                    let replacer = Replacer::new(&settings.active_replacements());
+                    if !replacer.is_empty() {
+                        pipeline.push(Arc::new(replacer));
+                    }
+                }
+                PostProcessingStep::Profanity => {
+                    let filter = ProfanityFilter::new(&settings.profanity);
+                    if !filter.is_off() {
+                        pipeline.push(Arc::new(filter));
+                    }
+                }
+                PostProcessingStep::Custom(name) => match custom.iter().find(|step| step.name() == name) {
+                    Some(step) => pipeline.push(Arc::clone(step)),
+                    None => warn!("No post-processing step named {:?}", name),
+                },
+            }
+        }
+
+        let listed = |step: &Arc<dyn TextProcessor>| settings.post_processing.steps.iter()
+            .any(|listed| matches!(listed, PostProcessingStep::Custom(name) if name == step.name()));
+        for step in custom.iter().filter(|step| !listed(step)) {
+            pipeline.push(Arc::clone(step));
+        }
+
+        pipeline
+    }
+
+    /// Add a step after the others
+    pub fn push(&mut self, step: Arc<dyn TextProcessor>) {
+        self.steps.push(step);
+    }
+
+    /// Names of the steps, in the order they run
+    pub fn steps(&self) -> Vec<&str> {
+        self.steps.iter().map(|step| step.name()).collect()
+    }
+
+    /// Run `text` through each step, stopping once nothing is left of it
+    pub fn process(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for step in &self.steps {
+            if text.trim().is_empty() {
+                break;
+            }
+            text = step.process(&text);
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, PostProcessingSettings, ProfanityMode, ReplacementRule};
+
+    struct Shout;
+
+    impl TextProcessor for Shout {
+        fn name(&self) -> &str {
+            "shout"
+        }
+
+        fn process(&self, text: &str) -> String {
+            text.to_uppercase()
+        }
+    }
+
+    fn settings(steps: Vec<PostProcessingStep>) -> SpeechSettings {
+        let mut settings = Config::default().audio.speech;
+        settings.post_processing.steps = steps;
+        settings.profanity.mode = ProfanityMode::Mask;
+        settings.replacements.push(ReplacementRule {
+            pattern: "twenty five".to_string(),
+            replacement: "a quarter".to_string(),
+            ..ReplacementRule::default()
+        });
+        settings
+    }
+
+    #[test]
+    fn test_steps_run_in_order() {
+        let text = "damn, it costs twenty five dollars";
+
+        let pipeline = TextPipeline::from_settings(&settings(PostProcessingSettings::default().steps), Punctuator::new(), &[]);
+        assert_eq!(pipeline.steps(), ["punctuation", "replacements", "profanity"]);
+        assert_eq!(pipeline.process(text), "****, it costs a quarter dollars.");
+
+        // Numbers written as digits before the rules leave nothing for them to replace
+        let steps = vec![PostProcessingStep::Numbers, PostProcessingStep::Replacements];
+        let pipeline = TextPipeline::from_settings(&settings(steps), Punctuator::new(), &[]);
+        assert_eq!(pipeline.process(text), "damn, it costs 25 dollars");
+
+        assert_eq!(TextPipeline::new().process(text), text);
+    }
+
+    #[test]
+    fn test_custom_steps() {
+        let custom: Vec<Arc<dyn TextProcessor>> = vec![Arc::new(Shout)];
+
+        let steps = vec![PostProcessingStep::Custom("shout".to_string()), PostProcessingStep::Profanity];
+        let pipeline = TextPipeline::from_settings(&settings(steps), Punctuator::new(), &custom);
+        assert_eq!(pipeline.process("damn it"), "**** IT");
+
+        // Unlisted custom steps run last
+        let mut settings = settings(vec![PostProcessingStep::Punctuation]);
+        settings.auto_punctuate = false;
+        let pipeline = TextPipeline::from_settings(&settings, Punctuator::new(), &custom);
+        assert_eq!(pipeline.steps(), ["shout"]);
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::VecDeque;
+
+/// Longest supported pre-roll in milliseconds
+pub const MAX_PREROLL_MS: u32 = 10_000;
+
+/// Rolling buffer holding the most recent interleaved audio while capture is idle
+///
+/// When recording starts the buffered audio is handed over in one piece so
+/// the first words spoken before the start are not lost.
+#[derive(Debug, Clone)]
+pub struct PreRollBuffer {
+    samples: VecDeque<f32>,
+
+    /// Maximum number of samples kept (a whole number of frames)
+    capacity: usize,
+
+    channels: usize,
+}
+
+impl PreRollBuffer {
+    /// Create a buffer holding `duration_ms` of audio
+    pub fn new(sample_rate: u32, channels: u16, duration_ms: u32) -> Self {
+        let channels = channels.max(1) as usize;
+        let frames = sample_rate as usize * duration_ms.min(MAX_PREROLL_MS) as usize / 1000;
+        let capacity = frames * channels;
+
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+            channels,
+        }
+    }
+
+    /// Append interleaved samples, discarding the oldest beyond the buffer length
+    pub fn push(&mut self, samples: &[f32]) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        // Only the tail of a large buffer can survive
+        let start = samples.len().saturating_sub(self.capacity);
+        let start = start - start % self.channels;
+        self.samples.extend(samples[start..].iter().copied());
+
+        let excess = self.samples.len().saturating_sub(self.capacity);
+        self.samples.drain(..excess);
+    }
+
+    /// Remove and return the buffered audio, oldest first
+    pub fn take(&mut self) -> Vec<f32> {
+        self.samples.drain(..).collect()
+    }
+
+    /// Number of buffered frames
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels
+    }
+
+    /// Whether no audio is buffered
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Discard the buffered audio
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_most_recent_audio() {
+        // 100 ms at 1 kHz is 100 frames
+        let mut buffer = PreRollBuffer::new(1000, 1, 100);
+
+        let samples: Vec<f32> = (0..250).map(|i| i as f32).collect();
+        buffer.push(&samples[..120]);
+        buffer.push(&samples[120..]);
+
+        assert_eq!(buffer.frames(), 100);
+        let kept = buffer.take();
+        assert_eq!(kept[0], 150.0);
+        assert_eq!(kept[99], 249.0);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_stays_frame_aligned() {
+        let mut buffer = PreRollBuffer::new(1000, 2, 10);
+
+        // 10 frames of stereo fit; pushing 13 keeps the last 10 whole frames
+        let samples: Vec<f32> = (0..26).map(|i| i as f32).collect();
+        buffer.push(&samples);
+
+        let kept = buffer.take();
+        assert_eq!(kept.len(), 20);
+        assert_eq!(kept[0], 6.0);
+    }
+
+    #[test]
+    fn test_zero_duration_keeps_nothing() {
+        let mut buffer = PreRollBuffer::new(16000, 1, 0);
+        buffer.push(&[0.5; 160]);
+        assert!(buffer.is_empty());
+    }
+}
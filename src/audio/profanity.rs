@@ -0,0 +1,131 @@
+use log::warn;
+use regex::{Regex, RegexBuilder};
+
+use crate::config::{ProfanityMode, ProfanitySettings};
+
+/// Words filtered unless the filter is off, matched whole and with common endings ("-s", "-ing", ...)
+const DEFAULT_WORDS: [&str; 20] = [
+    "arse", "ass", "asshole", "bastard", "bitch", "bollocks", "bullshit", "crap", "cunt", "damn",
+    "dick", "fuck", "goddamn", "motherfucker", "piss", "prick", "shit", "slut", "twat", "whore",
+];
+
+/// Endings a listed word may have and still be filtered
+const ENDINGS: &str = "(?:s|es|ed|er|ers|ing|in|y)?";
+
+/// Masks or removes profanity in final text
+#[derive(Debug, Clone)]
+pub struct ProfanityFilter {
+    mode: ProfanityMode,
+    pattern: Option<Regex>,
+}
+
+impl ProfanityFilter {
+    /// Filter the built-in words and any from `settings`, as its mode says
+    pub fn new(settings: &ProfanitySettings) -> Self {
+        if settings.mode == ProfanityMode::Off {
+            return Self { mode: ProfanityMode::Off, pattern: None };
+        }
+
+        let mut words: Vec<String> = DEFAULT_WORDS.iter()
+            .map(|word| word.to_string())
+            .chain(settings.words.iter().map(|word| word.trim().to_lowercase()))
+            .filter(|word| !word.is_empty())
+            .collect();
+
+        // Longest first, so a word isn't cut short by one it starts with
+        words.sort_by_key(|word| std::cmp::Reverse(word.len()));
+        words.dedup();
+
+        let alternatives: Vec<String> = words.iter().map(|word| regex::escape(word)).collect();
+        let pattern = RegexBuilder::new(&format!(r"\b(?:{}){}\b", alternatives.join("|"), ENDINGS))
+            .case_insensitive(true)
+            .build();
+
+        match pattern {
+            Ok(pattern) => Self { mode: settings.mode.clone(), pattern: Some(pattern) },
+            Err(e) => {
+                warn!("Profanity filter disabled: {}", e);
+                Self { mode: ProfanityMode::Off, pattern: None }
+            }
+        }
+    }
+
+    /// Whether the filter leaves text unchanged
+    pub fn is_off(&self) -> bool {
+        self.pattern.is_none()
+    }
+
+    /// Filter `text`
+    ///
+    /// Removing a word also removes the space before it, or at the start of the text
+    /// or a line the comma and space after it, so no gaps are left behind.
+    pub fn apply(&self, text: &str) -> String {
+        let pattern = match &self.pattern {
+            Some(pattern) => pattern,
+            None => return text.to_string(),
+        };
+
+        match self.mode {
+            ProfanityMode::Off => text.to_string(),
+            ProfanityMode::Mask => pattern
+                .replace_all(text, |captures: &regex::Captures| "*".repeat(captures[0].chars().count()))
+                .into_owned(),
+            ProfanityMode::Remove => {
+                let mut filtered = String::with_capacity(text.len());
+                let mut last = 0;
+
+                for found in pattern.find_iter(text) {
+                    filtered.push_str(&text[last..found.start()]);
+                    last = found.end();
+
+                    let before = filtered.trim_end_matches([' ', '\t']).len();
+                    if before == 0 || filtered[..before].ends_with('\n') {
+                        let rest = &text[last..];
+                        let rest = rest.strip_prefix(',').unwrap_or(rest);
+                        last = text.len() - rest.trim_start_matches([' ', '\t']).len();
+                    } else {
+                        filtered.truncate(before);
+                    }
+                }
+
+                filtered.push_str(&text[last..]);
+                filtered
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(mode: ProfanityMode, words: &[&str]) -> ProfanityFilter {
+        ProfanityFilter::new(&ProfanitySettings {
+            mode,
+            words: words.iter().map(|word| word.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn test_mask_and_remove() {
+        let text = "Shit, that's a damned good idea, you bastards. Assess the class.";
+
+        let mask = filter(ProfanityMode::Mask, &[]);
+        assert_eq!(mask.apply(text), "****, that's a ****** good idea, you ********. Assess the class.");
+
+        let remove = filter(ProfanityMode::Remove, &[]);
+        assert_eq!(remove.apply(text), "that's a good idea, you. Assess the class.");
+        assert_eq!(remove.apply("Fine.\n\nCrap I forgot"), "Fine.\n\nI forgot");
+
+        let off = filter(ProfanityMode::Off, &[]);
+        assert!(off.is_off());
+        assert_eq!(off.apply(text), text);
+    }
+
+    #[test]
+    fn test_user_words() {
+        let filter = filter(ProfanityMode::Mask, &["Frak", " smeg head "]);
+        assert_eq!(filter.apply("Frak it, smeg head, what the fraking hell?"), "**** it, *********, what the ******* hell?");
+        assert_eq!(filter.apply("Fraktal smegma"), "Fraktal smegma");
+    }
+}
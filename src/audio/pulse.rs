@@ -0,0 +1,384 @@
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+
+use super::source::{DataCallback, ErrorCallback, SourceError, SourceFormat};
+
+/// Length of the blocks a recorded source is delivered in, in milliseconds
+const RECORD_BLOCK_MS: usize = 10;
+
+/// Source (or sink monitor) known to the PulseAudio or PipeWire sound server
+///
+/// Source names are built from the card's bus path and serial number, so
+/// they stay the same when a device is unplugged and plugged back in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PulseSource {
+    /// Server-side source name (e.g. `alsa_input.usb-Blue_Yeti_123-00.analog-stereo`)
+    pub name: String,
+
+    /// Human-readable description
+    pub description: String,
+
+    /// Sink this source monitors, for loopback sources
+    pub monitor_of: Option<String>,
+}
+
+impl PulseSource {
+    /// Whether this source captures what a sink is playing
+    pub fn is_monitor(&self) -> bool {
+        self.monitor_of.is_some()
+    }
+}
+
+/// Run a sound server tool with untranslated output
+fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .env("LC_ALL", "C")
+        .stderr(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+
+    if !output.status.success() {
+        anyhow::bail!("{} exited with {}", program, output.status);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// List all sources, including the monitors of output devices
+///
+/// `pactl` talks to PulseAudio and to PipeWire's PulseAudio server alike.
+pub fn list_sources() -> Result<Vec<PulseSource>> {
+    Ok(parse_sources(&run("pactl", &["list", "sources"])?))
+}
+
+/// Parse the output of `pactl list sources`
+pub fn parse_sources(output: &str) -> Vec<PulseSource> {
+    let mut sources = Vec::new();
+    let mut current: Option<PulseSource> = None;
+
+    for line in output.lines() {
+        if line.starts_with("Source #") {
+            sources.extend(current.take());
+            current = Some(PulseSource {
+                name: String::new(),
+                description: String::new(),
+                monitor_of: None,
+            });
+            continue;
+        }
+
+        let Some(source) = current.as_mut() else { continue };
+        let line = line.trim();
+
+        if let Some(name) = line.strip_prefix("Name: ") {
+            source.name = name.to_string();
+        } else if let Some(description) = line.strip_prefix("Description: ") {
+            source.description = description.to_string();
+        } else if let Some(sink) = line.strip_prefix("Monitor of Sink: ") {
+            if sink != "n/a" {
+                source.monitor_of = Some(sink.to_string());
+            }
+        }
+    }
+
+    sources.extend(current);
+    sources.retain(|source| !source.name.is_empty());
+    sources
+}
+
+/// Name of the default source
+pub fn default_source() -> Option<String> {
+    default_node("default.audio.source", "get-default-source")
+}
+
+/// Name of the default sink
+pub fn default_sink() -> Option<String> {
+    default_node("default.audio.sink", "get-default-sink")
+}
+
+/// Read a default node from the PipeWire metadata, falling back to `pactl`
+fn default_node(key: &str, pactl_command: &str) -> Option<String> {
+    let from_metadata = run("pw-metadata", &["-n", "default", "0", key])
+        .ok()
+        .and_then(|output| parse_metadata_name(&output, key));
+
+    from_metadata.or_else(|| {
+        run("pactl", &[pactl_command])
+            .ok()
+            .map(|output| output.trim().to_string())
+            .filter(|name| !name.is_empty())
+    })
+}
+
+/// Extract the node name stored under `key` from `pw-metadata` output
+///
+/// Values look like `value:'{"name":"alsa_input.pci-0000_00_1f.3.analog-stereo"}'`.
+pub fn parse_metadata_name(output: &str, key: &str) -> Option<String> {
+    let key_marker = format!("key:'{}'", key);
+    let line = output.lines().find(|line| line.contains(&key_marker))?;
+
+    let value = &line[line.find("\"name\":")? + "\"name\":".len()..];
+    let value = value.trim_start().strip_prefix('"')?;
+    let name = &value[..value.find('"')?];
+
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Arguments for `parec` recording `source` as raw 32-bit float samples
+pub fn record_args(source: &str, format: SourceFormat, latency_ms: u32) -> Vec<String> {
+    vec![
+        format!("--device={}", source),
+        "--raw".to_string(),
+        "--format=float32le".to_string(),
+        format!("--rate={}", format.sample_rate),
+        format!("--channels={}", format.channels),
+        format!("--latency-msec={}", latency_ms.max(1)),
+    ]
+}
+
+/// `parec` child recording one source by name
+///
+/// The ALSA plugins record from the default source, and picking another one
+/// means setting `PULSE_SOURCE` or `PIPEWIRE_NODE` in the process
+/// environment. `parec` takes the source name as an argument instead, and
+/// the server converts it to the requested format.
+pub struct SourceRecorder {
+    child: Child,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SourceRecorder {
+    /// Start recording `source`, delivering interleaved samples in 10 ms blocks
+    pub fn start(
+        source: &str,
+        format: SourceFormat,
+        latency_ms: u32,
+        mut on_data: DataCallback,
+        mut on_error: ErrorCallback,
+    ) -> Result<Self> {
+        debug!("Recording from sound server source {}", source);
+        let mut child = Command::new("parec")
+            .args(record_args(source, format, latency_ms))
+            .env("LC_ALL", "C")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("Failed to run parec")?;
+
+        let mut stdout = child.stdout.take()
+            .ok_or_else(|| anyhow::anyhow!("parec has no output"))?;
+
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = running.clone();
+        let block_len = (format.sample_rate as usize * RECORD_BLOCK_MS / 1000).max(1) * format.channels.max(1) as usize;
+
+        let spawned = std::thread::Builder::new()
+            .name("pulse-record".to_string())
+            .spawn(move || {
+                let mut bytes = vec![0u8; block_len * 4];
+                let mut samples = vec![0.0f32; block_len];
+
+                // parec only stops on its own when the source goes away
+                while stdout.read_exact(&mut bytes).is_ok() {
+                    for (sample, raw) in samples.iter_mut().zip(bytes.chunks_exact(4)) {
+                        *sample = f32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]);
+                    }
+                    on_data(&samples);
+                }
+
+                if thread_running.load(Ordering::SeqCst) {
+                    on_error(SourceError::DeviceLost);
+                }
+                debug!("Sound server recording ended");
+            });
+
+        match spawned {
+            Ok(thread) => Ok(Self { child, running, thread: Some(thread) }),
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                Err(e.into())
+            }
+        }
+    }
+}
+
+impl Drop for SourceRecorder {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Err(e) = self.child.kill() {
+            warn!("Failed to stop parec: {}", e);
+        }
+        let _ = self.child.wait();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Raise or lower the volume of the default sink by `percent`, up to 100%
+pub fn change_sink_volume(percent: i32) -> Result<()> {
+    let volume = format!("{:+}%", percent);
+    run("pactl", &["set-sink-volume", "@DEFAULT_SINK@", &volume])?;
+
+    // pactl goes past 100% when asked, which distorts
+    let current = run("pactl", &["get-sink-volume", "@DEFAULT_SINK@"])?;
+    if parse_volume_percent(&current).is_some_and(|volume| volume > 100) {
+        run("pactl", &["set-sink-volume", "@DEFAULT_SINK@", "100%"])?;
+    }
+    Ok(())
+}
+
+/// Mute the default sink, or unmute it when muted
+pub fn toggle_sink_mute() -> Result<()> {
+    run("pactl", &["set-sink-mute", "@DEFAULT_SINK@", "toggle"])?;
+    Ok(())
+}
+
+/// Loudest channel volume in `pactl get-sink-volume` output, in percent
+pub fn parse_volume_percent(output: &str) -> Option<u32> {
+    output.split_whitespace()
+        .filter_map(|word| word.strip_suffix('%')?.parse().ok())
+        .max()
+}
+
+/// Whether a `pactl subscribe` line reports a device or default change
+pub fn is_device_change(line: &str) -> bool {
+    let added_or_removed = line.starts_with("Event 'new'") || line.starts_with("Event 'remove'");
+    let on_device = line.contains(" on source ") || line.contains(" on sink ");
+
+    // Changing the default source or sink is reported as a server change
+    (added_or_removed && on_device) || (line.starts_with("Event 'change'") && line.contains(" on server "))
+}
+
+/// `pactl subscribe` child that wakes the device watcher on changes
+pub struct Subscription {
+    child: Child,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Subscription {
+    /// Start listening for sound server events
+    pub fn start(wake: mpsc::Sender<()>) -> Result<Self> {
+        let mut child = Command::new("pactl")
+            .arg("subscribe")
+            .env("LC_ALL", "C")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("Failed to run pactl subscribe")?;
+
+        let stdout = child.stdout.take()
+            .ok_or_else(|| anyhow::anyhow!("pactl subscribe has no output"))?;
+
+        let thread = std::thread::Builder::new()
+            .name("pulse-subscribe".to_string())
+            .spawn(move || {
+                for line in BufReader::new(stdout).lines() {
+                    let Ok(line) = line else { break };
+                    if is_device_change(&line) && wake.send(()).is_err() {
+                        break;
+                    }
+                }
+
+                debug!("Sound server subscription ended");
+            })?;
+
+        Ok(Self { child, thread: Some(thread) })
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Err(e) = self.child.kill() {
+            warn!("Failed to stop pactl subscribe: {}", e);
+        }
+        let _ = self.child.wait();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCES: &str = "\
+Source #55
+\tState: SUSPENDED
+\tName: alsa_output.pci-0000_00_1f.3.analog-stereo.monitor
+\tDescription: Monitor of Built-in Audio Analog Stereo
+\tDriver: PipeWire
+\tMonitor of Sink: alsa_output.pci-0000_00_1f.3.analog-stereo
+\tLatency: 0 usec, configured 0 usec
+Source #56
+\tState: RUNNING
+\tName: alsa_input.usb-Blue_Microphones_Yeti_797_2020-00.analog-stereo
+\tDescription: Yeti Stereo Microphone Analog Stereo
+\tDriver: PipeWire
+\tMonitor of Sink: n/a
+\tProperties:
+\t\tdevice.description = \"Yeti Stereo Microphone\"
+";
+
+    #[test]
+    fn test_parses_sources_and_monitors() {
+        let sources = parse_sources(SOURCES);
+
+        assert_eq!(sources.len(), 2);
+        assert!(sources[0].is_monitor());
+        assert_eq!(sources[0].monitor_of.as_deref(), Some("alsa_output.pci-0000_00_1f.3.analog-stereo"));
+        assert_eq!(sources[1].name, "alsa_input.usb-Blue_Microphones_Yeti_797_2020-00.analog-stereo");
+        assert_eq!(sources[1].description, "Yeti Stereo Microphone Analog Stereo");
+        assert!(!sources[1].is_monitor());
+    }
+
+    #[test]
+    fn test_parses_metadata_default() {
+        let output = "Found \"default\" metadata 33\n\
+            update: id:0 key:'default.audio.source' value:'{\"name\":\"alsa_input.pci-0000_00_1f.3.analog-stereo\"}' type:'Spa:String:JSON'\n";
+
+        assert_eq!(
+            parse_metadata_name(output, "default.audio.source").as_deref(),
+            Some("alsa_input.pci-0000_00_1f.3.analog-stereo")
+        );
+        assert_eq!(parse_metadata_name(output, "default.audio.sink"), None);
+    }
+
+    #[test]
+    fn test_detects_device_changes() {
+        assert!(is_device_change("Event 'new' on source #61"));
+        assert!(is_device_change("Event 'remove' on sink #48"));
+        assert!(is_device_change("Event 'change' on server #-1"));
+        assert!(!is_device_change("Event 'change' on source #56"));
+        assert!(!is_device_change("Event 'new' on source-output #90"));
+    }
+
+    #[test]
+    fn test_record_args_name_the_source() {
+        let format = SourceFormat { sample_rate: 16000, channels: 1 };
+        let args = record_args("alsa_output.pci-0000_00_1f.3.analog-stereo.monitor", format, 0);
+
+        assert_eq!(args[0], "--device=alsa_output.pci-0000_00_1f.3.analog-stereo.monitor");
+        assert!(args.contains(&"--rate=16000".to_string()));
+        assert!(args.contains(&"--channels=1".to_string()));
+        assert!(args.contains(&"--latency-msec=1".to_string()));
+    }
+
+    #[test]
+    fn test_parse_volume_percent() {
+        let output = "Volume: front-left: 72090 /  110% / 2.48 dB,   front-right: 65536 / 100% / 0.00 dB\n        balance 0.00\n";
+        assert_eq!(parse_volume_percent(output), Some(110));
+        assert_eq!(parse_volume_percent("Volume: n/a"), None);
+    }
+}
@@ -0,0 +1,205 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use log::warn;
+
+/// Words that open a question when followed by anything but a subject, e.g. "what time is it"
+const QUESTION_WORDS: [&str; 14] = [
+    "who", "whom", "whose", "what", "which", "when", "where", "why", "how",
+    "what's", "who's", "where's", "how's", "whats",
+];
+
+/// Verbs that open a question when followed by a subject, e.g. "can you hear me"
+const AUXILIARIES: [&str; 31] = [
+    "am", "is", "are", "was", "were", "do", "does", "did", "have", "has", "had",
+    "can", "could", "will", "would", "shall", "should", "may", "might", "must",
+    "isn't", "aren't", "wasn't", "weren't", "don't", "doesn't", "didn't",
+    "can't", "couldn't", "won't", "wouldn't",
+];
+
+/// Words that can be the subject right after a question word
+const SUBJECTS: [&str; 14] = [
+    "i", "you", "we", "they", "he", "she", "it", "there", "this", "that",
+    "anyone", "anybody", "someone", "everyone",
+];
+
+/// Abbreviations whose trailing dot doesn't end a sentence
+const ABBREVIATIONS: [&str; 9] = ["mr.", "mrs.", "ms.", "dr.", "st.", "vs.", "etc.", "e.g.", "i.e."];
+
+/// A model restoring punctuation and capitalization, refining the rules
+pub trait PunctuationModel: Send + Sync {
+    /// Punctuate a transcription
+    fn punctuate(&self, text: &str) -> Result<String>;
+}
+
+/// Restores punctuation and capitalization in transcriptions
+///
+/// Engines like Vosk produce lowercase text without punctuation; Whisper mostly
+/// punctuates, but not always at the end of a segment. Rules capitalize
+/// sentences and "I", and end each segment with a full stop or, when it opens
+/// like one, a question mark. Punctuation already in the text is kept. A model
+/// can be added to place punctuation within sentences; the rules still run on
+/// its output.
+#[derive(Clone, Default)]
+pub struct Punctuator {
+    /// Optional model run before the rules
+    model: Option<Arc<dyn PunctuationModel>>,
+}
+
+impl Punctuator {
+    /// Create a rule-based punctuator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a punctuator refining its rules with a model
+    pub fn with_model(model: Arc<dyn PunctuationModel>) -> Self {
+        Self { model: Some(model) }
+    }
+
+    /// Punctuate and capitalize a transcription
+    pub fn punctuate(&self, text: &str) -> String {
+        let text = text.trim();
+        if text.is_empty() {
+            return String::new();
+        }
+
+        match &self.model {
+            Some(model) => match model.punctuate(text) {
+                Ok(punctuated) => apply_rules(&punctuated),
+                Err(e) => {
+                    warn!("Punctuation model failed, using rules only: {}", e);
+                    apply_rules(text)
+                }
+            },
+            None => apply_rules(text),
+        }
+    }
+}
+
+/// Capitalize sentences and end the text with a terminator
+fn apply_rules(text: &str) -> String {
+    let mut words: Vec<String> = text.split_whitespace().map(str::to_string).collect();
+    let mut sentence_start = 0;
+
+    for (i, word) in words.iter_mut().enumerate() {
+        let pronoun = matches!(bare(word).as_str(), "i" | "i'm" | "i'll" | "i've" | "i'd");
+        if pronoun || i == sentence_start {
+            *word = capitalize(word);
+        }
+        if ends_sentence(word) {
+            sentence_start = i + 1;
+        }
+    }
+
+    // The last sentence is closed unless it already is
+    if words.last().is_some_and(|last| !is_closed(last)) {
+        let terminator = if is_question(&words[sentence_start..]) { '?' } else { '.' };
+        let last = words.last_mut().expect("words are not empty");
+        let trimmed = last.trim_end_matches([',', ';', ':', '-']).len();
+        last.truncate(trimmed);
+        last.push(terminator);
+    }
+
+    words.join(" ")
+}
+
+/// Whether a word ends its sentence
+pub fn ends_sentence(word: &str) -> bool {
+    let word = strip_closing(word);
+    word.ends_with(['!', '?', '…'])
+        || (word.ends_with('.') && !ABBREVIATIONS.contains(&word.to_lowercase().as_str()))
+}
+
+/// Whether text already ends with a terminator, even an abbreviation's
+pub fn is_closed(word: &str) -> bool {
+    strip_closing(word).ends_with(['.', '!', '?', '…'])
+}
+
+/// A word without closing quotes or brackets
+fn strip_closing(word: &str) -> &str {
+    word.trim_end_matches(['"', '\'', ')', '»', '”'])
+}
+
+/// Whether a sentence reads as a question from its first words
+fn is_question(sentence: &[String]) -> bool {
+    let first = sentence.first().map(|word| bare(word)).unwrap_or_default();
+    let second = sentence.get(1).map(|word| bare(word)).unwrap_or_default();
+
+    if QUESTION_WORDS.contains(&first.as_str()) {
+        // "what you need is rest" and "when I was young" aren't questions
+        return sentence.len() == 1 || !SUBJECTS.contains(&second.as_str());
+    }
+
+    // "can you" and "is it" are, but "do the dishes" isn't
+    AUXILIARIES.contains(&first.as_str()) && SUBJECTS.contains(&second.as_str())
+}
+
+/// Lowercase word without surrounding punctuation, for comparing
+fn bare(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'')
+        .trim_matches('\'')
+        .to_lowercase()
+        .replace('’', "'")
+}
+
+/// Uppercase the first letter of a word, after any opening quotes or brackets
+pub fn capitalize(word: &str) -> String {
+    match word.char_indices().find(|(_, c)| c.is_alphabetic()) {
+        Some((i, c)) if c.is_lowercase() => {
+            format!("{}{}{}", &word[..i], c.to_uppercase(), &word[i + c.len_utf8()..])
+        }
+        _ => word.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sentence_boundaries() {
+        let punctuator = Punctuator::new();
+        assert_eq!(punctuator.punctuate("hello world"), "Hello world.");
+        assert_eq!(
+            punctuator.punctuate(" we met dr. smith. then i left and i'm home now"),
+            "We met dr. smith. Then I left and I'm home now."
+        );
+        assert_eq!(punctuator.punctuate("it works! really"), "It works! Really.");
+        assert_eq!(punctuator.punctuate("first, second,"), "First, second.");
+
+        // Punctuated text is left alone
+        assert_eq!(punctuator.punctuate("Already done."), "Already done.");
+        assert_eq!(punctuator.punctuate("\"quoted.\" next"), "\"Quoted.\" Next.");
+        assert_eq!(punctuator.punctuate("   "), "");
+    }
+
+    #[test]
+    fn test_questions() {
+        let punctuator = Punctuator::new();
+        assert_eq!(punctuator.punctuate("what time is it"), "What time is it?");
+        assert_eq!(punctuator.punctuate("can you hear me"), "Can you hear me?");
+        assert_eq!(punctuator.punctuate("okay. is it raining"), "Okay. Is it raining?");
+        assert_eq!(punctuator.punctuate("why"), "Why?");
+
+        // Statements opening like questions
+        assert_eq!(punctuator.punctuate("when i was young"), "When I was young.");
+        assert_eq!(punctuator.punctuate("do the dishes"), "Do the dishes.");
+
+        // A full stop the engine chose isn't second-guessed
+        assert_eq!(punctuator.punctuate("How odd."), "How odd.");
+    }
+
+    #[test]
+    fn test_model_output_is_refined_by_rules() {
+        struct Commas;
+        impl PunctuationModel for Commas {
+            fn punctuate(&self, text: &str) -> Result<String> {
+                Ok(text.replace("hello how are you", "hello, how are you?"))
+            }
+        }
+
+        let punctuator = Punctuator::with_model(Arc::new(Commas));
+        assert_eq!(punctuator.punctuate("hello how are you i asked"), "Hello, how are you? I asked.");
+    }
+}
@@ -0,0 +1,225 @@
+use anyhow::{Context, Result};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use log::{debug, info};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use super::capture::AudioData;
+use crate::config::RecordingSettings;
+
+/// Bytes per sample written to disk (16-bit PCM)
+const BYTES_PER_SAMPLE: u64 = 2;
+
+/// Writes the captured audio stream to timestamped WAV files
+///
+/// Files are opened lazily on the first buffer so the WAV header matches the
+/// actual stream format. A new file is started when the maximum duration or
+/// size is reached, or when the stream format changes (e.g. after a device switch).
+pub struct RecordingManager {
+    /// Recording settings
+    settings: RecordingSettings,
+
+    /// Directory where recordings are written
+    output_dir: PathBuf,
+
+    /// Writer for the current file
+    writer: Option<WavWriter<BufWriter<File>>>,
+
+    /// Format of the current file
+    spec: Option<WavSpec>,
+
+    /// Path of the current file
+    current_path: Option<PathBuf>,
+
+    /// Interleaved samples written to the current file
+    samples_written: u64,
+
+    /// Whether recording is active
+    active: bool,
+
+    /// Files completed during this session
+    completed: Vec<PathBuf>,
+}
+
+impl RecordingManager {
+    /// Create a new recording manager
+    pub fn new(settings: RecordingSettings) -> Result<Self> {
+        let output_dir = recordings_dir(&settings)?;
+
+        Ok(Self {
+            settings,
+            output_dir,
+            writer: None,
+            spec: None,
+            current_path: None,
+            samples_written: 0,
+            active: false,
+            completed: Vec::new(),
+        })
+    }
+
+    /// Start recording; the first file is created when audio arrives
+    pub fn start(&mut self) -> Result<()> {
+        if self.active {
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(&self.output_dir)
+            .with_context(|| format!("Failed to create recordings directory {}", self.output_dir.display()))?;
+
+        info!("Recording audio to {}", self.output_dir.display());
+        self.active = true;
+        self.completed.clear();
+        Ok(())
+    }
+
+    /// Stop recording and finalize the current file
+    ///
+    /// Returns the files written during this session.
+    pub fn stop(&mut self) -> Result<Vec<PathBuf>> {
+        self.finish_file()?;
+        self.active = false;
+        Ok(std::mem::take(&mut self.completed))
+    }
+
+    /// Check if recording is active
+    pub fn is_recording(&self) -> bool {
+        self.active
+    }
+
+    /// Get the file currently being written
+    pub fn current_file(&self) -> Option<&Path> {
+        self.current_path.as_deref()
+    }
+
+    /// Append a buffer of captured audio
+    pub fn write(&mut self, audio: &AudioData) -> Result<()> {
+        if !self.active {
+            return Ok(());
+        }
+
+        let spec = WavSpec {
+            channels: audio.channels(),
+            sample_rate: audio.sample_rate(),
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+
+        if self.spec != Some(spec) || self.limit_reached(&spec) {
+            self.finish_file()?;
+        }
+
+        if self.writer.is_none() {
+            self.open_file(spec)?;
+        }
+
+        if let Some(writer) = self.writer.as_mut() {
+            for &sample in audio.get_samples() {
+                let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+                writer.write_sample(value).context("Failed to write recording sample")?;
+            }
+            self.samples_written += audio.get_samples().len() as u64;
+        }
+
+        Ok(())
+    }
+
+    /// Check if the current file has reached its rotation limit
+    fn limit_reached(&self, spec: &WavSpec) -> bool {
+        if self.writer.is_none() {
+            return false;
+        }
+
+        let samples_per_second = spec.sample_rate as u64 * spec.channels.max(1) as u64;
+
+        let duration_reached = self.settings.max_duration_secs > 0
+            && self.samples_written >= self.settings.max_duration_secs * samples_per_second;
+
+        let size_reached = self.settings.max_file_size_mb > 0
+            && self.samples_written * BYTES_PER_SAMPLE >= self.settings.max_file_size_mb * 1024 * 1024;
+
+        duration_reached || size_reached
+    }
+
+    /// Create a new timestamped WAV file
+    fn open_file(&mut self, spec: WavSpec) -> Result<()> {
+        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+        let mut path = self.output_dir.join(format!("recording_{}.wav", timestamp));
+
+        // Rotation can happen more than once per second
+        let mut suffix = 1;
+        while path.exists() {
+            path = self.output_dir.join(format!("recording_{}_{}.wav", timestamp, suffix));
+            suffix += 1;
+        }
+
+        let writer = WavWriter::create(&path, spec)
+            .with_context(|| format!("Failed to create recording file {}", path.display()))?;
+
+        debug!("Started recording file {}", path.display());
+        self.writer = Some(writer);
+        self.spec = Some(spec);
+        self.current_path = Some(path);
+        self.samples_written = 0;
+        Ok(())
+    }
+
+    /// Finalize the current file, if any
+    fn finish_file(&mut self) -> Result<()> {
+        if let Some(writer) = self.writer.take() {
+            writer.finalize().context("Failed to finalize recording file")?;
+
+            if let Some(path) = self.current_path.take() {
+                info!("Saved recording {}", path.display());
+                self.completed.push(path);
+            }
+        }
+
+        self.spec = None;
+        self.samples_written = 0;
+        Ok(())
+    }
+}
+
+impl Drop for RecordingManager {
+    fn drop(&mut self) {
+        let _ = self.finish_file();
+    }
+}
+
+/// Directory recordings are written to with these settings
+pub fn recordings_dir(settings: &RecordingSettings) -> Result<PathBuf> {
+    match &settings.output_dir {
+        Some(dir) if !dir.is_empty() => Ok(PathBuf::from(dir)),
+        _ => default_recordings_dir(),
+    }
+}
+
+/// Recordings saved in a directory, newest first
+pub fn list_recordings(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut recordings = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read recordings directory {}", dir.display()))? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if name.starts_with("recording_") && name.ends_with(".wav") {
+            recordings.push(path);
+        }
+    }
+
+    // Names start with the time recording started
+    recordings.sort_by(|a, b| b.cmp(a));
+    Ok(recordings)
+}
+
+/// Default directory for recordings
+pub fn default_recordings_dir() -> Result<PathBuf> {
+    let project_dirs = directories::ProjectDirs::from("com", "bestme", "BestMe")
+        .context("Failed to determine project directories")?;
+
+    Ok(project_dirs.data_dir().join("recordings"))
+}
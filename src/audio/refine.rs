@@ -0,0 +1,122 @@
+use anyhow::{anyhow, Context, Result};
+use log::info;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use super::session::Session;
+use crate::config::RefineSettings;
+
+/// What a language model was asked to do with a transcript
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RefineKind {
+    /// Fix grammar and remove filler words from an utterance
+    Cleanup,
+
+    /// Summarize a whole session
+    Summary,
+}
+
+impl RefineKind {
+    /// Name of the kind in events, e.g. "cleanup"
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Cleanup => "cleanup",
+            Self::Summary => "summary",
+        }
+    }
+}
+
+/// Refines transcripts with a language model behind an OpenAI-compatible chat completions endpoint
+///
+/// The model's output is only ever offered next to the raw transcript, never
+/// in its place, since a model can get things wrong the engine got right.
+#[derive(Debug, Clone)]
+pub struct Refiner {
+    settings: RefineSettings,
+    client: reqwest::Client,
+}
+
+impl Refiner {
+    /// Refiner for the configured endpoint; nothing is sent until text is refined
+    pub fn new(settings: RefineSettings) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(settings.timeout_secs as u64))
+            .build()?;
+
+        info!("Refining transcripts with {} at {}", settings.model, settings.endpoint);
+        Ok(Self { settings, client })
+    }
+
+    /// Fix the grammar of an utterance and remove its filler words
+    pub async fn cleanup(&self, text: &str) -> Result<String> {
+        self.complete(&self.settings.cleanup_prompt, text).await
+    }
+
+    /// Summarize a session's transcript
+    pub async fn summarize(&self, session: &Session) -> Result<String> {
+        let text = session.text();
+        if text.trim().is_empty() {
+            return Err(anyhow!("Session {} has no transcript to summarize", session.id));
+        }
+        self.complete(&self.settings.summary_prompt, &text).await
+    }
+
+    async fn complete(&self, prompt: &str, text: &str) -> Result<String> {
+        let mut request = self.client.post(&self.settings.endpoint)
+            .json(&request_body(&self.settings.model, prompt, text));
+        if let Some(api_key) = self.settings.api_key.as_deref().filter(|key| !key.is_empty()) {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = request.send().await
+            .with_context(|| format!("Failed to reach {}", self.settings.endpoint))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Language model returned {}: {}", status, body.trim()));
+        }
+
+        let json: Value = response.json().await.context("Invalid language model response")?;
+        response_text(&json).ok_or_else(|| anyhow!("Language model response has no text"))
+    }
+}
+
+/// Chat completion request asking `model` to apply `prompt` to `text`
+fn request_body(model: &str, prompt: &str, text: &str) -> Value {
+    json!({
+        "model": model,
+        "messages": [
+            { "role": "system", "content": prompt },
+            { "role": "user", "content": text.trim() }
+        ],
+        "temperature": 0.2,
+        "stream": false
+    })
+}
+
+/// Text of the first choice of a chat completion response
+fn response_text(json: &Value) -> Option<String> {
+    let text = json["choices"][0]["message"]["content"].as_str()?.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_and_response() {
+        let body = request_body("llama3.2", "Clean up.", " um so we uh ship friday ");
+        assert_eq!(body["model"], "llama3.2");
+        assert_eq!(body["messages"][0]["content"], "Clean up.");
+        assert_eq!(body["messages"][1]["role"], "user");
+        assert_eq!(body["messages"][1]["content"], "um so we uh ship friday");
+
+        let response = json!({
+            "choices": [{ "index": 0, "message": { "role": "assistant", "content": "\nSo we ship on Friday.\n" } }]
+        });
+        assert_eq!(response_text(&response).as_deref(), Some("So we ship on Friday."));
+        assert_eq!(response_text(&json!({ "choices": [] })), None);
+        assert_eq!(response_text(&json!({ "choices": [{ "message": { "content": " " } }] })), None);
+    }
+}
@@ -0,0 +1,134 @@
+use anyhow::{Context, Result};
+use log::warn;
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::Serialize;
+
+use crate::config::ReplacementRule;
+
+/// What a set of rules would make of a text, without transcribing anything
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReplacementPreview {
+    /// The text after all rules
+    pub text: String,
+
+    /// Replacements each rule made, in rule order (0 for disabled rules)
+    pub matches: Vec<usize>,
+}
+
+/// Compile a rule's pattern, checking it is valid
+pub fn compile(rule: &ReplacementRule) -> Result<Regex> {
+    let pattern = if rule.regex { rule.pattern.clone() } else { regex::escape(&rule.pattern) };
+    let pattern = if rule.whole_word { format!(r"\b(?:{})\b", pattern) } else { pattern };
+
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!rule.case_sensitive)
+        .build()
+        .with_context(|| format!("Invalid pattern {:?}", rule.pattern))
+}
+
+/// Applies find/replace rules to final transcript text, in order
+#[derive(Debug, Clone, Default)]
+pub struct Replacer {
+    /// Compiled pattern, replacement and whether it expands group references
+    rules: Vec<(Regex, String, bool)>,
+}
+
+impl Replacer {
+    /// Compile the enabled rules, skipping any that are invalid
+    pub fn new(rules: &[ReplacementRule]) -> Self {
+        let rules = rules.iter()
+            .filter(|rule| rule.enabled && !rule.pattern.is_empty())
+            .filter_map(|rule| match compile(rule) {
+                Ok(regex) => Some((regex, rule.replacement.clone(), rule.regex)),
+                Err(e) => {
+                    warn!("Skipping replacement rule: {:#}", e);
+                    None
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Whether there are no rules to apply
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Apply every rule to `text`, each to the result of the one before
+    pub fn apply(&self, text: &str) -> String {
+        self.rules.iter().fold(text.to_string(), |text, (regex, replacement, expand)| {
+            replace(regex, &text, replacement, *expand)
+        })
+    }
+}
+
+/// Apply `rules` to `text` and count what each one replaced
+///
+/// Unlike a `Replacer`, an invalid rule is an error, so it can be fixed before it is saved.
+pub fn preview(rules: &[ReplacementRule], text: &str) -> Result<ReplacementPreview> {
+    let mut text = text.to_string();
+    let mut matches = Vec::with_capacity(rules.len());
+
+    for (i, rule) in rules.iter().enumerate() {
+        if !rule.enabled || rule.pattern.is_empty() {
+            matches.push(0);
+            continue;
+        }
+
+        let regex = compile(rule).with_context(|| format!("Rule {}", i + 1))?;
+        matches.push(regex.find_iter(&text).count());
+        text = replace(&regex, &text, &rule.replacement, rule.regex);
+    }
+
+    Ok(ReplacementPreview { text, matches })
+}
+
+fn replace(regex: &Regex, text: &str, replacement: &str, expand: bool) -> String {
+    if expand {
+        regex.replace_all(text, replacement).into_owned()
+    } else {
+        regex.replace_all(text, NoExpand(replacement)).into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, replacement: &str) -> ReplacementRule {
+        ReplacementRule {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            ..ReplacementRule::default()
+        }
+    }
+
+    #[test]
+    fn test_rules_apply_in_order() {
+        let rules = vec![
+            ReplacementRule { whole_word: true, ..rule("best me", "BestMe") },
+            ReplacementRule { regex: true, ..rule(r"\b(um|uh),? ", "") },
+            ReplacementRule { regex: true, case_sensitive: true, ..rule(r"(\d+) dollars", "$$$1") },
+            rule("$1", "one"),
+            ReplacementRule { enabled: false, ..rule("BestMe", "nothing") },
+        ];
+        let replacer = Replacer::new(&rules);
+
+        assert_eq!(replacer.apply("Um, I think Best Me costs 5 dollars."), "I think BestMe costs $5.");
+        assert_eq!(replacer.apply("bestmeal costs $1 in 10 Dollars"), "bestmeal costs one in 10 Dollars");
+    }
+
+    #[test]
+    fn test_preview_counts_matches() {
+        let rules = vec![rule("a", "b"), ReplacementRule { enabled: false, ..rule("b", "c") }, rule("bb", "c")];
+        let preview = preview(&rules, "aa ab").unwrap();
+        assert_eq!(preview.text, "c c");
+        assert_eq!(preview.matches, [3, 0, 2]);
+
+        let invalid = vec![rule("fine", ""), ReplacementRule { regex: true, ..rule("(unclosed", "") }];
+        assert!(super::preview(&invalid, "text").is_err());
+
+        // The replacer skips the invalid rule and applies the rest
+        assert_eq!(Replacer::new(&invalid).apply("fine text"), " text");
+    }
+}
@@ -0,0 +1,190 @@
+use anyhow::Result;
+use log::debug;
+use rubato::{
+    Resampler, SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction,
+};
+
+use crate::config::ResamplerQuality;
+
+/// Build sinc interpolation parameters for a quality preset
+fn interpolation_parameters(quality: &ResamplerQuality) -> SincInterpolationParameters {
+    match quality {
+        ResamplerQuality::Fast => SincInterpolationParameters {
+            sinc_len: 64,
+            f_cutoff: 0.91,
+            interpolation: SincInterpolationType::Linear,
+            oversampling_factor: 128,
+            window: WindowFunction::Hann2,
+        },
+        ResamplerQuality::Balanced => SincInterpolationParameters {
+            sinc_len: 128,
+            f_cutoff: 0.93,
+            interpolation: SincInterpolationType::Linear,
+            oversampling_factor: 256,
+            window: WindowFunction::BlackmanHarris2,
+        },
+        ResamplerQuality::High => SincInterpolationParameters {
+            sinc_len: 256,
+            f_cutoff: 0.95,
+            interpolation: SincInterpolationType::Cubic,
+            oversampling_factor: 256,
+            window: WindowFunction::BlackmanHarris2,
+        },
+    }
+}
+
+/// Streaming mono resampler for the capture path
+///
+/// Input arrives in arbitrarily sized chunks from the audio callback. Samples are
+/// accumulated until a full resampler chunk is available, so the added latency is
+/// bounded by the configured chunk duration.
+pub struct StreamResampler {
+    /// Underlying sinc resampler (None when rates already match)
+    resampler: Option<SincFixedIn<f32>>,
+
+    /// Samples waiting for a full chunk
+    input_buffer: Vec<f32>,
+
+    /// Input sample rate
+    from_rate: u32,
+
+    /// Output sample rate
+    to_rate: u32,
+}
+
+impl StreamResampler {
+    /// Create a new streaming resampler
+    ///
+    /// `chunk_ms` controls latency: smaller chunks reduce delay but cost more CPU per sample.
+    pub fn new(from_rate: u32, to_rate: u32, quality: &ResamplerQuality, chunk_ms: u32) -> Result<Self> {
+        let resampler = if from_rate == to_rate {
+            None
+        } else {
+            let chunk_size = ((from_rate as u64 * chunk_ms.max(1) as u64) / 1000).max(1) as usize;
+            let ratio = to_rate as f64 / from_rate as f64;
+
+            debug!(
+                "Creating {:?} resampler {} Hz -> {} Hz (chunk: {} samples)",
+                quality, from_rate, to_rate, chunk_size
+            );
+
+            Some(
+                SincFixedIn::<f32>::new(ratio, 1.0, interpolation_parameters(quality), chunk_size, 1)
+                    .map_err(|e| anyhow::anyhow!("Failed to create resampler: {}", e))?,
+            )
+        };
+
+        Ok(Self {
+            resampler,
+            input_buffer: Vec::new(),
+            from_rate,
+            to_rate,
+        })
+    }
+
+    /// Get the input sample rate
+    pub fn from_rate(&self) -> u32 {
+        self.from_rate
+    }
+
+    /// Get the output sample rate
+    pub fn to_rate(&self) -> u32 {
+        self.to_rate
+    }
+
+    /// Resample a chunk of mono samples, returning any output that is ready
+    pub fn process(&mut self, samples: &[f32]) -> Result<Vec<f32>> {
+        let resampler = match self.resampler.as_mut() {
+            Some(resampler) => resampler,
+            None => return Ok(samples.to_vec()),
+        };
+
+        self.input_buffer.extend_from_slice(samples);
+
+        let mut output = Vec::new();
+        loop {
+            let needed = resampler.input_frames_next();
+            if self.input_buffer.len() < needed {
+                break;
+            }
+
+            let chunk: Vec<f32> = self.input_buffer.drain(..needed).collect();
+            let resampled = resampler
+                .process(&[chunk], None)
+                .map_err(|e| anyhow::anyhow!("Resampling failed: {}", e))?;
+            output.extend_from_slice(&resampled[0]);
+        }
+
+        Ok(output)
+    }
+
+    /// Flush any buffered samples, padding the final chunk with silence
+    pub fn flush(&mut self) -> Result<Vec<f32>> {
+        if self.input_buffer.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let resampler = match self.resampler.as_mut() {
+            Some(resampler) => resampler,
+            None => return Ok(std::mem::take(&mut self.input_buffer)),
+        };
+
+        let remaining = self.input_buffer.len();
+        let expected = (remaining as u64 * self.to_rate as u64 / self.from_rate as u64) as usize;
+
+        let mut chunk = std::mem::take(&mut self.input_buffer);
+        chunk.resize(resampler.input_frames_next(), 0.0);
+
+        let resampled = resampler
+            .process(&[chunk], None)
+            .map_err(|e| anyhow::anyhow!("Resampling failed: {}", e))?;
+
+        let mut output = resampled.into_iter().next().unwrap_or_default();
+        output.truncate(expected);
+        Ok(output)
+    }
+
+    /// Discard buffered samples and reset the filter state
+    pub fn reset(&mut self) {
+        self.input_buffer.clear();
+        if let Some(resampler) = self.resampler.as_mut() {
+            resampler.reset();
+        }
+    }
+}
+
+/// Resample a complete mono buffer in one pass
+///
+/// The filter delay is compensated so the output lines up with the input.
+pub fn resample_once(samples: &[f32], from_rate: u32, to_rate: u32, quality: &ResamplerQuality) -> Result<Vec<f32>> {
+    if from_rate == to_rate || samples.is_empty() {
+        return Ok(samples.to_vec());
+    }
+
+    let params = interpolation_parameters(quality);
+    let padding = params.sinc_len;
+    let ratio = to_rate as f64 / from_rate as f64;
+    let expected = (samples.len() as f64 * ratio).round() as usize;
+
+    // Pad the tail so the filter delay does not swallow the last samples
+    let mut input = Vec::with_capacity(samples.len() + padding);
+    input.extend_from_slice(samples);
+    input.resize(samples.len() + padding, 0.0);
+
+    let mut resampler = SincFixedIn::<f32>::new(ratio, 1.0, params, input.len(), 1)
+        .map_err(|e| anyhow::anyhow!("Failed to create resampler: {}", e))?;
+    let delay = resampler.output_delay();
+
+    let resampled = resampler
+        .process(&[input], None)
+        .map_err(|e| anyhow::anyhow!("Resampling failed: {}", e))?;
+
+    Ok(resampled
+        .into_iter()
+        .next()
+        .unwrap_or_default()
+        .into_iter()
+        .skip(delay)
+        .take(expected)
+        .collect())
+}
@@ -0,0 +1,124 @@
+use anyhow::{Context, Result};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+
+use super::file::{transcribe_file, FileTranscriptionEvent};
+use crate::config::{ResamplerQuality, SpeechSettings};
+
+/// A transcript of a saved recording, one of possibly several made with different settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptVersion {
+    /// Version number, counting from 1 for each recording
+    pub version: u32,
+
+    /// Model the recording was transcribed with, e.g. "medium-q5_0"
+    pub model: String,
+
+    /// Language the model was told to expect ("auto" when detected)
+    pub language: String,
+
+    /// When the transcript was made (RFC 3339)
+    pub created: String,
+
+    /// The transcript
+    pub text: String,
+}
+
+/// File a transcript version of a recording is saved to, next to the recording
+pub fn version_path(recording: &Path, version: u32) -> PathBuf {
+    let stem = recording.file_stem().unwrap_or_default().to_string_lossy();
+    recording.with_file_name(format!("{}.v{}.json", stem, version))
+}
+
+/// Saved transcript versions of a recording, oldest first
+pub fn transcript_versions(recording: &Path) -> Result<Vec<TranscriptVersion>> {
+    let mut versions = Vec::new();
+
+    for version in 1.. {
+        let path = version_path(recording, version);
+        if !path.exists() {
+            break;
+        }
+
+        let json = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read transcript {}", path.display()))?;
+        let transcript = serde_json::from_str(&json)
+            .with_context(|| format!("Invalid transcript {}", path.display()))?;
+        versions.push(transcript);
+    }
+
+    Ok(versions)
+}
+
+/// Save a transcript of a recording as its next version
+pub fn save_version(recording: &Path, settings: &SpeechSettings, text: String) -> Result<TranscriptVersion> {
+    let version = (1..).find(|version| !version_path(recording, *version).exists()).unwrap_or(1);
+    let transcript = TranscriptVersion {
+        version,
+        model: settings.model_name(),
+        language: if settings.language.is_empty() { "auto".to_string() } else { settings.language.clone() },
+        created: chrono::Local::now().to_rfc3339(),
+        text,
+    };
+
+    let path = version_path(recording, version);
+    std::fs::write(&path, serde_json::to_string_pretty(&transcript)?)
+        .with_context(|| format!("Failed to save transcript {}", path.display()))?;
+
+    info!("Saved transcript version {} of {}", version, recording.display());
+    Ok(transcript)
+}
+
+/// Transcribe a saved recording again, e.g. with a larger model or another language
+///
+/// The recording goes through its own transcription manager, so live capture
+/// and transcription carry on undisturbed. Progress is reported on `progress`
+/// as for any file, and the transcript is saved as the recording's next version.
+pub async fn retranscribe_recording(
+    recording: &Path,
+    settings: SpeechSettings,
+    quality: &ResamplerQuality,
+    progress: Option<mpsc::Sender<FileTranscriptionEvent>>,
+) -> Result<TranscriptVersion> {
+    info!("Transcribing recording {} again with {}", recording.display(), settings.model_name());
+
+    let text = transcribe_file(recording, settings.clone(), quality, progress).await?;
+    save_version(recording, &settings, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_versions_are_numbered_per_recording() {
+        let dir = std::env::temp_dir().join(format!("bestme-retranscribe-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let recording = dir.join("recording_20240101_120000.wav");
+        let other = dir.join("recording_20240101_130000.wav");
+
+        let mut settings = Config::default().audio.speech;
+        assert_eq!(transcript_versions(&recording).unwrap(), Vec::new());
+
+        save_version(&recording, &settings, "first try".to_string()).unwrap();
+        settings.set_model_size_from_str("medium-q5_0").unwrap();
+        settings.language = "de".to_string();
+        let second = save_version(&recording, &settings, "second try".to_string()).unwrap();
+        save_version(&other, &settings, "another recording".to_string()).unwrap();
+
+        assert_eq!(second.version, 2);
+        assert_eq!(version_path(&recording, 2), dir.join("recording_20240101_120000.v2.json"));
+
+        let versions = transcript_versions(&recording).unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].text, "first try");
+        assert_eq!(versions[0].language, "auto");
+        assert_eq!(versions[1], second);
+        assert_eq!(versions[1].model, "medium-q5_0");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
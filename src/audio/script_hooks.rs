@@ -0,0 +1,237 @@
+use log::{debug, info, warn};
+use parking_lot::{Mutex, MutexGuard};
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+use super::pipeline::TextProcessor;
+use super::voice_commands::{CommandParameter, VoiceCommand};
+
+/// How often the script files are checked for changes
+const RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Operations one hook may run before it is stopped
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// A script file and what it compiled to
+struct HookScript {
+    path: PathBuf,
+
+    /// When the file was changed, as last read; `None` once it couldn't be read
+    modified: Option<SystemTime>,
+
+    /// Whether the file was read at all
+    loaded: bool,
+
+    /// The compiled script, unless it has mistakes
+    ast: Option<AST>,
+}
+
+impl HookScript {
+    /// Compile the script again if the file changed since it was read
+    fn reload(&mut self, engine: &Engine) {
+        let modified = std::fs::metadata(&self.path).and_then(|metadata| metadata.modified()).ok();
+        if self.loaded && modified == self.modified {
+            return;
+        }
+        self.loaded = true;
+        self.modified = modified;
+
+        let compiled = std::fs::read_to_string(&self.path)
+            .map_err(|e| e.to_string())
+            .and_then(|source| engine.compile(source).map_err(|e| e.to_string()));
+        self.ast = match compiled {
+            Ok(ast) => {
+                info!("Loaded hook script {}", self.path.display());
+                Some(ast)
+            }
+            Err(e) => {
+                warn!("Hook script {} left out: {}", self.path.display(), e);
+                None
+            }
+        };
+    }
+
+    /// The compiled script, if it defines `name` with `params` parameters
+    fn hook(&self, name: &str, params: usize) -> Option<&AST> {
+        self.ast.as_ref().filter(|ast| ast.iter_functions().any(|function| function.name == name && function.params.len() == params))
+    }
+}
+
+/// The scripts and when they were last checked for changes
+struct HookState {
+    scripts: Vec<HookScript>,
+    checked: Option<Instant>,
+}
+
+/// Rhai scripts changing what voice commands do and the final text
+///
+/// A script can define `on_command(command, text)`, called with each command
+/// heard, as a map of its `type`, `trigger`, `mode` and `parameters`, and the
+/// text being edited; returning `false` drops the command. `on_final_text(text)`
+/// gets the text of each final segment and returns it changed, as the
+/// post-processing step "scripts". Scripts have no access to files, programs
+/// or the network, and a hook running too long is stopped. Scripts are
+/// compiled again once their file changes, and ones with mistakes are left out.
+pub struct ScriptHooks {
+    engine: Engine,
+    state: Mutex<HookState>,
+}
+
+impl ScriptHooks {
+    /// Hooks from the scripts at `paths`, run in order
+    pub fn new(paths: &[String]) -> Self {
+        let scripts = paths.iter()
+            .map(|path| HookScript { path: PathBuf::from(path), modified: None, loaded: false, ast: None })
+            .collect();
+        Self {
+            engine: sandboxed_engine(),
+            state: Mutex::new(HookState { scripts, checked: None }),
+        }
+    }
+
+    /// The scripts, compiled again if they changed
+    fn scripts(&self) -> MutexGuard<'_, HookState> {
+        let mut state = self.state.lock();
+        if state.checked.map_or(true, |checked| checked.elapsed() >= RELOAD_INTERVAL) {
+            state.checked = Some(Instant::now());
+            for script in &mut state.scripts {
+                script.reload(&self.engine);
+            }
+        }
+        state
+    }
+
+    /// Whether `command` is carried out, as far as the `on_command` hooks go
+    pub fn on_command(&self, command: &VoiceCommand, text: &str) -> bool {
+        let state = self.scripts();
+        for ast in state.scripts.iter().filter_map(|script| script.hook("on_command", 2)) {
+            let arguments = (command_map(command), text.to_string());
+            match self.engine.call_fn::<Dynamic>(&mut Scope::new(), ast, "on_command", arguments) {
+                Ok(result) if result.as_bool() == Ok(false) => {
+                    debug!("Hook script dropped {:?}", command.command_type);
+                    return false;
+                }
+                Ok(_) => {}
+                Err(e) => warn!("on_command hook failed: {}", e),
+            }
+        }
+        true
+    }
+
+    /// `text` after the `on_final_text` hooks, each getting what the one before returned
+    pub fn on_final_text(&self, text: &str) -> String {
+        let state = self.scripts();
+        let mut text = text.to_string();
+        for ast in state.scripts.iter().filter_map(|script| script.hook("on_final_text", 1)) {
+            match self.engine.call_fn::<String>(&mut Scope::new(), ast, "on_final_text", (text.clone(),)) {
+                Ok(changed) => text = changed,
+                Err(e) => warn!("on_final_text hook failed: {}", e),
+            }
+        }
+        text
+    }
+}
+
+impl TextProcessor for ScriptHooks {
+    fn name(&self) -> &str {
+        "scripts"
+    }
+
+    fn process(&self, text: &str) -> String {
+        self.on_final_text(text)
+    }
+}
+
+/// An engine running scripts within limits, with `print` and `debug` going to the log
+fn sandboxed_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(32);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(1 << 20);
+    engine.set_max_array_size(10_000);
+    engine.set_max_map_size(10_000);
+    engine.disable_symbol("eval");
+    // `import` would otherwise load any script file by path
+    engine.set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new());
+    engine.on_print(|text| info!("Hook script: {}", text));
+    engine.on_debug(|text, _, position| debug!("Hook script at {}: {}", position, text));
+    engine
+}
+
+/// A command as scripts see it
+fn command_map(command: &VoiceCommand) -> Map {
+    let parameters: Map = command.parameters.iter()
+        .map(|(name, value)| {
+            let value = match value {
+                CommandParameter::Number(number) => Dynamic::from(*number as i64),
+                CommandParameter::Text(text) => Dynamic::from(text.clone()),
+            };
+            (name.as_str().into(), value)
+        })
+        .collect();
+
+    let mut map = Map::new();
+    map.insert("type".into(), format!("{:?}", command.command_type).into());
+    map.insert("trigger".into(), command.trigger_text.clone().into());
+    map.insert("mode".into(), format!("{:?}", command.mode).into());
+    map.insert("parameters".into(), parameters.into());
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::voice_commands::VoiceCommandType;
+
+    const SCRIPT: &str = r#"
+        fn on_command(command, text) {
+            // No deleting once the text is signed
+            !(command.type == "Delete" && text.ends_with("Alex") && command.parameters.count > 1)
+        }
+
+        fn on_final_text(text) {
+            text.replace("teh", "the");
+            text
+        }
+    "#;
+
+    #[test]
+    fn test_runs_hooks_and_reloads() {
+        let path = std::env::temp_dir().join(format!("bestme-hooks-{}.rhai", std::process::id()));
+        std::fs::write(&path, SCRIPT).unwrap();
+        let hooks = ScriptHooks::new(&[path.to_string_lossy().into_owned()]);
+
+        let delete = VoiceCommand::new(VoiceCommandType::Delete, "delete last three words")
+            .with_parameter("count", CommandParameter::Number(3));
+        assert!(!hooks.on_command(&delete, "Best, Alex"));
+        assert!(hooks.on_command(&delete, "Best,"));
+        assert!(hooks.on_command(&VoiceCommand::new(VoiceCommandType::Undo, "undo"), "Best, Alex"));
+        assert_eq!(hooks.on_final_text("teh end"), "the end");
+
+        // Changed scripts are compiled again; one that never ends is stopped and the text left as it was
+        std::fs::write(&path, "fn on_final_text(text) { loop {} }").unwrap();
+        // The rewrite may land within the same modification time tick, so the old one is made older
+        let mut state = hooks.state.lock();
+        state.scripts[0].modified = Some(SystemTime::UNIX_EPOCH);
+        state.checked = None;
+        drop(state);
+        assert_eq!(hooks.process("teh end"), "teh end");
+        assert!(hooks.on_command(&delete, "Best, Alex"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_scripts_cannot_import_files() {
+        let path = std::env::temp_dir().join(format!("bestme-module-{}.rhai", std::process::id()));
+        std::fs::write(&path, "export const SECRET = 42;").unwrap();
+        let script = format!("import {:?} as secrets; secrets::SECRET", path.to_string_lossy());
+
+        assert_eq!(Engine::new().eval::<i64>(&script).unwrap(), 42);
+        assert!(sandboxed_engine().eval::<i64>(&script).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
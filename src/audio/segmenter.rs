@@ -0,0 +1,115 @@
+use super::vad::VoiceActivityDetector;
+use crate::config::SegmentationSettings;
+
+/// Cuts a stream into segments at pauses in speech
+///
+/// A segment ends once speech has been heard and the voice activity detector
+/// reports a pause, as long as the segment is at least the minimum length. It
+/// is also cut at the maximum length, so long monologues and constant noise
+/// are still transcribed in reasonably sized pieces.
+#[derive(Debug, Clone)]
+pub struct SpeechSegmenter {
+    /// End-pointing detector; its hangover is the pause that ends a segment
+    vad: VoiceActivityDetector,
+
+    /// Shortest segment that a pause can end
+    min_samples: usize,
+
+    /// Longest segment before it is cut regardless of speech
+    max_samples: usize,
+
+    /// Samples in the current segment
+    samples: usize,
+
+    /// Whether the current segment contains speech
+    heard_speech: bool,
+}
+
+impl SpeechSegmenter {
+    /// Create a segmenter for a mono stream
+    pub fn new(sample_rate: u32, settings: &SegmentationSettings) -> Self {
+        let min_samples = (settings.min_duration_secs * sample_rate as f32) as usize;
+
+        Self {
+            vad: VoiceActivityDetector::new(sample_rate, 1, settings.sensitivity, settings.pause_ms),
+            min_samples,
+            max_samples: ((settings.max_duration_secs * sample_rate as f32) as usize).max(min_samples),
+            samples: 0,
+            heard_speech: false,
+        }
+    }
+
+    /// Add audio to the current segment; returns true when the segment should be transcribed
+    ///
+    /// The next segment starts right after the audio that completed this one.
+    pub fn push(&mut self, samples: &[f32]) -> bool {
+        self.samples += samples.len();
+        self.vad.process(samples);
+        self.heard_speech |= self.vad.is_speaking();
+
+        let paused = self.heard_speech && !self.vad.is_speaking();
+        let complete = self.samples >= self.max_samples || (paused && self.samples >= self.min_samples);
+        if complete {
+            self.samples = 0;
+            self.heard_speech = self.vad.is_speaking();
+        }
+
+        complete
+    }
+
+    /// Start a new segment, e.g. after the stream was flushed
+    pub fn reset(&mut self) {
+        self.vad.reset();
+        self.samples = 0;
+        self.heard_speech = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(len: usize, amplitude: f32) -> Vec<f32> {
+        (0..len)
+            .map(|i| (i as f32 * 0.1).sin() * amplitude)
+            .collect()
+    }
+
+    fn settings() -> SegmentationSettings {
+        SegmentationSettings {
+            adaptive: true,
+            min_duration_secs: 1.0,
+            max_duration_secs: 4.0,
+            pause_ms: 200,
+            sensitivity: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_segments_end_at_pauses() {
+        let mut segmenter = SpeechSegmenter::new(16000, &settings());
+        assert!(!segmenter.push(&tone(8000, 0.001)));
+        assert!(!segmenter.push(&tone(16000, 0.5)));
+
+        // A pause after enough speech ends the segment
+        assert!(segmenter.push(&vec![0.0; 8000]));
+
+        // A pause in a segment shorter than the minimum doesn't
+        assert!(!segmenter.push(&tone(3200, 0.5)));
+        assert!(!segmenter.push(&vec![0.0; 6400]));
+        assert!(segmenter.push(&vec![0.0; 8000]));
+    }
+
+    #[test]
+    fn test_long_segments_are_cut() {
+        let mut segmenter = SpeechSegmenter::new(16000, &settings());
+
+        // Silence never pauses speech, so it is cut at the maximum length
+        assert!(!segmenter.push(&vec![0.0; 48000]));
+        assert!(segmenter.push(&vec![0.0; 16000]));
+
+        // As is speech without pauses
+        assert!(!segmenter.push(&tone(48000, 0.5)));
+        assert!(segmenter.push(&tone(16000, 0.5)));
+    }
+}
@@ -0,0 +1,278 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Local};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::SpeechSettings;
+
+/// A stretch of a session's transcript
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionSegment {
+    /// Start of the speech, in milliseconds from the start of the session's audio
+    pub start_ms: u64,
+
+    /// End of the speech, in milliseconds from the start of the session's audio
+    pub end_ms: u64,
+
+    /// Who said it, when audio was captured as per-speaker tracks
+    pub speaker: Option<String>,
+
+    /// The text as it appears in the transcript, including any line or paragraph break before it
+    pub text: String,
+
+    /// The engine's confidence in the text (0.0 - 1.0), if it reports one
+    pub confidence: Option<f32>,
+}
+
+/// One run of transcription, from start to stop, with the settings it was made with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    /// Unique ID, derived from the start time
+    pub id: String,
+
+    /// Name given to the session by the user
+    pub title: Option<String>,
+
+    /// When transcription started
+    pub started: DateTime<Local>,
+
+    /// When transcription stopped, while the session is still running None
+    pub ended: Option<DateTime<Local>>,
+
+    /// Input device the audio came from (None for the default device)
+    pub device: Option<String>,
+
+    /// Model the session was transcribed with, e.g. "small-q8_0"
+    pub model: String,
+
+    /// Language the model was told to expect ("auto" when detected)
+    pub language: String,
+
+    /// The transcript, in the order it was written
+    pub segments: Vec<SessionSegment>,
+}
+
+impl Session {
+    /// Start a session transcribed with `settings` from `device`
+    pub fn new(settings: &SpeechSettings, device: Option<String>) -> Self {
+        let started = Local::now();
+        Self {
+            id: started.format("%Y%m%d_%H%M%S_%3f").to_string(),
+            title: None,
+            started,
+            ended: None,
+            device,
+            model: settings.model_name(),
+            language: if settings.language.is_empty() { "auto".to_string() } else { settings.language.clone() },
+            segments: Vec::new(),
+        }
+    }
+
+    /// Add text transcribed from `start` to `end` into the session's audio
+    pub fn push(&mut self, start: Duration, end: Duration, speaker: Option<String>, text: String, confidence: Option<f32>) {
+        self.segments.push(SessionSegment {
+            start_ms: start.as_millis() as u64,
+            end_ms: end.as_millis() as u64,
+            speaker,
+            text,
+            confidence,
+        });
+    }
+
+    /// Mark the session as stopped now
+    pub fn end(&mut self) {
+        self.ended.get_or_insert_with(Local::now);
+    }
+
+    /// The whole transcript, segments separated by a space unless they start with a break
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for segment in &self.segments {
+            if text.is_empty() {
+                text.push_str(segment.text.trim_start());
+                continue;
+            }
+            if !segment.text.starts_with(char::is_whitespace) && !text.ends_with(char::is_whitespace) {
+                text.push(' ');
+            }
+            text.push_str(&segment.text);
+        }
+        text
+    }
+
+    /// Overview of the session for listing
+    pub fn summary(&self) -> SessionSummary {
+        SessionSummary {
+            id: self.id.clone(),
+            title: self.title.clone(),
+            started: self.started,
+            ended: self.ended,
+            device: self.device.clone(),
+            model: self.model.clone(),
+            language: self.language.clone(),
+            segments: self.segments.len(),
+        }
+    }
+}
+
+/// A session without its transcript
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SessionSummary {
+    pub id: String,
+    pub title: Option<String>,
+    pub started: DateTime<Local>,
+    pub ended: Option<DateTime<Local>>,
+    pub device: Option<String>,
+    pub model: String,
+    pub language: String,
+
+    /// Number of segments in the transcript
+    pub segments: usize,
+}
+
+/// Sessions saved as one JSON file each in a directory
+#[derive(Debug, Clone)]
+pub struct SessionStore {
+    dir: PathBuf,
+}
+
+impl SessionStore {
+    /// Store sessions in `dir`, created when the first session is saved
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Sessions directory in the application data folder
+    pub fn default_dir() -> Result<PathBuf> {
+        let project_dirs = directories::ProjectDirs::from("com", "bestme", "BestMe")
+            .context("Failed to determine project directories")?;
+        Ok(project_dirs.data_dir().join("sessions"))
+    }
+
+    /// File a session is saved to, refusing IDs that could point outside the directory
+    fn path(&self, id: &str) -> Result<PathBuf> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(anyhow!("Invalid session ID: {:?}", id));
+        }
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+
+    /// Save a session, replacing any earlier save of it
+    pub fn save(&self, session: &Session) -> Result<()> {
+        let path = self.path(&session.id)?;
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create sessions directory {}", self.dir.display()))?;
+        std::fs::write(&path, serde_json::to_string_pretty(session)?)
+            .with_context(|| format!("Failed to save session {}", path.display()))
+    }
+
+    /// Load a saved session
+    pub fn load(&self, id: &str) -> Result<Session> {
+        read_session(&self.path(id)?)
+    }
+
+    /// Saved sessions, newest first; files that can't be read are skipped
+    pub fn list(&self) -> Result<Vec<SessionSummary>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut sessions = Vec::new();
+        for entry in std::fs::read_dir(&self.dir).with_context(|| format!("Failed to read sessions directory {}", self.dir.display()))? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+
+            match read_session(&path) {
+                Ok(session) => sessions.push(session.summary()),
+                Err(e) => warn!("Skipping session: {:#}", e),
+            }
+        }
+
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.started));
+        Ok(sessions)
+    }
+
+    /// Give a saved session a title, or remove it with None
+    pub fn rename(&self, id: &str, title: Option<String>) -> Result<Session> {
+        let mut session = self.load(id)?;
+        session.title = title.filter(|title| !title.trim().is_empty());
+        self.save(&session)?;
+        Ok(session)
+    }
+
+    /// Delete a saved session
+    pub fn delete(&self, id: &str) -> Result<()> {
+        let path = self.path(id)?;
+        std::fs::remove_file(&path).with_context(|| format!("Failed to delete session {}", path.display()))
+    }
+}
+
+fn read_session(path: &Path) -> Result<Session> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read session {}", path.display()))?;
+    serde_json::from_str(&json).with_context(|| format!("Invalid session {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn session(id: &str) -> Session {
+        let mut session = Session::new(&Config::default().audio.speech, Some("Microphone".to_string()));
+        session.id = id.to_string();
+        session
+    }
+
+    #[test]
+    fn test_transcript_text() {
+        let mut session = session("text");
+        assert_eq!(session.text(), "");
+
+        session.push(Duration::ZERO, Duration::from_millis(1500), None, "Hello there.".to_string(), Some(0.9));
+        session.push(Duration::from_secs(2), Duration::from_secs(3), None, "How are you?".to_string(), None);
+        session.push(Duration::from_secs(8), Duration::from_secs(9), None, "\n\nNew topic.".to_string(), None);
+        assert_eq!(session.text(), "Hello there. How are you?\n\nNew topic.");
+        assert_eq!(session.segments[0].end_ms, 1500);
+
+        session.end();
+        let ended = session.ended;
+        session.end();
+        assert!(ended.is_some());
+        assert_eq!(session.ended, ended);
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let dir = std::env::temp_dir().join(format!("bestme-sessions-{}", std::process::id()));
+        let store = SessionStore::new(dir.clone());
+        assert_eq!(store.list().unwrap(), Vec::new());
+
+        let mut first = session("first");
+        first.push(Duration::ZERO, Duration::from_secs(1), Some("Me".to_string()), "Me: hi".to_string(), None);
+        first.end();
+        let mut second = session("second");
+        second.started = first.started + chrono::Duration::minutes(5);
+        store.save(&first).unwrap();
+        store.save(&second).unwrap();
+
+        assert_eq!(store.load("first").unwrap(), first);
+        let listed: Vec<String> = store.list().unwrap().into_iter().map(|summary| summary.id).collect();
+        assert_eq!(listed, ["second", "first"]);
+
+        let renamed = store.rename("first", Some("Standup".to_string())).unwrap();
+        assert_eq!(store.load("first").unwrap().title.as_deref(), Some("Standup"));
+        assert_eq!(renamed.segments, first.segments);
+
+        store.delete("second").unwrap();
+        assert!(store.load("second").is_err());
+        assert!(store.load("../first").is_err());
+        assert_eq!(store.list().unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
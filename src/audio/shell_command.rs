@@ -0,0 +1,230 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use super::voice_commands::CommandParameter;
+
+/// A program voice commands can run, set up ahead of time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShellCommand {
+    /// Name scripts run it by, e.g. `run("deploy")`
+    pub name: String,
+
+    /// Program or script to run; it is started directly, never through a shell
+    pub command: String,
+
+    /// Arguments given to the program; `{name}` is replaced with the spoken parameter `name`
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Directory it runs in, or the application's own; a leading `~` is the home directory
+    #[serde(default)]
+    pub working_dir: Option<String>,
+
+    /// Seconds it may run before it is stopped
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+/// How a shell command ended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShellCommandOutput {
+    /// Name of the command that ran
+    pub name: String,
+
+    /// Exit code, if it exited rather than being stopped by a signal
+    pub status: Option<i32>,
+
+    /// Whether it exited successfully
+    pub success: bool,
+
+    /// What it wrote to standard output
+    pub stdout: String,
+
+    /// What it wrote to standard error
+    pub stderr: String,
+}
+
+/// Settings for voice commands running programs
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShellCommandSettings {
+    /// Whether `run("name")` in the command script runs anything
+    pub enabled: bool,
+
+    /// The only programs that can be run, by name
+    pub commands: Vec<ShellCommand>,
+}
+
+impl ShellCommandSettings {
+    /// The command called `name`, without regard to letter case
+    pub fn find(&self, name: &str) -> Option<&ShellCommand> {
+        let name = name.trim();
+        self.commands.iter().find(|command| command.name.trim().eq_ignore_ascii_case(name))
+    }
+}
+
+impl ShellCommand {
+    /// The arguments with the placeholders filled in from `parameters`
+    ///
+    /// Each spoken value stays within the argument it was put in, so nothing
+    /// said can add arguments or be read as shell syntax.
+    pub fn arguments(&self, parameters: &BTreeMap<String, CommandParameter>) -> Result<Vec<String>> {
+        self.args.iter().map(|arg| fill(arg, parameters)).collect()
+    }
+
+    /// The directory it runs in, with a leading `~` expanded to the home directory
+    ///
+    /// Commands are started without a shell, so nothing else would expand it.
+    pub fn working_dir(&self) -> Option<PathBuf> {
+        let dir = self.working_dir.as_deref()?;
+        let home = || directories::BaseDirs::new().map(|dirs| dirs.home_dir().to_path_buf());
+        let expanded = match dir.strip_prefix('~') {
+            Some("") => home(),
+            Some(rest) if rest.starts_with(['/', std::path::MAIN_SEPARATOR]) => {
+                home().map(|home| home.join(&rest[1..]))
+            }
+            _ => None,
+        };
+        Some(expanded.unwrap_or_else(|| PathBuf::from(dir)))
+    }
+
+    /// Run it with `parameters`, waiting until it exits, its time is up or `stop` is set
+    pub fn run(&self, parameters: &BTreeMap<String, CommandParameter>, stop: &AtomicBool) -> Result<ShellCommandOutput> {
+        let mut program = Command::new(&self.command);
+        program.args(self.arguments(parameters)?)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(dir) = self.working_dir() {
+            program.current_dir(dir);
+        }
+        let mut child = program.spawn()
+            .with_context(|| format!("Failed to run {} ({})", self.name, self.command))?;
+
+        // Read while it runs, so it doesn't stall on a full pipe
+        let stdout = child.stdout.take().map(read_to_end);
+        let stderr = child.stderr.take().map(read_to_end);
+
+        let deadline = Instant::now() + Duration::from_secs(self.timeout_secs);
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                anyhow::bail!("{} was stopped after {} seconds", self.name, self.timeout_secs);
+            }
+            if stop.load(Ordering::SeqCst) {
+                let _ = child.kill();
+                let _ = child.wait();
+                anyhow::bail!("{} was cancelled", self.name);
+            }
+            thread::sleep(Duration::from_millis(20));
+        };
+
+        let collect = |reader: Option<JoinHandle<String>>| reader.and_then(|reader| reader.join().ok()).unwrap_or_default();
+        Ok(ShellCommandOutput {
+            name: self.name.clone(),
+            status: status.code(),
+            success: status.success(),
+            stdout: collect(stdout),
+            stderr: collect(stderr),
+        })
+    }
+}
+
+/// Read all of `pipe` on another thread
+fn read_to_end(mut pipe: impl Read + Send + 'static) -> JoinHandle<String> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        let _ = pipe.read_to_end(&mut bytes);
+        String::from_utf8_lossy(&bytes).into_owned()
+    })
+}
+
+/// `arg` with each `{name}` replaced by the parameter `name`
+fn fill(arg: &str, parameters: &BTreeMap<String, CommandParameter>) -> Result<String> {
+    let mut filled = String::new();
+    let mut rest = arg;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..].find('}').ok_or_else(|| anyhow!("unclosed {{ in argument {:?}", arg))? + start;
+        let name = &rest[start + 1..end];
+        let value = match parameters.get(name) {
+            Some(CommandParameter::Number(number)) => number.to_string(),
+            Some(CommandParameter::Text(text)) => text.clone(),
+            None => return Err(anyhow!("no parameter {} was said for argument {:?}", name, arg)),
+        };
+        filled.push_str(&rest[..start]);
+        filled.push_str(&value);
+        rest = &rest[end + 1..];
+    }
+    filled.push_str(rest);
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fills_arguments_from_parameters() {
+        let command = ShellCommand {
+            name: "Deploy".to_string(),
+            command: "./deploy.sh".to_string(),
+            args: vec!["--branch={branch}".to_string(), "{count}".to_string(), "-v".to_string()],
+            working_dir: None,
+            timeout_secs: default_timeout_secs(),
+        };
+        let settings = ShellCommandSettings { enabled: true, commands: vec![command.clone()] };
+        assert_eq!(settings.find(" deploy"), Some(&command));
+        assert!(settings.find("deploy it").is_none());
+
+        let mut parameters = BTreeMap::new();
+        parameters.insert("branch".to_string(), CommandParameter::Text("main; rm -rf ~".to_string()));
+        parameters.insert("count".to_string(), CommandParameter::Number(3));
+        assert_eq!(command.arguments(&parameters).unwrap(), ["--branch=main; rm -rf ~", "3", "-v"]);
+
+        parameters.remove("count");
+        assert_eq!(
+            command.arguments(&parameters).unwrap_err().to_string(),
+            "no parameter count was said for argument \"{count}\""
+        );
+    }
+
+    #[test]
+    fn test_expands_home_in_working_dir() {
+        let mut command = ShellCommand {
+            name: "Build".to_string(),
+            command: "make".to_string(),
+            args: Vec::new(),
+            working_dir: Some("~/projects/site".to_string()),
+            timeout_secs: default_timeout_secs(),
+        };
+        let home = directories::BaseDirs::new().unwrap().home_dir().to_path_buf();
+        assert_eq!(command.working_dir(), Some(home.join("projects/site")));
+
+        command.working_dir = Some("~".to_string());
+        assert_eq!(command.working_dir(), Some(home));
+
+        // Other users' homes and paths without a tilde are left alone
+        command.working_dir = Some("~alice/site".to_string());
+        assert_eq!(command.working_dir(), Some(PathBuf::from("~alice/site")));
+        command.working_dir = Some("/srv/site".to_string());
+        assert_eq!(command.working_dir(), Some(PathBuf::from("/srv/site")));
+
+        command.working_dir = None;
+        assert_eq!(command.working_dir(), None);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::config::SilenceSettings;
+
+/// Length of a single analysis frame in milliseconds
+const FRAME_MS: u32 = 20;
+
+/// Level reported for digital silence
+const SILENCE_DB: f32 = -100.0;
+
+/// Outcome of trimming a buffer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trimmed<'a> {
+    /// Audio from the first to the last frame above the threshold, plus padding
+    Speech {
+        /// Index of the first kept sample in the original buffer
+        start: usize,
+
+        /// The kept samples
+        samples: &'a [f32],
+    },
+
+    /// No frame reached the threshold; `level_db` is the loudest frame
+    Silence { level_db: f32 },
+}
+
+/// Trims silent edges from mono buffers before they are sent to Whisper
+///
+/// Energy is measured per frame rather than over the whole buffer, so a
+/// single short word in an otherwise quiet buffer still counts as speech.
+#[derive(Debug, Clone)]
+pub struct SilenceTrimmer {
+    /// Samples per analysis frame
+    frame_len: usize,
+
+    /// Samples kept before the first and after the last voiced frame
+    padding: usize,
+
+    /// Frame level below which audio counts as silence (dBFS)
+    threshold_db: f32,
+}
+
+impl SilenceTrimmer {
+    /// Create a trimmer for mono audio at `sample_rate`
+    pub fn new(sample_rate: u32, settings: &SilenceSettings) -> Self {
+        Self {
+            frame_len: (sample_rate * FRAME_MS / 1000).max(1) as usize,
+            padding: (sample_rate as u64 * settings.padding_ms as u64 / 1000) as usize,
+            threshold_db: settings.threshold_db,
+        }
+    }
+
+    /// Trim leading and trailing silence from `samples`
+    pub fn trim<'a>(&self, samples: &'a [f32]) -> Trimmed<'a> {
+        let mut loudest = SILENCE_DB;
+        let mut first_voiced = None;
+        let mut last_voiced = 0;
+
+        for (index, frame) in samples.chunks(self.frame_len).enumerate() {
+            let level = frame_db(frame);
+            loudest = loudest.max(level);
+
+            if level >= self.threshold_db {
+                first_voiced.get_or_insert(index);
+                last_voiced = index;
+            }
+        }
+
+        match first_voiced {
+            Some(first) => {
+                let start = (first * self.frame_len).saturating_sub(self.padding);
+                let end = ((last_voiced + 1) * self.frame_len + self.padding).min(samples.len());
+                Trimmed::Speech { start, samples: &samples[start..end] }
+            }
+            None => Trimmed::Silence { level_db: loudest },
+        }
+    }
+}
+
+/// RMS level of a frame in dBFS
+fn frame_db(frame: &[f32]) -> f32 {
+    if frame.is_empty() {
+        return SILENCE_DB;
+    }
+
+    let mean_square = frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32;
+    if mean_square > 0.0 {
+        (10.0 * mean_square.log10()).max(SILENCE_DB)
+    } else {
+        SILENCE_DB
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trimmer() -> SilenceTrimmer {
+        SilenceTrimmer::new(16000, &SilenceSettings {
+            enabled: true,
+            threshold_db: -50.0,
+            padding_ms: 100,
+        })
+    }
+
+    fn tone(len: usize, amplitude: f32) -> Vec<f32> {
+        (0..len).map(|i| (i as f32 * 0.1).sin() * amplitude).collect()
+    }
+
+    #[test]
+    fn test_trims_silent_edges_with_padding() {
+        // 1 s of silence, 0.5 s of speech, 1 s of silence
+        let mut samples = vec![0.0; 16000];
+        samples.extend(tone(8000, 0.3));
+        samples.extend(vec![0.0; 16000]);
+
+        let Trimmed::Speech { start, samples: speech } = trimmer().trim(&samples) else {
+            panic!("speech was not detected");
+        };
+
+        // 100 ms of padding on each side
+        assert_eq!(start, 16000 - 1600);
+        assert_eq!(speech.len(), 8000 + 2 * 1600);
+    }
+
+    #[test]
+    fn test_quiet_buffer_is_silence() {
+        let samples = tone(48000, 0.001);
+
+        match trimmer().trim(&samples) {
+            Trimmed::Silence { level_db } => assert!(level_db < -50.0 && level_db > -70.0),
+            Trimmed::Speech { .. } => panic!("low-level noise was treated as speech"),
+        }
+        assert_eq!(trimmer().trim(&[]), Trimmed::Silence { level_db: SILENCE_DB });
+    }
+
+    #[test]
+    fn test_padding_is_clamped_to_buffer() {
+        let mut samples = tone(800, 0.3);
+        samples.extend(vec![0.0; 800]);
+
+        assert_eq!(trimmer().trim(&samples), Trimmed::Speech { start: 0, samples: &samples[..] });
+    }
+}
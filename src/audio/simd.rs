@@ -0,0 +1,129 @@
+use wide::f32x8;
+
+/// Samples processed per vector
+const LANES: usize = 8;
+
+/// Load eight samples into a vector
+#[inline]
+fn load(chunk: &[f32]) -> f32x8 {
+    f32x8::from(<[f32; LANES]>::try_from(chunk).expect("chunk of eight samples"))
+}
+
+/// Largest absolute sample value
+pub fn peak(samples: &[f32]) -> f32 {
+    let chunks = samples.chunks_exact(LANES);
+    let tail = chunks.remainder();
+
+    let mut max = f32x8::ZERO;
+    for chunk in chunks {
+        max = max.max(load(chunk).abs());
+    }
+
+    let vector_peak = max.to_array().into_iter().fold(0.0f32, f32::max);
+    tail.iter().fold(vector_peak, |peak, sample| peak.max(sample.abs()))
+}
+
+/// Multiply samples by `gain`, appending them to `output`
+///
+/// Returns the peak of the scaled samples so callers can skip limiting when
+/// nothing comes close to full scale.
+pub fn scale(samples: &[f32], gain: f32, output: &mut Vec<f32>) -> f32 {
+    output.reserve(samples.len());
+
+    let chunks = samples.chunks_exact(LANES);
+    let tail = chunks.remainder();
+
+    let gain_vector = f32x8::splat(gain);
+    let mut max = f32x8::ZERO;
+    for chunk in chunks {
+        let scaled = load(chunk) * gain_vector;
+        max = max.max(scaled.abs());
+        output.extend_from_slice(&scaled.to_array());
+    }
+
+    let mut peak = max.to_array().into_iter().fold(0.0f32, f32::max);
+    for &sample in tail {
+        let scaled = sample * gain;
+        peak = peak.max(scaled.abs());
+        output.push(scaled);
+    }
+
+    peak
+}
+
+/// Downmix interleaved samples to mono by averaging channels
+pub fn downmix(samples: &[f32], channels: u16) -> Vec<f32> {
+    match channels.max(1) {
+        1 => samples.to_vec(),
+        2 => downmix_stereo(samples),
+        channels => {
+            let channels = channels as usize;
+            samples
+                .chunks_exact(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+                .collect()
+        }
+    }
+}
+
+/// Stereo is by far the most common layout, so it gets a vectorized path
+fn downmix_stereo(samples: &[f32]) -> Vec<f32> {
+    let mut mono = Vec::with_capacity(samples.len() / 2);
+
+    let chunks = samples.chunks_exact(2 * LANES);
+    let tail = chunks.remainder();
+
+    let half = f32x8::splat(0.5);
+    for chunk in chunks {
+        let left = f32x8::from(std::array::from_fn::<f32, LANES, _>(|i| chunk[2 * i]));
+        let right = f32x8::from(std::array::from_fn::<f32, LANES, _>(|i| chunk[2 * i + 1]));
+        mono.extend_from_slice(&((left + right) * half).to_array());
+    }
+
+    mono.extend(tail.chunks_exact(2).map(|frame| (frame[0] + frame[1]) * 0.5));
+    mono
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signal(len: usize) -> Vec<f32> {
+        (0..len).map(|i| (i as f32 * 0.37).sin() * 0.8).collect()
+    }
+
+    #[test]
+    fn test_peak_matches_scalar() {
+        // 8 full vectors plus a 5-sample tail, with the peak in the tail
+        let mut samples = signal(69);
+        samples[67] = -0.95;
+
+        assert_eq!(peak(&samples), 0.95);
+        assert_eq!(peak(&samples[..64]), samples[..64].iter().fold(0.0f32, |p, s| p.max(s.abs())));
+        assert_eq!(peak(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_scale_returns_scaled_peak() {
+        let samples = signal(21);
+        let mut output = Vec::new();
+
+        let scaled_peak = scale(&samples, 1.5, &mut output);
+
+        assert_eq!(output.len(), 21);
+        for (scaled, sample) in output.iter().zip(&samples) {
+            assert_eq!(*scaled, sample * 1.5);
+        }
+        assert_eq!(scaled_peak, peak(&output));
+    }
+
+    #[test]
+    fn test_stereo_downmix_matches_scalar() {
+        let samples = signal(2 * 19);
+
+        let expected: Vec<f32> = samples.chunks_exact(2).map(|frame| frame.iter().sum::<f32>() / 2.0).collect();
+        assert_eq!(downmix(&samples, 2), expected);
+        assert_eq!(downmix(&samples, 1), samples);
+        assert_eq!(downmix(&[0.25, 0.5, 0.75], 3), vec![0.5]);
+    }
+}
@@ -0,0 +1,364 @@
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use std::any::Any;
+use std::fmt;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use super::AudioConfig;
+
+/// Length of the blocks a file source delivers in milliseconds
+const FILE_BLOCK_MS: u32 = 10;
+
+/// Receives interleaved samples from a running source
+pub type DataCallback = Box<dyn FnMut(&[f32]) + Send + 'static>;
+
+/// Receives errors from a running source
+pub type ErrorCallback = Box<dyn FnMut(SourceError) + Send + 'static>;
+
+/// Errors reported by a running audio source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceError {
+    /// The device disappeared (e.g. it was unplugged)
+    DeviceLost,
+
+    /// Any other stream failure; the stream should be rebuilt
+    Stream(String),
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceError::DeviceLost => write!(f, "device is no longer available"),
+            SourceError::Stream(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// Format of the audio a source delivers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// Keeps a started source running; the source stops when the handle is dropped
+pub struct SourceHandle {
+    _inner: Box<dyn Any>,
+}
+
+impl SourceHandle {
+    /// Wrap whatever keeps the source alive (a stream, a thread guard, ...)
+    pub fn new<T: 'static>(inner: T) -> Self {
+        Self { _inner: Box::new(inner) }
+    }
+}
+
+/// Where `CaptureManager` gets its main audio from
+///
+/// The default is the cpal device selected in the configuration; tests and
+/// headless builds can substitute a `FileSource` to get deterministic audio.
+pub trait AudioSource {
+    /// Name used in logs
+    fn name(&self) -> String;
+
+    /// Resolve the source and the format it will deliver
+    fn prepare(&mut self, config: &AudioConfig) -> Result<SourceFormat>;
+
+    /// Start delivering audio from the prepared source
+    ///
+    /// `on_data` may be called from a real-time thread and must not block.
+    fn start(&mut self, on_data: DataCallback, on_error: ErrorCallback) -> Result<SourceHandle>;
+}
+
+/// Replays audio from memory, a WAV file or a generated signal
+///
+/// Audio is delivered in 10 ms blocks on a background thread, paced at
+/// `speed` times real time.
+#[derive(Debug, Clone)]
+pub struct FileSource {
+    name: String,
+    samples: Arc<Vec<f32>>,
+    format: SourceFormat,
+    speed: f32,
+    looping: bool,
+}
+
+impl FileSource {
+    /// Replay interleaved samples
+    pub fn from_samples(samples: Vec<f32>, sample_rate: u32, channels: u16) -> Self {
+        Self {
+            name: "memory".to_string(),
+            samples: Arc::new(samples),
+            format: SourceFormat {
+                sample_rate: sample_rate.max(1),
+                channels: channels.max(1),
+            },
+            speed: 1.0,
+            looping: false,
+        }
+    }
+
+    /// Replay a WAV file, keeping its sample rate and channel layout
+    pub fn from_wav(path: &Path) -> Result<Self> {
+        let mut reader = hound::WavReader::open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        let spec = reader.spec();
+
+        let samples = match spec.sample_format {
+            hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>()?,
+            hound::SampleFormat::Int => {
+                let scale = 1.0 / (1i64 << (spec.bits_per_sample.max(1) - 1)) as f32;
+                reader.samples::<i32>()
+                    .map(|sample| sample.map(|s| s as f32 * scale))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+        };
+
+        info!(
+            "Loaded {} ({} Hz, {} channels, {} samples)",
+            path.display(),
+            spec.sample_rate,
+            spec.channels,
+            samples.len()
+        );
+
+        let mut source = Self::from_samples(samples, spec.sample_rate, spec.channels);
+        source.name = path.display().to_string();
+        Ok(source)
+    }
+
+    /// Generate a mono sine tone
+    pub fn sine(frequency: f32, amplitude: f32, sample_rate: u32, duration_ms: u32) -> Self {
+        let frames = sample_rate as usize * duration_ms as usize / 1000;
+        let samples = (0..frames)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * frequency * i as f32 / sample_rate as f32).sin())
+            .collect();
+
+        let mut source = Self::from_samples(samples, sample_rate, 1);
+        source.name = format!("sine {} Hz", frequency);
+        source
+    }
+
+    /// Replay at `speed` times real time (1.0 is real time)
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed.max(0.01);
+        self
+    }
+
+    /// Start again from the beginning when the end is reached
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Length of the audio at real-time speed
+    pub fn duration(&self) -> Duration {
+        let frames = self.samples.len() / self.format.channels as usize;
+        Duration::from_secs_f64(frames as f64 / self.format.sample_rate as f64)
+    }
+}
+
+impl AudioSource for FileSource {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn prepare(&mut self, config: &AudioConfig) -> Result<SourceFormat> {
+        if config.sample_rate != self.format.sample_rate {
+            debug!(
+                "{} is {} Hz, configured rate {} Hz is ignored",
+                self.name, self.format.sample_rate, config.sample_rate
+            );
+        }
+
+        Ok(self.format)
+    }
+
+    fn start(&mut self, mut on_data: DataCallback, _on_error: ErrorCallback) -> Result<SourceHandle> {
+        let samples = self.samples.clone();
+        let looping = self.looping;
+        let channels = self.format.channels as usize;
+        let block = (self.format.sample_rate * FILE_BLOCK_MS / 1000).max(1) as usize * channels;
+        let interval = Duration::from_secs_f64(FILE_BLOCK_MS as f64 / 1000.0 / self.speed as f64);
+
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = running.clone();
+
+        let thread = std::thread::Builder::new()
+            .name("audio-file-source".to_string())
+            .spawn(move || {
+                let mut position = 0;
+                let mut next = Instant::now();
+
+                while thread_running.load(Ordering::SeqCst) {
+                    if position >= samples.len() {
+                        if looping && !samples.is_empty() {
+                            position = 0;
+                        } else {
+                            break;
+                        }
+                    }
+
+                    let end = (position + block).min(samples.len());
+                    on_data(&samples[position..end]);
+                    position = end;
+
+                    next += interval;
+                    if let Some(wait) = next.checked_duration_since(Instant::now()) {
+                        std::thread::sleep(wait);
+                    }
+                }
+
+                debug!("File source finished");
+            })?;
+
+        Ok(SourceHandle::new(FileSourceThread {
+            running,
+            thread: Some(thread),
+        }))
+    }
+}
+
+/// Stops the replay thread when dropped
+struct FileSourceThread {
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for FileSourceThread {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                warn!("File source thread panicked");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_replays_all_samples_accelerated() {
+        let mut source = FileSource::sine(440.0, 0.5, 16000, 500).with_speed(50.0);
+        let format = source.prepare(&AudioConfig::default()).unwrap();
+        assert_eq!(format, SourceFormat { sample_rate: 16000, channels: 1 });
+
+        let (sender, receiver) = mpsc::channel();
+        let _handle = source.start(
+            Box::new(move |data| {
+                let _ = sender.send(data.to_vec());
+            }),
+            Box::new(|_| {}),
+        ).unwrap();
+
+        // Every sample arrives once, in order, in blocks of at most 10 ms
+        let mut received = Vec::new();
+        while let Ok(block) = receiver.recv_timeout(Duration::from_secs(5)) {
+            assert!(block.len() <= 160);
+            received.extend(block);
+        }
+        assert_eq!(received, *source.samples);
+        assert!((received[4] - 0.5 * (2.0 * std::f32::consts::PI * 440.0 * 4.0 / 16000.0).sin()).abs() < 1e-6);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_capture_manager_records_file_source() {
+        use crate::audio::capture::{AudioEvent, CaptureManager};
+
+        // A ramp, so reordered or repeated blocks would show
+        let samples: Vec<f32> = (0..8000).map(|i| (i % 800) as f32 / 1000.0).collect();
+        let source = FileSource::from_samples(samples.clone(), 16000, 1).with_speed(20.0);
+        let (mut manager, mut events) = CaptureManager::with_source(Box::new(source)).unwrap();
+        manager.start().unwrap();
+
+        let mut received = Vec::new();
+        while received.len() < samples.len() {
+            match tokio::time::timeout(Duration::from_secs(5), events.recv()).await {
+                Ok(Some(AudioEvent::Data(data))) => {
+                    assert_eq!(data.sample_rate(), 16000);
+                    received.extend_from_slice(data.get_samples());
+                }
+                Ok(Some(_)) => {}
+                _ => break,
+            }
+        }
+
+        drop(events);
+        manager.stop().unwrap();
+        assert_eq!(received, samples);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_raw_audio_keeps_the_silence_vad_drops() {
+        use crate::audio::capture::{AudioEvent, CaptureManager};
+
+        // A second of silence before the ramp
+        let samples: Vec<f32> = (0..16000).map(|i| if i < 8000 { 0.0 } else { (i % 800) as f32 / 1000.0 }).collect();
+        let source = FileSource::from_samples(samples.clone(), 16000, 1).with_speed(20.0);
+        let (mut manager, mut events) = CaptureManager::with_source(Box::new(source)).unwrap();
+        manager.set_config(AudioConfig { vad_enabled: true, ..AudioConfig::default() });
+        let (sender, receiver) = mpsc::channel();
+        manager.on_raw_audio(move |data| {
+            let _ = sender.send(data.get_samples().to_vec());
+        });
+        manager.start().unwrap();
+
+        // Drain the events as they come, so capture never waits for this test
+        let mut raw = Vec::new();
+        let mut gated = 0;
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while raw.len() < samples.len() && Instant::now() < deadline {
+            while let Ok(event) = events.try_recv() {
+                if let AudioEvent::Data(data) = event {
+                    gated += data.get_samples().len();
+                }
+            }
+            if let Ok(block) = receiver.recv_timeout(Duration::from_millis(10)) {
+                raw.extend(block);
+            }
+        }
+
+        drop(events);
+        manager.stop().unwrap();
+
+        // Most of the silence never reaches the transcriber, but all of it is recorded
+        assert_eq!(raw, samples);
+        assert!(gated > 0 && gated < samples.len() - 4000, "{} samples passed the VAD", gated);
+    }
+
+    #[test]
+    fn test_wav_keeps_channels_and_scales_samples() {
+        let path = std::env::temp_dir().join(format!("bestme_source_test_{}.wav", std::process::id()));
+
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..800 {
+            writer.write_sample(i16::MAX / 2).unwrap();
+            writer.write_sample(-i16::MAX / 4).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let mut source = FileSource::from_wav(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let format = source.prepare(&AudioConfig::default()).unwrap();
+        assert_eq!(format, SourceFormat { sample_rate: 8000, channels: 2 });
+        assert_eq!(source.duration(), Duration::from_millis(100));
+        assert!((source.samples[0] - 0.5).abs() < 0.01);
+        assert!((source.samples[1] + 0.25).abs() < 0.01);
+    }
+}
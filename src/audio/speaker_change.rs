@@ -0,0 +1,201 @@
+use std::time::Duration;
+
+use crate::config::SpeakerChangeSettings;
+
+/// Length of a single analysis frame in milliseconds, long enough for two periods of a low voice
+const FRAME_MS: u32 = 40;
+
+/// Most frames analyzed per segment; long segments are sampled evenly
+const MAX_FRAMES: usize = 64;
+
+/// Frames this far below the loudest one are left out as pauses or breathing (dB)
+const VOICED_RANGE_DB: f32 = 25.0;
+
+/// Frames below this level are never voiced (dBFS)
+const MIN_VOICED_DB: f32 = -50.0;
+
+/// Range of voice pitch searched (Hz)
+const MIN_PITCH_HZ: u32 = 60;
+const MAX_PITCH_HZ: u32 = 400;
+
+/// Normalized autocorrelation a frame needs to count as pitched
+const VOICING_THRESHOLD: f32 = 0.5;
+
+/// Pitched frames a segment needs for its pitch to be trusted
+const MIN_PITCHED_FRAMES: usize = 3;
+
+/// How a segment's voice sounds, for telling one speaker from another
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VoiceProfile {
+    /// Median pitch of the voiced frames, if enough of them were pitched (Hz)
+    pub pitch_hz: Option<f32>,
+
+    /// Median level of the voiced frames (dBFS)
+    pub level_db: f32,
+}
+
+impl VoiceProfile {
+    /// Measure the voice in mono `samples`; `None` when nothing in them is loud enough to be speech
+    pub fn measure(samples: &[f32], sample_rate: u32) -> Option<Self> {
+        let frame_len = (sample_rate * FRAME_MS / 1000).max(1) as usize;
+        let frames: Vec<&[f32]> = samples.chunks_exact(frame_len).collect();
+        let step = ((frames.len() + MAX_FRAMES - 1) / MAX_FRAMES).max(1);
+        let frames: Vec<(&[f32], f32)> = frames.into_iter()
+            .step_by(step)
+            .map(|frame| (frame, frame_db(frame)))
+            .collect();
+
+        let loudest = frames.iter().map(|(_, level)| *level).fold(f32::MIN, f32::max);
+        let voiced: Vec<(&[f32], f32)> = frames.into_iter()
+            .filter(|(_, level)| *level >= MIN_VOICED_DB && *level >= loudest - VOICED_RANGE_DB)
+            .collect();
+        if voiced.is_empty() {
+            return None;
+        }
+
+        let pitches: Vec<f32> = voiced.iter().filter_map(|(frame, _)| frame_pitch(frame, sample_rate)).collect();
+        Some(Self {
+            pitch_hz: (pitches.len() >= MIN_PITCHED_FRAMES).then(|| median(pitches)),
+            level_db: median(voiced.iter().map(|(_, level)| *level).collect()),
+        })
+    }
+}
+
+/// Guesses where the speaker changes in a single stream
+///
+/// A change takes a pause between segments and a voice that sounds different
+/// from the one before it: a shift in pitch, or in level when the pitch of
+/// either segment is unknown or the speakers sit at different distances from
+/// the microphone. It is a heuristic, not diarization; similar voices are
+/// missed, and one speaker changing their tone after a pause can be taken for two.
+#[derive(Debug, Clone)]
+pub struct SpeakerChangeDetector {
+    /// Shortest pause that can separate two speakers
+    min_pause: Duration,
+
+    /// Pitch shift that counts as a different voice (semitones)
+    pitch_shift: f32,
+
+    /// Level shift that counts as a different voice (dB)
+    level_shift: f32,
+
+    /// Voice and end of the last segment with speech in it
+    last: Option<(VoiceProfile, Duration)>,
+}
+
+impl SpeakerChangeDetector {
+    /// Create a detector for a stream without segments yet
+    pub fn new(settings: &SpeakerChangeSettings) -> Self {
+        Self {
+            min_pause: Duration::from_secs_f32(settings.min_pause_secs.max(0.0)),
+            pitch_shift: settings.pitch_shift_semitones,
+            level_shift: settings.level_shift_db,
+            last: None,
+        }
+    }
+
+    /// Add the voice of a segment spoken between `start` and `end`; returns true when a different speaker probably said it
+    pub fn detect(&mut self, profile: Option<VoiceProfile>, start: Duration, end: Duration) -> bool {
+        let profile = match profile {
+            Some(profile) => profile,
+            None => return false,
+        };
+
+        match self.last.replace((profile, end)) {
+            Some((last, last_end)) if start.saturating_sub(last_end) >= self.min_pause => {
+                let pitch_changed = match (last.pitch_hz, profile.pitch_hz) {
+                    (Some(last), Some(pitch)) => (12.0 * (pitch / last).log2()).abs() >= self.pitch_shift,
+                    _ => false,
+                };
+                pitch_changed || (profile.level_db - last.level_db).abs() >= self.level_shift
+            }
+            _ => false,
+        }
+    }
+
+    /// Forget the last speaker, e.g. for a new session
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+/// Pitch of a frame by autocorrelation, if it is pitched at all
+fn frame_pitch(frame: &[f32], sample_rate: u32) -> Option<f32> {
+    let min_lag = (sample_rate / MAX_PITCH_HZ) as usize;
+    let max_lag = ((sample_rate / MIN_PITCH_HZ) as usize).min(frame.len() / 2);
+    let energy: f32 = frame.iter().map(|s| s * s).sum();
+    if min_lag == 0 || min_lag >= max_lag || energy <= 0.0 {
+        return None;
+    }
+
+    let (lag, correlation) = (min_lag..=max_lag)
+        .map(|lag| (lag, frame.iter().zip(&frame[lag..]).map(|(a, b)| a * b).sum::<f32>()))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+
+    (correlation / energy >= VOICING_THRESHOLD).then(|| sample_rate as f32 / lag as f32)
+}
+
+/// RMS level of a frame in dBFS
+fn frame_db(frame: &[f32]) -> f32 {
+    let mean_square = frame.iter().map(|s| s * s).sum::<f32>() / frame.len().max(1) as f32;
+    if mean_square > 0.0 {
+        10.0 * mean_square.log10()
+    } else {
+        f32::MIN
+    }
+}
+
+fn median(mut values: Vec<f32>) -> f32 {
+    values.sort_by(f32::total_cmp);
+    values[values.len() / 2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn voice(pitch_hz: f32, amplitude: f32, secs: f32) -> Vec<f32> {
+        (0..(secs * 16000.0) as usize)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * pitch_hz * i as f32 / 16000.0).sin())
+            .collect()
+    }
+
+    fn secs(secs: f32) -> Duration {
+        Duration::from_secs_f32(secs)
+    }
+
+    #[test]
+    fn test_measures_pitch_and_level() {
+        let profile = VoiceProfile::measure(&voice(120.0, 0.1, 1.0), 16000).unwrap();
+        assert!((profile.pitch_hz.unwrap() - 120.0).abs() < 3.0, "{:?}", profile);
+        assert!((profile.level_db + 23.0).abs() < 1.0, "{:?}", profile);
+
+        let profile = VoiceProfile::measure(&voice(220.0, 0.1, 1.0), 16000).unwrap();
+        assert!((profile.pitch_hz.unwrap() - 220.0).abs() < 5.0, "{:?}", profile);
+
+        assert_eq!(VoiceProfile::measure(&vec![0.0; 16000], 16000), None);
+    }
+
+    #[test]
+    fn test_changes_need_a_pause_and_another_voice() {
+        let mut detector = SpeakerChangeDetector::new(&SpeakerChangeSettings::default());
+        let low = VoiceProfile::measure(&voice(120.0, 0.1, 1.0), 16000);
+        let high = VoiceProfile::measure(&voice(220.0, 0.1, 1.0), 16000);
+        let quiet = VoiceProfile::measure(&voice(120.0, 0.02, 1.0), 16000);
+
+        assert!(!detector.detect(low, secs(0.0), secs(1.0)));
+
+        // Another voice straight after isn't taken for a change, nor the same voice after a pause
+        assert!(!detector.detect(high, secs(1.1), secs(2.0)));
+        assert!(!detector.detect(high, secs(3.0), secs(4.0)));
+
+        // Another voice after a pause is, by pitch or by level
+        assert!(detector.detect(low, secs(5.0), secs(6.0)));
+        assert!(detector.detect(quiet, secs(7.0), secs(8.0)));
+
+        // Silence is no speaker
+        assert!(!detector.detect(None, secs(9.0), secs(10.0)));
+        detector.reset();
+        assert!(!detector.detect(high, secs(11.0), secs(12.0)));
+    }
+}
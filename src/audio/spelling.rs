@@ -0,0 +1,76 @@
+/// Words of the NATO phonetic alphabet and the letters they stand for
+const NATO_ALPHABET: [(&str, char); 28] = [
+    ("alpha", 'a'), ("alfa", 'a'), ("bravo", 'b'), ("charlie", 'c'), ("delta", 'd'), ("echo", 'e'),
+    ("foxtrot", 'f'), ("golf", 'g'), ("hotel", 'h'), ("india", 'i'), ("juliet", 'j'), ("juliett", 'j'),
+    ("kilo", 'k'), ("lima", 'l'), ("mike", 'm'), ("november", 'n'), ("oscar", 'o'), ("papa", 'p'),
+    ("quebec", 'q'), ("romeo", 'r'), ("sierra", 's'), ("tango", 't'), ("uniform", 'u'), ("victor", 'v'),
+    ("whiskey", 'w'), ("xray", 'x'), ("yankee", 'y'), ("zulu", 'z'),
+];
+
+/// Spoken digits, with the radio "niner"
+const DIGITS: [(&str, char); 11] = [
+    ("zero", '0'), ("one", '1'), ("two", '2'), ("three", '3'), ("four", '4'), ("five", '5'),
+    ("six", '6'), ("seven", '7'), ("eight", '8'), ("nine", '9'), ("niner", '9'),
+];
+
+/// Names of the symbols used in identifiers and codes
+const SYMBOLS: [(&str, char); 8] = [
+    ("dash", '-'), ("hyphen", '-'), ("underscore", '_'), ("dot", '.'), ("slash", '/'),
+    ("colon", ':'), ("at", '@'), ("space", ' '),
+];
+
+/// The characters spelled out in `text`, e.g. "alpha bravo seven" gives "ab7"
+///
+/// Each word is a letter, a NATO alphabet word, a digit or the name of a symbol;
+/// "capital" or "uppercase" before a letter writes it in uppercase. Other words,
+/// such as numbers the recognizer wrote in digits, are written as they are.
+pub fn spell(text: &str) -> String {
+    let mut spelled = String::new();
+    let mut capital = false;
+    for word in text.split_whitespace() {
+        let word: String = word.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase();
+        if word.is_empty() {
+            continue;
+        }
+        if word == "capital" || word == "uppercase" {
+            capital = true;
+            continue;
+        }
+
+        match character(&word) {
+            Some(c) if capital => spelled.extend(c.to_uppercase()),
+            Some(c) => spelled.push(c),
+            None => spelled.push_str(&word),
+        }
+        capital = false;
+    }
+    spelled
+}
+
+/// The character a spoken word stands for
+fn character(word: &str) -> Option<char> {
+    let mut chars = word.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(c);
+    }
+
+    NATO_ALPHABET.iter()
+        .chain(DIGITS.iter())
+        .chain(SYMBOLS.iter())
+        .find(|(name, _)| *name == word)
+        .map(|(_, c)| *c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spells_letters_and_codes() {
+        assert_eq!(spell("alpha bravo seven"), "ab7");
+        assert_eq!(spell("Capital X-ray, dash 42"), "X-42");
+        assert_eq!(spell("b. e underscore niner"), "be_9");
+        assert_eq!(spell("uppercase k i l o"), "Kilo");
+        assert_eq!(spell(""), "");
+    }
+}
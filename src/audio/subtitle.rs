@@ -0,0 +1,307 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use super::punctuate::ends_sentence;
+use crate::config::SubtitleSettings;
+
+/// Lines shown at once in a cue
+const LINES_PER_CUE: usize = 2;
+
+/// Subtitle file formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    /// SubRip (`.srt`)
+    Srt,
+
+    /// WebVTT (`.vtt`)
+    Vtt,
+}
+
+impl SubtitleFormat {
+    /// Subtitle format named by `SpeechSettings.output_format`, if it is one
+    pub fn from_output_format(format: &str) -> Option<Self> {
+        match format.to_lowercase().as_str() {
+            "srt" => Some(Self::Srt),
+            "vtt" => Some(Self::Vtt),
+            _ => None,
+        }
+    }
+
+    /// File extension without the dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Srt => "srt",
+            Self::Vtt => "vtt",
+        }
+    }
+}
+
+/// A piece of text shown between two points in the recording
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleCue {
+    /// Time the cue appears, from the start of the recording
+    pub start: Duration,
+
+    /// Time the cue disappears
+    pub end: Duration,
+
+    /// Cue text, with lines separated by `\n`
+    pub text: String,
+}
+
+/// Split a transcribed segment into cues that respect the segment, line length and duration limits
+///
+/// Long segments are first split at sentence boundaries. Text is then wrapped
+/// at word boundaries, two lines per cue. Long pieces get shorter lines so that
+/// no cue stays up longer than the maximum duration, and time is shared
+/// between cues by their length.
+pub fn split_segment(start: Duration, end: Duration, text: &str, settings: &SubtitleSettings) -> Vec<SubtitleCue> {
+    split_long_segment(start, end, text, settings)
+        .into_iter()
+        .flat_map(|piece| wrap_cues(piece.start, piece.end, &piece.text, settings))
+        .collect()
+}
+
+/// Split a segment longer than the segment limits into pieces, breaking at sentence boundaries
+///
+/// Sentences stay together while they fit. A sentence too long on its own is
+/// broken after a clause (",", ";" or ":"), or failing that between words.
+/// Speech is taken to be spread evenly over the segment, so the duration limit
+/// becomes a number of characters.
+pub fn split_long_segment(start: Duration, end: Duration, text: &str, settings: &SubtitleSettings) -> Vec<SubtitleCue> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let duration = end.saturating_sub(start);
+    let mut max_chars = settings.max_segment_chars.max(1);
+    if !duration.is_zero() {
+        let chars_per_sec = words.join(" ").chars().count() as f64 / duration.as_secs_f64();
+        max_chars = max_chars.min((chars_per_sec * settings.max_segment_secs.max(1.0) as f64) as usize).max(1);
+    }
+
+    let mut pieces = Vec::new();
+    let mut piece: Vec<&str> = Vec::new();
+    for word in words {
+        while !piece.is_empty() && joined_len(&piece) + 1 + word.chars().count() > max_chars {
+            let rest = piece.split_off(break_point(&piece));
+            pieces.push(piece.join(" "));
+            piece = rest;
+        }
+        piece.push(word);
+    }
+    pieces.push(piece.join(" "));
+
+    share_time(start, end, pieces)
+}
+
+/// Where to break a piece that can't take another word: after its last sentence, its last clause, or all of it
+fn break_point(piece: &[&str]) -> usize {
+    let after = |found: Option<usize>| found.map(|i| i + 1);
+    after(piece.iter().rposition(|word| ends_sentence(word)))
+        .or_else(|| after(piece.iter().rposition(|word| word.ends_with([',', ';', ':']))))
+        .unwrap_or(piece.len())
+}
+
+/// Length of words joined by spaces
+fn joined_len(words: &[&str]) -> usize {
+    words.iter().map(|word| word.chars().count()).sum::<usize>() + words.len().saturating_sub(1)
+}
+
+/// Wrap a piece of a segment into cues of at most two lines
+fn wrap_cues(start: Duration, end: Duration, text: &str, settings: &SubtitleSettings) -> Vec<SubtitleCue> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let duration = end.saturating_sub(start);
+    let total_chars = joined_len(&words);
+
+    let max_duration = settings.max_cue_duration_secs.max(0.5) as f64;
+    let cues_for_duration = (duration.as_secs_f64() / max_duration).ceil().max(1.0) as usize;
+    let line_length = settings.max_line_length.min((total_chars + cues_for_duration * LINES_PER_CUE - 1) / (cues_for_duration * LINES_PER_CUE)).max(1);
+
+    let lines = wrap(&words, line_length);
+    share_time(start, end, lines.chunks(LINES_PER_CUE).map(|lines| lines.join("\n")).collect())
+}
+
+/// Time chunks of text by characters (counting the space each break replaces), so they
+/// run back to back from `start` and the last one ends at `end`
+fn share_time(start: Duration, end: Duration, chunks: Vec<String>) -> Vec<SubtitleCue> {
+    let duration = end.saturating_sub(start);
+    let total_chars = (chunks.iter().map(|chunk| chunk.chars().count() + 1).sum::<usize>() - 1).max(1);
+
+    let mut cues = Vec::with_capacity(chunks.len());
+    let mut chars_before = 0;
+    for text in chunks {
+        let cue_start = start + duration.mul_f64(chars_before as f64 / total_chars as f64);
+        chars_before += text.chars().count() + 1;
+        cues.push(SubtitleCue {
+            start: cue_start,
+            end: start + duration.mul_f64((chars_before as f64 / total_chars as f64).min(1.0)),
+            text,
+        });
+    }
+
+    cues
+}
+
+/// Greedily wrap words into lines of at most `line_length` characters
+///
+/// A word longer than a line gets a line of its own.
+fn wrap(words: &[&str], line_length: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in words {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > line_length {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Render cues as a complete subtitle file
+pub fn render(cues: &[SubtitleCue], format: SubtitleFormat) -> String {
+    let mut output = String::new();
+    if format == SubtitleFormat::Vtt {
+        output.push_str("WEBVTT\n\n");
+    }
+
+    for (index, cue) in cues.iter().enumerate() {
+        if format == SubtitleFormat::Srt {
+            let _ = writeln!(output, "{}", index + 1);
+        }
+        let _ = writeln!(
+            output,
+            "{} --> {}",
+            format_timestamp(cue.start, format),
+            format_timestamp(cue.end, format)
+        );
+        let _ = writeln!(output, "{}\n", cue.text);
+    }
+
+    output
+}
+
+/// Format a timestamp as `HH:MM:SS,mmm` (SRT) or `HH:MM:SS.mmm` (WebVTT)
+fn format_timestamp(time: Duration, format: SubtitleFormat) -> String {
+    let millis = time.as_millis();
+    let separator = match format {
+        SubtitleFormat::Srt => ',',
+        SubtitleFormat::Vtt => '.',
+    };
+
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(max_line_length: usize, max_cue_duration_secs: f32) -> SubtitleSettings {
+        SubtitleSettings {
+            max_line_length,
+            max_cue_duration_secs,
+            ..SubtitleSettings::default()
+        }
+    }
+
+    #[test]
+    fn test_wraps_lines_and_shares_time() {
+        let cues = split_segment(
+            Duration::from_secs(10),
+            Duration::from_secs(14),
+            "the quick brown fox jumps over the lazy dog",
+            &settings(16, 10.0),
+        );
+
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0].text, "the quick brown\nfox jumps over");
+        assert_eq!(cues[1].text, "the lazy dog");
+        assert_eq!(cues[0].start, Duration::from_secs(10));
+        assert_eq!(cues[0].end, cues[1].start);
+        assert_eq!(cues[1].end, Duration::from_secs(14));
+    }
+
+    #[test]
+    fn test_long_segments_are_split_by_duration() {
+        let cues = split_segment(
+            Duration::ZERO,
+            Duration::from_secs(12),
+            "one two three four five six seven eight",
+            &settings(42, 4.0),
+        );
+
+        assert!(cues.len() >= 3);
+        for cue in &cues {
+            assert!(cue.text.lines().all(|line| line.chars().count() <= 42));
+        }
+        assert_eq!(cues.last().unwrap().end, Duration::from_secs(12));
+    }
+
+    #[test]
+    fn test_long_segments_break_at_sentences() {
+        let settings = SubtitleSettings {
+            max_segment_chars: 40,
+            ..SubtitleSettings::default()
+        };
+        let text = "We start at nine. Then we review the budget, the hiring plan and the roadmap for next year. Done.";
+        let pieces = split_long_segment(Duration::ZERO, Duration::from_secs(10), text, &settings);
+
+        let texts: Vec<&str> = pieces.iter().map(|piece| piece.text.as_str()).collect();
+        assert_eq!(texts, [
+            "We start at nine.",
+            "Then we review the budget,",
+            "the hiring plan and the roadmap for next",
+            "year. Done.",
+        ]);
+        assert_eq!(pieces[0].start, Duration::ZERO);
+        assert_eq!(pieces[1].start, pieces[0].end);
+        assert_eq!(pieces[3].end, Duration::from_secs(10));
+
+        // The duration limit splits slow speech too: 20 seconds is two thirds of this segment
+        let settings = SubtitleSettings {
+            max_segment_secs: 20.0,
+            ..SubtitleSettings::default()
+        };
+        let pieces = split_long_segment(Duration::ZERO, Duration::from_secs(30), "One. Two. Three. Four.", &settings);
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(pieces[0].text, "One. Two.");
+
+        // Each piece is wrapped into cues of its own
+        let cues = split_segment(Duration::ZERO, Duration::from_secs(10), text, &SubtitleSettings { max_segment_chars: 40, ..settings });
+        assert_eq!(cues[0].text, "We start\nat nine.");
+    }
+
+    #[test]
+    fn test_renders_srt_and_vtt() {
+        let cues = vec![SubtitleCue {
+            start: Duration::from_millis(3_723_004),
+            end: Duration::from_millis(3_725_500),
+            text: "Me: hello".to_string(),
+        }];
+
+        assert_eq!(render(&cues, SubtitleFormat::Srt), "1\n01:02:03,004 --> 01:02:05,500\nMe: hello\n\n");
+        assert_eq!(render(&cues, SubtitleFormat::Vtt), "WEBVTT\n\n01:02:03.004 --> 01:02:05.500\nMe: hello\n\n");
+        assert_eq!(SubtitleFormat::from_output_format("SRT"), Some(SubtitleFormat::Srt));
+        assert_eq!(SubtitleFormat::from_output_format("txt"), None);
+    }
+}
@@ -0,0 +1,143 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Volume change of one "volume up" or "volume down", in percent
+pub const VOLUME_STEP_PERCENT: i32 = 6;
+
+/// A system volume or media playback action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SystemAction {
+    VolumeUp,
+    VolumeDown,
+
+    /// Mute, or unmute when muted
+    Mute,
+
+    /// Play, or pause when playing
+    PlayPause,
+    NextTrack,
+    PreviousTrack,
+}
+
+/// Controls system volume and media playback for voice commands
+pub trait SystemControl: Send {
+    /// Carry out `action`
+    fn perform(&self, action: SystemAction) -> Result<()>;
+}
+
+/// System control for the platform the application runs on
+pub fn platform_system_control() -> Box<dyn SystemControl> {
+    Box::new(PlatformSystemControl)
+}
+
+/// Controls volume and playback with the platform's own tools
+///
+/// Windows presses the media keys, so whichever player has them responds.
+/// macOS sets the output volume and controls the Music app. Linux changes the
+/// default sound server sink with `pactl` and controls MPRIS players with
+/// `playerctl`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlatformSystemControl;
+
+impl SystemControl for PlatformSystemControl {
+    fn perform(&self, action: SystemAction) -> Result<()> {
+        #[cfg(target_os = "windows")]
+        {
+            windows_keys::press(action)
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            let script = match action {
+                SystemAction::VolumeUp => {
+                    format!("set volume output volume ((output volume of (get volume settings)) + {})", VOLUME_STEP_PERCENT)
+                }
+                SystemAction::VolumeDown => {
+                    format!("set volume output volume ((output volume of (get volume settings)) - {})", VOLUME_STEP_PERCENT)
+                }
+                SystemAction::Mute => "set volume output muted (not (output muted of (get volume settings)))".to_string(),
+                SystemAction::PlayPause => "tell application \"Music\" to playpause".to_string(),
+                SystemAction::NextTrack => "tell application \"Music\" to next track".to_string(),
+                SystemAction::PreviousTrack => "tell application \"Music\" to previous track".to_string(),
+            };
+            let status = std::process::Command::new("osascript")
+                .args(["-e", &script])
+                .status()
+                .map_err(|e| anyhow::anyhow!("Failed to run osascript: {}", e))?;
+            if !status.success() {
+                anyhow::bail!("Failed to {:?}: osascript exited with {}", action, status);
+            }
+            Ok(())
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            let player_command = match action {
+                SystemAction::VolumeUp => return super::pulse::change_sink_volume(VOLUME_STEP_PERCENT),
+                SystemAction::VolumeDown => return super::pulse::change_sink_volume(-VOLUME_STEP_PERCENT),
+                SystemAction::Mute => return super::pulse::toggle_sink_mute(),
+                SystemAction::PlayPause => "play-pause",
+                SystemAction::NextTrack => "next",
+                SystemAction::PreviousTrack => "previous",
+            };
+            let status = std::process::Command::new("playerctl")
+                .arg(player_command)
+                .status()
+                .map_err(|e| anyhow::anyhow!("Failed to run playerctl; install it to control playback by voice: {}", e))?;
+            if !status.success() {
+                anyhow::bail!("No media player responded to {:?}", action);
+            }
+            Ok(())
+        }
+
+        #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+        {
+            anyhow::bail!("{:?} isn't supported on this platform", action)
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows_keys {
+    use super::SystemAction;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP, VIRTUAL_KEY,
+        VK_MEDIA_NEXT_TRACK, VK_MEDIA_PLAY_PAUSE, VK_MEDIA_PREV_TRACK, VK_VOLUME_DOWN, VK_VOLUME_MUTE, VK_VOLUME_UP,
+    };
+
+    fn key_input(key: VIRTUAL_KEY, flags: KEYBD_EVENT_FLAGS) -> INPUT {
+        INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: key,
+                    wScan: 0,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: 0,
+                },
+            },
+        }
+    }
+
+    /// Press and release the media key for `action`
+    ///
+    /// Each volume key press changes the volume by the system's own step.
+    pub fn press(action: SystemAction) -> anyhow::Result<()> {
+        let key = match action {
+            SystemAction::VolumeUp => VK_VOLUME_UP,
+            SystemAction::VolumeDown => VK_VOLUME_DOWN,
+            SystemAction::Mute => VK_VOLUME_MUTE,
+            SystemAction::PlayPause => VK_MEDIA_PLAY_PAUSE,
+            SystemAction::NextTrack => VK_MEDIA_NEXT_TRACK,
+            SystemAction::PreviousTrack => VK_MEDIA_PREV_TRACK,
+        };
+        let inputs = [key_input(key, KEYBD_EVENT_FLAGS(0)), key_input(key, KEYEVENTF_KEYUP)];
+
+        let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+        if sent as usize != inputs.len() {
+            anyhow::bail!("Failed to press the key for {:?}", action);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// Segments the rolling averages are taken over
+pub const DEFAULT_WINDOW: usize = 50;
+
+/// How long transcribing one segment took
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SegmentMetrics {
+    /// Length of the transcribed audio, in seconds
+    pub audio_secs: f32,
+
+    /// Time the engine spent on the audio, in milliseconds
+    pub inference_ms: f32,
+
+    /// Inference time over audio length; above 1.0 the engine falls behind live speech
+    pub real_time_factor: f32,
+
+    /// Time from the segment being cut to its text being ready, in milliseconds
+    pub latency_ms: f32,
+
+    /// Segments waiting to be transcribed after this one
+    pub queue_depth: usize,
+}
+
+impl SegmentMetrics {
+    /// Metrics of a segment of `audio_secs` that took `inference` to transcribe and `latency` to be ready
+    pub fn new(audio_secs: f32, inference: Duration, latency: Duration, queue_depth: usize) -> Self {
+        let inference_secs = inference.as_secs_f32();
+        Self {
+            audio_secs,
+            inference_ms: inference_secs * 1000.0,
+            real_time_factor: if audio_secs > 0.0 { inference_secs / audio_secs } else { 0.0 },
+            latency_ms: latency.as_secs_f32() * 1000.0,
+            queue_depth,
+        }
+    }
+}
+
+/// Averages over the most recent segments
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ThroughputSummary {
+    /// Segments measured since the stats were created
+    pub segments: u64,
+
+    /// Average segment length, in seconds
+    pub avg_audio_secs: f32,
+
+    /// Average inference time, in milliseconds
+    pub avg_inference_ms: f32,
+
+    /// Total inference time over total audio length, so long segments weigh more
+    pub real_time_factor: f32,
+
+    /// Average time from a segment being cut to its text being ready, in milliseconds
+    pub avg_latency_ms: f32,
+
+    /// Longest queue behind a segment
+    pub max_queue_depth: usize,
+
+    /// Whether transcription keeps up with live speech
+    pub keeping_up: bool,
+}
+
+/// Rolling processing metrics over a window of recent segments
+#[derive(Debug, Clone)]
+pub struct ThroughputStats {
+    /// Most recent segments, oldest first
+    recent: VecDeque<SegmentMetrics>,
+
+    /// Segments kept in `recent`
+    window: usize,
+
+    /// Segments measured in total
+    segments: u64,
+}
+
+impl Default for ThroughputStats {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW)
+    }
+}
+
+impl ThroughputStats {
+    /// Create stats averaging over the last `window` segments
+    pub fn new(window: usize) -> Self {
+        Self {
+            recent: VecDeque::with_capacity(window.max(1)),
+            window: window.max(1),
+            segments: 0,
+        }
+    }
+
+    /// Add a segment's metrics, forgetting the oldest beyond the window
+    pub fn record(&mut self, metrics: SegmentMetrics) {
+        if self.recent.len() == self.window {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(metrics);
+        self.segments += 1;
+    }
+
+    /// Averages over the recent segments
+    pub fn summary(&self) -> ThroughputSummary {
+        if self.recent.is_empty() {
+            return ThroughputSummary { segments: self.segments, keeping_up: true, ..ThroughputSummary::default() };
+        }
+
+        let count = self.recent.len() as f32;
+        let audio_secs: f32 = self.recent.iter().map(|m| m.audio_secs).sum();
+        let inference_ms: f32 = self.recent.iter().map(|m| m.inference_ms).sum();
+        let latency_ms: f32 = self.recent.iter().map(|m| m.latency_ms).sum();
+        let real_time_factor = if audio_secs > 0.0 { inference_ms / 1000.0 / audio_secs } else { 0.0 };
+
+        ThroughputSummary {
+            segments: self.segments,
+            avg_audio_secs: audio_secs / count,
+            avg_inference_ms: inference_ms / count,
+            real_time_factor,
+            avg_latency_ms: latency_ms / count,
+            max_queue_depth: self.recent.iter().map(|m| m.queue_depth).max().unwrap_or(0),
+            keeping_up: real_time_factor < 1.0,
+        }
+    }
+
+    /// Forget all measurements
+    pub fn reset(&mut self) {
+        self.recent.clear();
+        self.segments = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(audio_secs: f32, inference_ms: u64) -> SegmentMetrics {
+        let inference = Duration::from_millis(inference_ms);
+        SegmentMetrics::new(audio_secs, inference, inference, 0)
+    }
+
+    #[test]
+    fn test_real_time_factor() {
+        let segment = SegmentMetrics::new(4.0, Duration::from_secs(1), Duration::from_millis(1200), 2);
+        assert!((segment.real_time_factor - 0.25).abs() < 1e-6);
+        assert!((segment.inference_ms - 1000.0).abs() < 1e-3);
+        assert!((segment.latency_ms - 1200.0).abs() < 1e-3);
+
+        assert_eq!(SegmentMetrics::new(0.0, Duration::from_secs(1), Duration::ZERO, 0).real_time_factor, 0.0);
+    }
+
+    #[test]
+    fn test_rolling_summary() {
+        let mut stats = ThroughputStats::new(2);
+        assert!(stats.summary().keeping_up);
+
+        stats.record(metrics(1.0, 3000));
+        stats.record(metrics(2.0, 1000));
+        stats.record(SegmentMetrics { queue_depth: 3, ..metrics(2.0, 1000) });
+
+        // The slow first segment has left the window
+        let summary = stats.summary();
+        assert_eq!(summary.segments, 3);
+        assert!((summary.real_time_factor - 0.5).abs() < 1e-6);
+        assert!((summary.avg_inference_ms - 1000.0).abs() < 1e-3);
+        assert_eq!(summary.max_queue_depth, 3);
+        assert!(summary.keeping_up);
+
+        stats.record(metrics(1.0, 4000));
+        assert!(!stats.summary().keeping_up);
+
+        stats.reset();
+        assert_eq!(stats.summary().segments, 0);
+    }
+}
@@ -1,15 +1,39 @@
 use anyhow::{Context, Result};
-use log::{info, warn};
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::fmt;
 use std::path::PathBuf;
 use std::sync::Arc;
-use parking_lot::Mutex;
+use std::time::Duration;
+use parking_lot::{Mutex, RwLock};
 use tokio::sync::mpsc;
 use thiserror::Error;
 
-use crate::config::{SpeechSettings, WhisperModelSize};
+use super::device::is_loopback_id;
+use super::cloud::CloudEngine;
+use super::engine::{self, DetectedLanguage, FallbackEngine, SttEngine, TranscribeOptions, TranscriptSegment};
+use super::formatting::TranscriptFormatter;
+use super::hallucination::{self, Hallucination, HallucinationStats};
+use super::history::TranscriptHistory;
+use super::live_output::LiveOutput;
+use super::overlap;
+use super::partial::PartialStabilizer;
+use super::pipeline::{TextPipeline, TextProcessor};
+use super::profanity::ProfanityFilter;
+use super::punctuate::{PunctuationModel, Punctuator};
+use super::refine::{RefineKind, Refiner};
+use super::segmenter::SpeechSegmenter;
+use super::session::Session;
+use super::silence::{SilenceTrimmer, Trimmed};
+use super::speaker_change::{SpeakerChangeDetector, VoiceProfile};
+use super::subtitle::{self, SubtitleCue, SubtitleFormat};
+use super::transcript_file::{TranscriptFile, TranscriptFileSegment};
+use crate::config::{RefineSettings, SpeechSettings, SttEngineKind};
 
 #[cfg(feature = "whisper")]
-use whisper_rs::{WhisperContext, FullParams, SamplingStrategy, WhisperContextParameters};
+use super::engine::WhisperEngine;
+#[cfg(feature = "vosk")]
+use super::vosk::{self, VoskEngine};
 
 /// Buffer size for audio accumulation before processing
 const AUDIO_BUFFER_SECONDS: usize = 3;
@@ -37,7 +61,43 @@ pub enum TranscriptionError {
     IoError(#[from] std::io::Error),
 }
 
-/// Transcription manager for handling speech recognition using Whisper
+/// Who is talking on a capture track
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Speaker {
+    /// The local user (microphone)
+    Me,
+    
+    /// Everyone else (system audio captured via loopback)
+    Others,
+}
+
+impl Speaker {
+    /// Speaker for audio tagged with a capture source ID
+    pub fn for_source(source_id: &str) -> Self {
+        if is_loopback_id(source_id) {
+            Speaker::Others
+        } else {
+            Speaker::Me
+        }
+    }
+}
+
+impl fmt::Display for Speaker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Speaker::Me => write!(f, "Me"),
+            Speaker::Others => write!(f, "Others"),
+        }
+    }
+}
+
+/// Partial results of each stream's unfinished buffer, and the buffer length they were last run at
+type StreamPartials = HashMap<Option<String>, (PartialStabilizer, usize)>;
+
+/// Last transcription on each stream and where it ended
+type PreviousSegments = HashMap<Option<String>, (String, Duration)>;
+
+/// Transcription manager for handling speech recognition (Whisper by default)
 #[derive(Clone)]
 pub struct TranscriptionManager {
     /// Transcription settings
@@ -55,12 +115,62 @@ pub struct TranscriptionManager {
     /// Current transcription text
     current_text: Arc<Mutex<String>>,
     
+    /// The running or last session, with its whole transcript
+    session: Arc<Mutex<Option<Session>>>,
+    
+    /// File final text is appended to as it arrives, when live output is on
+    live_output: Arc<Mutex<Option<LiveOutput>>>,
+    
     /// Audio buffer for accumulating audio before processing
     audio_buffer: Arc<Mutex<Vec<f32>>>,
     
-    /// Whisper context (only with whisper feature)
-    #[cfg(feature = "whisper")]
-    whisper_context: Option<Arc<WhisperContext>>,
+    /// Separate buffers for audio delivered as per-source tracks, by capture source ID
+    track_buffers: Arc<Mutex<HashMap<String, Vec<f32>>>>,
+    
+    /// Samples already transcribed on each stream, giving the start time of the next buffer
+    stream_positions: Arc<Mutex<HashMap<Option<String>, usize>>>,
+    
+    /// Subtitle cues written so far this session, and the file they are written to
+    subtitles: Arc<Mutex<(Vec<SubtitleCue>, Option<PathBuf>)>>,
+    
+    /// JSON transcript written so far this session, and the file it is written to
+    json_transcript: Arc<Mutex<Option<(TranscriptFile, PathBuf)>>>,
+    
+    /// Speech-to-text engine; transcriptions are simulated while there is none
+    engine: Arc<RwLock<Option<Box<dyn SttEngine>>>>,
+    
+    /// Partial results of each stream's unfinished buffer, and the buffer length they were last run at
+    partials: Arc<Mutex<StreamPartials>>,
+    
+    /// Pause detection on each stream, deciding when its buffer is complete
+    segmenters: Arc<Mutex<HashMap<Option<String>, SpeechSegmenter>>>,
+    
+    /// Segments dropped as hallucinations this session
+    hallucinations: Arc<Mutex<HallucinationStats>>,
+    
+    /// Last transcription on each stream and where it ended, to drop words repeated from the shared audio
+    previous_segments: Arc<Mutex<PreviousSegments>>,
+    
+    /// Restores punctuation and capitalization when auto_punctuate is on
+    punctuator: Punctuator,
+    
+    /// Steps added by the application, run where the settings list them
+    custom_steps: Vec<Arc<dyn TextProcessor>>,
+    
+    /// Post-processing applied to final text, built from the settings
+    pipeline: TextPipeline,
+    
+    /// Masks or removes profanity in captions, which skip the pipeline
+    profanity: ProfanityFilter,
+    
+    /// Cleans up final text with a language model when refinement is on
+    refiner: Option<Refiner>,
+    
+    /// Each stream's transcript so far, formatting new text when context_formatting is on
+    formatters: Arc<Mutex<HashMap<Option<String>, TranscriptFormatter>>>,
+    
+    /// Guesses at speaker changes in audio that isn't split into per-speaker tracks
+    speaker_changes: Arc<Mutex<SpeakerChangeDetector>>,
 }
 
 /// Transcription state
@@ -88,10 +198,55 @@ enum TranscriptionState {
 #[derive(Debug, Clone)]
 pub enum TranscriptionEvent {
     /// New transcription available
-    Transcription(String),
+    Transcription {
+        /// Transcribed text, labelled with its speaker
+        text: String,
+        
+        /// How sure the engine is of the text (0.0 - 1.0), if it reports that
+        confidence: Option<f32>,
+    },
+    
+    /// Language detected in a transcription while the language is "auto", sent before its `Transcription`
+    LanguageDetected {
+        /// Speaker of the track the transcription belongs to, if any
+        speaker: Option<Speaker>,
+        
+        /// Detected language and its probability
+        language: DetectedLanguage,
+    },
+    
+    /// A transcription scored below the confidence threshold, sent after its `Transcription`
+    LowConfidence {
+        /// Transcribed text, labelled with its speaker
+        text: String,
+        
+        /// Confidence of the text (0.0 - 1.0)
+        confidence: f32,
+    },
     
-    /// Partial transcription available
-    PartialTranscription(String),
+    /// Partial transcription of a buffer that is still being filled
+    PartialTranscription {
+        /// Speaker of the track the buffer belongs to, if any
+        speaker: Option<Speaker>,
+        
+        /// Leading words that are unlikely to change
+        stable: String,
+        
+        /// Trailing words that may still be revised
+        volatile: String,
+    },
+    
+    /// A language model's refinement of a transcription, sent after it; the transcription itself stays as it was
+    Refined {
+        /// What the model was asked to do
+        kind: RefineKind,
+        
+        /// The transcription as it was sent to the model
+        original: String,
+        
+        /// The model's version of it
+        text: String,
+    },
     
     /// Transcription started
     Started,
@@ -99,6 +254,15 @@ pub enum TranscriptionEvent {
     /// Transcription stopped
     Stopped,
     
+    /// A buffer was skipped because it contained only silence
+    SkippedSilence {
+        /// Length of the skipped buffer in seconds
+        duration_secs: f32,
+        
+        /// Level of the loudest frame in the buffer (dBFS)
+        level_db: f32,
+    },
+    
     /// Transcription error
     Error(String),
 }
@@ -115,15 +279,35 @@ impl TranscriptionManager {
             Self::get_default_model_path()?
         };
         
+        let pipeline = TextPipeline::from_settings(&settings, Punctuator::new(), &[]);
+        let profanity = ProfanityFilter::new(&settings.profanity);
+        let refiner = utterance_refiner(&settings.refine);
+        let speaker_changes = SpeakerChangeDetector::new(&settings.speaker_change);
         let manager = Self {
             settings,
             model_path,
             state: TranscriptionState::Uninitialized,
             event_sender,
             current_text: Arc::new(Mutex::new(String::new())),
+            session: Arc::new(Mutex::new(None)),
+            live_output: Arc::new(Mutex::new(None)),
             audio_buffer: Arc::new(Mutex::new(Vec::with_capacity(AUDIO_BUFFER_SECONDS * SAMPLE_RATE))),
-            #[cfg(feature = "whisper")]
-            whisper_context: None,
+            track_buffers: Arc::new(Mutex::new(HashMap::new())),
+            stream_positions: Arc::new(Mutex::new(HashMap::new())),
+            subtitles: Arc::new(Mutex::new((Vec::new(), None))),
+            json_transcript: Arc::new(Mutex::new(None)),
+            engine: Arc::new(RwLock::new(None)),
+            partials: Arc::new(Mutex::new(HashMap::new())),
+            segmenters: Arc::new(Mutex::new(HashMap::new())),
+            hallucinations: Arc::new(Mutex::new(HallucinationStats::default())),
+            previous_segments: Arc::new(Mutex::new(HashMap::new())),
+            punctuator: Punctuator::new(),
+            custom_steps: Vec::new(),
+            pipeline,
+            profanity,
+            refiner,
+            formatters: Arc::new(Mutex::new(HashMap::new())),
+            speaker_changes: Arc::new(Mutex::new(speaker_changes)),
         };
         
         Ok((manager, event_receiver))
@@ -148,38 +332,112 @@ impl TranscriptionManager {
     
     /// Initialize the transcription manager
     pub async fn initialize(&mut self) -> Result<()> {
-        info!("Initializing Whisper transcription with model size: {:?}", self.settings.model_size);
+        info!("Initializing transcription with model size: {:?}", self.settings.model_size);
         
-        // Initialize Whisper if the feature is enabled
-        #[cfg(feature = "whisper")]
-        {
-            // Get model file path
-            let model_file = self.model_path.join(format!("whisper-{}.bin", self.get_model_size_string()));
+        // Use the configured engine unless another one was set
+        if self.engine.read().is_none() {
+            let engine = match self.settings.engine {
+                SttEngineKind::Cloud if self.settings.download.offline => {
+                    info!("Offline mode is enabled, transcribing locally");
+                    self.local_engine()
+                }
+                SttEngineKind::Cloud => {
+                    let cloud = Box::new(CloudEngine::new(self.settings.cloud.clone()));
+                    match self.local_engine() {
+                        Some(local) => Some(Box::new(FallbackEngine::new(cloud, local)) as Box<dyn SttEngine>),
+                        None => {
+                            warn!("No local Whisper model, cloud transcription can't fall back when offline");
+                            Some(cloud as Box<dyn SttEngine>)
+                        }
+                    }
+                }
+                SttEngineKind::Vosk => self.vosk_engine(),
+                SttEngineKind::Whisper => self.local_engine(),
+            };
             
-            if !model_file.exists() {
-                warn!("Whisper model file not found: {:?}", model_file);
-                warn!("Running in simulation mode without actual transcription");
-            } else {
-                info!("Loading Whisper model from {:?}", model_file);
-                let builder = WhisperContextParameters::new();
-                let whisper = WhisperContext::new_with_params(&model_file.to_string_lossy(), builder)
-                    .map_err(|e| anyhow::anyhow!("Failed to load whisper model: {}", e))?;
-                self.whisper_context = Some(Arc::new(whisper));
-                info!("Whisper model loaded successfully");
+            if let Some(engine) = engine {
+                self.set_engine(engine)?;
             }
         }
         
-        #[cfg(not(feature = "whisper"))]
-        {
-            info!("Whisper feature is not enabled, running in simulation mode");
+        self.state = TranscriptionState::Ready;
+        match self.engine.read().as_ref() {
+            Some(engine) => info!("Transcription initialized with the {} engine", engine.name()),
+            None => info!("Transcription initialized (simulation mode)"),
         }
         
-        self.state = TranscriptionState::Ready;
-        info!("Whisper transcription initialized (simulation mode)");
+        Ok(())
+    }
+    
+    /// Whisper engine for the configured model, if the model file is there
+    #[cfg(feature = "whisper")]
+    fn local_engine(&self) -> Option<Box<dyn SttEngine>> {
+        // Custom models are used from wherever they were registered
+        let model_file = match self.settings.selected_custom_model() {
+            Some(model) => PathBuf::from(&model.path),
+            None => self.model_path.join(format!("whisper-{}.bin", self.settings.model_name())),
+        };
+        
+        if model_file.exists() {
+            Some(Box::new(WhisperEngine::new(model_file)))
+        } else {
+            warn!("Whisper model file not found: {:?}", model_file);
+            None
+        }
+    }
+    
+    /// Whisper engine for the configured model (unavailable without the whisper feature)
+    #[cfg(not(feature = "whisper"))]
+    fn local_engine(&self) -> Option<Box<dyn SttEngine>> {
+        info!("Whisper feature is not enabled, running in simulation mode");
+        None
+    }
+    
+    /// Vosk engine for the configured model, if it has been unpacked into the models directory
+    #[cfg(feature = "vosk")]
+    fn vosk_engine(&self) -> Option<Box<dyn SttEngine>> {
+        let Some(dir_name) = vosk::model_dir_name(&self.settings.vosk.model) else {
+            warn!("Unknown Vosk model: {}", self.settings.vosk.model);
+            return None;
+        };
+        
+        let model_dir = self.model_path.join(dir_name);
+        if model_dir.is_dir() {
+            Some(Box::new(VoskEngine::new(model_dir)))
+        } else {
+            warn!("Vosk model directory not found: {:?}", model_dir);
+            None
+        }
+    }
+    
+    /// Vosk engine for the configured model (unavailable without the vosk feature)
+    #[cfg(not(feature = "vosk"))]
+    fn vosk_engine(&self) -> Option<Box<dyn SttEngine>> {
+        warn!("Vosk feature is not enabled, running in simulation mode");
+        None
+    }
+    
+    /// Load a speech-to-text engine and use it in place of the current one
+    pub fn set_engine(&self, mut engine: Box<dyn SttEngine>) -> Result<()> {
+        if !engine.is_loaded() {
+            engine.load()?;
+        }
+        info!("Using the {} speech-to-text engine", engine.name());
         
+        if let Some(mut previous) = self.engine.write().replace(engine) {
+            previous.unload();
+        }
         Ok(())
     }
     
+    /// Unload the engine, falling back to simulated transcriptions
+    pub fn unload_engine(&self) {
+        if let Some(mut engine) = self.engine.write().take() {
+            engine.unload();
+            info!("Unloaded the {} speech-to-text engine", engine.name());
+        }
+    }
+    
     /// Start transcription
     pub async fn start(&mut self) -> Result<()> {
         if self.state == TranscriptionState::Uninitialized {
@@ -198,6 +456,18 @@ impl TranscriptionManager {
             let mut buffer = self.audio_buffer.lock();
             buffer.clear();
         }
+        self.track_buffers.lock().clear();
+        self.stream_positions.lock().clear();
+        self.partials.lock().clear();
+        self.segmenters.lock().clear();
+        self.previous_segments.lock().clear();
+        self.formatters.lock().clear();
+        *self.speaker_changes.lock() = SpeakerChangeDetector::new(&self.settings.speaker_change);
+        *self.hallucinations.lock() = HallucinationStats::default();
+        *self.subtitles.lock() = (Vec::new(), None);
+        *self.json_transcript.lock() = None;
+        *self.session.lock() = Some(Session::new(&self.settings, None));
+        *self.live_output.lock() = self.settings.live_output.enabled.then(|| LiveOutput::new(self.settings.live_output.clone()));
         
         // Send started event
         let _ = self.event_sender.send(TranscriptionEvent::Started).await;
@@ -219,6 +489,23 @@ impl TranscriptionManager {
         // Process any remaining audio in the buffer
         self.process_buffer().await?;
         
+        // Closing the live output file writes what is left of it
+        self.live_output.lock().take();
+        
+        // Keep the session's transcript in the searchable history
+        let session = self.session.lock().as_mut().map(|session| {
+            session.end();
+            session.clone()
+        });
+        if let Some(session) = session.filter(|_| self.settings.save_transcription) {
+            let saved = TranscriptHistory::default_path()
+                .and_then(|path| TranscriptHistory::open(&path))
+                .and_then(|history| history.save_session(&session));
+            if let Err(e) = saved {
+                warn!("Failed to save session: {}", e);
+            }
+        }
+        
         // Send stopped event
         let _ = self.event_sender.send(TranscriptionEvent::Stopped).await;
         
@@ -234,166 +521,408 @@ impl TranscriptionManager {
         }
         
         // Create a scope to ensure the lock is released before the await
-        let buffer_clone = {
+        let (buffer_clone, partial_window) = {
             let mut buffer = self.audio_buffer.lock();
             buffer.extend_from_slice(audio_data);
             
-            // If buffer is large enough, process it
-            if buffer.len() >= AUDIO_BUFFER_SECONDS * SAMPLE_RATE {
+            // If the segment is complete, process it, keeping its end to start the next one
+            if self.segment_complete(None, audio_data, buffer.len()) {
                 let buffer_clone = buffer.clone();
-                buffer.clear();
-                Some(buffer_clone)
+                let carried = self.overlap_samples().min(buffer.len());
+                let keep_from = buffer.len() - carried;
+                buffer.drain(..keep_from);
+                (Some((buffer_clone, carried)), None)
+            } else if self.partial_due(None, buffer.len()) {
+                (None, Some(buffer.clone()))
             } else {
-                None
+                (None, None)
             }
             // Lock is released here when buffer goes out of scope
         };
         
+        if let Some(window) = partial_window {
+            self.transcribe_partial(window, None).await;
+        }
+        
         // Process the audio buffer if we got a clone
-        if let Some(buffer) = buffer_clone {
-            self.transcribe_audio(&buffer).await
+        if let Some((buffer, carried)) = buffer_clone {
+            self.transcribe_segment(&buffer, None, carried).await
         } else {
             Ok(None)
         }
     }
     
+    /// Process audio from one capture source's track; the transcription is labelled with the source's speaker
+    pub async fn process_track(&self, source_id: &str, audio_data: &[f32]) -> Result<Option<String>> {
+        if self.state != TranscriptionState::Transcribing {
+            return Ok(None);
+        }
+        
+        let (buffer_to_process, partial_window) = {
+            let mut tracks = self.track_buffers.lock();
+            let buffer = tracks.entry(source_id.to_string()).or_default();
+            buffer.extend_from_slice(audio_data);
+            
+            if self.segment_complete(Some(source_id), audio_data, buffer.len()) {
+                let carried = self.overlap_samples().min(buffer.len());
+                let overlap = buffer[buffer.len() - carried..].to_vec();
+                (Some((std::mem::replace(buffer, overlap), carried)), None)
+            } else if self.partial_due(Some(source_id), buffer.len()) {
+                (None, Some(buffer.clone()))
+            } else {
+                (None, None)
+            }
+        };
+        
+        if let Some(window) = partial_window {
+            self.transcribe_partial(window, Some(source_id)).await;
+        }
+        
+        match buffer_to_process {
+            Some((buffer, carried)) => self.transcribe_segment(&buffer, Some(source_id), carried).await,
+            None => Ok(None),
+        }
+    }
+    
     /// Process the current audio buffer
     async fn process_buffer(&self) -> Result<()> {
+        self.flush().await?;
+        self.flush_tracks().await?;
+        Ok(())
+    }
+    
+    /// Transcribe whatever audio is buffered, even if less than a full buffer
+    pub async fn flush(&self) -> Result<Option<String>> {
+        self.segmenters.lock().remove(&None);
+        
         // Create a scope to ensure the lock is released before the await
         let buffer_to_process = {
-            let buffer = self.audio_buffer.lock();
+            let mut buffer = self.audio_buffer.lock();
             if buffer.is_empty() {
                 None
             } else {
-                Some(buffer.clone())
+                Some(std::mem::take(&mut *buffer))
             }
             // Lock is released here when buffer goes out of scope
         };
         
         // Process the buffer if we have one
-        if let Some(buffer) = buffer_to_process {
-            self.transcribe_audio(&buffer).await?;
+        match buffer_to_process {
+            Some(buffer) => self.transcribe_segment(&buffer, None, 0).await,
+            None => Ok(None),
         }
+    }
+    
+    /// Transcribe whatever audio is buffered on each source's track
+    pub async fn flush_tracks(&self) -> Result<Vec<String>> {
+        self.segmenters.lock().retain(|track, _| track.is_none());
         
-        Ok(())
+        let tracks: Vec<(String, Vec<f32>)> = self.track_buffers.lock()
+            .drain()
+            .filter(|(_, buffer)| !buffer.is_empty())
+            .collect();
+        
+        let mut segments = Vec::new();
+        for (source_id, buffer) in tracks {
+            if let Some(text) = self.transcribe_segment(&buffer, Some(&source_id), 0).await? {
+                segments.push(text);
+            }
+        }
+        
+        Ok(segments)
     }
     
-    /// Transcribe audio data
-    #[cfg(feature = "whisper")]
-    async fn transcribe_audio(&self, audio_data: &[f32]) -> Result<Option<String>> {
-        // Ensure we have a whisper context
-        if let Some(context) = &self.whisper_context {
-            // Gather settings needed for the closure first
-            let language = self.settings.language.clone();
-            let translate_to_english = self.settings.translate_to_english;
-            
-            // Set up parameters and clone context and data for the blocking task
-            let context = Arc::clone(context);
-            let audio_data = audio_data.to_vec(); // Create owned copy for the blocking task
-            
-            // Spawn a blocking task for CPU-intensive processing
-            let transcription = tokio::task::spawn_blocking(move || {
-                // Set up parameters inside the closure
-                let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
-                
-                // Configure language settings 
-                if language.is_empty() || language == "auto" {
-                    params.set_language(None);
-                } else {
-                    params.set_language(Some(&language));
-                }
-                
-                // Configure translation if needed
-                if translate_to_english {
-                    params.set_translate(true);
-                }
-                
-                // Create the state
-                let mut state = match context.create_state() {
-                    Ok(state) => state,
-                    Err(e) => {
-                        return Err(anyhow::anyhow!("Failed to create whisper state: {}", e));
-                    }
-                };
-                
-                // Run inference
-                if let Err(e) = state.full(params, &audio_data) {
-                    return Err(anyhow::anyhow!("Failed to run inference: {}", e));
-                }
-                
-                // Extract text from segments
-                let num_segments = match state.full_n_segments() {
-                    Ok(n) => n,
-                    Err(e) => {
-                        return Err(anyhow::anyhow!("Failed to get segments: {}", e));
-                    }
-                };
-                
-                let mut text = String::new();
+    /// Whether a stream's unfinished buffer has grown enough since its last partial transcription
+    fn partial_due(&self, track: Option<&str>, buffer_len: usize) -> bool {
+        // Simulated transcriptions have nothing to show before the buffer is full
+        if !self.settings.partials.enabled || self.engine.read().is_none() {
+            return false;
+        }
+        
+        let interval = self.settings.partials.interval_ms as usize * SAMPLE_RATE / 1000;
+        let mut partials = self.partials.lock();
+        let (_, transcribed_len) = partials.entry(track.map(str::to_string)).or_default();
+        
+        if buffer_len >= *transcribed_len + interval {
+            *transcribed_len = buffer_len;
+            true
+        } else {
+            false
+        }
+    }
+    
+    /// Transcribe an unfinished buffer and send what it says so far as a partial result
+    async fn transcribe_partial(&self, window: Vec<f32>, track: Option<&str>) {
+        // Skip windows that are silent so far rather than running the engine on nothing
+        if self.settings.silence.enabled {
+            let trimmed = SilenceTrimmer::new(SAMPLE_RATE as u32, &self.settings.silence).trim(&window);
+            if matches!(trimmed, Trimmed::Silence { .. }) {
+                return;
+            }
+        }
+        
+        // A failed partial is only a missed caption update; the full buffer is still transcribed
+        let segments = match self.run_engine(&window).await {
+            Ok((segments, _)) => segments,
+            Err(e) => {
+                warn!("Partial transcription failed: {}", e);
+                return;
+            }
+        };
+        
+        let hypothesis = match self.partials.lock().get_mut(&track.map(str::to_string)) {
+            Some((stabilizer, _)) => stabilizer.update(&engine::segments_text(&segments)),
+            // The buffer was transcribed in full while this partial was running
+            None => return,
+        };
+        if hypothesis.text().is_empty() {
+            return;
+        }
+        
+        let event = TranscriptionEvent::PartialTranscription {
+            speaker: track.map(Speaker::for_source),
+            stable: self.profanity.apply(&hypothesis.stable),
+            volatile: self.profanity.apply(&hypothesis.volatile),
+        };
+        if let Err(e) = self.event_sender.send(event).await {
+            warn!("Failed to send partial transcription event: {}", e);
+        }
+    }
+    
+    /// Whether a stream's buffer is ready to transcribe after `audio_data` was added to it
+    fn segment_complete(&self, track: Option<&str>, audio_data: &[f32], buffer_len: usize) -> bool {
+        if !self.settings.segmentation.adaptive {
+            return buffer_len >= AUDIO_BUFFER_SECONDS * SAMPLE_RATE;
+        }
+        
+        self.segmenters.lock()
+            .entry(track.map(str::to_string))
+            .or_insert_with(|| SpeechSegmenter::new(SAMPLE_RATE as u32, &self.settings.segmentation))
+            .push(audio_data)
+    }
+    
+    /// Samples kept from the end of each buffer to start the next one
+    fn overlap_samples(&self) -> usize {
+        if self.settings.overlap.enabled {
+            (self.settings.overlap.duration_secs * SAMPLE_RATE as f32) as usize
+        } else {
+            0
+        }
+    }
+    
+    /// Trim silence from a buffer and transcribe what is left
+    ///
+    /// `carried` is the number of samples at the end of the buffer that start the next one.
+    async fn transcribe_segment(&self, audio_data: &[f32], track: Option<&str>, carried: usize) -> Result<Option<String>> {
+        // The buffer is complete, so its partial results are replaced by the full transcription
+        self.partials.lock().remove(&track.map(str::to_string));
+        
+        // Position of this buffer in its stream, for subtitle timestamps
+        let position = {
+            let mut positions = self.stream_positions.lock();
+            let position = positions.entry(track.map(str::to_string)).or_insert(0);
+            let start = *position;
+            *position += audio_data.len() - carried;
+            start
+        };
+        
+        if !self.settings.silence.enabled {
+            return self.transcribe_audio(audio_data, track, samples_to_duration(position)).await;
+        }
+        
+        match SilenceTrimmer::new(SAMPLE_RATE as u32, &self.settings.silence).trim(audio_data) {
+            Trimmed::Speech { start, samples } => {
+                self.transcribe_audio(samples, track, samples_to_duration(position + start)).await
+            }
+            Trimmed::Silence { level_db } => {
+                let duration_secs = audio_data.len() as f32 / SAMPLE_RATE as f32;
+                debug!("Skipping {:.1}s of silence ({:.1} dBFS)", duration_secs, level_db);
                 
-                for i in 0..num_segments {
-                    if let Ok(segment) = state.full_get_segment_text(i) {
-                        text.push_str(&segment);
-                        text.push(' ');
-                    }
+                let event = TranscriptionEvent::SkippedSilence { duration_secs, level_db };
+                if let Err(e) = self.event_sender.send(event).await {
+                    warn!("Failed to send skipped silence event: {}", e);
                 }
                 
-                let result = text.trim().to_string();
-                if result.is_empty() {
-                    Ok(None)
-                } else {
-                    Ok(Some(result))
-                }
-            }).await.context("Failed to run transcription task")?;
-            
-            // Handle the transcription result
-            match transcription {
-                Ok(Some(text)) => {
-                    // Update current text
-                    {
-                        let mut current = self.current_text.lock();
-                        *current = text.clone();
-                    }
+                Ok(None)
+            }
+        }
+    }
+    
+    /// Transcribe audio data starting `offset` into its stream
+    async fn transcribe_audio(&self, audio_data: &[f32], track: Option<&str>, offset: Duration) -> Result<Option<String>> {
+        // Streams are kept apart by source, and only labelled with who is talking on them
+        let speaker = track.map(Speaker::for_source);
+        if self.engine.read().is_none() {
+            return self.simulate_transcription(speaker).await;
+        }
+        
+        let (segments, dropped) = self.run_engine(audio_data).await?;
+        if !dropped.is_empty() {
+            debug!("Dropped {} hallucinated segment(s): {:?}", dropped.len(), dropped);
+            self.hallucinations.lock().record(&dropped);
+        }
+        let text = engine::segments_text(&segments);
+        let confidence = engine::segments_confidence(&segments);
+        let language = engine::segments_language(&segments);
+        
+        // Drop the words already transcribed from the audio shared with the previous buffer
+        let (previous_text, previous_end) = if self.settings.overlap.enabled && !text.is_empty() {
+            let end = segments.last().map_or(offset, |segment| offset + segment.end);
+            self.previous_segments.lock()
+                .insert(track.map(str::to_string), (text.clone(), end))
+                .unwrap_or_default()
+        } else {
+            (String::new(), Duration::ZERO)
+        };
+        let text = overlap::overlap_suffix(&previous_text, &text, self.settings.overlap.duration_secs);
+        
+        if text.is_empty() {
+            return Ok(None);
+        }
+        let text = self.pipeline.process(&text);
+        if text.trim().is_empty() {
+            return Ok(None);
+        }
+        
+        let start = segments.first().map_or(offset, |segment| offset + segment.start);
+        let end = segments.last().map_or(offset, |segment| offset + segment.end);
+        
+        // Without per-speaker tracks, guess at speaker changes from pauses and shifts in the voice
+        let speaker_change = (self.settings.speaker_change.enabled && speaker.is_none()
+            && self.speaker_changes.lock().detect(VoiceProfile::measure(audio_data, SAMPLE_RATE as u32), start, end))
+            .then(|| self.settings.speaker_change.marker.clone());
+        
+        // Format the text as part of the stream's transcript, keeping any paragraph or line break before it
+        let text = if self.settings.context_formatting {
+            let mut formatters = self.formatters.lock();
+            let formatter = formatters.entry(track.map(str::to_string)).or_insert_with(|| TranscriptFormatter::new(&self.settings.formatting));
+            if let Some(marker) = speaker_change {
+                formatter.mark_speaker_change(marker);
+            }
+            let fragment = formatter.push(&text, start, end);
+            label_fragment(speaker, &fragment).trim_start_matches(' ').to_string()
+        } else {
+            match speaker_change {
+                Some(marker) => format!("{}{}{}", marker.separator(), marker.prefix(), text),
+                None => label_segment(speaker, text),
+            }
+        };
+        
+        // Update current text
+        {
+            let mut current = self.current_text.lock();
+            *current = text.clone();
+        }
+        if let Some(session) = self.session.lock().as_mut() {
+            session.push(start, end, speaker.map(|s| s.to_string()), text.clone(), confidence);
+        }
+        if let Some(output) = self.live_output.lock().as_mut() {
+            if let Err(e) = output.append(&text) {
+                warn!("Failed to append to live output file: {}", e);
+            }
+        }
+        
+        // Handle post-processing
+        if self.settings.save_transcription {
+            let cues: Vec<SubtitleCue> = segments.into_iter()
+                .filter(|segment| offset + segment.end > previous_end)
+                .flat_map(|segment| {
+                    let (start, end) = (offset + segment.start, offset + segment.end);
                     
-                    // Handle post-processing
-                    if self.settings.save_transcription {
-                        if let Err(e) = self.save_transcription(&text).await {
-                            warn!("Failed to save transcription: {}", e);
-                        }
+                    // A segment running into the shared audio keeps only its new words
+                    let text = if start < previous_end {
+                        overlap::overlap_suffix(&previous_text, &segment.text, self.settings.overlap.duration_secs)
+                    } else {
+                        segment.text.trim().to_string()
+                    };
+                    let text = self.profanity.apply(&text);
+                    if text.trim().is_empty() {
+                        return Vec::new();
                     }
                     
-                    // Send transcription event
-                    if let Err(e) = self.event_sender.send(TranscriptionEvent::Transcription(text.clone())).await {
-                        warn!("Failed to send transcription event: {}", e);
+                    let text = label_segment(speaker, text);
+                    subtitle::split_segment(start.max(previous_end), end, &text, &self.settings.subtitles)
+                })
+                .collect();
+            
+            let segment = TranscriptFileSegment {
+                start_ms: start.as_millis() as u64,
+                end_ms: end.as_millis() as u64,
+                text: text.clone(),
+                confidence,
+                language: language.as_ref().map(|language| language.language.clone()),
+                speaker: speaker.map(|s| s.to_string()),
+            };
+            if let Err(e) = self.save_transcription(segment, cues).await {
+                warn!("Failed to save transcription: {}", e);
+            }
+        }
+        
+        // Report the detected language, so a wrong guess is visible
+        if let Some(language) = language {
+            debug!("Detected language {} ({:?})", language.language, language.probability);
+            if let Err(e) = self.event_sender.send(TranscriptionEvent::LanguageDetected { speaker, language }).await {
+                warn!("Failed to send language detection event: {}", e);
+            }
+        }
+        
+        // Send transcription event
+        let event = TranscriptionEvent::Transcription { text: text.clone(), confidence };
+        if let Err(e) = self.event_sender.send(event).await {
+            warn!("Failed to send transcription event: {}", e);
+        }
+        
+        // The refined text follows when the model answers, without holding up the next buffer
+        if let Some(refiner) = self.refiner.clone() {
+            let event_sender = self.event_sender.clone();
+            let original = text.clone();
+            tokio::spawn(async move {
+                match refiner.cleanup(&original).await {
+                    Ok(refined) => {
+                        let event = TranscriptionEvent::Refined { kind: RefineKind::Cleanup, original, text: refined };
+                        if let Err(e) = event_sender.send(event).await {
+                            warn!("Failed to send refined transcription event: {}", e);
+                        }
                     }
-                    
-                    Ok(Some(text))
-                },
-                Ok(None) => Ok(None),
-                Err(e) => {
-                    // Forward the error
-                    Err(e)
+                    Err(e) => warn!("Failed to refine transcription: {}", e),
                 }
+            });
+        }
+        
+        // Flag doubtful text for review
+        if let Some(confidence) = confidence.filter(|c| self.settings.confidence.flag_low && *c < self.settings.confidence.threshold) {
+            debug!("Low confidence transcription ({:.2}): {}", confidence, text);
+            let event = TranscriptionEvent::LowConfidence { text: text.clone(), confidence };
+            if let Err(e) = self.event_sender.send(event).await {
+                warn!("Failed to send low confidence event: {}", e);
             }
-        } else {
-            self.simulate_transcription().await
         }
+        
+        Ok(Some(text))
     }
     
-    /// Transcribe audio data (simulation when whisper is not enabled)
-    #[cfg(not(feature = "whisper"))]
-    async fn transcribe_audio(&self, _audio_data: &[f32]) -> Result<Option<String>> {
-        // Simulation mode - generate some fake transcription
-        self.simulate_transcription().await
+    /// Run the engine over audio data, returning the segments kept and why any others were dropped
+    async fn run_engine(&self, audio_data: &[f32]) -> Result<(Vec<TranscriptSegment>, Vec<Hallucination>)> {
+        // Engines are CPU-bound, so run them on a blocking task with an owned copy of the audio
+        let engine = Arc::clone(&self.engine);
+        let options = TranscribeOptions::from_settings(&self.settings);
+        let audio = audio_data.to_vec();
+        
+        let segments = tokio::task::spawn_blocking(move || match engine.read().as_ref() {
+            Some(engine) => engine.transcribe(&audio, &options),
+            None => Ok(Vec::new()),
+        }).await.context("Failed to run transcription task")??;
+        
+        Ok(hallucination::filter_segments(segments, audio_data, &self.settings.hallucinations))
     }
     
     /// Generate a simulated transcription for testing
-    async fn simulate_transcription(&self) -> Result<Option<String>> {
+    async fn simulate_transcription(&self, speaker: Option<Speaker>) -> Result<Option<String>> {
         // Add a small delay to simulate processing time
         tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
         
-        let fake_text = format!("This is a simulated transcription with the {} model", 
-                              self.get_model_size_string());
+        let fake_text = label_segment(speaker, format!("This is a simulated transcription with the {} model", 
+                              self.get_model_size_string()));
         
         // Update current text
         {
@@ -402,7 +931,8 @@ impl TranscriptionManager {
         }
         
         // Send the simulated text
-        if let Err(e) = self.event_sender.send(TranscriptionEvent::Transcription(fake_text.clone())).await {
+        let event = TranscriptionEvent::Transcription { text: fake_text.clone(), confidence: None };
+        if let Err(e) = self.event_sender.send(event).await {
             warn!("Failed to send simulated transcription: {}", e);
         }
         
@@ -410,16 +940,16 @@ impl TranscriptionManager {
     }
     
     /// Save transcription to file
-    async fn save_transcription(&self, text: &str) -> Result<()> {
+    ///
+    /// Subtitle formats collect `cues`, and JSON collects segments, into one file
+    /// per session, rewritten as they arrive.
+    async fn save_transcription(&self, segment: TranscriptFileSegment, cues: Vec<SubtitleCue>) -> Result<()> {
         if !self.settings.save_transcription {
             return Ok(());
         }
         
         // Create transcription directory if it doesn't exist
-        let project_dirs = directories::ProjectDirs::from("com", "bestme", "BestMe")
-            .context("Failed to determine project directories")?;
-        
-        let transcription_dir = project_dirs.data_dir().join("transcriptions");
+        let transcription_dir = TranscriptFile::default_dir()?;
         
         if !transcription_dir.exists() {
             std::fs::create_dir_all(&transcription_dir)
@@ -428,34 +958,50 @@ impl TranscriptionManager {
         
         // Create a filename with timestamp
         let now = chrono::Local::now();
-        let filename = match self.settings.output_format.as_str() {
-            "json" => format!("transcription_{}.json", now.format("%Y%m%d_%H%M%S")),
-            _ => format!("transcription_{}.txt", now.format("%Y%m%d_%H%M%S")),
-        };
         
-        let file_path = transcription_dir.join(filename);
+        if let Some(format) = SubtitleFormat::from_output_format(&self.settings.output_format) {
+            let (contents, file_path) = {
+                let mut subtitles = self.subtitles.lock();
+                let (session_cues, session_file) = &mut *subtitles;
+                
+                // Speaker tracks are transcribed separately, so keep cues in time order
+                session_cues.extend(cues);
+                session_cues.sort_by_key(|cue| cue.start);
+                
+                let file_path = session_file.get_or_insert_with(|| {
+                    transcription_dir.join(format!("transcription_{}.{}", now.format("%Y%m%d_%H%M%S"), format.extension()))
+                });
+                (subtitle::render(session_cues, format), file_path.clone())
+            };
+            
+            tokio::fs::write(file_path, contents)
+                .await
+                .context("Failed to write subtitle file")?;
+            return Ok(());
+        }
         
-        // Write the file
-        match self.settings.output_format.as_str() {
-            "json" => {
-                let json = serde_json::json!({
-                    "timestamp": now.to_rfc3339(),
-                    "text": text,
-                    "model": self.get_model_size_string(),
-                    "language": if self.settings.language.is_empty() { "auto" } else { &self.settings.language },
+        if self.settings.output_format == "json" {
+            let (contents, file_path) = {
+                let mut json_transcript = self.json_transcript.lock();
+                let (transcript, file_path) = json_transcript.get_or_insert_with(|| {
+                    let file_path = transcription_dir.join(format!("transcription_{}.json", now.format("%Y%m%d_%H%M%S")));
+                    (TranscriptFile::new(&self.settings), file_path)
                 });
-                
-                tokio::fs::write(file_path, serde_json::to_string_pretty(&json)?)
-                    .await
-                    .context("Failed to write JSON transcription file")?;
-            },
-            _ => {
-                tokio::fs::write(file_path, text)
-                    .await
-                    .context("Failed to write TXT transcription file")?;
-            }
+                transcript.push(segment);
+                (serde_json::to_string_pretty(transcript)?, file_path.clone())
+            };
+            
+            tokio::fs::write(file_path, contents)
+                .await
+                .context("Failed to write JSON transcription file")?;
+            return Ok(());
         }
         
+        let file_path = transcription_dir.join(format!("transcription_{}.txt", now.format("%Y%m%d_%H%M%S")));
+        tokio::fs::write(file_path, &segment.text)
+            .await
+            .context("Failed to write TXT transcription file")?;
+        
         Ok(())
     }
     
@@ -464,15 +1010,54 @@ impl TranscriptionManager {
         self.current_text.lock().clone()
     }
     
+    /// Get the running session, or the last one once stopped
+    pub fn current_session(&self) -> Option<Session> {
+        self.session.lock().clone()
+    }
+    
+    /// Get the number of segments dropped as hallucinations this session
+    pub fn get_hallucination_stats(&self) -> HallucinationStats {
+        *self.hallucinations.lock()
+    }
+    
+    /// Refine automatic punctuation with a model
+    pub fn set_punctuation_model(&mut self, model: Arc<dyn PunctuationModel>) {
+        self.punctuator = Punctuator::with_model(model);
+        self.rebuild_pipeline();
+    }
+    
+    /// Add a post-processing step, run where the settings list it by name or else after the others
+    pub fn add_text_processor(&mut self, step: Arc<dyn TextProcessor>) {
+        self.custom_steps.push(step);
+        self.rebuild_pipeline();
+    }
+    
+    /// Summarize the running session, or the last one once stopped, with the configured language model
+    pub async fn summarize_session(&self) -> Result<String> {
+        let session = self.current_session().ok_or_else(|| anyhow::anyhow!("No session to summarize"))?;
+        let summary = Refiner::new(self.settings.refine.clone())?.summarize(&session).await?;
+        
+        let event = TranscriptionEvent::Refined { kind: RefineKind::Summary, original: session.text(), text: summary.clone() };
+        if let Err(e) = self.event_sender.send(event).await {
+            warn!("Failed to send session summary event: {}", e);
+        }
+        Ok(summary)
+    }
+    
+    /// Names of the post-processing steps applied to final text, in order
+    pub fn post_processing_steps(&self) -> Vec<&str> {
+        self.pipeline.steps()
+    }
+    
+    fn rebuild_pipeline(&mut self) {
+        self.pipeline = TextPipeline::from_settings(&self.settings, self.punctuator.clone(), &self.custom_steps);
+        self.profanity = ProfanityFilter::new(&self.settings.profanity);
+        self.refiner = utterance_refiner(&self.settings.refine);
+    }
+    
     /// Get model size string
     pub fn get_model_size_string(&self) -> &'static str {
-        match self.settings.model_size {
-            WhisperModelSize::Tiny => "tiny",
-            WhisperModelSize::Base => "base",
-            WhisperModelSize::Small => "small",
-            WhisperModelSize::Medium => "medium",
-            WhisperModelSize::Large => "large",
-        }
+        self.settings.model_size.name()
     }
     
     /// Get the transcription settings
@@ -483,6 +1068,7 @@ impl TranscriptionManager {
     /// Update the transcription settings
     pub fn update_settings(&mut self, settings: SpeechSettings) {
         self.settings = settings;
+        self.rebuild_pipeline();
     }
     
     // Add an alias method for compatibility
@@ -491,3 +1077,80 @@ impl TranscriptionManager {
         self.get_model_size_string()
     }
 } 
+
+/// Language model cleanup of final text, when refinement is on
+fn utterance_refiner(settings: &RefineSettings) -> Option<Refiner> {
+    if !settings.enabled {
+        return None;
+    }
+    
+    match Refiner::new(settings.clone()) {
+        Ok(refiner) => Some(refiner),
+        Err(e) => {
+            warn!("Refinement disabled: {}", e);
+            None
+        }
+    }
+}
+
+/// Time covered by a number of 16 kHz samples
+fn samples_to_duration(samples: usize) -> Duration {
+    Duration::from_secs_f64(samples as f64 / SAMPLE_RATE as f64)
+}
+
+/// Prefix a transcribed segment with its speaker, if the audio came from a labelled track
+pub fn label_segment(speaker: Option<Speaker>, text: String) -> String {
+    match speaker {
+        Some(speaker) => format!("{}: {}", speaker, text),
+        None => text,
+    }
+}
+
+/// Label formatted text with its speaker, after the separator it starts with
+pub fn label_fragment(speaker: Option<Speaker>, fragment: &str) -> String {
+    let text = fragment.trim_start();
+    let separator = &fragment[..fragment.len() - text.len()];
+    format!("{}{}", separator, label_segment(speaker, text.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loopback_tracks_are_others() {
+        assert_eq!(Speaker::for_source("loopback:speakers"), Speaker::Others);
+        assert_eq!(Speaker::for_source("wasapi:microphone"), Speaker::Me);
+        assert_eq!(Speaker::for_source("default"), Speaker::Me);
+
+        assert_eq!(label_segment(Some(Speaker::Others), "hello".to_string()), "Others: hello");
+        assert_eq!(label_segment(None, "hello".to_string()), "hello");
+    }
+
+    #[tokio::test]
+    async fn test_two_inputs_for_one_speaker_are_buffered_apart() {
+        let mut settings = crate::config::Config::default().audio.speech;
+        settings.model_path = Some(std::env::temp_dir().to_string_lossy().into_owned());
+        settings.silence.enabled = false;
+        let (mut manager, _events) = TranscriptionManager::new(settings).unwrap();
+        manager.state = TranscriptionState::Transcribing;
+
+        // Two microphones are both "Me", but their audio must not be interleaved into one buffer
+        manager.process_track("wasapi:headset", &[0.1; 1600]).await.unwrap();
+        manager.process_track("wasapi:desk-mic", &[0.2; 800]).await.unwrap();
+        manager.process_track("wasapi:headset", &[0.1; 1600]).await.unwrap();
+        {
+            let tracks = manager.track_buffers.lock();
+            assert_eq!(tracks.len(), 2);
+            assert_eq!(tracks["wasapi:headset"], vec![0.1; 3200]);
+            assert_eq!(tracks["wasapi:desk-mic"], vec![0.2; 800]);
+        }
+
+        // Each input is transcribed on its own, and both are labelled with their speaker
+        let segments = manager.flush_tracks().await.unwrap();
+        assert_eq!(segments.len(), 2);
+        assert!(segments.iter().all(|text| text.starts_with("Me: ")));
+        assert_eq!(manager.stream_positions.lock()[&Some("wasapi:headset".to_string())], 3200);
+        assert_eq!(manager.stream_positions.lock()[&Some("wasapi:desk-mic".to_string())], 800);
+    }
+}
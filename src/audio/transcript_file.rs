@@ -0,0 +1,202 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use super::session::{Session, SessionSegment};
+use crate::config::SpeechSettings;
+
+/// Version of the JSON transcript format written by this build
+///
+/// Version 1 files, from before versions were written, held the text of a
+/// single buffer; they can still be read.
+pub const TRANSCRIPT_FILE_VERSION: u32 = 2;
+
+/// A stretch of a JSON transcript
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptFileSegment {
+    /// Start of the speech, in milliseconds from the start of transcription
+    pub start_ms: u64,
+
+    /// End of the speech, in milliseconds from the start of transcription
+    pub end_ms: u64,
+
+    /// The text as it appears in the transcript, including any line or paragraph break before it
+    pub text: String,
+
+    /// The engine's confidence in the text (0.0 - 1.0), if it reports one
+    pub confidence: Option<f32>,
+
+    /// Language the engine detected, if it reports one
+    pub language: Option<String>,
+
+    /// Who said it, when audio was captured as per-speaker tracks
+    pub speaker: Option<String>,
+}
+
+/// A transcript written as JSON, one file per run of transcription
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptFile {
+    /// Format version, see [`TRANSCRIPT_FILE_VERSION`]
+    pub version: u32,
+
+    /// When transcription started
+    pub created: DateTime<Local>,
+
+    /// Model the transcript was made with, e.g. "small-q8_0"
+    pub model: String,
+
+    /// Language the model was told to expect ("auto" when detected)
+    pub language: String,
+
+    /// The transcript, in the order it was written
+    pub segments: Vec<TranscriptFileSegment>,
+}
+
+/// What version 1 files held: the text of one buffer
+#[derive(Deserialize)]
+struct TranscriptFileV1 {
+    timestamp: DateTime<Local>,
+    text: String,
+    model: String,
+    language: String,
+    detected_language: Option<Value>,
+}
+
+impl TranscriptFile {
+    /// Start a transcript made with `settings`
+    pub fn new(settings: &SpeechSettings) -> Self {
+        Self {
+            version: TRANSCRIPT_FILE_VERSION,
+            created: Local::now(),
+            model: settings.model_name(),
+            language: if settings.language.is_empty() { "auto".to_string() } else { settings.language.clone() },
+            segments: Vec::new(),
+        }
+    }
+
+    /// Transcripts directory in the application data folder
+    pub fn default_dir() -> Result<PathBuf> {
+        let project_dirs = directories::ProjectDirs::from("com", "bestme", "BestMe")
+            .context("Failed to determine project directories")?;
+        Ok(project_dirs.data_dir().join("transcriptions"))
+    }
+
+    /// Add a segment after the others
+    pub fn push(&mut self, segment: TranscriptFileSegment) {
+        self.segments.push(segment);
+    }
+
+    /// Read a transcript of this or an earlier version
+    pub fn parse(json: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(json)?;
+        match value.get("version").map(Value::as_u64) {
+            None => {
+                let old: TranscriptFileV1 = serde_json::from_value(value)?;
+                let language = old.detected_language.as_ref()
+                    .and_then(|detected| detected["language"].as_str())
+                    .map(|language| language.to_string());
+                Ok(Self {
+                    version: TRANSCRIPT_FILE_VERSION,
+                    created: old.timestamp,
+                    model: old.model,
+                    language: old.language,
+                    segments: vec![TranscriptFileSegment {
+                        start_ms: 0,
+                        end_ms: 0,
+                        text: old.text,
+                        confidence: None,
+                        language,
+                        speaker: None,
+                    }],
+                })
+            }
+            Some(Some(version)) if version <= TRANSCRIPT_FILE_VERSION as u64 => Ok(serde_json::from_value(value)?),
+            Some(Some(version)) => Err(anyhow!("Transcript version {} is newer than this version of BestMe supports", version)),
+            Some(None) => Err(anyhow!("Invalid transcript version")),
+        }
+    }
+
+    /// Read a transcript file
+    pub fn read(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read transcript {}", path.display()))?;
+        Self::parse(&json).with_context(|| format!("Invalid transcript {}", path.display()))
+    }
+
+    /// The transcript as a session, e.g. for the history
+    pub fn into_session(self) -> Session {
+        Session {
+            id: self.created.format("%Y%m%d_%H%M%S_%3f").to_string(),
+            title: None,
+            started: self.created,
+            ended: self.segments.last().map(|segment| self.created + chrono::Duration::milliseconds(segment.end_ms as i64)),
+            device: None,
+            model: self.model,
+            language: self.language,
+            segments: self.segments.into_iter()
+                .map(|segment| SessionSegment {
+                    start_ms: segment.start_ms,
+                    end_ms: segment.end_ms,
+                    speaker: segment.speaker,
+                    text: segment.text,
+                    confidence: segment.confidence,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_round_trip() {
+        let mut transcript = TranscriptFile::new(&Config::default().audio.speech);
+        transcript.push(TranscriptFileSegment {
+            start_ms: 1200,
+            end_ms: 3400,
+            text: "Hello there.".to_string(),
+            confidence: Some(0.9),
+            language: Some("en".to_string()),
+            speaker: Some("Me".to_string()),
+        });
+
+        let json = serde_json::to_string_pretty(&transcript).unwrap();
+        assert!(json.contains("\"version\": 2"));
+        assert_eq!(TranscriptFile::parse(&json).unwrap(), transcript);
+
+        let session = transcript.clone().into_session();
+        assert_eq!(session.text(), "Hello there.");
+        assert_eq!(session.segments[0].speaker.as_deref(), Some("Me"));
+        assert_eq!(session.ended, Some(transcript.created + chrono::Duration::milliseconds(3400)));
+
+        let newer = json.replace("\"version\": 2", "\"version\": 3");
+        assert!(TranscriptFile::parse(&newer).is_err());
+    }
+
+    #[test]
+    fn test_reads_version_1() {
+        let json = r#"{
+            "timestamp": "2024-03-01T10:15:30.123+01:00",
+            "text": "An old transcript.",
+            "model": "small",
+            "language": "auto",
+            "detected_language": { "language": "en", "probability": 0.97 }
+        }"#;
+
+        let transcript = TranscriptFile::parse(json).unwrap();
+        assert_eq!(transcript.version, TRANSCRIPT_FILE_VERSION);
+        assert_eq!(transcript.model, "small");
+        assert_eq!(transcript.segments.len(), 1);
+        assert_eq!(transcript.segments[0].text, "An old transcript.");
+        assert_eq!(transcript.segments[0].language.as_deref(), Some("en"));
+
+        let session = transcript.into_session();
+        assert_eq!(session.text(), "An old transcript.");
+        assert_eq!(session.id.len(), "20240301_101530_123".len());
+    }
+}
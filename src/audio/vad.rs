@@ -0,0 +1,230 @@
+use log::debug;
+
+/// Length of a single analysis frame in milliseconds
+const FRAME_MS: u32 = 20;
+
+/// Number of consecutive voiced frames required before speech is reported
+const ONSET_FRAMES: u32 = 2;
+
+/// Absolute RMS floor below which a frame is never considered speech
+const MIN_SPEECH_RMS: f32 = 0.002;
+
+/// Smoothing factor for the adaptive noise floor estimate
+const NOISE_FLOOR_ALPHA: f32 = 0.05;
+
+/// Speech state change reported by the detector
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VadTransition {
+    /// No change in speech state
+    None,
+    /// Speech has started
+    SpeechStart,
+    /// Speech has ended (after the hangover period)
+    SpeechEnd,
+}
+
+/// Energy-based voice activity detector with an adaptive noise floor
+///
+/// Audio is analysed in short frames. A frame is voiced when its RMS energy
+/// rises sufficiently above the running noise floor estimate. Speech is kept
+/// "open" for a hangover period after the last voiced frame so word endings
+/// and short pauses are not cut off.
+#[derive(Debug, Clone)]
+pub struct VoiceActivityDetector {
+    /// Samples per analysis frame (interleaved, all channels)
+    frame_len: usize,
+
+    /// Ratio above the noise floor required to count a frame as voiced
+    threshold_ratio: f32,
+
+    /// Number of unvoiced frames to wait before ending speech
+    hangover_frames: u32,
+
+    /// Current noise floor estimate (RMS)
+    noise_floor: f32,
+
+    /// Consecutive voiced frames seen while not speaking
+    onset_count: u32,
+
+    /// Remaining hangover frames while speaking
+    hangover_remaining: u32,
+
+    /// Whether speech is currently active
+    speaking: bool,
+
+    /// Samples carried over from the previous call that did not fill a frame
+    pending: Vec<f32>,
+}
+
+impl VoiceActivityDetector {
+    /// Create a new detector
+    ///
+    /// `sensitivity` ranges from 0.0 (only loud speech) to 1.0 (very quiet speech).
+    pub fn new(sample_rate: u32, channels: u16, sensitivity: f32, hangover_ms: u32) -> Self {
+        let frame_len = ((sample_rate * FRAME_MS / 1000) as usize * channels.max(1) as usize).max(1);
+        let sensitivity = sensitivity.clamp(0.0, 1.0);
+
+        Self {
+            frame_len,
+            // 1.5x the noise floor at maximum sensitivity, 6x at minimum
+            threshold_ratio: 1.5 + (1.0 - sensitivity) * 4.5,
+            hangover_frames: (hangover_ms / FRAME_MS).max(1),
+            noise_floor: MIN_SPEECH_RMS,
+            onset_count: 0,
+            hangover_remaining: 0,
+            speaking: false,
+            pending: Vec::with_capacity(frame_len),
+        }
+    }
+
+    /// Feed interleaved samples to the detector and return any state change
+    pub fn process(&mut self, samples: &[f32]) -> VadTransition {
+        let was_speaking = self.speaking;
+
+        let mut offset = 0;
+
+        // Complete a frame left over from the previous call first
+        if !self.pending.is_empty() {
+            let needed = self.frame_len - self.pending.len();
+            let take = needed.min(samples.len());
+            self.pending.extend_from_slice(&samples[..take]);
+            offset = take;
+
+            if self.pending.len() == self.frame_len {
+                let rms = frame_rms(&self.pending);
+                self.pending.clear();
+                self.process_frame(rms);
+            }
+        }
+
+        while offset + self.frame_len <= samples.len() {
+            let rms = frame_rms(&samples[offset..offset + self.frame_len]);
+            self.process_frame(rms);
+            offset += self.frame_len;
+        }
+
+        self.pending.extend_from_slice(&samples[offset..]);
+
+        match (was_speaking, self.speaking) {
+            (false, true) => VadTransition::SpeechStart,
+            (true, false) => VadTransition::SpeechEnd,
+            _ => VadTransition::None,
+        }
+    }
+
+    /// Update detector state with the energy of one frame
+    fn process_frame(&mut self, rms: f32) {
+        let voiced = rms > MIN_SPEECH_RMS && rms > self.noise_floor * self.threshold_ratio;
+
+        if voiced {
+            if self.speaking {
+                self.hangover_remaining = self.hangover_frames;
+            } else {
+                self.onset_count += 1;
+                if self.onset_count >= ONSET_FRAMES {
+                    debug!("VAD speech start (rms {:.4}, floor {:.4})", rms, self.noise_floor);
+                    self.speaking = true;
+                    self.onset_count = 0;
+                    self.hangover_remaining = self.hangover_frames;
+                }
+            }
+        } else {
+            self.onset_count = 0;
+
+            // Only adapt the noise floor on unvoiced frames so speech does not raise it
+            self.noise_floor += (rms.max(MIN_SPEECH_RMS) - self.noise_floor) * NOISE_FLOOR_ALPHA;
+
+            if self.speaking {
+                self.hangover_remaining = self.hangover_remaining.saturating_sub(1);
+                if self.hangover_remaining == 0 {
+                    debug!("VAD speech end (floor {:.4})", self.noise_floor);
+                    self.speaking = false;
+                }
+            }
+        }
+    }
+
+    /// Check if speech is currently active
+    pub fn is_speaking(&self) -> bool {
+        self.speaking
+    }
+
+    /// Get the current noise floor estimate
+    pub fn noise_floor(&self) -> f32 {
+        self.noise_floor
+    }
+
+    /// Reset the detector to its initial state
+    pub fn reset(&mut self) {
+        self.noise_floor = MIN_SPEECH_RMS;
+        self.onset_count = 0;
+        self.hangover_remaining = 0;
+        self.speaking = false;
+        self.pending.clear();
+    }
+}
+
+/// Calculate the RMS energy of a frame
+fn frame_rms(frame: &[f32]) -> f32 {
+    if frame.is_empty() {
+        return 0.0;
+    }
+
+    let sum: f32 = frame.iter().map(|s| s * s).sum();
+    (sum / frame.len() as f32).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(len: usize, amplitude: f32) -> Vec<f32> {
+        (0..len)
+            .map(|i| (i as f32 * 0.1).sin() * amplitude)
+            .collect()
+    }
+
+    #[test]
+    fn test_silence_is_not_speech() {
+        let mut vad = VoiceActivityDetector::new(16000, 1, 0.5, 200);
+
+        assert_eq!(vad.process(&vec![0.0; 16000]), VadTransition::None);
+        assert!(!vad.is_speaking());
+    }
+
+    #[test]
+    fn test_speech_start_and_end() {
+        let mut vad = VoiceActivityDetector::new(16000, 1, 0.5, 200);
+
+        // Let the noise floor settle on low-level noise
+        vad.process(&tone(8000, 0.001));
+
+        // Loud tone should start speech
+        assert_eq!(vad.process(&tone(3200, 0.5)), VadTransition::SpeechStart);
+        assert!(vad.is_speaking());
+
+        // Short pause within the hangover keeps speech open
+        assert_eq!(vad.process(&vec![0.0; 1600]), VadTransition::None);
+        assert!(vad.is_speaking());
+
+        // Silence longer than the hangover ends speech
+        assert_eq!(vad.process(&vec![0.0; 8000]), VadTransition::SpeechEnd);
+        assert!(!vad.is_speaking());
+    }
+
+    #[test]
+    fn test_partial_frames_are_carried_over() {
+        let mut vad = VoiceActivityDetector::new(16000, 1, 0.5, 200);
+
+        // Feed a loud signal in chunks smaller than a frame
+        let loud = tone(3200, 0.5);
+        let mut started = false;
+        for chunk in loud.chunks(100) {
+            if vad.process(chunk) == VadTransition::SpeechStart {
+                started = true;
+            }
+        }
+
+        assert!(started);
+    }
+}
@@ -1,10 +1,27 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use log::{info, debug};
+use std::time::{Duration, Instant};
+use log::{info, debug, warn};
 use anyhow::Result;
 use parking_lot::Mutex;
 use tokio::sync::mpsc;
 use crate::config::SpeechSettings;
+use crate::audio::active_window::ActiveWindow;
+use crate::audio::app_control::{platform_app_control, AppControl, AppControlSettings};
+use crate::audio::command_grammar::{CommandGrammar, CommandPattern, ParameterKind};
+use crate::audio::command_plugin::CommandPlugin;
+use crate::audio::command_script::CommandScript;
+use crate::audio::command_stats::CommandStats;
+use crate::audio::injection::{platform_sink, InjectedText, OutputSink};
+use crate::audio::keystroke::{platform_keyboard, KeyChord, Keyboard};
+use crate::audio::script_hooks::ScriptHooks;
+use crate::audio::shell_command::{ShellCommand, ShellCommandOutput, ShellCommandSettings};
+use crate::audio::spelling::spell;
+use crate::audio::system_control::{platform_system_control, SystemAction, SystemControl};
+use crate::audio::wake_word::{WakeWordSettings, MAX_ARMED_SECS};
 use serde::{Deserialize, Serialize};
 use chrono;
 
@@ -14,10 +31,48 @@ pub enum VoiceCommandEvent {
     /// A command was detected
     CommandDetected(VoiceCommand),
     
+    /// Switched to another mode, by voice or from the application
+    ModeChanged(CommandMode),
+    
+    /// Switched to another profile, by its name
+    ProfileChanged(String),
+    
+    /// Went to sleep, hearing nothing but "wake up", or woke up
+    SleepChanged(bool),
+    
+    /// What was going on was cancelled: the utterance, spelling, the wake word and running programs
+    Cancelled,
+    
+    /// Another window got the focus, changing which commands are heard
+    ContextChanged(ActiveWindow),
+    
+    /// A shell command started by a voice command has exited
+    ShellCommandFinished(ShellCommandOutput),
+    
+    /// Asked what can be said, with the commands heard now
+    HelpRequested(Vec<CommandCategory>),
+    
+    /// The text being edited was changed by a command, with all of it as it is now
+    TextUpdated(String),
+    
     /// Error processing commands
     Error(String),
 }
 
+/// What transcriptions are scanned for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum CommandMode {
+    /// Only text is dictated; no commands are heard but switching modes
+    Dictation,
+    
+    /// Everything said is a command, without the prefix
+    Command,
+    
+    /// Commands are heard among dictated text
+    #[default]
+    Mixed,
+}
+
 /// Types of voice commands
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum VoiceCommandType {
@@ -27,10 +82,17 @@ pub enum VoiceCommandType {
     Redo,
     Capitalize,
     Lowercase,
+    Uppercase,
+    InsertText,
+    
+    /// Replace the last words sounding like what was said, e.g. "correct gradle to Gradle"
+    Replace,
     
     /// Text navigation commands
     NewLine,
     NewParagraph,
+    GoToLine,
+    Select,
     
     /// Punctuation commands
     Period,
@@ -43,10 +105,169 @@ pub enum VoiceCommandType {
     Resume,
     Stop,
     
+    /// Hear nothing but "wake up" until it is said, and let no dictation through
+    Sleep,
+    Wake,
+    
+    /// Spelling mode, where words are read as letters until it ends
+    StartSpelling,
+    StopSpelling,
+    
+    /// Switch to a mode, e.g. "dictation mode"
+    SetMode(CommandMode),
+    
+    /// Switch to a named profile, e.g. "switch to coding profile"
+    SwitchProfile,
+    
+    /// List the commands that can be said, e.g. "what can I say"
+    Help,
+    
+    /// Report the last command as heard by mistake, e.g. "that was wrong"
+    ReportMistake,
+    
+    /// Drop what was said with it and stop what is going on, e.g. "cancel"
+    Cancel,
+    
+    /// Application commands
+    LaunchApp,
+    SwitchWindow,
+    
+    /// System volume and media playback commands
+    System(SystemAction),
+    
+    /// A keystroke for the application to press, e.g. "ctrl+s"
+    Keystroke,
+    
+    /// Run a program from the shell command settings, by name
+    RunShellCommand,
+    
+    /// Several commands run in order, undone together
+    Macro,
+    
     /// Custom command
     Custom(String),
 }
 
+/// Categories commands are listed in, in order
+const CATEGORIES: [&str; 6] = ["Editing", "Punctuation", "Dictation", "Applications", "System", "Custom"];
+
+impl VoiceCommandType {
+    /// The category the command is listed in
+    pub fn category(&self) -> &'static str {
+        match self {
+            Self::Delete | Self::Undo | Self::Redo | Self::Capitalize | Self::Lowercase
+            | Self::Uppercase | Self::InsertText | Self::Replace | Self::GoToLine | Self::Select => "Editing",
+            Self::NewLine | Self::NewParagraph | Self::Period | Self::Comma
+            | Self::QuestionMark | Self::ExclamationMark => "Punctuation",
+            Self::Pause | Self::Resume | Self::Stop | Self::Sleep | Self::Wake | Self::StartSpelling | Self::StopSpelling
+            | Self::SetMode(_) | Self::SwitchProfile | Self::Help | Self::ReportMistake | Self::Cancel => "Dictation",
+            Self::LaunchApp | Self::SwitchWindow | Self::Keystroke | Self::RunShellCommand => "Applications",
+            Self::System(_) => "System",
+            Self::Macro | Self::Custom(_) => "Custom",
+        }
+    }
+    
+    /// What the command does, for listing it
+    pub fn description(&self) -> String {
+        let description = match self {
+            Self::Delete => "Delete the last words, sentence or paragraph, or the selection",
+            Self::Undo => "Undo the last change",
+            Self::Redo => "Redo the last change undone",
+            Self::Capitalize => "Capitalize the last word, the last sentence, the selection or all the text",
+            Self::Lowercase => "Lowercase the last word, the last sentence, the selection or all the text",
+            Self::Uppercase => "Uppercase the last word, the last sentence, the selection or all the text",
+            Self::InsertText => "Insert text",
+            Self::Replace => "Replace the last words sounding alike",
+            Self::GoToLine => "Go to a line",
+            Self::Select => "Select text",
+            Self::NewLine => "Start a new line",
+            Self::NewParagraph => "Start a new paragraph",
+            Self::Period => "Insert a period",
+            Self::Comma => "Insert a comma",
+            Self::QuestionMark => "Insert a question mark",
+            Self::ExclamationMark => "Insert an exclamation mark",
+            Self::Pause => "Pause transcription, still listening for commands",
+            Self::Resume => "Resume transcription",
+            Self::Stop => "Stop recording and transcription",
+            Self::Sleep => "Stop hearing commands and dictation until woken up",
+            Self::Wake => "Hear commands and dictation again",
+            Self::StartSpelling => "Spell words letter by letter",
+            Self::StopSpelling => "Stop spelling",
+            Self::SetMode(CommandMode::Command) => "Hear only commands",
+            Self::SetMode(CommandMode::Dictation) => "Hear only dictation, until a mode is switched to",
+            Self::SetMode(CommandMode::Mixed) => "Hear commands among dictation",
+            Self::SwitchProfile => "Switch to another command profile",
+            Self::Help => "List the commands that can be said",
+            Self::ReportMistake => "Report the last command as heard by mistake",
+            Self::Cancel => "Drop what was just said and stop spelling and running programs",
+            Self::LaunchApp => "Open an application",
+            Self::SwitchWindow => "Switch to a window",
+            Self::Keystroke => "Press keys",
+            Self::RunShellCommand => "Run a program",
+            Self::System(SystemAction::VolumeUp) => "Turn the volume up",
+            Self::System(SystemAction::VolumeDown) => "Turn the volume down",
+            Self::System(SystemAction::Mute) => "Mute or unmute",
+            Self::System(SystemAction::PlayPause) => "Play or pause music",
+            Self::System(SystemAction::NextTrack) => "Skip to the next track",
+            Self::System(SystemAction::PreviousTrack) => "Go back to the previous track",
+            Self::Macro => "Run several commands",
+            Self::Custom(name) => return format!("Run {}", name),
+        };
+        description.to_string()
+    }
+}
+
+/// A command that can be said, for showing users what they can say
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommandHelp {
+    /// What to say, with `{name}` where a parameter goes and `|` between words that can be said instead
+    pub trigger: String,
+    
+    /// What the command does
+    pub description: String,
+    
+    /// The parameters said with it, in order
+    pub parameters: Vec<ParameterHelp>,
+}
+
+/// A parameter said with a command
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParameterHelp {
+    pub name: String,
+    
+    /// Whether it takes a number or words
+    pub kind: ParameterKind,
+}
+
+/// The commands of one category, e.g. "Editing"
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommandCategory {
+    pub name: String,
+    pub commands: Vec<CommandHelp>,
+}
+
+impl CommandHelp {
+    /// A command with a plain trigger
+    fn plain(trigger: &str, command_type: &VoiceCommandType) -> Self {
+        Self {
+            trigger: trigger.to_string(),
+            description: command_type.description(),
+            parameters: Vec::new(),
+        }
+    }
+    
+    /// A command with parameters, giving commands of `command_type`
+    fn pattern(pattern: &CommandPattern, command_type: &VoiceCommandType) -> Self {
+        Self {
+            trigger: pattern.trigger(),
+            description: command_type.description(),
+            parameters: pattern.parameters().into_iter()
+                .map(|(name, kind)| ParameterHelp { name: name.to_string(), kind })
+                .collect(),
+        }
+    }
+}
+
 /// Voice command information
 #[derive(Debug, Clone)]
 pub struct VoiceCommand {
@@ -56,8 +277,30 @@ pub struct VoiceCommand {
     /// The original text that triggered the command
     pub trigger_text: String,
     
-    /// Any additional parameters
-    pub parameters: Option<String>,
+    /// Values spoken with the command, by name, e.g. "count" in "delete last three words"
+    pub parameters: BTreeMap<String, CommandParameter>,
+    
+    /// The commands a macro runs, in order
+    pub steps: Vec<VoiceCommand>,
+    
+    /// Mode the manager was in once the command was heard
+    pub mode: CommandMode,
+    
+    /// Where the command was said in the transcription, from its prefix when it had one, in bytes
+    pub span: Option<Range<usize>>,
+    
+    /// How closely what was said matched the command (0.0-1.0), 1.0 when it was said exactly
+    pub score: f32,
+}
+
+/// A value spoken as part of a command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CommandParameter {
+    /// A count or position, e.g. "three" in "delete last three words"
+    Number(u32),
+    
+    /// Words to act on, e.g. "quarterly report" in "select quarterly report"
+    Text(String),
 }
 
 impl VoiceCommand {
@@ -66,19 +309,48 @@ impl VoiceCommand {
         Self {
             command_type,
             trigger_text: trigger_text.to_string(),
-            parameters: None,
+            parameters: BTreeMap::new(),
+            steps: Vec::new(),
+            mode: CommandMode::default(),
+            span: None,
+            score: 1.0,
         }
     }
     
-    /// Add parameters to the command
-    pub fn with_parameters(mut self, parameters: &str) -> Self {
-        self.parameters = Some(parameters.to_string());
+    /// Make the command a macro running `steps`
+    pub fn with_steps(mut self, steps: Vec<VoiceCommand>) -> Self {
+        self.steps = steps;
         self
     }
+    
+    /// Add a parameter to the command
+    pub fn with_parameter(mut self, name: &str, value: CommandParameter) -> Self {
+        self.parameters.insert(name.to_string(), value);
+        self
+    }
+    
+    /// A number spoken with the command
+    pub fn number(&self, name: &str) -> Option<u32> {
+        match self.parameters.get(name) {
+            Some(CommandParameter::Number(number)) => Some(*number),
+            _ => None,
+        }
+    }
+    
+    /// Words spoken with the command
+    pub fn text(&self, name: &str) -> Option<&str> {
+        match self.parameters.get(name) {
+            Some(CommandParameter::Text(text)) => Some(text),
+            _ => None,
+        }
+    }
 }
 
-/// Configuration for the voice command system
+/// Configuration for the voice command system, as the core, the application and its saved settings have it
+///
+/// Settings left out of a saved configuration take their defaults.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct VoiceCommandConfig {
     /// Whether voice commands are enabled
     pub enabled: bool,
@@ -89,11 +361,120 @@ pub struct VoiceCommandConfig {
     /// Whether to require a prefix for all commands
     pub require_prefix: bool,
     
-    /// Command detection sensitivity (0.0-1.0)
+    /// Similarity a misheard command needs to be detected (0.0-1.0); higher is stricter, 1.0 only detects exact commands
     pub sensitivity: f32,
     
     /// Custom command mappings (text to command type)
+    #[serde(deserialize_with = "deserialize_custom_commands")]
     pub custom_commands: Vec<(String, VoiceCommandType)>,
+    
+    /// User-defined commands, one per line, e.g. `"scratch that" => undo()`; see `CommandScript`
+    pub script: String,
+    
+    /// Spoken wake word arming commands, heard in the audio rather than the transcript
+    pub wake_word: WakeWordSettings,
+    
+    /// Commands opening applications and switching windows
+    pub app_control: AppControlSettings,
+    
+    /// Whether system volume and media playback commands are understood
+    pub system_commands: bool,
+    
+    /// Whether commands may press keys in other applications, e.g. `key("ctrl+s")` in the script
+    pub keystrokes: bool,
+    
+    /// Whether dictated text is typed into the application that has the focus, where "undo" and "redo" act on it
+    pub inject_text: bool,
+    
+    /// Programs commands may run, e.g. `run("deploy")` in the script; nothing else is ever run
+    pub shell_commands: ShellCommandSettings,
+    
+    /// Directory `.wasm` command plugins are loaded from at startup; see `CommandPlugin`
+    pub plugin_dir: Option<String>,
+    
+    /// Rhai scripts with `on_command` and `on_final_text` hooks, run in order; see `ScriptHooks`
+    pub hook_scripts: Vec<String>,
+    
+    /// Mode voice commands start in
+    pub mode: CommandMode,
+    
+    /// Settings of single commands by trigger, built-in or custom, e.g. a stricter sensitivity for "stop"
+    pub overrides: BTreeMap<String, CommandOverride>,
+    
+    /// Whole configurations to switch to by name, e.g. "coding" and "email"; their own profiles are ignored
+    pub profiles: BTreeMap<String, VoiceCommandConfig>,
+    
+    /// Name of the profile voice commands start with, if any
+    pub active_profile: Option<String>,
+}
+
+/// Custom commands as saved, each with its command type or, as the application saved them before, an action name
+///
+/// A name that isn't a command type, e.g. "open_terminal", becomes a custom command of that name.
+fn deserialize_custom_commands<'de, D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Vec<(String, VoiceCommandType)>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum SavedCommandType {
+        Type(VoiceCommandType),
+        Name(String),
+    }
+    
+    let saved: Vec<(String, SavedCommandType)> = Vec::deserialize(deserializer)?;
+    Ok(saved.into_iter()
+        .map(|(trigger, saved)| match saved {
+            SavedCommandType::Type(command_type) => (trigger, command_type),
+            SavedCommandType::Name(name) => (trigger, VoiceCommandType::Custom(name)),
+        })
+        .collect())
+}
+
+impl VoiceCommandConfig {
+    /// The settings overridden for the command with `trigger`, without regard to letter case
+    pub fn command_override(&self, trigger: &str) -> Option<&CommandOverride> {
+        let trigger = trigger.trim();
+        self.overrides.iter()
+            .find(|(overridden, _)| overridden.trim().eq_ignore_ascii_case(trigger))
+            .map(|(_, command_override)| command_override)
+    }
+    
+    /// Whether the command with `trigger` is heard at all
+    pub fn is_command_enabled(&self, trigger: &str) -> bool {
+        self.command_override(trigger).map_or(true, |command_override| command_override.enabled)
+    }
+    
+    /// Whether the command with `trigger` is heard while `window` has the focus; every command is when it isn't known
+    pub fn is_command_available(&self, trigger: &str, window: Option<&ActiveWindow>) -> bool {
+        match (self.command_override(trigger), window) {
+            (Some(command_override), Some(window)) if !command_override.apps.is_empty() => {
+                command_override.apps.iter().any(|app| window.is_app(app))
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Settings of one command that differ from the others
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandOverride {
+    /// Whether the command is heard at all
+    pub enabled: bool,
+    
+    /// Similarity a misheard trigger needs, instead of the global sensitivity
+    pub sensitivity: Option<f32>,
+    
+    /// Applications the command is only heard in, e.g. "Code.exe"; it is heard in all of them when empty
+    pub apps: Vec<String>,
+}
+
+impl Default for CommandOverride {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sensitivity: None,
+            apps: Vec::new(),
+        }
+    }
 }
 
 impl Default for VoiceCommandConfig {
@@ -104,6 +485,19 @@ impl Default for VoiceCommandConfig {
             require_prefix: false,
             sensitivity: 0.8,
             custom_commands: Vec::new(),
+            script: String::new(),
+            wake_word: WakeWordSettings::default(),
+            app_control: AppControlSettings::default(),
+            system_commands: false,
+            keystrokes: false,
+            inject_text: false,
+            shell_commands: ShellCommandSettings::default(),
+            plugin_dir: None,
+            hook_scripts: Vec::new(),
+            mode: CommandMode::default(),
+            overrides: BTreeMap::new(),
+            profiles: BTreeMap::new(),
+            active_profile: None,
         }
     }
 }
@@ -124,6 +518,12 @@ pub enum TextEditOperation {
     
     /// Format text (capitalize, lowercase)
     Format(FormatOperation),
+    
+    /// Insert text at the end
+    Insert(String),
+    
+    /// Several operations applied together, as one for undo
+    Macro(Vec<TextEditOperation>),
 }
 
 /// Scope for delete operations
@@ -143,19 +543,59 @@ pub enum DeleteScope {
     Range(usize, usize),
 }
 
+/// Part of the text to select
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelectScope {
+    /// All the text
+    All,
+    /// The last word
+    LastWord,
+    /// The last sentence
+    LastSentence,
+    /// The last paragraph
+    LastParagraph,
+    /// The last occurrence of a phrase, without regard to letter case
+    Phrase(String),
+    /// From an occurrence of one phrase to the end of the next occurrence of another
+    Between(String, String),
+}
+
 /// Text formatting operations
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FormatOperation {
     /// Capitalize text
-    Capitalize,
+    Capitalize(FormatScope),
     /// Convert text to lowercase
-    Lowercase,
+    Lowercase(FormatScope),
     /// Convert text to uppercase
-    Uppercase,
-    /// Apply a specific style (bold, italic)
+    Uppercase(FormatScope),
+    /// Apply a specific style (bold, italic) to the last word or the selection
     Style(TextStyle),
 }
 
+impl FormatOperation {
+    /// The part of the text it applies to
+    pub fn scope(&self) -> FormatScope {
+        match self {
+            Self::Capitalize(scope) | Self::Lowercase(scope) | Self::Uppercase(scope) => scope.clone(),
+            Self::Style(_) => FormatScope::LastWord,
+        }
+    }
+}
+
+/// Part of the text a format operation applies to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatScope {
+    /// The last word, or the selection while there is one
+    LastWord,
+    /// The last sentence
+    LastSentence,
+    /// The selection; without one there is nothing to format
+    Selection,
+    /// All the text
+    All,
+}
+
 /// Text styling options
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TextStyle {
@@ -180,6 +620,15 @@ pub struct TextOperationHistory {
     pub timestamp: chrono::DateTime<chrono::Local>,
 }
 
+/// Punctuation written right after the word before it, with no space
+const ATTACHED_PUNCTUATION: &str = ".,?!;:)";
+
+/// Punctuation ending a sentence
+const SENTENCE_ENDINGS: [char; 3] = ['.', '!', '?'];
+
+/// Closing quotes and brackets that can follow the end of a sentence
+const SENTENCE_CLOSERS: [char; 6] = ['"', '\'', ')', ']', '\u{201D}', '\u{2019}'];
+
 /// Text editor that handles voice commands for text editing
 pub struct VoiceTextEditor {
     /// Operation history for undo/redo
@@ -188,6 +637,8 @@ pub struct VoiceTextEditor {
     history_position: usize,
     /// Maximum history size
     max_history: usize,
+    /// Selected byte range, with the text it selected so it is dropped once the text changes there
+    selection: Option<(Range<usize>, String)>,
 }
 
 impl VoiceTextEditor {
@@ -197,12 +648,48 @@ impl VoiceTextEditor {
             history: Vec::new(),
             history_position: 0,
             max_history: 50,
+            selection: None,
         }
     }
     
-    /// Apply a delete operation to text
+    /// Select part of the text, for the next delete or format to apply to
+    pub fn select(&mut self, text: &str, scope: &SelectScope) -> Option<Range<usize>> {
+        self.selection = select_range(text, scope).filter(|range| !range.is_empty())
+            .map(|range| (range.clone(), text[range].to_string()));
+        self.selection.as_ref().map(|(range, _)| range.clone())
+    }
+    
+    /// The selected range of `text`, if the text there is still what was selected
+    pub fn selection(&self, text: &str) -> Option<Range<usize>> {
+        let (range, selected) = self.selection.as_ref()?;
+        (text.get(range.clone()) == Some(selected.as_str())).then(|| range.clone())
+    }
+    
+    /// Forget the selection
+    pub fn clear_selection(&mut self) {
+        self.selection = None;
+    }
+    
+    /// Apply a delete operation to text, or delete the selection when there is one
     pub fn apply_delete(&mut self, text: &str, scope: &DeleteScope) -> Result<String, String> {
         let previous_text = text.to_string();
+        if let Some(range) = self.selection(text) {
+            self.selection = None;
+            let mut current_text = self.delete_range(text, range.start, range.end);
+            
+            // Deleting up to the end leaves no space behind, as deleting the last words doesn't
+            if range.end == text.trim_end().len() {
+                current_text.truncate(current_text.trim_end().len());
+            }
+            
+            self.add_to_history(
+                TextEditOperation::Delete(DeleteScope::Range(range.start, range.end)),
+                previous_text,
+                current_text.clone()
+            );
+            return Ok(current_text);
+        }
+        
         let current_text = match scope {
             DeleteScope::LastWord => self.delete_last_word(text),
             DeleteScope::LastSentence => self.delete_last_sentence(text),
@@ -245,8 +732,9 @@ impl VoiceTextEditor {
             return String::new();
         }
         
-        // Find the last sentence boundary (., !, ?)
-        if let Some(pos) = text.rfind(|c: char| c == '.' || c == '!' || c == '?') {
+        // Find the end of the sentence before the last one (., !, ?), past the last one's own ending
+        let last_sentence = text.trim_end_matches(['.', '!', '?']);
+        if let Some(pos) = last_sentence.rfind(['.', '!', '?']) {
             // Include the sentence-ending character
             let end_pos = pos + 1;
             // Trim any trailing whitespace after the sentence
@@ -373,14 +861,24 @@ impl VoiceTextEditor {
         self.history_position = 0;
     }
     
-    /// Apply a formatting operation to text
+    /// Apply a formatting operation to the last word, or to the selection when there is one
     pub fn apply_format(&mut self, text: &str, format_op: FormatOperation) -> Result<String, String> {
         let previous_text = text.to_string();
-        let current_text = match format_op {
-            FormatOperation::Capitalize => self.capitalize_last_word(text),
-            FormatOperation::Lowercase => self.lowercase_last_word(text),
-            FormatOperation::Uppercase => self.uppercase_last_word(text),
-            FormatOperation::Style(ref style) => self.apply_style(text, style.clone()),
+        let selection = self.selection(text);
+        if format_op.scope() == FormatScope::Selection && selection.is_none() {
+            return Err("Nothing is selected".to_string());
+        }
+        
+        let current_text = match self.format_range(text, &format_op.scope()) {
+            Some(range) => {
+                let formatted = formatted(&text[range.clone()], &format_op);
+                if selection == Some(range.clone()) {
+                    // Keep the formatted text selected, for further formatting
+                    self.selection = Some((range.start..range.start + formatted.len(), formatted.clone()));
+                }
+                format!("{}{}{}", &text[..range.start], formatted, &text[range.end..])
+            }
+            None => self.format_last_word(text, &format_op),
         };
         
         // Record the operation in history
@@ -393,6 +891,136 @@ impl VoiceTextEditor {
         Ok(current_text)
     }
     
+    /// Insert text at the end, spaced as written text is
+    pub fn apply_insert(&mut self, text: &str, insertion: &str) -> Result<String, String> {
+        let previous_text = text.to_string();
+        let current_text = self.insert(text, insertion);
+        
+        // Record the operation in history
+        self.add_to_history(
+            TextEditOperation::Insert(insertion.to_string()),
+            previous_text,
+            current_text.clone()
+        );
+        
+        Ok(current_text)
+    }
+    
+    /// Append spelled characters, continuing the word being spelled or after a space as a new one
+    pub fn apply_spelling(&mut self, text: &str, letters: &str, continues: bool) -> Result<String, String> {
+        let previous_text = text.to_string();
+        let current_text = if continues {
+            format!("{}{}", text, letters)
+        } else {
+            self.insert(text, letters)
+        };
+        
+        // Record the operation in history
+        self.add_to_history(
+            TextEditOperation::Insert(letters.to_string()),
+            previous_text,
+            current_text.clone()
+        );
+        
+        Ok(current_text)
+    }
+    
+    /// Replace the most recent words sounding like `original` with `replacement`
+    ///
+    /// The words are matched as command triggers are, so the recognizer's spelling of
+    /// a word is found from how it was said in the correction.
+    pub fn apply_replace(&mut self, text: &str, original: &str, replacement: &str) -> Result<String, String> {
+        let range = find_similar(text, original)
+            .ok_or_else(|| format!("Nothing like \"{}\" to replace", original))?;
+        let previous_text = text.to_string();
+        let current_text = format!("{}{}{}", &text[..range.start], replacement, &text[range.end..]);
+        
+        // Record what was actually replaced, so redoing it finds the same words
+        self.add_to_history(
+            TextEditOperation::Replace {
+                original: text[range].to_string(),
+                replacement: replacement.to_string(),
+            },
+            previous_text,
+            current_text.clone()
+        );
+        
+        Ok(current_text)
+    }
+    
+    /// Apply several operations in order, recorded as one so a single undo reverts them all
+    pub fn apply_macro(&mut self, text: &str, operations: Vec<TextEditOperation>) -> Result<String, String> {
+        let previous_text = text.to_string();
+        let current_text = operations.iter()
+            .fold(previous_text.clone(), |current_text, operation| self.edit(&current_text, operation));
+        
+        // Record the operations in history as one
+        self.add_to_history(
+            TextEditOperation::Macro(operations),
+            previous_text,
+            current_text.clone()
+        );
+        
+        Ok(current_text)
+    }
+    
+    /// Text after an operation, without recording it in history
+    fn edit(&self, text: &str, operation: &TextEditOperation) -> String {
+        match operation {
+            TextEditOperation::Delete(DeleteScope::LastWord) => self.delete_last_word(text),
+            TextEditOperation::Delete(DeleteScope::LastSentence) => self.delete_last_sentence(text),
+            TextEditOperation::Delete(DeleteScope::LastParagraph) => self.delete_last_paragraph(text),
+            TextEditOperation::Delete(DeleteScope::Range(start, end)) => self.delete_range(text, *start, *end),
+            TextEditOperation::Delete(DeleteScope::Words(count)) => self.delete_words(text, *count),
+            TextEditOperation::Delete(DeleteScope::FromPosition(pos)) => self.delete_from_position(text, *pos),
+            TextEditOperation::Format(format_op) => match self.format_range(text, &format_op.scope()) {
+                Some(range) => format!("{}{}{}", &text[..range.start], formatted(&text[range.clone()], format_op), &text[range.end..]),
+                None => self.format_last_word(text, format_op),
+            },
+            TextEditOperation::Insert(insertion) => self.insert(text, insertion),
+            TextEditOperation::Replace { original, replacement } => match find_similar(text, original) {
+                Some(range) => format!("{}{}{}", &text[..range.start], replacement, &text[range.end..]),
+                None => text.to_string(),
+            },
+            TextEditOperation::Macro(operations) => operations.iter()
+                .fold(text.to_string(), |current_text, operation| self.edit(&current_text, operation)),
+        }
+    }
+    
+    /// Insert text at the end, after a space when it starts with a word and
+    /// without one when it starts with punctuation or a line break
+    fn insert(&self, text: &str, insertion: &str) -> String {
+        let starts_word = insertion.starts_with(char::is_alphanumeric);
+        if insertion.starts_with(|c: char| ATTACHED_PUNCTUATION.contains(c) || c == '\n') {
+            // Punctuation goes right after the word before it, and a line break takes the place of spaces
+            format!("{}{}", text.trim_end_matches([' ', '\t']), insertion)
+        } else if starts_word && !text.is_empty() && !text.ends_with(char::is_whitespace) {
+            format!("{} {}", text, insertion)
+        } else {
+            format!("{}{}", text, insertion)
+        }
+    }
+    
+    /// The range `scope` covers in `text`, or `None` when it is the last word
+    fn format_range(&self, text: &str, scope: &FormatScope) -> Option<Range<usize>> {
+        let range = match scope {
+            FormatScope::LastWord | FormatScope::Selection => self.selection(text),
+            FormatScope::LastSentence => select_range(text, &SelectScope::LastSentence),
+            FormatScope::All => select_range(text, &SelectScope::All),
+        };
+        range.filter(|range| !range.is_empty())
+    }
+    
+    /// Apply a format operation to the last word
+    fn format_last_word(&self, text: &str, format_op: &FormatOperation) -> String {
+        match format_op {
+            FormatOperation::Capitalize(_) => self.capitalize_last_word(text),
+            FormatOperation::Lowercase(_) => self.lowercase_last_word(text),
+            FormatOperation::Uppercase(_) => self.uppercase_last_word(text),
+            FormatOperation::Style(style) => self.apply_style(text, style.clone()),
+        }
+    }
+    
     /// Capitalize the last word in text
     fn capitalize_last_word(&self, text: &str) -> String {
         if text.is_empty() {
@@ -475,12 +1103,7 @@ impl VoiceTextEditor {
             return text.to_string();
         }
         
-        let last_word = words[words.len() - 1];
-        let styled_word = match style {
-            TextStyle::Bold => format!("**{}**", last_word),
-            TextStyle::Italic => format!("*{}*", last_word),
-            TextStyle::Underline => format!("_{}_", last_word),
-        };
+        let styled_word = styled(words[words.len() - 1], &style);
         
         // Replace last word with styled version
         if words.len() == 1 {
@@ -500,6 +1123,33 @@ pub struct VoiceCommandManager {
     /// Active command detectors
     command_detectors: Vec<CommandDetector>,
     
+    /// Commands with parameters, tried before the detectors
+    grammar: CommandGrammar,
+    
+    /// User-defined commands, tried first
+    script: CommandScript,
+    
+    /// Commands implemented by WebAssembly plugins, tried after the script
+    plugins: Vec<CommandPlugin>,
+    
+    /// Scripts deciding whether commands are carried out, when configured
+    hooks: Option<ScriptHooks>,
+    
+    /// Commands opening applications and switching windows, when enabled
+    app_grammar: Option<CommandGrammar>,
+    
+    /// Opens the applications and switches the windows
+    app_control: Box<dyn AppControl>,
+    
+    /// Changes the volume and controls playback
+    system_control: Box<dyn SystemControl>,
+    
+    /// Presses the keys of keystroke commands
+    keyboard: Box<dyn Keyboard>,
+    
+    /// Types dictated text into the application that has the focus
+    output: Box<dyn OutputSink>,
+    
     /// Set of commands that are currently registered
     registered_commands: HashSet<VoiceCommandType>,
     
@@ -514,14 +1164,61 @@ pub struct VoiceCommandManager {
     
     /// Current text buffer being edited
     current_text: Arc<Mutex<String>>,
+    
+    /// Until when commands are accepted without the prefix, after the wake word was heard
+    armed_until: Option<Instant>,
+    
+    /// Characters spelled so far while in spelling mode
+    spelling: Option<String>,
+    
+    /// What transcriptions are scanned for
+    mode: CommandMode,
+    
+    /// The configuration the manager was created with, holding the profiles
+    default_config: VoiceCommandConfig,
+    
+    /// Profile in use, `None` for the main configuration
+    profile: Option<String>,
+    
+    /// How often each command was carried out and reported as heard by mistake
+    stats: CommandStats,
+    
+    /// The last command carried out, for reporting it as heard by mistake
+    last_command: Option<VoiceCommandType>,
+    
+    /// Whether nothing but "wake up" is heard
+    asleep: bool,
+    
+    /// Set to stop the programs running for commands, when they are cancelled
+    stop_programs: Arc<AtomicBool>,
+    
+    /// Dictated text typed into other applications, for undoing it there
+    injected: InjectedText,
+    
+    /// The window that has the focus, for commands heard in some applications only
+    active_window: Option<ActiveWindow>,
 }
 
+/// Name of the main configuration among the profiles
+pub const DEFAULT_PROFILE: &str = "default";
+
 impl VoiceCommandManager {
     /// Create a new voice command manager
     pub fn new(config: VoiceCommandConfig) -> Result<(Self, mpsc::Receiver<VoiceCommandEvent>)> {
         // Create channel for events
         let (sender, receiver) = mpsc::channel(100);
+        let mut manager = Self::build(config, sender);
         
+        if let Some(profile) = manager.config.active_profile.clone() {
+            if let Err(e) = manager.switch_profile(&profile) {
+                warn!("Starting without the voice command profile: {}", e);
+            }
+        }
+        Ok((manager, receiver))
+    }
+    
+    /// A manager for `config`, sending its events to `sender`
+    fn build(config: VoiceCommandConfig, sender: mpsc::Sender<VoiceCommandEvent>) -> Self {
         // Default command detectors
         let default_detectors = vec![
             CommandDetector::new("delete", VoiceCommandType::Delete),
@@ -535,70 +1232,355 @@ impl VoiceCommandManager {
             CommandDetector::new("redo that", VoiceCommandType::Redo),
             CommandDetector::new("capitalize", VoiceCommandType::Capitalize),
             CommandDetector::new("capitalize that", VoiceCommandType::Capitalize),
+            CommandDetector::new("capitalize last word", VoiceCommandType::Capitalize),
+            CommandDetector::new("capitalize last sentence", VoiceCommandType::Capitalize),
+            CommandDetector::new("capitalize selection", VoiceCommandType::Capitalize),
+            CommandDetector::new("capitalize everything", VoiceCommandType::Capitalize),
+            CommandDetector::new("capitalize all", VoiceCommandType::Capitalize),
             CommandDetector::new("lowercase", VoiceCommandType::Lowercase),
             CommandDetector::new("lowercase that", VoiceCommandType::Lowercase),
+            CommandDetector::new("lowercase last word", VoiceCommandType::Lowercase),
+            CommandDetector::new("lowercase last sentence", VoiceCommandType::Lowercase),
+            CommandDetector::new("lowercase selection", VoiceCommandType::Lowercase),
+            CommandDetector::new("lowercase everything", VoiceCommandType::Lowercase),
+            CommandDetector::new("lowercase all", VoiceCommandType::Lowercase),
+            CommandDetector::new("uppercase", VoiceCommandType::Uppercase),
+            CommandDetector::new("uppercase that", VoiceCommandType::Uppercase),
+            CommandDetector::new("uppercase last word", VoiceCommandType::Uppercase),
+            CommandDetector::new("uppercase last sentence", VoiceCommandType::Uppercase),
+            CommandDetector::new("uppercase selection", VoiceCommandType::Uppercase),
+            CommandDetector::new("uppercase everything", VoiceCommandType::Uppercase),
+            CommandDetector::new("uppercase all", VoiceCommandType::Uppercase),
             CommandDetector::new("new line", VoiceCommandType::NewLine),
             CommandDetector::new("new paragraph", VoiceCommandType::NewParagraph),
             CommandDetector::new("period", VoiceCommandType::Period),
             CommandDetector::new("comma", VoiceCommandType::Comma),
             CommandDetector::new("question mark", VoiceCommandType::QuestionMark),
             CommandDetector::new("exclamation", VoiceCommandType::ExclamationMark),
+            // Before "stop", so "stop spelling" isn't taken for it
+            CommandDetector::new("spell that", VoiceCommandType::StartSpelling),
+            CommandDetector::new("spelling mode", VoiceCommandType::StartSpelling),
+            CommandDetector::new("start spelling", VoiceCommandType::StartSpelling),
+            CommandDetector::new("end spelling", VoiceCommandType::StopSpelling),
+            CommandDetector::new("stop spelling", VoiceCommandType::StopSpelling),
             CommandDetector::new("pause", VoiceCommandType::Pause),
             CommandDetector::new("resume", VoiceCommandType::Resume),
             CommandDetector::new("stop", VoiceCommandType::Stop),
+            CommandDetector::new("go to sleep", VoiceCommandType::Sleep),
+            CommandDetector::new("wake up", VoiceCommandType::Wake),
+            CommandDetector::new("what can i say", VoiceCommandType::Help),
+            CommandDetector::new("show help", VoiceCommandType::Help),
+            CommandDetector::new("that was wrong", VoiceCommandType::ReportMistake),
+            CommandDetector::new("cancel", VoiceCommandType::Cancel),
+            CommandDetector::new("abort", VoiceCommandType::Cancel),
         ];
         
+        // System volume and playback commands, when enabled
+        let system_detectors = if config.system_commands {
+            vec![
+                CommandDetector::new("volume up", VoiceCommandType::System(SystemAction::VolumeUp)),
+                CommandDetector::new("turn it up", VoiceCommandType::System(SystemAction::VolumeUp)),
+                CommandDetector::new("volume down", VoiceCommandType::System(SystemAction::VolumeDown)),
+                CommandDetector::new("turn it down", VoiceCommandType::System(SystemAction::VolumeDown)),
+                CommandDetector::new("mute", VoiceCommandType::System(SystemAction::Mute)),
+                CommandDetector::new("unmute", VoiceCommandType::System(SystemAction::Mute)),
+                CommandDetector::new("play music", VoiceCommandType::System(SystemAction::PlayPause)),
+                CommandDetector::new("pause music", VoiceCommandType::System(SystemAction::PlayPause)),
+                CommandDetector::new("next track", VoiceCommandType::System(SystemAction::NextTrack)),
+                CommandDetector::new("previous track", VoiceCommandType::System(SystemAction::PreviousTrack)),
+            ]
+        } else {
+            Vec::new()
+        };
+        // Ahead of the defaults, so "pause music" isn't taken for "pause"
+        let default_detectors: Vec<CommandDetector> = system_detectors.into_iter()
+            .chain(default_detectors)
+            .filter_map(|detector| detector.configured(&config))
+            .collect();
+        
         // Register the default commands
         let mut registered_commands = HashSet::new();
         for detector in &default_detectors {
             registered_commands.insert(detector.command_type.clone());
         }
         
-        // Add custom commands
+        // Add custom commands; triggers with parameters are patterns
         let mut command_detectors = default_detectors;
+        let mut grammar = CommandGrammar::new();
         for (trigger, command_type) in &config.custom_commands {
-            command_detectors.push(CommandDetector::new(trigger, command_type.clone()));
+            if !config.is_command_enabled(trigger) {
+                debug!("Custom command {:?} is disabled", trigger);
+                continue;
+            }
+            
+            if CommandPattern::is_pattern(trigger) {
+                match CommandPattern::new(trigger, command_type.clone()) {
+                    Ok(pattern) => grammar.add(pattern),
+                    Err(e) => {
+                        warn!("Skipping custom command: {}", e);
+                        continue;
+                    }
+                }
+            } else if let Some(detector) = CommandDetector::new(trigger, command_type.clone()).configured(&config) {
+                command_detectors.push(detector);
+            }
             registered_commands.insert(command_type.clone());
         }
         
-        Ok((
-            Self {
-                config,
-                command_detectors,
-                registered_commands,
-                event_sender: sender,
-                is_active: Arc::new(Mutex::new(false)),
-                text_editor: VoiceTextEditor::new(),
-                current_text: Arc::new(Mutex::new(String::new())),
-            },
-            receiver
-        ))
+        let script = CommandScript::parse(&config.script).unwrap_or_else(|e| {
+            warn!("Ignoring voice command script: {}", e);
+            CommandScript::default()
+        });
+        for command in script.commands() {
+            registered_commands.insert(command.command_type().clone());
+        }
+        
+        let plugins = config.plugin_dir.as_deref()
+            .map(|dir| CommandPlugin::load_dir(Path::new(dir)))
+            .unwrap_or_default();
+        if plugins.iter().any(|plugin| !plugin.patterns().is_empty()) {
+            registered_commands.insert(VoiceCommandType::Macro);
+        }
+        
+        let hooks = (!config.hook_scripts.is_empty()).then(|| ScriptHooks::new(&config.hook_scripts));
+        
+        let app_grammar = config.app_control.enabled.then(|| {
+            registered_commands.insert(VoiceCommandType::LaunchApp);
+            registered_commands.insert(VoiceCommandType::SwitchWindow);
+            CommandGrammar::app_control()
+        });
+        
+        let mode = config.mode;
+        Self {
+            default_config: config.clone(),
+            profile: None,
+            config,
+            command_detectors,
+            grammar,
+            script,
+            plugins,
+            hooks,
+            app_grammar,
+            app_control: platform_app_control(),
+            system_control: platform_system_control(),
+            keyboard: platform_keyboard(),
+            output: platform_sink(),
+            registered_commands,
+            event_sender: sender,
+            is_active: Arc::new(Mutex::new(false)),
+            text_editor: VoiceTextEditor::new(),
+            current_text: Arc::new(Mutex::new(String::new())),
+            armed_until: None,
+            spelling: None,
+            mode,
+            stats: CommandStats::default(),
+            last_command: None,
+            asleep: false,
+            stop_programs: Arc::new(AtomicBool::new(false)),
+            injected: InjectedText::default(),
+            active_window: None,
+        }
     }
     
-    /// Start processing voice commands
-    pub fn start(&mut self) -> Result<()> {
-        let mut active = self.is_active.lock();
-        *active = true;
-        info!("Voice command detection started");
-        Ok(())
+    /// Names of the profiles that can be switched to, with "default" for the main configuration
+    pub fn profiles(&self) -> Vec<String> {
+        std::iter::once(DEFAULT_PROFILE.to_string())
+            .chain(self.default_config.profiles.keys().cloned())
+            .collect()
     }
     
-    /// Stop processing voice commands
-    pub fn stop(&mut self) -> Result<()> {
-        let mut active = self.is_active.lock();
+    /// The profile in use, "default" for the main configuration
+    pub fn profile(&self) -> &str {
+        self.profile.as_deref().unwrap_or(DEFAULT_PROFILE)
+    }
+    
+    /// The commands heard now, by category, for showing what can be said
+    ///
+    /// Each trigger is listed once, from the command it is taken for; in
+    /// dictation mode only switching modes is heard.
+    pub fn available_commands(&self) -> Vec<CommandCategory> {
+        let mut listed: Vec<(&str, CommandHelp)> = Vec::new();
+        if self.mode != CommandMode::Dictation {
+            for command in self.script.commands().iter().filter(|command| self.is_available(&command.pattern().trigger())) {
+                listed.push((command.command_type().category(), CommandHelp::pattern(command.pattern(), command.command_type())));
+            }
+            for plugin in &self.plugins {
+                for pattern in plugin.patterns() {
+                    let mut help = CommandHelp::pattern(pattern, pattern.command_type());
+                    help.description = format!("Run by the {} plugin", plugin.name());
+                    listed.push((pattern.command_type().category(), help));
+                }
+            }
+            let patterns = self.grammar.patterns().iter().chain(self.app_grammar.iter().flat_map(CommandGrammar::patterns));
+            for pattern in patterns.filter(|pattern| self.is_available(&pattern.trigger())) {
+                listed.push((pattern.command_type().category(), CommandHelp::pattern(pattern, pattern.command_type())));
+            }
+            for detector in self.command_detectors.iter().filter(|detector| self.is_available(&detector.trigger)) {
+                listed.push((detector.command_type.category(), CommandHelp::plain(&detector.trigger, &detector.command_type)));
+            }
+        }
+        for (trigger, mode) in [("command mode", CommandMode::Command), ("dictation mode", CommandMode::Dictation), ("mixed mode", CommandMode::Mixed)] {
+            let command_type = VoiceCommandType::SetMode(mode);
+            listed.push((command_type.category(), CommandHelp::plain(trigger, &command_type)));
+        }
+        
+        let mut seen = HashSet::new();
+        listed.retain(|(_, help)| seen.insert(help.trigger.clone()));
+        CATEGORIES.iter()
+            .map(|category| CommandCategory {
+                name: category.to_string(),
+                commands: listed.iter().filter(|(listed_in, _)| listed_in == category).map(|(_, help)| help.clone()).collect(),
+            })
+            .filter(|category| !category.commands.is_empty())
+            .collect()
+    }
+    
+    /// Switch to the commands and settings of another profile, without regard to letter case
+    ///
+    /// The text being edited and its history are kept; spelling ends and the mode is the profile's.
+    pub fn switch_profile(&mut self, name: &str) -> Result<()> {
+        let name = name.trim();
+        let (profile, config) = if name.eq_ignore_ascii_case(DEFAULT_PROFILE) {
+            (None, self.default_config.clone())
+        } else {
+            let (profile, config) = self.default_config.profiles.iter()
+                .find(|(profile, _)| profile.eq_ignore_ascii_case(name))
+                .ok_or_else(|| anyhow::anyhow!("No voice command profile named {}", name))?;
+            (Some(profile.clone()), config.clone())
+        };
+        
+        // Only the commands are rebuilt; the events still go where they went
+        let rebuilt = Self::build(config, self.event_sender.clone());
+        self.config = rebuilt.config;
+        self.command_detectors = rebuilt.command_detectors;
+        self.grammar = rebuilt.grammar;
+        self.script = rebuilt.script;
+        self.plugins = rebuilt.plugins;
+        self.hooks = rebuilt.hooks;
+        self.app_grammar = rebuilt.app_grammar;
+        self.registered_commands = rebuilt.registered_commands;
+        self.set_mode(rebuilt.mode);
+        self.spelling = None;
+        self.profile = profile;
+        
+        info!("Voice commands switched to the {} profile", self.profile());
+        let _ = self.event_sender.try_send(VoiceCommandEvent::ProfileChanged(self.profile().to_string()));
+        Ok(())
+    }
+    
+    /// Start processing voice commands
+    pub fn start(&mut self) -> Result<()> {
+        let mut active = self.is_active.lock();
+        *active = true;
+        info!("Voice command detection started");
+        Ok(())
+    }
+    
+    /// Stop processing voice commands
+    pub fn stop(&mut self) -> Result<()> {
+        let mut active = self.is_active.lock();
         *active = false;
         info!("Voice command detection stopped");
         Ok(())
     }
     
+    /// Accept commands without the prefix for the configured time, after the wake word was heard
+    pub fn arm(&mut self) {
+        // Settings that skipped the config file's clamp may be negative, NaN or infinite
+        let secs = self.config.wake_word.armed_secs;
+        let duration = Duration::from_secs_f32(if secs.is_nan() { 0.0 } else { secs.clamp(0.0, MAX_ARMED_SECS) });
+        self.armed_until = Some(Instant::now() + duration);
+        debug!("Voice commands armed for {:?}", duration);
+    }
+    
+    /// Whether the wake word was heard recently enough for commands to be accepted without the prefix
+    pub fn is_armed(&self) -> bool {
+        self.armed_until.is_some_and(|until| Instant::now() < until)
+    }
+    
+    /// What transcriptions are scanned for
+    pub fn mode(&self) -> CommandMode {
+        self.mode
+    }
+    
+    /// Switch to another mode
+    pub fn set_mode(&mut self, mode: CommandMode) {
+        if self.mode != mode {
+            self.mode = mode;
+            info!("Voice commands switched to {:?} mode", mode);
+            let _ = self.event_sender.try_send(VoiceCommandEvent::ModeChanged(mode));
+        }
+    }
+    
+    /// Go to sleep, hearing nothing but "wake up", or wake up
+    pub fn set_asleep(&mut self, asleep: bool) {
+        if self.asleep != asleep {
+            self.asleep = asleep;
+            self.spelling = None;
+            info!("Voice commands {}", if asleep { "went to sleep" } else { "woke up" });
+            let _ = self.event_sender.try_send(VoiceCommandEvent::SleepChanged(asleep));
+        }
+    }
+    
+    /// Whether nothing but "wake up" is heard
+    pub fn is_asleep(&self) -> bool {
+        self.asleep
+    }
+    
+    /// Cancel what is going on: spelling ends, the wake word no longer stands in for the prefix,
+    /// and programs run by commands are stopped
+    pub fn cancel(&mut self) {
+        self.spelling = None;
+        self.armed_until = None;
+        self.stop_programs.store(true, Ordering::SeqCst);
+        self.stop_programs = Arc::new(AtomicBool::new(false));
+        info!("Voice commands cancelled");
+        let _ = self.event_sender.try_send(VoiceCommandEvent::Cancelled);
+    }
+    
+    /// Take `window` as the one that has the focus, so the commands set for its application are heard
+    pub fn set_active_window(&mut self, window: ActiveWindow) {
+        if self.active_window.as_ref() != Some(&window) {
+            debug!("Voice commands now heard for {}", window.app);
+            self.active_window = Some(window.clone());
+            let _ = self.event_sender.try_send(VoiceCommandEvent::ContextChanged(window));
+        }
+    }
+    
+    /// The window taken as having the focus, if it is known
+    pub fn active_window(&self) -> Option<&ActiveWindow> {
+        self.active_window.as_ref()
+    }
+    
+    /// Whether the command with `trigger` is heard in the window that has the focus
+    fn is_available(&self, trigger: &str) -> bool {
+        self.config.is_command_available(trigger, self.active_window.as_ref())
+    }
+    
+    /// Whether transcriptions are being spelled letter by letter
+    pub fn is_spelling(&self) -> bool {
+        self.spelling.is_some()
+    }
+    
     /// Process a transcription to detect commands
     pub fn process_transcription(&mut self, text: &str) -> Result<Vec<VoiceCommand>> {
         if !*self.is_active.lock() || !self.config.enabled {
             return Ok(Vec::new());
         }
         
+        let leading = text.len() - text.trim_start().len();
+        let spoken = text.trim();
+        
+        // Every word is a character in spelling mode, even single letters, and needs no prefix
+        if self.is_spelling() {
+            if let Some(command) = self.cancellation(&spoken.to_lowercase()) {
+                return Ok(self.cancel_utterance(command, leading..leading + spoken.len()));
+            }
+            return Ok(self.process_spelling(text));
+        }
+        
         let mut detected_commands = Vec::new();
-        let text = text.to_lowercase().trim().to_string();
+        let text = spoken.to_lowercase();
         
         // If the text is too short, skip processing
         if text.len() < 2 {
@@ -607,35 +1589,81 @@ impl VoiceCommandManager {
         
         // Prepare command text based on prefix settings
         let original_text = text.clone(); // Clone the text for logging
-        let (has_prefix, command_text) = if let Some(prefix) = &self.config.command_prefix {
+        let (has_prefix, command_text, prefix_at) = if self.is_armed() {
+            // The wake word stands in for the prefix
+            (true, text.clone(), None)
+        } else if let Some(prefix) = &self.config.command_prefix {
             let prefix = prefix.to_lowercase();
             
             // Check if text starts with or contains the prefix
             if text.starts_with(&prefix) {
                 // Extract command text after the prefix
                 let after_prefix = &text[prefix.len()..].trim();
-                (true, after_prefix.to_string())
+                (true, after_prefix.to_string(), Some(0))
             } else if text.contains(&prefix) {
                 // Find the prefix position and extract text after it
                 if let Some(pos) = text.find(&prefix) {
                     let after_prefix = &text[pos + prefix.len()..].trim();
-                    (true, after_prefix.to_string())
+                    (true, after_prefix.to_string(), Some(pos))
                 } else {
-                    (false, text.clone()) // Clone here
+                    (false, text.clone(), None) // Clone here
                 }
             } else {
-                (false, text.clone()) // Clone here
+                (false, text.clone(), None) // Clone here
             }
         } else {
-            (false, text.clone()) // Clone here
+            (false, text.clone(), None) // Clone here
+        };
+        
+        // Where a command found in `search_text` was said in the transcription as given, the first from the prefix;
+        // searches are in the text's end, and lowercasing can move characters, so then it isn't known
+        let locate = |search_text: &str, span: Range<usize>, first: bool| {
+            let offset = text.len() - search_text.len();
+            let start = prefix_at.filter(|_| offset > 0 && first).unwrap_or(offset + span.start);
+            (spoken.len() == text.len()).then(|| leading + start..leading + offset + span.end)
         };
         
+        // Command mode stands in for the prefix too, though one said anyway is left out
+        let has_prefix = has_prefix || self.mode == CommandMode::Command;
+        
+        // Asleep, only waking up is heard, and only said on its own so talking about it doesn't wake
+        if self.asleep {
+            let wake = self.wake_up(&command_text).filter(|_| has_prefix || !self.config.require_prefix);
+            let Some(mut command) = wake else {
+                return Ok(Vec::new());
+            };
+            self.set_asleep(false);
+            command.mode = self.mode;
+            command.span = locate(&command_text, 0..command_text.len(), true);
+            let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
+            return Ok(vec![command]);
+        }
+        
+        // Switching modes is heard in every mode, but only said on its own so dictating about it doesn't switch
+        if let Some(mode) = mode_switch(&command_text) {
+            self.set_mode(mode);
+            let mut command = VoiceCommand::new(VoiceCommandType::SetMode(mode), &text);
+            command.mode = mode;
+            command.span = locate(&command_text, 0..command_text.len(), true);
+            let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
+            return Ok(vec![command]);
+        }
+        
+        if self.mode == CommandMode::Dictation {
+            return Ok(Vec::new());
+        }
+        
         // Check if a prefix is required but not present
         if self.config.require_prefix && !has_prefix {
             debug!("Prefix required but not found in transcription: {:?}", original_text);
             return Ok(Vec::new());
         }
         
+        // Cancelling takes back the whole utterance, so nothing else said in it is carried out or kept
+        if let Some(command) = self.cancellation(&command_text) {
+            return Ok(self.cancel_utterance(command, leading..leading + spoken.len()));
+        }
+        
         // Prepare texts to search through
         let texts_to_search = if has_prefix {
             vec![command_text.clone()]
@@ -647,119 +1675,464 @@ impl VoiceCommandManager {
         
         // Process the transcription for commands
         for search_text in texts_to_search {
-            for detector in &self.command_detectors {
-                if let Some(command) = detector.detect(&search_text, self.config.sensitivity) {
-                    // Process commands based on type
-                    match &command.command_type {
-                        VoiceCommandType::Delete => {
-                            // Determine delete scope based on command context
-                            let scope = if command.trigger_text.contains("word") {
-                                DeleteScope::LastWord
-                            } else if command.trigger_text.contains("sentence") {
-                                DeleteScope::LastSentence
-                            } else if command.trigger_text.contains("paragraph") {
-                                DeleteScope::LastParagraph
-                            } else {
-                                // Default to last word
-                                DeleteScope::LastWord
-                            };
+            // Commands are heard left to right, each in the text after the one before;
+            // words matching none of them are dictation
+            let mut from = 0;
+            let mut heard = false;
+            while let Some(mut command) = self.detect_command(&search_text[from..], has_prefix) {
+                heard = true;
+                let span = command.span.clone().unwrap_or(0..search_text.len() - from);
+                
+                // What was said up to the command, so the words of the ones after it don't change what it does
+                command.trigger_text = search_text[from..from + span.end].trim().to_string();
+                command.mode = self.mode;
+                command.span = locate(&search_text, from + span.start..from + span.end, from == 0);
+                from += span.end;
+                
+                // A hook script can drop the command before it is carried out
+                if let Some(hooks) = &self.hooks {
+                    if !hooks.on_command(&command, &self.current_text.lock()) {
+                        continue;
+                    }
+                }
+                
+                // Process commands based on type
+                match &command.command_type {
+                    VoiceCommandType::Delete => {
+                        // Determine delete scope based on command context
+                        let scope = delete_scope(&command);
+                        
+                        // Get current text and apply delete operation
+                        let mut current_text = self.current_text.lock();
+                        if let Ok(new_text) = self.text_editor.apply_delete(&current_text, &scope) {
+                            // Update the current text
+                            *current_text = new_text;
                             
-                            // Get current text and apply delete operation
-                            let mut current_text = self.current_text.lock();
-                            if let Ok(new_text) = self.text_editor.apply_delete(&current_text, &scope) {
-                                // Update the current text
-                                *current_text = new_text;
-                                
-                                // Send a text update event
-                                let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
-                            } else {
-                                // Send error event if operation failed
-                                let _ = self.event_sender.try_send(VoiceCommandEvent::Error(
-                                    format!("Failed to apply delete operation: {:?}", scope)
-                                ));
-                            }
-                        },
-                        VoiceCommandType::Capitalize => {
-                            // Apply capitalize operation
-                            let mut current_text = self.current_text.lock();
-                            if let Ok(new_text) = self.text_editor.apply_format(&current_text, FormatOperation::Capitalize) {
+                            // Send a command event
+                            let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
+                            self.text_updated(&current_text);
+                        } else {
+                            // Send error event if operation failed
+                            let _ = self.event_sender.try_send(VoiceCommandEvent::Error(
+                                format!("Failed to apply delete operation: {:?}", scope)
+                            ));
+                        }
+                    },
+                    VoiceCommandType::Capitalize | VoiceCommandType::Lowercase | VoiceCommandType::Uppercase => {
+                        // Change the case of the part of the text the command names
+                        let format_op = case_operation(&command);
+                        let mut current_text = self.current_text.lock();
+                        match self.text_editor.apply_format(&current_text, format_op.clone()) {
+                            Ok(new_text) => {
                                 // Update the current text
                                 *current_text = new_text;
                                 
                                 // Send a command event
                                 let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
-                            } else {
-                                // Send error event if operation failed
-                                let _ = self.event_sender.try_send(VoiceCommandEvent::Error(
-                                    "Failed to capitalize text".to_string()
-                                ));
+                                self.text_updated(&current_text);
                             }
-                        },
-                        VoiceCommandType::Lowercase => {
-                            // Apply lowercase operation
-                            let mut current_text = self.current_text.lock();
-                            if let Ok(new_text) = self.text_editor.apply_format(&current_text, FormatOperation::Lowercase) {
-                                // Update the current text
-                                *current_text = new_text;
-                                
-                                // Send a command event
-                                let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
-                            } else {
+                            Err(e) => {
                                 // Send error event if operation failed
                                 let _ = self.event_sender.try_send(VoiceCommandEvent::Error(
-                                    "Failed to lowercase text".to_string()
+                                    format!("Failed to apply {:?}: {}", format_op, e)
                                 ));
                             }
-                        },
-                        VoiceCommandType::Undo => {
-                            // Apply undo operation
-                            if let Some(new_text) = self.text_editor.undo() {
+                        }
+                    },
+                    VoiceCommandType::InsertText | VoiceCommandType::NewLine | VoiceCommandType::NewParagraph
+                    | VoiceCommandType::Period | VoiceCommandType::Comma
+                    | VoiceCommandType::QuestionMark | VoiceCommandType::ExclamationMark => {
+                        // Insert the punctuation said, or the text the command was defined with
+                        let insertion = punctuation(&command.command_type).or_else(|| command.text("text")).unwrap_or_default();
+                        let mut current_text = self.current_text.lock();
+                        if let Ok(new_text) = self.text_editor.apply_insert(&current_text, insertion) {
+                            // Update the current text
+                            *current_text = new_text;
+                            
+                            // Send a command event
+                            let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
+                            self.text_updated(&current_text);
+                        } else {
+                            // Send error event if operation failed
+                            let _ = self.event_sender.try_send(VoiceCommandEvent::Error(
+                                "Failed to insert text".to_string()
+                            ));
+                        }
+                    },
+                    VoiceCommandType::Replace => {
+                        // The replacement is written as it was said, e.g. "Gradle" rather than "gradle"
+                        let original = command.text("original").unwrap_or_default();
+                        let replacement = as_spoken(spoken, command.text("replacement").unwrap_or_default());
+                        let mut current_text = self.current_text.lock();
+                        match self.text_editor.apply_replace(&current_text, original, &replacement) {
+                            Ok(new_text) => {
                                 // Update the current text
-                                let mut current_text = self.current_text.lock();
                                 *current_text = new_text;
                                 
                                 // Send a command event
                                 let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
-                            } else {
-                                // Send error event if no operation to undo
-                                let _ = self.event_sender.try_send(VoiceCommandEvent::Error(
-                                    "Nothing to undo".to_string()
-                                ));
+                                self.text_updated(&current_text);
+                            },
+                            // Send error event if there was nothing to replace
+                            Err(e) => {
+                                let _ = self.event_sender.try_send(VoiceCommandEvent::Error(e));
+                            },
+                        }
+                    },
+                    VoiceCommandType::Macro => {
+                        // Apply the text steps together; the others are left to whoever handles the event
+                        let operations: Vec<TextEditOperation> = command.steps.iter().filter_map(text_operation).collect();
+                        let mut current_text = self.current_text.lock();
+                        if let Ok(new_text) = self.text_editor.apply_macro(&current_text, operations) {
+                            // Update the current text
+                            *current_text = new_text;
+                            
+                            // Send a command event
+                            let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
+                            self.text_updated(&current_text);
+                        } else {
+                            // Send error event if operation failed
+                            let _ = self.event_sender.try_send(VoiceCommandEvent::Error(
+                                "Failed to run macro".to_string()
+                            ));
+                        }
+                        
+                        // Keys are pressed and programs run once the text is edited
+                        for step in &command.steps {
+                            let result = match step.command_type {
+                                VoiceCommandType::Keystroke => self.press_keys(step),
+                                VoiceCommandType::RunShellCommand => self.shell_command(step)
+                                    .map(|shell_command| self.run_shell_command(shell_command, &step.parameters)),
+                                _ => continue,
+                            };
+                            if let Err(e) = result {
+                                let _ = self.event_sender.try_send(VoiceCommandEvent::Error(e.to_string()));
                             }
-                        },
-                        VoiceCommandType::Redo => {
-                            // Apply redo operation
-                            if let Some(new_text) = self.text_editor.redo() {
-                                // Update the current text
-                                let mut current_text = self.current_text.lock();
-                                *current_text = new_text;
-                                
-                                // Send a command event
-                                let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
-                            } else {
-                                // Send error event if no operation to redo
-                                let _ = self.event_sender.try_send(VoiceCommandEvent::Error(
-                                    "Nothing to redo".to_string()
-                                ));
+                        }
+                    },
+                    VoiceCommandType::LaunchApp | VoiceCommandType::SwitchWindow => {
+                        let result = if command.command_type == VoiceCommandType::LaunchApp {
+                            let name = command.text("app").unwrap_or_default();
+                            match self.config.app_control.find(name) {
+                                Some(app) => self.app_control.launch(app),
+                                None => Err(anyhow::anyhow!("No application named {} is configured", name)),
                             }
-                        },
-                        // Add other command types as needed
-                        _ => {
-                            // For now, just send the command event
+                        } else {
+                            self.app_control.switch_to(command.text("window").unwrap_or_default())
+                        };
+                        
+                        match result {
+                            // Send a command event
+                            Ok(()) => {
+                                let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
+                            },
+                            // Send error event if it failed
+                            Err(e) => {
+                                let _ = self.event_sender.try_send(VoiceCommandEvent::Error(e.to_string()));
+                            },
+                        }
+                    },
+                    VoiceCommandType::Select => {
+                        let scope = select_scope(&command);
+                        let current_text = self.current_text.lock();
+                        if self.text_editor.select(&current_text, &scope).is_some() {
+                            // Send a command event
+                            let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
+                        } else {
+                            // Send error event if there was nothing to select
+                            let _ = self.event_sender.try_send(VoiceCommandEvent::Error(
+                                format!("Nothing to select: {:?}", scope)
+                            ));
+                        }
+                    },
+                    VoiceCommandType::Keystroke | VoiceCommandType::RunShellCommand => {
+                        let mut program = None;
+                        let result = if command.command_type == VoiceCommandType::Keystroke {
+                            self.press_keys(&command)
+                        } else {
+                            self.shell_command(&command).map(|shell_command| program = Some(shell_command))
+                        };
+                        
+                        match result {
+                            // Send a command event
+                            Ok(()) => {
+                                let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
+                            },
+                            // Send error event if it isn't allowed or it failed
+                            Err(e) => {
+                                let _ = self.event_sender.try_send(VoiceCommandEvent::Error(e.to_string()));
+                            },
+                        }
+                        
+                        // Programs start once the command is sent, so they can't be seen finishing before it
+                        if let Some(shell_command) = program {
+                            self.run_shell_command(shell_command, &command.parameters);
+                        }
+                    },
+                    VoiceCommandType::System(action) => {
+                        match self.system_control.perform(*action) {
+                            // Send a command event
+                            Ok(()) => {
+                                let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
+                            },
+                            // Send error event if it failed
+                            Err(e) => {
+                                let _ = self.event_sender.try_send(VoiceCommandEvent::Error(e.to_string()));
+                            },
+                        }
+                    },
+                    VoiceCommandType::SwitchProfile => {
+                        match self.switch_profile(command.text("profile").unwrap_or_default()) {
+                            // Send a command event
+                            Ok(()) => {
+                                command.mode = self.mode;
+                                let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
+                            },
+                            // Send error event if there is no such profile
+                            Err(e) => {
+                                let _ = self.event_sender.try_send(VoiceCommandEvent::Error(e.to_string()));
+                            },
+                        }
+                    },
+                    VoiceCommandType::StartSpelling => {
+                        self.spelling = Some(String::new());
+                        debug!("Spelling mode started");
+                        
+                        // Send a command event
+                        let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
+                    },
+                    VoiceCommandType::Help => {
+                        // Send a command event, then the commands for the application to show
+                        let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
+                        let _ = self.event_sender.try_send(VoiceCommandEvent::HelpRequested(self.available_commands()));
+                    },
+                    VoiceCommandType::Sleep | VoiceCommandType::Wake => {
+                        // Waking up while awake changes nothing
+                        self.set_asleep(command.command_type == VoiceCommandType::Sleep);
+                        let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
+                    },
+                    VoiceCommandType::ReportMistake => {
+                        // Count the command before it as heard by mistake, once
+                        if self.report_mistake() {
+                            let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
+                        } else {
+                            let _ = self.event_sender.try_send(VoiceCommandEvent::Error(
+                                "No command to report".to_string()
+                            ));
+                        }
+                    },
+                    // Text typed into another application is taken out there first
+                    VoiceCommandType::Undo | VoiceCommandType::Redo if self.config.inject_text && self.can_change_injected(&command.command_type) => {
+                        let result = if command.command_type == VoiceCommandType::Undo {
+                            self.undo_injected()
+                        } else {
+                            self.redo_injected()
+                        };
+                        
+                        match result {
+                            // Send a command event
+                            Ok(()) => {
+                                let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
+                            },
+                            // Send error event if it failed
+                            Err(e) => {
+                                let _ = self.event_sender.try_send(VoiceCommandEvent::Error(e.to_string()));
+                            },
+                        }
+                    },
+                    VoiceCommandType::Undo => {
+                        // Apply undo operation
+                        if let Some(new_text) = self.text_editor.undo() {
+                            // Update the current text
+                            let mut current_text = self.current_text.lock();
+                            *current_text = new_text;
+                            
+                            // Send a command event
+                            let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
+                            self.text_updated(&current_text);
+                        } else {
+                            // Send error event if no operation to undo
+                            let _ = self.event_sender.try_send(VoiceCommandEvent::Error(
+                                "Nothing to undo".to_string()
+                            ));
+                        }
+                    },
+                    VoiceCommandType::Redo => {
+                        // Apply redo operation
+                        if let Some(new_text) = self.text_editor.redo() {
+                            // Update the current text
+                            let mut current_text = self.current_text.lock();
+                            *current_text = new_text;
+                            
+                            // Send a command event
                             let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
+                            self.text_updated(&current_text);
+                        } else {
+                            // Send error event if no operation to redo
+                            let _ = self.event_sender.try_send(VoiceCommandEvent::Error(
+                                "Nothing to redo".to_string()
+                            ));
                         }
+                    },
+                    // Add other command types as needed
+                    _ => {
+                        // For now, just send the command event
+                        let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
                     }
-                    
-                    detected_commands.push(command);
-                    // Once we've found a command, no need to check further
+                }
+                
+                // Counted for tuning the sensitivity; a report of a mistake is left out, as it isn't one itself
+                if command.command_type != VoiceCommandType::ReportMistake {
+                    self.stats.record(&command);
+                    self.last_command = Some(command.command_type.clone());
+                }
+                
+                detected_commands.push(command);
+                
+                // Spelled words aren't commands, nothing else is heard once asleep,
+                // and a command with no words can't be moved past
+                if self.is_spelling() || self.asleep || span.end == 0 {
                     break;
                 }
             }
+            
+            // Once we've found a command, no need to check further
+            if heard {
+                break;
+            }
         }
         
         Ok(detected_commands)
     }
     
+    /// The first command heard in `text`
+    ///
+    /// The user's commands come first, then ones with parameters, so "delete last three words" isn't taken for "delete"
+    /// and "switch to coding profile" isn't taken for a window; these are heard at the start of the text. The plain
+    /// triggers are heard anywhere in it, the one said first taken, and the longest of those said at the same place.
+    /// Commands overridden with applications are only heard while one of those has the focus.
+    fn detect_command(&mut self, text: &str, has_prefix: bool) -> Option<VoiceCommand> {
+        // Application commands are only heard after the prefix or wake word, so dictating "open the file" opens nothing
+        let app_grammar = self.app_grammar.as_ref().filter(|_| has_prefix);
+        // Commands set for other applications than the one in focus are left out
+        let available = |trigger: &str| self.config.is_command_available(trigger, self.active_window.as_ref());
+        let parse = |patterns: &[CommandPattern], text: &str| {
+            patterns.iter().filter(|pattern| available(&pattern.trigger())).find_map(|pattern| pattern.parse(text))
+        };
+        
+        self.script.commands().iter()
+            .filter(|command| available(&command.pattern().trigger()))
+            .find_map(|command| command.detect(text))
+            .or_else(|| {
+                // Plugins run as they are matched; a failing one is reported and left out
+                let current_text = self.current_text.lock().clone();
+                self.plugins.iter_mut().find_map(|plugin| match plugin.run(text, &current_text)? {
+                    Ok(command) => Some(command),
+                    Err(e) => {
+                        let _ = self.event_sender.try_send(VoiceCommandEvent::Error(e.to_string()));
+                        None
+                    }
+                })
+            })
+            .or_else(|| parse(self.grammar.patterns(), text))
+            .or_else(|| app_grammar.and_then(|grammar| parse(grammar.patterns(), text)))
+            .or_else(|| {
+                // Of commands said at the same place, one heard exactly goes before a misheard one
+                self.command_detectors.iter()
+                    .filter(|detector| available(&detector.trigger))
+                    .filter_map(|detector| {
+                        let misheard = !text.contains(&detector.trigger);
+                        detector.detect(text, self.config.sensitivity).map(|command| (misheard, command))
+                    })
+                    .min_by_key(|(misheard, command)| command.span.clone().map(|span| (span.start, *misheard, std::cmp::Reverse(span.end))))
+                    .map(|(_, command)| command)
+            })
+    }
+    
+    /// The command waking up, when it is all that was said
+    fn wake_up(&self, text: &str) -> Option<VoiceCommand> {
+        let said = text.trim_end_matches(|c: char| !c.is_alphanumeric());
+        self.command_detectors.iter()
+            .filter(|detector| detector.command_type == VoiceCommandType::Wake)
+            .filter_map(|detector| detector.detect(said, self.config.sensitivity))
+            .find(|command| command.span.as_ref().is_some_and(|span| span.start == 0 && span.end == said.len()))
+    }
+    
+    /// The command cancelling, if it was said anywhere in `text`
+    fn cancellation(&self, text: &str) -> Option<VoiceCommand> {
+        self.command_detectors.iter()
+            .filter(|detector| detector.command_type == VoiceCommandType::Cancel && self.is_available(&detector.trigger))
+            .find_map(|detector| detector.detect(text, self.config.sensitivity))
+    }
+    
+    /// Cancel what is going on with `command`, which takes the utterance at `span` with it
+    fn cancel_utterance(&mut self, mut command: VoiceCommand, span: Range<usize>) -> Vec<VoiceCommand> {
+        self.cancel();
+        command.mode = self.mode;
+        command.span = Some(span);
+        let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
+        vec![command]
+    }
+    
+    /// Write the characters spelled in a transcription, until spelling is ended
+    ///
+    /// The characters are inserted as text, and the words before "end spelling" are
+    /// spelled too. Characters spelled one after another in the mode make one word.
+    fn process_spelling(&mut self, text: &str) -> Vec<VoiceCommand> {
+        let text = text.trim();
+        let lowered = text.to_lowercase();
+        let stop = self.command_detectors.iter()
+            .filter(|detector| detector.command_type == VoiceCommandType::StopSpelling)
+            .find_map(|detector| {
+                let command = detector.detect(&lowered, self.config.sensitivity)?;
+                Some((command, lowered.find(&detector.trigger)))
+            });
+        
+        // Words said along with a misheard "end spelling" can't be told apart from it, so they are dropped
+        let spelled_text = match &stop {
+            Some((_, Some(pos))) => &lowered[..*pos],
+            Some((_, None)) => "",
+            None => &lowered,
+        };
+        let letters = spell(spelled_text);
+        
+        let mut commands = Vec::new();
+        if !letters.is_empty() {
+            let continues = self.spelling.as_ref().is_some_and(|spelled| !spelled.is_empty());
+            let mut current_text = self.current_text.lock();
+            if let Ok(new_text) = self.text_editor.apply_spelling(&current_text, &letters, continues) {
+                // Update the current text
+                *current_text = new_text;
+                
+                // Send the spelled characters as inserted text
+                let mut command = VoiceCommand::new(VoiceCommandType::InsertText, text)
+                    .with_parameter("text", CommandParameter::Text(letters.clone()));
+                command.mode = self.mode;
+                let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
+                self.text_updated(&current_text);
+                commands.push(command);
+            } else {
+                // Send error event if operation failed
+                let _ = self.event_sender.try_send(VoiceCommandEvent::Error(
+                    "Failed to insert spelled text".to_string()
+                ));
+            }
+            
+            if let Some(spelled) = &mut self.spelling {
+                spelled.push_str(&letters);
+            }
+        }
+        
+        if let Some((mut command, _)) = stop {
+            command.mode = self.mode;
+            self.spelling = None;
+            debug!("Spelling mode ended");
+            let _ = self.event_sender.try_send(VoiceCommandEvent::CommandDetected(command.clone()));
+            commands.push(command);
+        }
+        commands
+    }
+    
     /// Check if a command type is registered
     pub fn is_command_registered(&self, command_type: &VoiceCommandType) -> bool {
         self.registered_commands.contains(command_type)
@@ -767,8 +2140,18 @@ impl VoiceCommandManager {
     
     /// Register a custom command
     pub fn register_custom_command(&mut self, trigger: &str, command_type: VoiceCommandType) -> Result<()> {
-        // Add to detectors
-        self.command_detectors.push(CommandDetector::new(trigger, command_type.clone()));
+        // Overrides apply to commands registered later as well
+        if !self.config.is_command_enabled(trigger) {
+            debug!("Custom command {:?} is disabled", trigger);
+            return Ok(());
+        }
+        
+        // Add to the grammar when it has parameters, otherwise to the detectors
+        if CommandPattern::is_pattern(trigger) {
+            self.grammar.add(CommandPattern::new(trigger, command_type.clone())?);
+        } else if let Some(detector) = CommandDetector::new(trigger, command_type.clone()).configured(&self.config) {
+            self.command_detectors.push(detector);
+        }
         
         // Add to registered commands
         self.registered_commands.insert(command_type);
@@ -776,11 +2159,150 @@ impl VoiceCommandManager {
         Ok(())
     }
     
+    /// Replace what opens applications and switches windows, e.g. to run commands elsewhere
+    pub fn set_app_control(&mut self, app_control: Box<dyn AppControl>) {
+        self.app_control = app_control;
+    }
+    
+    /// Replace what changes the volume and controls playback
+    pub fn set_system_control(&mut self, system_control: Box<dyn SystemControl>) {
+        self.system_control = system_control;
+    }
+    
+    /// Add a plugin's commands, after those of the plugins already loaded
+    pub fn add_plugin(&mut self, plugin: CommandPlugin) {
+        if !plugin.patterns().is_empty() {
+            self.registered_commands.insert(VoiceCommandType::Macro);
+        }
+        self.plugins.push(plugin);
+    }
+    
+    /// Replace what presses the keys of keystroke commands
+    pub fn set_keyboard(&mut self, keyboard: Box<dyn Keyboard>) {
+        self.keyboard = keyboard;
+    }
+    
+    /// Press the keys of a keystroke command, if commands may press keys
+    fn press_keys(&self, command: &VoiceCommand) -> Result<()> {
+        if !self.config.keystrokes {
+            anyhow::bail!("Pressing keys is turned off; allow keystrokes in the voice command settings");
+        }
+        let chord = KeyChord::parse(command.text("keys").unwrap_or_default())?;
+        self.keyboard.press(&chord)
+    }
+    
+    /// Replace where dictated text is typed
+    pub fn set_output_sink(&mut self, output: Box<dyn OutputSink>) {
+        self.output = output;
+    }
+    
+    /// Whether typing dictated text into the application that has the focus is turned on
+    pub fn injects_text(&self) -> bool {
+        self.config.inject_text
+    }
+    
+    /// Type dictated `text` into the application that has the focus, set off from what was typed before
+    pub fn inject(&mut self, text: &str) -> Result<()> {
+        if !self.config.inject_text {
+            anyhow::bail!("Typing text is turned off; enable it in the voice command settings");
+        }
+        if text.is_empty() {
+            return Ok(());
+        }
+        let chunk = self.injected.chunk(text, self.active_window.as_ref());
+        self.output.type_text(&chunk.text)?;
+        self.injected.record(chunk);
+        Ok(())
+    }
+    
+    /// Type what was said in `text` into the application that has the focus: the dictation,
+    /// with the punctuation and line breaks of the `commands` heard in it where they were said
+    pub fn inject_utterance(&mut self, text: &str, commands: &[VoiceCommand]) -> Result<()> {
+        self.inject(&typed_output(text, commands))
+    }
+    
+    /// Whether "undo" or "redo" has typed text to act on
+    fn can_change_injected(&self, command_type: &VoiceCommandType) -> bool {
+        match command_type {
+            VoiceCommandType::Undo => self.injected.can_undo(),
+            _ => self.injected.can_redo(),
+        }
+    }
+    
+    /// Erase the text typed last with backspaces
+    fn undo_injected(&mut self) -> Result<()> {
+        let chunk = self.injected.undo(self.active_window.as_ref())?;
+        self.output.erase(chunk.backspaces())
+    }
+    
+    /// Type the text undone last again
+    fn redo_injected(&mut self) -> Result<()> {
+        let chunk = self.injected.redo(self.active_window.as_ref())?;
+        self.output.type_text(&chunk.text)
+    }
+    
+    /// Start the configured program a command runs, reporting how it ended once it exits
+    fn run_shell_command(&self, shell_command: ShellCommand, parameters: &BTreeMap<String, CommandParameter>) {
+        // Programs can take a while, so they run off the transcription thread
+        let parameters = parameters.clone();
+        let sender = self.event_sender.clone();
+        let stop = Arc::clone(&self.stop_programs);
+        std::thread::spawn(move || {
+            let event = match shell_command.run(&parameters, &stop) {
+                Ok(output) => VoiceCommandEvent::ShellCommandFinished(output),
+                Err(e) => VoiceCommandEvent::Error(e.to_string()),
+            };
+            let _ = sender.try_send(event);
+        });
+    }
+    
+    /// The configured program a command runs, if running programs is allowed
+    fn shell_command(&self, command: &VoiceCommand) -> Result<ShellCommand> {
+        if !self.config.shell_commands.enabled {
+            anyhow::bail!("Running programs is turned off; enable shell commands in the voice command settings");
+        }
+        let name = command.text("program").unwrap_or_default();
+        self.config.shell_commands.find(name)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No shell command named {} is configured", name))
+    }
+    
+    /// How often each command was carried out, how well it was heard and how often by mistake
+    pub fn command_stats(&self) -> &CommandStats {
+        &self.stats
+    }
+    
+    /// Count on from `stats`, e.g. as saved the last time
+    pub fn set_command_stats(&mut self, stats: CommandStats) {
+        self.stats = stats;
+    }
+    
+    /// Forget the commands counted
+    pub fn clear_command_stats(&mut self) {
+        self.stats.clear();
+    }
+    
+    /// Count the last command carried out as heard by mistake, once; false when there is none
+    pub fn report_mistake(&mut self) -> bool {
+        match self.last_command.take() {
+            Some(command_type) => {
+                self.stats.report_wrong(&command_type);
+                true
+            }
+            None => false,
+        }
+    }
+    
     /// Get the current text being edited
     pub fn get_current_text(&self) -> String {
         self.current_text.lock().clone()
     }
     
+    /// Let the application know the text was changed, sending all of it
+    fn text_updated(&self, text: &str) {
+        let _ = self.event_sender.try_send(VoiceCommandEvent::TextUpdated(text.to_string()));
+    }
+    
     /// Set the current text
     pub fn set_current_text(&self, text: &str) {
         let mut current = self.current_text.lock();
@@ -800,6 +2322,9 @@ struct CommandDetector {
     
     /// The type of command this detector is for
     command_type: VoiceCommandType,
+    
+    /// Sensitivity of this command, in place of the one given to `detect`
+    sensitivity: Option<f32>,
 }
 
 impl CommandDetector {
@@ -808,49 +2333,87 @@ impl CommandDetector {
         Self {
             trigger: trigger.to_lowercase(),
             command_type,
+            sensitivity: None,
+        }
+    }
+    
+    /// The detector with the settings overridden for its trigger, or `None` when the command is disabled
+    fn configured(mut self, config: &VoiceCommandConfig) -> Option<Self> {
+        match config.command_override(&self.trigger) {
+            Some(command_override) if !command_override.enabled => None,
+            Some(command_override) => {
+                self.sensitivity = command_override.sensitivity;
+                Some(self)
+            }
+            None => Some(self),
         }
     }
     
     /// Detect if this command is present in the given text
+    ///
+    /// Besides the exact trigger, words that sound alike to the recognizer are
+    /// accepted when their similarity to the trigger is above `sensitivity`, or
+    /// the detector's own; at 1.0 only the exact trigger is.
     fn detect(&self, text: &str, sensitivity: f32) -> Option<VoiceCommand> {
+        let sensitivity = self.sensitivity.unwrap_or(sensitivity);
+        
+        let detected = |span: Range<usize>, score: f32| {
+            Some(VoiceCommand { span: Some(span), score, ..VoiceCommand::new(self.command_type.clone(), text) })
+        };
+        
         // Simple strategies first - exact match
-        if text.contains(&self.trigger) {
-            return Some(VoiceCommand::new(self.command_type.clone(), text));
+        if let Some(start) = text.find(&self.trigger) {
+            // The trigger can be heard within words, as "new line" in "knew line", so the span takes them whole
+            let end = start + self.trigger.len();
+            let start = text[..start].trim_end_matches(|c: char| !c.is_whitespace()).len();
+            let end = text.len() - text[end..].trim_start_matches(|c: char| !c.is_whitespace()).len();
+            return detected(start..end, 1.0);
         }
         
-        // For higher sensitivity, perform more fuzzy matching
-        if sensitivity > 0.5 {
-            // Split the text into words
-            let text_words: Vec<&str> = text.split_whitespace().collect();
-            let trigger_words: Vec<&str> = self.trigger.split_whitespace().collect();
+        if sensitivity >= 1.0 {
+            return None;
+        }
+        let min_similarity = sensitivity.max(MIN_SIMILARITY);
+        
+        // Split the text into words, without the punctuation the recognizer adds
+        let text_words: Vec<&str> = text.split_whitespace()
+            .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+            .filter(|word| !word.is_empty())
+            .collect();
+        let trigger_words: Vec<&str> = self.trigger.split_whitespace().collect();
+        
+        // If the trigger is a single word
+        if trigger_words.len() == 1 {
+            // Every word is compared, so short triggers must be exact; one edit turns "stop" into "shop"
+            if self.trigger.chars().count() < MIN_FUZZY_CHARS {
+                return None;
+            }
             
-            // If the trigger is a single word
-            if trigger_words.len() == 1 {
-                // Check if any word is similar to our trigger
-                for word in &text_words {
-                    if word_similarity(word, &self.trigger) > sensitivity {
-                        return Some(VoiceCommand::new(self.command_type.clone(), text));
-                    }
+            // Check if any word is similar to our trigger
+            for word in &text_words {
+                let similarity = word_similarity(word, &self.trigger, min_similarity);
+                if similarity > min_similarity {
+                    let start = offset_in(text, word);
+                    return detected(start..start + word.len(), similarity);
                 }
-            } else {
-                // For multi-word triggers, try to match a sequence
-                if text_words.len() >= trigger_words.len() {
-                    'outer: for i in 0..=(text_words.len() - trigger_words.len()) {
-                        let mut total_similarity = 0.0;
-                        
-                        for j in 0..trigger_words.len() {
-                            let similarity = word_similarity(text_words[i + j], trigger_words[j]);
-                            if similarity < 0.3 {  // Minimum word match threshold
-                                continue 'outer;
-                            }
-                            total_similarity += similarity;
-                        }
-                        
-                        let avg_similarity = total_similarity / trigger_words.len() as f32;
-                        if avg_similarity > sensitivity {
-                            return Some(VoiceCommand::new(self.command_type.clone(), text));
-                        }
+            }
+        } else if text_words.len() >= trigger_words.len() {
+            // For multi-word triggers, try to match a sequence
+            'outer: for window in text_words.windows(trigger_words.len()) {
+                let mut total_similarity = 0.0;
+                
+                for (word, trigger_word) in window.iter().zip(&trigger_words) {
+                    let similarity = word_similarity(word, trigger_word, MIN_WORD_SIMILARITY);
+                    if similarity < MIN_WORD_SIMILARITY {
+                        continue 'outer;
                     }
+                    total_similarity += similarity;
+                }
+                
+                let avg_similarity = total_similarity / trigger_words.len() as f32;
+                if avg_similarity > min_similarity {
+                    let last = window[window.len() - 1];
+                    return detected(offset_in(text, window[0])..offset_in(text, last) + last.len(), avg_similarity);
                 }
             }
         }
@@ -859,30 +2422,396 @@ impl CommandDetector {
     }
 }
 
-/// Calculate similarity between two words (simplified edit distance approach)
-fn word_similarity(a: &str, b: &str) -> f32 {
+/// Where `part`, a slice of `text`, starts in it
+fn offset_in(text: &str, part: &str) -> usize {
+    part.as_ptr() as usize - text.as_ptr() as usize
+}
+
+/// Lowest similarity a misheard command is accepted at, whatever the sensitivity
+const MIN_SIMILARITY: f32 = 0.6;
+
+/// Lowest similarity of each word of a multi-word trigger, so well-matched words can't carry a wrong one
+const MIN_WORD_SIMILARITY: f32 = 0.5;
+
+/// Single-word triggers shorter than this are only detected exactly
+const MIN_FUZZY_CHARS: usize = 5;
+
+/// Similarity of two words from 0.0 to 1.0: one less their edit distance over the longer word's length
+///
+/// Similarities below `min` are returned as 0.0, which lets the distance stop early.
+fn word_similarity(a: &str, b: &str, min: f32) -> f32 {
     if a == b {
         return 1.0;
     }
     
     let a_chars: Vec<char> = a.chars().collect();
     let b_chars: Vec<char> = b.chars().collect();
+    let max_len = a_chars.len().max(b_chars.len());
+    let max_distance = ((1.0 - min.clamp(0.0, 1.0)) * max_len as f32).floor() as usize;
+    
+    match edit_distance(&a_chars, &b_chars, max_distance) {
+        Some(distance) => 1.0 - distance as f32 / max_len as f32,
+        None => 0.0,
+    }
+}
+
+/// Damerau-Levenshtein distance (optimal string alignment) between `a` and `b`, counting a swap of
+/// neighbouring characters as one edit; `None` as soon as it is sure to be over `max`
+fn edit_distance(a: &[char], b: &[char], max: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    
+    // Rows of the distance matrix for the two previous characters of `a` and the current one
+    let mut before_last: Vec<usize> = vec![0; b.len() + 1];
+    let mut last: Vec<usize> = (0..=b.len()).collect();
+    let mut current: Vec<usize> = vec![0; b.len() + 1];
+    
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (last[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(last[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before_last[j - 2] + 1);
+            }
+        }
+        
+        if current.iter().min().is_some_and(|&lowest| lowest > max) {
+            return None;
+        }
+        std::mem::swap(&mut before_last, &mut last);
+        std::mem::swap(&mut last, &mut current);
+    }
     
-    // For very different length words, return low similarity
-    let max_len = a_chars.len().max(b_chars.len()) as f32;
-    if (a_chars.len() as f32 - b_chars.len() as f32).abs() / max_len > 0.5 {
-        return 0.0;
+    Some(last[b.len()]).filter(|&distance| distance <= max)
+}
+
+/// The range of `text` that `scope` covers, if any
+fn select_range(text: &str, scope: &SelectScope) -> Option<Range<usize>> {
+    let end = text.trim_end().len();
+    let body = &text[..end];
+    match scope {
+        SelectScope::All => Some(0..end),
+        SelectScope::LastWord => {
+            let start = body.rfind(char::is_whitespace).map_or(0, |pos| after_char(body, pos));
+            Some(start..end)
+        }
+        SelectScope::LastSentence => {
+            // The sentence's own closing punctuation is part of it. The one before ends at a
+            // space after its punctuation and any closing quotes, so points in "2.5" don't end one
+            let unclosed = body.trim_end_matches(|c| SENTENCE_ENDINGS.contains(&c) || SENTENCE_CLOSERS.contains(&c));
+            let start = unclosed.char_indices().rev()
+                .find(|&(pos, c)| c.is_whitespace() && unclosed[..pos].trim_end_matches(SENTENCE_CLOSERS).ends_with(SENTENCE_ENDINGS))
+                .map_or(0, |(pos, _)| pos);
+            Some(skip_whitespace(body, start)..end)
+        }
+        SelectScope::LastParagraph => {
+            let start = body.rfind("\n\n").map(|pos| pos + 2)
+                .or_else(|| body.rfind('\n').map(|pos| pos + 1))
+                .unwrap_or(0);
+            Some(skip_whitespace(body, start)..end)
+        }
+        SelectScope::Phrase(phrase) => find_ignoring_case(text, phrase).pop(),
+        SelectScope::Between(from, to) => {
+            let ends = find_ignoring_case(text, to);
+            find_ignoring_case(text, from).into_iter().rev().find_map(|from| {
+                let to = ends.iter().find(|to| to.start >= from.end)?;
+                Some(from.start..to.end)
+            })
+        }
+    }
+}
+
+/// `text` with `format_op` applied to all of it
+fn formatted(text: &str, format_op: &FormatOperation) -> String {
+    match format_op {
+        FormatOperation::Capitalize(_) => capitalize_words(text),
+        FormatOperation::Lowercase(_) => text.to_lowercase(),
+        FormatOperation::Uppercase(_) => text.to_uppercase(),
+        FormatOperation::Style(style) => styled(text, style),
+    }
+}
+
+/// `text` with the markers of `style` around it
+fn styled(text: &str, style: &TextStyle) -> String {
+    match style {
+        TextStyle::Bold => format!("**{}**", text),
+        TextStyle::Italic => format!("*{}*", text),
+        TextStyle::Underline => format!("_{}_", text),
+    }
+}
+
+/// `text` with the first letter of each word in uppercase
+fn capitalize_words(text: &str) -> String {
+    let mut capitalized = String::with_capacity(text.len());
+    let mut word_start = true;
+    for c in text.chars() {
+        if word_start {
+            capitalized.extend(c.to_uppercase());
+        } else {
+            capitalized.push(c);
+        }
+        word_start = c.is_whitespace();
+    }
+    capitalized
+}
+
+/// Position after the character at `pos`
+fn after_char(text: &str, pos: usize) -> usize {
+    pos + text[pos..].chars().next().map_or(0, char::len_utf8)
+}
+
+/// Position of the first character at or after `pos` that isn't whitespace
+fn skip_whitespace(text: &str, pos: usize) -> usize {
+    text.len() - text[pos..].trim_start().len()
+}
+
+/// Where `phrase` occurs in `text`, without regard to letter case, in order
+fn find_ignoring_case(text: &str, phrase: &str) -> Vec<Range<usize>> {
+    if phrase.is_empty() {
+        return Vec::new();
+    }
+    
+    text.char_indices()
+        .filter_map(|(start, _)| {
+            let mut rest = text[start..].char_indices();
+            for expected in phrase.chars() {
+                let (_, c) = rest.next()?;
+                if !c.to_lowercase().eq(expected.to_lowercase()) {
+                    return None;
+                }
+            }
+            let end = rest.next().map_or(text.len(), |(offset, _)| start + offset);
+            Some(start..end)
+        })
+        .collect()
+}
+
+/// The most recent words of `text` sounding like `phrase`, without their punctuation
+///
+/// Each word has to be about as similar as a word of a multi-word trigger, and the
+/// words together as similar as a misheard trigger.
+fn find_similar(text: &str, phrase: &str) -> Option<Range<usize>> {
+    let phrase_words: Vec<String> = phrase.split_whitespace().map(str::to_lowercase).collect();
+    if phrase_words.is_empty() {
+        return None;
+    }
+    
+    // Words of the text with where they are, punctuation trimmed
+    let mut text_words = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        match start {
+            Some(from) if c.is_whitespace() => {
+                let word = &text[from..i];
+                let trimmed = word.trim_start_matches(|c: char| !c.is_alphanumeric());
+                let word_start = from + word.len() - trimmed.len();
+                let trimmed = trimmed.trim_end_matches(|c: char| !c.is_alphanumeric());
+                if !trimmed.is_empty() {
+                    text_words.push((trimmed.to_lowercase(), word_start..word_start + trimmed.len()));
+                }
+                start = None;
+            }
+            None if !c.is_whitespace() => start = Some(i),
+            _ => {}
+        }
     }
     
-    // Calculate number of matching characters (simplified)
-    let mut matches = 0;
-    for i in 0..a_chars.len().min(b_chars.len()) {
-        if a_chars[i] == b_chars[i] {
-            matches += 1;
+    text_words.windows(phrase_words.len()).rev().find_map(|window| {
+        let mut total_similarity = 0.0;
+        for ((word, _), phrase_word) in window.iter().zip(&phrase_words) {
+            let similarity = word_similarity(word, phrase_word, MIN_WORD_SIMILARITY);
+            if similarity < MIN_WORD_SIMILARITY {
+                return None;
+            }
+            total_similarity += similarity;
         }
+        
+        (total_similarity / phrase_words.len() as f32 > MIN_SIMILARITY)
+            .then(|| window[0].1.start..window[window.len() - 1].1.end)
+    })
+}
+
+/// `phrase` as it was said in `spoken`, before the command was lowercased
+fn as_spoken(spoken: &str, phrase: &str) -> String {
+    find_ignoring_case(spoken, phrase).pop()
+        .map_or_else(|| phrase.to_string(), |range| spoken[range].to_string())
+}
+
+/// The mode a command switches to, when it is nothing but "command mode", "dictation mode" or "mixed mode"
+fn mode_switch(text: &str) -> Option<CommandMode> {
+    let words: Vec<&str> = text.split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .collect();
+    match words[..] {
+        ["command", "mode"] => Some(CommandMode::Command),
+        ["dictation", "mode"] => Some(CommandMode::Dictation),
+        ["mixed", "mode"] => Some(CommandMode::Mixed),
+        _ => None,
+    }
+}
+
+/// What a select command selects, from its phrases or the words it was said with
+fn select_scope(command: &VoiceCommand) -> SelectScope {
+    if let Some(phrase) = command.text("phrase") {
+        SelectScope::Phrase(phrase.to_string())
+    } else if let (Some(from), Some(to)) = (command.text("from"), command.text("to")) {
+        SelectScope::Between(from.to_string(), to.to_string())
+    } else if command.trigger_text.contains("sentence") {
+        SelectScope::LastSentence
+    } else if command.trigger_text.contains("paragraph") {
+        SelectScope::LastParagraph
+    } else if command.trigger_text.contains("word") {
+        SelectScope::LastWord
+    } else {
+        SelectScope::All
+    }
+}
+
+/// What a delete command deletes, from its count or the words it was said with
+fn delete_scope(command: &VoiceCommand) -> DeleteScope {
+    if let Some(count) = command.number("count") {
+        DeleteScope::Words(count as usize)
+    } else if command.trigger_text.contains("word") {
+        DeleteScope::LastWord
+    } else if command.trigger_text.contains("sentence") {
+        DeleteScope::LastSentence
+    } else if command.trigger_text.contains("paragraph") {
+        DeleteScope::LastParagraph
+    } else {
+        // Default to last word
+        DeleteScope::LastWord
+    }
+}
+
+/// What part of the text a case command changes, from the words it was said with
+fn format_scope(command: &VoiceCommand) -> FormatScope {
+    let words: Vec<&str> = command.trigger_text.split(|c: char| !c.is_alphanumeric()).collect();
+    if words.contains(&"sentence") {
+        FormatScope::LastSentence
+    } else if words.contains(&"selection") || words.contains(&"selected") {
+        FormatScope::Selection
+    } else if words.contains(&"all") || words.contains(&"everything") {
+        FormatScope::All
+    } else {
+        FormatScope::LastWord
+    }
+}
+
+/// The case conversion a capitalize, lowercase or uppercase command makes
+fn case_operation(command: &VoiceCommand) -> FormatOperation {
+    let scope = format_scope(command);
+    match command.command_type {
+        VoiceCommandType::Capitalize => FormatOperation::Capitalize(scope),
+        VoiceCommandType::Lowercase => FormatOperation::Lowercase(scope),
+        _ => FormatOperation::Uppercase(scope),
+    }
+}
+
+/// `text` without the commands said in it, so the transcript keeps only what was dictated
+///
+/// Punctuation the recognizer put right after a command goes with it, so
+/// "Send it, new paragraph, then rest." leaves "Send it, then rest.".
+pub fn strip_commands(text: &str, commands: &[VoiceCommand]) -> String {
+    let mut spans: Vec<Range<usize>> = commands.iter()
+        .filter_map(|command| command.span.clone())
+        .filter(|span| text.get(span.clone()).is_some())
+        .collect();
+    if spans.is_empty() {
+        return text.to_string();
     }
+    spans.sort_by_key(|span| span.start);
     
-    matches as f32 / max_len
+    let mut dictated = String::new();
+    let mut kept_from = 0;
+    for span in spans {
+        if span.start < kept_from {
+            continue;
+        }
+        join_dictated(&mut dictated, &text[kept_from..span.start]);
+        let after = &text[span.end..];
+        kept_from = text.len() - after.trim_start_matches(TRAILING_PUNCTUATION).len();
+    }
+    join_dictated(&mut dictated, &text[kept_from..]);
+    dictated
+}
+
+/// What an utterance types into another application: `text` without the commands said in it,
+/// but with the punctuation and line breaks they write, in the order they were said
+///
+/// So "Dear Sam comma new line thanks period" types "Dear Sam,\nthanks.".
+pub fn typed_output(text: &str, commands: &[VoiceCommand]) -> String {
+    let mut said: Vec<(Range<usize>, Option<&str>)> = commands.iter()
+        .filter_map(|command| command.span.clone().map(|span| (span, punctuation(&command.command_type))))
+        .filter(|(span, _)| text.get(span.clone()).is_some())
+        .collect();
+    said.sort_by_key(|(span, _)| span.start);
+    
+    let mut typed = String::new();
+    let mut kept_from = 0;
+    for (span, punctuation) in said {
+        if span.start < kept_from {
+            continue;
+        }
+        join_dictated(&mut typed, &text[kept_from..span.start]);
+        typed.push_str(punctuation.unwrap_or_default());
+        let after = &text[span.end..];
+        kept_from = text.len() - after.trim_start_matches(TRAILING_PUNCTUATION).len();
+    }
+    join_dictated(&mut typed, &text[kept_from..]);
+    typed
+}
+
+/// Punctuation taken out along with the command it follows
+const TRAILING_PUNCTUATION: [char; 6] = [',', '.', ';', ':', '!', '?'];
+
+/// Add a piece of dictated text after the pieces before it, a space apart
+fn join_dictated(dictated: &mut String, piece: &str) {
+    let piece = piece.trim();
+    if piece.is_empty() {
+        return;
+    }
+    if !dictated.is_empty() && !dictated.ends_with(char::is_whitespace) && !piece.starts_with(TRAILING_PUNCTUATION) {
+        dictated.push(' ');
+    }
+    dictated.push_str(piece);
+}
+
+/// What a punctuation or line break command writes
+fn punctuation(command_type: &VoiceCommandType) -> Option<&'static str> {
+    match command_type {
+        VoiceCommandType::NewLine => Some("\n"),
+        VoiceCommandType::NewParagraph => Some("\n\n"),
+        VoiceCommandType::Period => Some("."),
+        VoiceCommandType::Comma => Some(","),
+        VoiceCommandType::QuestionMark => Some("?"),
+        VoiceCommandType::ExclamationMark => Some("!"),
+        _ => None,
+    }
+}
+
+/// The change a macro step makes to the text, if it edits it
+fn text_operation(command: &VoiceCommand) -> Option<TextEditOperation> {
+    if let Some(punctuation) = punctuation(&command.command_type) {
+        return Some(TextEditOperation::Insert(punctuation.to_string()));
+    }
+    let insert = |text: &str| Some(TextEditOperation::Insert(text.to_string()));
+    match &command.command_type {
+        VoiceCommandType::Delete => Some(TextEditOperation::Delete(delete_scope(command))),
+        VoiceCommandType::Capitalize | VoiceCommandType::Lowercase | VoiceCommandType::Uppercase => {
+            Some(TextEditOperation::Format(case_operation(command)))
+        }
+        VoiceCommandType::InsertText => insert(command.text("text").unwrap_or_default()),
+        VoiceCommandType::Replace => Some(TextEditOperation::Replace {
+            original: command.text("original").unwrap_or_default().to_string(),
+            replacement: command.text("replacement").unwrap_or_default().to_string(),
+        }),
+        _ => None,
+    }
 }
 
 #[cfg(test)]
@@ -904,25 +2833,881 @@ mod tests {
         assert!(detector.detect("hello", 0.8).is_none());
     }
     
+    #[test]
+    fn test_edit_distance() {
+        let distance = |a: &str, b: &str| {
+            let a: Vec<char> = a.chars().collect();
+            let b: Vec<char> = b.chars().collect();
+            edit_distance(&a, &b, usize::MAX)
+        };
+        
+        assert_eq!(distance("delete", "delete"), Some(0));
+        assert_eq!(distance("delete", "deleet"), Some(1));
+        assert_eq!(distance("paragraph", "paragraf"), Some(2));
+        assert_eq!(distance("undo", ""), Some(4));
+        assert_eq!(distance("kitten", "sitting"), Some(3));
+        
+        // Gives up once the distance is over the limit
+        let a: Vec<char> = "capitalize".chars().collect();
+        let b: Vec<char> = "paragraph".chars().collect();
+        assert_eq!(edit_distance(&a, &b, 2), None);
+        
+        // A swap counts as one edit however long the word, so similarity isn't biased by where it is
+        assert!((word_similarity("exclamation", "exclamaiton", 0.0) - 10.0 / 11.0).abs() < 1e-6);
+        assert!((word_similarity("capitalize", "acpitalize", 0.0) - 0.9).abs() < 1e-6);
+    }
+    
+    #[test]
+    fn test_common_misrecognitions() {
+        let detects = |trigger: &str, text: &str| {
+            CommandDetector::new(trigger, VoiceCommandType::Delete).detect(text, 0.7).is_some()
+        };
+        
+        // Misheard commands are still detected
+        assert!(detects("capitalize", "capitalise"));
+        assert!(detects("paragraph", "paragraf"));
+        assert!(detects("exclamation", "Exclamations!"));
+        assert!(detects("new line", "new lime"));
+        assert!(detects("delete that", "delete dat"));
+        assert!(detects("question mark", "question marc."));
+        assert!(detects("delete last word", "delete lost word"));
+        
+        // Other words are not, nor are short triggers with a letter changed
+        assert!(!detects("stop", "we went to the shop"));
+        assert!(!detects("undo", "it was into the night"));
+        assert!(!detects("redo", "the ready state"));
+        assert!(!detects("period", "a perfect day"));
+        assert!(!detects("new line", "new lights"));
+        assert!(!detects("delete that", "the lead that"));
+        
+        // At full sensitivity only the exact trigger is detected
+        let detector = CommandDetector::new("capitalize", VoiceCommandType::Capitalize);
+        assert!(detector.detect("capitalize that", 1.0).is_some());
+        assert!(detector.detect("capitalise that", 1.0).is_none());
+    }
+    
+    #[test]
+    fn test_wake_word_arms_commands() {
+        let config = VoiceCommandConfig {
+            command_prefix: Some("computer".to_string()),
+            require_prefix: true,
+            ..VoiceCommandConfig::default()
+        };
+        let (mut manager, _events) = VoiceCommandManager::new(config).unwrap();
+        manager.start().unwrap();
+        
+        assert!(manager.process_transcription("new paragraph").unwrap().is_empty());
+        
+        manager.arm();
+        assert!(manager.is_armed());
+        assert!(!manager.process_transcription("new paragraph").unwrap().is_empty());
+        
+        manager.config.wake_word.armed_secs = 0.0;
+        manager.arm();
+        assert!(!manager.is_armed());
+        
+        manager.config.wake_word.armed_secs = f32::INFINITY;
+        manager.arm();
+        assert!(manager.is_armed());
+        
+        manager.config.wake_word.armed_secs = f32::NAN;
+        manager.arm();
+        assert!(!manager.is_armed());
+    }
+    
+    #[test]
+    fn test_select_then_edit() {
+        let (mut manager, _events) = VoiceCommandManager::new(VoiceCommandConfig::default()).unwrap();
+        manager.start().unwrap();
+        manager.set_current_text("We shipped it. the launch went well.");
+        
+        manager.process_transcription("select last sentence").unwrap();
+        manager.process_transcription("capitalize").unwrap();
+        assert_eq!(manager.get_current_text(), "We shipped it. The Launch Went Well.");
+        
+        manager.process_transcription("delete that").unwrap();
+        assert_eq!(manager.get_current_text(), "We shipped it.");
+    }
+    
+    #[test]
+    fn test_sleep_until_woken() {
+        let (mut manager, mut events) = VoiceCommandManager::new(VoiceCommandConfig::default()).unwrap();
+        manager.start().unwrap();
+        manager.set_current_text("Dear Sam");
+        
+        let commands = manager.process_transcription("Comma, go to sleep. New line").unwrap();
+        assert_eq!(commands.iter().map(|command| command.command_type.clone()).collect::<Vec<_>>(), [VoiceCommandType::Comma, VoiceCommandType::Sleep]);
+        assert!(manager.is_asleep());
+        assert_eq!(manager.get_current_text(), "Dear Sam,");
+        
+        // Asleep, commands aren't heard, nor waking up among other words
+        assert!(manager.process_transcription("new paragraph").unwrap().is_empty());
+        assert!(manager.process_transcription("I have to wake up early").unwrap().is_empty());
+        assert_eq!(manager.get_current_text(), "Dear Sam,");
+        
+        let commands = manager.process_transcription("Wake up.").unwrap();
+        assert_eq!(commands[0].command_type, VoiceCommandType::Wake);
+        assert!(!manager.is_asleep());
+        assert!(!manager.process_transcription("new line").unwrap().is_empty());
+        
+        let changes: Vec<bool> = std::iter::from_fn(|| events.try_recv().ok())
+            .filter_map(|event| match event {
+                VoiceCommandEvent::SleepChanged(asleep) => Some(asleep),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(changes, [true, false]);
+    }
+    
+    #[test]
+    fn test_cancel_drops_the_utterance() {
+        let (mut manager, mut events) = VoiceCommandManager::new(VoiceCommandConfig::default()).unwrap();
+        manager.start().unwrap();
+        manager.set_current_text("Dear Sam");
+        
+        // Nothing said with it is carried out or kept as dictation
+        let said = " New paragraph, see you Friday. Cancel. ";
+        let commands = manager.process_transcription(said).unwrap();
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].command_type, VoiceCommandType::Cancel);
+        assert_eq!(manager.get_current_text(), "Dear Sam");
+        assert_eq!(strip_commands(said, &commands), "");
+        
+        // Spelling ends, without writing the letters said with it
+        manager.arm();
+        manager.process_transcription("spell that").unwrap();
+        assert!(manager.is_spelling());
+        manager.process_transcription("alpha bravo abort").unwrap();
+        assert!(!manager.is_spelling());
+        assert!(!manager.is_armed());
+        assert_eq!(manager.get_current_text(), "Dear Sam");
+        
+        let cancelled = std::iter::from_fn(|| events.try_recv().ok())
+            .filter(|event| matches!(event, VoiceCommandEvent::Cancelled))
+            .count();
+        assert_eq!(cancelled, 2);
+    }
+    
+    #[test]
+    fn test_counts_commands_and_mistakes() {
+        let (mut manager, _events) = VoiceCommandManager::new(VoiceCommandConfig::default()).unwrap();
+        manager.start().unwrap();
+        manager.set_current_text("Ship it today");
+        
+        // Reported from the application, then by voice; each command is reported once
+        manager.process_transcription("capitalise").unwrap();
+        assert!(manager.report_mistake());
+        manager.process_transcription("undo").unwrap();
+        manager.process_transcription("that was wrong").unwrap();
+        manager.process_transcription("that was wrong").unwrap();
+        assert!(!manager.report_mistake());
+        
+        let usage = manager.command_stats().usage();
+        let capitalize = usage.iter().find(|usage| usage.command_type == VoiceCommandType::Capitalize).unwrap();
+        assert_eq!((capitalize.count, capitalize.wrong), (1, 1));
+        assert!(capitalize.average_score < 1.0);
+        let undo = usage.iter().find(|usage| usage.command_type == VoiceCommandType::Undo).unwrap();
+        assert_eq!((undo.count, undo.average_score, undo.wrong), (1, 1.0, 1));
+        assert!(usage.iter().all(|usage| usage.command_type != VoiceCommandType::ReportMistake));
+        
+        manager.clear_command_stats();
+        assert!(manager.command_stats().usage().is_empty());
+    }
+    
+    #[test]
+    fn test_text_updated_after_edits() {
+        let (mut manager, mut events) = VoiceCommandManager::new(VoiceCommandConfig::default()).unwrap();
+        manager.start().unwrap();
+        manager.set_current_text("Ship it today");
+        
+        manager.process_transcription("delete last word").unwrap();
+        assert!(matches!(events.try_recv(), Ok(VoiceCommandEvent::CommandDetected(_))));
+        assert!(matches!(events.try_recv(), Ok(VoiceCommandEvent::TextUpdated(text)) if text == "Ship it"));
+        
+        manager.process_transcription("undo").unwrap();
+        assert!(matches!(events.try_recv(), Ok(VoiceCommandEvent::CommandDetected(_))));
+        assert!(matches!(events.try_recv(), Ok(VoiceCommandEvent::TextUpdated(text)) if text == "Ship it today"));
+        
+        // Selecting leaves the text as it was
+        manager.process_transcription("select today").unwrap();
+        assert!(matches!(events.try_recv(), Ok(VoiceCommandEvent::CommandDetected(_))));
+        assert!(events.try_recv().is_err());
+    }
+    
+    #[test]
+    fn test_case_conversion_scopes() {
+        let (mut manager, _events) = VoiceCommandManager::new(VoiceCommandConfig::default()).unwrap();
+        manager.start().unwrap();
+        manager.set_current_text("We shipped it. the launch went well. ");
+        
+        let commands = manager.process_transcription("uppercase last sentence").unwrap();
+        assert_eq!(case_operation(&commands[0]), FormatOperation::Uppercase(FormatScope::LastSentence));
+        assert_eq!(manager.get_current_text(), "We shipped it. THE LAUNCH WENT WELL. ");
+        
+        manager.process_transcription("lowercase everything").unwrap();
+        assert_eq!(manager.get_current_text(), "we shipped it. the launch went well. ");
+        
+        manager.process_transcription("capitalize that").unwrap();
+        assert_eq!(manager.get_current_text(), "we shipped it. the launch went Well.");
+        
+        // Without a selection there is nothing to convert; once there is, it stays selected
+        manager.process_transcription("uppercase selection").unwrap();
+        assert_eq!(manager.get_current_text(), "we shipped it. the launch went Well.");
+        manager.process_transcription("select shipped").unwrap();
+        manager.process_transcription("uppercase selection").unwrap();
+        manager.process_transcription("capitalize").unwrap();
+        assert_eq!(manager.get_current_text(), "we SHIPPED it. the launch went Well.");
+        
+        manager.process_transcription("undo").unwrap();
+        manager.process_transcription("undo").unwrap();
+        assert_eq!(manager.get_current_text(), "we shipped it. the launch went Well.");
+    }
+    
+    #[test]
+    fn test_punctuation_commands() {
+        let (mut manager, _events) = VoiceCommandManager::new(VoiceCommandConfig::default()).unwrap();
+        manager.start().unwrap();
+        manager.set_current_text("Are we done ");
+        
+        manager.process_transcription("question mark").unwrap();
+        assert_eq!(manager.get_current_text(), "Are we done?");
+        
+        manager.process_transcription("new line").unwrap();
+        manager.process_transcription("comma").unwrap();
+        assert_eq!(manager.get_current_text(), "Are we done?\n,");
+        
+        manager.process_transcription("undo").unwrap();
+        manager.set_current_text(&format!("{}Yes ", manager.get_current_text()));
+        manager.process_transcription("period").unwrap();
+        manager.process_transcription("new paragraph").unwrap();
+        assert_eq!(manager.get_current_text(), "Are we done?\nYes.\n\n");
+    }
+    
+    #[test]
+    fn test_help_lists_commands() {
+        let config = VoiceCommandConfig {
+            script: "\"sign off\" => insert_text(\"Best, Alex\")\n\"scratch that\" => undo()".to_string(),
+            ..VoiceCommandConfig::default()
+        };
+        let (mut manager, mut events) = VoiceCommandManager::new(config).unwrap();
+        manager.start().unwrap();
+        
+        assert_eq!(manager.process_transcription("What can I say?").unwrap()[0].command_type, VoiceCommandType::Help);
+        assert!(matches!(events.try_recv(), Ok(VoiceCommandEvent::CommandDetected(_))));
+        let Ok(VoiceCommandEvent::HelpRequested(categories)) = events.try_recv() else {
+            panic!("no help was sent");
+        };
+        assert_eq!(categories[0].name, "Editing");
+        assert_eq!(categories, manager.available_commands());
+        
+        let editing = &categories[0].commands;
+        assert_eq!(editing[0].description, "Insert text");
+        assert_eq!(editing[1], CommandHelp::plain("scratch that", &VoiceCommandType::Undo));
+        let delete = editing.iter().find(|help| help.trigger == "delete last {count} word|words").unwrap();
+        assert_eq!(delete.parameters, [ParameterHelp { name: "count".to_string(), kind: ParameterKind::Number }]);
+        assert_eq!(categories.iter().filter(|category| category.name == "Custom").count(), 0);
+        
+        // Only switching modes is heard while dictating
+        manager.set_mode(CommandMode::Dictation);
+        let categories = manager.available_commands();
+        assert_eq!(categories.len(), 1);
+        assert_eq!(categories[0].commands.len(), 3);
+    }
+    
+    #[test]
+    fn test_strips_command_triggers() {
+        let (mut manager, _events) = VoiceCommandManager::new(VoiceCommandConfig::default()).unwrap();
+        manager.start().unwrap();
+        
+        let strip = |manager: &mut VoiceCommandManager, text: &str| {
+            let commands = manager.process_transcription(text).unwrap();
+            assert!(!commands.is_empty(), "no command in {:?}", text);
+            strip_commands(text, &commands)
+        };
+        assert_eq!(strip(&mut manager, " We should go, new paragraph, and then rest."), "We should go, and then rest.");
+        assert_eq!(strip(&mut manager, "That's all for today period"), "That's all for today");
+        assert_eq!(strip(&mut manager, "Is it ready Question mark?"), "Is it ready");
+        assert_eq!(strip(&mut manager, "Undo."), "");
+        
+        // Misheard triggers are taken out where they were heard, and so are parameters
+        assert_eq!(strip(&mut manager, "Dear team, knew paragraph thanks for coming"), "Dear team, thanks for coming");
+        assert_eq!(strip(&mut manager, "Delete last three words."), "");
+        
+        // The prefix goes with the command after it
+        let config = VoiceCommandConfig {
+            command_prefix: Some("computer".to_string()),
+            require_prefix: true,
+            ..VoiceCommandConfig::default()
+        };
+        let (mut manager, _events) = VoiceCommandManager::new(config).unwrap();
+        manager.start().unwrap();
+        assert_eq!(strip(&mut manager, "Send the report, Computer, new line. Thanks"), "Send the report, Thanks");
+        assert_eq!(strip(&mut manager, "computer command mode"), "");
+        assert_eq!(strip_commands("No commands here", &[]), "No commands here");
+    }
+    
+    #[test]
+    fn test_chained_commands() {
+        let (mut manager, _events) = VoiceCommandManager::new(VoiceCommandConfig::default()).unwrap();
+        manager.start().unwrap();
+        let types = |commands: &[VoiceCommand]| commands.iter().map(|command| command.command_type.clone()).collect::<Vec<_>>();
+        
+        // Carried out in the order said, whatever order the triggers are tried in
+        let commands = manager.process_transcription("New paragraph capitalize that").unwrap();
+        assert_eq!(types(&commands), [VoiceCommandType::NewParagraph, VoiceCommandType::Capitalize]);
+        let commands = manager.process_transcription("Capitalize that, new paragraph.").unwrap();
+        assert_eq!(types(&commands), [VoiceCommandType::Capitalize, VoiceCommandType::NewParagraph]);
+        
+        manager.set_current_text("we shipped it");
+        let text = "capitalize that period and we're done new line";
+        let commands = manager.process_transcription(text).unwrap();
+        assert_eq!(types(&commands), [VoiceCommandType::Capitalize, VoiceCommandType::Period, VoiceCommandType::NewLine]);
+        assert_eq!(manager.get_current_text(), "we shipped It.\n");
+        assert_eq!(strip_commands(text, &commands), "and we're done");
+        
+        // Each command only hears the words up to it, so this deletes a word rather than a paragraph
+        manager.set_current_text("One. Two three");
+        let commands = manager.process_transcription("delete that new paragraph").unwrap();
+        assert_eq!(commands[0].trigger_text, "delete that");
+        assert_eq!(manager.get_current_text(), "One. Two\n\n");
+    }
+    
+    #[test]
+    fn test_correct_words() {
+        let (mut manager, _events) = VoiceCommandManager::new(VoiceCommandConfig::default()).unwrap();
+        manager.start().unwrap();
+        manager.set_current_text("Build it with gradel, then run gradel tests.");
+        
+        // The most recent match is corrected, written as it was said
+        let commands = manager.process_transcription("Correct gradle to Gradle").unwrap();
+        assert_eq!(commands[0].command_type, VoiceCommandType::Replace);
+        assert_eq!(manager.get_current_text(), "Build it with gradel, then run Gradle tests.");
+        
+        manager.process_transcription("undo").unwrap();
+        assert_eq!(manager.get_current_text(), "Build it with gradel, then run gradel tests.");
+    }
+    
+    #[test]
+    fn test_spelling_mode() {
+        let (mut manager, _events) = VoiceCommandManager::new(VoiceCommandConfig::default()).unwrap();
+        manager.start().unwrap();
+        manager.set_current_text("The code is");
+        
+        manager.process_transcription("spell that").unwrap();
+        assert!(manager.is_spelling());
+        
+        // Single letters and words that are commands elsewhere are spelled
+        manager.process_transcription("Alpha bravo").unwrap();
+        manager.process_transcription("x").unwrap();
+        manager.process_transcription("seven delete").unwrap();
+        assert_eq!(manager.get_current_text(), "The code is abx7delete");
+        
+        manager.process_transcription("dash nine, end spelling.").unwrap();
+        assert!(!manager.is_spelling());
+        assert_eq!(manager.get_current_text(), "The code is abx7delete-9");
+        
+        manager.process_transcription("delete").unwrap();
+        assert_eq!(manager.get_current_text(), "The code is");
+    }
+    
+    #[test]
+    fn test_command_modes() {
+        let config = VoiceCommandConfig {
+            command_prefix: Some("computer".to_string()),
+            require_prefix: true,
+            mode: CommandMode::Dictation,
+            ..VoiceCommandConfig::default()
+        };
+        let (mut manager, mut events) = VoiceCommandManager::new(config).unwrap();
+        manager.start().unwrap();
+        manager.set_current_text("one two three");
+        
+        // Nothing but switching modes is heard while dictating
+        assert!(manager.process_transcription("computer delete that").unwrap().is_empty());
+        assert!(manager.process_transcription("we talked about command mode").unwrap().is_empty());
+        assert_eq!(manager.get_current_text(), "one two three");
+        
+        let commands = manager.process_transcription("Command mode.").unwrap();
+        assert_eq!(commands[0].command_type, VoiceCommandType::SetMode(CommandMode::Command));
+        assert_eq!(manager.mode(), CommandMode::Command);
+        assert!(matches!(events.try_recv(), Ok(VoiceCommandEvent::ModeChanged(CommandMode::Command))));
+        
+        // Commands need no prefix in command mode
+        let commands = manager.process_transcription("delete").unwrap();
+        assert_eq!(commands[0].mode, CommandMode::Command);
+        assert_eq!(manager.get_current_text(), "one two");
+        
+        manager.process_transcription("computer mixed mode").unwrap();
+        assert_eq!(manager.mode(), CommandMode::Mixed);
+        assert!(manager.process_transcription("delete").unwrap().is_empty());
+        manager.process_transcription("computer delete").unwrap();
+        assert_eq!(manager.get_current_text(), "one");
+    }
+    
+    #[test]
+    fn test_loads_saved_configs() {
+        // As the application saved its settings before, with action names and fewer settings
+        let saved = r#"{
+            "enabled": true,
+            "command_prefix": "computer",
+            "require_prefix": true,
+            "sensitivity": 0.7,
+            "custom_commands": [["scratch that", "Undo"], ["open terminal", "open_terminal"]],
+            "default_commands": true
+        }"#;
+        let config: VoiceCommandConfig = serde_json::from_str(saved).unwrap();
+        assert_eq!(config.command_prefix.as_deref(), Some("computer"));
+        assert_eq!(config.custom_commands, [
+            ("scratch that".to_string(), VoiceCommandType::Undo),
+            ("open terminal".to_string(), VoiceCommandType::Custom("open_terminal".to_string())),
+        ]);
+        assert_eq!(config.mode, CommandMode::Mixed);
+        assert!(config.overrides.is_empty());
+        
+        // A configuration saved now loads as it was
+        let config = VoiceCommandConfig {
+            custom_commands: vec![("louder".to_string(), VoiceCommandType::System(SystemAction::VolumeUp))],
+            ..config
+        };
+        let loaded: VoiceCommandConfig = serde_json::from_str(&serde_json::to_string(&config).unwrap()).unwrap();
+        assert_eq!(loaded.custom_commands, config.custom_commands);
+        assert_eq!(serde_json::from_str::<VoiceCommandConfig>("{}").unwrap().sensitivity, VoiceCommandConfig::default().sensitivity);
+    }
+    
+    #[test]
+    fn test_command_overrides() {
+        let strict = CommandOverride { sensitivity: Some(1.0), ..CommandOverride::default() };
+        let disabled = CommandOverride { enabled: false, ..CommandOverride::default() };
+        let config = VoiceCommandConfig {
+            custom_commands: vec![("shout".to_string(), VoiceCommandType::Custom("shout".to_string()))],
+            overrides: BTreeMap::from([
+                ("capitalize".to_string(), strict),
+                ("Delete".to_string(), disabled.clone()),
+                ("shout".to_string(), disabled),
+            ]),
+            ..VoiceCommandConfig::default()
+        };
+        let (mut manager, _events) = VoiceCommandManager::new(config).unwrap();
+        manager.start().unwrap();
+        manager.set_current_text("one two");
+        
+        // Only the exact trigger is heard at a sensitivity of 1.0, while other commands stay fuzzy
+        assert!(manager.process_transcription("capitalise").unwrap().is_empty());
+        manager.process_transcription("capitalize").unwrap();
+        assert_eq!(manager.get_current_text(), "one Two");
+        manager.process_transcription("lowercas").unwrap();
+        assert_eq!(manager.get_current_text(), "one two");
+        
+        // Disabled commands aren't heard, their other triggers still are
+        assert!(manager.process_transcription("delete").unwrap().is_empty());
+        assert!(manager.process_transcription("shout").unwrap().is_empty());
+        manager.process_transcription("delete that").unwrap();
+        assert_eq!(manager.get_current_text(), "one");
+    }
+    
+    #[test]
+    fn test_commands_for_focused_app() {
+        let in_code = CommandOverride { apps: vec!["Code.exe".to_string()], ..CommandOverride::default() };
+        let config = VoiceCommandConfig {
+            custom_commands: vec![("run tests".to_string(), VoiceCommandType::Custom("run tests".to_string()))],
+            overrides: BTreeMap::from([
+                ("run tests".to_string(), in_code.clone()),
+                ("go to line {line}".to_string(), in_code),
+            ]),
+            ..VoiceCommandConfig::default()
+        };
+        let (mut manager, mut events) = VoiceCommandManager::new(config).unwrap();
+        manager.start().unwrap();
+        let heard = |manager: &mut VoiceCommandManager, text: &str| {
+            manager.process_transcription(text).unwrap().first().map(|command| command.command_type.clone())
+        };
+        let lists = |manager: &VoiceCommandManager, trigger: &str| {
+            manager.available_commands().iter().flat_map(|category| &category.commands).any(|help| help.trigger == trigger)
+        };
+        
+        // Until the focused window is known, every command is heard
+        assert_eq!(heard(&mut manager, "run tests"), Some(VoiceCommandType::Custom("run tests".to_string())));
+        
+        let browser = ActiveWindow { app: "firefox.exe".to_string(), title: "Inbox".to_string() };
+        manager.set_active_window(browser.clone());
+        manager.set_active_window(browser.clone());
+        assert_eq!(heard(&mut manager, "run tests"), None);
+        assert_eq!(heard(&mut manager, "go to line twelve"), None);
+        assert!(!lists(&manager, "run tests"));
+        assert_eq!(heard(&mut manager, "undo"), Some(VoiceCommandType::Undo));
+        
+        let editor = ActiveWindow { app: r"C:\Program Files\Microsoft VS Code\code.exe".to_string(), title: "main.rs".to_string() };
+        manager.set_active_window(editor.clone());
+        assert_eq!(heard(&mut manager, "go to line twelve"), Some(VoiceCommandType::GoToLine));
+        assert!(lists(&manager, "run tests"));
+        assert_eq!(manager.active_window(), Some(&editor));
+        
+        let changes: Vec<ActiveWindow> = std::iter::from_fn(|| events.try_recv().ok())
+            .filter_map(|event| match event {
+                VoiceCommandEvent::ContextChanged(window) => Some(window),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(changes, [browser, editor]);
+    }
+    
+    #[test]
+    fn test_switch_profiles() {
+        let coding = VoiceCommandConfig {
+            custom_commands: vec![("run tests".to_string(), VoiceCommandType::Custom("run tests".to_string()))],
+            mode: CommandMode::Command,
+            ..VoiceCommandConfig::default()
+        };
+        let config = VoiceCommandConfig {
+            profiles: BTreeMap::from([("Coding".to_string(), coding)]),
+            ..VoiceCommandConfig::default()
+        };
+        let starting = VoiceCommandConfig { active_profile: Some("coding".to_string()), ..config.clone() };
+        assert_eq!(VoiceCommandManager::new(starting).unwrap().0.profile(), "Coding");
+        
+        let (mut manager, mut events) = VoiceCommandManager::new(config).unwrap();
+        manager.start().unwrap();
+        manager.set_current_text("one two");
+        assert_eq!(manager.profiles(), ["default", "Coding"]);
+        assert!(manager.process_transcription("run tests").unwrap().is_empty());
+        
+        manager.process_transcription("switch to coding profile").unwrap();
+        assert_eq!(manager.profile(), "Coding");
+        assert_eq!(manager.mode(), CommandMode::Command);
+        assert!(matches!(events.try_recv(), Ok(VoiceCommandEvent::ModeChanged(CommandMode::Command))));
+        assert!(matches!(events.try_recv(), Ok(VoiceCommandEvent::ProfileChanged(profile)) if profile == "Coding"));
+        assert_eq!(manager.process_transcription("run tests").unwrap().len(), 1);
+        
+        // The text and its history carry over
+        manager.process_transcription("delete").unwrap();
+        manager.switch_profile("Default").unwrap();
+        assert_eq!(manager.mode(), CommandMode::Mixed);
+        manager.process_transcription("undo").unwrap();
+        assert_eq!(manager.get_current_text(), "one two");
+        
+        assert!(manager.switch_profile("email").is_err());
+        assert_eq!(manager.profile(), "default");
+    }
+    
+    #[test]
+    fn test_delete_spoken_word_count() {
+        let (mut manager, _events) = VoiceCommandManager::new(VoiceCommandConfig::default()).unwrap();
+        manager.start().unwrap();
+        manager.set_current_text("one two three four five");
+        
+        let commands = manager.process_transcription("delete last three words").unwrap();
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].number("count"), Some(3));
+        assert_eq!(manager.get_current_text(), "one two");
+    }
+    
+    #[test]
+    fn test_script_commands() {
+        let config = VoiceCommandConfig {
+            script: "\"insert signature\" => insert_text(\"Best regards\")\n\"scratch that\" => undo()".to_string(),
+            ..VoiceCommandConfig::default()
+        };
+        let (mut manager, _events) = VoiceCommandManager::new(config).unwrap();
+        manager.start().unwrap();
+        manager.set_current_text("Thanks.");
+        
+        manager.process_transcription("insert signature").unwrap();
+        assert_eq!(manager.get_current_text(), "Thanks. Best regards");
+        
+        manager.process_transcription("scratch that").unwrap();
+        assert_eq!(manager.get_current_text(), "Thanks.");
+        assert!(manager.is_command_registered(&VoiceCommandType::InsertText));
+    }
+    
+    /// Records what it was asked to do instead of doing it
+    struct RecordingAppControl(Arc<Mutex<Vec<String>>>);
+    
+    impl AppControl for RecordingAppControl {
+        fn launch(&self, app: &crate::audio::app_control::LaunchableApp) -> Result<()> {
+            self.0.lock().push(format!("launch {}", app.command));
+            Ok(())
+        }
+        
+        fn switch_to(&self, title: &str) -> Result<()> {
+            self.0.lock().push(format!("switch {}", title));
+            Ok(())
+        }
+    }
+    
+    #[test]
+    fn test_app_commands_need_prefix() {
+        let config = VoiceCommandConfig {
+            command_prefix: Some("computer".to_string()),
+            require_prefix: false,
+            app_control: AppControlSettings {
+                enabled: true,
+                applications: vec![crate::audio::app_control::LaunchableApp {
+                    name: "Notepad".to_string(),
+                    command: "notepad.exe".to_string(),
+                    args: Vec::new(),
+                }],
+            },
+            ..VoiceCommandConfig::default()
+        };
+        let (mut manager, _events) = VoiceCommandManager::new(config).unwrap();
+        let actions = Arc::new(Mutex::new(Vec::new()));
+        manager.set_app_control(Box::new(RecordingAppControl(actions.clone())));
+        manager.start().unwrap();
+        
+        assert!(manager.process_transcription("open notepad").unwrap().is_empty());
+        manager.process_transcription("Computer, open Notepad").unwrap();
+        manager.process_transcription("computer switch to inbox").unwrap();
+        manager.process_transcription("computer open paint").unwrap();
+        assert_eq!(*actions.lock(), ["launch notepad.exe", "switch inbox"]);
+    }
+    
+    /// Records the actions it was asked to carry out
+    struct RecordingSystemControl(Arc<Mutex<Vec<SystemAction>>>);
+    
+    impl SystemControl for RecordingSystemControl {
+        fn perform(&self, action: SystemAction) -> Result<()> {
+            self.0.lock().push(action);
+            Ok(())
+        }
+    }
+    
+    #[test]
+    fn test_system_commands() {
+        let actions = Arc::new(Mutex::new(Vec::new()));
+        let (mut manager, _events) = VoiceCommandManager::new(VoiceCommandConfig::default()).unwrap();
+        manager.set_system_control(Box::new(RecordingSystemControl(actions.clone())));
+        manager.start().unwrap();
+        assert!(manager.process_transcription("volume up").unwrap().is_empty());
+        
+        let config = VoiceCommandConfig { system_commands: true, ..VoiceCommandConfig::default() };
+        let (mut manager, _events) = VoiceCommandManager::new(config).unwrap();
+        manager.set_system_control(Box::new(RecordingSystemControl(actions.clone())));
+        manager.start().unwrap();
+        for text in ["volume up", "Mute.", "pause music", "next track"] {
+            manager.process_transcription(text).unwrap();
+        }
+        assert_eq!(*actions.lock(), [SystemAction::VolumeUp, SystemAction::Mute, SystemAction::PlayPause, SystemAction::NextTrack]);
+    }
+    
+    /// Records the keys it was asked to press
+    struct RecordingKeyboard(Arc<Mutex<Vec<String>>>);
+    
+    impl Keyboard for RecordingKeyboard {
+        fn press(&self, chord: &KeyChord) -> Result<()> {
+            self.0.lock().push(chord.to_string());
+            Ok(())
+        }
+    }
+    
+    /// Records what it was asked to type and erase
+    struct RecordingSink(Arc<Mutex<Vec<String>>>);
+    
+    impl OutputSink for RecordingSink {
+        fn type_text(&self, text: &str) -> Result<()> {
+            self.0.lock().push(format!("type {}", text));
+            Ok(())
+        }
+        
+        fn erase(&self, count: usize) -> Result<()> {
+            self.0.lock().push(format!("erase {}", count));
+            Ok(())
+        }
+    }
+    
+    #[test]
+    fn test_keystroke_commands() {
+        let script = "\"save file\" => key(\"ctrl+s\")\n\"send it\" => insert_text(\"Thanks\"); key(\"ctrl+enter\")";
+        let pressed = Arc::new(Mutex::new(Vec::new()));
+        let config = VoiceCommandConfig { script: script.to_string(), ..VoiceCommandConfig::default() };
+        let (mut manager, mut events) = VoiceCommandManager::new(config).unwrap();
+        manager.set_keyboard(Box::new(RecordingKeyboard(pressed.clone())));
+        manager.start().unwrap();
+        
+        // Nothing is pressed unless keystrokes are allowed
+        manager.process_transcription("save file").unwrap();
+        assert!(pressed.lock().is_empty());
+        assert!(matches!(events.try_recv(), Ok(VoiceCommandEvent::Error(_))));
+        
+        let config = VoiceCommandConfig { script: script.to_string(), keystrokes: true, ..VoiceCommandConfig::default() };
+        let (mut manager, _events) = VoiceCommandManager::new(config).unwrap();
+        manager.set_keyboard(Box::new(RecordingKeyboard(pressed.clone())));
+        manager.start().unwrap();
+        manager.process_transcription("save file").unwrap();
+        manager.process_transcription("send it").unwrap();
+        assert_eq!(*pressed.lock(), ["ctrl+s", "ctrl+Return"]);
+        assert_eq!(manager.get_current_text(), "Thanks");
+    }
+    
+    #[test]
+    fn test_undo_typed_text() {
+        let typed = Arc::new(Mutex::new(Vec::new()));
+        let (mut manager, _events) = VoiceCommandManager::new(VoiceCommandConfig::default()).unwrap();
+        manager.set_output_sink(Box::new(RecordingSink(typed.clone())));
+        assert!(manager.inject("Hello").is_err());
+        
+        let config = VoiceCommandConfig { inject_text: true, ..VoiceCommandConfig::default() };
+        let (mut manager, _events) = VoiceCommandManager::new(config).unwrap();
+        manager.set_output_sink(Box::new(RecordingSink(typed.clone())));
+        manager.start().unwrap();
+        manager.inject("Hello").unwrap();
+        manager.inject("world").unwrap();
+        
+        // Undo erases the last chunk typed and redo types it again
+        manager.process_transcription("undo").unwrap();
+        manager.process_transcription("redo").unwrap();
+        assert_eq!(*typed.lock(), ["type Hello", "type  world", "erase 6", "type  world"]);
+        
+        // Not while another application has the focus
+        manager.set_active_window(ActiveWindow { app: "firefox".to_string(), title: String::new() });
+        manager.inject("again").unwrap();
+        manager.set_active_window(ActiveWindow { app: "Code.exe".to_string(), title: String::new() });
+        typed.lock().clear();
+        manager.process_transcription("undo").unwrap();
+        assert!(typed.lock().is_empty());
+    }
+    
+    #[test]
+    fn test_typed_output_keeps_punctuation_in_place() {
+        let (mut manager, _events) = VoiceCommandManager::new(VoiceCommandConfig::default()).unwrap();
+        manager.start().unwrap();
+        let text = "Dear Sam comma new line thanks for the notes period";
+        let commands = manager.process_transcription(text).unwrap();
+        assert_eq!(typed_output(text, &commands), "Dear Sam,\nthanks for the notes.");
+        assert_eq!(strip_commands(text, &commands), "Dear Sam thanks for the notes");
+        
+        let text = "Call me tomorrow.";
+        assert_eq!(typed_output(text, &manager.process_transcription(text).unwrap()), text);
+    }
+    
+    #[test]
+    fn test_plugin_commands() {
+        let dir = std::env::temp_dir().join(format!("bestme-plugins-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let plugin = wat::parse_str(r#"
+            (module
+                (import "bestme" "register_command" (func $register_command (param i32 i32) (result i32)))
+                (import "bestme" "insert_text" (func $insert_text (param i32 i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "sign off")
+                (data (i32.const 16) "Cheers, Alex")
+                (func (export "init") (drop (call $register_command (i32.const 0) (i32.const 8))))
+                (func (export "on_command") (param i32) (call $insert_text (i32.const 16) (i32.const 12))))
+        "#).unwrap();
+        std::fs::write(dir.join("signature.wasm"), plugin).unwrap();
+        std::fs::write(dir.join("broken.wasm"), b"not wasm").unwrap();
+        
+        let config = VoiceCommandConfig { plugin_dir: Some(dir.to_string_lossy().into_owned()), ..VoiceCommandConfig::default() };
+        let (mut manager, _events) = VoiceCommandManager::new(config).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        manager.start().unwrap();
+        
+        // What a plugin does is undone at once
+        manager.set_current_text("Thanks.");
+        manager.process_transcription("sign off").unwrap();
+        assert_eq!(manager.get_current_text(), "Thanks. Cheers, Alex");
+        manager.process_transcription("undo").unwrap();
+        assert_eq!(manager.get_current_text(), "Thanks.");
+    }
+    
+    #[test]
+    fn test_hook_scripts_drop_commands() {
+        let path = std::env::temp_dir().join(format!("bestme-command-hooks-{}.rhai", std::process::id()));
+        std::fs::write(&path, "fn on_command(command, text) { command.type != \"Stop\" }").unwrap();
+        let config = VoiceCommandConfig { hook_scripts: vec![path.to_string_lossy().into_owned()], ..VoiceCommandConfig::default() };
+        let (mut manager, _events) = VoiceCommandManager::new(config).unwrap();
+        manager.start().unwrap();
+        
+        assert!(manager.process_transcription("stop").unwrap().is_empty());
+        assert_eq!(manager.process_transcription("new line").unwrap()[0].command_type, VoiceCommandType::NewLine);
+        std::fs::remove_file(&path).unwrap();
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_shell_commands() {
+        let script = "\"greet {who:text}\" => run(\"greet\")\n\"deploy\" => run(\"deploy\")";
+        let greet = crate::audio::shell_command::ShellCommand {
+            name: "greet".to_string(),
+            command: "echo".to_string(),
+            args: vec!["hello {who}".to_string()],
+            working_dir: None,
+            timeout_secs: 5,
+        };
+        let mut config = VoiceCommandConfig { script: script.to_string(), ..VoiceCommandConfig::default() };
+        config.shell_commands.commands.push(greet);
+        
+        // Nothing runs unless shell commands are enabled
+        let (mut manager, mut events) = VoiceCommandManager::new(config.clone()).unwrap();
+        manager.start().unwrap();
+        manager.process_transcription("greet the world").unwrap();
+        assert!(matches!(events.try_recv(), Ok(VoiceCommandEvent::Error(_))));
+        
+        config.shell_commands.enabled = true;
+        let (mut manager, mut events) = VoiceCommandManager::new(config).unwrap();
+        manager.start().unwrap();
+        manager.process_transcription("greet the world").unwrap();
+        assert!(matches!(events.try_recv(), Ok(VoiceCommandEvent::CommandDetected(_))));
+        match events.blocking_recv() {
+            Some(VoiceCommandEvent::ShellCommandFinished(output)) => {
+                assert!(output.success);
+                assert_eq!(output.stdout, "hello the world\n");
+            }
+            event => panic!("expected the command to finish, got {:?}", event),
+        }
+        
+        // Only configured programs are run
+        manager.process_transcription("deploy").unwrap();
+        match events.try_recv() {
+            Ok(VoiceCommandEvent::Error(error)) => assert_eq!(error, "No shell command named deploy is configured"),
+            event => panic!("expected an error, got {:?}", event),
+        }
+    }
+    
+    #[test]
+    fn test_macro_is_undone_at_once() {
+        let config = VoiceCommandConfig {
+            script: "\"wrap it up\" => delete_words(1); insert_text(\"That's all\"); period(); new_paragraph(); stop()".to_string(),
+            ..VoiceCommandConfig::default()
+        };
+        let (mut manager, mut events) = VoiceCommandManager::new(config).unwrap();
+        manager.start().unwrap();
+        manager.set_current_text("We're done um");
+        
+        manager.process_transcription("wrap it up").unwrap();
+        assert_eq!(manager.get_current_text(), "We're done That's all.\n\n");
+        
+        // The control steps are passed on with the macro
+        match events.try_recv() {
+            Ok(VoiceCommandEvent::CommandDetected(command)) => {
+                assert_eq!(command.command_type, VoiceCommandType::Macro);
+                assert_eq!(command.steps.last().unwrap().command_type, VoiceCommandType::Stop);
+            }
+            other => panic!("expected the macro, got {:?}", other),
+        }
+        
+        manager.process_transcription("undo").unwrap();
+        assert_eq!(manager.get_current_text(), "We're done um");
+    }
+    
     #[test]
     fn test_text_editor_delete_word() {
         let mut editor = VoiceTextEditor::new();
         
         // Test deleting the last word
         let text = "This is a test sentence";
-        let result = editor.apply_delete(text, DeleteScope::LastWord);
+        let result = editor.apply_delete(text, &DeleteScope::LastWord);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "This is a test");
         
         // Test with trailing whitespace
         let text = "This is a test   ";
-        let result = editor.apply_delete(text, DeleteScope::LastWord);
+        let result = editor.apply_delete(text, &DeleteScope::LastWord);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "This is a");
         
         // Test with empty text
         let text = "";
-        let result = editor.apply_delete(text, DeleteScope::LastWord);
+        let result = editor.apply_delete(text, &DeleteScope::LastWord);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "");
     }
@@ -933,19 +3718,19 @@ mod tests {
         
         // Test deleting the last sentence
         let text = "This is the first sentence. This is the second sentence.";
-        let result = editor.apply_delete(text, DeleteScope::LastSentence);
+        let result = editor.apply_delete(text, &DeleteScope::LastSentence);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "This is the first sentence.");
         
         // Test with multiple sentence endings
         let text = "Hello! This is a test. And another one!";
-        let result = editor.apply_delete(text, DeleteScope::LastSentence);
+        let result = editor.apply_delete(text, &DeleteScope::LastSentence);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "Hello! This is a test.");
         
         // Test with no sentence ending
         let text = "This has no sentence ending";
-        let result = editor.apply_delete(text, DeleteScope::LastSentence);
+        let result = editor.apply_delete(text, &DeleteScope::LastSentence);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "");
     }
@@ -956,19 +3741,19 @@ mod tests {
         
         // Test deleting the last paragraph with double newlines
         let text = "First paragraph.\n\nSecond paragraph.";
-        let result = editor.apply_delete(text, DeleteScope::LastParagraph);
+        let result = editor.apply_delete(text, &DeleteScope::LastParagraph);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "First paragraph.");
         
         // Test with single newlines
         let text = "First line.\nSecond line.";
-        let result = editor.apply_delete(text, DeleteScope::LastParagraph);
+        let result = editor.apply_delete(text, &DeleteScope::LastParagraph);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "First line.");
         
         // Test with no paragraph breaks
         let text = "Single paragraph.";
-        let result = editor.apply_delete(text, DeleteScope::LastParagraph);
+        let result = editor.apply_delete(text, &DeleteScope::LastParagraph);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "");
     }
@@ -982,14 +3767,14 @@ mod tests {
         
         // Apply a delete operation
         let text = "This is a test sentence";
-        let result = editor.apply_delete(text, DeleteScope::LastWord);
+        let result = editor.apply_delete(text, &DeleteScope::LastWord);
         assert!(result.is_ok());
         let new_text = result.unwrap();
         assert_eq!(new_text, "This is a test");
         assert_eq!(editor.get_history().len(), 1);
         
         // Apply another operation
-        let result = editor.apply_delete(&new_text, DeleteScope::LastWord);
+        let result = editor.apply_delete(&new_text, &DeleteScope::LastWord);
         assert!(result.is_ok());
         let new_text = result.unwrap();
         assert_eq!(new_text, "This is a");
@@ -1045,16 +3830,16 @@ mod tests {
         ];
         
         for text in texts.iter() {
-            let _ = editor.apply_delete(text, DeleteScope::LastWord);
+            let _ = editor.apply_delete(text, &DeleteScope::LastWord);
         }
         
         // History should be truncated to max_history
         assert_eq!(editor.get_history().len(), 3);
         
-        // The oldest operations should be removed
-        assert!(editor.get_history()[0].previous_text.contains("Fifth"));
+        // The oldest operations should be removed; what is left stays in order, for undo to walk back
+        assert!(editor.get_history()[0].previous_text.contains("Third"));
         assert!(editor.get_history()[1].previous_text.contains("Fourth"));
-        assert!(editor.get_history()[2].previous_text.contains("Third"));
+        assert!(editor.get_history()[2].previous_text.contains("Fifth"));
     }
     
     #[test]
@@ -1083,42 +3868,57 @@ mod tests {
         
         // Test capitalizing the last word
         let text = "this is a test sentence";
-        let result = editor.apply_format(text, FormatOperation::Capitalize);
+        let result = editor.apply_format(text, FormatOperation::Capitalize(FormatScope::LastWord));
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "this is a test Sentence");
         
         // Test with single word
         let text = "test";
-        let result = editor.apply_format(text, FormatOperation::Capitalize);
+        let result = editor.apply_format(text, FormatOperation::Capitalize(FormatScope::LastWord));
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "Test");
         
         // Test with empty text
         let text = "";
-        let result = editor.apply_format(text, FormatOperation::Capitalize);
+        let result = editor.apply_format(text, FormatOperation::Capitalize(FormatScope::LastWord));
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "");
     }
     
+    #[test]
+    fn test_text_editor_lowercase_sentence() {
+        let mut editor = VoiceTextEditor::new();
+        let lowercase = FormatOperation::Lowercase(FormatScope::LastSentence);
+        
+        assert_eq!(editor.apply_format("First one. THE SECOND ONE.", lowercase.clone()).unwrap(), "First one. the second one.");
+        assert_eq!(editor.apply_format("ONLY ONE SENTENCE", lowercase.clone()).unwrap(), "only one sentence");
+        assert_eq!(editor.apply_format("One! TWO?!  ", lowercase.clone()).unwrap(), "One! two?!  ");
+        
+        // Points within numbers don't end the sentence, and closing quotes and brackets belong to it
+        assert_eq!(editor.apply_format("First one. VERSION 2.5 IS OUT.", lowercase.clone()).unwrap(), "First one. version 2.5 is out.");
+        assert_eq!(editor.apply_format("She said. \"THE QUOTE.\"", lowercase.clone()).unwrap(), "She said. \"the quote.\"");
+        assert_eq!(editor.apply_format("Hi (THERE.)", lowercase).unwrap(), "hi (there.)");
+    }
+    
     #[test]
     fn test_text_editor_lowercase() {
         let mut editor = VoiceTextEditor::new();
         
         // Test lowercasing the last word
-        let text = "this is a TEST sentence";
-        let result = editor.apply_format(text, FormatOperation::Lowercase);
+        let text = "this is a test SENTENCE";
+        let result = editor.apply_format(text, FormatOperation::Lowercase(FormatScope::LastWord));
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "this is a test sentence");
         
         // Test with mixed case
         let text = "this is a TeSt";
-        let result = editor.apply_format(text, FormatOperation::Lowercase);
+        let result = editor.apply_format(text, FormatOperation::Lowercase(FormatScope::LastWord));
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "this is a test");
         
         // Test with single word
         let text = "TEST";
-        let result = editor.apply_format(text, FormatOperation::Lowercase);
+        let result = editor.apply_format(text, FormatOperation::Lowercase(FormatScope::LastWord));
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "test");
     }
@@ -1129,19 +3929,19 @@ mod tests {
         
         // Test uppercasing the last word
         let text = "this is a test sentence";
-        let result = editor.apply_format(text, FormatOperation::Uppercase);
+        let result = editor.apply_format(text, FormatOperation::Uppercase(FormatScope::LastWord));
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "this is a test SENTENCE");
         
         // Test with mixed case
         let text = "this is a TeSt";
-        let result = editor.apply_format(text, FormatOperation::Uppercase);
+        let result = editor.apply_format(text, FormatOperation::Uppercase(FormatScope::LastWord));
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "this is a TEST");
         
         // Test with single word
         let text = "test";
-        let result = editor.apply_format(text, FormatOperation::Uppercase);
+        let result = editor.apply_format(text, FormatOperation::Uppercase(FormatScope::LastWord));
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "TEST");
     }
@@ -1169,6 +3969,63 @@ mod tests {
         assert_eq!(result.unwrap(), "this is a test _sentence_");
     }
     
+    #[test]
+    fn test_text_editor_replace() {
+        let mut editor = VoiceTextEditor::new();
+        let text = "Send the quarterly reports to Anna. Anna will check them.";
+        
+        let result = editor.apply_replace(text, "quartely report", "annual report").unwrap();
+        assert_eq!(result, "Send the annual report to Anna. Anna will check them.");
+        assert_eq!(editor.get_history()[0].operation, TextEditOperation::Replace {
+            original: "quarterly reports".to_string(),
+            replacement: "annual report".to_string(),
+        });
+        
+        // Punctuation around the words stays, and only the last match is replaced
+        let result = editor.apply_replace(&result, "anna", "Hannah").unwrap();
+        assert_eq!(result, "Send the annual report to Anna. Hannah will check them.");
+        
+        assert!(editor.apply_replace(&result, "budget", "plan").is_err());
+        assert_eq!(editor.get_history().len(), 2);
+    }
+    
+    #[test]
+    fn test_text_editor_selection() {
+        let mut editor = VoiceTextEditor::new();
+        let text = "First point. Second point!\n\nNew paragraph here. ";
+        
+        let select = |editor: &mut VoiceTextEditor, scope: SelectScope| {
+            editor.select(text, &scope).map(|range| text[range].to_string())
+        };
+        assert_eq!(select(&mut editor, SelectScope::LastWord).as_deref(), Some("here."));
+        assert_eq!(select(&mut editor, SelectScope::LastSentence).as_deref(), Some("New paragraph here."));
+        assert_eq!(select(&mut editor, SelectScope::LastParagraph).as_deref(), Some("New paragraph here."));
+        assert_eq!(select(&mut editor, SelectScope::Phrase("POINT".to_string())).as_deref(), Some("point"));
+        assert_eq!(
+            select(&mut editor, SelectScope::Between("second".to_string(), "paragraph".to_string())).as_deref(),
+            Some("Second point!\n\nNew paragraph")
+        );
+        assert_eq!(select(&mut editor, SelectScope::Phrase("missing".to_string())), None);
+        assert_eq!(editor.selection(text), None);
+        
+        // Formatting applies to the selection and keeps it selected
+        editor.select(text, &SelectScope::Phrase("second point".to_string()));
+        let text = editor.apply_format(text, FormatOperation::Capitalize(FormatScope::LastWord)).unwrap();
+        assert_eq!(text, "First point. Second Point!\n\nNew paragraph here. ");
+        let text = editor.apply_format(&text, FormatOperation::Style(TextStyle::Bold)).unwrap();
+        assert_eq!(text, "First point. **Second Point**!\n\nNew paragraph here. ");
+        
+        // Deleting deletes the selection whatever the scope, then it's gone
+        let text = editor.apply_delete(&text, &DeleteScope::LastWord).unwrap();
+        assert_eq!(text, "First point. !\n\nNew paragraph here. ");
+        assert_eq!(editor.selection(&text), None);
+        
+        // A selection of text that has since changed is ignored
+        editor.select(&text, &SelectScope::All);
+        let text = editor.apply_format("Something else entirely", FormatOperation::Uppercase(FormatScope::LastWord)).unwrap();
+        assert_eq!(text, "Something else ENTIRELY");
+    }
+    
     #[test]
     fn test_tauri_2_compatible_formats() {
         // Test the Tauri 2.0-specific formatting operations
@@ -1182,13 +4039,13 @@ mod tests {
             assert!(config.enabled);
             
             // Test creating the format operations
-            let capitalize = FormatOperation::Capitalize;
-            let lowercase = FormatOperation::Lowercase;
-            let uppercase = FormatOperation::Uppercase;
+            let capitalize = FormatOperation::Capitalize(FormatScope::LastWord);
+            let lowercase = FormatOperation::Lowercase(FormatScope::LastWord);
+            let uppercase = FormatOperation::Uppercase(FormatScope::LastWord);
             
-            assert!(matches!(capitalize, FormatOperation::Capitalize));
-            assert!(matches!(lowercase, FormatOperation::Lowercase));
-            assert!(matches!(uppercase, FormatOperation::Uppercase));
+            assert!(matches!(capitalize, FormatOperation::Capitalize(FormatScope::LastWord)));
+            assert!(matches!(lowercase, FormatOperation::Lowercase(FormatScope::LastWord)));
+            assert!(matches!(uppercase, FormatOperation::Uppercase(FormatScope::LastWord)));
         }
         
         // If not using Tauri 2.0, just pass
@@ -0,0 +1,139 @@
+#[cfg(feature = "vosk")]
+use anyhow::Result;
+#[cfg(feature = "vosk")]
+use log::{debug, info};
+#[cfg(feature = "vosk")]
+use std::path::PathBuf;
+#[cfg(feature = "vosk")]
+use std::time::Duration;
+
+#[cfg(feature = "vosk")]
+use super::engine::{SttEngine, TranscribeOptions, TranscriptSegment};
+
+/// Vosk models that can be downloaded, by name: archive URL, download size and language
+///
+/// Each archive unpacks to a directory named after the archive.
+pub const VOSK_MODELS: [(&str, &str, &str, &str); 7] = [
+    ("small-en-us", "https://alphacephei.com/vosk/models/vosk-model-small-en-us-0.15.zip", "40 MB", "en"),
+    ("en-us", "https://alphacephei.com/vosk/models/vosk-model-en-us-0.22-lgraph.zip", "128 MB", "en"),
+    ("small-de", "https://alphacephei.com/vosk/models/vosk-model-small-de-0.15.zip", "45 MB", "de"),
+    ("small-fr", "https://alphacephei.com/vosk/models/vosk-model-small-fr-0.22.zip", "41 MB", "fr"),
+    ("small-es", "https://alphacephei.com/vosk/models/vosk-model-small-es-0.42.zip", "39 MB", "es"),
+    ("small-ru", "https://alphacephei.com/vosk/models/vosk-model-small-ru-0.22.zip", "45 MB", "ru"),
+    ("small-cn", "https://alphacephei.com/vosk/models/vosk-model-small-cn-0.22.zip", "42 MB", "zh"),
+];
+
+/// Sample rate of the audio engines are given
+#[cfg(feature = "vosk")]
+const SAMPLE_RATE: f32 = 16000.0;
+
+/// Archive URL of a Vosk model
+pub fn model_url(name: &str) -> Option<&'static str> {
+    VOSK_MODELS.iter().find(|(model, ..)| *model == name).map(|(_, url, ..)| *url)
+}
+
+/// Directory a Vosk model unpacks to, e.g. "vosk-model-small-en-us-0.15"
+pub fn model_dir_name(name: &str) -> Option<&'static str> {
+    let url = model_url(name)?;
+    url.rsplit('/').next()?.strip_suffix(".zip")
+}
+
+/// Local transcription with Vosk, much lighter than Whisper but less accurate
+///
+/// Vosk models are trained for a single language, so the language option
+/// is ignored, and translation is not supported.
+#[cfg(feature = "vosk")]
+pub struct VoskEngine {
+    /// Unpacked model directory
+    model_dir: PathBuf,
+
+    /// Loaded model
+    model: Option<vosk::Model>,
+}
+
+#[cfg(feature = "vosk")]
+impl VoskEngine {
+    /// Engine for an unpacked model directory; the model is read when the engine is loaded
+    pub fn new(model_dir: PathBuf) -> Self {
+        Self { model_dir, model: None }
+    }
+}
+
+#[cfg(feature = "vosk")]
+impl SttEngine for VoskEngine {
+    fn name(&self) -> &'static str {
+        "vosk"
+    }
+
+    fn load(&mut self) -> Result<()> {
+        info!("Loading Vosk model from {:?}", self.model_dir);
+        let model = vosk::Model::new(self.model_dir.to_string_lossy())
+            .ok_or_else(|| anyhow::anyhow!("Failed to load Vosk model from {:?}", self.model_dir))?;
+        self.model = Some(model);
+        Ok(())
+    }
+
+    fn is_loaded(&self) -> bool {
+        self.model.is_some()
+    }
+
+    fn transcribe(&self, audio: &[f32], options: &TranscribeOptions) -> Result<Vec<TranscriptSegment>> {
+        let model = self.model.as_ref().ok_or_else(|| anyhow::anyhow!("Vosk model is not loaded"))?;
+        if options.translate {
+            debug!("Vosk does not translate, transcribing in the model's language");
+        }
+
+        // Recognizers are cheap next to the model, and one per call keeps the engine shareable
+        let mut recognizer = vosk::Recognizer::new(model, SAMPLE_RATE)
+            .ok_or_else(|| anyhow::anyhow!("Failed to create Vosk recognizer"))?;
+        recognizer.set_words(true);
+
+        let samples: Vec<i16> = audio.iter()
+            .map(|sample| (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+            .collect();
+        recognizer.accept_waveform(&samples)
+            .map_err(|e| anyhow::anyhow!("Vosk failed to decode audio: {:?}", e))?;
+
+        let result = recognizer.final_result();
+        let Some(result) = result.single() else {
+            return Ok(Vec::new());
+        };
+        if result.text.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Vosk times and scores words rather than phrases, so the buffer becomes one segment
+        let start = result.result.first().map_or(0.0, |word| word.start);
+        let end = result.result.last().map_or(audio.len() as f32 / SAMPLE_RATE, |word| word.end);
+        let confidence = (!result.result.is_empty())
+            .then(|| result.result.iter().map(|word| word.conf).sum::<f32>() / result.result.len() as f32);
+        Ok(vec![TranscriptSegment {
+            start: Duration::from_secs_f32(start.max(0.0)),
+            end: Duration::from_secs_f32(end.max(start).max(0.0)),
+            text: result.text.to_string(),
+            confidence,
+            language: None,
+            no_speech_probability: None,
+        }])
+    }
+
+    fn unload(&mut self) {
+        self.model = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_dirs_follow_archive_names() {
+        assert_eq!(model_dir_name("small-en-us"), Some("vosk-model-small-en-us-0.15"));
+        assert_eq!(model_dir_name("en-us"), Some("vosk-model-en-us-0.22-lgraph"));
+        assert_eq!(model_dir_name("tiny"), None);
+
+        for (name, ..) in VOSK_MODELS {
+            assert!(model_dir_name(name).is_some_and(|dir| dir.starts_with("vosk-model-")));
+        }
+    }
+}
@@ -0,0 +1,480 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+/// Sample rate the detector and enrollment recordings work at
+pub const WAKE_WORD_SAMPLE_RATE: u32 = 16000;
+
+/// Recordings of the wake word needed to enroll it
+pub const MIN_ENROLLMENT_RECORDINGS: usize = 3;
+
+/// Longest time commands are accepted for after the wake word, in seconds
+pub const MAX_ARMED_SECS: f32 = 60.0;
+
+/// Length of a feature frame in milliseconds
+const HOP_MS: u32 = 10;
+
+/// Filter bands the spectrum is measured in, spaced evenly in octaves
+const BANDS: usize = 16;
+const LOWEST_BAND_HZ: f32 = 200.0;
+const HIGHEST_BAND_HZ: f32 = 5000.0;
+
+/// Cepstral coefficients kept per frame, leaving out the overall level
+const CEPSTRA: usize = 12;
+
+/// Shortest and longest wake word, in frames
+const MIN_WORD_FRAMES: usize = 25;
+const MAX_WORD_FRAMES: usize = 200;
+
+/// Frames this far below the loudest one are silence around the word (dB)
+const VOICED_RANGE_DB: f32 = 30.0;
+
+/// Frames below this level are never part of the word (dBFS)
+const MIN_VOICED_DB: f32 = -55.0;
+
+/// Frames between comparisons with the templates
+const CHECK_FRAMES: usize = 5;
+
+/// Lengths of audio, relative to each template, compared with it, for faster and slower speech
+const LENGTH_SCALES: [f32; 3] = [0.8, 1.0, 1.25];
+
+/// Frames after a detection before the wake word can be detected again
+const REFRACTORY_FRAMES: usize = 100;
+
+/// Listening for a spoken wake word that arms voice commands
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WakeWordSettings {
+    /// Listen for the enrolled wake word in the audio, instead of the command prefix in transcribed text
+    pub enabled: bool,
+
+    /// How readily the wake word is detected (0.0 - 1.0); higher also mistakes other words for it more often
+    pub sensitivity: f32,
+
+    /// Seconds commands are accepted for after the wake word
+    pub armed_secs: f32,
+}
+
+impl Default for WakeWordSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sensitivity: 0.5,
+            armed_secs: 5.0,
+        }
+    }
+}
+
+/// A wake word enrolled from recordings of the user saying it
+///
+/// Each recording is kept as a template of spectral features; audio is
+/// compared with the templates by dynamic time warping, so the wake word is
+/// personal to the voice it was enrolled with, like a trained keyword model
+/// but without one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WakeWordModel {
+    /// The wake word, for display
+    pub phrase: String,
+
+    /// Features of each recording, frame by frame
+    templates: Vec<Vec<[f32; CEPSTRA]>>,
+
+    /// Mean distance between the templates, how much the user's own repetitions differ
+    spread: f32,
+}
+
+impl WakeWordModel {
+    /// Enroll `phrase` from recordings of it at [`WAKE_WORD_SAMPLE_RATE`], trimming the silence around each
+    pub fn enroll(phrase: &str, recordings: &[Vec<f32>]) -> Result<Self> {
+        if recordings.len() < MIN_ENROLLMENT_RECORDINGS {
+            return Err(anyhow!("Enrolling a wake word takes at least {} recordings", MIN_ENROLLMENT_RECORDINGS));
+        }
+
+        let templates = recordings.iter()
+            .enumerate()
+            .map(|(i, samples)| {
+                let mut extractor = FeatureExtractor::new();
+                let frames = extractor.push(samples);
+                let word = trim_silence(&frames)
+                    .ok_or_else(|| anyhow!("Recording {} has no speech in it", i + 1))?;
+                if word.len() < MIN_WORD_FRAMES {
+                    return Err(anyhow!("Recording {} is too short for a wake word", i + 1));
+                }
+                if word.len() > MAX_WORD_FRAMES {
+                    return Err(anyhow!("Recording {} is too long for a wake word", i + 1));
+                }
+                Ok(normalized(word.iter().map(|(features, _)| *features)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut distances = Vec::new();
+        for (i, a) in templates.iter().enumerate() {
+            for b in &templates[i + 1..] {
+                distances.push(dtw_distance(a, b));
+            }
+        }
+        let spread = distances.iter().sum::<f32>() / distances.len() as f32;
+
+        Ok(Self {
+            phrase: phrase.trim().to_string(),
+            templates,
+            spread,
+        })
+    }
+
+    /// File the enrolled wake word is kept in, in the application data folder
+    pub fn default_path() -> Result<PathBuf> {
+        let project_dirs = directories::ProjectDirs::from("com", "bestme", "BestMe")
+            .context("Failed to determine project directories")?;
+        Ok(project_dirs.data_dir().join("wake_word.json"))
+    }
+
+    /// Load a wake word saved at `path`
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read wake word {}", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("Invalid wake word {}", path.display()))
+    }
+
+    /// Save the wake word to `path`
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)
+            .with_context(|| format!("Failed to save wake word {}", path.display()))
+    }
+}
+
+/// Listens for an enrolled wake word in a stream of audio at [`WAKE_WORD_SAMPLE_RATE`]
+///
+/// Works on the audio directly, so the wake word is heard as soon as it is
+/// said, without waiting for a segment to be transcribed.
+#[derive(Debug, Clone)]
+pub struct WakeWordDetector {
+    model: WakeWordModel,
+
+    /// Greatest distance from a template that counts as the wake word
+    threshold: f32,
+
+    extractor: FeatureExtractor,
+
+    /// Recent frames, long enough for the longest template at its slowest
+    frames: VecDeque<([f32; CEPSTRA], f32)>,
+    capacity: usize,
+
+    /// Frames until the next comparison
+    until_check: usize,
+
+    /// Frames to wait after a detection
+    refractory: usize,
+}
+
+impl WakeWordDetector {
+    /// Listen for `model`'s wake word
+    pub fn new(model: WakeWordModel, settings: &WakeWordSettings) -> Self {
+        let longest = model.templates.iter().map(Vec::len).max().unwrap_or(0);
+        let capacity = (longest as f32 * LENGTH_SCALES[LENGTH_SCALES.len() - 1]).ceil() as usize;
+        let threshold = model.spread * (0.9 + 0.7 * settings.sensitivity.clamp(0.0, 1.0));
+        Self {
+            model,
+            threshold,
+            extractor: FeatureExtractor::new(),
+            frames: VecDeque::with_capacity(capacity + 1),
+            capacity,
+            until_check: CHECK_FRAMES,
+            refractory: 0,
+        }
+    }
+
+    /// The wake word listened for
+    pub fn phrase(&self) -> &str {
+        &self.model.phrase
+    }
+
+    /// Add mono samples; returns true when they complete the wake word
+    pub fn process(&mut self, samples: &[f32]) -> bool {
+        let mut detected = false;
+        for frame in self.extractor.push(samples) {
+            self.frames.push_back(frame);
+            if self.frames.len() > self.capacity {
+                self.frames.pop_front();
+            }
+
+            if self.refractory > 0 {
+                self.refractory -= 1;
+                continue;
+            }
+            self.until_check -= 1;
+            if self.until_check > 0 {
+                continue;
+            }
+            self.until_check = CHECK_FRAMES;
+
+            if self.best_distance().is_some_and(|distance| distance <= self.threshold) {
+                detected = true;
+                self.frames.clear();
+                self.refractory = REFRACTORY_FRAMES;
+            }
+        }
+        detected
+    }
+
+    /// Distance of the most recent audio from the closest template, when there is speech in it
+    fn best_distance(&self) -> Option<f32> {
+        let loudest = self.frames.iter().map(|(_, level)| *level).fold(f32::MIN, f32::max);
+        let mut best: Option<f32> = None;
+
+        for template in &self.model.templates {
+            for scale in LENGTH_SCALES {
+                let len = (template.len() as f32 * scale).round() as usize;
+                if len > self.frames.len() {
+                    continue;
+                }
+
+                // Most of the candidate must be speech, or silence and noise get compared
+                let candidate: Vec<&([f32; CEPSTRA], f32)> = self.frames.iter().skip(self.frames.len() - len).collect();
+                let voiced = candidate.iter().filter(|(_, level)| is_voiced(*level, loudest)).count();
+                if voiced * 2 < len {
+                    continue;
+                }
+
+                let distance = dtw_distance(template, &normalized(candidate.iter().map(|(features, _)| *features)));
+                best = Some(best.map_or(distance, |best| best.min(distance)));
+            }
+        }
+        best
+    }
+
+    /// Forget the audio heard so far, e.g. when capture restarts
+    pub fn reset(&mut self) {
+        self.extractor = FeatureExtractor::new();
+        self.frames.clear();
+        self.until_check = CHECK_FRAMES;
+        self.refractory = 0;
+    }
+}
+
+/// Band-pass filter (RBJ, 0 dB peak gain)
+#[derive(Debug, Clone)]
+struct BandFilter {
+    b0: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    x1: f32,
+    x2: f32,
+    y1: f32,
+    y2: f32,
+}
+
+impl BandFilter {
+    fn new(center_hz: f32, q: f32) -> Self {
+        let w0 = 2.0 * std::f32::consts::PI * center_hz / WAKE_WORD_SAMPLE_RATE as f32;
+        let alpha = w0.sin() / (2.0 * q);
+        let a0 = 1.0 + alpha;
+        Self {
+            b0: alpha / a0,
+            b2: -alpha / a0,
+            a1: -2.0 * w0.cos() / a0,
+            a2: (1.0 - alpha) / a0,
+            x1: 0.0,
+            x2: 0.0,
+            y1: 0.0,
+            y2: 0.0,
+        }
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        let y = self.b0 * x + self.b2 * self.x2 - self.a1 * self.y1 - self.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+}
+
+/// Turns audio into frames of cepstral features and their level
+#[derive(Debug, Clone)]
+struct FeatureExtractor {
+    filters: Vec<BandFilter>,
+    hop_len: usize,
+
+    /// Energy in each band, and overall, over the frame so far
+    band_energy: [f32; BANDS],
+    energy: f32,
+    count: usize,
+}
+
+impl FeatureExtractor {
+    fn new() -> Self {
+        let ratio = (HIGHEST_BAND_HZ / LOWEST_BAND_HZ).powf(1.0 / (BANDS - 1) as f32);
+        // Each band reaches the centres of its neighbours
+        let q = ratio / (ratio * ratio - 1.0);
+        Self {
+            filters: (0..BANDS).map(|band| BandFilter::new(LOWEST_BAND_HZ * ratio.powi(band as i32), q)).collect(),
+            hop_len: (WAKE_WORD_SAMPLE_RATE * HOP_MS / 1000) as usize,
+            band_energy: [0.0; BANDS],
+            energy: 0.0,
+            count: 0,
+        }
+    }
+
+    /// Add samples, returning the frames they complete as (features, level in dBFS)
+    fn push(&mut self, samples: &[f32]) -> Vec<([f32; CEPSTRA], f32)> {
+        let mut frames = Vec::new();
+        for &sample in samples {
+            for (filter, energy) in self.filters.iter_mut().zip(self.band_energy.iter_mut()) {
+                let y = filter.process(sample);
+                *energy += y * y;
+            }
+            self.energy += sample * sample;
+            self.count += 1;
+
+            if self.count == self.hop_len {
+                let log_energy = self.band_energy.map(|energy| (energy / self.hop_len as f32 + 1e-10).log10());
+                let level = 10.0 * (self.energy / self.hop_len as f32 + 1e-10).log10();
+                frames.push((cepstra(&log_energy), level));
+                self.band_energy = [0.0; BANDS];
+                self.energy = 0.0;
+                self.count = 0;
+            }
+        }
+        frames
+    }
+}
+
+/// DCT of the log band energies, without the first coefficient (the overall level)
+fn cepstra(log_energy: &[f32; BANDS]) -> [f32; CEPSTRA] {
+    let mut coefficients = [0.0; CEPSTRA];
+    for (n, coefficient) in coefficients.iter_mut().enumerate() {
+        *coefficient = log_energy.iter()
+            .enumerate()
+            .map(|(k, energy)| energy * (std::f32::consts::PI * (n + 1) as f32 * (k as f32 + 0.5) / BANDS as f32).cos())
+            .sum();
+    }
+    coefficients
+}
+
+fn is_voiced(level: f32, loudest: f32) -> bool {
+    level >= MIN_VOICED_DB && level >= loudest - VOICED_RANGE_DB
+}
+
+/// The frames from the first voiced one to the last
+fn trim_silence(frames: &[([f32; CEPSTRA], f32)]) -> Option<&[([f32; CEPSTRA], f32)]> {
+    let loudest = frames.iter().map(|(_, level)| *level).fold(f32::MIN, f32::max);
+    let first = frames.iter().position(|(_, level)| is_voiced(*level, loudest))?;
+    let last = frames.iter().rposition(|(_, level)| is_voiced(*level, loudest))?;
+    Some(&frames[first..=last])
+}
+
+/// Features less their mean, so the microphone and room colour them less
+fn normalized(frames: impl Iterator<Item = [f32; CEPSTRA]>) -> Vec<[f32; CEPSTRA]> {
+    let mut frames: Vec<[f32; CEPSTRA]> = frames.collect();
+    let mut mean = [0.0; CEPSTRA];
+    for frame in &frames {
+        for (sum, value) in mean.iter_mut().zip(frame) {
+            *sum += value / frames.len() as f32;
+        }
+    }
+    for frame in &mut frames {
+        for (value, mean) in frame.iter_mut().zip(&mean) {
+            *value -= mean;
+        }
+    }
+    frames
+}
+
+/// Dynamic time warping distance between two sequences of frames, per step of the path
+fn dtw_distance(a: &[[f32; CEPSTRA]], b: &[[f32; CEPSTRA]]) -> f32 {
+    let distance = |x: &[f32; CEPSTRA], y: &[f32; CEPSTRA]| {
+        x.iter().zip(y).map(|(x, y)| (x - y) * (x - y)).sum::<f32>().sqrt()
+    };
+
+    let mut previous = vec![f32::INFINITY; b.len() + 1];
+    let mut current = vec![f32::INFINITY; b.len() + 1];
+    previous[0] = 0.0;
+    for x in a {
+        current[0] = f32::INFINITY;
+        for (j, y) in b.iter().enumerate() {
+            current[j + 1] = distance(x, y) + previous[j].min(previous[j + 1]).min(current[j]);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()] / (a.len() + b.len()) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A made-up word: tones gliding between frequencies, over a little noise
+    fn word(glides: &[(f32, f32, f32)], pitch: f32) -> Vec<f32> {
+        let mut samples = Vec::new();
+        let mut phase = 0.0f32;
+        for &(from_hz, to_hz, secs) in glides {
+            let len = (secs * WAKE_WORD_SAMPLE_RATE as f32) as usize;
+            for i in 0..len {
+                let hz = (from_hz + (to_hz - from_hz) * i as f32 / len as f32) * pitch;
+                phase += 2.0 * std::f32::consts::PI * hz / WAKE_WORD_SAMPLE_RATE as f32;
+                samples.push(0.3 * phase.sin());
+            }
+        }
+        with_silence(samples)
+    }
+
+    fn with_silence(samples: Vec<f32>) -> Vec<f32> {
+        let silence = vec![0.0; WAKE_WORD_SAMPLE_RATE as usize / 4];
+        let mut noisy: Vec<f32> = silence.iter().chain(&samples).chain(&silence).copied().collect();
+        let mut seed = 12345u32;
+        for sample in &mut noisy {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            *sample += (seed as f32 / u32::MAX as f32 - 0.5) * 0.002;
+        }
+        noisy
+    }
+
+    fn hey_best(pitch: f32, stretch: f32) -> Vec<f32> {
+        word(&[(400.0, 900.0, 0.15 * stretch), (900.0, 900.0, 0.1 * stretch), (2200.0, 1800.0, 0.2 * stretch)], pitch)
+    }
+
+    fn other_word() -> Vec<f32> {
+        word(&[(1500.0, 600.0, 0.2), (300.0, 300.0, 0.25)], 1.0)
+    }
+
+    fn enrolled() -> WakeWordModel {
+        WakeWordModel::enroll("hey best", &[hey_best(1.0, 1.0), hey_best(1.03, 0.95), hey_best(0.97, 1.1)]).unwrap()
+    }
+
+    #[test]
+    fn test_enrollment() {
+        let model = enrolled();
+        assert_eq!(model.templates.len(), 3);
+        assert!(model.spread > 0.0);
+
+        // Silence around the word is trimmed
+        assert!((45..=55).contains(&model.templates[0].len()), "{}", model.templates[0].len());
+
+        assert!(WakeWordModel::enroll("hey best", &[hey_best(1.0, 1.0), hey_best(1.0, 1.0)]).is_err());
+        assert!(WakeWordModel::enroll("hey best", &[hey_best(1.0, 1.0), hey_best(1.0, 1.0), vec![0.0; 16000]]).is_err());
+        assert!(WakeWordModel::enroll("hey best", &[hey_best(1.0, 1.0), hey_best(1.0, 1.0), hey_best(1.0, 0.2)]).is_err());
+    }
+
+    #[test]
+    fn test_detects_the_wake_word_only() {
+        let mut detector = WakeWordDetector::new(enrolled(), &WakeWordSettings::default());
+
+        // Other words and silence don't wake it
+        for chunk in other_word().chunks(320) {
+            assert!(!detector.process(chunk));
+        }
+        assert!(!detector.process(&vec![0.0; 16000]));
+
+        // The wake word said a little differently does, once
+        let detections = hey_best(1.02, 1.05).chunks(320).filter(|chunk| detector.process(chunk)).count();
+        assert_eq!(detections, 1);
+    }
+}
@@ -0,0 +1,353 @@
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use windows::core::{GUID, HSTRING};
+use windows::Win32::Foundation::{CloseHandle, HANDLE, WAIT_OBJECT_0};
+use windows::Win32::Media::Audio::{
+    eCapture, eConsole, IAudioCaptureClient, IAudioClient, IMMDeviceEnumerator, MMDeviceEnumerator,
+    AUDCLNT_BUFFERFLAGS_SILENT, AUDCLNT_E_BUFFER_SIZE_NOT_ALIGNED, AUDCLNT_E_DEVICE_INVALIDATED,
+    AUDCLNT_SHAREMODE_EXCLUSIVE, AUDCLNT_STREAMFLAGS_EVENTCALLBACK, WAVEFORMATEX, WAVEFORMATEXTENSIBLE,
+    WAVEFORMATEXTENSIBLE_0,
+};
+use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CoTaskMemFree, CLSCTX_ALL, COINIT_MULTITHREADED};
+use windows::Win32::System::Threading::{CreateEventW, WaitForSingleObject};
+
+use super::source::{DataCallback, ErrorCallback, SourceError, SourceFormat, SourceHandle};
+
+/// Format tag of a `WAVEFORMATEXTENSIBLE`
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// `KSDATAFORMAT_SUBTYPE_PCM`
+const SUBTYPE_PCM: GUID = GUID::from_u128(0x00000001_0000_0010_8000_00aa00389b71);
+
+/// `KSDATAFORMAT_SUBTYPE_IEEE_FLOAT`
+const SUBTYPE_IEEE_FLOAT: GUID = GUID::from_u128(0x00000003_0000_0010_8000_00aa00389b71);
+
+/// How long the capture thread waits for a buffer before checking whether it should stop
+const BUFFER_WAIT_MS: u32 = 200;
+
+/// WASAPI durations are in units of 100 ns
+const HNS_PER_SEC: i64 = 10_000_000;
+
+/// Sample layouts tried in exclusive mode, where the device's own format must be matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SampleType {
+    F32,
+    I16,
+
+    /// 24-bit samples in 32-bit containers
+    I24In32,
+}
+
+impl SampleType {
+    const ALL: [SampleType; 3] = [SampleType::F32, SampleType::I24In32, SampleType::I16];
+
+    fn container_bits(self) -> u16 {
+        match self {
+            SampleType::F32 | SampleType::I24In32 => 32,
+            SampleType::I16 => 16,
+        }
+    }
+
+    fn valid_bits(self) -> u16 {
+        match self {
+            SampleType::F32 => 32,
+            SampleType::I24In32 => 24,
+            SampleType::I16 => 16,
+        }
+    }
+
+    /// Convert one buffer of interleaved samples to `f32`
+    fn convert(self, bytes: &[u8], output: &mut Vec<f32>) {
+        output.clear();
+        match self {
+            SampleType::F32 => output.extend(
+                bytes.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+            ),
+            SampleType::I24In32 => output.extend(
+                bytes.chunks_exact(4).map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / i32::MAX as f32),
+            ),
+            SampleType::I16 => output.extend(
+                bytes.chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / i16::MAX as f32),
+            ),
+        }
+    }
+}
+
+/// Build the extensible wave format for `sample_type` at `sample_rate` with `channels`
+fn wave_format(sample_type: SampleType, sample_rate: u32, channels: u16) -> WAVEFORMATEXTENSIBLE {
+    let block_align = channels * sample_type.container_bits() / 8;
+    WAVEFORMATEXTENSIBLE {
+        Format: WAVEFORMATEX {
+            wFormatTag: WAVE_FORMAT_EXTENSIBLE,
+            nChannels: channels,
+            nSamplesPerSec: sample_rate,
+            nAvgBytesPerSec: sample_rate * block_align as u32,
+            nBlockAlign: block_align,
+            wBitsPerSample: sample_type.container_bits(),
+            cbSize: (std::mem::size_of::<WAVEFORMATEXTENSIBLE>() - std::mem::size_of::<WAVEFORMATEX>()) as u16,
+        },
+        Samples: WAVEFORMATEXTENSIBLE_0 { wValidBitsPerSample: sample_type.valid_bits() },
+        // Channels are taken in the device's default order
+        dwChannelMask: 0,
+        SubFormat: if sample_type == SampleType::F32 { SUBTYPE_IEEE_FLOAT } else { SUBTYPE_PCM },
+    }
+}
+
+/// A capture endpoint opened in WASAPI exclusive mode, bypassing the shared-mode engine
+///
+/// Exclusive mode skips the system mixer and its buffering, so the device
+/// period can be as short as the driver allows. No other application can
+/// record from the device while the stream is open. The stream is opened on
+/// its own thread, where its COM objects live, and only starts delivering
+/// audio once `start` is called.
+pub struct ExclusiveStream {
+    format: SourceFormat,
+    callbacks: Option<mpsc::Sender<(DataCallback, ErrorCallback)>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ExclusiveStream {
+    /// Open the endpoint with ID `endpoint_id` (the default capture endpoint if None)
+    ///
+    /// The configured rate and channel count are tried first, then the
+    /// device's shared-mode format. Fails if the device refuses exclusive
+    /// mode or is in use, so the caller can fall back to shared mode.
+    pub fn open(endpoint_id: Option<&str>, sample_rate: u32, channels: u16, buffer_ms: u32) -> Result<Self> {
+        let endpoint_id = endpoint_id.map(str::to_string);
+        let (opened_sender, opened) = mpsc::sync_channel(1);
+        let (callbacks, callback_receiver) = mpsc::channel::<(DataCallback, ErrorCallback)>();
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = running.clone();
+
+        let thread = std::thread::Builder::new()
+            .name("wasapi-exclusive".to_string())
+            .spawn(move || {
+                let stream = unsafe { OpenedStream::open(endpoint_id.as_deref(), sample_rate, channels, buffer_ms) };
+                let stream = match stream {
+                    Ok(stream) => {
+                        let _ = opened_sender.send(Ok(stream.format));
+                        stream
+                    }
+                    Err(e) => {
+                        let _ = opened_sender.send(Err(e));
+                        return;
+                    }
+                };
+
+                // Wait for `start`; the stream is closed if it is dropped first
+                if let Ok((on_data, on_error)) = callback_receiver.recv() {
+                    unsafe { stream.run(&thread_running, on_data, on_error) };
+                }
+            })?;
+
+        let format = opened.recv()
+            .context("Exclusive-mode capture thread exited")??;
+
+        Ok(Self {
+            format,
+            callbacks: Some(callbacks),
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    /// Format the stream delivers
+    pub fn format(&self) -> SourceFormat {
+        self.format
+    }
+
+    /// Start delivering audio; the stream stops when the handle is dropped
+    pub fn start(mut self, on_data: DataCallback, on_error: ErrorCallback) -> Result<SourceHandle> {
+        self.callbacks.as_ref()
+            .context("Exclusive-mode stream was already started")?
+            .send((on_data, on_error))
+            .map_err(|_| anyhow::anyhow!("Exclusive-mode capture thread exited"))?;
+        self.callbacks = None;
+
+        Ok(SourceHandle::new(self))
+    }
+}
+
+impl Drop for ExclusiveStream {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+
+        // Wakes the thread if it is still waiting to be started
+        self.callbacks = None;
+
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                warn!("Exclusive-mode capture thread panicked");
+            }
+        }
+    }
+}
+
+/// An initialized exclusive-mode client, owned by the capture thread
+struct OpenedStream {
+    client: IAudioClient,
+    capture: IAudioCaptureClient,
+    event: HANDLE,
+    sample_type: SampleType,
+    format: SourceFormat,
+}
+
+impl OpenedStream {
+    unsafe fn open(endpoint_id: Option<&str>, sample_rate: u32, channels: u16, buffer_ms: u32) -> Result<Self> {
+        // COM may already be initialized on this thread, possibly in another mode
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+
+        let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
+            .context("Failed to create device enumerator")?;
+        let device = match endpoint_id {
+            Some(id) => enumerator.GetDevice(&HSTRING::from(id))
+                .with_context(|| format!("No capture endpoint '{}'", id))?,
+            None => enumerator.GetDefaultAudioEndpoint(eCapture, eConsole)
+                .context("No default capture endpoint")?,
+        };
+
+        let client: IAudioClient = device.Activate(CLSCTX_ALL, None)?;
+        let (sample_type, format) = Self::supported_format(&client, sample_rate, channels)?;
+        let wave = wave_format(sample_type, format.sample_rate, format.channels);
+
+        let mut minimum_period = 0i64;
+        client.GetDevicePeriod(None, Some(&mut minimum_period))?;
+        let period = (buffer_ms as i64 * HNS_PER_SEC / 1000).max(minimum_period);
+
+        // Exclusive mode wants a period matching the device's buffer alignment; if it
+        // doesn't, the aligned buffer size is read back and a fresh client is tried
+        let client = match client.Initialize(AUDCLNT_SHAREMODE_EXCLUSIVE, AUDCLNT_STREAMFLAGS_EVENTCALLBACK, period, period, &wave.Format, None) {
+            Ok(()) => client,
+            Err(e) if e.code() == AUDCLNT_E_BUFFER_SIZE_NOT_ALIGNED => {
+                let frames = client.GetBufferSize()? as i64;
+                let aligned = (HNS_PER_SEC * frames + format.sample_rate as i64 / 2) / format.sample_rate as i64;
+                debug!("Exclusive-mode period realigned to {} frames", frames);
+
+                let client: IAudioClient = device.Activate(CLSCTX_ALL, None)?;
+                client.Initialize(AUDCLNT_SHAREMODE_EXCLUSIVE, AUDCLNT_STREAMFLAGS_EVENTCALLBACK, aligned, aligned, &wave.Format, None)
+                    .context("Device refused exclusive mode")?;
+                client
+            }
+            Err(e) => return Err(anyhow::Error::new(e).context("Device refused exclusive mode")),
+        };
+
+        let event = CreateEventW(None, false, false, None)?;
+        if let Err(e) = client.SetEventHandle(event) {
+            CloseHandle(event);
+            return Err(e.into());
+        }
+        let capture: IAudioCaptureClient = match client.GetService() {
+            Ok(capture) => capture,
+            Err(e) => {
+                CloseHandle(event);
+                return Err(e.into());
+            }
+        };
+
+        info!(
+            "Exclusive-mode capture at {} Hz, {} channels ({:?}), {} frame buffer",
+            format.sample_rate,
+            format.channels,
+            sample_type,
+            client.GetBufferSize().unwrap_or_default()
+        );
+
+        Ok(Self { client, capture, event, sample_type, format })
+    }
+
+    /// Find a format the device accepts in exclusive mode
+    unsafe fn supported_format(client: &IAudioClient, sample_rate: u32, channels: u16) -> Result<(SampleType, SourceFormat)> {
+        let mut candidates = vec![(sample_rate, channels)];
+
+        // The shared-mode format is the one the device is certain to run at
+        if let Ok(mix_format) = client.GetMixFormat() {
+            let mix = (*mix_format).nSamplesPerSec;
+            let mix_channels = (*mix_format).nChannels;
+            CoTaskMemFree(Some(mix_format as *const _));
+
+            for candidate in [(mix, channels), (mix, mix_channels), (sample_rate, mix_channels)] {
+                if !candidates.contains(&candidate) {
+                    candidates.push(candidate);
+                }
+            }
+        }
+
+        for (sample_rate, channels) in candidates {
+            for sample_type in SampleType::ALL {
+                let wave = wave_format(sample_type, sample_rate, channels);
+                // Exclusive mode never offers a closest match
+                if client.IsFormatSupported(AUDCLNT_SHAREMODE_EXCLUSIVE, &wave.Format, None).is_ok() {
+                    return Ok((sample_type, SourceFormat { sample_rate, channels }));
+                }
+            }
+        }
+
+        anyhow::bail!("Device supports none of the tried formats in exclusive mode")
+    }
+
+    /// Deliver audio until `running` is cleared or the device fails
+    unsafe fn run(self, running: &AtomicBool, mut on_data: DataCallback, mut on_error: ErrorCallback) {
+        let block_align = (self.format.channels * self.sample_type.container_bits() / 8) as usize;
+        let mut samples = Vec::new();
+
+        if let Err(e) = self.client.Start() {
+            on_error(SourceError::Stream(format!("Failed to start exclusive-mode capture: {}", e)));
+            return;
+        }
+
+        while running.load(Ordering::SeqCst) {
+            // A timeout is left to the watchdog, which notices audio stopping
+            if WaitForSingleObject(self.event, BUFFER_WAIT_MS) != WAIT_OBJECT_0 {
+                continue;
+            }
+
+            if let Err(e) = self.drain(block_align, &mut samples, &mut on_data) {
+                on_error(if e.code() == AUDCLNT_E_DEVICE_INVALIDATED {
+                    SourceError::DeviceLost
+                } else {
+                    SourceError::Stream(e.to_string())
+                });
+                break;
+            }
+        }
+
+        debug!("Exclusive-mode capture finished");
+    }
+
+    /// Pass on every packet the device has ready
+    unsafe fn drain(&self, block_align: usize, samples: &mut Vec<f32>, on_data: &mut DataCallback) -> windows::core::Result<()> {
+        while self.capture.GetNextPacketSize()? > 0 {
+            let mut data = std::ptr::null_mut();
+            let mut frames = 0u32;
+            let mut flags = 0u32;
+            self.capture.GetBuffer(&mut data, &mut frames, &mut flags, None, None)?;
+
+            let length = frames as usize * block_align;
+            if flags & AUDCLNT_BUFFERFLAGS_SILENT.0 as u32 != 0 || data.is_null() {
+                samples.clear();
+                samples.resize(frames as usize * self.format.channels as usize, 0.0);
+            } else {
+                self.sample_type.convert(std::slice::from_raw_parts(data, length), samples);
+            }
+
+            self.capture.ReleaseBuffer(frames)?;
+            on_data(samples);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for OpenedStream {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = self.client.Stop();
+            CloseHandle(self.event);
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use anyhow::Result;
+use log::{debug, warn};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Longest time between watchdog checks
+const MAX_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Why the watchdog decided a stream needs rebuilding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StallReason {
+    /// No audio callbacks arrived for the given time
+    NoAudio(Duration),
+
+    /// The stream reported an error
+    StreamError,
+}
+
+impl fmt::Display for StallReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StallReason::NoAudio(elapsed) => write!(f, "no audio for {:.1}s", elapsed.as_secs_f32()),
+            StallReason::StreamError => write!(f, "stream error"),
+        }
+    }
+}
+
+/// Tracks a callback counter and reports when it stops advancing
+#[derive(Debug, Clone)]
+pub struct StallDetector {
+    timeout: Duration,
+    last_count: u64,
+    last_change: Instant,
+}
+
+impl StallDetector {
+    /// Create a detector that starts counting from `now`
+    pub fn new(timeout: Duration, now: Instant) -> Self {
+        Self {
+            timeout,
+            last_count: 0,
+            last_change: now,
+        }
+    }
+
+    /// Record the current counter value, returning how long it has been stuck if past the timeout
+    pub fn check(&mut self, count: u64, now: Instant) -> Option<Duration> {
+        if count != self.last_count {
+            self.last_count = count;
+            self.last_change = now;
+            return None;
+        }
+
+        let elapsed = now.saturating_duration_since(self.last_change);
+        if elapsed >= self.timeout {
+            Some(elapsed)
+        } else {
+            None
+        }
+    }
+}
+
+/// Background thread that watches a capture stream for stalls and errors
+///
+/// The audio callback bumps `heartbeat` on every buffer. If it stops
+/// advancing for the timeout, or `fault` is set by the stream error callback,
+/// `on_stall` is called once and the watchdog exits. The owner is expected to
+/// rebuild the stream, which starts a new watchdog.
+pub struct StreamWatchdog {
+    /// Whether the watchdog thread should keep running
+    running: Arc<AtomicBool>,
+
+    /// Watchdog thread handle
+    thread: Option<JoinHandle<()>>,
+}
+
+impl StreamWatchdog {
+    /// Start watching a stream
+    pub fn start<F>(heartbeat: Arc<AtomicU64>, fault: Arc<AtomicBool>, timeout: Duration, on_stall: F) -> Result<Self>
+    where
+        F: Fn(StallReason) + Send + 'static,
+    {
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = running.clone();
+        let interval = (timeout / 4).min(MAX_CHECK_INTERVAL).max(Duration::from_millis(10));
+
+        let thread = std::thread::Builder::new()
+            .name("audio-watchdog".to_string())
+            .spawn(move || {
+                let mut detector = StallDetector::new(timeout, Instant::now());
+                detector.check(heartbeat.load(Ordering::Relaxed), Instant::now());
+
+                while thread_running.load(Ordering::SeqCst) {
+                    std::thread::park_timeout(interval);
+                    if !thread_running.load(Ordering::SeqCst) {
+                        break;
+                    }
+
+                    let reason = if fault.swap(false, Ordering::SeqCst) {
+                        Some(StallReason::StreamError)
+                    } else {
+                        detector.check(heartbeat.load(Ordering::Relaxed), Instant::now())
+                            .map(StallReason::NoAudio)
+                    };
+
+                    if let Some(reason) = reason {
+                        warn!("Capture stream stalled ({}), requesting restart", reason);
+                        on_stall(reason);
+                        break;
+                    }
+                }
+
+                debug!("Audio watchdog stopped");
+            })?;
+
+        Ok(Self {
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    /// Stop the watchdog and wait for its thread to exit
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            if thread.join().is_err() {
+                warn!("Audio watchdog thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for StreamWatchdog {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_stalled_counter() {
+        let start = Instant::now();
+        let mut detector = StallDetector::new(Duration::from_secs(5), start);
+
+        assert_eq!(detector.check(10, start + Duration::from_secs(1)), None);
+        assert_eq!(detector.check(10, start + Duration::from_secs(4)), None);
+        assert_eq!(
+            detector.check(10, start + Duration::from_secs(6)),
+            Some(Duration::from_secs(5))
+        );
+    }
+
+    #[test]
+    fn test_progress_resets_timer() {
+        let start = Instant::now();
+        let mut detector = StallDetector::new(Duration::from_secs(5), start);
+
+        assert_eq!(detector.check(0, start + Duration::from_secs(4)), None);
+        assert_eq!(detector.check(1, start + Duration::from_secs(8)), None);
+        assert_eq!(detector.check(1, start + Duration::from_secs(12)), None);
+    }
+
+    #[test]
+    fn test_fault_triggers_restart() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let fault = Arc::new(AtomicBool::new(true));
+
+        let _watchdog = StreamWatchdog::start(
+            Arc::new(AtomicU64::new(0)),
+            fault,
+            Duration::from_secs(60),
+            move |reason| {
+                let _ = sender.send(reason);
+            },
+        ).unwrap();
+
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)), Ok(StallReason::StreamError));
+    }
+}
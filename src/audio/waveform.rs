@@ -0,0 +1,197 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+/// Shortest supported waveform update interval in milliseconds
+pub const MIN_WAVEFORM_INTERVAL_MS: u32 = 50;
+
+/// Longest supported waveform update interval in milliseconds
+pub const MAX_WAVEFORM_INTERVAL_MS: u32 = 100;
+
+/// Minimum and maximum sample value over a slice of time
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WaveformBucket {
+    pub min: f32,
+    pub max: f32,
+}
+
+/// Reduces interleaved audio to a fixed number of min/max buckets per interval
+///
+/// Channels are averaged before bucketing. Each completed interval yields
+/// exactly `buckets` buckets regardless of the sample rate.
+#[derive(Debug, Clone)]
+pub struct WaveformAggregator {
+    channels: usize,
+
+    /// Buckets per interval
+    buckets: usize,
+
+    /// Frames per interval
+    interval_frames: usize,
+
+    /// Frames consumed in the current interval
+    frames: usize,
+
+    /// Bucket currently being filled
+    current: Option<WaveformBucket>,
+
+    /// Buckets completed in the current interval
+    ready: Vec<WaveformBucket>,
+}
+
+impl WaveformAggregator {
+    /// Create an aggregator producing `buckets` buckets every `interval_ms` milliseconds
+    ///
+    /// The interval is clamped to 50-100 ms.
+    pub fn new(sample_rate: u32, channels: u16, interval_ms: u32, buckets: usize) -> Self {
+        let interval_ms = interval_ms.clamp(MIN_WAVEFORM_INTERVAL_MS, MAX_WAVEFORM_INTERVAL_MS);
+        let interval_frames = (sample_rate as usize * interval_ms as usize / 1000).max(1);
+        let buckets = buckets.clamp(1, interval_frames);
+
+        Self {
+            channels: channels.max(1) as usize,
+            buckets,
+            interval_frames,
+            frames: 0,
+            current: None,
+            ready: Vec::with_capacity(buckets),
+        }
+    }
+
+    /// Number of buckets per interval
+    pub fn buckets_per_interval(&self) -> usize {
+        self.buckets
+    }
+
+    /// Feed interleaved samples, returning the buckets of every interval completed
+    pub fn process(&mut self, samples: &[f32]) -> Option<Vec<WaveformBucket>> {
+        let mut completed = Vec::new();
+
+        for frame in samples.chunks_exact(self.channels) {
+            let value = frame.iter().sum::<f32>() / self.channels as f32;
+
+            let bucket = self.current.get_or_insert(WaveformBucket { min: value, max: value });
+            bucket.min = bucket.min.min(value);
+            bucket.max = bucket.max.max(value);
+            self.frames += 1;
+
+            // Bucket boundaries are spread evenly across the interval
+            let boundary = (self.ready.len() + 1) * self.interval_frames / self.buckets;
+            if self.frames >= boundary {
+                if let Some(bucket) = self.current.take() {
+                    self.ready.push(bucket);
+                }
+            }
+
+            if self.frames >= self.interval_frames {
+                completed.append(&mut self.ready);
+                self.frames = 0;
+            }
+        }
+
+        if completed.is_empty() {
+            None
+        } else {
+            Some(completed)
+        }
+    }
+
+    /// Discard the partial interval
+    pub fn reset(&mut self) {
+        self.frames = 0;
+        self.current = None;
+        self.ready.clear();
+    }
+}
+
+/// Rolling history of waveform buckets for display
+#[derive(Debug, Clone)]
+pub struct WaveformBuffer {
+    buckets: VecDeque<WaveformBucket>,
+    capacity: usize,
+}
+
+impl WaveformBuffer {
+    /// Create a buffer holding `history_ms` of waveform at the given resolution
+    pub fn new(history_ms: u32, interval_ms: u32, buckets_per_interval: usize) -> Self {
+        let interval_ms = interval_ms.clamp(MIN_WAVEFORM_INTERVAL_MS, MAX_WAVEFORM_INTERVAL_MS);
+        let intervals = (history_ms / interval_ms).max(1) as usize;
+        let capacity = intervals * buckets_per_interval.max(1);
+
+        Self {
+            buckets: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Append buckets, discarding the oldest beyond the buffer length
+    pub fn extend(&mut self, buckets: &[WaveformBucket]) {
+        self.buckets.extend(buckets.iter().copied());
+
+        let excess = self.buckets.len().saturating_sub(self.capacity);
+        self.buckets.drain(..excess);
+    }
+
+    /// Get the buffered buckets, oldest first
+    pub fn to_vec(&self) -> Vec<WaveformBucket> {
+        self.buckets.iter().copied().collect()
+    }
+
+    /// Maximum number of buckets kept
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Remove all buckets
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_bucket_count_per_interval() {
+        let mut aggregator = WaveformAggregator::new(16000, 1, 50, 10);
+
+        // 50 ms at 16 kHz is 800 frames, 80 per bucket
+        assert!(aggregator.process(&vec![0.0; 799]).is_none());
+        let buckets = aggregator.process(&[0.0]).unwrap();
+        assert_eq!(buckets.len(), 10);
+
+        // Two full intervals in one buffer
+        let buckets = aggregator.process(&vec![0.0; 1600]).unwrap();
+        assert_eq!(buckets.len(), 20);
+    }
+
+    #[test]
+    fn test_bucket_tracks_min_and_max() {
+        let mut aggregator = WaveformAggregator::new(1000, 2, 50, 5);
+
+        // 50 frames of stereo; the first bucket holds a spike in one channel
+        let mut samples = vec![0.0; 100];
+        samples[2] = 0.8;
+        samples[5] = -0.4;
+        let buckets = aggregator.process(&samples).unwrap();
+
+        assert_eq!(buckets[0], WaveformBucket { min: -0.2, max: 0.4 });
+        assert_eq!(buckets[1], WaveformBucket { min: 0.0, max: 0.0 });
+    }
+
+    #[test]
+    fn test_buffer_keeps_history_length() {
+        let mut buffer = WaveformBuffer::new(200, 50, 4);
+        assert_eq!(buffer.capacity(), 16);
+
+        let buckets: Vec<WaveformBucket> = (0..20)
+            .map(|i| WaveformBucket { min: -(i as f32), max: i as f32 })
+            .collect();
+        buffer.extend(&buckets);
+
+        let kept = buffer.to_vec();
+        assert_eq!(kept.len(), 16);
+        assert_eq!(kept[0].max, 4.0);
+    }
+}
@@ -5,7 +5,12 @@ use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 
-use crate::audio::voice_commands::VoiceCommandConfig;
+use crate::audio::active_window::executable_name;
+use crate::audio::app_control::LaunchableApp;
+use crate::audio::command_script::CommandScript;
+use crate::audio::shell_command::ShellCommand;
+use crate::audio::voice_commands::{CommandMode, CommandOverride, VoiceCommandConfig};
+use crate::audio::wake_word::MAX_ARMED_SECS;
 
 /// Application configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,33 +36,1176 @@ pub struct GeneralSettings {
     
     /// Minimize to tray on startup
     pub minimize_to_tray: bool,
+    
+    /// Local HTTP API for scripts and other tools
+    #[serde(default)]
+    pub api: ApiSettings,
+}
+
+/// Local HTTP API settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiSettings {
+    /// Whether the API is served
+    pub enabled: bool,
+    
+    /// Loopback address and port to listen on
+    pub bind_address: String,
+    
+    /// Token clients send as `Authorization: Bearer <token>`; the API isn't served without one
+    pub token: String,
+}
+
+impl Default for ApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1:7878".to_string(),
+            token: String::new(),
+        }
+    }
+}
+
+/// Audio configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioSettings {
+    /// Input device ID
+    pub input_device: Option<String>,
+    
+    /// Input volume level (0.0 - 2.0, 1.0 is unity gain)
+    pub input_volume: f32,
+    
+    /// Additional input devices captured alongside the main input device
+    #[serde(default)]
+    pub additional_devices: Vec<String>,
+    
+    /// How audio from multiple input devices is combined
+    #[serde(default)]
+    pub mix_mode: MixMode,
+    
+    /// What to do with captured audio when the transcriber falls behind
+    #[serde(default)]
+    pub overflow_policy: OverflowPolicy,
+    
+    /// Capture buffer size preset
+    #[serde(default)]
+    pub latency: LatencyPreset,
+    
+    /// Speech recognition settings
+    pub speech: SpeechSettings,
+    
+    /// Voice command settings
+    pub voice_commands: VoiceCommandConfig,
+    
+    /// Voice activity detection settings
+    #[serde(default)]
+    pub vad: VadSettings,
+    
+    /// Whether to apply noise suppression before transcription
+    #[serde(default)]
+    pub noise_suppression: bool,
+    
+    /// Sample rate conversion settings
+    #[serde(default)]
+    pub resampler: ResamplerSettings,
+    
+    /// Acoustic echo cancellation settings
+    #[serde(default)]
+    pub echo_cancellation: EchoCancellationSettings,
+    
+    /// Raw audio recording settings
+    #[serde(default)]
+    pub recording: RecordingSettings,
+    
+    /// Level metering settings
+    #[serde(default)]
+    pub metering: MeteringSettings,
+    
+    /// Waveform visualization settings
+    #[serde(default)]
+    pub waveform: WaveformSettings,
+    
+    /// Stalled stream detection settings
+    #[serde(default)]
+    pub watchdog: WatchdogSettings,
+    
+    /// Low-latency capture settings
+    #[serde(default)]
+    pub low_latency: LowLatencySettings,
+    
+    /// Pre-roll buffer settings
+    #[serde(default)]
+    pub preroll: PreRollSettings,
+    
+    /// Network audio input settings
+    #[serde(default)]
+    pub network: NetworkSettings,
+}
+
+/// Transport used to receive network audio
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub enum NetworkProtocol {
+    /// One datagram per packet of raw audio
+    #[default]
+    Udp,
+    
+    /// RTP packets over UDP (e.g. from GStreamer or ffmpeg)
+    Rtp,
+    
+    /// A continuous stream of raw PCM from one sender at a time
+    Tcp,
+}
+
+/// Encoding of network audio
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub enum NetworkFormat {
+    /// Signed 16-bit little-endian PCM
+    #[default]
+    S16Le,
+    
+    /// Signed 16-bit big-endian PCM (RTP L16)
+    S16Be,
+    
+    /// 32-bit float little-endian PCM
+    F32Le,
+    
+    /// Opus packets (UDP or RTP only)
+    Opus,
+}
+
+/// Network audio input settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkSettings {
+    /// Whether audio is received from the network instead of a local device
+    pub enabled: bool,
+    
+    /// Transport the sender uses
+    pub protocol: NetworkProtocol,
+    
+    /// Local address and port to listen on
+    pub bind_address: String,
+    
+    /// Encoding of the received audio
+    pub format: NetworkFormat,
+    
+    /// Sample rate of the received audio
+    pub sample_rate: u32,
+    
+    /// Channel count of the received audio
+    pub channels: u16,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            protocol: NetworkProtocol::Udp,
+            bind_address: "0.0.0.0:5004".to_string(),
+            format: NetworkFormat::S16Le,
+            sample_rate: 16000,
+            channels: 1,
+        }
+    }
+}
+
+/// Pre-roll buffer settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreRollSettings {
+    /// Whether audio is buffered while idle and prepended when recording starts
+    pub enabled: bool,
+    
+    /// Length of the pre-roll buffer in milliseconds
+    pub duration_ms: u32,
+}
+
+impl Default for PreRollSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            duration_ms: 2500,
+        }
+    }
+}
+
+/// Low-latency capture settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LowLatencySettings {
+    /// Whether capture requests small device buffers for live dictation
+    pub enabled: bool,
+    
+    /// Requested device buffer length in milliseconds
+    pub buffer_ms: u32,
+    
+    /// Whether the microphone is opened in WASAPI exclusive mode on Windows
+    #[serde(default)]
+    pub exclusive: bool,
+}
+
+impl Default for LowLatencySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            buffer_ms: 10,
+            exclusive: false,
+        }
+    }
+}
+
+/// Stalled stream detection settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchdogSettings {
+    /// Whether a stalled or failed capture stream is rebuilt automatically
+    pub enabled: bool,
+    
+    /// Seconds without audio before the stream is considered stalled
+    pub timeout_secs: u32,
+}
+
+impl Default for WatchdogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            timeout_secs: 5,
+        }
+    }
+}
+
+/// Waveform visualization settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaveformSettings {
+    /// Whether waveform data is produced while capturing
+    pub enabled: bool,
+    
+    /// How often waveform buckets are sent (50 - 100 milliseconds)
+    pub interval_ms: u32,
+    
+    /// Min/max buckets per interval
+    pub buckets: usize,
+    
+    /// Length of the waveform history kept for display (milliseconds)
+    pub history_ms: u32,
+}
+
+impl Default for WaveformSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_ms: 50,
+            buckets: 32,
+            history_ms: 5000,
+        }
+    }
+}
+
+/// Level metering settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeteringSettings {
+    /// How often level metrics are reported (milliseconds)
+    pub interval_ms: u32,
+    
+    /// Sample magnitude counted as clipping (0.0 - 1.0)
+    pub clip_threshold: f32,
+}
+
+impl Default for MeteringSettings {
+    fn default() -> Self {
+        Self {
+            interval_ms: 100,
+            clip_threshold: 0.99,
+        }
+    }
+}
+
+/// Raw audio recording settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingSettings {
+    /// Whether captured audio is saved to WAV files while capturing
+    pub enabled: bool,
+    
+    /// Directory for recordings (None for the application data directory)
+    pub output_dir: Option<String>,
+    
+    /// Start a new file after this many seconds (0 for no limit)
+    pub max_duration_secs: u64,
+    
+    /// Start a new file after this many megabytes (0 for no limit)
+    pub max_file_size_mb: u64,
+}
+
+impl Default for RecordingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            output_dir: None,
+            max_duration_secs: 1800,
+            max_file_size_mb: 500,
+        }
+    }
+}
+
+/// How audio from multiple capture devices is combined
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub enum MixMode {
+    /// Average all devices into a single mono channel
+    #[default]
+    Mix,
+    
+    /// Keep each device on its own channel
+    Separate,
+    
+    /// Deliver each device as its own mono track tagged with the device ID
+    Tracks,
+}
+
+/// How capture handles audio events when the receiver cannot keep up
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub enum OverflowPolicy {
+    /// Queue a short backlog and discard the oldest audio beyond it
+    #[default]
+    DropOldest,
+    
+    /// Merge pending level updates and wait for the receiver instead of dropping audio
+    CoalesceLevels,
+    
+    /// Discard new audio until the receiver catches up
+    PauseCapture,
+}
+
+/// Capture buffer size presets, trading latency against dropout resistance
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub enum LatencyPreset {
+    /// Smallest buffers the platform handles reliably, for live dictation
+    Low,
+    
+    /// Moderate buffers suited to most devices
+    #[default]
+    Normal,
+    
+    /// Large buffers for busy machines and USB or Bluetooth devices that drop out
+    Relaxed,
+}
+
+/// Acoustic echo cancellation settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EchoCancellationSettings {
+    /// Whether speaker playback is removed from the microphone signal
+    pub enabled: bool,
+    
+    /// Loopback source used as the echo reference (None for the default output)
+    pub reference_device: Option<String>,
+    
+    /// Longest echo tail to cancel in milliseconds
+    pub filter_ms: u32,
+}
+
+impl Default for EchoCancellationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            reference_device: None,
+            filter_ms: 128,
+        }
+    }
+}
+
+/// Sample rate conversion settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResamplerSettings {
+    /// Resampling quality preset
+    pub quality: ResamplerQuality,
+    
+    /// Resampler chunk duration in milliseconds (lower means less latency)
+    pub chunk_ms: u32,
+}
+
+impl Default for ResamplerSettings {
+    fn default() -> Self {
+        Self {
+            quality: ResamplerQuality::default(),
+            chunk_ms: 20,
+        }
+    }
+}
+
+/// Resampling quality presets
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub enum ResamplerQuality {
+    /// Short filter, lowest CPU usage
+    Fast,
+    
+    /// Good anti-aliasing at moderate CPU cost
+    #[default]
+    Balanced,
+    
+    /// Long filter with cubic interpolation, highest CPU usage
+    High,
+}
+
+/// Voice activity detection settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VadSettings {
+    /// Whether silent audio is suppressed before transcription
+    pub enabled: bool,
+    
+    /// Detection sensitivity (0.0 - 1.0, higher detects quieter speech)
+    pub sensitivity: f32,
+    
+    /// How long to keep speech open after the last voiced frame (milliseconds)
+    pub hangover_ms: u32,
+}
+
+impl Default for VadSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sensitivity: 0.6,
+            hangover_ms: 400,
+        }
+    }
+}
+
+/// Silence trimming applied to buffers before transcription
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SilenceSettings {
+    /// Whether silent edges are trimmed and silent buffers skipped
+    pub enabled: bool,
+    
+    /// Level below which audio counts as silence (dBFS)
+    pub threshold_db: f32,
+    
+    /// Audio kept around speech when trimming (milliseconds)
+    pub padding_ms: u32,
+}
+
+impl Default for SilenceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold_db: -50.0,
+            padding_ms: 200,
+        }
+    }
+}
+
+/// Cue layout for SRT and WebVTT transcription files and live captions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubtitleSettings {
+    /// Longest line in a cue (characters)
+    pub max_line_length: usize,
+    
+    /// Longest time a cue stays on screen (seconds)
+    pub max_cue_duration_secs: f32,
+    
+    /// Longest piece of a segment before it is split at a sentence boundary (characters)
+    pub max_segment_chars: usize,
+    
+    /// Longest piece of a segment before it is split at a sentence boundary (seconds)
+    pub max_segment_secs: f32,
+}
+
+impl Default for SubtitleSettings {
+    fn default() -> Self {
+        Self {
+            max_line_length: 42,
+            max_cue_duration_secs: 6.0,
+            max_segment_chars: 120,
+            max_segment_secs: 10.0,
+        }
+    }
+}
+
+/// How the audio stream is cut into segments for transcription
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentationSettings {
+    /// Cut segments at pauses in speech instead of every `segment_duration` seconds
+    pub adaptive: bool,
+    
+    /// Shortest segment a pause can end (seconds)
+    pub min_duration_secs: f32,
+    
+    /// Longest segment before it is cut mid-speech (seconds)
+    pub max_duration_secs: f32,
+    
+    /// Silence after speech that ends a segment (milliseconds)
+    pub pause_ms: u32,
+    
+    /// Speech detection sensitivity (0.0 - 1.0, higher detects quieter speech)
+    pub sensitivity: f32,
+}
+
+impl Default for SegmentationSettings {
+    fn default() -> Self {
+        Self {
+            adaptive: true,
+            min_duration_secs: 1.5,
+            max_duration_secs: 10.0,
+            pause_ms: 500,
+            sensitivity: 0.6,
+        }
+    }
+}
+
+/// Audio shared between consecutive segments so words at the boundary aren't cut
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentOverlapSettings {
+    /// Whether the end of each segment is transcribed again with the next one
+    pub enabled: bool,
+    
+    /// Length of the shared audio (seconds)
+    pub duration_secs: f32,
+}
+
+impl Default for SegmentOverlapSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            duration_secs: 1.0,
+        }
+    }
+}
+
+/// Layout of the transcript built up from segments, when context formatting is on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormattingSettings {
+    /// Pause between segments that starts a new paragraph (seconds)
+    pub paragraph_pause_secs: f32,
+    
+    /// Whether spelled-out numbers from ten up and percentages are written as digits
+    pub format_numbers: bool,
+    
+    /// Whether spoken list items ("first, ...", "step two") are numbered on their own lines
+    pub format_lists: bool,
+}
+
+impl Default for FormattingSettings {
+    fn default() -> Self {
+        Self {
+            paragraph_pause_secs: 3.0,
+            format_numbers: true,
+            format_lists: true,
+        }
+    }
+}
+
+/// Decoder tuning for Whisper, trading accuracy for speed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DecodingSettings {
+    /// Beams searched in parallel; 1 decodes greedily
+    pub beam_size: u32,
+    
+    /// Candidates sampled per temperature when decoding greedily
+    pub best_of: u32,
+    
+    /// Sampling temperature of the first attempt (0.0 always picks the likeliest token)
+    pub temperature: f32,
+    
+    /// Temperature added on each retry after a failed attempt (0.0 disables the fallback)
+    pub temperature_increment: f32,
+    
+    /// Probability of no speech above which a segment is treated as silence
+    pub no_speech_threshold: f32,
+    
+    /// Average log probability below which an attempt counts as failed
+    pub logprob_threshold: f32,
+}
+
+impl Default for DecodingSettings {
+    fn default() -> Self {
+        Self {
+            beam_size: 1,
+            best_of: 1,
+            temperature: 0.0,
+            temperature_increment: 0.2,
+            no_speech_threshold: 0.6,
+            logprob_threshold: -1.0,
+        }
+    }
+}
+
+/// Dropping of text the engine made up from silence or music
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HallucinationSettings {
+    /// Whether likely hallucinations are dropped
+    pub enabled: bool,
+    
+    /// No-speech probability at or above which a segment is dropped (engines that report it)
+    pub no_speech_threshold: f32,
+    
+    /// Level the audio under a segment has to reach somewhere to count as speech (dBFS)
+    pub min_level_db: f32,
+    
+    /// Phrases to drop in addition to the built-in list, when they are all a segment says
+    pub extra_phrases: Vec<String>,
+}
+
+impl Default for HallucinationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            no_speech_threshold: 0.8,
+            min_level_db: -60.0,
+            extra_phrases: Vec::new(),
+        }
+    }
+}
+
+/// Flagging of transcriptions the engine is unsure about
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfidenceSettings {
+    /// Whether low-confidence transcriptions are reported for review
+    pub flag_low: bool,
+    
+    /// Confidence below which a transcription is flagged (0.0 - 1.0)
+    pub threshold: f32,
+}
+
+impl Default for ConfidenceSettings {
+    fn default() -> Self {
+        Self {
+            flag_low: true,
+            threshold: 0.5,
+        }
+    }
+}
+
+/// Live partial results while a segment is still being recorded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PartialResultSettings {
+    /// Whether the growing segment is transcribed repeatedly to show live captions
+    pub enabled: bool,
+    
+    /// Audio added between partial transcriptions (milliseconds)
+    pub interval_ms: u32,
+    
+    /// Smaller Whisper model used for live captions (e.g. "tiny"), leaving the configured model
+    /// for the final text; None transcribes captions with the configured model
+    pub fast_model: Option<String>,
+    
+    /// Audio the fast model transcribes for each caption, from the end of the segment (seconds)
+    pub window_secs: f32,
+}
+
+impl Default for PartialResultSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_ms: 1000,
+            fast_model: None,
+            window_secs: 5.0,
+        }
+    }
+}
+
+/// Where and whether Whisper models are downloaded from
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ModelDownloadSettings {
+    /// Never download models; they must be placed in the models directory by hand
+    pub offline: bool,
+    
+    /// HTTP(S) proxy for downloads (the system proxy variables are used when unset)
+    pub proxy: Option<String>,
+    
+    /// Base URLs serving the same model files, tried before Hugging Face
+    pub mirrors: Vec<String>,
+}
+
+/// Loading the speech model before it is first needed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelPreloadSettings {
+    /// Load the configured model in the background when the application starts
+    pub on_startup: bool,
+    
+    /// Run a short transcription of silence after loading, so the first real one isn't slowed down
+    pub warm_up: bool,
+}
+
+impl Default for ModelPreloadSettings {
+    fn default() -> Self {
+        Self {
+            on_startup: false,
+            warm_up: true,
+        }
+    }
+}
+
+/// Releasing the speech model while it isn't used
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IdleUnloadSettings {
+    /// Unload the model after a while without transcription; it is loaded again when needed
+    pub enabled: bool,
+    
+    /// Minutes without transcription before the model is unloaded
+    pub timeout_mins: u32,
+}
+
+impl Default for IdleUnloadSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout_mins: 30,
+        }
+    }
+}
+
+/// When text appended to the live output file is written to disk
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub enum FlushPolicy {
+    /// After every segment, so the file is always up to date
+    #[default]
+    EachSegment,
+    
+    /// Every `flush_interval_secs`, for slow or network drives
+    Interval,
+    
+    /// Only when transcription stops or a new file is started
+    OnStop,
+}
+
+/// Appending final text to a file of the user's choosing as it is transcribed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LiveOutputSettings {
+    /// Append each final segment to the file
+    pub enabled: bool,
+    
+    /// File appended to, e.g. meeting notes
+    pub path: String,
+    
+    /// When appended text is written to disk
+    pub flush: FlushPolicy,
+    
+    /// Seconds between writes with the interval policy
+    pub flush_interval_secs: u32,
+    
+    /// Start a new file each day, named after the date
+    pub rotate_daily: bool,
+}
+
+impl Default for LiveOutputSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: String::new(),
+            flush: FlushPolicy::default(),
+            flush_interval_secs: 5,
+            rotate_daily: false,
+        }
+    }
+}
+
+/// When final text is copied to the clipboard
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub enum ClipboardMode {
+    /// Each segment as it is transcribed, replacing the one before
+    #[default]
+    EachSegment,
+    
+    /// The whole session's text once transcription stops
+    OnStop,
+}
+
+/// Copying final text to the system clipboard, for applications text shouldn't be typed into
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClipboardOutputSettings {
+    /// Copy final text to the clipboard
+    pub enabled: bool,
+    
+    /// Whether each segment or the whole session is copied
+    pub mode: ClipboardMode,
+    
+    /// Dictations kept in the clipboard history
+    pub history_size: usize,
+}
+
+impl Default for ClipboardOutputSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: ClipboardMode::default(),
+            history_size: 20,
+        }
+    }
+}
+
+/// What the profanity filter does with words on its list
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub enum ProfanityMode {
+    /// Leave the text as transcribed
+    #[default]
+    Off,
+    
+    /// Replace each letter of the word with an asterisk
+    Mask,
+    
+    /// Drop the word from the text
+    Remove,
 }
 
-/// Audio configuration
+/// Filtering profanity out of final text before it is shown, saved or passed on
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfanitySettings {
+    /// How listed words are filtered
+    pub mode: ProfanityMode,
+    
+    /// Words filtered in addition to the built-in list
+    pub words: Vec<String>,
+}
+
+/// Refining transcripts with a language model, alongside the raw text
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RefineSettings {
+    /// Send each finished utterance to the model for cleanup
+    pub enabled: bool,
+    
+    /// OpenAI-compatible chat completions endpoint, local (e.g. Ollama) or remote
+    pub endpoint: String,
+    
+    /// API key sent with each request, if the endpoint needs one
+    pub api_key: Option<String>,
+    
+    /// Model name requested from the endpoint
+    pub model: String,
+    
+    /// Instructions for cleaning up an utterance
+    pub cleanup_prompt: String,
+    
+    /// Instructions for summarizing a session
+    pub summary_prompt: String,
+    
+    /// Time to wait for a response, in seconds
+    pub timeout_secs: u32,
+}
+
+impl Default for RefineSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: "http://localhost:11434/v1/chat/completions".to_string(),
+            api_key: None,
+            model: "llama3.2".to_string(),
+            cleanup_prompt: "Fix the grammar and punctuation of this transcribed speech and remove filler words \
+                such as \"um\" and \"you know\". Keep the meaning and wording otherwise. Reply with the corrected text only.".to_string(),
+            summary_prompt: "Summarize this transcript in a few short bullet points, \
+                followed by any decisions and action items. Reply with the summary only.".to_string(),
+            timeout_secs: 30,
+        }
+    }
+}
+
+/// How a probable change of speaker is shown in the transcript
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub enum SpeakerChangeMarker {
+    /// Start a new paragraph
+    #[default]
+    Paragraph,
+    
+    /// Start a new line with a dash, as dialogue
+    Dash,
+}
+
+impl SpeakerChangeMarker {
+    /// Break put before text after a speaker change
+    pub fn separator(&self) -> &'static str {
+        match self {
+            Self::Paragraph => "\n\n",
+            Self::Dash => "\n",
+        }
+    }
+    
+    /// Marker put at the start of text after a speaker change
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Self::Paragraph => "",
+            Self::Dash => "— ",
+        }
+    }
+}
+
+/// Guessing where the speaker changes in audio from a single microphone, from pauses and voice shifts
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AudioSettings {
-    /// Input device ID
-    pub input_device: Option<String>,
+#[serde(default)]
+pub struct SpeakerChangeSettings {
+    /// Mark probable speaker changes in the transcript
+    pub enabled: bool,
     
-    /// Input volume level (0.0 - 1.0)
-    pub input_volume: f32,
+    /// How a change is marked
+    pub marker: SpeakerChangeMarker,
     
-    /// Speech recognition settings
-    pub speech: SpeechSettings,
+    /// Shortest pause between segments that can be a change of speaker (seconds)
+    pub min_pause_secs: f32,
     
-    /// Voice command settings
-    pub voice_commands: VoiceCommandConfig,
+    /// Change in typical pitch that counts as a different voice (semitones)
+    pub pitch_shift_semitones: f32,
+    
+    /// Change in typical level that counts as a different voice (dB)
+    pub level_shift_db: f32,
+}
+
+impl Default for SpeakerChangeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            marker: SpeakerChangeMarker::default(),
+            min_pause_secs: 0.7,
+            pitch_shift_semitones: 4.0,
+            level_shift_db: 8.0,
+        }
+    }
+}
+
+/// Learning replacement rules from the corrections the user makes to transcripts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CorrectionSettings {
+    /// Count corrections made to transcripts
+    pub learn: bool,
+    
+    /// Times a correction is made before it is suggested as a replacement rule
+    pub suggest_after: u32,
+    
+    /// Times a correction is made before it becomes a replacement rule without asking (0 = never)
+    pub auto_add_after: u32,
+}
+
+impl Default for CorrectionSettings {
+    fn default() -> Self {
+        Self {
+            learn: true,
+            suggest_after: 2,
+            auto_add_after: 0,
+        }
+    }
+}
+
+/// Speech-to-text engine used for transcription
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub enum SttEngineKind {
+    /// Local whisper.cpp models
+    #[default]
+    Whisper,
+    
+    /// A hosted transcription API, falling back to Whisper when it can't be reached
+    Cloud,
+    
+    /// Local Vosk models, faster than Whisper on slow machines but less accurate
+    Vosk,
+}
+
+/// Request format of a cloud transcription API
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub enum CloudApiStyle {
+    /// Multipart upload to an OpenAI-compatible `/audio/transcriptions` endpoint
+    #[default]
+    OpenAi,
+    
+    /// Raw audio posted to a Deepgram-compatible `/listen` endpoint
+    Deepgram,
+}
+
+/// Cloud transcription settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CloudSettings {
+    /// Request format the endpoint expects
+    pub api: CloudApiStyle,
+    
+    /// Transcription endpoint URL
+    pub endpoint: String,
+    
+    /// API key sent with each request
+    pub api_key: Option<String>,
+    
+    /// Model name requested from the API
+    pub model: String,
+    
+    /// Time to wait for a response before falling back, in seconds
+    pub timeout_secs: u32,
+}
+
+impl Default for CloudSettings {
+    fn default() -> Self {
+        Self {
+            api: CloudApiStyle::OpenAi,
+            endpoint: "https://api.openai.com/v1/audio/transcriptions".to_string(),
+            api_key: None,
+            model: "whisper-1".to_string(),
+            timeout_secs: 30,
+        }
+    }
+}
+
+/// Vosk engine settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VoskSettings {
+    /// Vosk model name, e.g. "small-en-us"
+    pub model: String,
+}
+
+impl Default for VoskSettings {
+    fn default() -> Self {
+        Self {
+            model: "small-en-us".to_string(),
+        }
+    }
+}
+
+/// Prefix of custom model names where a model name is expected, e.g. "custom:my-finetune"
+pub const CUSTOM_MODEL_PREFIX: &str = "custom:";
+
+/// A Whisper model file on disk, used instead of the downloadable models
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomModel {
+    /// Name shown when choosing a model
+    pub name: String,
+    
+    /// Path to the GGML or GGUF model file
+    pub path: String,
+}
+
+/// A find/replace rule applied to final transcript text
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplacementRule {
+    /// Text, or regular expression, to find
+    pub pattern: String,
+    
+    /// Text put in its place; regular expressions can refer to groups as $1 or ${name}
+    pub replacement: String,
+    
+    /// Treat the pattern as a regular expression
+    pub regex: bool,
+    
+    /// Match letter case exactly
+    pub case_sensitive: bool,
+    
+    /// Only match whole words
+    pub whole_word: bool,
+    
+    /// Apply the rule; disabled rules are kept but skipped
+    pub enabled: bool,
+}
+
+impl Default for ReplacementRule {
+    fn default() -> Self {
+        Self {
+            pattern: String::new(),
+            replacement: String::new(),
+            regex: false,
+            case_sensitive: false,
+            whole_word: false,
+            enabled: true,
+        }
+    }
+}
+
+/// A named set of find/replace rules for dictating into particular applications
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplacementDictionary {
+    /// Name the dictionary is activated by
+    pub name: String,
+    
+    /// Executables the dictionary is activated for, e.g. "code.exe"
+    pub applications: Vec<String>,
+    
+    /// Rules applied after the general ones while the dictionary is active
+    pub rules: Vec<ReplacementRule>,
+}
+
+impl ReplacementDictionary {
+    /// Whether the dictionary is for `application`, an executable name or path
+    ///
+    /// Names are compared as `executable_name` gives them, so "Code", "code.exe"
+    /// and "C:\Program Files\Code\Code.exe" are the same.
+    pub fn is_for_application(&self, application: &str) -> bool {
+        let application = executable_name(application);
+        !application.is_empty() && self.applications.iter().any(|name| executable_name(name) == application)
+    }
+}
+
+/// A step of the post-processing applied to final text
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum PostProcessingStep {
+    /// Restore punctuation and capitalization, when auto_punctuate is on
+    Punctuation,
+    
+    /// Write spelled-out numbers as digits ("twenty five" becomes "25")
+    Numbers,
+    
+    /// Apply the find/replace rules
+    Replacements,
+    
+    /// Filter profanity, unless its mode is off
+    Profanity,
+    
+    /// A step added by the application under this name
+    Custom(String),
+}
+
+/// Order of the post-processing applied to final text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PostProcessingSettings {
+    /// Steps run in order; steps left out are skipped
+    pub steps: Vec<PostProcessingStep>,
+}
+
+impl Default for PostProcessingSettings {
+    fn default() -> Self {
+        Self {
+            steps: vec![
+                PostProcessingStep::Punctuation,
+                PostProcessingStep::Replacements,
+                PostProcessingStep::Profanity,
+            ],
+        }
+    }
 }
 
 /// Speech recognition settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpeechSettings {
+    /// Speech-to-text engine
+    #[serde(default)]
+    pub engine: SttEngineKind,
+    
+    /// Cloud engine settings
+    #[serde(default)]
+    pub cloud: CloudSettings,
+    
+    /// Vosk engine settings
+    #[serde(default)]
+    pub vosk: VoskSettings,
+    
     /// Whisper model size
     pub model_size: WhisperModelSize,
     
+    /// Weight quantization of the Whisper model
+    #[serde(default)]
+    pub quantization: ModelQuantization,
+    
     /// Path to whisper model directory
     pub model_path: Option<String>,
     
+    /// Model files registered by hand
+    #[serde(default)]
+    pub custom_models: Vec<CustomModel>,
+    
+    /// Name of the custom model used instead of model_size, if any
+    #[serde(default)]
+    pub custom_model: Option<String>,
+    
     /// Language for transcription (blank or "auto" for auto-detect)
     pub language: String,
     
@@ -70,32 +1218,188 @@ pub struct SpeechSettings {
     /// Whether to use enhanced context-aware formatting
     pub context_formatting: bool,
     
-    /// Segment duration in seconds
+    /// Transcript layout used by context-aware formatting
+    #[serde(default)]
+    pub formatting: FormattingSettings,
+    
+    /// Find/replace rules applied in order to final text
+    #[serde(default)]
+    pub replacements: Vec<ReplacementRule>,
+    
+    /// Named rule sets, one of which can be active at a time
+    #[serde(default)]
+    pub dictionaries: Vec<ReplacementDictionary>,
+    
+    /// Name of the active dictionary, if any
+    #[serde(default)]
+    pub active_dictionary: Option<String>,
+    
+    /// Steps applied to final text, in order
+    #[serde(default)]
+    pub post_processing: PostProcessingSettings,
+    
+    /// Segment duration in seconds, unless segments are cut at pauses
     pub segment_duration: f32,
     
+    /// Pause-based segmentation
+    #[serde(default)]
+    pub segmentation: SegmentationSettings,
+    
     /// Whether to save transcription to file
     pub save_transcription: bool,
     
-    /// Transcription output format: "txt" or "json"
+    /// Transcription output format: "txt", "json", "srt" or "vtt"
     pub output_format: String,
     
     /// Buffer size in seconds for optimized streaming
     pub buffer_size: f32,
+    
+    /// Silence trimming before inference
+    #[serde(default)]
+    pub silence: SilenceSettings,
+    
+    /// Cue layout for subtitle output formats
+    #[serde(default)]
+    pub subtitles: SubtitleSettings,
+    
+    /// Live partial results
+    #[serde(default)]
+    pub partials: PartialResultSettings,
+    
+    /// Low-confidence flagging
+    #[serde(default)]
+    pub confidence: ConfidenceSettings,
+    
+    /// Decoder tuning
+    #[serde(default)]
+    pub decoding: DecodingSettings,
+    
+    /// Hallucination filtering
+    #[serde(default)]
+    pub hallucinations: HallucinationSettings,
+    
+    /// Audio shared between consecutive segments
+    #[serde(default)]
+    pub overlap: SegmentOverlapSettings,
+    
+    /// Model download sources and offline mode
+    #[serde(default)]
+    pub download: ModelDownloadSettings,
+    
+    /// Loading the model ahead of the first transcription
+    #[serde(default)]
+    pub preload: ModelPreloadSettings,
+    
+    /// Unloading the model while it isn't used
+    #[serde(default)]
+    pub idle_unload: IdleUnloadSettings,
+    
+    /// Appending final text to a file as it arrives
+    #[serde(default)]
+    pub live_output: LiveOutputSettings,
+    
+    /// Copying final text to the clipboard
+    #[serde(default)]
+    pub clipboard_output: ClipboardOutputSettings,
+    
+    /// Filtering profanity out of final text
+    #[serde(default)]
+    pub profanity: ProfanitySettings,
+    
+    /// Refining transcripts with a language model
+    #[serde(default)]
+    pub refine: RefineSettings,
+    
+    /// Marking probable speaker changes in single-microphone transcripts
+    #[serde(default)]
+    pub speaker_change: SpeakerChangeSettings,
+    
+    /// Learning replacement rules from corrections
+    #[serde(default)]
+    pub corrections: CorrectionSettings,
 }
 
 impl SpeechSettings {
-    /// Set model size from string
+    /// Set model size from string, with an optional quantization suffix (e.g. "medium-q5_0")
+    ///
+    /// A registered custom model is selected as "custom:<name>".
     pub fn set_model_size_from_str(&mut self, model_str: &str) -> Result<()> {
-        self.model_size = match model_str.to_lowercase().as_str() {
-            "tiny" => WhisperModelSize::Tiny,
-            "base" => WhisperModelSize::Base,
-            "small" => WhisperModelSize::Small,
-            "medium" => WhisperModelSize::Medium,
-            "large" => WhisperModelSize::Large,
-            _ => return Err(anyhow::anyhow!("Invalid model size: {}", model_str)),
+        if let Some(name) = model_str.strip_prefix(CUSTOM_MODEL_PREFIX) {
+            if !self.custom_models.iter().any(|model| model.name == name) {
+                return Err(anyhow::anyhow!("Unknown custom model: {}", name));
+            }
+            self.custom_model = Some(name.to_string());
+            return Ok(());
+        }
+        
+        let model_str = model_str.to_lowercase();
+        
+        // Model names contain dashes too (e.g. "large-v3-turbo"), so only a trailing q suffix is a quantization
+        let (size, quantization) = match model_str.rsplit_once('-') {
+            Some((size, "q5_0")) => (size, ModelQuantization::Q5_0),
+            Some((size, "q8_0")) => (size, ModelQuantization::Q8_0),
+            Some((_, suffix)) if suffix.starts_with('q') => {
+                return Err(anyhow::anyhow!("Invalid model quantization: {}", model_str))
+            }
+            _ => (model_str.as_str(), ModelQuantization::None),
         };
+        
+        self.model_size = WhisperModelSize::from_name(size)
+            .ok_or_else(|| anyhow::anyhow!("Invalid model size: {}", model_str))?;
+        self.quantization = quantization;
+        self.custom_model = None;
         Ok(())
     }
+    
+    /// The general replacement rules followed by those of the active dictionary
+    pub fn active_replacements(&self) -> Vec<ReplacementRule> {
+        let dictionary = self.active_dictionary.as_deref()
+            .and_then(|name| self.dictionaries.iter().find(|dictionary| dictionary.name == name));
+        
+        self.replacements.iter()
+            .chain(dictionary.into_iter().flat_map(|dictionary| &dictionary.rules))
+            .cloned()
+            .collect()
+    }
+    
+    /// The first dictionary for `application`, see [`ReplacementDictionary::is_for_application`]
+    pub fn dictionary_for_application(&self, application: &str) -> Option<&ReplacementDictionary> {
+        self.dictionaries.iter().find(|dictionary| dictionary.is_for_application(application))
+    }
+    
+    /// The selected custom model, if it is registered
+    pub fn selected_custom_model(&self) -> Option<&CustomModel> {
+        let name = self.custom_model.as_ref()?;
+        self.custom_models.iter().find(|model| &model.name == name)
+    }
+    
+    /// Model name including any quantization suffix, as used in model file names (e.g. "medium-q5_0")
+    ///
+    /// A custom model is named "custom:<name>"; its file is wherever it was registered.
+    pub fn model_name(&self) -> String {
+        if let Some(model) = self.selected_custom_model() {
+            return format!("{}{}", CUSTOM_MODEL_PREFIX, model.name);
+        }
+        
+        let size = self.model_size.name();
+        
+        match self.quantization {
+            ModelQuantization::None => size.to_string(),
+            ModelQuantization::Q5_0 => format!("{}-q5_0", size),
+            ModelQuantization::Q8_0 => format!("{}-q8_0", size),
+        }
+    }
+    
+    /// Whether an engine loaded for `other` can be used for these settings as is
+    pub fn same_engine(&self, other: &SpeechSettings) -> bool {
+        self.engine == other.engine
+            && self.model_name() == other.model_name()
+            && self.selected_custom_model() == other.selected_custom_model()
+            && self.model_path == other.model_path
+            && self.vosk == other.vosk
+            && self.cloud == other.cloud
+            && self.download.offline == other.download.offline
+    }
 }
 
 /// Available Whisper model sizes
@@ -115,6 +1419,15 @@ pub enum WhisperModelSize {
     
     /// Large model - slowest, most accurate
     Large,
+    
+    /// Distilled small model - English only, faster than small at similar accuracy
+    DistilSmallEn,
+    
+    /// Distilled medium model - English only, about as fast as small and nearly as accurate as medium
+    DistilMediumEn,
+    
+    /// Large v3 with a pruned decoder - close to large accuracy at several times the speed
+    LargeV3Turbo,
 }
 
 impl Default for WhisperModelSize {
@@ -123,6 +1436,54 @@ impl Default for WhisperModelSize {
     }
 }
 
+impl WhisperModelSize {
+    /// Name of the model, as used in model file names and settings
+    pub fn name(&self) -> &'static str {
+        match self {
+            WhisperModelSize::Tiny => "tiny",
+            WhisperModelSize::Base => "base",
+            WhisperModelSize::Small => "small",
+            WhisperModelSize::Medium => "medium",
+            WhisperModelSize::Large => "large",
+            WhisperModelSize::DistilSmallEn => "distil-small.en",
+            WhisperModelSize::DistilMediumEn => "distil-medium.en",
+            WhisperModelSize::LargeV3Turbo => "large-v3-turbo",
+        }
+    }
+    
+    /// Model size for a name, ignoring case
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "tiny" => Some(WhisperModelSize::Tiny),
+            "base" => Some(WhisperModelSize::Base),
+            "small" => Some(WhisperModelSize::Small),
+            "medium" => Some(WhisperModelSize::Medium),
+            "large" => Some(WhisperModelSize::Large),
+            "distil-small.en" => Some(WhisperModelSize::DistilSmallEn),
+            "distil-medium.en" => Some(WhisperModelSize::DistilMediumEn),
+            "large-v3-turbo" => Some(WhisperModelSize::LargeV3Turbo),
+            _ => None,
+        }
+    }
+}
+
+/// Weight quantization of a Whisper model file
+///
+/// Quantized models need much less memory, so a medium model fits on
+/// machines that could otherwise only run small.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub enum ModelQuantization {
+    /// Full-precision weights
+    #[default]
+    None,
+    
+    /// 5-bit weights, about a third of the full size
+    Q5_0,
+    
+    /// 8-bit weights, about half the full size with little accuracy loss
+    Q8_0,
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
@@ -131,23 +1492,67 @@ impl Default for Config {
                 theme: "system".to_string(),
                 auto_start: false,
                 minimize_to_tray: true,
+                api: ApiSettings::default(),
             },
             audio: AudioSettings {
                 input_device: None,
                 input_volume: 1.0,
+                additional_devices: Vec::new(),
+                mix_mode: MixMode::default(),
+                overflow_policy: OverflowPolicy::default(),
+                latency: LatencyPreset::default(),
                 speech: SpeechSettings {
+                    engine: SttEngineKind::default(),
+                    cloud: CloudSettings::default(),
+                    vosk: VoskSettings::default(),
                     model_size: WhisperModelSize::default(),
+                    quantization: ModelQuantization::default(),
                     model_path: None,
+                    custom_models: Vec::new(),
+                    custom_model: None,
                     language: "auto".to_string(),
                     auto_punctuate: true,
                     translate_to_english: false,
                     context_formatting: true,
+                    formatting: FormattingSettings::default(),
+                    replacements: Vec::new(),
+                    dictionaries: Vec::new(),
+                    active_dictionary: None,
+                    post_processing: PostProcessingSettings::default(),
                     segment_duration: 5.0,
+                    segmentation: SegmentationSettings::default(),
                     save_transcription: false,
                     output_format: "txt".to_string(),
                     buffer_size: 3.0,
+                    silence: SilenceSettings::default(),
+                    subtitles: SubtitleSettings::default(),
+                    partials: PartialResultSettings::default(),
+                    confidence: ConfidenceSettings::default(),
+                    decoding: DecodingSettings::default(),
+                    hallucinations: HallucinationSettings::default(),
+                    overlap: SegmentOverlapSettings::default(),
+                    download: ModelDownloadSettings::default(),
+                    preload: ModelPreloadSettings::default(),
+                    idle_unload: IdleUnloadSettings::default(),
+                    live_output: LiveOutputSettings::default(),
+                    clipboard_output: ClipboardOutputSettings::default(),
+                    profanity: ProfanitySettings::default(),
+                    refine: RefineSettings::default(),
+                    speaker_change: SpeakerChangeSettings::default(),
+                    corrections: CorrectionSettings::default(),
                 },
                 voice_commands: VoiceCommandConfig::default(),
+                vad: VadSettings::default(),
+                noise_suppression: false,
+                resampler: ResamplerSettings::default(),
+                echo_cancellation: EchoCancellationSettings::default(),
+                recording: RecordingSettings::default(),
+                metering: MeteringSettings::default(),
+                waveform: WaveformSettings::default(),
+                watchdog: WatchdogSettings::default(),
+                low_latency: LowLatencySettings::default(),
+                preroll: PreRollSettings::default(),
+                network: NetworkSettings::default(),
             },
         }
     }
@@ -334,37 +1739,252 @@ impl ConfigManager {
                 config.general.theme = theme.to_string();
             }
             
-            if let Some(auto_start) = general.get("auto_start").and_then(|v| v.as_bool()) {
-                config.general.auto_start = auto_start;
+            if let Some(auto_start) = general.get("auto_start").and_then(|v| v.as_bool()) {
+                config.general.auto_start = auto_start;
+            }
+            
+            if let Some(minimize_to_tray) = general.get("minimize_to_tray").and_then(|v| v.as_bool()) {
+                config.general.minimize_to_tray = minimize_to_tray;
+            }
+            
+            if let Some(api) = general.get("api").and_then(|v| v.as_table()) {
+                if let Some(enabled) = api.get("enabled").and_then(|v| v.as_bool()) {
+                    config.general.api.enabled = enabled;
+                }
+                
+                if let Some(bind_address) = api.get("bind_address").and_then(|v| v.as_str()) {
+                    config.general.api.bind_address = bind_address.trim().to_string();
+                }
+                
+                if let Some(token) = api.get("token").and_then(|v| v.as_str()) {
+                    config.general.api.token = token.trim().to_string();
+                }
+            }
+        }
+        
+        // Process audio settings
+        if let Some(audio) = table.get("audio").and_then(|v| v.as_table()) {
+            if let Some(input_device) = audio.get("input_device").and_then(|v| v.as_str()) {
+                if !input_device.is_empty() {
+                    config.audio.input_device = Some(input_device.to_string());
+                }
+            }
+            
+            if let Some(input_volume) = audio.get("input_volume").and_then(|v| v.as_float()) {
+                config.audio.input_volume = input_volume as f32;
+            }
+            
+            if let Some(additional_devices) = audio.get("additional_devices").and_then(|v| v.as_array()) {
+                config.audio.additional_devices = additional_devices.iter()
+                    .filter_map(|v| v.as_str())
+                    .filter(|id| !id.is_empty())
+                    .map(|id| id.to_string())
+                    .collect();
+            }
+            
+            if let Some(mix_mode) = audio.get("mix_mode").and_then(|v| v.as_str()) {
+                config.audio.mix_mode = match mix_mode.to_lowercase().as_str() {
+                    "separate" => MixMode::Separate,
+                    "tracks" => MixMode::Tracks,
+                    _ => MixMode::Mix,
+                };
+            }
+            
+            if let Some(policy) = audio.get("overflow_policy").and_then(|v| v.as_str()) {
+                config.audio.overflow_policy = match policy.to_lowercase().as_str() {
+                    "coalesce_levels" => OverflowPolicy::CoalesceLevels,
+                    "pause_capture" => OverflowPolicy::PauseCapture,
+                    _ => OverflowPolicy::DropOldest,
+                };
+            }
+            
+            if let Some(latency) = audio.get("latency").and_then(|v| v.as_str()) {
+                config.audio.latency = match latency.to_lowercase().as_str() {
+                    "low" => LatencyPreset::Low,
+                    "relaxed" => LatencyPreset::Relaxed,
+                    _ => LatencyPreset::Normal,
+                };
+            }
+            
+            if let Some(noise_suppression) = audio.get("noise_suppression").and_then(|v| v.as_bool()) {
+                config.audio.noise_suppression = noise_suppression;
+            }
+            
+            // Process resampler settings under audio.resampler
+            if let Some(resampler) = audio.get("resampler").and_then(|v| v.as_table()) {
+                if let Some(quality) = resampler.get("quality").and_then(|v| v.as_str()) {
+                    config.audio.resampler.quality = match quality.to_lowercase().as_str() {
+                        "fast" => ResamplerQuality::Fast,
+                        "high" => ResamplerQuality::High,
+                        _ => ResamplerQuality::Balanced,
+                    };
+                }
+                
+                if let Some(chunk_ms) = resampler.get("chunk_ms").and_then(|v| v.as_integer()) {
+                    config.audio.resampler.chunk_ms = chunk_ms.max(1) as u32;
+                }
+            }
+            
+            // Process echo cancellation settings under audio.echo_cancellation
+            if let Some(aec) = audio.get("echo_cancellation").and_then(|v| v.as_table()) {
+                if let Some(enabled) = aec.get("enabled").and_then(|v| v.as_bool()) {
+                    config.audio.echo_cancellation.enabled = enabled;
+                }
+                
+                if let Some(reference_device) = aec.get("reference_device").and_then(|v| v.as_str()) {
+                    if !reference_device.is_empty() {
+                        config.audio.echo_cancellation.reference_device = Some(reference_device.to_string());
+                    }
+                }
+                
+                if let Some(filter_ms) = aec.get("filter_ms").and_then(|v| v.as_integer()) {
+                    config.audio.echo_cancellation.filter_ms = filter_ms.clamp(1, 1000) as u32;
+                }
+            }
+            
+            // Process recording settings under audio.recording
+            if let Some(recording) = audio.get("recording").and_then(|v| v.as_table()) {
+                if let Some(enabled) = recording.get("enabled").and_then(|v| v.as_bool()) {
+                    config.audio.recording.enabled = enabled;
+                }
+                
+                if let Some(output_dir) = recording.get("output_dir").and_then(|v| v.as_str()) {
+                    if !output_dir.is_empty() {
+                        config.audio.recording.output_dir = Some(output_dir.to_string());
+                    }
+                }
+                
+                if let Some(max_duration) = recording.get("max_duration_secs").and_then(|v| v.as_integer()) {
+                    config.audio.recording.max_duration_secs = max_duration.max(0) as u64;
+                }
+                
+                if let Some(max_size) = recording.get("max_file_size_mb").and_then(|v| v.as_integer()) {
+                    config.audio.recording.max_file_size_mb = max_size.max(0) as u64;
+                }
+            }
+            
+            // Process metering settings under audio.metering
+            if let Some(metering) = audio.get("metering").and_then(|v| v.as_table()) {
+                if let Some(interval_ms) = metering.get("interval_ms").and_then(|v| v.as_integer()) {
+                    config.audio.metering.interval_ms = interval_ms.max(10) as u32;
+                }
+                
+                if let Some(clip_threshold) = metering.get("clip_threshold").and_then(|v| v.as_float()) {
+                    config.audio.metering.clip_threshold = (clip_threshold as f32).clamp(0.0, 1.0);
+                }
+            }
+            
+            // Process waveform settings under audio.waveform
+            if let Some(waveform) = audio.get("waveform").and_then(|v| v.as_table()) {
+                if let Some(enabled) = waveform.get("enabled").and_then(|v| v.as_bool()) {
+                    config.audio.waveform.enabled = enabled;
+                }
+                
+                if let Some(interval_ms) = waveform.get("interval_ms").and_then(|v| v.as_integer()) {
+                    config.audio.waveform.interval_ms = interval_ms.clamp(50, 100) as u32;
+                }
+                
+                if let Some(buckets) = waveform.get("buckets").and_then(|v| v.as_integer()) {
+                    config.audio.waveform.buckets = buckets.max(1) as usize;
+                }
+                
+                if let Some(history_ms) = waveform.get("history_ms").and_then(|v| v.as_integer()) {
+                    config.audio.waveform.history_ms = history_ms.max(0) as u32;
+                }
+            }
+            
+            // Process watchdog settings under audio.watchdog
+            if let Some(watchdog) = audio.get("watchdog").and_then(|v| v.as_table()) {
+                if let Some(enabled) = watchdog.get("enabled").and_then(|v| v.as_bool()) {
+                    config.audio.watchdog.enabled = enabled;
+                }
+                
+                if let Some(timeout_secs) = watchdog.get("timeout_secs").and_then(|v| v.as_integer()) {
+                    config.audio.watchdog.timeout_secs = timeout_secs.max(1) as u32;
+                }
             }
             
-            if let Some(minimize_to_tray) = general.get("minimize_to_tray").and_then(|v| v.as_bool()) {
-                config.general.minimize_to_tray = minimize_to_tray;
+            // Process low-latency settings under audio.low_latency
+            if let Some(low_latency) = audio.get("low_latency").and_then(|v| v.as_table()) {
+                if let Some(enabled) = low_latency.get("enabled").and_then(|v| v.as_bool()) {
+                    config.audio.low_latency.enabled = enabled;
+                }
+                
+                if let Some(buffer_ms) = low_latency.get("buffer_ms").and_then(|v| v.as_integer()) {
+                    config.audio.low_latency.buffer_ms = buffer_ms.clamp(1, 100) as u32;
+                }
+                
+                if let Some(exclusive) = low_latency.get("exclusive").and_then(|v| v.as_bool()) {
+                    config.audio.low_latency.exclusive = exclusive;
+                }
             }
-        }
-        
-        // Process audio settings
-        if let Some(audio) = table.get("audio").and_then(|v| v.as_table()) {
-            if let Some(input_device) = audio.get("input_device").and_then(|v| v.as_str()) {
-                if !input_device.is_empty() {
-                    config.audio.input_device = Some(input_device.to_string());
+            
+            // Process pre-roll settings under audio.preroll
+            if let Some(preroll) = audio.get("preroll").and_then(|v| v.as_table()) {
+                if let Some(enabled) = preroll.get("enabled").and_then(|v| v.as_bool()) {
+                    config.audio.preroll.enabled = enabled;
+                }
+                
+                if let Some(duration_ms) = preroll.get("duration_ms").and_then(|v| v.as_integer()) {
+                    config.audio.preroll.duration_ms = duration_ms.clamp(0, 10_000) as u32;
                 }
             }
             
-            if let Some(input_volume) = audio.get("input_volume").and_then(|v| v.as_float()) {
-                config.audio.input_volume = input_volume as f32;
+            // Process network input settings under audio.network
+            if let Some(network) = audio.get("network").and_then(|v| v.as_table()) {
+                if let Some(enabled) = network.get("enabled").and_then(|v| v.as_bool()) {
+                    config.audio.network.enabled = enabled;
+                }
+                
+                if let Some(protocol) = network.get("protocol").and_then(|v| v.as_str()) {
+                    config.audio.network.protocol = match protocol.to_lowercase().as_str() {
+                        "rtp" => NetworkProtocol::Rtp,
+                        "tcp" => NetworkProtocol::Tcp,
+                        _ => NetworkProtocol::Udp,
+                    };
+                }
+                
+                if let Some(bind_address) = network.get("bind_address").and_then(|v| v.as_str()) {
+                    config.audio.network.bind_address = bind_address.to_string();
+                }
+                
+                if let Some(format) = network.get("format").and_then(|v| v.as_str()) {
+                    config.audio.network.format = match format.to_lowercase().as_str() {
+                        "s16be" => NetworkFormat::S16Be,
+                        "f32le" => NetworkFormat::F32Le,
+                        "opus" => NetworkFormat::Opus,
+                        _ => NetworkFormat::S16Le,
+                    };
+                }
+                
+                if let Some(sample_rate) = network.get("sample_rate").and_then(|v| v.as_integer()) {
+                    config.audio.network.sample_rate = sample_rate.clamp(8000, 192_000) as u32;
+                }
+                
+                if let Some(channels) = network.get("channels").and_then(|v| v.as_integer()) {
+                    config.audio.network.channels = channels.clamp(1, 8) as u16;
+                }
             }
             
             // Process speech settings under audio.speech
             if let Some(speech) = audio.get("speech").and_then(|v| v.as_table()) {
                 if let Some(model_size) = speech.get("model_size").and_then(|v| v.as_str()) {
-                    config.audio.speech.model_size = match model_size.to_lowercase().as_str() {
-                        "tiny" => WhisperModelSize::Tiny,
-                        "base" => WhisperModelSize::Base,
-                        "small" => WhisperModelSize::Small,
-                        "medium" => WhisperModelSize::Medium,
-                        "large" => WhisperModelSize::Large,
-                        _ => WhisperModelSize::Small,
+                    config.audio.speech.model_size = WhisperModelSize::from_name(model_size).unwrap_or_default();
+                }
+                
+                if let Some(engine) = speech.get("engine").and_then(|v| v.as_str()) {
+                    config.audio.speech.engine = match engine.to_lowercase().as_str() {
+                        "cloud" => SttEngineKind::Cloud,
+                        "vosk" => SttEngineKind::Vosk,
+                        _ => SttEngineKind::Whisper,
+                    };
+                }
+                
+                if let Some(quantization) = speech.get("quantization").and_then(|v| v.as_str()) {
+                    config.audio.speech.quantization = match quantization.to_lowercase().as_str() {
+                        "q5_0" => ModelQuantization::Q5_0,
+                        "q8_0" => ModelQuantization::Q8_0,
+                        _ => ModelQuantization::None,
                     };
                 }
                 
@@ -374,6 +1994,66 @@ impl ConfigManager {
                     }
                 }
                 
+                let parse_rules = |rules: &Vec<toml::Value>| -> Vec<ReplacementRule> {
+                    rules.iter()
+                        .filter_map(|v| v.as_table())
+                        .filter_map(|rule| {
+                            let flag = |name: &str, default: bool| rule.get(name).and_then(|v| v.as_bool()).unwrap_or(default);
+                            let pattern = rule.get("pattern")?.as_str()?;
+                            (!pattern.is_empty()).then(|| ReplacementRule {
+                                pattern: pattern.to_string(),
+                                replacement: rule.get("replacement").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+                                regex: flag("regex", false),
+                                case_sensitive: flag("case_sensitive", false),
+                                whole_word: flag("whole_word", false),
+                                enabled: flag("enabled", true),
+                            })
+                        })
+                        .collect()
+                };
+                
+                if let Some(replacements) = speech.get("replacements").and_then(|v| v.as_array()) {
+                    config.audio.speech.replacements = parse_rules(replacements);
+                }
+                
+                if let Some(dictionaries) = speech.get("dictionaries").and_then(|v| v.as_array()) {
+                    config.audio.speech.dictionaries = dictionaries.iter()
+                        .filter_map(|v| v.as_table())
+                        .filter_map(|dictionary| {
+                            let name = dictionary.get("name")?.as_str()?.trim();
+                            (!name.is_empty()).then(|| ReplacementDictionary {
+                                name: name.to_string(),
+                                applications: dictionary.get("applications").and_then(|v| v.as_array())
+                                    .map(|names| names.iter().filter_map(|v| v.as_str()).map(|name| name.to_string()).collect())
+                                    .unwrap_or_default(),
+                                rules: dictionary.get("rules").and_then(|v| v.as_array()).map(&parse_rules).unwrap_or_default(),
+                            })
+                        })
+                        .collect();
+                }
+                
+                if let Some(active_dictionary) = speech.get("active_dictionary").and_then(|v| v.as_str()) {
+                    config.audio.speech.active_dictionary = (!active_dictionary.is_empty()).then(|| active_dictionary.to_string());
+                }
+                
+                if let Some(custom_models) = speech.get("custom_models").and_then(|v| v.as_array()) {
+                    config.audio.speech.custom_models = custom_models.iter()
+                        .filter_map(|v| v.as_table())
+                        .filter_map(|model| {
+                            let name = model.get("name")?.as_str()?.trim();
+                            let path = model.get("path")?.as_str()?.trim();
+                            (!name.is_empty() && !path.is_empty()).then(|| CustomModel {
+                                name: name.to_string(),
+                                path: path.to_string(),
+                            })
+                        })
+                        .collect();
+                }
+                
+                if let Some(custom_model) = speech.get("custom_model").and_then(|v| v.as_str()) {
+                    config.audio.speech.custom_model = (!custom_model.is_empty()).then(|| custom_model.to_string());
+                }
+                
                 if let Some(language) = speech.get("language").and_then(|v| v.as_str()) {
                     config.audio.speech.language = language.to_string();
                 }
@@ -390,6 +2070,41 @@ impl ConfigManager {
                     config.audio.speech.context_formatting = context_formatting;
                 }
                 
+                if let Some(formatting) = speech.get("formatting").and_then(|v| v.as_table()) {
+                    if let Some(pause) = formatting.get("paragraph_pause_secs").and_then(|v| v.as_float()) {
+                        config.audio.speech.formatting.paragraph_pause_secs = (pause as f32).clamp(0.5, 60.0);
+                    }
+                    
+                    if let Some(format_numbers) = formatting.get("format_numbers").and_then(|v| v.as_bool()) {
+                        config.audio.speech.formatting.format_numbers = format_numbers;
+                    }
+                    
+                    if let Some(format_lists) = formatting.get("format_lists").and_then(|v| v.as_bool()) {
+                        config.audio.speech.formatting.format_lists = format_lists;
+                    }
+                }
+                
+                if let Some(post_processing) = speech.get("post_processing").and_then(|v| v.as_table()) {
+                    if let Some(steps) = post_processing.get("steps").and_then(|v| v.as_array()) {
+                        config.audio.speech.post_processing.steps = steps.iter()
+                            .filter_map(|v| v.as_str())
+                            .filter_map(|step| match step.to_lowercase().as_str() {
+                                "punctuation" => Some(PostProcessingStep::Punctuation),
+                                "numbers" => Some(PostProcessingStep::Numbers),
+                                "replacements" => Some(PostProcessingStep::Replacements),
+                                "profanity" => Some(PostProcessingStep::Profanity),
+                                _ => match step.strip_prefix("custom:") {
+                                    Some(name) => Some(PostProcessingStep::Custom(name.to_string())),
+                                    None => {
+                                        warn!("Unknown post-processing step: {}", step);
+                                        None
+                                    }
+                                },
+                            })
+                            .collect();
+                    }
+                }
+                
                 if let Some(segment_duration) = speech.get("segment_duration").and_then(|v| v.as_float()) {
                     config.audio.speech.segment_duration = segment_duration as f32;
                 }
@@ -405,34 +2120,554 @@ impl ConfigManager {
                 if let Some(buffer_size) = speech.get("buffer_size").and_then(|v| v.as_float()) {
                     config.audio.speech.buffer_size = buffer_size as f32;
                 }
+                
+                if let Some(silence) = speech.get("silence").and_then(|v| v.as_table()) {
+                    if let Some(enabled) = silence.get("enabled").and_then(|v| v.as_bool()) {
+                        config.audio.speech.silence.enabled = enabled;
+                    }
+                    
+                    if let Some(threshold_db) = silence.get("threshold_db").and_then(|v| v.as_float()) {
+                        config.audio.speech.silence.threshold_db = (threshold_db as f32).clamp(-96.0, 0.0);
+                    }
+                    
+                    if let Some(padding_ms) = silence.get("padding_ms").and_then(|v| v.as_integer()) {
+                        config.audio.speech.silence.padding_ms = padding_ms.clamp(0, 2000) as u32;
+                    }
+                }
+                
+                if let Some(subtitles) = speech.get("subtitles").and_then(|v| v.as_table()) {
+                    if let Some(max_line_length) = subtitles.get("max_line_length").and_then(|v| v.as_integer()) {
+                        config.audio.speech.subtitles.max_line_length = max_line_length.clamp(10, 200) as usize;
+                    }
+                    
+                    if let Some(max_duration) = subtitles.get("max_cue_duration_secs").and_then(|v| v.as_float()) {
+                        config.audio.speech.subtitles.max_cue_duration_secs = (max_duration as f32).clamp(0.5, 30.0);
+                    }
+                    
+                    if let Some(max_chars) = subtitles.get("max_segment_chars").and_then(|v| v.as_integer()) {
+                        config.audio.speech.subtitles.max_segment_chars = max_chars.clamp(20, 1000) as usize;
+                    }
+                    
+                    if let Some(max_secs) = subtitles.get("max_segment_secs").and_then(|v| v.as_float()) {
+                        config.audio.speech.subtitles.max_segment_secs = (max_secs as f32).clamp(1.0, 60.0);
+                    }
+                }
+                
+                if let Some(partials) = speech.get("partials").and_then(|v| v.as_table()) {
+                    if let Some(enabled) = partials.get("enabled").and_then(|v| v.as_bool()) {
+                        config.audio.speech.partials.enabled = enabled;
+                    }
+                    
+                    if let Some(interval_ms) = partials.get("interval_ms").and_then(|v| v.as_integer()) {
+                        config.audio.speech.partials.interval_ms = interval_ms.clamp(250, 10000) as u32;
+                    }
+                    
+                    if let Some(fast_model) = partials.get("fast_model").and_then(|v| v.as_str()) {
+                        config.audio.speech.partials.fast_model = if fast_model.is_empty() { None } else { Some(fast_model.to_string()) };
+                    }
+                    
+                    if let Some(window_secs) = partials.get("window_secs").and_then(|v| v.as_float()) {
+                        config.audio.speech.partials.window_secs = (window_secs as f32).clamp(1.0, 30.0);
+                    }
+                }
+                
+                if let Some(decoding) = speech.get("decoding").and_then(|v| v.as_table()) {
+                    if let Some(beam_size) = decoding.get("beam_size").and_then(|v| v.as_integer()) {
+                        config.audio.speech.decoding.beam_size = beam_size.clamp(1, 16) as u32;
+                    }
+                    
+                    if let Some(best_of) = decoding.get("best_of").and_then(|v| v.as_integer()) {
+                        config.audio.speech.decoding.best_of = best_of.clamp(1, 16) as u32;
+                    }
+                    
+                    if let Some(temperature) = decoding.get("temperature").and_then(|v| v.as_float()) {
+                        config.audio.speech.decoding.temperature = (temperature as f32).clamp(0.0, 1.0);
+                    }
+                    
+                    if let Some(increment) = decoding.get("temperature_increment").and_then(|v| v.as_float()) {
+                        config.audio.speech.decoding.temperature_increment = (increment as f32).clamp(0.0, 1.0);
+                    }
+                    
+                    if let Some(threshold) = decoding.get("no_speech_threshold").and_then(|v| v.as_float()) {
+                        config.audio.speech.decoding.no_speech_threshold = (threshold as f32).clamp(0.0, 1.0);
+                    }
+                    
+                    if let Some(threshold) = decoding.get("logprob_threshold").and_then(|v| v.as_float()) {
+                        config.audio.speech.decoding.logprob_threshold = (threshold as f32).min(0.0);
+                    }
+                }
+                
+                if let Some(hallucinations) = speech.get("hallucinations").and_then(|v| v.as_table()) {
+                    if let Some(enabled) = hallucinations.get("enabled").and_then(|v| v.as_bool()) {
+                        config.audio.speech.hallucinations.enabled = enabled;
+                    }
+                    
+                    if let Some(threshold) = hallucinations.get("no_speech_threshold").and_then(|v| v.as_float()) {
+                        config.audio.speech.hallucinations.no_speech_threshold = (threshold as f32).clamp(0.0, 1.0);
+                    }
+                    
+                    if let Some(level) = hallucinations.get("min_level_db").and_then(|v| v.as_float()) {
+                        config.audio.speech.hallucinations.min_level_db = (level as f32).clamp(-100.0, 0.0);
+                    }
+                    
+                    if let Some(phrases) = hallucinations.get("extra_phrases").and_then(|v| v.as_array()) {
+                        config.audio.speech.hallucinations.extra_phrases = phrases.iter()
+                            .filter_map(|v| v.as_str())
+                            .map(|phrase| phrase.to_string())
+                            .collect();
+                    }
+                }
+                
+                if let Some(confidence) = speech.get("confidence").and_then(|v| v.as_table()) {
+                    if let Some(flag_low) = confidence.get("flag_low").and_then(|v| v.as_bool()) {
+                        config.audio.speech.confidence.flag_low = flag_low;
+                    }
+                    
+                    if let Some(threshold) = confidence.get("threshold").and_then(|v| v.as_float()) {
+                        config.audio.speech.confidence.threshold = (threshold as f32).clamp(0.0, 1.0);
+                    }
+                }
+                
+                if let Some(segmentation) = speech.get("segmentation").and_then(|v| v.as_table()) {
+                    if let Some(adaptive) = segmentation.get("adaptive").and_then(|v| v.as_bool()) {
+                        config.audio.speech.segmentation.adaptive = adaptive;
+                    }
+                    
+                    if let Some(min_duration) = segmentation.get("min_duration_secs").and_then(|v| v.as_float()) {
+                        config.audio.speech.segmentation.min_duration_secs = (min_duration as f32).clamp(0.5, 30.0);
+                    }
+                    
+                    if let Some(max_duration) = segmentation.get("max_duration_secs").and_then(|v| v.as_float()) {
+                        config.audio.speech.segmentation.max_duration_secs = (max_duration as f32).clamp(1.0, 30.0);
+                    }
+                    
+                    if let Some(pause_ms) = segmentation.get("pause_ms").and_then(|v| v.as_integer()) {
+                        config.audio.speech.segmentation.pause_ms = pause_ms.clamp(100, 5000) as u32;
+                    }
+                    
+                    if let Some(sensitivity) = segmentation.get("sensitivity").and_then(|v| v.as_float()) {
+                        config.audio.speech.segmentation.sensitivity = (sensitivity as f32).clamp(0.0, 1.0);
+                    }
+                }
+                
+                if let Some(overlap) = speech.get("overlap").and_then(|v| v.as_table()) {
+                    if let Some(enabled) = overlap.get("enabled").and_then(|v| v.as_bool()) {
+                        config.audio.speech.overlap.enabled = enabled;
+                    }
+                    
+                    if let Some(duration) = overlap.get("duration_secs").and_then(|v| v.as_float()) {
+                        config.audio.speech.overlap.duration_secs = (duration as f32).clamp(0.0, 2.0);
+                    }
+                }
+                
+                if let Some(cloud) = speech.get("cloud").and_then(|v| v.as_table()) {
+                    if let Some(api) = cloud.get("api").and_then(|v| v.as_str()) {
+                        config.audio.speech.cloud.api = match api.to_lowercase().as_str() {
+                            "deepgram" => CloudApiStyle::Deepgram,
+                            _ => CloudApiStyle::OpenAi,
+                        };
+                    }
+                    
+                    if let Some(endpoint) = cloud.get("endpoint").and_then(|v| v.as_str()) {
+                        if !endpoint.is_empty() {
+                            config.audio.speech.cloud.endpoint = endpoint.to_string();
+                        }
+                    }
+                    
+                    if let Some(api_key) = cloud.get("api_key").and_then(|v| v.as_str()) {
+                        config.audio.speech.cloud.api_key = if api_key.is_empty() { None } else { Some(api_key.to_string()) };
+                    }
+                    
+                    if let Some(model) = cloud.get("model").and_then(|v| v.as_str()) {
+                        if !model.is_empty() {
+                            config.audio.speech.cloud.model = model.to_string();
+                        }
+                    }
+                    
+                    if let Some(timeout) = cloud.get("timeout_secs").and_then(|v| v.as_integer()) {
+                        config.audio.speech.cloud.timeout_secs = timeout.clamp(1, 300) as u32;
+                    }
+                }
+                
+                if let Some(vosk) = speech.get("vosk").and_then(|v| v.as_table()) {
+                    if let Some(model) = vosk.get("model").and_then(|v| v.as_str()) {
+                        if !model.is_empty() {
+                            config.audio.speech.vosk.model = model.to_string();
+                        }
+                    }
+                }
+                
+                if let Some(download) = speech.get("download").and_then(|v| v.as_table()) {
+                    if let Some(offline) = download.get("offline").and_then(|v| v.as_bool()) {
+                        config.audio.speech.download.offline = offline;
+                    }
+                    
+                    if let Some(proxy) = download.get("proxy").and_then(|v| v.as_str()) {
+                        config.audio.speech.download.proxy = if proxy.is_empty() { None } else { Some(proxy.to_string()) };
+                    }
+                    
+                    if let Some(mirrors) = download.get("mirrors").and_then(|v| v.as_array()) {
+                        config.audio.speech.download.mirrors = mirrors.iter()
+                            .filter_map(|v| v.as_str())
+                            .filter(|url| !url.is_empty())
+                            .map(|url| url.to_string())
+                            .collect();
+                    }
+                }
+                
+                if let Some(preload) = speech.get("preload").and_then(|v| v.as_table()) {
+                    if let Some(on_startup) = preload.get("on_startup").and_then(|v| v.as_bool()) {
+                        config.audio.speech.preload.on_startup = on_startup;
+                    }
+                    
+                    if let Some(warm_up) = preload.get("warm_up").and_then(|v| v.as_bool()) {
+                        config.audio.speech.preload.warm_up = warm_up;
+                    }
+                }
+                
+                if let Some(idle_unload) = speech.get("idle_unload").and_then(|v| v.as_table()) {
+                    if let Some(enabled) = idle_unload.get("enabled").and_then(|v| v.as_bool()) {
+                        config.audio.speech.idle_unload.enabled = enabled;
+                    }
+                    
+                    if let Some(timeout_mins) = idle_unload.get("timeout_mins").and_then(|v| v.as_integer()) {
+                        config.audio.speech.idle_unload.timeout_mins = timeout_mins.clamp(1, 1440) as u32;
+                    }
+                }
+                
+                if let Some(live_output) = speech.get("live_output").and_then(|v| v.as_table()) {
+                    if let Some(enabled) = live_output.get("enabled").and_then(|v| v.as_bool()) {
+                        config.audio.speech.live_output.enabled = enabled;
+                    }
+                    
+                    if let Some(path) = live_output.get("path").and_then(|v| v.as_str()) {
+                        config.audio.speech.live_output.path = path.to_string();
+                    }
+                    
+                    if let Some(flush) = live_output.get("flush").and_then(|v| v.as_str()) {
+                        config.audio.speech.live_output.flush = match flush.to_lowercase().as_str() {
+                            "interval" => FlushPolicy::Interval,
+                            "on_stop" => FlushPolicy::OnStop,
+                            _ => FlushPolicy::EachSegment,
+                        };
+                    }
+                    
+                    if let Some(flush_interval_secs) = live_output.get("flush_interval_secs").and_then(|v| v.as_integer()) {
+                        config.audio.speech.live_output.flush_interval_secs = flush_interval_secs.clamp(1, 3600) as u32;
+                    }
+                    
+                    if let Some(rotate_daily) = live_output.get("rotate_daily").and_then(|v| v.as_bool()) {
+                        config.audio.speech.live_output.rotate_daily = rotate_daily;
+                    }
+                }
+                
+                if let Some(clipboard_output) = speech.get("clipboard_output").and_then(|v| v.as_table()) {
+                    if let Some(enabled) = clipboard_output.get("enabled").and_then(|v| v.as_bool()) {
+                        config.audio.speech.clipboard_output.enabled = enabled;
+                    }
+                    
+                    if let Some(mode) = clipboard_output.get("mode").and_then(|v| v.as_str()) {
+                        config.audio.speech.clipboard_output.mode = match mode.to_lowercase().as_str() {
+                            "on_stop" => ClipboardMode::OnStop,
+                            _ => ClipboardMode::EachSegment,
+                        };
+                    }
+                    
+                    if let Some(history_size) = clipboard_output.get("history_size").and_then(|v| v.as_integer()) {
+                        config.audio.speech.clipboard_output.history_size = history_size.clamp(1, 500) as usize;
+                    }
+                }
+                
+                if let Some(profanity) = speech.get("profanity").and_then(|v| v.as_table()) {
+                    if let Some(mode) = profanity.get("mode").and_then(|v| v.as_str()) {
+                        config.audio.speech.profanity.mode = match mode.to_lowercase().as_str() {
+                            "mask" => ProfanityMode::Mask,
+                            "remove" => ProfanityMode::Remove,
+                            _ => ProfanityMode::Off,
+                        };
+                    }
+                    
+                    if let Some(words) = profanity.get("words").and_then(|v| v.as_array()) {
+                        config.audio.speech.profanity.words = words.iter()
+                            .filter_map(|v| v.as_str())
+                            .map(|word| word.trim().to_string())
+                            .filter(|word| !word.is_empty())
+                            .collect();
+                    }
+                }
+                
+                if let Some(refine) = speech.get("refine").and_then(|v| v.as_table()) {
+                    if let Some(enabled) = refine.get("enabled").and_then(|v| v.as_bool()) {
+                        config.audio.speech.refine.enabled = enabled;
+                    }
+                    
+                    if let Some(endpoint) = refine.get("endpoint").and_then(|v| v.as_str()) {
+                        if !endpoint.is_empty() {
+                            config.audio.speech.refine.endpoint = endpoint.to_string();
+                        }
+                    }
+                    
+                    if let Some(api_key) = refine.get("api_key").and_then(|v| v.as_str()) {
+                        config.audio.speech.refine.api_key = if api_key.is_empty() { None } else { Some(api_key.to_string()) };
+                    }
+                    
+                    if let Some(model) = refine.get("model").and_then(|v| v.as_str()) {
+                        if !model.is_empty() {
+                            config.audio.speech.refine.model = model.to_string();
+                        }
+                    }
+                    
+                    if let Some(prompt) = refine.get("cleanup_prompt").and_then(|v| v.as_str()) {
+                        if !prompt.trim().is_empty() {
+                            config.audio.speech.refine.cleanup_prompt = prompt.trim().to_string();
+                        }
+                    }
+                    
+                    if let Some(prompt) = refine.get("summary_prompt").and_then(|v| v.as_str()) {
+                        if !prompt.trim().is_empty() {
+                            config.audio.speech.refine.summary_prompt = prompt.trim().to_string();
+                        }
+                    }
+                    
+                    if let Some(timeout) = refine.get("timeout_secs").and_then(|v| v.as_integer()) {
+                        config.audio.speech.refine.timeout_secs = timeout.clamp(1, 600) as u32;
+                    }
+                }
+                
+                if let Some(speaker_change) = speech.get("speaker_change").and_then(|v| v.as_table()) {
+                    if let Some(enabled) = speaker_change.get("enabled").and_then(|v| v.as_bool()) {
+                        config.audio.speech.speaker_change.enabled = enabled;
+                    }
+                    
+                    if let Some(marker) = speaker_change.get("marker").and_then(|v| v.as_str()) {
+                        config.audio.speech.speaker_change.marker = match marker.to_lowercase().as_str() {
+                            "dash" => SpeakerChangeMarker::Dash,
+                            _ => SpeakerChangeMarker::Paragraph,
+                        };
+                    }
+                    
+                    if let Some(pause) = speaker_change.get("min_pause_secs").and_then(|v| v.as_float()) {
+                        config.audio.speech.speaker_change.min_pause_secs = (pause as f32).clamp(0.0, 10.0);
+                    }
+                    
+                    if let Some(shift) = speaker_change.get("pitch_shift_semitones").and_then(|v| v.as_float()) {
+                        config.audio.speech.speaker_change.pitch_shift_semitones = (shift as f32).clamp(0.5, 24.0);
+                    }
+                    
+                    if let Some(shift) = speaker_change.get("level_shift_db").and_then(|v| v.as_float()) {
+                        config.audio.speech.speaker_change.level_shift_db = (shift as f32).clamp(1.0, 40.0);
+                    }
+                }
+                
+                if let Some(corrections) = speech.get("corrections").and_then(|v| v.as_table()) {
+                    if let Some(learn) = corrections.get("learn").and_then(|v| v.as_bool()) {
+                        config.audio.speech.corrections.learn = learn;
+                    }
+                    
+                    if let Some(count) = corrections.get("suggest_after").and_then(|v| v.as_integer()) {
+                        config.audio.speech.corrections.suggest_after = count.clamp(1, 100) as u32;
+                    }
+                    
+                    if let Some(count) = corrections.get("auto_add_after").and_then(|v| v.as_integer()) {
+                        config.audio.speech.corrections.auto_add_after = count.clamp(0, 100) as u32;
+                    }
+                }
             }
             
             // Process voice commands settings
             if let Some(voice_commands) = audio.get("voice_commands").and_then(|v| v.as_table()) {
-                if let Some(enabled) = voice_commands.get("enabled").and_then(|v| v.as_bool()) {
-                    config.audio.voice_commands.enabled = enabled;
-                }
+                Self::apply_voice_command_settings(&mut config.audio.voice_commands, voice_commands);
                 
-                if let Some(command_prefix) = voice_commands.get("command_prefix").and_then(|v| v.as_str()) {
-                    config.audio.voice_commands.command_prefix = Some(command_prefix.to_string());
+                // Profiles start from the settings above and change only what they set
+                if let Some(profiles) = voice_commands.get("profiles").and_then(|v| v.as_table()) {
+                    let mut base = config.audio.voice_commands.clone();
+                    base.profiles.clear();
+                    for (name, settings) in profiles {
+                        if let Some(settings) = settings.as_table() {
+                            let mut profile = base.clone();
+                            Self::apply_voice_command_settings(&mut profile, settings);
+                            config.audio.voice_commands.profiles.insert(name.trim().to_string(), profile);
+                        }
+                    }
                 }
                 
-                if let Some(require_prefix) = voice_commands.get("require_prefix").and_then(|v| v.as_bool()) {
-                    config.audio.voice_commands.require_prefix = require_prefix;
+                if let Some(active_profile) = voice_commands.get("active_profile").and_then(|v| v.as_str()) {
+                    config.audio.voice_commands.active_profile = (!active_profile.is_empty()).then(|| active_profile.to_string());
+                }
+            }
+            
+            // Process voice activity detection settings
+            if let Some(vad) = audio.get("vad").and_then(|v| v.as_table()) {
+                if let Some(enabled) = vad.get("enabled").and_then(|v| v.as_bool()) {
+                    config.audio.vad.enabled = enabled;
                 }
                 
-                if let Some(sensitivity) = voice_commands.get("sensitivity").and_then(|v| v.as_float()) {
-                    config.audio.voice_commands.sensitivity = sensitivity as f32;
+                if let Some(sensitivity) = vad.get("sensitivity").and_then(|v| v.as_float()) {
+                    config.audio.vad.sensitivity = sensitivity as f32;
                 }
                 
-                // Note: custom_commands are not handled here as they have a more complex format
-                // that would require special parsing from the TOML structure
+                if let Some(hangover_ms) = vad.get("hangover_ms").and_then(|v| v.as_integer()) {
+                    config.audio.vad.hangover_ms = hangover_ms.max(0) as u32;
+                }
             }
         }
         
         Ok(())
     }
     
+    /// Apply the `[audio.voice_commands]` settings of the settings file, or those of one of its profiles
+    fn apply_voice_command_settings(config: &mut VoiceCommandConfig, voice_commands: &toml::Table) {
+        if let Some(enabled) = voice_commands.get("enabled").and_then(|v| v.as_bool()) {
+            config.enabled = enabled;
+        }
+        
+        if let Some(command_prefix) = voice_commands.get("command_prefix").and_then(|v| v.as_str()) {
+            config.command_prefix = Some(command_prefix.to_string());
+        }
+        
+        if let Some(require_prefix) = voice_commands.get("require_prefix").and_then(|v| v.as_bool()) {
+            config.require_prefix = require_prefix;
+        }
+        
+        if let Some(sensitivity) = voice_commands.get("sensitivity").and_then(|v| v.as_float()) {
+            config.sensitivity = (sensitivity as f32).clamp(0.0, 1.0);
+        }
+        
+        if let Some(mode) = voice_commands.get("mode").and_then(|v| v.as_str()) {
+            config.mode = match mode.to_lowercase().as_str() {
+                "dictation" => CommandMode::Dictation,
+                "command" => CommandMode::Command,
+                _ => CommandMode::Mixed,
+            };
+        }
+        
+        if let Some(wake_word) = voice_commands.get("wake_word").and_then(|v| v.as_table()) {
+            if let Some(enabled) = wake_word.get("enabled").and_then(|v| v.as_bool()) {
+                config.wake_word.enabled = enabled;
+            }
+            
+            if let Some(sensitivity) = wake_word.get("sensitivity").and_then(|v| v.as_float()) {
+                config.wake_word.sensitivity = (sensitivity as f32).clamp(0.0, 1.0);
+            }
+            
+            if let Some(armed) = wake_word.get("armed_secs").and_then(|v| v.as_float()).filter(|armed| !armed.is_nan()) {
+                config.wake_word.armed_secs = (armed as f32).clamp(1.0, MAX_ARMED_SECS);
+            }
+        }
+        
+        if let Some(overrides) = voice_commands.get("overrides").and_then(|v| v.as_table()) {
+            config.overrides = overrides.iter()
+                .filter_map(|(trigger, settings)| {
+                    let settings = settings.as_table()?;
+                    let command_override = CommandOverride {
+                        enabled: settings.get("enabled").and_then(|v| v.as_bool()).unwrap_or(true),
+                        sensitivity: settings.get("sensitivity")
+                            .and_then(|v| v.as_float())
+                            .map(|sensitivity| (sensitivity as f32).clamp(0.0, 1.0)),
+                        apps: settings.get("apps").and_then(|v| v.as_array())
+                            .map(|apps| apps.iter().filter_map(|v| v.as_str()).map(|app| app.trim().to_string()).collect())
+                            .unwrap_or_default(),
+                    };
+                    Some((trigger.trim().to_string(), command_override))
+                })
+                .collect();
+        }
+        
+        if let Some(system_commands) = voice_commands.get("system_commands").and_then(|v| v.as_bool()) {
+            config.system_commands = system_commands;
+        }
+        
+        if let Some(keystrokes) = voice_commands.get("keystrokes").and_then(|v| v.as_bool()) {
+            config.keystrokes = keystrokes;
+        }
+        
+        if let Some(inject_text) = voice_commands.get("inject_text").and_then(|v| v.as_bool()) {
+            config.inject_text = inject_text;
+        }
+        
+        if let Some(plugin_dir) = voice_commands.get("plugin_dir").and_then(|v| v.as_str()) {
+            let plugin_dir = plugin_dir.trim();
+            config.plugin_dir = (!plugin_dir.is_empty()).then(|| plugin_dir.to_string());
+        }
+        
+        if let Some(hook_scripts) = voice_commands.get("hook_scripts").and_then(|v| v.as_array()) {
+            config.hook_scripts = hook_scripts.iter()
+                .filter_map(|v| v.as_str())
+                .map(|path| path.trim().to_string())
+                .filter(|path| !path.is_empty())
+                .collect();
+        }
+        
+        if let Some(app_control) = voice_commands.get("app_control").and_then(|v| v.as_table()) {
+            if let Some(enabled) = app_control.get("enabled").and_then(|v| v.as_bool()) {
+                config.app_control.enabled = enabled;
+            }
+            
+            if let Some(applications) = app_control.get("applications").and_then(|v| v.as_array()) {
+                config.app_control.applications = applications.iter()
+                    .filter_map(|v| v.as_table())
+                    .filter_map(|app| {
+                        let name = app.get("name")?.as_str()?.trim();
+                        let command = app.get("command")?.as_str()?.trim();
+                        let args = app.get("args").and_then(|v| v.as_array()).map(|args| {
+                            args.iter().filter_map(|arg| arg.as_str().map(|arg| arg.to_string())).collect()
+                        });
+                        (!name.is_empty() && !command.is_empty()).then(|| LaunchableApp {
+                            name: name.to_string(),
+                            command: command.to_string(),
+                            args: args.unwrap_or_default(),
+                        })
+                    })
+                    .collect();
+            }
+        }
+        
+        if let Some(shell_commands) = voice_commands.get("shell_commands").and_then(|v| v.as_table()) {
+            if let Some(enabled) = shell_commands.get("enabled").and_then(|v| v.as_bool()) {
+                config.shell_commands.enabled = enabled;
+            }
+            
+            if let Some(commands) = shell_commands.get("commands").and_then(|v| v.as_array()) {
+                config.shell_commands.commands = commands.iter()
+                    .filter_map(|v| v.as_table())
+                    .filter_map(|program| {
+                        let name = program.get("name")?.as_str()?.trim();
+                        let command = program.get("command")?.as_str()?.trim();
+                        let args = program.get("args").and_then(|v| v.as_array()).map(|args| {
+                            args.iter().filter_map(|arg| arg.as_str().map(|arg| arg.to_string())).collect()
+                        });
+                        let working_dir = program.get("working_dir").and_then(|v| v.as_str()).map(|dir| dir.to_string());
+                        let timeout_secs = program.get("timeout_secs").and_then(|v| v.as_integer()).unwrap_or(30).clamp(1, 3600);
+                        (!name.is_empty() && !command.is_empty()).then(|| ShellCommand {
+                            name: name.to_string(),
+                            command: command.to_string(),
+                            args: args.unwrap_or_default(),
+                            working_dir,
+                            timeout_secs: timeout_secs as u64,
+                        })
+                    })
+                    .collect();
+            }
+        }
+        
+        // A script with mistakes is reported and left out, keeping the one already configured
+        if let Some(script) = voice_commands.get("script").and_then(|v| v.as_str()) {
+            match CommandScript::parse(script) {
+                Ok(_) => config.script = script.to_string(),
+                Err(e) => warn!("Invalid voice command script in settings file, {}", e),
+            }
+        }
+        
+        // Read as saved configurations have them, e.g. `custom_commands = [["scratch that", "Undo"]]`
+        if let Some(custom_commands) = voice_commands.get("custom_commands") {
+            let saved = toml::Value::Table(toml::Table::from_iter([("custom_commands".to_string(), custom_commands.clone())]));
+            match saved.try_into::<VoiceCommandConfig>() {
+                Ok(saved) => config.custom_commands = saved.custom_commands,
+                Err(e) => warn!("Invalid custom voice commands in settings file, {}", e),
+            }
+        }
+    }
+    
     /// Get a reference to the configuration
     pub fn get_config(&self) -> &Config {
         &self.config
@@ -490,23 +2725,21 @@ impl ConfigManager {
         true
     }
     
-    /// Set offline mode flag
+    /// Set offline mode flag (models are never downloaded while offline)
     pub fn set_offline_mode(&mut self, offline_mode: bool) {
-        // This is a new feature, so we'll just print for now
-        println!("Setting offline mode: {}", offline_mode);
-        // In a real implementation, this would modify a field in the config
+        self.config.audio.speech.download.offline = offline_mode;
     }
     
     /// Get offline mode flag
     pub fn offline_mode(&self) -> bool {
-        // This is a new feature, so we'll just return a default for now
-        true
+        self.config.audio.speech.download.offline
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::audio::voice_commands::VoiceCommandType;
     
     #[test]
     fn test_default_config() {
@@ -517,4 +2750,107 @@ mod tests {
         assert_eq!(config.audio.input_volume, 1.0);
         assert!(config.audio.input_device.is_none());
     }
-} 
+    
+    #[test]
+    fn test_quantized_model_names() {
+        let mut speech = Config::default().audio.speech;
+        assert_eq!(speech.model_name(), "small");
+        
+        speech.set_model_size_from_str("Medium-Q5_0").unwrap();
+        assert_eq!(speech.model_size, WhisperModelSize::Medium);
+        assert_eq!(speech.quantization, ModelQuantization::Q5_0);
+        assert_eq!(speech.model_name(), "medium-q5_0");
+        
+        speech.set_model_size_from_str("tiny").unwrap();
+        assert_eq!(speech.model_name(), "tiny");
+        assert!(speech.set_model_size_from_str("base-q4_k").is_err());
+        
+        // Names with dashes of their own
+        speech.set_model_size_from_str("large-v3-turbo-q5_0").unwrap();
+        assert_eq!(speech.model_size, WhisperModelSize::LargeV3Turbo);
+        assert_eq!(speech.model_name(), "large-v3-turbo-q5_0");
+        
+        speech.set_model_size_from_str("distil-small.en").unwrap();
+        assert_eq!(speech.model_size, WhisperModelSize::DistilSmallEn);
+        assert_eq!(speech.quantization, ModelQuantization::None);
+        assert!(speech.set_model_size_from_str("large-v3").is_err());
+    }
+    
+    #[test]
+    fn test_custom_model_selection() {
+        let mut speech = Config::default().audio.speech;
+        speech.custom_models.push(CustomModel {
+            name: "Dutch Finetune".to_string(),
+            path: "/models/nl.bin".to_string(),
+        });
+        
+        assert!(speech.set_model_size_from_str("custom:Unknown").is_err());
+        speech.set_model_size_from_str("custom:Dutch Finetune").unwrap();
+        assert_eq!(speech.model_name(), "custom:Dutch Finetune");
+        assert_eq!(speech.selected_custom_model().unwrap().path, "/models/nl.bin");
+        
+        // Choosing a built-in model again leaves the custom one registered
+        speech.set_model_size_from_str("base").unwrap();
+        assert_eq!(speech.model_name(), "base");
+        assert_eq!(speech.custom_models.len(), 1);
+    }
+    
+    #[test]
+    fn test_same_engine() {
+        let speech = Config::default().audio.speech;
+        
+        // Options applied per buffer don't need a new engine
+        let mut other = speech.clone();
+        other.language = "de".to_string();
+        other.auto_punctuate = false;
+        assert!(speech.same_engine(&other));
+        
+        other.quantization = ModelQuantization::Q8_0;
+        assert!(!speech.same_engine(&other));
+        
+        let mut other = speech.clone();
+        other.engine = SttEngineKind::Cloud;
+        assert!(!speech.same_engine(&other));
+    }    
+    #[test]
+    fn test_replacement_dictionaries() {
+        let rule = |pattern: &str| ReplacementRule { pattern: pattern.to_string(), ..ReplacementRule::default() };
+        let mut speech = Config::default().audio.speech;
+        speech.replacements.push(rule("best me"));
+        speech.dictionaries.push(ReplacementDictionary {
+            name: "code".to_string(),
+            applications: vec!["Code.exe".to_string(), "devenv".to_string()],
+            rules: vec![rule("open bracket")],
+        });
+        
+        assert_eq!(speech.active_replacements().len(), 1);
+        speech.active_dictionary = Some("code".to_string());
+        let patterns: Vec<String> = speech.active_replacements().into_iter().map(|rule| rule.pattern).collect();
+        assert_eq!(patterns, ["best me", "open bracket"]);
+        
+        assert_eq!(speech.dictionary_for_application(r"C:\Program Files\Microsoft VS Code\code.exe").unwrap().name, "code");
+        assert_eq!(speech.dictionary_for_application("/usr/bin/devenv").unwrap().name, "code");
+        assert!(speech.dictionary_for_application("outlook.exe").is_none());
+        assert!(speech.dictionary_for_application("").is_none());
+    }
+    
+    #[test]
+    fn test_voice_command_settings() {
+        let settings: toml::Table = r#"
+            require_prefix = false
+            custom_commands = [["scratch that", "Undo"], ["open terminal", "open_terminal"]]
+            
+            [overrides."go to line {line}"]
+            apps = ["Code.exe"]
+        "#.parse().unwrap();
+        let mut config = VoiceCommandConfig::default();
+        ConfigManager::apply_voice_command_settings(&mut config, &settings);
+        
+        assert!(!config.require_prefix);
+        assert_eq!(config.custom_commands, [
+            ("scratch that".to_string(), VoiceCommandType::Undo),
+            ("open terminal".to_string(), VoiceCommandType::Custom("open_terminal".to_string())),
+        ]);
+        assert_eq!(config.command_override("Go to line {line}").unwrap().apps, ["Code.exe"]);
+    }
+}
@@ -1,6 +1,11 @@
+// Native Win32 windows; on macOS and Linux the Tauri shell provides the window and tray
+#[cfg(target_os = "windows")]
 pub mod icons;
+#[cfg(target_os = "windows")]
 pub mod settings;
+#[cfg(target_os = "windows")]
 pub mod tray;
+#[cfg(target_os = "windows")]
 pub mod window;
 
 use anyhow::Result;
@@ -81,6 +86,11 @@ impl Gui {
             }
         }
         
+        #[cfg(not(target_os = "windows"))]
+        {
+            info!("No native GUI loop on this platform; the window and tray are provided by the Tauri app");
+        }
+        
         // Will be implemented with Tauri integration
         Ok(())
     }
@@ -2,6 +2,8 @@ pub mod config;
 pub mod app;
 pub mod audio;
 pub mod gui;
+#[cfg(feature = "http-api")]
+pub mod api;
 
 use anyhow::Result;
 use log::{error, info};